@@ -145,6 +145,53 @@ fn expr2_to_string_helper(
                 ));
             }
         }
+        Expr2::If {
+            branches,
+            final_else,
+            ..
+        } => {
+            out_string.push_str("If:\n");
+
+            for (cond_id, then_id) in branches.iter(pool) {
+                out_string.push_str(&format!("{}cond:\n", get_spacing(indent_level + 1)));
+                expr2_to_string_helper(pool.get(*cond_id), indent_level + 2, pool, out_string);
+                out_string.push_str(&format!("{}then:\n", get_spacing(indent_level + 1)));
+                expr2_to_string_helper(pool.get(*then_id), indent_level + 2, pool, out_string);
+            }
+
+            out_string.push_str(&format!("{}else:\n", get_spacing(indent_level + 1)));
+            expr2_to_string_helper(pool.get(*final_else), indent_level + 2, pool, out_string);
+        }
+        Expr2::When { cond, branches, .. } => {
+            out_string.push_str("When:\n");
+            out_string.push_str(&format!("{}cond:\n", get_spacing(indent_level + 1)));
+            expr2_to_string_helper(pool.get(*cond), indent_level + 2, pool, out_string);
+
+            for when_branch in branches.iter(pool) {
+                out_string.push_str(&format!("{}branch:\n", get_spacing(indent_level + 1)));
+
+                for pattern2 in when_branch.patterns.iter(pool) {
+                    out_string.push_str(&format!(
+                        "{}{:?}\n",
+                        get_spacing(indent_level + 2),
+                        pattern2
+                    ));
+                }
+
+                if let Some(guard_id) = when_branch.guard {
+                    out_string.push_str(&format!("{}guard:\n", get_spacing(indent_level + 2)));
+                    expr2_to_string_helper(pool.get(guard_id), indent_level + 3, pool, out_string);
+                }
+
+                out_string.push_str(&format!("{}body:\n", get_spacing(indent_level + 2)));
+                expr2_to_string_helper(
+                    pool.get(when_branch.body),
+                    indent_level + 3,
+                    pool,
+                    out_string,
+                );
+            }
+        }
         &Expr2::Var { .. } => {
             out_string.push_str(&format!("{:?}", expr2,));
         }
@@ -650,6 +650,12 @@ pub fn expr_to_expr2<'a>(
             //            (RuntimeError(MalformedClosure(region)), Output::default())
             todo!()
         }
+        Malformed(_, _) => {
+            // the parser already reported the syntax error; Expr2 has no room for the details
+            env.problem(Problem::RuntimeError(RuntimeError::MalformedSyntax(region)));
+
+            (Expr2::RuntimeError(), Output::default())
+        }
         MalformedIdent(_name, _problem) => {
             //            use roc_problem::can::RuntimeError::*;
             //
@@ -71,7 +71,42 @@ pub enum Pattern2 {
 
 impl ShallowClone for Pattern2 {
     fn shallow_clone(&self) -> Self {
-        todo!()
+        match self {
+            Pattern2::Identifier(symbol) => Pattern2::Identifier(*symbol),
+            Pattern2::NumLiteral(var, number) => Pattern2::NumLiteral(*var, *number),
+            Pattern2::IntLiteral(int_val) => Pattern2::IntLiteral(*int_val),
+            Pattern2::FloatLiteral(float_val) => Pattern2::FloatLiteral(*float_val),
+            Pattern2::StrLiteral(pool_str) => Pattern2::StrLiteral(pool_str.shallow_clone()),
+            Pattern2::CharacterLiteral(ch) => Pattern2::CharacterLiteral(*ch),
+            Pattern2::Underscore => Pattern2::Underscore,
+            Pattern2::Tag {
+                whole_var,
+                ext_var,
+                tag_name,
+                arguments,
+            } => Pattern2::Tag {
+                whole_var: *whole_var,
+                ext_var: *ext_var,
+                tag_name: tag_name.shallow_clone(),
+                arguments: arguments.shallow_clone(),
+            },
+            Pattern2::RecordDestructure {
+                whole_var,
+                ext_var,
+                destructs,
+            } => Pattern2::RecordDestructure {
+                whole_var: *whole_var,
+                ext_var: *ext_var,
+                destructs: destructs.shallow_clone(),
+            },
+            Pattern2::Shadowed { shadowed_ident } => Pattern2::Shadowed {
+                shadowed_ident: shadowed_ident.shallow_clone(),
+            },
+            Pattern2::UnsupportedPattern(region) => Pattern2::UnsupportedPattern(*region),
+            Pattern2::MalformedPattern(problem, region) => {
+                Pattern2::MalformedPattern(*problem, *region)
+            }
+        }
     }
 }
 
@@ -89,6 +89,24 @@ pub struct Pool {
     // free_1node_slots: Vec<NodeId<T>>,
 }
 
+/// A copy of all nodes in a Pool, see Pool::snapshot.
+#[derive(Debug, Clone)]
+pub struct PoolSnapshot {
+    nodes: Vec<[MaybeUninit<u8>; NODE_BYTES]>,
+}
+
+/// The previous contents of the nodes that changed since a snapshot was updated, see Pool::update_snapshot.
+#[derive(Debug, Clone)]
+pub struct PoolDiff {
+    nodes: Vec<(u32, [MaybeUninit<u8>; NODE_BYTES])>,
+}
+
+impl PoolDiff {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
 impl Pool {
     pub fn with_capacity(nodes: u32) -> Self {
         // round up number of nodes requested to nearest page size in bytes
@@ -207,6 +225,58 @@ impl Pool {
         }
     }
 
+    /// Copies all nodes, to find out later which nodes were changed with set or get_mut.
+    pub fn snapshot(&self) -> PoolSnapshot {
+        PoolSnapshot {
+            nodes: self.nodes_slice().to_vec(),
+        }
+    }
+
+    /// Makes the snapshot equal to the pool again, and returns what the nodes that changed used to be.
+    /// Nodes that were added after the snapshot was taken are not part of the diff.
+    pub fn update_snapshot(&self, snapshot: &mut PoolSnapshot) -> PoolDiff {
+        let nodes = self.nodes_slice();
+        let mut changed = Vec::new();
+
+        for (index, (old_node, node)) in snapshot.nodes.iter_mut().zip(nodes).enumerate() {
+            if node_bytes(old_node) != node_bytes(node) {
+                changed.push((index as u32, *old_node));
+                *old_node = *node;
+            }
+        }
+
+        snapshot
+            .nodes
+            .extend_from_slice(&nodes[snapshot.nodes.len()..]);
+
+        PoolDiff { nodes: changed }
+    }
+
+    /// Writes the nodes of the diff back into the pool and the snapshot, and returns the diff that undoes this.
+    /// Nodes that were added after the diff was made are kept, something may still refer to them.
+    pub fn apply_diff(&mut self, diff: &PoolDiff, snapshot: &mut PoolSnapshot) -> PoolDiff {
+        let nodes = unsafe { std::slice::from_raw_parts_mut(self.nodes, self.num_nodes as usize) };
+
+        let undo_nodes = diff
+            .nodes
+            .iter()
+            .map(|(index, node)| {
+                snapshot.nodes[*index as usize] = *node;
+
+                (
+                    *index,
+                    std::mem::replace(&mut nodes[*index as usize], *node),
+                )
+            })
+            .collect();
+
+        PoolDiff { nodes: undo_nodes }
+    }
+
+    fn nodes_slice(&self) -> &[[MaybeUninit<u8>; NODE_BYTES]] {
+        unsafe { std::slice::from_raw_parts(self.nodes, self.num_nodes as usize) }
+    }
+
     fn get_ptr<T>(&self, node_id: NodeId<T>) -> *mut MaybeUninit<T> {
         let node_offset = unsafe { self.nodes.offset(node_id.index as isize) };
 
@@ -243,6 +313,12 @@ impl<T> std::ops::IndexMut<NodeId<T>> for Pool {
     }
 }
 
+// The pool's memory comes zeroed from the OS, so all bytes of a node are initialized,
+// even if the value that was written there has padding.
+fn node_bytes(node: &[MaybeUninit<u8>; NODE_BYTES]) -> &[u8; NODE_BYTES] {
+    unsafe { &*(node as *const [MaybeUninit<u8>; NODE_BYTES] as *const [u8; NODE_BYTES]) }
+}
+
 impl Drop for Pool {
     fn drop(&mut self) {
         unsafe {
@@ -267,3 +343,32 @@ impl Drop for Pool {
         }
     }
 }
+
+#[test]
+fn pool_apply_diff() {
+    let mut test_pool = Pool::with_capacity(1024);
+    let first_id = test_pool.add(1usize);
+    let unchanged_id = test_pool.add(5usize);
+
+    let mut snapshot = test_pool.snapshot();
+
+    test_pool.set(first_id, 2usize);
+    let second_id = test_pool.add(3usize);
+
+    let diff = test_pool.update_snapshot(&mut snapshot);
+    assert_eq!(diff.nodes.len(), 1);
+
+    // undo
+    let redo_diff = test_pool.apply_diff(&diff, &mut snapshot);
+
+    assert_eq!(*test_pool.get(first_id), 1);
+    assert_eq!(*test_pool.get(unchanged_id), 5);
+    assert_eq!(*test_pool.get(second_id), 3);
+    assert!(test_pool.update_snapshot(&mut snapshot).is_empty());
+
+    // redo
+    test_pool.apply_diff(&redo_diff, &mut snapshot);
+
+    assert_eq!(*test_pool.get(first_id), 2);
+    assert!(test_pool.update_snapshot(&mut snapshot).is_empty());
+}
@@ -11,6 +11,9 @@ pub fn load_module(src_file: &Path, threading: Threading) -> LoadedModule {
         render: roc_reporting::report::RenderTarget::ColorTerminal,
         threading,
         exec_mode: ExecutionMode::Check,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: false,
     };
 
     let arena = Bump::new();
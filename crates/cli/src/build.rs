@@ -1,13 +1,14 @@
 use bumpalo::Bump;
 use roc_build::{
     link::{link, preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy},
-    program::{self, Problems},
+    program::{self, EmitOptions, Problems},
 };
 use roc_builtins::bitcode;
 use roc_collections::VecMap;
 use roc_load::{EntryPoint, ExecutionMode, Expectations, LoadConfig, LoadingProblem, Threading};
 use roc_module::symbol::{Interns, ModuleId};
 use roc_mono::ir::OptLevel;
+use roc_reporting::lint::WarningLevels;
 use roc_reporting::report::RenderTarget;
 use roc_target::TargetInfo;
 use std::time::{Duration, Instant};
@@ -42,7 +43,13 @@ pub fn build_file<'a>(
     app_module_path: PathBuf,
     opt_level: OptLevel,
     emit_debug_info: bool,
+    debug_refcounts: bool,
+    profile_allocations: bool,
+    report_in_place: bool,
+    emit: EmitOptions,
+    inline_and_fold: bool,
     emit_timings: bool,
+    warning_levels: &WarningLevels,
     link_type: LinkType,
     linking_strategy: LinkingStrategy,
     precompiled: bool,
@@ -61,6 +68,9 @@ pub fn build_file<'a>(
         render: RenderTarget::ColorTerminal,
         threading,
         exec_mode: ExecutionMode::Executable,
+        inline_and_fold,
+        record_call_regions: report_in_place || emit_debug_info,
+        collect_package_modules: false,
     };
     let loaded = roc_load::load_and_monomorphize(
         arena,
@@ -101,6 +111,11 @@ pub fn build_file<'a>(
         binary_path.set_extension(extension);
     }
 
+    let emit = EmitOptions {
+        output_path: binary_path.clone(),
+        ..emit
+    };
+
     let host_input_path = if let EntryPoint::Executable { platform_path, .. } = &loaded.entry_point
     {
         cwd.join(platform_path)
@@ -212,7 +227,7 @@ pub fn build_file<'a>(
     // This only needs to be mutable for report_problems. This can't be done
     // inside a nested scope without causing a borrow error!
     let mut loaded = loaded;
-    let problems = program::report_problems_monomorphized(&mut loaded);
+    let problems = program::report_problems_monomorphized(&mut loaded, warning_levels);
     let expectations = std::mem::take(&mut loaded.expectations);
     let loaded = loaded;
 
@@ -244,6 +259,10 @@ pub fn build_file<'a>(
         app_o_file,
         opt_level,
         emit_debug_info,
+        debug_refcounts,
+        profile_allocations,
+        report_in_place,
+        &emit,
         &preprocessed_host_path,
         wasm_dev_stack_bytes,
     );
@@ -305,6 +324,17 @@ pub fn build_file<'a>(
             // Just copy the object file to the output folder.
             binary_path.set_extension(app_extension);
             std::fs::copy(app_o_file, &binary_path).unwrap();
+
+            // wasm builds with --debug come with a source map
+            let source_map_path = program::wasm_source_map_path(app_o_file);
+            if source_map_path.exists() {
+                std::fs::copy(
+                    &source_map_path,
+                    program::wasm_source_map_path(&binary_path),
+                )
+                .unwrap();
+                let _ = std::fs::remove_file(&source_map_path);
+            }
             problems
         }
         (LinkingStrategy::Legacy, _) => {
@@ -431,6 +461,8 @@ pub fn check_file(
     arena: &Bump,
     roc_file_path: PathBuf,
     emit_timings: bool,
+    report_unused: bool,
+    warning_levels: &WarningLevels,
     threading: Threading,
 ) -> Result<(program::Problems, Duration), LoadingProblem> {
     let compilation_start = Instant::now();
@@ -448,6 +480,9 @@ pub fn check_file(
         render: RenderTarget::ColorTerminal,
         threading,
         exec_mode: ExecutionMode::Check,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: report_unused,
     };
     let mut loaded =
         roc_load::load_and_typecheck(arena, roc_file_path, subs_by_module, load_config)?;
@@ -484,6 +519,17 @@ pub fn check_file(
         }
     }
 
+    if report_unused {
+        // these are reported along with the problems of each module
+        for (module_id, problems) in roc_load::find_unused(&loaded) {
+            loaded
+                .can_problems
+                .entry(module_id)
+                .or_default()
+                .extend(problems);
+        }
+    }
+
     let compilation_end = compilation_start.elapsed();
 
     if emit_timings {
@@ -496,7 +542,7 @@ pub fn check_file(
     }
 
     Ok((
-        program::report_problems_typechecked(&mut loaded),
+        program::report_problems_typechecked(&mut loaded, warning_levels),
         compilation_end,
     ))
 }
@@ -1,18 +1,24 @@
 use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 
 use crate::FormatMode;
 use bumpalo::Bump;
-use roc_error_macros::{internal_error, user_error};
+use roc_error_macros::internal_error;
+use roc_fmt::annotation::Formattable;
 use roc_fmt::def::fmt_defs;
 use roc_fmt::module::fmt_module;
 use roc_fmt::spaces::RemoveSpaces;
 use roc_fmt::{Ast, Buf};
+use roc_module::symbol::{Interns, ModuleIds};
 use roc_parse::{
     module::{self, module_defs},
-    parser::{Parser, SyntaxError},
+    parser::{Parser, SourceError, SyntaxError},
     state::State,
 };
+use roc_region::all::LineInfo;
+use roc_reporting::report::{parse_problem, RenderTarget, RocDocAllocator, DEFAULT_PALETTE};
 
 fn flatten_directories(files: std::vec::Vec<PathBuf>) -> std::vec::Vec<PathBuf> {
     let mut to_flatten = files;
@@ -59,110 +65,439 @@ fn is_roc_file(path: &Path) -> bool {
     return matches!(ext, Some("roc"));
 }
 
-pub fn format(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), String> {
+pub fn format(
+    files: std::vec::Vec<PathBuf>,
+    mode: FormatMode,
+    lines: Option<RangeInclusive<u32>>,
+    max_width: Option<usize>,
+) -> Result<(), String> {
     let files = flatten_directories(files);
 
+    if lines.is_some() && files.len() != 1 {
+        return Err("--lines can only be used when formatting a single file.".to_string());
+    }
+
     for file in files {
         let arena = Bump::new();
 
         let src = std::fs::read_to_string(&file).unwrap();
 
-        let ast = arena.alloc(parse_all(&arena, &src).unwrap_or_else(|e| {
-            user_error!("Unexpected parse failure when parsing this formatting:\n\n{:?}\n\nParse error was:\n\n{:?}\n\n", src, e)
-        }));
-        let mut buf = Buf::new_in(&arena);
-        fmt_all(&mut buf, ast);
-
-        let reparsed_ast = arena.alloc(parse_all(&arena, buf.as_str()).unwrap_or_else(|e| {
-            let mut fail_file = file.clone();
-            fail_file.set_extension("roc-format-failed");
-            std::fs::write(&fail_file, buf.as_str()).unwrap();
-            internal_error!(
-                "Formatting bug; formatted code isn't valid\n\n\
-                I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
-                Parse error was: {:?}\n\n",
-                fail_file.display(),
-                e
-            );
-        }));
-
-        let ast_normalized = ast.remove_spaces(&arena);
-        let reparsed_ast_normalized = reparsed_ast.remove_spaces(&arena);
-
-        // HACK!
-        // We compare the debug format strings of the ASTs, because I'm finding in practice that _somewhere_ deep inside the ast,
-        // the PartialEq implementation is returning `false` even when the Debug-formatted impl is exactly the same.
-        // I don't have the patience to debug this right now, so let's leave it for another day...
-        // TODO: fix PartialEq impl on ast types
-        if format!("{:?}", ast_normalized) != format!("{:?}", reparsed_ast_normalized) {
-            let mut fail_file = file.clone();
-            fail_file.set_extension("roc-format-failed");
-            std::fs::write(&fail_file, buf.as_str()).unwrap();
-
-            let mut before_file = file.clone();
-            before_file.set_extension("roc-format-failed-ast-before");
-            std::fs::write(&before_file, &format!("{:#?}\n", ast)).unwrap();
-
-            let mut after_file = file.clone();
-            after_file.set_extension("roc-format-failed-ast-after");
-            std::fs::write(&after_file, &format!("{:#?}\n", reparsed_ast)).unwrap();
-
-            internal_error!(
-                "Formatting bug; formatting didn't reparse as the same tree\n\n\
-                I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
-                I wrote the tree before and after formatting to these files for debugging purposes:\n{}\n{}\n\n",
-                fail_file.display(),
-                before_file.display(),
-                after_file.display());
-        }
-
-        // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
-        let mut reformatted_buf = Buf::new_in(&arena);
-        fmt_all(&mut reformatted_buf, reparsed_ast);
-        if buf.as_str() != reformatted_buf.as_str() {
-            let mut unstable_1_file = file.clone();
-            unstable_1_file.set_extension("roc-format-unstable-1");
-            std::fs::write(&unstable_1_file, buf.as_str()).unwrap();
-
-            let mut unstable_2_file = file.clone();
-            unstable_2_file.set_extension("roc-format-unstable-2");
-            std::fs::write(&unstable_2_file, reformatted_buf.as_str()).unwrap();
-
-            internal_error!(
-                "Formatting bug; formatting is not stable. Reformatting the formatted file changed it again.\n\n\
-                I wrote the result of formatting to this file for debugging purposes:\n{}\n\n\
-                I wrote the result of double-formatting here:\n{}\n\n",
-                unstable_1_file.display(),
-                unstable_2_file.display());
-        }
+        let formatted = match format_src(
+            &arena,
+            &file,
+            &src,
+            lines.clone(),
+            max_width,
+            RenderTarget::ColorTerminal,
+        ) {
+            Ok(formatted) => formatted,
+            Err(FormatProblem::ParseError(report)) => return Err(report),
+            Err(bug) => formatter_bug(bug, Some(&file)),
+        };
 
         match mode {
             FormatMode::CheckOnly => {
                 // If we notice that this file needs to be formatted, return early
-                if buf.as_str() != src {
+                if formatted != src {
                     return Err("One or more files need to be reformatted.".to_string());
                 }
             }
 
             FormatMode::Format => {
-                // If all the checks above passed, actually write out the new file.
-                std::fs::write(&file, buf.as_str()).unwrap();
+                // If all the checks passed, actually write out the new file.
+                std::fs::write(&file, formatted).unwrap();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Format the source code read from stdin, and write the result to stdout. This is what editors
+/// use to format a buffer that may not have been saved yet.
+pub fn format_stdin(
+    mode: FormatMode,
+    lines: Option<RangeInclusive<u32>>,
+    max_width: Option<usize>,
+) -> Result<(), String> {
+    let mut src = String::new();
+    std::io::stdin()
+        .read_to_string(&mut src)
+        .map_err(|error| format!("I could not read the source code from stdin: {}", error))?;
+
+    let arena = Bump::new();
+
+    // editors show this to their users, who may not be looking at a terminal
+    let formatted = match format_src(
+        &arena,
+        Path::new("stdin.roc"),
+        &src,
+        lines,
+        max_width,
+        RenderTarget::Generic,
+    ) {
+        Ok(formatted) => formatted,
+        Err(FormatProblem::ParseError(report)) => return Err(report),
+        Err(bug) => formatter_bug(bug, None),
+    };
+
+    match mode {
+        FormatMode::CheckOnly => {
+            if formatted != src {
+                return Err("The source code needs to be reformatted.".to_string());
             }
         }
+
+        FormatMode::Format => {
+            std::io::stdout()
+                .write_all(formatted.as_bytes())
+                .map_err(|error| format!("I could not write the formatted code: {}", error))?;
+        }
     }
 
     Ok(())
 }
 
-fn parse_all<'a>(arena: &'a Bump, src: &'a str) -> Result<Ast<'a>, SyntaxError<'a>> {
+/// Parse a line range like `3:7`, as given to `roc format --lines`. Line numbers start at 1, and
+/// both ends are included.
+pub fn parse_line_range(range: &str) -> Result<RangeInclusive<u32>, String> {
+    let error = || {
+        format!(
+            "I expected a line range like `3:7` for --lines, but got `{}`.",
+            range
+        )
+    };
+
+    let (start, end) = range.split_once(':').ok_or_else(error)?;
+    let start: u32 = start.trim().parse().map_err(|_| error())?;
+    let end: u32 = end.trim().parse().map_err(|_| error())?;
+
+    if start == 0 || end < start {
+        return Err(error());
+    }
+
+    Ok(start..=end)
+}
+
+/// How often to format the code again, until breaking up its long lines has settled
+const MAX_WIDTH_PASSES: usize = 8;
+
+/// Something that went wrong in `format_src`. Only a `ParseError` is a problem with the source
+/// code; the others are bugs in the formatter.
+#[derive(Debug)]
+pub enum FormatProblem {
+    /// The source code does not parse. This is the rendered report explaining why.
+    ParseError(String),
+    /// The formatted code does not parse anymore.
+    NotReparsable { formatted: String, error: String },
+    /// The formatted code parses to a different tree than the original code.
+    ChangedTree {
+        formatted: String,
+        ast_before: String,
+        ast_after: String,
+    },
+    /// Formatting the formatted code changes it again.
+    Unstable {
+        formatted: String,
+        reformatted: String,
+    },
+}
+
+/// Format the source code of a module, returning the formatted code.
+///
+/// With `lines`, only the top-level defs that overlap those lines are formatted, and the rest of
+/// the source code is left as it is. Line numbers start at 1, and both ends of the range are
+/// included. With a `max_width`, code that makes a line longer than that is broken up over
+/// multiple lines where possible. The `filename` is only used in error reports.
+pub fn format_src<'a>(
+    arena: &'a Bump,
+    filename: &Path,
+    src: &'a str,
+    lines: Option<RangeInclusive<u32>>,
+    max_width: Option<usize>,
+    render: RenderTarget,
+) -> Result<String, FormatProblem> {
+    let ast = match parse_all(arena, src) {
+        Ok(ast) => arena.alloc(ast),
+        Err(error) => {
+            let report = parse_error_report(filename.to_path_buf(), error, render);
+
+            return Err(FormatProblem::ParseError(report));
+        }
+    };
+
+    // Which top-level defs to format, if not all of them. We remember them by their index,
+    // so that we can check the formatted code by formatting the same defs again.
+    let selected_defs = lines.map(|lines| defs_overlapping(src, ast, lines));
+
+    let mut formatted = fmt_selected(arena, src, ast, selected_defs.as_deref(), max_width);
+
+    // Breaking up a long line can change the layout of the code around it, so keep formatting
+    // until that settles. Should it not, the stability check below reports it.
+    if max_width.is_some() {
+        for _ in 0..MAX_WIDTH_PASSES {
+            let ast = match parse_all(arena, formatted) {
+                Ok(ast) => arena.alloc(ast),
+                // reported below
+                Err(_) => break,
+            };
+
+            let reformatted =
+                fmt_selected(arena, formatted, ast, selected_defs.as_deref(), max_width);
+
+            if reformatted == formatted {
+                break;
+            }
+
+            formatted = reformatted;
+        }
+    }
+
+    let reparsed_ast = match parse_all(arena, formatted) {
+        Ok(reparsed_ast) => arena.alloc(reparsed_ast),
+        Err(error) => {
+            return Err(FormatProblem::NotReparsable {
+                formatted: formatted.to_string(),
+                error: format!("{:?}", error.problem),
+            });
+        }
+    };
+
+    let ast_normalized = ast.remove_spaces(arena);
+    let reparsed_ast_normalized = reparsed_ast.remove_spaces(arena);
+
+    // HACK!
+    // We compare the debug format strings of the ASTs, because I'm finding in practice that _somewhere_ deep inside the ast,
+    // the PartialEq implementation is returning `false` even when the Debug-formatted impl is exactly the same.
+    // I don't have the patience to debug this right now, so let's leave it for another day...
+    // TODO: fix PartialEq impl on ast types
+    if format!("{:?}", ast_normalized) != format!("{:?}", reparsed_ast_normalized) {
+        return Err(FormatProblem::ChangedTree {
+            formatted: formatted.to_string(),
+            ast_before: format!("{:#?}\n", ast),
+            ast_after: format!("{:#?}\n", reparsed_ast),
+        });
+    }
+
+    // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
+    let reformatted = fmt_selected(
+        arena,
+        formatted,
+        reparsed_ast,
+        selected_defs.as_deref(),
+        max_width,
+    );
+
+    if formatted != reformatted {
+        return Err(FormatProblem::Unstable {
+            formatted: formatted.to_string(),
+            reformatted: reformatted.to_string(),
+        });
+    }
+
+    Ok(formatted.to_string())
+}
+
+/// Report a bug in the formatter, and exit. When formatting a file, the results are written
+/// next to it for debugging purposes.
+fn formatter_bug(problem: FormatProblem, file: Option<&Path>) -> ! {
+    let write_next_to_file = |extension: &str, contents: &str| {
+        file.map(|file| {
+            let mut debug_file = file.to_path_buf();
+            debug_file.set_extension(extension);
+            std::fs::write(&debug_file, contents).unwrap();
+
+            debug_file
+        })
+    };
+
+    match problem {
+        FormatProblem::ParseError(report) => internal_error!("{}", report),
+        FormatProblem::NotReparsable { formatted, error } => {
+            match write_next_to_file("roc-format-failed", &formatted) {
+                Some(fail_file) => internal_error!(
+                    "Formatting bug; formatted code isn't valid\n\n\
+                    I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
+                    Parse error was: {}\n\n",
+                    fail_file.display(),
+                    error
+                ),
+                None => internal_error!(
+                    "Formatting bug; formatted code isn't valid\n\n\
+                    The incorrect result was:\n{}\n\n\
+                    Parse error was: {}\n\n",
+                    formatted,
+                    error
+                ),
+            }
+        }
+        FormatProblem::ChangedTree {
+            formatted,
+            ast_before,
+            ast_after,
+        } => {
+            let fail_file = write_next_to_file("roc-format-failed", &formatted);
+            let before_file = write_next_to_file("roc-format-failed-ast-before", &ast_before);
+            let after_file = write_next_to_file("roc-format-failed-ast-after", &ast_after);
+
+            match (fail_file, before_file, after_file) {
+                (Some(fail_file), Some(before_file), Some(after_file)) => internal_error!(
+                    "Formatting bug; formatting didn't reparse as the same tree\n\n\
+                    I wrote the incorrect result to this file for debugging purposes:\n{}\n\n\
+                    I wrote the tree before and after formatting to these files for debugging purposes:\n{}\n{}\n\n",
+                    fail_file.display(),
+                    before_file.display(),
+                    after_file.display()
+                ),
+                _ => internal_error!(
+                    "Formatting bug; formatting didn't reparse as the same tree\n\n\
+                    The incorrect result was:\n{}\n\n",
+                    formatted
+                ),
+            }
+        }
+        FormatProblem::Unstable {
+            formatted,
+            reformatted,
+        } => {
+            let unstable_1_file = write_next_to_file("roc-format-unstable-1", &formatted);
+            let unstable_2_file = write_next_to_file("roc-format-unstable-2", &reformatted);
+
+            match (unstable_1_file, unstable_2_file) {
+                (Some(unstable_1_file), Some(unstable_2_file)) => internal_error!(
+                    "Formatting bug; formatting is not stable. Reformatting the formatted file changed it again.\n\n\
+                    I wrote the result of formatting to this file for debugging purposes:\n{}\n\n\
+                    I wrote the result of double-formatting here:\n{}\n\n",
+                    unstable_1_file.display(),
+                    unstable_2_file.display()
+                ),
+                _ => internal_error!(
+                    "Formatting bug; formatting is not stable. Reformatting the formatted code changed it again.\n\n\
+                    The result of formatting was:\n{}\n\n\
+                    The result of double-formatting was:\n{}\n\n",
+                    formatted,
+                    reformatted
+                ),
+            }
+        }
+    }
+}
+
+fn parse_error_report(
+    filename: PathBuf,
+    error: SourceError<'_, SyntaxError<'_>>,
+    render: RenderTarget,
+) -> String {
+    let src = std::str::from_utf8(error.bytes).unwrap_or_default();
+    let src_lines: Vec<&str> = src.lines().collect();
+    let lines = LineInfo::new(src);
+
+    // syntax errors do not mention any symbols, so a module without any will do
+    let mut module_ids = ModuleIds::default();
+    let home = module_ids.get_or_insert(&"".into());
+    let interns = Interns {
+        module_ids,
+        ..Interns::default()
+    };
+
+    let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+    let report = parse_problem(
+        &alloc,
+        &lines,
+        filename.clone(),
+        0,
+        error.into_file_error(filename),
+    );
+
+    let mut buf = String::new();
+    report.render(render, &mut buf, &alloc, &DEFAULT_PALETTE);
+
+    buf
+}
+
+fn parse_all<'a>(
+    arena: &'a Bump,
+    src: &'a str,
+) -> Result<Ast<'a>, SourceError<'a, SyntaxError<'a>>> {
     let (module, state) = module::parse_header(arena, State::new(src.as_bytes()))
-        .map_err(|e| SyntaxError::Header(e.problem))?;
+        .map_err(|e| e.map_problem(SyntaxError::Header))?;
 
-    let (_, defs, _) = module_defs().parse(arena, state).map_err(|(_, e, _)| e)?;
+    let (_, defs, _) = module_defs()
+        .parse(arena, state)
+        .map_err(|(_, e, state)| e.into_source_error(&state))?;
 
     Ok(Ast { module, defs })
 }
 
+/// The indices of the top-level defs that overlap the lines, which start at 1.
+fn defs_overlapping(src: &str, ast: &Ast, lines: RangeInclusive<u32>) -> Vec<usize> {
+    let line_info = LineInfo::new(src);
+    let first_line = lines.start().saturating_sub(1);
+    let last_line = lines.end().saturating_sub(1);
+
+    ast.defs
+        .regions
+        .iter()
+        .enumerate()
+        .filter(|(_, region)| {
+            let region = line_info.convert_region(**region);
+
+            region.start.line <= last_line && region.end.line >= first_line
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Format the whole module, or only the given top-level defs. The source code in between
+/// those defs, including the comments and blank lines around them, is left as it is.
+fn fmt_selected<'a>(
+    arena: &'a Bump,
+    src: &str,
+    ast: &'a Ast<'a>,
+    selected_defs: Option<&[usize]>,
+    max_width: Option<usize>,
+) -> &'a str {
+    let new_buf = || match max_width {
+        Some(max_width) => Buf::with_max_width(arena, max_width),
+        None => Buf::new_in(arena),
+    };
+
+    let selected_defs = match selected_defs {
+        Some(selected_defs) => selected_defs,
+        None => {
+            let mut buf = new_buf();
+            fmt_all(&mut buf, ast);
+
+            return buf.into_bump_str();
+        }
+    };
+
+    let mut formatted = bumpalo::collections::String::new_in(arena);
+    let mut copied_until = 0;
+
+    for (index, def) in ast.defs.defs().enumerate() {
+        if !selected_defs.contains(&index) {
+            continue;
+        }
+
+        let region = ast.defs.regions[index];
+
+        let mut buf = new_buf();
+        match def {
+            Ok(type_def) => type_def.format(&mut buf, 0),
+            Err(value_def) => value_def.format(&mut buf, 0),
+        }
+
+        formatted.push_str(&src[copied_until..region.start().offset as usize]);
+        formatted.push_str(buf.as_str().trim_end());
+        copied_until = region.end().offset as usize;
+    }
+
+    formatted.push_str(&src[copied_until..]);
+
+    formatted.into_bump_str()
+}
+
 fn fmt_all<'a>(buf: &mut Buf<'a>, ast: &'a Ast) {
     fmt_module(buf, &ast.module);
 
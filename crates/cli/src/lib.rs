@@ -5,15 +5,21 @@ use build::BuiltFile;
 use bumpalo::Bump;
 use clap::{Arg, ArgMatches, Command, ValueSource};
 use roc_build::link::{LinkType, LinkingStrategy};
+use roc_build::program::EmitOptions;
 use roc_collections::VecMap;
 use roc_error_macros::{internal_error, user_error};
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
 use roc_gen_llvm::run_roc::RocCallResult;
 use roc_gen_llvm::run_roc_dylib;
-use roc_load::{ExecutionMode, Expectations, LoadConfig, LoadingProblem, Threading};
+use roc_load::{
+    ExecutionMode, Expectations, LoadConfig, LoadingProblem, MonomorphizedModule, Threading,
+};
 use roc_module::symbol::{Interns, ModuleId};
 use roc_mono::ir::OptLevel;
-use roc_repl_expect::run::{expect_mono_module_to_dylib, roc_dev_expect};
+use roc_repl_expect::run::{
+    expect_mono_module_to_dev_dylib, expect_mono_module_to_dylib, roc_dev_expect, ExpectBackend,
+};
+use roc_reporting::lint::{Level, Lint, WarningLevels};
 use roc_target::TargetInfo;
 use std::env;
 use std::ffi::{CString, OsStr};
@@ -31,7 +37,8 @@ use tempfile::TempDir;
 
 pub mod build;
 mod format;
-pub use format::format;
+pub use format::{format, format_src, format_stdin, parse_line_range, FormatProblem};
+pub mod refactor;
 
 const DEFAULT_ROC_FILENAME: &str = "main.roc";
 
@@ -45,8 +52,16 @@ pub const CMD_VERSION: &str = "version";
 pub const CMD_FORMAT: &str = "format";
 pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
+pub const CMD_LSP: &str = "lsp";
+pub const CMD_REFACTOR: &str = "refactor";
+pub const CMD_RENAME: &str = "rename";
 
 pub const FLAG_DEBUG: &str = "debug";
+pub const FLAG_DEBUG_REFCOUNTS: &str = "debug-refcounts";
+pub const FLAG_PROFILE_ALLOCATIONS: &str = "profile-allocations";
+pub const FLAG_REPORT_IN_PLACE: &str = "report-in-place";
+pub const FLAG_EMIT: &str = "emit";
+pub const FLAG_INLINE: &str = "inline";
 pub const FLAG_DEV: &str = "dev";
 pub const FLAG_OPTIMIZE: &str = "optimize";
 pub const FLAG_MAX_THREADS: &str = "max-threads";
@@ -58,12 +73,23 @@ pub const FLAG_TIME: &str = "time";
 pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PRECOMPILED: &str = "precompiled-host";
 pub const FLAG_CHECK: &str = "check";
+pub const FLAG_STDIN: &str = "stdin";
+pub const FLAG_LINES: &str = "lines";
+pub const FLAG_MAX_WIDTH: &str = "max-width";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
+pub const FLAG_INTERPRET: &str = "interpret";
+pub const FLAG_UNUSED: &str = "unused";
+pub const FLAG_DENY_WARNINGS: &str = "deny-warnings";
+pub const FLAG_ALLOW: &str = "allow";
+pub const FLAG_WARN: &str = "warn";
+pub const FLAG_DENY: &str = "deny";
 pub const ROC_FILE: &str = "ROC_FILE";
 pub const ROC_DIR: &str = "ROC_DIR";
 pub const GLUE_FILE: &str = "GLUE_FILE";
 pub const DIRECTORY_OR_FILES: &str = "DIRECTORY_OR_FILES";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
+pub const RENAME_TARGET: &str = "TARGET";
+pub const NEW_NAME: &str = "NEW_NAME";
 
 const VERSION: &str = include_str!("../../../version.txt");
 
@@ -92,7 +118,36 @@ pub fn build_app<'a>() -> Command<'a> {
 
     let flag_debug = Arg::new(FLAG_DEBUG)
         .long(FLAG_DEBUG)
-        .help("Store LLVM debug information in the generated program.")
+        .help("Store debug information in the generated program, so debuggers can map it back to .roc files.\nUses DWARF for native targets, and a source map next to the binary for wasm32 dev builds.")
+        .required(false);
+
+    let flag_debug_refcounts = Arg::new(FLAG_DEBUG_REFCOUNTS)
+        .long(FLAG_DEBUG_REFCOUNTS)
+        .help("Track every allocation to find refcounting bugs. Freeing a value twice aborts the program with the procedures involved, and values that are never freed are reported when the program exits (or, for `roc test`, when an expect finishes). Needs the LLVM backend, so it cannot be combined with `--dev`.")
+        .required(false);
+
+    let flag_profile_allocations = Arg::new(FLAG_PROFILE_ALLOCATIONS)
+        .long(FLAG_PROFILE_ALLOCATIONS)
+        .help("Count the allocations of the program by procedure and layout, including how often memory is reused instead of allocated. When the program exits, a summary is printed, and `roc-allocations.folded` is written for flamegraph tools. Needs the LLVM backend, so it cannot be combined with `--dev`.")
+        .required(false);
+
+    let flag_report_in_place = Arg::new(FLAG_REPORT_IN_PLACE)
+        .long(FLAG_REPORT_IN_PLACE)
+        .help("List every update of a list (like `List.set` or `List.append`) with its location, and whether alias analysis lets it happen in place or it copies the list when it is shared. For the copies, explains which other reference to the list may keep it from being unique. Describes the LLVM backend, so it cannot be combined with `--dev`.")
+        .required(false);
+
+    let flag_emit = Arg::new(FLAG_EMIT)
+        .long(FLAG_EMIT)
+        .help("Also write intermediate representations of the program next to the output, e.g. `--emit=mono,llvm-ir`. `mono` writes the mono IR (.mono), `llvm-ir` the optimized LLVM IR (.ll), `asm` the native assembly (.s), and `wasm-text` the WebAssembly text format (.wat) of a `--dev --target wasm32` build.")
+        .takes_value(true)
+        .use_value_delimiter(true)
+        .multiple_occurrences(true)
+        .possible_values(["mono", "llvm-ir", "asm", "wasm-text"])
+        .required(false);
+
+    let flag_inline = Arg::new(FLAG_INLINE)
+        .long(FLAG_INLINE)
+        .help("Inline small procedures and fold constant arithmetic, comparisons and branches before generating code. This is experimental, so it is off by default. It helps `--dev` builds most, because they don't run LLVM's optimizer.")
         .required(false);
 
     let flag_time = Arg::new(FLAG_TIME)
@@ -119,6 +174,32 @@ pub fn build_app<'a>() -> Command<'a> {
         .validator(|s| s.parse::<u32>())
         .required(false);
 
+    let flag_interpret = Arg::new(FLAG_INTERPRET)
+        .long(FLAG_INTERPRET)
+        .help("Interpret the program instead of compiling it. This runs slower, but needs neither LLVM nor a linker.")
+        .required(false);
+
+    let flag_deny_warnings = Arg::new(FLAG_DENY_WARNINGS)
+        .long(FLAG_DENY_WARNINGS)
+        .help("Report every warning as an error, so that warnings fail the build. Warnings that are allowed, e.g. with `--allow` or a `# roc: allow(...)` comment in a module, are still not reported.")
+        .required(false);
+
+    let lint_flag = |name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .help(help)
+            .value_name("WARNING")
+            .takes_value(true)
+            .use_value_delimiter(true)
+            .multiple_occurrences(true)
+            .possible_values(Lint::NAMES)
+            .required(false)
+    };
+
+    let flag_allow = lint_flag(FLAG_ALLOW, "Don't report these kinds of problems. A `# roc: warn(...)` or `# roc: deny(...)` comment in a module overrides this for that module.");
+    let flag_warn = lint_flag(FLAG_WARN, "Report these kinds of problems as warnings");
+    let flag_deny = lint_flag(FLAG_DENY, "Report these kinds of problems as errors");
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .allow_invalid_utf8(true)
@@ -143,10 +224,19 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
+            .arg(flag_debug_refcounts.clone())
+            .arg(flag_profile_allocations.clone())
+            .arg(flag_report_in_place.clone())
+            .arg(flag_emit.clone())
+            .arg(flag_inline.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_precompiled.clone())
             .arg(flag_wasm_stack_size_kb.clone())
+            .arg(flag_deny_warnings.clone())
+            .arg(flag_allow.clone())
+            .arg(flag_warn.clone())
+            .arg(flag_deny.clone())
             .arg(
                 Arg::new(FLAG_TARGET)
                     .long(FLAG_TARGET)
@@ -182,9 +272,11 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
+            .arg(flag_debug_refcounts.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_precompiled.clone())
+            .arg(flag_interpret.clone())
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file for the main module")
@@ -196,6 +288,7 @@ pub fn build_app<'a>() -> Command<'a> {
         )
         .subcommand(Command::new(CMD_REPL)
             .about("Launch the interactive Read Eval Print Loop (REPL)")
+            .arg(flag_interpret.clone())
         )
         .subcommand(Command::new(CMD_RUN)
             .about("Run a .roc file even if it has build errors")
@@ -204,9 +297,18 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
+            .arg(flag_debug_refcounts.clone())
+            .arg(flag_profile_allocations.clone())
+            .arg(flag_report_in_place.clone())
+            .arg(flag_emit.clone())
+            .arg(flag_inline.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_precompiled.clone())
+            .arg(flag_deny_warnings.clone())
+            .arg(flag_allow.clone())
+            .arg(flag_warn.clone())
+            .arg(flag_deny.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone())
         )
@@ -224,6 +326,28 @@ pub fn build_app<'a>() -> Command<'a> {
                     .help("Checks that specified files are formatted. If formatting is needed, it will return a non-zero exit code.")
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_STDIN)
+                    .long(FLAG_STDIN)
+                    .help("Format the code read from stdin, and write the result to stdout")
+                    .conflicts_with(DIRECTORY_OR_FILES)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_LINES)
+                    .long(FLAG_LINES)
+                    .help("Only format the top-level definitions that overlap these lines, like 3:7. Line numbers start at 1.")
+                    .takes_value(true)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_MAX_WIDTH)
+                    .long(FLAG_MAX_WIDTH)
+                    .help("Break collections, function applications, |> chains and type annotations that would make a line longer than this many columns.")
+                    .takes_value(true)
+                    .validator(|s| s.parse::<usize>())
+                    .required(false),
+            )
         )
         .subcommand(Command::new(CMD_VERSION)
             .about(concatcp!("Print the Roc compiler’s version, which is currently ", VERSION)))
@@ -231,6 +355,16 @@ pub fn build_app<'a>() -> Command<'a> {
             .about("Check the code for problems, but doesn’t build or run it")
             .arg(flag_time.clone())
             .arg(flag_max_threads.clone())
+            .arg(
+                Arg::new(FLAG_UNUSED)
+                    .long(FLAG_UNUSED)
+                    .help("Also warn about exposed values, types and ability implementations that nothing reachable from the app uses")
+                    .required(false),
+            )
+            .arg(flag_deny_warnings.clone())
+            .arg(flag_allow.clone())
+            .arg(flag_warn.clone())
+            .arg(flag_deny.clone())
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -239,6 +373,34 @@ pub fn build_app<'a>() -> Command<'a> {
                     .default_value(DEFAULT_ROC_FILENAME),
             )
             )
+        .subcommand(Command::new(CMD_LSP)
+            .about("Start a language server, which talks to editors over stdin and stdout")
+        )
+        .subcommand(Command::new(CMD_REFACTOR)
+            .about("Change code across all the modules of a package")
+            .subcommand_required(true)
+            .subcommand(Command::new(CMD_RENAME)
+                .about("Rename a top-level definition, and update everything that uses it")
+                .arg(flag_max_threads.clone())
+                .arg(
+                    Arg::new(RENAME_TARGET)
+                        .help("The definition to rename, like `Module.name`, or just `name` for a definition in the .roc file")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(NEW_NAME)
+                        .help("The new name of the definition")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(ROC_FILE)
+                        .help("The .roc file of an app, whose package to change")
+                        .allow_invalid_utf8(true)
+                        .required(false)
+                        .default_value(DEFAULT_ROC_FILENAME),
+                )
+            )
+        )
         .subcommand(
             Command::new(CMD_DOCS)
                 .about("Generate documentation for Roc modules (Work In Progress)")
@@ -270,9 +432,18 @@ pub fn build_app<'a>() -> Command<'a> {
         .arg(flag_opt_size)
         .arg(flag_dev)
         .arg(flag_debug)
+        .arg(flag_debug_refcounts)
+        .arg(flag_profile_allocations)
+        .arg(flag_report_in_place)
+        .arg(flag_emit)
+        .arg(flag_inline)
         .arg(flag_time)
         .arg(flag_linker)
         .arg(flag_precompiled)
+        .arg(flag_deny_warnings)
+        .arg(flag_allow)
+        .arg(flag_warn)
+        .arg(flag_deny)
         .arg(roc_file_to_run.required(false))
         .arg(args_for_app);
 
@@ -322,6 +493,13 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         _ => user_error!("build can be only one of `--dev`, `--optimize`, or `--opt-size`"),
     };
 
+    let debug_refcounts = matches.is_present(FLAG_DEBUG_REFCOUNTS);
+    if debug_refcounts
+        && (matches!(opt_level, OptLevel::Development) || matches.is_present(FLAG_INTERPRET))
+    {
+        user_error!("`--debug-refcounts` needs the LLVM backend, so it cannot be combined with `--dev` or `--interpret`");
+    }
+
     let threading = match matches
         .value_of(FLAG_MAX_THREADS)
         .and_then(|s| s.parse::<usize>().ok())
@@ -366,6 +544,9 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         render: roc_reporting::report::RenderTarget::ColorTerminal,
         threading,
         exec_mode: ExecutionMode::Test,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: false,
     };
     let loaded =
         roc_load::load_and_monomorphize(arena, path.to_path_buf(), subs_by_module, load_config)
@@ -375,16 +556,83 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let mut expectations = std::mem::take(&mut loaded.expectations);
     let loaded = loaded;
 
+    let (failed, passed) = if matches.is_present(FLAG_INTERPRET) {
+        roc_repl_expect::run::run_expects_interpreted(
+            &mut std::io::stdout(),
+            roc_reporting::report::RenderTarget::ColorTerminal,
+            arena,
+            &mut expectations,
+            loaded,
+        )?
+    } else {
+        run_compiled_expects(
+            arena,
+            target,
+            opt_level,
+            debug_refcounts,
+            loaded,
+            &mut expectations,
+        )?
+    };
+
+    let total_time = start_time.elapsed();
+
+    if failed == 0 && passed == 0 {
+        // TODO print this in a more nicely formatted way!
+        println!("No expectations were found.");
+
+        // If no tests ran, treat that as an error. This is perhaps
+        // briefly annoying at the very beginning of a project when
+        // you actually have zero tests, but it can save you from
+        // having a change to your CI script accidentally stop
+        // running tests altogether!
+        Ok(2)
+    } else {
+        let failed_color = if failed == 0 {
+            32 // green
+        } else {
+            31 // red
+        };
+
+        println!(
+            "\n\x1B[{failed_color}m{failed}\x1B[39m failed and \x1B[32m{passed}\x1B[39m passed in {} ms.\n",
+            total_time.as_millis(),
+        );
+
+        Ok((failed > 0) as i32)
+    }
+}
+
+/// Compile the expects to a dylib, run them, and report the ones that fail
+fn run_compiled_expects<'a>(
+    arena: &'a Bump,
+    target: &Triple,
+    opt_level: OptLevel,
+    debug_refcounts: bool,
+    loaded: MonomorphizedModule<'a>,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+) -> io::Result<(usize, usize)> {
     let interns = loaded.interns.clone();
 
-    let (lib, expects) = expect_mono_module_to_dylib(
-        arena,
-        target.clone(),
-        loaded,
-        opt_level,
-        LlvmBackendMode::CliTest,
-    )
-    .unwrap();
+    // `--dev` skips LLVM entirely, which is by far the slowest part of compiling tests
+    let (backend, (lib, expects)) = match opt_level {
+        OptLevel::Development => (
+            ExpectBackend::Dev,
+            expect_mono_module_to_dev_dylib(arena, target.clone(), loaded).unwrap(),
+        ),
+        _ => (
+            ExpectBackend::Llvm,
+            expect_mono_module_to_dylib(
+                arena,
+                target.clone(),
+                loaded,
+                opt_level,
+                LlvmBackendMode::CliTest,
+                debug_refcounts,
+            )
+            .unwrap(),
+        ),
+    };
 
     let arena = &bumpalo::Bump::new();
     let interns = arena.alloc(interns);
@@ -398,44 +646,75 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let slice = (shared_buffer.as_mut_ptr(), shared_buffer.len());
     unsafe { set_shared_buffer(slice, &mut result) };
 
-    let (failed, passed) = roc_repl_expect::run::run_expects(
+    roc_repl_expect::run::run_expects(
         &mut writer,
         roc_reporting::report::RenderTarget::ColorTerminal,
         arena,
         interns,
         &lib,
-        &mut expectations,
+        backend,
+        expectations,
         shared_buffer.as_mut_ptr(),
         expects,
     )
-    .unwrap();
-
-    let total_time = start_time.elapsed();
+}
 
-    if failed == 0 && passed == 0 {
-        // TODO print this in a more nicely formatted way!
-        println!("No expectations were found.");
+fn emit_options(
+    matches: &ArgMatches,
+    opt_level: OptLevel,
+    triple: &Triple,
+    wasm_dev_backend: bool,
+) -> EmitOptions {
+    let mut emit = EmitOptions::default();
+
+    for value in matches.values_of(FLAG_EMIT).into_iter().flatten() {
+        match value {
+            "mono" => emit.mono = true,
+            "llvm-ir" => emit.llvm_ir = true,
+            "asm" => emit.asm = true,
+            "wasm-text" => emit.wasm_text = true,
+            _ => unreachable!("clap only accepts the possible values"),
+        }
+    }
 
-        // If no tests ran, treat that as an error. This is perhaps
-        // briefly annoying at the very beginning of a project when
-        // you actually have zero tests, but it can save you from
-        // having a change to your CI script accidentally stop
-        // running tests altogether!
-        Ok(2)
-    } else {
-        let failed_color = if failed == 0 {
-            32 // green
-        } else {
-            31 // red
-        };
+    if (emit.llvm_ir || emit.asm) && matches!(opt_level, OptLevel::Development) {
+        user_error!("`--emit=llvm-ir` and `--emit=asm` need the LLVM backend, so they cannot be combined with `--dev`");
+    }
 
-        println!(
-            "\n\x1B[{failed_color}m{failed}\x1B[39m failed and \x1B[32m{passed}\x1B[39m passed in {} ms.\n",
-            total_time.as_millis(),
+    if emit.asm && matches!(triple.architecture, Architecture::Wasm32) {
+        user_error!(
+            "`--emit=asm` is for native targets. For wasm32, try `--emit=wasm-text` with `--dev`"
         );
+    }
 
-        Ok((failed > 0) as i32)
+    if emit.wasm_text && !wasm_dev_backend {
+        user_error!("`--emit=wasm-text` shows the output of the wasm dev backend, so it needs `--dev --target wasm32`");
     }
+
+    emit
+}
+
+/// The levels of the `--allow`, `--warn` and `--deny` flags. When a kind of problem is given to
+/// more than one of them, the most severe level wins.
+pub fn warning_levels(matches: &ArgMatches) -> WarningLevels {
+    let mut levels = WarningLevels {
+        deny_warnings: matches.is_present(FLAG_DENY_WARNINGS),
+        ..WarningLevels::default()
+    };
+
+    for (flag, level) in [
+        (FLAG_ALLOW, Level::Allow),
+        (FLAG_WARN, Level::Warn),
+        (FLAG_DENY, Level::Deny),
+    ] {
+        for name in matches.values_of(flag).into_iter().flatten() {
+            let lint = Lint::from_name(name).expect("clap only accepts the possible values");
+
+            levels.set(lint, level);
+        }
+    }
+
+    levels
 }
 
 pub fn build(
@@ -461,7 +740,27 @@ pub fn build(
         _ => user_error!("build can be only one of `--dev`, `--optimize`, or `--opt-size`"),
     };
     let emit_debug_info = matches.is_present(FLAG_DEBUG);
+    let debug_refcounts = matches.is_present(FLAG_DEBUG_REFCOUNTS);
+    if debug_refcounts && matches!(opt_level, OptLevel::Development) {
+        user_error!(
+            "`--debug-refcounts` needs the LLVM backend, so it cannot be combined with `--dev`"
+        );
+    }
+    let profile_allocations = matches.is_present(FLAG_PROFILE_ALLOCATIONS);
+    if profile_allocations && matches!(opt_level, OptLevel::Development) {
+        user_error!(
+            "`--profile-allocations` needs the LLVM backend, so it cannot be combined with `--dev`"
+        );
+    }
+    let report_in_place = matches.is_present(FLAG_REPORT_IN_PLACE);
+    if report_in_place && matches!(opt_level, OptLevel::Development) {
+        user_error!(
+            "`--report-in-place` describes the LLVM backend, so it cannot be combined with `--dev`"
+        );
+    }
+    let inline_and_fold = matches.is_present(FLAG_INLINE);
     let emit_timings = matches.is_present(FLAG_TIME);
+    let warning_levels = warning_levels(matches);
 
     let threading = match matches
         .value_of(FLAG_MAX_THREADS)
@@ -476,6 +775,8 @@ pub fn build(
     let wasm_dev_backend = matches!(opt_level, OptLevel::Development)
         && matches!(triple.architecture, Architecture::Wasm32);
 
+    let emit = emit_options(matches, opt_level, &triple, wasm_dev_backend);
+
     let linking_strategy = if wasm_dev_backend {
         LinkingStrategy::Additive
     } else if !roc_linker::supported(link_type, &triple)
@@ -527,7 +828,13 @@ pub fn build(
         path.to_path_buf(),
         opt_level,
         emit_debug_info,
+        debug_refcounts,
+        profile_allocations,
+        report_in_place,
+        emit,
+        inline_and_fold,
         emit_timings,
+        &warning_levels,
         link_type,
         linking_strategy,
         precompiled,
@@ -1,13 +1,16 @@
 use roc_build::link::LinkType;
 use roc_cli::build::check_file;
 use roc_cli::{
-    build_app, format, test, BuildConfig, FormatMode, Target, CMD_BUILD, CMD_CHECK, CMD_DOCS,
-    CMD_EDIT, CMD_FORMAT, CMD_GLUE, CMD_REPL, CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES,
-    FLAG_CHECK, FLAG_LIB, FLAG_NO_LINK, FLAG_TARGET, FLAG_TIME, GLUE_FILE, ROC_FILE,
+    build_app, format, format_stdin, parse_line_range, test, warning_levels, BuildConfig,
+    FormatMode, Target, CMD_BUILD, CMD_CHECK, CMD_DOCS, CMD_EDIT, CMD_FORMAT, CMD_GLUE, CMD_LSP,
+    CMD_REFACTOR, CMD_RENAME, CMD_REPL, CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES,
+    FLAG_CHECK, FLAG_INTERPRET, FLAG_LIB, FLAG_LINES, FLAG_MAX_WIDTH, FLAG_NO_LINK, FLAG_STDIN,
+    FLAG_TARGET, FLAG_TIME, FLAG_UNUSED, GLUE_FILE, NEW_NAME, RENAME_TARGET, ROC_FILE,
 };
 use roc_docs::generate_docs_html;
 use roc_error_macros::user_error;
 use roc_load::{LoadingProblem, Threading};
+use roc_repl_cli::ReplBackend;
 use std::fs::{self, FileType};
 use std::io;
 use std::path::{Path, PathBuf};
@@ -57,7 +60,13 @@ fn main() -> io::Result<()> {
         }
         Some((CMD_TEST, matches)) => {
             if matches.is_present(ROC_FILE) {
-                test(matches, Triple::host())
+                if matches.is_present(FLAG_INTERPRET) {
+                    let matches = matches.clone();
+
+                    roc_interpret::with_native_stack(move || test(&matches, Triple::host()))
+                } else {
+                    test(matches, Triple::host())
+                }
             } else {
                 eprintln!("What .roc file do you want to test? Specify it at the end of the `roc test` command.");
 
@@ -100,6 +109,8 @@ fn main() -> io::Result<()> {
             let arena = bumpalo::Bump::new();
 
             let emit_timings = matches.is_present(FLAG_TIME);
+            let report_unused = matches.is_present(FLAG_UNUSED);
+            let warning_levels = warning_levels(matches);
             let filename = matches.value_of_os(ROC_FILE).unwrap();
             let roc_file_path = PathBuf::from(filename);
             let threading = match matches
@@ -112,7 +123,14 @@ fn main() -> io::Result<()> {
                 Some(n) => Threading::AtMost(n),
             };
 
-            match check_file(&arena, roc_file_path, emit_timings, threading) {
+            match check_file(
+                &arena,
+                roc_file_path,
+                emit_timings,
+                report_unused,
+                &warning_levels,
+                threading,
+            ) {
                 Ok((problems, total_time)) => {
                     println!(
                         "\x1B[{}m{}\x1B[39m {} and \x1B[{}m{}\x1B[39m {} found in {} ms.",
@@ -154,14 +172,40 @@ fn main() -> io::Result<()> {
                 }
             }
         }
-        Some((CMD_REPL, _)) => {
+        Some((CMD_REPL, matches)) => {
             {
-                roc_repl_cli::main()?;
+                if matches.is_present(FLAG_INTERPRET) {
+                    roc_interpret::with_native_stack(|| {
+                        roc_repl_cli::main(ReplBackend::Interpreter)
+                    })?;
+                } else {
+                    roc_repl_cli::main(ReplBackend::Llvm)?;
+                }
 
                 // Exit 0 if the repl exited normally
                 Ok(0)
             }
         }
+        Some((CMD_LSP, _)) => roc_lang_srv::run_stdio(),
+        Some((CMD_REFACTOR, matches)) => match matches.subcommand() {
+            Some((CMD_RENAME, matches)) => {
+                let filename = matches.value_of_os(ROC_FILE).unwrap();
+                let target = matches.value_of(RENAME_TARGET).unwrap();
+                let new_name = matches.value_of(NEW_NAME).unwrap();
+                let threading = match matches
+                    .value_of(roc_cli::FLAG_MAX_THREADS)
+                    .and_then(|s| s.parse::<usize>().ok())
+                {
+                    None => Threading::AllAvailable,
+                    Some(0) => user_error!("cannot build with at most 0 threads"),
+                    Some(1) => Threading::Single,
+                    Some(n) => Threading::AtMost(n),
+                };
+
+                roc_cli::refactor::rename(PathBuf::from(filename), target, new_name, threading)
+            }
+            _ => unreachable!(),
+        },
         Some((CMD_EDIT, matches)) => {
             match matches
                 .values_of_os(DIRECTORY_OR_FILES)
@@ -214,42 +258,65 @@ fn main() -> io::Result<()> {
             Ok(0)
         }
         Some((CMD_FORMAT, matches)) => {
-            let maybe_values = matches.values_of_os(DIRECTORY_OR_FILES);
+            let format_mode = match matches.is_present(FLAG_CHECK) {
+                true => FormatMode::CheckOnly,
+                false => FormatMode::Format,
+            };
 
-            let mut values: Vec<OsString> = Vec::new();
+            let lines = matches
+                .value_of(FLAG_LINES)
+                .map(parse_line_range)
+                .transpose();
 
-            match maybe_values {
-                None => {
-                    let mut os_string_values: Vec<OsString> = Vec::new();
-                    read_all_roc_files(
-                        &std::env::current_dir()?.as_os_str().to_os_string(),
-                        &mut os_string_values,
-                    )?;
-                    for os_string in os_string_values {
-                        values.push(os_string);
-                    }
+            let max_width = matches
+                .value_of(FLAG_MAX_WIDTH)
+                .and_then(|s| s.parse::<usize>().ok());
+
+            let format_result = match lines {
+                Err(message) => Err(message),
+                Ok(lines) if matches.is_present(FLAG_STDIN) => {
+                    format_stdin(format_mode, lines, max_width)
                 }
-                Some(os_values) => {
-                    for os_str in os_values {
-                        values.push(os_str.to_os_string());
+                Ok(lines) => {
+                    let maybe_values = matches.values_of_os(DIRECTORY_OR_FILES);
+
+                    let mut values: Vec<OsString> = Vec::new();
+
+                    match maybe_values {
+                        None => {
+                            let mut os_string_values: Vec<OsString> = Vec::new();
+                            read_all_roc_files(
+                                &std::env::current_dir()?.as_os_str().to_os_string(),
+                                &mut os_string_values,
+                            )?;
+                            for os_string in os_string_values {
+                                values.push(os_string);
+                            }
+                        }
+                        Some(os_values) => {
+                            for os_str in os_values {
+                                values.push(os_str.to_os_string());
+                            }
+                        }
                     }
-                }
-            }
 
-            let mut roc_files = Vec::new();
+                    let mut roc_files = Vec::new();
 
-            // Populate roc_files
-            for os_str in values {
-                let metadata = fs::metadata(os_str.clone())?;
-                roc_files_recursive(os_str.as_os_str(), metadata.file_type(), &mut roc_files)?;
-            }
+                    // Populate roc_files
+                    for os_str in values {
+                        let metadata = fs::metadata(os_str.clone())?;
+                        roc_files_recursive(
+                            os_str.as_os_str(),
+                            metadata.file_type(),
+                            &mut roc_files,
+                        )?;
+                    }
 
-            let format_mode = match matches.is_present(FLAG_CHECK) {
-                true => FormatMode::CheckOnly,
-                false => FormatMode::Format,
+                    format(roc_files, format_mode, lines, max_width)
+                }
             };
 
-            let format_exit_code = match format(roc_files, format_mode) {
+            let format_exit_code = match format_result {
                 Ok(_) => 0,
                 Err(message) => {
                     eprintln!("{}", message);
@@ -0,0 +1,80 @@
+use bumpalo::Bump;
+use roc_load::{LoadingProblem, Threading};
+use roc_refactor::{rename_in_package, RenameError};
+use roc_reporting::report::RenderTarget;
+use std::io;
+use std::path::PathBuf;
+
+/// Rename the top-level definition `target` everywhere in the package of `roc_file`, and write
+/// the changed modules back. Returns the exit code.
+pub fn rename(
+    roc_file: PathBuf,
+    target: &str,
+    new_name: &str,
+    threading: Threading,
+) -> io::Result<i32> {
+    let arena = Bump::new();
+
+    let changed = match rename_in_package(
+        &arena,
+        roc_file,
+        target,
+        new_name,
+        RenderTarget::ColorTerminal,
+        threading,
+    ) {
+        Ok(changed) => changed,
+        Err(RenameError::Loading(LoadingProblem::FormattedReport(report))) => {
+            print!("{}", report);
+
+            return Ok(1);
+        }
+        Err(RenameError::Loading(other)) => {
+            panic!("loading the package failed with error:\n{:?}", other);
+        }
+        Err(RenameError::NotFound(target)) => {
+            eprintln!(
+                "I could not find a top-level definition named `{}` in this package.",
+                target
+            );
+
+            return Ok(1);
+        }
+        Err(RenameError::InvalidName(new_name)) => {
+            eprintln!("`{}` can not be the new name of `{}`.", new_name, target);
+
+            return Ok(1);
+        }
+        Err(RenameError::Shadowing(reports)) => {
+            for report in reports {
+                println!("{}\n", report);
+            }
+
+            eprintln!(
+                "I did not rename `{}`, because `{}` is already used for something else.",
+                target, new_name
+            );
+
+            return Ok(1);
+        }
+    };
+
+    let mut edits = 0;
+
+    for module in changed.iter() {
+        std::fs::write(&module.path, &module.source)?;
+
+        edits += module.edits.len();
+    }
+
+    println!(
+        "Renamed {} occurrence{} of `{}` in {} module{}.",
+        edits,
+        if edits == 1 { "" } else { "s" },
+        target,
+        changed.len(),
+        if changed.len() == 1 { "" } else { "s" },
+    );
+
+    Ok(0)
+}
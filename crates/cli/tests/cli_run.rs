@@ -16,7 +16,7 @@ mod cli_run {
     };
     use const_format::concatcp;
     use indoc::indoc;
-    use roc_cli::{CMD_BUILD, CMD_CHECK, CMD_FORMAT, CMD_RUN};
+    use roc_cli::{CMD_BUILD, CMD_CHECK, CMD_FORMAT, CMD_RUN, CMD_TEST};
     use roc_test_utils::assert_multiline_str_eq;
     use serial_test::serial;
     use std::iter;
@@ -27,6 +27,16 @@ mod cli_run {
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const DEV_FLAG: &str = concatcp!("--", roc_cli::FLAG_DEV);
+    const PROFILE_ALLOCATIONS_FLAG: &str = concatcp!("--", roc_cli::FLAG_PROFILE_ALLOCATIONS);
+    const EMIT_FLAG: &str = concatcp!("--", roc_cli::FLAG_EMIT);
+    const INLINE_FLAG: &str = concatcp!("--", roc_cli::FLAG_INLINE);
+    const DENY_WARNINGS_FLAG: &str = concatcp!("--", roc_cli::FLAG_DENY_WARNINGS);
+    const ALLOW_FLAG: &str = concatcp!("--", roc_cli::FLAG_ALLOW);
+    const DENY_FLAG: &str = concatcp!("--", roc_cli::FLAG_DENY);
+    const STDIN_FLAG: &str = concatcp!("--", roc_cli::FLAG_STDIN);
+    const LINES_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINES);
+    const MAX_WIDTH_FLAG: &str = concatcp!("--", roc_cli::FLAG_MAX_WIDTH);
     const PRECOMPILED_HOST: &str = concatcp!("--", roc_cli::FLAG_PRECOMPILED, "=true");
     #[allow(dead_code)]
     const TARGET_FLAG: &str = concatcp!("--", roc_cli::FLAG_TARGET);
@@ -749,6 +759,22 @@ mod cli_run {
                 expected_ending: "10\n",
                 use_valgrind: true,
             },
+            cons_list => Example {
+                filename: "ConsList.roc",
+                executable_filename: "cons-list",
+                stdin: &["100"],
+                input_file: None,
+                expected_ending: "3775\n",
+                use_valgrind: true,
+            },
+            small_records => Example {
+                filename: "SmallRecords.roc",
+                executable_filename: "small-records",
+                stdin: &["100"],
+                input_file: None,
+                expected_ending: "5050 & 100 & 176750\n",
+                use_valgrind: true,
+            },
             rbtree_insert => Example {
                 filename: "RBTreeInsert.roc",
                 executable_filename: "rbtree-insert",
@@ -920,6 +946,20 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[serial(multi_dep_str)]
+    fn run_multi_dep_str_inlined() {
+        check_output_with_stdin(
+            &fixture_file("multi-dep-str", "Main.roc"),
+            &[],
+            "multi-dep-str",
+            &[INLINE_FLAG],
+            None,
+            "I am Dep2.str2\n",
+            true,
+        );
+    }
+
     #[test]
     #[serial(multi_dep_thunk)]
     fn run_multi_dep_thunk_unoptimized() {
@@ -948,6 +988,206 @@ mod cli_run {
         );
     }
 
+    #[test]
+    fn test_dev_backend() {
+        let out = run_roc(
+            [
+                CMD_TEST,
+                DEV_FLAG,
+                fixture_file("expects", "Expects.roc").to_str().unwrap(),
+            ],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+
+        assert!(!out.status.success(), "{}", stdout);
+        assert!(stdout.contains("3 failed and 1 passed in "), "{}", stdout);
+
+        // looked up values, including refcounted ones, are copied out of the dev backend's code
+        for expected in [
+            "a = 1",
+            "b = 2",
+            r#"str = "short""#,
+            "list = [1, 2, 3]",
+            r#"name: "Roc""#,
+            r#"result = Ok "done""#,
+        ] {
+            assert!(
+                stdout.contains(expected),
+                "missing {:?} in {}",
+                expected,
+                stdout
+            );
+        }
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    fn emit_mono_and_llvm_ir() {
+        let file = fixture_file("multi-dep-str", "Main.roc");
+        let mono_path = file.with_file_name("multi-dep-str.mono");
+        let llvm_ir_path = file.with_file_name("multi-dep-str.ll");
+
+        for path in [&mono_path, &llvm_ir_path] {
+            let _ = std::fs::remove_file(path);
+        }
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                concatcp!(EMIT_FLAG, "=mono,llvm-ir"),
+                file.to_str().unwrap(),
+            ],
+            &[],
+        );
+        assert!(out.status.success(), "bad status {:?}", out);
+
+        // symbols are printed with their names, also by release builds of the compiler
+        let mono = std::fs::read_to_string(&mono_path).unwrap();
+        assert!(mono.contains("procedure : Dep1.str1 Str"), "{}", mono);
+        assert!(mono.contains("procedure = Dep2.str2 ():"), "{}", mono);
+
+        let llvm_ir = std::fs::read_to_string(&llvm_ir_path).unwrap();
+        assert!(
+            llvm_ir.lines().any(|line| line.starts_with("define ")
+                && line.contains("@roc__mainForHost_1_exposed_generic(")),
+            "{}",
+            llvm_ir
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wasm32-cli-run")]
+    fn emit_wasm_text() {
+        let file = examples_dir("platform-switching/web-assembly-platform")
+            .join("rocLovesWebAssembly.roc");
+        let wat_path = file.with_extension("wat");
+
+        let _ = std::fs::remove_file(&wat_path);
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                DEV_FLAG,
+                concatcp!(TARGET_FLAG, "=wasm32"),
+                concatcp!(EMIT_FLAG, "=wasm-text"),
+                file.to_str().unwrap(),
+            ],
+            &[],
+        );
+        assert!(out.status.success(), "bad status {:?}", out);
+
+        let wat = std::fs::read_to_string(&wat_path).unwrap();
+        assert!(wat.starts_with("(module\n"), "{}", wat);
+        assert!(wat.contains("(func "), "{}", wat);
+    }
+
+    /// The counts of one allocation site in the summary of `--profile-allocations`
+    #[derive(Debug)]
+    struct AllocationSite {
+        allocations: u64,
+        bytes: u64,
+        reuses: u64,
+        site: String,
+    }
+
+    fn allocation_sites(summary: &str) -> Vec<AllocationSite> {
+        summary
+            .lines()
+            .filter_map(|line| {
+                // allocations, bytes, reallocs, realloc bytes, reuses, and then the site, which
+                // may contain spaces
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let numbers: Vec<u64> = fields
+                    .get(..5)?
+                    .iter()
+                    .map(|field| field.parse().ok())
+                    .collect::<Option<_>>()?;
+
+                Some(AllocationSite {
+                    allocations: numbers[0],
+                    bytes: numbers[1] + numbers[3],
+                    reuses: numbers[4],
+                    site: fields[5..].join(" "),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    #[serial(allocations)]
+    fn profile_allocations() {
+        let file = fixture_file("allocations", "Main.roc");
+        let compile_out = run_roc(
+            [
+                CMD_BUILD,
+                PROFILE_ALLOCATIONS_FLAG,
+                "--max-threads=1",
+                file.to_str().unwrap(),
+            ],
+            &[],
+        );
+        assert!(compile_out.status.success(), "bad status {:?}", compile_out);
+
+        // the profile is written to the working directory of the program
+        let dir = tempfile::tempdir().unwrap();
+        let out = std::process::Command::new(file.with_file_name("allocations"))
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        let stderr = String::from_utf8(out.stderr).unwrap();
+
+        assert!(out.status.success(), "{}", stderr);
+        assert_eq!(stdout, "65\n");
+        assert!(
+            stderr.contains(
+                "Allocation profile, by bytes requested (also written to roc-allocations.folded):"
+            ),
+            "{}",
+            stderr
+        );
+
+        let sites = allocation_sites(&stderr);
+        let proc_of = |site: &AllocationSite| site.site.split(';').next().unwrap().to_string();
+
+        // `build` allocates a cell for each of the 10 elements
+        let build = sites
+            .iter()
+            .find(|site| proc_of(site).ends_with("build"))
+            .unwrap_or_else(|| panic!("no allocations by `build` in {}", stderr));
+        assert_eq!(build.allocations, 10, "{}", stderr);
+        assert_eq!(build.reuses, 0, "{}", stderr);
+
+        // `increment` reuses all of those cells, so it allocates nothing
+        let increment: Vec<_> = sites
+            .iter()
+            .filter(|site| proc_of(site).ends_with("increment"))
+            .collect();
+        assert_eq!(increment.len(), 1, "{}", stderr);
+        assert!(increment[0].site.ends_with(";reuse"), "{}", stderr);
+        assert_eq!(increment[0].allocations, 0, "{}", stderr);
+        assert_eq!(increment[0].reuses, 10, "{}", stderr);
+
+        // the folded file has a line for every site that requested bytes
+        let folded = std::fs::read_to_string(dir.path().join("roc-allocations.folded")).unwrap();
+        let folded_build = format!("{} {}", build.site, build.bytes);
+        assert!(
+            folded.lines().any(|line| line == folded_build),
+            "missing {:?} in {}",
+            folded_build,
+            folded
+        );
+        assert!(
+            !folded
+                .lines()
+                .any(|line| line.starts_with(&increment[0].site)),
+            "{}",
+            folded
+        );
+    }
+
     #[test]
     fn known_type_error() {
         check_compile_error(
@@ -1020,6 +1260,57 @@ mod cli_run {
         );
     }
 
+    /// The exit code of `roc check`, and its summary, e.g. "0 errors and 1 warning"
+    fn check_exit_code(file: &Path, flags: &[&str]) -> (Option<i32>, String) {
+        let out = run_roc([CMD_CHECK, file.to_str().unwrap()].iter().chain(flags), &[]);
+        let stdout = strip_colors(out.stdout.trim());
+        let summary = stdout.lines().last().unwrap_or_default();
+        let summary = summary.split(" found in ").next().unwrap().to_string();
+
+        (out.status.code(), summary)
+    }
+
+    #[test]
+    fn warning_exit_codes() {
+        let file = known_bad_file("UnusedImport.roc");
+
+        // warnings alone exit with 2, so scripts can tell them apart from errors
+        assert_eq!(
+            check_exit_code(&file, &[]),
+            (Some(2), "0 errors and 1 warning".to_string())
+        );
+        assert_eq!(
+            check_exit_code(&file, &[concatcp!(ALLOW_FLAG, "=unused-import")]),
+            (Some(0), "0 errors and 0 warnings".to_string())
+        );
+        assert_eq!(
+            check_exit_code(&file, &[concatcp!(DENY_FLAG, "=unused-import")]),
+            (Some(1), "1 error and 0 warnings".to_string())
+        );
+        assert_eq!(
+            check_exit_code(&file, &[DENY_WARNINGS_FLAG]),
+            (Some(1), "1 error and 0 warnings".to_string())
+        );
+        assert_eq!(
+            check_exit_code(&known_bad_file("TypeError.roc"), &[]),
+            (Some(1), "1 error and 0 warnings".to_string())
+        );
+    }
+
+    #[test]
+    fn module_comment_allows_warning() {
+        let file = known_bad_file("AllowedUnusedImport.roc");
+
+        // the module's comment takes precedence over the command line
+        assert_eq!(
+            check_exit_code(
+                &file,
+                &[DENY_WARNINGS_FLAG, concatcp!(DENY_FLAG, "=unused-import")]
+            ),
+            (Some(0), "0 errors and 0 warnings".to_string())
+        );
+    }
+
     #[test]
     fn unknown_generates_with() {
         check_compile_error(
@@ -1062,6 +1353,105 @@ mod cli_run {
         // This doesn't fail, since only "Formatted.roc" and non-roc files are present in this folder
         check_format_check_as_expected(&fixtures_dir("format/formatted_directory"), true);
     }
+
+    #[test]
+    fn format_stdin() {
+        let not_formatted =
+            std::fs::read_to_string(fixture_file("format", "NotFormatted.roc")).unwrap();
+        let formatted = std::fs::read_to_string(fixture_file("format", "Formatted.roc")).unwrap();
+
+        let out = run_roc([CMD_FORMAT, STDIN_FLAG], &[&not_formatted]);
+
+        assert!(out.status.success());
+        assert_multiline_str_eq!(out.stdout.as_str(), formatted.as_str());
+    }
+
+    #[test]
+    fn format_stdin_lines() {
+        let src = indoc!(
+            r#"
+            interface Test
+                exposes [a, b]
+                imports []
+
+            a = [1,2,   3]
+
+            b = [1,2,   3]
+            "#
+        );
+
+        // only `b` overlaps line 7
+        let out = run_roc([CMD_FORMAT, STDIN_FLAG, LINES_FLAG, "7:7"], &[src]);
+
+        assert!(out.status.success());
+        assert_multiline_str_eq!(
+            out.stdout.as_str(),
+            indoc!(
+                r#"
+                interface Test
+                    exposes [a, b]
+                    imports []
+
+                a = [1,2,   3]
+
+                b = [1, 2, 3]
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn format_stdin_max_width() {
+        let src = indoc!(
+            r#"
+            interface Test
+                exposes [numbers]
+                imports []
+
+            numbers = [1000000, 2000000, 3000000, 4000000]
+            "#
+        );
+
+        let out = run_roc([CMD_FORMAT, STDIN_FLAG, MAX_WIDTH_FLAG, "40"], &[src]);
+
+        assert!(out.status.success());
+        assert_multiline_str_eq!(
+            out.stdout.as_str(),
+            indoc!(
+                r#"
+                interface Test
+                    exposes [numbers]
+                    imports []
+
+                numbers = [
+                    1000000,
+                    2000000,
+                    3000000,
+                    4000000,
+                ]
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn format_stdin_syntax_error() {
+        let src = indoc!(
+            r#"
+            interface Test
+                exposes [a]
+                imports []
+
+            a = [1, 2
+            "#
+        );
+
+        let out = run_roc([CMD_FORMAT, STDIN_FLAG], &[src]);
+
+        assert!(!out.status.success());
+        assert!(out.stdout.is_empty());
+        assert!(out.stderr.contains("UNFINISHED LIST"), "{}", out.stderr);
+    }
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,34 @@
+app "allocations"
+    packages { pf: "platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+LinkedList : [Nil, Cons I64 LinkedList]
+
+main : Str
+main =
+    build 10 Nil
+    |> increment
+    |> sum 0
+    |> Num.toStr
+
+# allocates a cell for every element
+build : I64, LinkedList -> LinkedList
+build = \n, list ->
+    if n == 0 then
+        list
+    else
+        build (n - 1) (Cons n list)
+
+# the list is unique, so every cell is reused
+increment : LinkedList -> LinkedList
+increment = \list ->
+    when list is
+        Nil -> Nil
+        Cons x rest -> Cons (x + 1) (increment rest)
+
+sum : LinkedList, I64 -> I64
+sum = \list, acc ->
+    when list is
+        Nil -> acc
+        Cons x rest -> sum rest (acc + x)
@@ -0,0 +1,26 @@
+interface Expects
+    exposes []
+    imports []
+
+expect 1 + 1 == 2
+
+expect
+    a = 1
+    b = 2
+
+    a == b
+
+expect
+    str = "short"
+    list = [1, 2, 3]
+
+    Str.countUtf8Bytes str == List.len list
+
+expect
+    record = { name: "Roc", count: 3 }
+    result : Result Str U64
+    result = Ok "done"
+
+    when result is
+        Ok str -> Str.countUtf8Bytes str == record.count
+        Err _ -> False
@@ -0,0 +1,9 @@
+interface AllowedUnusedImport
+    exposes [plainText, emText]
+    imports [Symbol.{ Ident }]
+
+# roc: allow(unused-import)
+
+plainText = \str -> PlainText str
+
+emText = \str -> EmText str
@@ -147,6 +147,26 @@ pub fn bench_rbtree_ck<T: Measurement>(bench_group_opt: Option<&mut BenchmarkGro
     );
 }
 
+pub fn bench_cons_list<T: Measurement>(bench_group_opt: Option<&mut BenchmarkGroup<T>>) {
+    exec_bench_w_input(
+        &example_file("benchmarks", "ConsList.roc"),
+        "1000000",
+        "cons-list",
+        "375000250000\n",
+        bench_group_opt,
+    );
+}
+
+pub fn bench_small_records<T: Measurement>(bench_group_opt: Option<&mut BenchmarkGroup<T>>) {
+    exec_bench_w_input(
+        &example_file("benchmarks", "SmallRecords.roc"),
+        "1000000",
+        "small-records",
+        "500000500000 & 1000000 & 166667666667500000\n",
+        bench_group_opt,
+    );
+}
+
 #[allow(dead_code)]
 pub fn bench_rbtree_delete<T: Measurement>(bench_group_opt: Option<&mut BenchmarkGroup<T>>) {
     exec_bench_w_input(
@@ -141,6 +141,24 @@ pub fn else_mn() -> MarkupNode {
     keyword_mn(" else ")
 }
 
+pub fn when_mn() -> MarkupNode {
+    keyword_mn("when ")
+}
+
+// the branches of a when start on the next line
+pub fn is_mn() -> MarkupNode {
+    common_text_node(" is".to_owned(), HighlightStyle::Keyword, 1)
+}
+
+// for a guard in a when branch, e.g. `x if x > 3 -> ...`
+pub fn guard_if_mn() -> MarkupNode {
+    keyword_mn(" if ")
+}
+
+pub fn new_pipe_mn() -> MarkupNode {
+    new_operator_mn(nodes::PIPE.to_owned())
+}
+
 fn keyword_mn(keyword: &str) -> MarkupNode {
     common_text_node(keyword.to_owned(), HighlightStyle::Keyword, 0)
 }
@@ -2,13 +2,15 @@ use crate::{
     markup::{
         attribute::Attributes,
         common_nodes::{
-            new_arg_name_mn, new_arrow_mn, new_blank_mn, new_colon_mn, new_comma_mn, new_equals_mn,
-            new_left_accolade_mn, new_left_square_mn, new_operator_mn, new_right_accolade_mn,
-            new_right_square_mn,
+            else_mn, guard_if_mn, if_mn, is_mn, new_arg_name_mn, new_arrow_mn, new_blank_mn,
+            new_colon_mn, new_comma_mn, new_equals_mn, new_left_accolade_mn, new_left_square_mn,
+            new_operator_mn, new_pipe_mn, new_right_accolade_mn, new_right_square_mn, then_mn,
+            when_mn,
         },
         mark_id_ast_id_map::MarkIdAstIdMap,
         nodes::{
-            get_string, join_mark_nodes_commas, join_mark_nodes_spaces, new_markup_node, MarkupNode,
+            get_string, join_mark_nodes_commas, join_mark_nodes_spaces, make_nested_mn,
+            new_markup_node, MarkupNode,
         },
     },
     slow_pool::{MarkNodeId, SlowPool},
@@ -22,18 +24,21 @@ use roc_ast::{
         core::{
             ast::ASTNodeId,
             expr::{
-                expr2::{Expr2, ExprId},
+                expr2::{Expr2, ExprId, WhenBranch},
                 record_field::RecordField,
             },
-            pattern::{get_identifier_string, Pattern2},
             val_def::ValueDef,
         },
         env::Env,
     },
+    mem_pool::pool::NodeId,
 };
-use roc_module::{module_err::ModuleResult, symbol::Interns};
+use roc_module::symbol::Interns;
 
-use super::from_def2::add_node;
+use super::{
+    from_def2::add_node,
+    from_pattern2::{pattern2_to_markup, pattern2_to_string},
+};
 
 // make Markup Nodes: generate String representation, assign Highlighting Style
 pub fn expr2_to_markup<'a>(
@@ -312,7 +317,7 @@ pub fn expr2_to_markup<'a>(
 
             let pattern2 = env.pool.get(pattern_id);
 
-            let val_name = get_identifier_string(pattern2, interns)?;
+            let val_name = pattern2_to_string(env, pattern2)?;
 
             let val_name_mn = MarkupNode::Text {
                 content: val_name,
@@ -385,34 +390,12 @@ pub fn expr2_to_markup<'a>(
                 mark_id_ast_id_map,
             );
 
-            let arg_names: Vec<&str> = args
+            let arg_names: Vec<String> = args
                 .iter(env.pool)
-                .map(|(_, arg_node_id)| {
-                    let arg_pattern2 = env.pool.get(*arg_node_id);
-
-                    match arg_pattern2 {
-                        Pattern2::Identifier(id_symbol) => {
-                            let ident_id = id_symbol.ident_id();
-
-                            env.ident_ids.get_name_str_res(ident_id)
-                        }
-                        Pattern2::Shadowed { shadowed_ident } => {
-                            Ok(shadowed_ident.as_str(env.pool))
-                        }
-                        other => {
-                            todo!(
-                                "TODO: support the following pattern2 as function arg: {:?}",
-                                other
-                            );
-                        }
-                    }
-                })
-                .collect::<ModuleResult<Vec<&str>>>()?;
+                .map(|(_, arg_node_id)| pattern2_to_string(env, env.pool.get(*arg_node_id)))
+                .collect::<ASTResult<Vec<String>>>()?;
 
-            let arg_mark_nodes = arg_names
-                .iter()
-                .map(|arg_name| new_arg_name_mn(arg_name.to_string()))
-                .collect_vec();
+            let arg_mark_nodes = arg_names.into_iter().map(new_arg_name_mn).collect_vec();
 
             let args_with_commas: Vec<MarkupNode> = join_mark_nodes_commas(arg_mark_nodes);
 
@@ -423,7 +406,7 @@ pub fn expr2_to_markup<'a>(
                 })
                 .collect();
 
-            let arrow_mn = new_arrow_mn(1);
+            let arrow_mn = new_arrow_mn(0);
             let arrow_mn_id = add_node(arrow_mn, ast_node_id, mark_node_pool, mark_id_ast_id_map);
 
             let mut children_ids = vec![backslash_mn_id];
@@ -445,7 +428,7 @@ pub fn expr2_to_markup<'a>(
                 mark_node_pool,
                 mark_id_ast_id_map,
                 interns,
-                indent_level + 1,
+                0,
             )?;
 
             let function_mn = MarkupNode::Nested {
@@ -456,6 +439,124 @@ pub fn expr2_to_markup<'a>(
 
             add_node(function_mn, ast_node_id, mark_node_pool, mark_id_ast_id_map)
         }
+        Expr2::If {
+            branches,
+            final_else,
+            ..
+        } => {
+            let mut children_ids = Vec::new();
+
+            let branch_ids: Vec<(ExprId, ExprId)> = branches.iter(env.pool).copied().collect();
+
+            for (idx, (cond_id, then_id)) in branch_ids.iter().enumerate() {
+                if idx > 0 {
+                    // else if
+                    children_ids.push(add_node(
+                        else_mn(),
+                        ast_node_id,
+                        mark_node_pool,
+                        mark_id_ast_id_map,
+                    ));
+                }
+
+                children_ids.push(add_node(
+                    if_mn(),
+                    ast_node_id,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                ));
+                children_ids.push(expr2_to_markup(
+                    env,
+                    env.pool.get(*cond_id),
+                    *cond_id,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                    interns,
+                    0,
+                )?);
+                children_ids.push(add_node(
+                    then_mn(),
+                    ast_node_id,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                ));
+                children_ids.push(expr2_to_markup(
+                    env,
+                    env.pool.get(*then_id),
+                    *then_id,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                    interns,
+                    0,
+                )?);
+            }
+
+            children_ids.push(add_node(
+                else_mn(),
+                ast_node_id,
+                mark_node_pool,
+                mark_id_ast_id_map,
+            ));
+            children_ids.push(expr2_to_markup(
+                env,
+                env.pool.get(*final_else),
+                *final_else,
+                mark_node_pool,
+                mark_id_ast_id_map,
+                interns,
+                0,
+            )?);
+
+            add_node(
+                make_nested_mn(children_ids, 0),
+                ast_node_id,
+                mark_node_pool,
+                mark_id_ast_id_map,
+            )
+        }
+        Expr2::When { cond, branches, .. } => {
+            let mut children_ids = vec![
+                add_node(when_mn(), ast_node_id, mark_node_pool, mark_id_ast_id_map),
+                expr2_to_markup(
+                    env,
+                    env.pool.get(*cond),
+                    *cond,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                    interns,
+                    0,
+                )?,
+                add_node(is_mn(), ast_node_id, mark_node_pool, mark_id_ast_id_map),
+            ];
+
+            let branch_node_ids: Vec<NodeId<WhenBranch>> = branches.iter_node_ids().collect();
+
+            for (idx, branch_node_id) in branch_node_ids.iter().enumerate() {
+                let newlines_at_end = if idx + 1 < branch_node_ids.len() {
+                    1
+                } else {
+                    0
+                };
+
+                children_ids.push(when_branch_to_markup(
+                    env,
+                    env.pool.get(*branch_node_id),
+                    ast_node_id,
+                    mark_node_pool,
+                    mark_id_ast_id_map,
+                    interns,
+                    indent_level + 1,
+                    newlines_at_end,
+                )?);
+            }
+
+            add_node(
+                make_nested_mn(children_ids, 0),
+                ast_node_id,
+                mark_node_pool,
+                mark_id_ast_id_map,
+            )
+        }
         Expr2::RuntimeError() => new_markup_node(
             "RunTimeError".to_string(),
             ast_node_id,
@@ -470,6 +571,91 @@ pub fn expr2_to_markup<'a>(
     Ok(mark_node_id)
 }
 
+// Every branch is a Nested MarkupNode: indent, patterns separated by pipes, optional guard, arrow and body.
+#[allow(clippy::too_many_arguments)]
+fn when_branch_to_markup<'a>(
+    env: &Env<'a>,
+    when_branch: &WhenBranch,
+    when_ast_node_id: ASTNodeId,
+    mark_node_pool: &mut SlowPool,
+    mark_id_ast_id_map: &mut MarkIdAstIdMap,
+    interns: &Interns,
+    indent_level: usize,
+    newlines_at_end: usize,
+) -> ASTResult<MarkNodeId> {
+    let indent_mn = MarkupNode::Indent {
+        indent_level,
+        parent_id_opt: None,
+    };
+
+    let mut children_ids = vec![add_node(
+        indent_mn,
+        when_ast_node_id,
+        mark_node_pool,
+        mark_id_ast_id_map,
+    )];
+
+    for (idx, pattern2) in when_branch.patterns.iter(env.pool).enumerate() {
+        if idx > 0 {
+            children_ids.push(add_node(
+                new_pipe_mn(),
+                when_ast_node_id,
+                mark_node_pool,
+                mark_id_ast_id_map,
+            ));
+        }
+
+        children_ids.push(pattern2_to_markup(
+            env,
+            pattern2,
+            when_ast_node_id,
+            mark_node_pool,
+            mark_id_ast_id_map,
+        )?);
+    }
+
+    if let Some(guard_id) = when_branch.guard {
+        children_ids.push(add_node(
+            guard_if_mn(),
+            when_ast_node_id,
+            mark_node_pool,
+            mark_id_ast_id_map,
+        ));
+        children_ids.push(expr2_to_markup(
+            env,
+            env.pool.get(guard_id),
+            guard_id,
+            mark_node_pool,
+            mark_id_ast_id_map,
+            interns,
+            0,
+        )?);
+    }
+
+    children_ids.push(add_node(
+        new_arrow_mn(0),
+        when_ast_node_id,
+        mark_node_pool,
+        mark_id_ast_id_map,
+    ));
+    children_ids.push(expr2_to_markup(
+        env,
+        env.pool.get(when_branch.body),
+        when_branch.body,
+        mark_node_pool,
+        mark_id_ast_id_map,
+        interns,
+        0,
+    )?);
+
+    Ok(add_node(
+        make_nested_mn(children_ids, newlines_at_end),
+        when_ast_node_id,
+        mark_node_pool,
+        mark_id_ast_id_map,
+    ))
+}
+
 fn with_indent(indent_level: usize, some_str: &str) -> String {
     let full_indent = std::iter::repeat(" ").take(indent_level * 4);
     let mut full_string: String = full_indent.collect();
@@ -0,0 +1,114 @@
+use crate::{
+    markup::{mark_id_ast_id_map::MarkIdAstIdMap, nodes::new_markup_node},
+    slow_pool::{MarkNodeId, SlowPool},
+    syntax_highlight::HighlightStyle,
+};
+
+use roc_ast::{
+    ast_error::{ASTResult, UnexpectedPattern2VariantSnafu},
+    lang::{
+        core::{
+            ast::ASTNodeId,
+            expr::expr2::{FloatVal, IntVal},
+            pattern::Pattern2,
+        },
+        env::Env,
+    },
+};
+
+// A pattern is a single Text MarkupNode, the ast_node_id is the id of the expression the pattern is part of,
+// e.g. the when or the closure.
+pub fn pattern2_to_markup<'a>(
+    env: &Env<'a>,
+    pattern2: &Pattern2,
+    ast_node_id: ASTNodeId,
+    mark_node_pool: &mut SlowPool,
+    mark_id_ast_id_map: &mut MarkIdAstIdMap,
+) -> ASTResult<MarkNodeId> {
+    let highlight_style = match pattern2 {
+        Pattern2::NumLiteral(..) | Pattern2::IntLiteral(_) | Pattern2::FloatLiteral(_) => {
+            HighlightStyle::Number
+        }
+        Pattern2::StrLiteral(_) | Pattern2::CharacterLiteral(_) => HighlightStyle::String,
+        Pattern2::Tag { .. } => HighlightStyle::Type,
+        _ => HighlightStyle::Value,
+    };
+
+    Ok(new_markup_node(
+        pattern2_to_string(env, pattern2)?,
+        ast_node_id,
+        highlight_style,
+        mark_node_pool,
+        mark_id_ast_id_map,
+        0,
+    ))
+}
+
+pub fn pattern2_to_string<'a>(env: &Env<'a>, pattern2: &Pattern2) -> ASTResult<String> {
+    let pattern_str = match pattern2 {
+        Pattern2::Identifier(symbol) => env
+            .ident_ids
+            .get_name_str_res(symbol.ident_id())?
+            .to_owned(),
+        Pattern2::Shadowed { shadowed_ident } => shadowed_ident.as_str(env.pool).to_owned(),
+        Pattern2::NumLiteral(_, number) => number.to_string(),
+        Pattern2::IntLiteral(int_val) => int_val_to_string(int_val),
+        Pattern2::FloatLiteral(FloatVal::F64(number)) => number.to_string(),
+        Pattern2::FloatLiteral(FloatVal::F32(number)) => number.to_string(),
+        Pattern2::StrLiteral(pool_str) => format!("\"{}\"", pool_str.as_str(env.pool)),
+        Pattern2::CharacterLiteral(ch) => format!("'{}'", ch),
+        Pattern2::Underscore => "_".to_owned(),
+        Pattern2::Tag {
+            tag_name,
+            arguments,
+            ..
+        } => {
+            let mut tag_str = tag_name.as_str(env.pool).to_owned();
+
+            for (_, arg_id) in arguments.iter(env.pool) {
+                let arg_pattern2 = env.pool.get(*arg_id);
+                let arg_str = pattern2_to_string(env, arg_pattern2)?;
+
+                match arg_pattern2 {
+                    Pattern2::Tag { arguments, .. } if !arguments.is_empty() => {
+                        tag_str.push_str(&format!(" ({})", arg_str))
+                    }
+                    _ => tag_str.push_str(&format!(" {}", arg_str)),
+                }
+            }
+
+            tag_str
+        }
+        Pattern2::RecordDestructure { destructs, .. } => {
+            let labels: Vec<&str> = destructs
+                .iter(env.pool)
+                .map(|destruct| destruct.label.as_str(env.pool))
+                .collect();
+
+            format!("{{ {} }}", labels.join(", "))
+        }
+        // the editor only creates patterns that can be displayed
+        other @ (Pattern2::UnsupportedPattern(_) | Pattern2::MalformedPattern(..)) => {
+            UnexpectedPattern2VariantSnafu {
+                required_pattern2: "pattern that can be displayed".to_string(),
+                encountered_pattern2: format!("{:?}", other),
+            }
+            .fail()?
+        }
+    };
+
+    Ok(pattern_str)
+}
+
+fn int_val_to_string(int_val: &IntVal) -> String {
+    match int_val {
+        IntVal::I64(number) => number.to_string(),
+        IntVal::U64(number) => number.to_string(),
+        IntVal::I32(number) => number.to_string(),
+        IntVal::U32(number) => number.to_string(),
+        IntVal::I16(number) => number.to_string(),
+        IntVal::U16(number) => number.to_string(),
+        IntVal::I8(number) => number.to_string(),
+        IntVal::U8(number) => number.to_string(),
+    }
+}
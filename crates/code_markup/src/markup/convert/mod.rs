@@ -2,3 +2,4 @@ pub mod from_ast;
 pub mod from_def2;
 pub mod from_expr2;
 pub mod from_header;
+pub mod from_pattern2;
@@ -274,6 +274,7 @@ pub const DOT: &str = ".";
 pub const STRING_QUOTES: &str = "\"\"";
 pub const EQUALS: &str = " = ";
 pub const ARROW: &str = " -> ";
+pub const PIPE: &str = " | ";
 pub const SINGLE_INDENT: &str = "    "; // 4 spaces
 
 pub fn new_markup_node(
@@ -0,0 +1,713 @@
+//! A report of the list updates that Morphic turned into in-place mutation, for `--report-in-place`.
+//!
+//! Updates happen inside builtins: `List.set` calls `List.replace`, which uses the
+//! `ListReplaceUnsafe` low-level. Morphic specializes those builtins for every call site, so we
+//! follow the calls from each procedure of the program into the builtins, and report the update
+//! mode that was chosen at the end of the chain, at the call in the program. When Morphic had to
+//! keep a copy-when-shared update, we look at the list that was passed in to explain why it may be
+//! shared.
+use morphic_lib::{CalleeSpecVar, FuncName, FuncSpec, FuncSpecSolutions, ModSolutions};
+use morphic_lib::{Result, UpdateMode, UpdateModeVar};
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::ident::ModuleName;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_mono::inc_dec::occurring_variables_expr;
+use roc_mono::ir::{
+    Call, CallType, EntryPoint, Expr, JoinPointId, ListLiteralElement, ModifyRc, OptLevel, Proc,
+    ProcLayout, Stmt,
+};
+use roc_region::all::{LineInfo, Region};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::{func_name_bytes, func_name_bytes_help, spec_program, MOD_APP, SIZE};
+
+/// An update of a list that is reached from a call in the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateSite {
+    /// The procedure of the program that makes the call
+    pub proc: Symbol,
+    /// The symbol that the result of the call is assigned to; it identifies the call
+    pub call: Symbol,
+    /// The builtins that are called on the way to the update, outermost first
+    pub builtins: Vec<Symbol>,
+    pub op: LowLevel,
+    pub mode: UpdateMode,
+    /// Why the list may be shared, when the update cannot happen in place
+    pub reason: Option<SharedReason>,
+}
+
+/// Why the list that is passed to an update may have another reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SharedReason {
+    /// The list is used again after the update
+    UsedLater(Symbol),
+    /// The list is an argument that the procedure only borrows
+    Borrowed(Symbol),
+    /// The list is an argument, and some caller still holds on to it
+    Argument(Symbol),
+    /// The list is a literal of constants, which lives in read-only memory
+    StaticList(Symbol),
+    /// The list was taken out of a value that still refers to it
+    ExtractedFrom { list: Symbol, container: Symbol },
+    /// The list was returned by a function that may return a shared list
+    ReturnedBy { list: Symbol, function: Symbol },
+    /// Morphic could not prove that the list is unique, and we don't know why
+    Unknown(Symbol),
+}
+
+pub struct InPlaceReport {
+    opt_level: OptLevel,
+    sites: Vec<UpdateSite>,
+}
+
+impl InPlaceReport {
+    /// Runs alias analysis, just like code generation does, and collects its decisions.
+    pub fn new<'a>(
+        opt_level: OptLevel,
+        opt_entry_point: Option<EntryPoint<'a>>,
+        procedures: &'a MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) -> Result<Self> {
+        let solutions = spec_program(opt_level, opt_entry_point, procedures.values())?;
+        let mod_solutions = solutions.mod_solutions(MOD_APP)?;
+
+        let procs_by_name: MutMap<[u8; SIZE], &Proc<'a>> = procedures
+            .values()
+            .map(|proc| (func_name_bytes(proc), proc))
+            .collect();
+
+        let mut collector = Collector {
+            mod_solutions,
+            procs_by_name,
+            // without optimizations, Morphic does not even try
+            explain_shared: matches!(opt_level, OptLevel::Optimize | OptLevel::Size),
+            sites: Vec::new(),
+        };
+
+        for proc in procedures.values() {
+            if proc.name.name().is_builtin() {
+                continue;
+            }
+
+            let bytes = func_name_bytes(proc);
+
+            // functions that are never called from the entry point have no specializations
+            let func_solutions = match mod_solutions.func_solutions(FuncName(&bytes)) {
+                Ok(func_solutions) => func_solutions,
+                Err(_) => continue,
+            };
+
+            let facts = ProcFacts::new(proc);
+
+            for spec in func_solutions.specs() {
+                let spec_solutions = func_solutions.spec(spec)?;
+
+                collector.program_stmt(proc, &facts, spec_solutions, &proc.body)?;
+            }
+        }
+
+        Ok(Self {
+            opt_level,
+            sites: collector.sites,
+        })
+    }
+
+    pub fn sites(&self) -> &[UpdateSite] {
+        &self.sites
+    }
+
+    /// Renders the report, pointing at the calls in the source files.
+    pub fn render(
+        &self,
+        interns: &Interns,
+        sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+        def_regions: &MutMap<Symbol, Region>,
+        call_regions: &MutMap<Symbol, Region>,
+    ) -> String {
+        let line_infos: MutMap<ModuleId, LineInfo> = sources
+            .iter()
+            .map(|(module_id, (_, src))| (*module_id, LineInfo::new(src)))
+            .collect();
+
+        // the call, or else the definition of the procedure that makes it
+        let region_of = |site: &UpdateSite| {
+            call_regions
+                .get(&site.call)
+                .or_else(|| def_regions.get(&site.proc))
+                .copied()
+        };
+
+        let location = |site: &UpdateSite| {
+            let module_id = site.proc.module_id();
+            let path = match sources.get(&module_id) {
+                Some((path, _)) => path.display().to_string(),
+                None => return proc_name(interns, site.proc),
+            };
+
+            match (region_of(site), line_infos.get(&module_id)) {
+                (Some(region), Some(line_info)) => {
+                    let position = line_info.convert_pos(region.start());
+
+                    format!("{}:{}:{}", path, position.line + 1, position.column + 1)
+                }
+                _ => path,
+            }
+        };
+
+        let mut sites: Vec<_> = self.sites.iter().collect();
+        sites.sort_by_key(|site| {
+            let path = sources.get(&site.proc.module_id()).map(|(path, _)| path);
+            let start = region_of(site).map(|region| region.start().offset);
+
+            (path, start, proc_name(interns, site.proc))
+        });
+
+        let mut buf = String::new();
+
+        if let OptLevel::Development | OptLevel::Normal = self.opt_level {
+            buf.push_str(
+                "Morphic only looks for in-place updates in optimized builds, so in this build every update copies the list when it is shared. Build with `--optimize` to see its decisions.\n\n",
+            );
+        }
+
+        if sites.is_empty() {
+            buf.push_str("The program does not update any lists.\n");
+
+            return buf;
+        }
+
+        let in_place = sites
+            .iter()
+            .filter(|site| site.mode == UpdateMode::InPlace)
+            .count();
+
+        writeln!(
+            buf,
+            "{} of {} list updates happen in place:\n",
+            in_place,
+            sites.len()
+        )
+        .unwrap();
+
+        for site in sites {
+            let mode = match site.mode {
+                UpdateMode::InPlace => "in place",
+                UpdateMode::Immutable => "copies the list when it is shared",
+            };
+
+            writeln!(
+                buf,
+                "{}, in {}: {}: {}",
+                location(site),
+                proc_name(interns, site.proc),
+                update_chain(interns, site),
+                mode
+            )
+            .unwrap();
+
+            if let Some(reason) = site.reason {
+                writeln!(buf, "    {}", explain(interns, site.proc, reason)).unwrap();
+            }
+        }
+
+        buf
+    }
+}
+
+/// The updates whose mode the LLVM backend uses, and the names of their builtins
+fn update_name(op: LowLevel) -> Option<&'static str> {
+    match op {
+        LowLevel::ListReplaceUnsafe => Some("List.replaceUnsafe"),
+        LowLevel::ListSwap => Some("List.swap"),
+        LowLevel::ListReserve => Some("List.reserve"),
+        _ => None,
+    }
+}
+
+fn proc_name(interns: &Interns, symbol: Symbol) -> String {
+    let ident = match variable_name(interns, symbol) {
+        Some(ident) => ident,
+        None => return "an anonymous function".to_string(),
+    };
+
+    match symbol.module_string(interns).as_str() {
+        ModuleName::APP => format!("`{}`", ident),
+        module_name => format!("`{}.{}`", module_name, ident),
+    }
+}
+
+/// e.g. `List.set > List.replace > List.replaceUnsafe`
+fn update_chain(interns: &Interns, site: &UpdateSite) -> String {
+    let mut names: Vec<_> = site
+        .builtins
+        .iter()
+        .map(|symbol| {
+            format!(
+                "{}.{}",
+                symbol.module_string(interns),
+                symbol.as_str(interns)
+            )
+        })
+        .collect();
+
+    let op_name = update_name(site.op).unwrap_or_default();
+    if names.last().map(String::as_str) != Some(op_name) {
+        names.push(op_name.to_string());
+    }
+
+    names.join(" > ")
+}
+
+/// The name of a variable in the program, if the user gave it one
+fn variable_name(interns: &Interns, symbol: Symbol) -> Option<&str> {
+    let name = interns
+        .all_ident_ids
+        .get(&symbol.module_id())?
+        .get_name(symbol.ident_id())?;
+
+    // the compiler names its temporaries with numbers
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn explain(interns: &Interns, proc: Symbol, reason: SharedReason) -> String {
+    use SharedReason::*;
+
+    let list = |symbol| match variable_name(interns, symbol) {
+        Some(name) => format!("`{}`", name),
+        None => "The list".to_string(),
+    };
+
+    let value = |symbol| match variable_name(interns, symbol) {
+        Some(name) => format!("`{}`", name),
+        None => "a value".to_string(),
+    };
+
+    match reason {
+        UsedLater(symbol) => format!(
+            "{} is used again after this call, so the list has another reference here.",
+            list(symbol)
+        ),
+        Borrowed(symbol) => format!(
+            "{} is an argument that {} only borrows, so the list of the caller must stay intact.",
+            list(symbol),
+            proc_name(interns, proc)
+        ),
+        Argument(symbol) => format!(
+            "{} is an argument of {}, and a caller still holds another reference to the list it passes in.",
+            list(symbol),
+            proc_name(interns, proc)
+        ),
+        StaticList(symbol) => format!(
+            "{} is a list literal of constants, which is stored in read-only memory, so it is never unique.",
+            list(symbol)
+        ),
+        ExtractedFrom { list: symbol, container } => format!(
+            "{} was taken out of {}, which still refers to the same list.",
+            list(symbol),
+            value(container)
+        ),
+        ReturnedBy { list: symbol, function } => format!(
+            "{} is the result of {}, which may return a list that is referenced elsewhere.",
+            list(symbol),
+            proc_name(interns, function)
+        ),
+        Unknown(symbol) => format!(
+            "Morphic could not prove that {} is the only reference to the list.",
+            value(symbol)
+        ),
+    }
+}
+
+/// Where the symbols of a procedure come from, to explain why a list may be shared
+struct ProcFacts<'r, 'a> {
+    definitions: MutMap<Symbol, &'r Expr<'a>>,
+    join_parameters: MutMap<Symbol, (JoinPointId, usize)>,
+    jumps: MutMap<JoinPointId, Vec<&'a [Symbol]>>,
+    incremented: MutSet<Symbol>,
+    arguments: MutSet<Symbol>,
+}
+
+impl<'r, 'a> ProcFacts<'r, 'a> {
+    fn new(proc: &'r Proc<'a>) -> Self {
+        let mut facts = Self {
+            definitions: MutMap::default(),
+            join_parameters: MutMap::default(),
+            jumps: MutMap::default(),
+            incremented: MutSet::default(),
+            arguments: proc.args.iter().map(|(_, symbol)| *symbol).collect(),
+        };
+
+        let mut stack = vec![&proc.body];
+
+        while let Some(stmt) = stack.pop() {
+            match stmt {
+                Stmt::Let(symbol, expr, _, cont) => {
+                    facts.definitions.insert(*symbol, expr);
+                    stack.push(cont);
+                }
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                    stack.push(default_branch.1);
+                }
+                Stmt::Refcounting(modify, cont) => {
+                    if let ModifyRc::Inc(symbol, _) = modify {
+                        facts.incremented.insert(*symbol);
+                    }
+
+                    stack.push(cont);
+                }
+                Stmt::Expect { remainder, .. } => stack.push(remainder),
+                Stmt::Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                } => {
+                    for (index, param) in parameters.iter().enumerate() {
+                        facts.join_parameters.insert(param.symbol, (*id, index));
+                    }
+
+                    stack.push(body);
+                    stack.push(remainder);
+                }
+                Stmt::Jump(id, arguments) => {
+                    facts.jumps.entry(*id).or_default().push(arguments);
+                }
+                Stmt::Ret(_) | Stmt::RuntimeError(_) => {}
+            }
+        }
+
+        facts
+    }
+
+    /// Why the list that is passed to an update may be shared; `rest` is what runs after the call
+    fn explain(&self, list: Symbol, rest: &Stmt<'a>) -> SharedReason {
+        if is_used(rest, list) {
+            SharedReason::UsedLater(list)
+        } else {
+            self.explain_definition(list, &mut MutSet::default())
+        }
+    }
+
+    fn explain_definition(&self, list: Symbol, visited: &mut MutSet<Symbol>) -> SharedReason {
+        use SharedReason::*;
+
+        if !visited.insert(list) {
+            return Unknown(list);
+        }
+
+        if self.arguments.contains(&list) {
+            // we only need to increment an argument we own if it is used again, and then we
+            // would have reported that instead
+            return if self.incremented.contains(&list) {
+                Borrowed(list)
+            } else {
+                Argument(list)
+            };
+        }
+
+        if let Some((id, index)) = self.join_parameters.get(&list) {
+            // a loop variable: look at the values it takes
+            let jumps = self.jumps.get(id).map(Vec::as_slice).unwrap_or_default();
+
+            for arguments in jumps {
+                let argument = arguments[*index];
+
+                // the list that the loop itself updates is passed to the next iteration
+                if self.is_update_of(argument, list) {
+                    continue;
+                }
+
+                match self.explain_definition(argument, visited) {
+                    Unknown(_) => {}
+                    reason => return reason,
+                }
+            }
+
+            return Unknown(list);
+        }
+
+        match self.definitions.get(&list) {
+            Some(Expr::Array { elems, .. })
+                if elems
+                    .iter()
+                    .all(|elem| matches!(elem, ListLiteralElement::Literal(_))) =>
+            {
+                StaticList(list)
+            }
+            Some(Expr::StructAtIndex { structure, .. })
+            | Some(Expr::UnionAtIndex { structure, .. }) => ExtractedFrom {
+                list,
+                container: *structure,
+            },
+            Some(Expr::ExprUnbox { symbol }) => ExtractedFrom {
+                list,
+                container: *symbol,
+            },
+            Some(Expr::Call(Call {
+                call_type: CallType::ByName { name, .. },
+                ..
+            })) => ReturnedBy {
+                list,
+                function: name.name(),
+            },
+            _ => Unknown(list),
+        }
+    }
+}
+
+impl<'r, 'a> ProcFacts<'r, 'a> {
+    /// Whether the symbol is the result of a call that takes the list as its first argument
+    fn is_update_of(&self, symbol: Symbol, list: Symbol) -> bool {
+        matches!(
+            self.definitions.get(&symbol),
+            Some(Expr::Call(Call { arguments, .. })) if arguments.first() == Some(&list)
+        )
+    }
+}
+
+/// Whether the statement uses the symbol, other than to change its refcount
+fn is_used(stmt: &Stmt<'_>, symbol: Symbol) -> bool {
+    let mut stack = vec![stmt];
+    let mut occurring = MutSet::default();
+
+    while let Some(stmt) = stack.pop() {
+        match stmt {
+            Stmt::Let(_, expr, _, cont) => {
+                occurring_variables_expr(expr, &mut occurring);
+                stack.push(cont);
+            }
+            Stmt::Switch {
+                cond_symbol,
+                branches,
+                default_branch,
+                ..
+            } => {
+                occurring.insert(*cond_symbol);
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Ret(returned) => {
+                occurring.insert(*returned);
+            }
+            Stmt::Refcounting(_, cont) => stack.push(cont),
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                occurring.insert(*condition);
+                occurring.extend(lookups.iter().copied());
+                stack.push(remainder);
+            }
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Jump(_, arguments) => occurring.extend(arguments.iter().copied()),
+            Stmt::RuntimeError(_) => {}
+        }
+
+        if occurring.contains(&symbol) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// An update inside a builtin: the builtins that lead to it, its low-level, and its mode
+type BuiltinUpdate = (Vec<Symbol>, LowLevel, UpdateMode);
+
+struct Collector<'r, 'a> {
+    mod_solutions: &'r ModSolutions,
+    procs_by_name: MutMap<[u8; SIZE], &'r Proc<'a>>,
+    explain_shared: bool,
+    sites: Vec<UpdateSite>,
+}
+
+impl<'r, 'a> Collector<'r, 'a> {
+    fn add_site(&mut self, site: UpdateSite) {
+        // a procedure of the program can have several specializations with the same decisions
+        if !self.sites.contains(&site) {
+            self.sites.push(site);
+        }
+    }
+
+    fn program_stmt(
+        &mut self,
+        proc: &Proc<'a>,
+        facts: &ProcFacts<'_, 'a>,
+        spec_solutions: &FuncSpecSolutions,
+        stmt: &Stmt<'a>,
+    ) -> Result<()> {
+        let mut stack = vec![stmt];
+
+        while let Some(stmt) = stack.pop() {
+            match stmt {
+                Stmt::Let(assigned, Expr::Call(call), _, cont) => {
+                    let updates = self.call_updates(spec_solutions, call, &mut Vec::new())?;
+
+                    for (builtins, op, mode) in updates {
+                        let reason = match (mode, call.arguments.first()) {
+                            (UpdateMode::Immutable, Some(list)) if self.explain_shared => {
+                                Some(facts.explain(*list, cont))
+                            }
+                            _ => None,
+                        };
+
+                        self.add_site(UpdateSite {
+                            proc: proc.name.name(),
+                            call: *assigned,
+                            builtins,
+                            op,
+                            mode,
+                            reason,
+                        });
+                    }
+
+                    stack.push(cont);
+                }
+                Stmt::Let(_, _, _, cont) => stack.push(cont),
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                    stack.push(default_branch.1);
+                }
+                Stmt::Refcounting(_, cont) => stack.push(cont),
+                Stmt::Expect { remainder, .. } => stack.push(remainder),
+                Stmt::Join {
+                    body, remainder, ..
+                } => {
+                    stack.push(body);
+                    stack.push(remainder);
+                }
+                Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::RuntimeError(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The updates that a call leads to: the call itself if it is an update, or the updates in
+    /// the builtin that it calls. Calls to procedures of the program are reported on their own.
+    fn call_updates(
+        &self,
+        spec_solutions: &FuncSpecSolutions,
+        call: &Call<'a>,
+        visiting: &mut Vec<(Symbol, FuncSpec)>,
+    ) -> Result<Vec<BuiltinUpdate>> {
+        match &call.call_type {
+            CallType::LowLevel { op, update_mode } => {
+                if update_name(*op).is_none() {
+                    return Ok(Vec::new());
+                }
+
+                let bytes = update_mode.to_bytes();
+                let mode = spec_solutions.update_mode(UpdateModeVar(&bytes))?;
+
+                Ok(vec![(Vec::new(), *op, mode)])
+            }
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                specialization_id,
+            } if name.name().is_builtin() => {
+                let bytes = func_name_bytes_help(
+                    name.name(),
+                    arg_layouts.iter().copied(),
+                    name.captures_niche(),
+                    ret_layout,
+                );
+
+                let callee = match self.procs_by_name.get(&bytes) {
+                    Some(callee) => *callee,
+                    None => return Ok(Vec::new()),
+                };
+
+                let spec_bytes = specialization_id.to_bytes();
+                let callee_spec = spec_solutions.callee_spec(CalleeSpecVar(&spec_bytes))?;
+
+                // builtins can be recursive
+                if visiting.contains(&(name.name(), callee_spec)) {
+                    return Ok(Vec::new());
+                }
+
+                let callee_solutions = self
+                    .mod_solutions
+                    .func_solutions(FuncName(&bytes))?
+                    .spec(&callee_spec)?;
+
+                visiting.push((name.name(), callee_spec));
+                let updates = self.builtin_updates(callee, callee_solutions, visiting)?;
+                visiting.pop();
+
+                Ok(updates
+                    .into_iter()
+                    .map(|(mut builtins, op, mode)| {
+                        builtins.insert(0, name.name());
+                        (builtins, op, mode)
+                    })
+                    .collect())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn builtin_updates(
+        &self,
+        builtin: &Proc<'a>,
+        spec_solutions: &FuncSpecSolutions,
+        visiting: &mut Vec<(Symbol, FuncSpec)>,
+    ) -> Result<Vec<BuiltinUpdate>> {
+        let mut updates = Vec::new();
+        let mut stack = vec![&builtin.body];
+
+        while let Some(stmt) = stack.pop() {
+            match stmt {
+                Stmt::Let(_, expr, _, cont) => {
+                    if let Expr::Call(call) = expr {
+                        updates.extend(self.call_updates(spec_solutions, call, visiting)?);
+                    }
+
+                    stack.push(cont);
+                }
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                    stack.push(default_branch.1);
+                }
+                Stmt::Refcounting(_, cont) => stack.push(cont),
+                Stmt::Expect { remainder, .. } => stack.push(remainder),
+                Stmt::Join {
+                    body, remainder, ..
+                } => {
+                    stack.push(body);
+                    stack.push(remainder);
+                }
+                Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::RuntimeError(_) => {}
+            }
+        }
+
+        Ok(updates)
+    }
+}
@@ -14,6 +14,10 @@ use roc_mono::ir::{
 };
 use roc_mono::layout::{Builtin, CapturesNiche, Layout, RawFunctionLayout, UnionLayout};
 
+mod in_place;
+
+pub use in_place::{InPlaceReport, SharedReason, UpdateSite};
+
 // just using one module for now
 pub const MOD_APP: ModName = ModName(b"UserApp");
 
@@ -976,6 +980,24 @@ fn lowlevel_spec(
             // just dream up a unit value
             builder.add_make_tuple(block, &[])
         }
+        RefCountIsUnique => {
+            // only inspects the refcount, so this does not touch the heap cell
+            builder.add_make_tuple(block, &[])
+        }
+        PtrStore => {
+            // the stored value escapes into memory that we don't model
+            let value_id = env.symbols[&arguments[1]];
+
+            builder.add_unknown_with(block, &[value_id], type_id)
+        }
+        PtrLoad => {
+            // the loaded value was built from values stored in earlier iterations of a loop, so
+            // conservatively assume it can alias anything in scope
+            let mut value_ids: Vec<_> = env.symbols.values().copied().collect();
+            value_ids.sort_unstable();
+
+            builder.add_unknown_with(block, &value_ids, type_id)
+        }
         NumLte | NumLt | NumGt | NumGte | NumCompare => {
             // just dream up a unit value
             builder.add_make_tuple(block, &[])
@@ -1354,6 +1376,16 @@ fn expr_spec<'a>(
 
             builder.add_make_tuple(block, &[])
         }
+        UnionFieldPtrAtIndex { .. } | Alloca { .. } => {
+            // addresses are just numbers
+            builder.add_make_tuple(block, &[])
+        }
+        NullPointer => {
+            // a placeholder that is overwritten before it can be observed
+            let type_id = layout_spec(builder, layout, &WhenRecursive::Unreachable)?;
+
+            builder.add_unknown_with(block, &[], type_id)
+        }
     }
 }
 
@@ -0,0 +1,112 @@
+#[macro_use]
+extern crate indoc;
+
+use bumpalo::Bump;
+use morphic_lib::UpdateMode;
+use roc_alias_analysis::{InPlaceReport, SharedReason, UpdateSite};
+use roc_load::{EntryPoint, ExecutionMode, LoadConfig, MonomorphizedModule, Threading};
+use roc_module::low_level::LowLevel;
+use roc_mono::ir::OptLevel;
+use std::path::PathBuf;
+
+/// Loads the program, and returns the update sites in `main` that Morphic decided on
+fn update_sites(src: &str) -> Vec<(UpdateSite, Option<String>)> {
+    let arena = &Bump::new();
+
+    let load_config = LoadConfig {
+        target_info: roc_target::TargetInfo::default_x86_64(),
+        render: roc_reporting::report::RenderTarget::Generic,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Executable,
+        inline_and_fold: false,
+        record_call_regions: true,
+        collect_package_modules: false,
+    };
+
+    let loaded = roc_load::load_and_monomorphize_from_str(
+        arena,
+        PathBuf::from("Test.roc"),
+        arena.alloc_str(src),
+        PathBuf::from("fake/test/path"),
+        Default::default(),
+        load_config,
+    );
+
+    let loaded: MonomorphizedModule = match loaded {
+        Ok(loaded) => loaded,
+        Err(roc_load::LoadingProblem::FormattedReport(report)) => panic!("{}", report),
+        Err(e) => panic!("{:?}", e),
+    };
+
+    let opt_entry_point = match loaded.entry_point {
+        EntryPoint::Executable { symbol, layout, .. } => {
+            Some(roc_mono::ir::EntryPoint { symbol, layout })
+        }
+        EntryPoint::Test => None,
+    };
+
+    let report = InPlaceReport::new(OptLevel::Optimize, opt_entry_point, &loaded.procedures)
+        .unwrap_or_else(|e| panic!("Error in alias analysis: {}", e));
+
+    report
+        .sites()
+        .iter()
+        .map(|site| {
+            // every update is reported at a call that the program makes
+            assert!(loaded.call_regions.contains_key(&site.call));
+
+            let shared_list = match site.reason {
+                Some(SharedReason::UsedLater(list)) => {
+                    Some(list.as_str(&loaded.interns).to_string())
+                }
+                _ => None,
+            };
+
+            (site.clone(), shared_list)
+        })
+        .collect()
+}
+
+#[test]
+fn unique_list_is_updated_in_place() {
+    let sites = update_sites(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            list = List.repeat 0 3
+
+            List.set list 0 4
+        "#
+    ));
+
+    assert_eq!(sites.len(), 1);
+
+    let (site, shared_list) = &sites[0];
+    assert_eq!(site.op, LowLevel::ListReplaceUnsafe);
+    assert_eq!(site.mode, UpdateMode::InPlace);
+    assert_eq!(site.reason, None);
+    assert_eq!(shared_list, &None);
+}
+
+#[test]
+fn shared_list_is_copied() {
+    let sites = update_sites(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            list = List.repeat 0 3
+            updated = List.set list 0 4
+
+            List.concat updated list
+        "#
+    ));
+
+    assert_eq!(sites.len(), 1);
+
+    let (site, shared_list) = &sites[0];
+    assert_eq!(site.op, LowLevel::ListReplaceUnsafe);
+    assert_eq!(site.mode, UpdateMode::Immutable);
+    assert_eq!(shared_list.as_deref(), Some("list"));
+}
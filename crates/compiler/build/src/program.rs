@@ -1,10 +1,13 @@
+use roc_alias_analysis::InPlaceReport;
 pub use roc_gen_llvm::llvm::build::FunctionIterator;
 use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode};
+use roc_gen_llvm::llvm::debug_info::{add_debug_info_flags, DebugSources};
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_load::{EntryPoint, LoadedModule, MonomorphizedModule};
 use roc_module::symbol::{Interns, ModuleId};
 use roc_mono::ir::OptLevel;
 use roc_region::all::LineInfo;
+use roc_reporting::lint::WarningLevels;
 use roc_solve_problem::TypeError;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
@@ -19,23 +22,85 @@ pub struct CodeGenTiming {
     pub emit_o_file: Duration,
 }
 
-pub fn report_problems_monomorphized(loaded: &mut MonomorphizedModule) -> Problems {
+/// Intermediate representations of the program that `roc build --emit` writes next to the output
+#[derive(Debug, Clone, Default)]
+pub struct EmitOptions {
+    /// The pretty-printed mono IR, in `<output>.mono`
+    pub mono: bool,
+    /// The optimized LLVM module, in `<output>.ll`
+    pub llvm_ir: bool,
+    /// Native assembly, in `<output>.s`
+    pub asm: bool,
+    /// The WebAssembly text format of a wasm dev backend build, in `<output>.wat`
+    pub wasm_text: bool,
+    /// The emitted files are named after this path
+    pub output_path: PathBuf,
+}
+
+impl EmitOptions {
+    fn path(&self, extension: &str) -> PathBuf {
+        self.output_path.with_extension(extension)
+    }
+}
+
+fn write_emitted_file(path: &Path, contents: impl AsRef<[u8]>) {
+    std::fs::write(path, contents).unwrap_or_else(|e| {
+        panic!(
+            "I wasn't able to write to the file {}\n{}",
+            path.display(),
+            e
+        )
+    });
+}
+
+/// Print which list updates alias analysis lets happen in place, for `--report-in-place`.
+fn report_in_place_updates(loaded: &MonomorphizedModule, opt_level: OptLevel) {
+    let opt_entry_point = match loaded.entry_point {
+        EntryPoint::Executable { symbol, layout, .. } => {
+            Some(roc_mono::ir::EntryPoint { symbol, layout })
+        }
+        EntryPoint::Test => None,
+    };
+
+    let report = InPlaceReport::new(opt_level, opt_entry_point, &loaded.procedures)
+        .unwrap_or_else(|e| panic!("Error in alias analysis: {}", e));
+
+    eprintln!(
+        "\n{}",
+        report.render(
+            &loaded.interns,
+            &loaded.sources,
+            &loaded.def_regions,
+            &loaded.call_regions,
+        )
+    );
+}
+
+pub fn report_problems_monomorphized(
+    loaded: &mut MonomorphizedModule,
+    warning_levels: &WarningLevels,
+) -> Problems {
     report_problems_help(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        warning_levels,
     )
 }
 
-pub fn report_problems_typechecked(loaded: &mut LoadedModule) -> Problems {
+pub fn report_problems_typechecked(
+    loaded: &mut LoadedModule,
+    warning_levels: &WarningLevels,
+) -> Problems {
     report_problems_help(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        warning_levels,
     )
 }
 
@@ -50,8 +115,10 @@ impl Problems {
         // 0 means no problems, 1 means errors, 2 means warnings
         if self.errors > 0 {
             1
+        } else if self.warnings > 0 {
+            2
         } else {
-            self.warnings.min(1) as i32
+            0
         }
     }
 }
@@ -62,7 +129,9 @@ fn report_problems_help(
     interns: &Interns,
     can_problems: &mut MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: &mut MutMap<ModuleId, Vec<TypeError>>,
+    warning_levels: &WarningLevels,
 ) -> Problems {
+    use roc_reporting::lint::{level_problem, Lint, ModuleLevels};
     use roc_reporting::report::{
         can_problem, type_problem, Report, RocDocAllocator, Severity::*, DEFAULT_PALETTE,
     };
@@ -83,42 +152,49 @@ fn report_problems_help(
         // Report parsing and canonicalization problems
         let alloc = RocDocAllocator::new(&src_lines, *home, interns);
 
+        let module_levels = ModuleLevels::from_source(src);
+        let mut reports = Vec::new();
+
+        for problem in module_levels.problems.iter().cloned() {
+            let report = level_problem(&alloc, &lines, module_path.clone(), problem);
+
+            reports.push((report, None));
+        }
+
         let problems = can_problems.remove(home).unwrap_or_default();
 
         for problem in problems.into_iter() {
+            let lint = Lint::of_can_problem(&problem);
             let report = can_problem(&alloc, &lines, module_path.clone(), problem);
-            let severity = report.severity;
-            let mut buf = String::new();
-
-            report.render_color_terminal(&mut buf, &alloc, &palette);
 
-            match severity {
-                Warning => {
-                    warnings.push(buf);
-                }
-                RuntimeError => {
-                    errors.push(buf);
-                }
-            }
+            reports.push((report, lint));
         }
 
         let problems = type_problems.remove(home).unwrap_or_default();
 
         for problem in problems {
+            let lint = Lint::of_type_problem(&problem);
+
             if let Some(report) = type_problem(&alloc, &lines, module_path.clone(), problem) {
-                let severity = report.severity;
-                let mut buf = String::new();
-
-                report.render_color_terminal(&mut buf, &alloc, &palette);
-
-                match severity {
-                    Warning => {
-                        warnings.push(buf);
-                    }
-                    RuntimeError => {
-                        errors.push(buf);
-                    }
+                reports.push((report, lint));
+            }
+        }
+
+        // The levels decide whether a problem is reported, and whether as a warning or an error
+        for (report, lint) in reports {
+            let severity = warning_levels.severity(&module_levels, lint, report.severity);
+            let mut buf = String::new();
+
+            match severity {
+                Some(Warning) => {
+                    report.render_color_terminal(&mut buf, &alloc, &palette);
+                    warnings.push(buf);
+                }
+                Some(RuntimeError) => {
+                    report.render_color_terminal(&mut buf, &alloc, &palette);
+                    errors.push(buf);
                 }
+                None => {}
             }
         }
     }
@@ -165,9 +241,22 @@ pub fn gen_from_mono_module(
     app_o_file: &Path,
     opt_level: OptLevel,
     emit_debug_info: bool,
+    debug_refcounts: bool,
+    profile_allocations: bool,
+    report_in_place: bool,
+    emit: &EmitOptions,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> CodeGenTiming {
+    if emit.mono {
+        let mono = roc_mono::ir::procs_to_pretty(loaded.procedures.values(), &loaded.interns, 200);
+        write_emitted_file(&emit.path("mono"), mono);
+    }
+
+    if report_in_place {
+        report_in_place_updates(&loaded, opt_level);
+    }
+
     match opt_level {
         OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => gen_from_mono_module_llvm(
             arena,
@@ -177,12 +266,17 @@ pub fn gen_from_mono_module(
             app_o_file,
             opt_level,
             emit_debug_info,
+            debug_refcounts,
+            profile_allocations,
+            emit,
         ),
         OptLevel::Development => gen_from_mono_module_dev(
             arena,
             loaded,
             target,
             app_o_file,
+            emit_debug_info,
+            emit,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
         ),
@@ -192,6 +286,7 @@ pub fn gen_from_mono_module(
 // TODO how should imported modules factor into this? What if those use builtins too?
 // TODO this should probably use more helper functions
 // TODO make this polymorphic in the llvm functions so it can be reused for another backend.
+#[allow(clippy::too_many_arguments)]
 pub fn gen_from_mono_module_llvm(
     arena: &bumpalo::Bump,
     loaded: MonomorphizedModule,
@@ -200,6 +295,9 @@ pub fn gen_from_mono_module_llvm(
     app_o_file: &Path,
     opt_level: OptLevel,
     emit_debug_info: bool,
+    debug_refcounts: bool,
+    profile_allocations: bool,
+    emit: &EmitOptions,
 ) -> CodeGenTiming {
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
@@ -253,6 +351,17 @@ pub fn gen_from_mono_module_llvm(
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
     let (mpm, _fpm) = roc_gen_llvm::llvm::build::construct_optimization_passes(module, opt_level);
 
+    let debug_sources = if emit_debug_info {
+        Some(DebugSources::new(
+            &dibuilder,
+            &loaded.sources,
+            loaded.def_regions,
+            loaded.call_regions,
+        ))
+    } else {
+        None
+    };
+
     // Compile and add all the Procs before adding main
     let env = roc_gen_llvm::llvm::build::Env {
         arena,
@@ -265,6 +374,9 @@ pub fn gen_from_mono_module_llvm(
         target_info,
         mode: LlvmBackendMode::Binary,
         exposed_to_host: loaded.exposed_to_host.values.keys().copied().collect(),
+        debug_sources: debug_sources.as_ref(),
+        debug_refcounts,
+        profile_allocations,
     };
 
     // does not add any externs for this mode (we have a host) but cleans up some functions around
@@ -288,8 +400,12 @@ pub fn gen_from_mono_module_llvm(
 
     env.dibuilder.finalize();
 
-    // we don't use the debug info, and it causes weird errors.
-    module.strip_debug_info();
+    if emit_debug_info {
+        add_debug_info_flags(module);
+    } else {
+        // we don't use the debug info, and it causes weird errors.
+        module.strip_debug_info();
+    }
 
     // Uncomment this to see the module's optimized LLVM instruction output:
     // env.module.print_to_stderr();
@@ -311,101 +427,47 @@ pub fn gen_from_mono_module_llvm(
     // Uncomment this to see the module's optimized LLVM instruction output:
     // env.module.print_to_stderr();
 
+    if emit.llvm_ir {
+        let llvm_ir_path = emit.path("ll");
+        module.print_to_file(&llvm_ir_path).unwrap_or_else(|e| {
+            panic!(
+                "I wasn't able to write to the file {}\n{}",
+                llvm_ir_path.display(),
+                e
+            )
+        });
+    }
+
     let code_gen = code_gen_start.elapsed();
     let emit_o_file_start = Instant::now();
 
-    // annotate the LLVM IR output with debug info
-    // so errors are reported with the line number of the LLVM source
-    if emit_debug_info {
-        module.strip_debug_info();
-
-        let mut app_ll_dbg_file = PathBuf::from(roc_file_path);
-        app_ll_dbg_file.set_extension("dbg.ll");
-
-        let mut app_bc_file = PathBuf::from(roc_file_path);
-        app_bc_file.set_extension("bc");
-
-        use std::process::Command;
-
-        // write the ll code to a file, so we can modify it
-        module.print_to_file(&app_ll_file).unwrap();
-
-        // run the debugir https://github.com/vaivaswatha/debugir tool
-        match Command::new("debugir")
-            .args(&["-instnamer", app_ll_file.to_str().unwrap()])
-            .output()
-        {
-            Ok(_) => {}
-            Err(error) => {
-                use std::io::ErrorKind;
-                match error.kind() {
-                    ErrorKind::NotFound => panic!(
-                        r"I could not find the `debugir` tool on the PATH, install it from https://github.com/vaivaswatha/debugir"
-                    ),
-                    _ => panic!("{:?}", error),
-                }
-            }
-        }
+    // Emit the .o file
+    use target_lexicon::Architecture;
+    match target.architecture {
+        Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
+            let reloc = RelocMode::PIC;
+            let target_machine =
+                target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
 
-        use target_lexicon::Architecture;
-        match target.architecture {
-            Architecture::X86_64
-            | Architecture::X86_32(_)
-            | Architecture::Aarch64(_)
-            | Architecture::Wasm32 => {
-                let ll_to_bc = Command::new("llvm-as")
-                    .args(&[
-                        app_ll_dbg_file.to_str().unwrap(),
-                        "-o",
-                        app_bc_file.to_str().unwrap(),
-                    ])
-                    .output()
-                    .unwrap();
-
-                assert!(ll_to_bc.stderr.is_empty(), "{:#?}", ll_to_bc);
-
-                let llc_args = &[
-                    "-relocation-model=pic",
-                    "-filetype=obj",
-                    app_bc_file.to_str().unwrap(),
-                    "-o",
-                    app_o_file.to_str().unwrap(),
-                ];
-
-                // write the .o file. Note that this builds the .o for the local machine,
-                // and ignores the `target_machine` entirely.
-                //
-                // different systems name this executable differently, so we shotgun for
-                // the most common ones and then give up.
-                let bc_to_object = Command::new("llc").args(llc_args).output().unwrap();
-
-                assert!(bc_to_object.stderr.is_empty(), "{:#?}", bc_to_object);
-            }
-            _ => unreachable!(),
-        }
-    } else {
-        // Emit the .o file
-        use target_lexicon::Architecture;
-        match target.architecture {
-            Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
-                let reloc = RelocMode::PIC;
-                let target_machine =
-                    target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
+            target_machine
+                .write_to_file(env.module, FileType::Object, app_o_file)
+                .expect("Writing .o file failed");
 
+            if emit.asm {
                 target_machine
-                    .write_to_file(env.module, FileType::Object, app_o_file)
-                    .expect("Writing .o file failed");
-            }
-            Architecture::Wasm32 => {
-                // Useful for debugging
-                // module.print_to_file(app_ll_file);
-                module.write_bitcode_to_path(app_o_file);
+                    .write_to_file(env.module, FileType::Assembly, &emit.path("s"))
+                    .expect("Writing the assembly file failed");
             }
-            _ => panic!(
-                "TODO gracefully handle unsupported architecture: {:?}",
-                target.architecture
-            ),
         }
+        Architecture::Wasm32 => {
+            // Useful for debugging
+            // module.print_to_file(app_ll_file);
+            module.write_bitcode_to_path(app_o_file);
+        }
+        _ => panic!(
+            "TODO gracefully handle unsupported architecture: {:?}",
+            target.architecture
+        ),
     }
 
     let emit_o_file = emit_o_file_start.elapsed();
@@ -416,11 +478,14 @@ pub fn gen_from_mono_module_llvm(
     }
 }
 #[cfg(feature = "target-wasm32")]
+#[allow(clippy::too_many_arguments)]
 pub fn gen_from_mono_module_dev(
     arena: &bumpalo::Bump,
     loaded: MonomorphizedModule,
     target: &target_lexicon::Triple,
     app_o_file: &Path,
+    emit_debug_info: bool,
+    emit: &EmitOptions,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> CodeGenTiming {
@@ -431,6 +496,8 @@ pub fn gen_from_mono_module_dev(
             arena,
             loaded,
             app_o_file,
+            emit_debug_info,
+            emit,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
         ),
@@ -442,11 +509,14 @@ pub fn gen_from_mono_module_dev(
 }
 
 #[cfg(not(feature = "target-wasm32"))]
+#[allow(clippy::too_many_arguments)]
 pub fn gen_from_mono_module_dev(
     arena: &bumpalo::Bump,
     loaded: MonomorphizedModule,
     target: &target_lexicon::Triple,
     app_o_file: &Path,
+    _emit_debug_info: bool,
+    _emit: &EmitOptions,
     _host_input_path: &Path,
     _wasm_dev_stack_bytes: Option<u32>,
 ) -> CodeGenTiming {
@@ -465,6 +535,8 @@ fn gen_from_mono_module_dev_wasm32(
     arena: &bumpalo::Bump,
     loaded: MonomorphizedModule,
     app_o_file: &Path,
+    emit_debug_info: bool,
+    emit: &EmitOptions,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> CodeGenTiming {
@@ -473,6 +545,9 @@ fn gen_from_mono_module_dev_wasm32(
         module_id,
         procedures,
         mut interns,
+        sources,
+        def_regions,
+        output_path,
         ..
     } = loaded;
 
@@ -488,6 +563,7 @@ fn gen_from_mono_module_dev_wasm32(
         module_id,
         exposed_to_host,
         stack_bytes: wasm_dev_stack_bytes.unwrap_or(roc_gen_wasm::Env::DEFAULT_STACK_BYTES),
+        mode: roc_gen_wasm::WasmBackendMode::Binary,
     };
 
     let host_bytes = std::fs::read(preprocessed_host_path).unwrap_or_else(|_| {
@@ -506,12 +582,58 @@ fn gen_from_mono_module_dev_wasm32(
         )
     });
 
-    let final_binary_bytes =
-        roc_gen_wasm::build_app_binary(&env, &mut interns, host_module, procedures);
+    // With --debug, browser devtools can map the binary back to the .roc files.
+    // The source map gets copied next to the final binary, so point at it by file name.
+    let source_map_path = wasm_source_map_path(app_o_file);
+
+    let (final_binary_bytes, source_map) = if emit_debug_info {
+        let source_map_url = wasm_source_map_path(&output_path.with_extension("wasm"))
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let (bytes, source_map) = roc_gen_wasm::build_app_binary_with_source_map(
+            &env,
+            &mut interns,
+            host_module,
+            procedures,
+            &sources,
+            &def_regions,
+            &source_map_url,
+        );
+
+        (bytes, Some(source_map))
+    } else {
+        let bytes = roc_gen_wasm::build_app_binary(&env, &mut interns, host_module, procedures);
+
+        (bytes, None)
+    };
 
     let code_gen = code_gen_start.elapsed();
     let emit_o_file_start = Instant::now();
 
+    if emit.wasm_text {
+        let wat = roc_gen_wasm::wasm_module::wat::binary_to_wat(&final_binary_bytes)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "I wasn't able to render the wasm binary as text, at offset 0x{:x}:\n{}",
+                    e.offset, e.message
+                )
+            });
+        write_emitted_file(&emit.path("wat"), wat);
+    }
+
+    if let Some(source_map) = source_map {
+        std::fs::write(&source_map_path, source_map).unwrap_or_else(|e| {
+            panic!(
+                "I wasn't able to write to the source map file {}\n{}",
+                source_map_path.display(),
+                e
+            )
+        });
+    }
+
     // The app_o_file is actually the final binary
     std::fs::write(&app_o_file, &final_binary_bytes).unwrap_or_else(|e| {
         panic!(
@@ -529,6 +651,14 @@ fn gen_from_mono_module_dev_wasm32(
     }
 }
 
+/// Where `--debug` builds put the source map of a wasm binary
+pub fn wasm_source_map_path(wasm_path: &Path) -> PathBuf {
+    let mut file_name = wasm_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".map");
+
+    wasm_path.with_file_name(file_name)
+}
+
 fn gen_from_mono_module_dev_assembly(
     arena: &bumpalo::Bump,
     loaded: MonomorphizedModule,
@@ -554,6 +684,7 @@ fn gen_from_mono_module_dev_assembly(
         exposed_to_host: exposed_to_host.values.keys().copied().collect(),
         lazy_literals,
         generate_allocators,
+        mode: roc_gen_dev::AssemblyBackendMode::Binary,
     };
 
     let module_object = roc_gen_dev::build_module(&env, &mut interns, target, procedures);
@@ -0,0 +1,176 @@
+const std = @import("std");
+const builtin = @import("builtin");
+
+// Allocation profiling, enabled with `roc build --profile-allocations`.
+//
+// Allocations are counted per site: a Roc procedure, and the layout of the value it was building.
+// The generated code calls `setAllocationSite` before anything that may allocate. When the
+// program exits, we print a summary to stderr, and write the bytes requested by each site to
+// `roc-allocations.folded` in the "folded stacks" format, which flamegraph tools can read.
+
+// Set by the compiler, like `debug_refcounts.enabled`.
+pub var enabled: bool = false;
+
+extern fn roc_alloc(size: usize, alignment: u32) callconv(.C) ?*anyopaque;
+extern fn roc_dealloc(c_ptr: *anyopaque, alignment: u32) callconv(.C) void;
+
+extern fn atexit(func: fn () callconv(.C) void) c_int;
+
+const FOLDED_FILE_NAME = "roc-allocations.folded";
+
+const Site = struct {
+    // null marks an empty slot
+    name: ?[*:0]const u8,
+    allocations: u64,
+    allocated_bytes: u64,
+    reallocations: u64,
+    reallocated_bytes: u64,
+    reuses: u64,
+
+    fn bytes(self: Site) u64 {
+        return self.allocated_bytes + self.reallocated_bytes;
+    }
+};
+
+const EMPTY = Site{ .name = null, .allocations = 0, .allocated_bytes = 0, .reallocations = 0, .reallocated_bytes = 0, .reuses = 0 };
+
+// e.g. allocations by the host, or by a builtin before any Roc code ran
+const UNATTRIBUTED: [*:0]const u8 = "(unattributed)";
+
+// An open-addressing hash table from the address of a site name to the counts of the site.
+// The generated code uses a single global per site, so comparing addresses is enough.
+var table: [*]Site = undefined;
+var table_capacity: usize = 0;
+var table_len: usize = 0;
+
+var current_site: [*:0]const u8 = UNATTRIBUTED;
+var registered_exit_report: bool = false;
+
+pub fn setAllocationSite(site: [*:0]const u8) callconv(.C) void {
+    current_site = site;
+}
+
+fn slotFor(name: [*:0]const u8) *Site {
+    const mask = table_capacity - 1;
+    const address = @ptrToInt(name);
+    var index = @truncate(usize, std.hash.Wyhash.hash(0, std.mem.asBytes(&address))) & mask;
+
+    while (true) {
+        const slot = &table[index];
+        if (slot.name == null or @ptrToInt(slot.name) == address) {
+            return slot;
+        }
+
+        index = (index + 1) & mask;
+    }
+}
+
+fn grow() void {
+    const old_table = table;
+    const old_capacity = table_capacity;
+
+    // must stay a power of two
+    table_capacity = if (old_capacity == 0) 256 else 2 * old_capacity;
+
+    const bytes = roc_alloc(table_capacity * @sizeOf(Site), @alignOf(Site)) orelse unreachable;
+    table = @ptrCast([*]Site, @alignCast(@alignOf(Site), bytes));
+
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        table[i] = EMPTY;
+    }
+
+    i = 0;
+    while (i < old_capacity) : (i += 1) {
+        const site = old_table[i];
+        if (site.name) |name| {
+            slotFor(name).* = site;
+        }
+    }
+
+    if (old_capacity != 0) {
+        roc_dealloc(@ptrCast(*anyopaque, old_table), @alignOf(Site));
+    }
+}
+
+fn currentSite() *Site {
+    // keep the table at most 3/4 full, so probe sequences stay short
+    if (4 * table_len >= 3 * table_capacity) {
+        grow();
+    }
+
+    const slot = slotFor(current_site);
+    if (slot.name == null) {
+        slot.name = current_site;
+        table_len += 1;
+    }
+
+    if (builtin.target.cpu.arch != .wasm32) {
+        if (!registered_exit_report) {
+            registered_exit_report = true;
+            _ = atexit(reportAtExit);
+        }
+    }
+
+    return slot;
+}
+
+pub fn recordAllocation(size: usize) void {
+    const site = currentSite();
+    site.allocations += 1;
+    site.allocated_bytes += size;
+}
+
+pub fn recordReallocation(new_size: usize) void {
+    const site = currentSite();
+    site.reallocations += 1;
+    site.reallocated_bytes += new_size;
+}
+
+pub fn recordReuse() callconv(.C) void {
+    currentSite().reuses += 1;
+}
+
+fn moreBytes(_: void, a: Site, b: Site) bool {
+    return a.bytes() > b.bytes();
+}
+
+fn reportAtExit() callconv(.C) void {
+    // gather the sites at the start of the table; we will not look anything up anymore
+    var count: usize = 0;
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        if (table[i].name != null) {
+            table[count] = table[i];
+            count += 1;
+        }
+    }
+
+    const sites = table[0..count];
+    std.sort.sort(Site, sites, {}, moreBytes);
+
+    const stderr = std.io.getStdErr().writer();
+    stderr.print("\nAllocation profile, by bytes requested (also written to {s}):\n\n", .{FOLDED_FILE_NAME}) catch return;
+    stderr.print("{s:>12} {s:>14} {s:>12} {s:>14} {s:>12}  {s}\n", .{ "allocations", "bytes", "reallocs", "realloc bytes", "reuses", "site" }) catch return;
+
+    for (sites) |site| {
+        stderr.print("{d:>12} {d:>14} {d:>12} {d:>14} {d:>12}  {s}\n", .{
+            site.allocations,
+            site.allocated_bytes,
+            site.reallocations,
+            site.reallocated_bytes,
+            site.reuses,
+            std.mem.span(site.name.?),
+        }) catch return;
+    }
+
+    const file = std.fs.cwd().createFile(FOLDED_FILE_NAME, .{}) catch return;
+    defer file.close();
+
+    const writer = file.writer();
+    for (sites) |site| {
+        if (site.bytes() > 0) {
+            writer.print("{s} {d}\n", .{ std.mem.span(site.name.?), site.bytes() }) catch return;
+        }
+    }
+}
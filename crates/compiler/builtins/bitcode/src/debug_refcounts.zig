@@ -0,0 +1,243 @@
+const std = @import("std");
+const builtin = @import("builtin");
+
+// Refcount debugging, enabled with `roc build --debug-refcounts` or `roc test --debug-refcounts`.
+//
+// Every allocation made through `utils.alloc` is recorded, together with the Roc procedure that
+// was running at the time. Freed allocations stay in the table, so that freeing an allocation a
+// second time, or decrementing the refcount of an allocation that is already gone, can report
+// where the allocation was made and where it was freed. Whatever is still live at the end of
+// the program (or of an expect) has leaked.
+//
+// The generated code calls `setRefcountOrigin` to tell us which procedure is running.
+
+// The compiler sets the initial values of these, and makes them constant; they are never written
+// at runtime. When they are false, LLVM removes all of the tracking code.
+pub var enabled: bool = false;
+pub var report_leaks_at_exit: bool = false;
+
+// Not the `utils` versions, those track the allocations they make!
+extern fn roc_alloc(size: usize, alignment: u32) callconv(.C) ?*anyopaque;
+extern fn roc_dealloc(c_ptr: *anyopaque, alignment: u32) callconv(.C) void;
+extern fn roc_panic(c_ptr: *const anyopaque, tag_id: u32) callconv(.C) void;
+
+extern fn atexit(func: fn () callconv(.C) void) c_int;
+
+const Origin = ?[*:0]const u8;
+
+const Allocation = struct {
+    // the start of the allocation; 0 marks an empty slot
+    address: usize,
+    allocated_in: Origin,
+    freed_in: Origin,
+    live: bool,
+};
+
+const EMPTY = Allocation{ .address = 0, .allocated_in = null, .freed_in = null, .live = false };
+
+// An open-addressing hash table from address to allocation. Entries are never removed (a freed
+// entry is marked as such), and an address that is handed out again just overwrites its entry.
+var table: [*]Allocation = undefined;
+var table_capacity: usize = 0;
+var table_len: usize = 0;
+
+var current_origin: Origin = null;
+var registered_exit_report: bool = false;
+
+var message_buffer: [1024]u8 = undefined;
+
+pub fn setRefcountOrigin(origin: [*:0]const u8) callconv(.C) void {
+    current_origin = origin;
+}
+
+fn hashAddress(address: usize) usize {
+    return @truncate(usize, std.hash.Wyhash.hash(0, std.mem.asBytes(&address)));
+}
+
+// The slot for this address: either the entry of the address, or an empty slot
+fn slotFor(address: usize) *Allocation {
+    const mask = table_capacity - 1;
+    var index = hashAddress(address) & mask;
+
+    while (true) {
+        const slot = &table[index];
+        if (slot.address == address or slot.address == 0) {
+            return slot;
+        }
+
+        index = (index + 1) & mask;
+    }
+}
+
+fn lookup(address: usize) ?*Allocation {
+    if (table_capacity == 0) {
+        return null;
+    }
+
+    const slot = slotFor(address);
+
+    return if (slot.address == 0) null else slot;
+}
+
+fn grow() void {
+    const old_table = table;
+    const old_capacity = table_capacity;
+
+    // must stay a power of two
+    table_capacity = if (old_capacity == 0) 1024 else 2 * old_capacity;
+
+    const bytes = roc_alloc(table_capacity * @sizeOf(Allocation), @alignOf(Allocation)) orelse unreachable;
+    table = @ptrCast([*]Allocation, @alignCast(@alignOf(Allocation), bytes));
+
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        table[i] = EMPTY;
+    }
+
+    i = 0;
+    while (i < old_capacity) : (i += 1) {
+        const allocation = old_table[i];
+        if (allocation.address != 0) {
+            slotFor(allocation.address).* = allocation;
+        }
+    }
+
+    if (old_capacity != 0) {
+        roc_dealloc(@ptrCast(*anyopaque, old_table), @alignOf(Allocation));
+    }
+}
+
+pub fn trackAllocation(ptr: [*]u8, origin: Origin) void {
+    // keep the table at most 3/4 full, so probe sequences stay short
+    if (4 * table_len >= 3 * table_capacity) {
+        grow();
+    }
+
+    const address = @ptrToInt(ptr);
+    const slot = slotFor(address);
+
+    if (slot.address == 0) {
+        table_len += 1;
+    }
+
+    slot.* = Allocation{ .address = address, .allocated_in = origin, .freed_in = null, .live = true };
+
+    if (builtin.target.cpu.arch != .wasm32) {
+        if (report_leaks_at_exit and !registered_exit_report) {
+            registered_exit_report = true;
+            _ = atexit(reportLeaksAtExit);
+        }
+    }
+}
+
+pub fn trackNewAllocation(ptr: [*]u8) void {
+    trackAllocation(ptr, current_origin);
+}
+
+// Returns where the allocation was made, so `realloc` can keep it
+pub fn trackDeallocation(ptr: [*]u8) Origin {
+    // allocations we do not know about were made by the host
+    const allocation = lookup(@ptrToInt(ptr)) orelse return current_origin;
+
+    if (!allocation.live) {
+        refcountError("a value was freed twice", allocation.*);
+    }
+
+    allocation.live = false;
+    allocation.freed_in = current_origin;
+
+    return allocation.allocated_in;
+}
+
+pub fn checkDecrement(refcount_ptr: [*]isize, alignment: u32) void {
+    // the inverse of `utils.allocateWithRefcount`
+    const extra_bytes = std.math.max(alignment, @sizeOf(usize));
+    const address = @ptrToInt(refcount_ptr) + @sizeOf(usize) - extra_bytes;
+
+    if (lookup(address)) |allocation| {
+        if (!allocation.live) {
+            refcountError("the refcount of a value was decremented after it was freed", allocation.*);
+        }
+    }
+}
+
+fn originName(origin: Origin) []const u8 {
+    return if (origin) |name| std.mem.span(name) else "an unknown procedure";
+}
+
+fn refcountError(problem: []const u8, allocation: Allocation) void {
+    const message: [:0]const u8 = std.fmt.bufPrintZ(
+        &message_buffer,
+        "Refcount error: {s}, in {s}. It was allocated in {s}, and freed in {s}.",
+        .{ problem, originName(current_origin), originName(allocation.allocated_in), originName(allocation.freed_in) },
+    ) catch "Refcount error: a value was freed twice, or its refcount was decremented after it was freed.";
+
+    roc_panic(message.ptr, 0);
+}
+
+pub const Leak = extern struct {
+    address: usize,
+    allocated_in: Origin,
+};
+
+// Writes up to `capacity` leaked allocations into `leaks`, and returns the total number of leaks
+pub fn refcountLeaks(leaks: [*]Leak, capacity: usize) callconv(.C) usize {
+    var count: usize = 0;
+
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        const allocation = table[i];
+        if (allocation.address != 0 and allocation.live) {
+            if (count < capacity) {
+                leaks[count] = Leak{ .address = allocation.address, .allocated_in = allocation.allocated_in };
+            }
+
+            count += 1;
+        }
+    }
+
+    return count;
+}
+
+// Forget all allocations, e.g. between two expects
+pub fn refcountReset() callconv(.C) void {
+    if (table_capacity != 0) {
+        roc_dealloc(@ptrCast(*anyopaque, table), @alignOf(Allocation));
+    }
+
+    table_capacity = 0;
+    table_len = 0;
+    current_origin = null;
+}
+
+fn leakCount() usize {
+    var count: usize = 0;
+
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        if (table[i].address != 0 and table[i].live) {
+            count += 1;
+        }
+    }
+
+    return count;
+}
+
+// Not exposed to the host, so it must not call the exported functions
+fn reportLeaksAtExit() callconv(.C) void {
+    const count = leakCount();
+    if (count == 0) {
+        return;
+    }
+
+    const stderr = std.io.getStdErr().writer();
+    stderr.print("\nRefcount error: {d} values were never freed:\n", .{count}) catch return;
+
+    var i: usize = 0;
+    while (i < table_capacity) : (i += 1) {
+        const allocation = table[i];
+        if (allocation.address != 0 and allocation.live) {
+            stderr.print("    0x{x}, allocated in {s}\n", .{ allocation.address, originName(allocation.allocated_in) }) catch return;
+        }
+    }
+}
@@ -1,4 +1,9 @@
 const std = @import("std");
+const str = @import("str.zig");
+const list = @import("list.zig");
+const RocStr = str.RocStr;
+const RocList = list.RocList;
+const testing = std.testing;
 
 extern fn shm_open(name: *const i8, oflag: c_int, mode: c_uint) c_int;
 extern fn mmap(addr: ?*anyopaque, length: c_uint, prot: c_int, flags: c_int, fd: c_int, offset: c_uint) *anyopaque;
@@ -32,3 +37,172 @@ pub fn expectFailedFinalize() callconv(.C) void {
 
     _ = kill(parent_pid, SIGUSR1);
 }
+
+// The shapes that expectCloneTo walks. They are written by the dev backend,
+// see `expect_shape` in gen_dev, and the two must be kept in sync.
+const SHAPE_COPY: u32 = 0; // size
+const SHAPE_STR: u32 = 1;
+const SHAPE_LIST: u32 = 2; // element size, element shape
+const SHAPE_STRUCT: u32 = 3; // number of fields, then the size and shape of each field
+const SHAPE_BOX: u32 = 4; // inner size, inner shape
+const SHAPE_NON_RECURSIVE: u32 = 5; // size, tag id offset, tag id size, then the payload shape of each tag
+const SHAPE_RECURSIVE: u32 = 6; // data size, tag id offset, tag id size, tag id mask, implicit tag id, then the payload shape of each tag
+
+/// Clones a value into the expect buffer the way the LLVM backend does:
+/// the value is written at `offset`, and everything it points to is written after it,
+/// with pointers replaced by offsets into the buffer.
+/// `shape` starts with the stack size of the value, followed by the shape of the value.
+/// Returns the offset just past everything that was written.
+pub fn expectCloneTo(value: [*]const u8, shape: [*]const u32, ptr: [*]u8, offset: usize) callconv(.C) usize {
+    return cloneShape(value, shape, 1, ptr, offset, offset + shape[0]);
+}
+
+fn cloneShape(value: [*]const u8, shape: [*]const u32, node: u32, ptr: [*]u8, offset: usize, extra_offset: usize) usize {
+    const s = shape + node;
+
+    switch (s[0]) {
+        SHAPE_COPY => {
+            @memcpy(ptr + offset, value, s[1]);
+
+            return extra_offset;
+        },
+        SHAPE_STR => {
+            return str.strCloneTo(readValue(RocStr, value), ptr, offset, extra_offset);
+        },
+        SHAPE_LIST => {
+            const element_width = s[1];
+            const element_shape = s[2];
+            const roc_list = readValue(RocList, value);
+
+            if (shape[element_shape] == SHAPE_COPY) {
+                return list.listCloneTo(roc_list, ptr, offset, extra_offset, element_width);
+            }
+
+            // write the list struct, with the elements just after it
+            const len = roc_list.len();
+            var relative = roc_list;
+            relative.bytes = @intToPtr(?[*]u8, extra_offset);
+            relative.capacity = len;
+            writeValue(RocList, ptr + offset, relative);
+
+            // anything the elements point to goes after all of the elements
+            var rest_offset = extra_offset + len * element_width;
+            if (roc_list.bytes) |source| {
+                var i: usize = 0;
+                while (i < len) : (i += 1) {
+                    const element_offset = i * element_width;
+                    rest_offset = cloneShape(source + element_offset, shape, element_shape, ptr, extra_offset + element_offset, rest_offset);
+                }
+            }
+
+            return rest_offset;
+        },
+        SHAPE_STRUCT => {
+            var field_offset: usize = 0;
+            var rest_offset = extra_offset;
+
+            var i: u32 = 0;
+            while (i < s[1]) : (i += 1) {
+                const field_width = s[2 + 2 * i];
+                const field_shape = s[3 + 2 * i];
+                rest_offset = cloneShape(value + field_offset, shape, field_shape, ptr, offset + field_offset, rest_offset);
+                field_offset += field_width;
+            }
+
+            return rest_offset;
+        },
+        SHAPE_BOX => {
+            const inner = readValue([*]const u8, value);
+            writeValue(usize, ptr + offset, extra_offset);
+
+            return cloneShape(inner, shape, s[2], ptr, extra_offset, extra_offset + s[1]);
+        },
+        SHAPE_NON_RECURSIVE => {
+            // copy the whole union, including its tag id, and then clone the payload of its tag over it
+            @memcpy(ptr + offset, value, s[1]);
+            const tag_id = readTagId(value + s[2], s[3]);
+
+            return cloneShape(value, shape, s[4 + tag_id], ptr, offset, extra_offset);
+        },
+        SHAPE_RECURSIVE => {
+            const address = readValue(usize, value);
+            if (address == 0) {
+                // the nullable tag
+                writeValue(usize, ptr + offset, 0);
+
+                return extra_offset;
+            }
+
+            const data_width = s[1];
+            const tag_id_mask = s[4];
+            const data = @intToPtr([*]const u8, address & ~@as(usize, tag_id_mask));
+
+            var tag_id: u32 = s[5];
+            if (tag_id_mask != 0) {
+                tag_id = @intCast(u32, address & tag_id_mask);
+
+                // offsets can't spare their low bits, so the tag id is written next to the offset
+                writeValue(u32, ptr + offset, tag_id);
+                writeValue(u32, ptr + offset + 4, @intCast(u32, extra_offset));
+            } else {
+                if (s[3] != 0) {
+                    tag_id = readTagId(data + s[2], s[3]);
+                }
+
+                writeValue(usize, ptr + offset, extra_offset);
+            }
+
+            @memcpy(ptr + extra_offset, data, data_width);
+
+            return cloneShape(data, shape, s[6 + tag_id], ptr, extra_offset, extra_offset + data_width);
+        },
+        else => unreachable,
+    }
+}
+
+fn readTagId(bytes: [*]const u8, size: u32) u32 {
+    return switch (size) {
+        1 => readValue(u8, bytes),
+        2 => readValue(u16, bytes),
+        else => unreachable,
+    };
+}
+
+fn readValue(comptime T: type, bytes: [*]const u8) T {
+    return @ptrCast(*align(1) const T, bytes).*;
+}
+
+fn writeValue(comptime T: type, bytes: [*]u8, value: T) void {
+    @ptrCast(*align(1) T, bytes).* = value;
+}
+
+test "expectCloneTo: record with a string" {
+    const Record = extern struct { name: RocStr, count: u64 };
+
+    const name = RocStr.init("a string that is too long to be small", 37);
+    defer name.deinit();
+    const record = Record{ .name = name, .count = 42 };
+
+    const shape = [_]u32{ 32, SHAPE_STRUCT, 2, 24, 7, 8, 8, SHAPE_STR, SHAPE_COPY, 8 };
+    var buffer = [_]u8{0} ** 128;
+    const end = expectCloneTo(@ptrCast([*]const u8, &record), &shape, &buffer, 16);
+
+    try testing.expectEqual(@as(usize, 16 + 32 + 37), end);
+    try testing.expectEqual(@as(usize, 16 + 32), readValue(usize, buffer[16..].ptr));
+    try testing.expectEqual(@as(usize, 37), readValue(usize, buffer[24..].ptr));
+    try testing.expectEqual(@as(u64, 42), readValue(u64, buffer[40..].ptr));
+    try testing.expectEqualSlices(u8, name.asSlice(), buffer[48..85]);
+}
+
+test "expectCloneTo: box" {
+    const inner: u64 = 1234;
+    const boxed: *const u64 = &inner;
+
+    const shape = [_]u32{ 8, SHAPE_BOX, 8, 4, SHAPE_COPY, 8 };
+    var buffer = [_]u8{0} ** 32;
+    const end = expectCloneTo(@ptrCast([*]const u8, &boxed), &shape, &buffer, 0);
+
+    try testing.expectEqual(@as(usize, 16), end);
+    try testing.expectEqual(@as(usize, 8), readValue(usize, buffer[0..].ptr));
+    try testing.expectEqual(@as(u64, 1234), readValue(u64, buffer[8..].ptr));
+}
@@ -819,3 +819,31 @@ pub fn listIsUnique(
 ) callconv(.C) bool {
     return list.isEmpty() or list.isUnique();
 }
+
+/// Copies a list whose elements need no cloning of their own into `ptr`, like `strCloneTo`.
+/// The list is written at `offset` with its elements at `extra_offset`, and the
+/// offset just after the elements is returned.
+pub fn listCloneTo(
+    list: RocList,
+    ptr: [*]u8,
+    offset: usize,
+    extra_offset: usize,
+    element_width: usize,
+) callconv(.C) usize {
+    const WIDTH: usize = @sizeOf(RocList);
+    const elements_width = list.len() * element_width;
+
+    var relative = list;
+    relative.bytes = @intToPtr(?[*]u8, extra_offset); // i.e. just after the list struct
+    relative.capacity = list.len();
+
+    // write the list struct
+    @memcpy(ptr + offset, @ptrCast([*]const u8, &relative), WIDTH);
+
+    // write the elements just after the struct
+    if (list.bytes) |source| {
+        @memcpy(ptr + extra_offset, source, elements_width);
+    }
+
+    return extra_offset + elements_width;
+}
@@ -3,6 +3,8 @@ const builtin = @import("builtin");
 const math = std.math;
 const utils = @import("utils.zig");
 const expect = @import("expect.zig");
+const debug_refcounts = @import("debug_refcounts.zig");
+const allocation_profile = @import("allocation_profile.zig");
 
 const ROC_BUILTINS = "roc_builtins";
 const NUM = "num";
@@ -53,6 +55,7 @@ comptime {
     exportListFn(list.listReplaceInPlace, "replace_in_place");
     exportListFn(list.listSwap, "swap");
     exportListFn(list.listIsUnique, "is_unique");
+    exportListFn(list.listCloneTo, "clone_to");
 }
 
 // Num Module
@@ -95,6 +98,12 @@ comptime {
         }
     }
 
+    inline for (NUMBERS) |FROM| {
+        inline for (FLOATS) |TO| {
+            num.exportToFloatChecking(FROM, TO, ROC_BUILTINS ++ "." ++ NUM ++ ".num_to_" ++ @typeName(TO) ++ "_checked.");
+        }
+    }
+
     inline for (FLOATS) |T| {
         num.exportAsin(T, ROC_BUILTINS ++ "." ++ NUM ++ ".asin.");
         num.exportAcos(T, ROC_BUILTINS ++ "." ++ NUM ++ ".acos.");
@@ -114,6 +123,13 @@ comptime {
     }
 }
 
+// Dict Module
+const hash = @import("hash.zig");
+comptime {
+    exportDictFn(hash.wyhash, "hash");
+    exportDictFn(hash.wyhash_rocstr, "hash_str");
+}
+
 // Str Module
 const str = @import("str.zig");
 comptime {
@@ -166,9 +182,22 @@ comptime {
 
     @export(utils.panic, .{ .name = "roc_builtins.utils." ++ "panic", .linkage = .Weak });
 
+    // see `roc build --debug-refcounts`
+    exportUtilsFn(debug_refcounts.setRefcountOrigin, "set_refcount_origin");
+    @export(debug_refcounts.enabled, .{ .name = "roc_builtins.utils.debug_refcounts", .linkage = .Strong });
+    @export(debug_refcounts.report_leaks_at_exit, .{ .name = "roc_builtins.utils.report_leaks_at_exit", .linkage = .Strong });
+    @export(debug_refcounts.refcountLeaks, .{ .name = "roc_refcount_leaks", .linkage = .Weak });
+    @export(debug_refcounts.refcountReset, .{ .name = "roc_refcount_reset", .linkage = .Weak });
+
+    // see `roc build --profile-allocations`
+    exportUtilsFn(allocation_profile.setAllocationSite, "set_allocation_site");
+    exportUtilsFn(allocation_profile.recordReuse, "record_reuse");
+    @export(allocation_profile.enabled, .{ .name = "roc_builtins.utils.profile_allocations", .linkage = .Strong });
+
     if (builtin.target.cpu.arch != .wasm32) {
         exportUtilsFn(expect.expectFailedStart, "expect_failed_start");
         exportUtilsFn(expect.expectFailedFinalize, "expect_failed_finalize");
+        exportUtilsFn(expect.expectCloneTo, "expect_clone_to");
 
         // sets the buffer used for expect failures
         @export(expect.setSharedBuffer, .{ .name = "set_shared_buffer", .linkage = .Weak });
@@ -218,6 +218,30 @@ pub fn exportToIntCheckingMaxAndMin(comptime From: type, comptime To: type, comp
     @export(f, .{ .name = name ++ @typeName(From), .linkage = .Strong });
 }
 
+pub fn exportToFloatChecking(comptime From: type, comptime To: type, comptime name: []const u8) void {
+    comptime var f = struct {
+        fn func(input: From) callconv(.C) ToIntCheckedResult(To) {
+            const max: f64 = if (To == f32) std.math.f32_max else std.math.f64_max;
+            const wide: f64 = switch (@typeInfo(From)) {
+                .Float => @floatCast(f64, input),
+                else => @intToFloat(f64, input),
+            };
+
+            // Infinities and NaN convert to themselves; only finite values can be too large.
+            if (std.math.isFinite(wide) and (wide > max or wide < -max)) {
+                return .{ .out_of_bounds = true, .value = 0 };
+            }
+
+            const value = switch (@typeInfo(From)) {
+                .Float => @floatCast(To, input),
+                else => @intToFloat(To, input),
+            };
+            return .{ .out_of_bounds = false, .value = value };
+        }
+    }.func;
+    @export(f, .{ .name = name ++ @typeName(From), .linkage = .Strong });
+}
+
 pub fn bytesToU16C(arg: RocList, position: usize) callconv(.C) u16 {
     return @call(.{ .modifier = always_inline }, bytesToU16, .{ arg, position });
 }
@@ -1,4 +1,6 @@
 const std = @import("std");
+const debug_refcounts = @import("debug_refcounts.zig");
+const allocation_profile = @import("allocation_profile.zig");
 const always_inline = std.builtin.CallOptions.Modifier.always_inline;
 const Monotonic = std.builtin.AtomicOrder.Monotonic;
 
@@ -67,14 +69,41 @@ fn testing_roc_memcpy(dest: *anyopaque, src: *anyopaque, bytes: usize) callconv(
 }
 
 pub fn alloc(size: usize, alignment: u32) ?[*]u8 {
-    return @ptrCast(?[*]u8, @call(.{ .modifier = always_inline }, roc_alloc, .{ size, alignment }));
+    const ptr = @ptrCast(?[*]u8, @call(.{ .modifier = always_inline }, roc_alloc, .{ size, alignment }));
+
+    if (debug_refcounts.enabled) {
+        if (ptr) |bytes| debug_refcounts.trackNewAllocation(bytes);
+    }
+
+    if (allocation_profile.enabled) {
+        allocation_profile.recordAllocation(size);
+    }
+
+    return ptr;
 }
 
 pub fn realloc(c_ptr: [*]u8, new_size: usize, old_size: usize, alignment: u32) [*]u8 {
-    return @ptrCast([*]u8, @call(.{ .modifier = always_inline }, roc_realloc, .{ c_ptr, new_size, old_size, alignment }));
+    const origin = if (debug_refcounts.enabled) debug_refcounts.trackDeallocation(c_ptr) else null;
+
+    const ptr = @ptrCast([*]u8, @call(.{ .modifier = always_inline }, roc_realloc, .{ c_ptr, new_size, old_size, alignment }));
+
+    if (debug_refcounts.enabled) {
+        // the new allocation still belongs to the code that made the original one
+        debug_refcounts.trackAllocation(ptr, origin);
+    }
+
+    if (allocation_profile.enabled) {
+        allocation_profile.recordReallocation(new_size);
+    }
+
+    return ptr;
 }
 
 pub fn dealloc(c_ptr: [*]u8, alignment: u32) void {
+    if (debug_refcounts.enabled) {
+        _ = debug_refcounts.trackDeallocation(c_ptr);
+    }
+
     return @call(.{ .modifier = always_inline }, roc_dealloc, .{ c_ptr, alignment });
 }
 
@@ -191,6 +220,9 @@ inline fn decref_ptr_to_refcount(
     alignment: u32,
 ) void {
     if (RC_TYPE == Refcount.none) return;
+    if (debug_refcounts.enabled) {
+        debug_refcounts.checkDecrement(refcount_ptr, alignment);
+    }
     const extra_bytes = std.math.max(alignment, @sizeOf(usize));
     // Ensure that the refcount is not whole program lifetime.
     const refcount: isize = refcount_ptr[0];
@@ -15,6 +15,7 @@ interface Dict
         insertAll,
         keepShared,
         removeAll,
+        hashTestOnly,
     ]
     imports [
         Bool.{ Bool },
@@ -163,6 +164,9 @@ removeAll : Dict k v, Dict k v -> Dict k v
 removeAll = \xs, @Dict ys ->
     List.walk ys xs (\state, Pair k _ -> Dict.remove state k)
 
+## Hash a value with the given seed. This is only exposed to test code gen of hashing.
+hashTestOnly : a, U64 -> U64
+
 ## Internal helper function to insert a new association
 ##
 ## Precondition: `k` should not exist in the Dict yet.
@@ -1,3 +1,4 @@
+use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_target::TargetInfo;
 use roc_utils::get_lib_path;
@@ -351,6 +352,9 @@ pub const STR_APPEND_SCALAR: &str = "roc_builtins.str.append_scalar";
 pub const STR_GET_SCALAR_UNSAFE: &str = "roc_builtins.str.get_scalar_unsafe";
 pub const STR_CLONE_TO: &str = "roc_builtins.str.clone_to";
 
+pub const DICT_HASH: &str = "roc_builtins.dict.hash";
+pub const DICT_HASH_STR: &str = "roc_builtins.dict.hash_str";
+
 pub const LIST_MAP: &str = "roc_builtins.list.map";
 pub const LIST_MAP2: &str = "roc_builtins.list.map2";
 pub const LIST_MAP3: &str = "roc_builtins.list.map3";
@@ -367,6 +371,7 @@ pub const LIST_IS_UNIQUE: &str = "roc_builtins.list.is_unique";
 pub const LIST_PREPEND: &str = "roc_builtins.list.prepend";
 pub const LIST_APPEND_UNSAFE: &str = "roc_builtins.list.append_unsafe";
 pub const LIST_RESERVE: &str = "roc_builtins.list.reserve";
+pub const LIST_CLONE_TO: &str = "roc_builtins.list.clone_to";
 
 pub const DEC_FROM_STR: &str = "roc_builtins.dec.from_str";
 pub const DEC_TO_STR: &str = "roc_builtins.dec.to_str";
@@ -390,9 +395,23 @@ pub const UTILS_ALLOCATE_WITH_REFCOUNT: &str = "roc_builtins.utils.allocate_with
 pub const UTILS_INCREF: &str = "roc_builtins.utils.incref";
 pub const UTILS_DECREF: &str = "roc_builtins.utils.decref";
 pub const UTILS_DECREF_CHECK_NULL: &str = "roc_builtins.utils.decref_check_null";
+pub const UTILS_SET_REFCOUNT_ORIGIN: &str = "roc_builtins.utils.set_refcount_origin";
+
+/// Globals that switch on refcount debugging, see `debug_refcounts.zig`
+pub const UTILS_DEBUG_REFCOUNTS: &str = "roc_builtins.utils.debug_refcounts";
+pub const UTILS_REPORT_LEAKS_AT_EXIT: &str = "roc_builtins.utils.report_leaks_at_exit";
+/// Exposed to the runner of `roc test --debug-refcounts`
+pub const UTILS_REFCOUNT_LEAKS: &str = "roc_refcount_leaks";
+pub const UTILS_REFCOUNT_RESET: &str = "roc_refcount_reset";
+
+/// Switches on allocation profiling, see `allocation_profile.zig`
+pub const UTILS_PROFILE_ALLOCATIONS: &str = "roc_builtins.utils.profile_allocations";
+pub const UTILS_SET_ALLOCATION_SITE: &str = "roc_builtins.utils.set_allocation_site";
+pub const UTILS_RECORD_REUSE: &str = "roc_builtins.utils.record_reuse";
 
 pub const UTILS_EXPECT_FAILED_START: &str = "roc_builtins.utils.expect_failed_start";
 pub const UTILS_EXPECT_FAILED_FINALIZE: &str = "roc_builtins.utils.expect_failed_finalize";
+pub const UTILS_EXPECT_CLONE_TO: &str = "roc_builtins.utils.expect_clone_to";
 
 pub const UTILS_LONGJMP: &str = "longjmp";
 pub const UTILS_SETJMP: &str = "setjmp";
@@ -443,3 +462,49 @@ pub const NUM_INT_TO_INT_CHECKING_MAX: IntToIntrinsicName =
     int_to_int_intrinsic!("roc_builtins.num.int_to_", "_checking_max");
 pub const NUM_INT_TO_INT_CHECKING_MAX_AND_MIN: IntToIntrinsicName =
     int_to_int_intrinsic!("roc_builtins.num.int_to_", "_checking_max_and_min");
+
+#[derive(Debug, Default)]
+pub struct NumToFloatIntrinsicName {
+    pub options: [IntrinsicName; 2],
+}
+
+impl NumToFloatIntrinsicName {
+    pub const fn default() -> Self {
+        Self {
+            options: [IntrinsicName::default(); 2],
+        }
+    }
+}
+
+impl Index<FloatWidth> for NumToFloatIntrinsicName {
+    type Output = IntrinsicName;
+
+    fn index(&self, index: FloatWidth) -> &Self::Output {
+        match index {
+            FloatWidth::F32 => &self.options[0],
+            FloatWidth::F64 => &self.options[1],
+            FloatWidth::F128 => internal_error!("conversions to f128 are not supported"),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! num_to_float_intrinsic {
+    ($name_prefix:literal, $name_suffix:literal) => {{
+        let mut output = NumToFloatIntrinsicName::default();
+
+        output.options[0] = int_intrinsic!(concat!($name_prefix, "f32", $name_suffix));
+        output.options[1] = int_intrinsic!(concat!($name_prefix, "f64", $name_suffix));
+
+        // The source number can also be a float
+        output.options[0].options[1] = concat!($name_prefix, "f32", $name_suffix, ".f32");
+        output.options[0].options[2] = concat!($name_prefix, "f32", $name_suffix, ".f64");
+        output.options[1].options[1] = concat!($name_prefix, "f64", $name_suffix, ".f32");
+        output.options[1].options[2] = concat!($name_prefix, "f64", $name_suffix, ".f64");
+
+        output
+    }};
+}
+
+pub const NUM_TO_FLOAT_CHECKED: NumToFloatIntrinsicName =
+    num_to_float_intrinsic!("roc_builtins.num.num_to_", "_checked");
@@ -1077,6 +1077,10 @@ pub fn canonicalize_expr<'a>(
             use roc_problem::can::RuntimeError::*;
             (RuntimeError(MalformedClosure(region)), Output::default())
         }
+        ast::Expr::Malformed(_, _) => {
+            use roc_problem::can::RuntimeError::*;
+            (RuntimeError(MalformedSyntax(region)), Output::default())
+        }
         ast::Expr::MalformedIdent(name, bad_ident) => {
             use roc_problem::can::RuntimeError::*;
 
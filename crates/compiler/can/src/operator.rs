@@ -125,6 +125,7 @@ pub fn desugar_expr<'a>(arena: &'a Bump, loc_expr: &'a Loc<Expr<'a>>) -> &'a Loc
         | Underscore { .. }
         | MalformedIdent(_, _)
         | MalformedClosure
+        | Malformed(_, _)
         | PrecedenceConflict { .. }
         | Tag(_)
         | OpaqueRef(_) => loc_expr,
@@ -1,7 +1,7 @@
 //! Traversals over the can ast.
 
 use roc_module::{ident::Lowercase, symbol::Symbol};
-use roc_region::all::{Loc, Region};
+use roc_region::all::{Loc, Position, Region};
 use roc_types::{subs::Variable, types::MemberImpl};
 
 use crate::{
@@ -520,6 +520,117 @@ pub fn find_type_at(region: Region, decls: &Declarations) -> Option<Variable> {
     visitor.typ
 }
 
+struct ClosestTypeVisitor {
+    position: Region,
+    found: Option<(Region, Variable)>,
+}
+
+impl Visitor for ClosestTypeVisitor {
+    fn should_visit(&mut self, region: Region) -> bool {
+        region.contains(&self.position)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        if self.should_visit(region) {
+            // nested nodes are visited after their parents, so the last match is the closest
+            if var != Variable::NULL {
+                self.found = Some((region, var));
+            }
+
+            walk_expr(self, expr, var);
+        }
+    }
+
+    fn visit_pattern(&mut self, pat: &Pattern, region: Region, opt_var: Option<Variable>) {
+        if self.should_visit(region) {
+            if let Some(var) = opt_var {
+                self.found = Some((region, var));
+            }
+
+            walk_pattern(self, pat);
+        }
+    }
+}
+
+/// Finds the innermost expression or pattern that covers `position`, and returns its region
+/// and type. Unlike [find_type_at], this does not need to know the exact region of the node.
+pub fn find_closest_type_at(
+    position: Position,
+    decls: &Declarations,
+) -> Option<(Region, Variable)> {
+    let mut visitor = ClosestTypeVisitor {
+        position: Region::from_pos(position),
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    visitor.found
+}
+
+/// A place where a symbol is defined, or referenced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SymbolOccurrence {
+    pub symbol: Symbol,
+    pub region: Region,
+    pub is_definition: bool,
+}
+
+/// Collects the places where symbols are defined or referenced, in the order they appear in
+/// the can ast.
+pub fn symbol_occurrences(decls: &Declarations) -> Vec<SymbolOccurrence> {
+    let mut visitor = Collector {
+        occurrences: Vec::new(),
+    };
+    visitor.visit_decls(decls);
+    return visitor.occurrences;
+
+    struct Collector {
+        occurrences: Vec<SymbolOccurrence>,
+    }
+
+    impl Collector {
+        fn push(&mut self, symbol: Symbol, region: Region, is_definition: bool) {
+            self.occurrences.push(SymbolOccurrence {
+                symbol,
+                region,
+                is_definition,
+            });
+        }
+    }
+
+    impl Visitor for Collector {
+        fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+            match expr {
+                Expr::Var(symbol) | Expr::AbilityMember(symbol, _, _) => {
+                    self.push(*symbol, region, false)
+                }
+                _ => walk_expr(self, expr, var),
+            }
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+            use Pattern::*;
+            match pattern {
+                Identifier(symbol)
+                | Shadowed(_, _, symbol)
+                | AbilityMemberSpecialization { ident: symbol, .. } => {
+                    self.push(*symbol, region, true)
+                }
+                _ => walk_pattern(self, pattern),
+            }
+        }
+
+        fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+            // same as in `symbols_introduced_from_pattern`
+            if let DestructType::Guard(_, subpattern) = &destruct.typ {
+                self.visit_pattern(&subpattern.value, subpattern.region, None);
+            } else {
+                self.push(destruct.symbol, region, true);
+                walk_record_destruct(self, destruct);
+            }
+        }
+    }
+}
+
 /// Given an ability Foo has foo : ..., returns (T, foo1) if the symbol at the given region is a
 /// symbol foo1 that specializes foo for T. Otherwise if the symbol is foo but the specialization
 /// is unknown, (Foo, foo) is returned. Otherwise [None] is returned.
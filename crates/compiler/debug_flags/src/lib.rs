@@ -96,6 +96,10 @@ flags! {
     /// Writes a pretty-printed mono IR to stderr after function specialization.
     ROC_PRINT_IR_AFTER_SPECIALIZATION
 
+    /// Writes a pretty-printed mono IR to stderr after inlining and constant
+    /// folding, if that pass is enabled.
+    ROC_PRINT_IR_AFTER_INLINING
+
     /// Writes a pretty-printed mono IR to stderr after insertion of reset/reuse
     /// instructions.
     ROC_PRINT_IR_AFTER_RESET_REUSE
@@ -104,6 +108,14 @@ flags! {
     /// instructions.
     ROC_PRINT_IR_AFTER_REFCOUNT
 
+    /// Writes a pretty-printed mono IR to stderr after refcount operations have
+    /// been expanded and cancelled.
+    ROC_PRINT_IR_AFTER_EXPAND_RC
+
+    /// Checks that the mono IR is well-formed after each stage, and panics with a
+    /// list of the problems if it is not.
+    ROC_CHECK_MONO_IR
+
     /// Prints debug information during the alias analysis pass.
     ROC_DEBUG_ALIAS_ANALYSIS
 
@@ -292,9 +292,18 @@ impl<'a> Formattable for TypeAnnotation<'a> {
                     buf.push(')')
                 }
             }
-            BoundVariable(v) => buf.push_str(v),
-            Wildcard => buf.push('*'),
-            Inferred => buf.push('_'),
+            BoundVariable(v) => {
+                buf.indent(indent);
+                buf.push_str(v)
+            }
+            Wildcard => {
+                buf.indent(indent);
+                buf.push('*')
+            }
+            Inferred => {
+                buf.indent(indent);
+                buf.push('_')
+            }
 
             TagUnion { tags, ext } => {
                 fmt_collection(buf, indent, Braces::Square, *tags, newlines);
@@ -31,7 +31,12 @@ pub fn fmt_collection<'a, 'buf, T: ExtractSpaces<'a> + Formattable>(
         Braces::Square => ']',
     };
 
-    if items.is_multiline() {
+    let is_multiline = items.is_multiline()
+        || !buf.fits_on_line(indent, |buf| {
+            fmt_collection_single_line(buf, indent, braces, &items)
+        });
+
+    if is_multiline {
         let braces_indent = indent;
         let item_indent = braces_indent + INDENT;
         if newline == Newlines::Yes {
@@ -116,26 +121,41 @@ pub fn fmt_collection<'a, 'buf, T: ExtractSpaces<'a> + Formattable>(
 
         buf.ensure_ends_with_newline();
         buf.indent(braces_indent);
+        buf.push(end);
     } else {
         // is_multiline == false
         // there is no comment to add
-        buf.indent(indent);
-        buf.push(start);
-        let mut iter = items.iter().enumerate().peekable();
-        while let Some((index, item)) = iter.next() {
-            if braces == Braces::Curly || index != 0 {
-                buf.spaces(1);
-            }
+        fmt_collection_single_line(buf, indent, braces, &items);
+    }
+}
 
-            item.format(buf, indent);
-            if iter.peek().is_some() {
-                buf.push(',');
-            }
-        }
+fn fmt_collection_single_line<'a, 'buf, T: ExtractSpaces<'a> + Formattable>(
+    buf: &mut Buf<'buf>,
+    indent: u16,
+    braces: Braces,
+    items: &Collection<'a, T>,
+) {
+    let (start, end) = match braces {
+        Braces::Curly => ('{', '}'),
+        Braces::Square => ('[', ']'),
+    };
 
-        if !items.is_empty() && braces == Braces::Curly {
+    buf.indent(indent);
+    buf.push(start);
+    let mut iter = items.iter().enumerate().peekable();
+    while let Some((index, item)) = iter.next() {
+        if braces == Braces::Curly || index != 0 {
             buf.spaces(1);
         }
+
+        item.format(buf, indent);
+        if iter.peek().is_some() {
+            buf.push(',');
+        }
+    }
+
+    if !items.is_empty() && braces == Braces::Curly {
+        buf.spaces(1);
     }
 
     buf.push(end);
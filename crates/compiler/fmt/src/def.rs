@@ -175,7 +175,18 @@ impl<'a> Formattable for ValueDef<'a> {
             Annotation(loc_pattern, loc_annotation) => {
                 loc_pattern.format(buf, indent);
 
-                if loc_annotation.is_multiline() {
+                if !loc_annotation.is_multiline()
+                    && !function_type_fits_on_line(buf, &loc_annotation.value, indent)
+                {
+                    buf.push_str(" :");
+                    buf.newline();
+                    loc_annotation.format_with_options(
+                        buf,
+                        Parens::NotNeeded,
+                        Newlines::Yes,
+                        indent + INDENT,
+                    );
+                } else if loc_annotation.is_multiline() {
                     buf.push_str(" :");
 
                     let should_outdent = match loc_annotation.value {
@@ -259,9 +270,18 @@ impl<'a> Formattable for ValueDef<'a> {
                 };
 
                 ann_pattern.format(buf, indent);
-                buf.push_str(" :");
 
-                if is_type_multiline && is_type_function {
+                if !is_type_multiline && !function_type_fits_on_line(buf, &ann_type.value, indent) {
+                    buf.push_str(" :");
+                    buf.newline();
+                    ann_type.format_with_options(
+                        buf,
+                        Parens::NotNeeded,
+                        Newlines::Yes,
+                        indent + INDENT,
+                    );
+                } else if is_type_multiline && is_type_function {
+                    buf.push_str(" :");
                     ann_type.format_with_options(
                         buf,
                         Parens::NotNeeded,
@@ -269,6 +289,7 @@ impl<'a> Formattable for ValueDef<'a> {
                         next_indent,
                     );
                 } else {
+                    buf.push_str(" :");
                     buf.spaces(1);
                     ann_type.format(buf, indent);
                 }
@@ -286,6 +307,17 @@ impl<'a> Formattable for ValueDef<'a> {
     }
 }
 
+/// Whether a type fits after the ` :` of its annotation. Only function types are broken up when
+/// they do not; they then go on lines of their own, one argument per line.
+fn function_type_fits_on_line(buf: &Buf, ann: &TypeAnnotation, indent: u16) -> bool {
+    !matches!(ann, TypeAnnotation::Function(..))
+        || buf.fits_on_line(indent, |buf| {
+            buf.push_str(" :");
+            buf.spaces(1);
+            ann.format(buf, indent);
+        })
+}
+
 fn fmt_expect<'a, 'buf>(
     buf: &mut Buf<'buf>,
     condition: &'a Loc<Expr<'a>>,
@@ -353,7 +385,15 @@ pub fn fmt_body<'a, 'buf>(
     pattern.format_with_options(buf, Parens::InApply, Newlines::No, indent);
     buf.push_str(" =");
 
-    if body.is_multiline() {
+    // a `|>` chain or `if` that does not fit goes on lines of its own, just like a multiline one
+    let is_multiline = body.is_multiline()
+        || (matches!(body, Expr::BinOps(..) | Expr::If(..))
+            && !buf.fits_on_line(indent, |buf| {
+                buf.spaces(1);
+                body.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, indent);
+            }));
+
+    if is_multiline {
         match body {
             Expr::SpaceBefore(sub_def, spaces) => {
                 let should_outdent = match sub_def {
@@ -392,6 +432,11 @@ pub fn fmt_body<'a, 'buf>(
                 buf.newline();
                 body.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, indent + INDENT);
             }
+            Expr::If(..) | Expr::ParensAround(..) | Expr::Access(..) => {
+                // The `else` or `)` has to be indented further than the def, so it cannot go after the `=`
+                buf.newline();
+                body.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, indent + INDENT);
+            }
             _ => {
                 buf.spaces(1);
                 body.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, indent);
@@ -34,7 +34,6 @@ impl<'a> Formattable for Expr<'a> {
             | Num(..)
             | NonBase10Int { .. }
             | SingleQuote(_)
-            | Access(_, _)
             | AccessorFunction(_)
             | Var { .. }
             | Underscore { .. }
@@ -46,6 +45,8 @@ impl<'a> Formattable for Expr<'a> {
             // These expressions always have newlines
             Defs(_, _) | When(_, _) => true,
 
+            Malformed(text, _) => text.contains('\n'),
+
             List(items) => items.iter().any(|loc_expr| loc_expr.is_multiline()),
 
             Str(literal) => {
@@ -87,7 +88,7 @@ impl<'a> Formattable for Expr<'a> {
                 expr: loc_subexpr, ..
             }) => loc_subexpr.is_multiline(),
 
-            ParensAround(subexpr) => subexpr.is_multiline(),
+            ParensAround(subexpr) | Access(subexpr, _) => subexpr.is_multiline(),
 
             Closure(loc_patterns, loc_body) => {
                 // check the body first because it's more likely to be multiline
@@ -188,6 +189,12 @@ impl<'a> Formattable for Expr<'a> {
                 buf.push_str(name);
             }
             Apply(loc_expr, loc_args, _) => {
+                // arguments that do not fit on the line each go on a line of their own
+                let multiline_args = loc_args.iter().any(|loc_arg| loc_arg.is_multiline())
+                    || !buf.fits_on_line(indent, |buf| {
+                        self.format_with_options(buf, parens, newlines, indent)
+                    });
+
                 buf.indent(indent);
                 if apply_needs_parens && !loc_args.is_empty() {
                     buf.push('(');
@@ -195,8 +202,6 @@ impl<'a> Formattable for Expr<'a> {
 
                 loc_expr.format_with_options(buf, Parens::InApply, Newlines::Yes, indent);
 
-                let multiline_args = loc_args.iter().any(|loc_arg| loc_arg.is_multiline());
-
                 let mut found_multiline_expr = false;
                 let mut iter = loc_args.iter().peekable();
 
@@ -288,6 +293,7 @@ impl<'a> Formattable for Expr<'a> {
                 buf.push_str(string)
             }
             SingleQuote(string) => {
+                buf.indent(indent);
                 buf.push('\'');
                 buf.push_str(string);
                 buf.push('\'');
@@ -368,7 +374,15 @@ impl<'a> Formattable for Expr<'a> {
                 fmt_expect(buf, condition, continuation, self.is_multiline(), indent);
             }
             If(branches, final_else) => {
-                fmt_if(buf, branches, final_else, self.is_multiline(), indent);
+                // like a `|>` chain, only an `if` that starts its line is broken up for being
+                // too long, so that the `else` lines up with the `if`
+                let is_multiline = self.is_multiline()
+                    || (buf.column(indent) == indent as usize
+                        && !buf.fits_on_line(indent, |buf| {
+                            fmt_if(buf, branches, final_else, false, indent)
+                        }));
+
+                fmt_if(buf, branches, final_else, is_multiline, indent);
             }
             When(loc_condition, branches) => fmt_when(buf, loc_condition, branches, indent),
             List(items) => fmt_collection(buf, indent, Braces::Square, *items, Newlines::No),
@@ -398,6 +412,10 @@ impl<'a> Formattable for Expr<'a> {
             }
             MalformedIdent(_, _) => {}
             MalformedClosure => {}
+            Malformed(text, _) => {
+                buf.indent(indent);
+                buf.push_str_allow_spaces(text);
+            }
             PrecedenceConflict { .. } => {}
         }
     }
@@ -528,9 +546,22 @@ fn fmt_binops<'a, 'buf>(
     apply_needs_parens: Parens,
     indent: u16,
 ) {
+    // Only a chain that starts its line is broken up for being too long, so that the operators
+    // line up with the first operand.
     let is_multiline = part_of_multi_line_binops
         || (&loc_right_side.value).is_multiline()
-        || lefts.iter().any(|(expr, _)| expr.value.is_multiline());
+        || lefts.iter().any(|(expr, _)| expr.value.is_multiline())
+        || (buf.column(indent) == indent as usize
+            && !buf.fits_on_line(indent, |buf| {
+                fmt_binops(
+                    buf,
+                    lefts,
+                    loc_right_side,
+                    false,
+                    apply_needs_parens,
+                    indent,
+                )
+            }));
 
     for (loc_left_side, loc_binop) in lefts {
         let binop = loc_binop.value;
@@ -1041,6 +1072,19 @@ fn fmt_closure<'a, 'buf>(
         indent
     };
 
+    // A body that does not fit on the line goes on the next one.
+    if !is_multiline
+        && !buf.fits_on_line(indent, |buf| {
+            buf.spaces(1);
+            loc_ret.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, body_indent);
+        })
+    {
+        buf.newline();
+        loc_ret.format_with_options(buf, Parens::NotNeeded, Newlines::Yes, indent + INDENT);
+
+        return;
+    }
+
     // the body of the Closure can be on the same line, or
     // on a new line. If it's on the same line, insert a space.
 
@@ -1188,6 +1232,11 @@ fn fmt_record<'a, 'buf>(
 ) {
     let loc_fields = fields.items;
     let final_comments = fields.final_comments();
+
+    let is_multiline = loc_fields.iter().any(|loc_field| loc_field.is_multiline())
+        || !final_comments.is_empty()
+        || !buf.fits_on_line(indent, |buf| fmt_record(buf, update, fields, indent));
+
     buf.indent(indent);
     if loc_fields.is_empty() && final_comments.iter().all(|c| c.is_newline()) {
         buf.push_str("{}");
@@ -1207,9 +1256,6 @@ fn fmt_record<'a, 'buf>(
             }
         }
 
-        let is_multiline = loc_fields.iter().any(|loc_field| loc_field.is_multiline())
-            || !final_comments.is_empty();
-
         if is_multiline {
             let field_indent = indent + INDENT;
             for (index, field) in loc_fields.iter().enumerate() {
@@ -1378,6 +1424,9 @@ fn sub_expr_requests_parens(expr: &Expr<'_>) -> bool {
                 })
         }
         Expr::If(_, _) => true,
+        Expr::SpaceBefore(sub_expr, _) | Expr::SpaceAfter(sub_expr, _) => {
+            sub_expr_requests_parens(sub_expr)
+        }
         _ => false,
     }
 }
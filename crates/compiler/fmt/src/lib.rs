@@ -20,20 +20,43 @@ pub struct Ast<'a> {
 
 #[derive(Debug)]
 pub struct Buf<'a> {
+    arena: &'a Bump,
     text: String<'a>,
     spaces_to_flush: usize,
     beginning_of_line: bool,
+    /// Collections, applications, `|>` chains and function types that would make a line longer
+    /// than this are broken up over multiple lines. Without a maximum, code is only formatted
+    /// over multiple lines where the original code already had newlines.
+    max_width: Option<usize>,
 }
 
 impl<'a> Buf<'a> {
     pub fn new_in(arena: &'a Bump) -> Buf<'a> {
         Buf {
+            arena,
             text: String::new_in(arena),
             spaces_to_flush: 0,
             beginning_of_line: true,
+            max_width: None,
         }
     }
 
+    /// A buffer that breaks up code that would make a line longer than `max_width`.
+    ///
+    /// Breaking up a line can change the layout of the code around it, e.g. a body that no longer
+    /// fits moves to its own line. So the output only settles after formatting it again, until
+    /// it does not change anymore.
+    pub fn with_max_width(arena: &'a Bump, max_width: usize) -> Buf<'a> {
+        Buf {
+            max_width: Some(max_width),
+            ..Buf::new_in(arena)
+        }
+    }
+
+    pub fn max_width(&self) -> Option<usize> {
+        self.max_width
+    }
+
     pub fn as_str(&'a self) -> &'a str {
         self.text.as_str()
     }
@@ -142,6 +165,37 @@ impl<'a> Buf<'a> {
         self.spaces_to_flush == 0 && self.text.ends_with('\n')
     }
 
+    /// The column the next character goes in, if it is indented by `indent`
+    pub fn column(&self, indent: u16) -> usize {
+        if self.beginning_of_line {
+            indent as usize
+        } else {
+            let line_start = self.text.rfind('\n').map_or(0, |index| index + 1);
+
+            self.text[line_start..].chars().count() + self.spaces_to_flush
+        }
+    }
+
+    /// Whether the current line stays within the maximum width if `format` writes its code here.
+    /// Anything `format` writes on multiple lines is left alone; only single-line code is measured.
+    pub fn fits_on_line(&self, indent: u16, format: impl FnOnce(&mut Buf<'a>)) -> bool {
+        let max_width = match self.max_width {
+            Some(max_width) => max_width,
+            None => return true,
+        };
+
+        // measure the code without breaking anything up
+        let mut scratch = Buf::new_in(self.arena);
+        scratch.beginning_of_line = false;
+        format(&mut scratch);
+
+        if scratch.text.contains('\n') {
+            return true;
+        }
+
+        self.column(indent) + scratch.text.chars().count() <= max_width
+    }
+
     fn is_empty(&self) -> bool {
         self.spaces_to_flush == 0 && self.text.is_empty()
     }
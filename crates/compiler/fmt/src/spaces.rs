@@ -542,17 +542,17 @@ impl<'a> RemoveSpaces<'a> for ValueDef<'a> {
             },
             Expect {
                 condition,
-                preceding_comment,
+                preceding_comment: _,
             } => Expect {
                 condition: arena.alloc(condition.remove_spaces(arena)),
-                preceding_comment,
+                preceding_comment: Region::zero(),
             },
             ExpectFx {
                 condition,
-                preceding_comment,
+                preceding_comment: _,
             } => ExpectFx {
                 condition: arena.alloc(condition.remove_spaces(arena)),
-                preceding_comment,
+                preceding_comment: Region::zero(),
             },
         }
     }
@@ -703,6 +703,7 @@ impl<'a> RemoveSpaces<'a> for Expr<'a> {
             }
             Expr::MalformedIdent(a, b) => Expr::MalformedIdent(a, b),
             Expr::MalformedClosure => Expr::MalformedClosure,
+            Expr::Malformed(a, b) => Expr::Malformed(a, b),
             Expr::PrecedenceConflict(a) => Expr::PrecedenceConflict(a),
             Expr::SpaceBefore(a, _) => a.remove_spaces(arena),
             Expr::SpaceAfter(a, _) => a.remove_spaces(arena),
@@ -176,6 +176,102 @@ mod test_fmt {
         module_formats_to(input, input);
     }
 
+    fn expr_formats_at_width(max_width: usize, input: &str, expected: &str) {
+        let arena = Bump::new();
+        let input = input.trim();
+        let expected = expected.trim();
+
+        let format = |src: &str| -> String {
+            let ast = roc_parse::test_helpers::parse_expr_with(&arena, src).unwrap_or_else(|err| {
+                panic!(
+                    "After formatting, the source code no longer parsed!\n\nParse error was: {:?}\n\nThe code that failed to parse:\n\n{}\n\n",
+                    err, src
+                );
+            });
+
+            let mut buf = Buf::with_max_width(&arena, max_width);
+            ast.format_with_options(&mut buf, Parens::NotNeeded, Newlines::Yes, 0);
+
+            buf.as_str().to_string()
+        };
+
+        // breaking up a line can change the layout around it, so format until that settles
+        let mut output = format(input);
+        for _ in 0..8 {
+            let reformatted = format(&output);
+            if reformatted == output {
+                break;
+            }
+            output = reformatted;
+        }
+
+        assert_multiline_str_eq!(expected, output.as_str());
+
+        // breaking up lines must not change what the code means
+        expr_formats_same(expected);
+        assert_eq!(
+            output,
+            format(&output),
+            "formatting at a max width is not stable"
+        );
+    }
+
+    fn module_formats_at_width(max_width: usize, src: &str) {
+        use roc_fmt::spaces::RemoveSpaces;
+
+        let arena = Bump::new();
+
+        let normalized = |src: &str| -> String {
+            let (module, state) = module::parse_header(&arena, State::new(src.as_bytes()))
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "After formatting, the source code no longer parsed!\n\nParse error was: {:?}\n\nThe code that failed to parse:\n\n{}\n\n",
+                        err, src
+                    );
+                });
+            let (_, defs, _) = module_defs().parse(&arena, state).unwrap_or_else(|err| {
+                panic!(
+                    "After formatting, the source code no longer parsed!\n\nParse error was: {:?}\n\nThe code that failed to parse:\n\n{}\n\n",
+                    err, src
+                );
+            });
+
+            format!(
+                "{:?}\n{:?}",
+                module.remove_spaces(&arena),
+                defs.remove_spaces(&arena)
+            )
+        };
+
+        let format = |src: &str| -> String {
+            let (module, state) = module::parse_header(&arena, State::new(src.as_bytes())).unwrap();
+            let mut buf = Buf::with_max_width(&arena, max_width);
+
+            fmt_module_and_defs(&arena, src, &module, state, &mut buf);
+
+            buf.as_str().to_string()
+        };
+
+        let mut output = format(src);
+        for _ in 0..8 {
+            let reformatted = format(&output);
+            if reformatted == output {
+                break;
+            }
+            output = reformatted;
+        }
+
+        if normalized(src) != normalized(&output) {
+            panic!(
+                "Formatting bug; formatting at a max width of {} didn't reparse to the same AST (after removing spaces)\n\n\
+                * * * Source code after formatting:\n{}\n\n",
+                max_width, output
+            );
+        }
+
+        assert_multiline_str_eq!(output.as_str(), format(&output).as_str());
+    }
+
     // STRING LITERALS
 
     #[test]
@@ -5457,6 +5553,255 @@ mod test_fmt {
         ));
     }
 
+    #[test]
+    fn multiline_if_in_parens_def_body() {
+        expr_formats_to(
+            indoc!(
+                r#"
+                x = (
+                    if a then
+                        b
+                    else
+                        c
+                    )
+
+                x
+                "#
+            ),
+            indoc!(
+                r#"
+                x =
+                    (
+                        if a then
+                            b
+                        else
+                            c
+                    )
+
+                x
+                "#
+            ),
+        );
+    }
+
+    // MAX WIDTH
+
+    #[test]
+    fn max_width_leaves_short_lines_alone() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                x = [a, b, c]
+                y = { a: 1, b: 2 }
+
+                f x (y |> g) "short"
+                "#
+            ),
+            indoc!(
+                r#"
+                x = [a, b, c]
+                y = { a: 1, b: 2 }
+
+                f x (y |> g) "short"
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_list() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                x = [aaaaaaaaaa, bbbbbbbbbb, cccccccccc, dddddddddd]
+
+                x
+                "#
+            ),
+            indoc!(
+                r#"
+                x = [
+                    aaaaaaaaaa,
+                    bbbbbbbbbb,
+                    cccccccccc,
+                    dddddddddd,
+                ]
+
+                x
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_record() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                x = { name: "Roc", version: 1, license: "UPL-1.0" }
+
+                x
+                "#
+            ),
+            indoc!(
+                r#"
+                x = {
+                    name: "Roc",
+                    version: 1,
+                    license: "UPL-1.0",
+                }
+
+                x
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_apply() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                foo aaaaaaaaaaaa bbbbbbbbbbbb cccccccccccc
+                "#
+            ),
+            indoc!(
+                r#"
+                foo
+                    aaaaaaaaaaaa
+                    bbbbbbbbbbbb
+                    cccccccccccc
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_pipeline() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                x = list |> List.map inc |> List.keepIf isEven |> List.sum
+
+                x
+                "#
+            ),
+            indoc!(
+                r#"
+                x =
+                    list
+                    |> List.map inc
+                    |> List.keepIf isEven
+                    |> List.sum
+
+                x
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_if_def_body() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                x = if isEven count then "even" else "odd"
+
+                x
+                "#
+            ),
+            indoc!(
+                r#"
+                x =
+                    if isEven count then
+                        "even"
+                    else
+                        "odd"
+
+                x
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_closure_body() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                f = \list -> List.walk list 0 \state, elem -> state + elem
+
+                f
+                "#
+            ),
+            indoc!(
+                r#"
+                f = \list ->
+                    List.walk
+                        list
+                        0
+                        \state, elem -> state + elem
+
+                f
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn max_width_breaks_function_annotation() {
+        expr_formats_at_width(
+            40,
+            indoc!(
+                r#"
+                concat : List Str, Str, Str, Str, Str -> Str
+                concat = \strs, a, b, c, d -> a
+
+                concat
+                "#
+            ),
+            indoc!(
+                r#"
+                concat :
+                    List Str,
+                    Str,
+                    Str,
+                    Str,
+                    Str
+                    -> Str
+                concat = \strs, a, b, c, d -> a
+
+                concat
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    /// Breaking lines must not break the examples, no matter how narrow they get
+    fn test_fmt_examples_at_max_width() {
+        let mut root = workspace_root();
+        root.push("examples");
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry.unwrap();
+            let path = entry.path();
+            if path.extension() == Some(std::ffi::OsStr::new("roc")) {
+                let src = std::fs::read_to_string(path).unwrap();
+                for max_width in [40, 80, 120] {
+                    println!("Now trying to format {} at {}", path.display(), max_width);
+                    module_formats_at_width(max_width, &src);
+                }
+            }
+        }
+    }
+
     // this is a parse error atm
     //    #[test]
     //    fn multiline_apply() {
@@ -1,6 +1,6 @@
 use crate::{
-    single_register_floats, single_register_int_builtins, single_register_integers, Backend, Env,
-    Relocation,
+    single_register_floats, single_register_int_builtins, single_register_integers,
+    zero_extended_int_builtins, Backend, Env, Relocation,
 };
 use bumpalo::collections::Vec;
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
@@ -11,7 +11,8 @@ use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{
     BranchInfo, JoinPointId, ListLiteralElement, Literal, Param, ProcLayout, SelfRecursive, Stmt,
 };
-use roc_mono::layout::{Builtin, Layout, TagIdIntType, UnionLayout};
+use roc_mono::layout::{Builtin, Discriminant, Layout, TagIdIntType, UnionLayout};
+use roc_region::all::Region;
 use roc_target::TargetInfo;
 use std::marker::PhantomData;
 
@@ -143,6 +144,13 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    fn and_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
     fn call(buf: &mut Vec<'_, u8>, relocs: &mut Vec<'_, Relocation>, fn_name: String);
 
     /// Jumps by an offset of offset bytes unconditionally.
@@ -196,6 +204,13 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         offset: i32,
         src: GeneralReg,
     );
+    /// Stores the low 32 bits of `src` to `dst` + `offset`.
+    fn mov_mem32_offset32_reg32(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        offset: i32,
+        src: GeneralReg,
+    );
 
     /// Sign extends the data at `offset` with `size` as it copies it to `dst`
     /// size must be less than or equal to 8.
@@ -204,12 +219,45 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
     /// size must be less than or equal to 8.
     fn movzx_reg64_base32(buf: &mut Vec<'_, u8>, dst: GeneralReg, offset: i32, size: u8);
 
+    /// Sign extends the low `size` bytes of `src` as it copies them to `dst`
+    /// size must be less than or equal to 8.
+    fn movsx_reg64_reg64(buf: &mut Vec<'_, u8>, dst: GeneralReg, src: GeneralReg, size: u8);
+    /// Zero extends the low `size` bytes of `src` as it copies them to `dst`
+    /// size must be less than or equal to 8.
+    fn movzx_reg64_reg64(buf: &mut Vec<'_, u8>, dst: GeneralReg, src: GeneralReg, size: u8);
+
     fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: FloatReg, offset: i32);
     fn mov_reg64_stack32(buf: &mut Vec<'_, u8>, dst: GeneralReg, offset: i32);
     fn mov_stack32_freg64(buf: &mut Vec<'_, u8>, offset: i32, src: FloatReg);
     fn mov_stack32_reg64(buf: &mut Vec<'_, u8>, offset: i32, src: GeneralReg);
 
+    fn mul_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
+    fn mul_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
+
     fn neg_reg64_reg64(buf: &mut Vec<'_, u8>, dst: GeneralReg, src: GeneralReg);
+    fn div_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
+    fn div_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
+
     fn imul_reg64_reg64_reg64(
         buf: &mut Vec<'_, u8>,
         dst: GeneralReg,
@@ -217,6 +265,28 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    fn or_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
+    fn sqrt_freg32_freg32(buf: &mut Vec<'_, u8>, dst: FloatReg, src: FloatReg);
+    fn sqrt_freg64_freg64(buf: &mut Vec<'_, u8>, dst: FloatReg, src: FloatReg);
+
+    fn sub_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
+    fn sub_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: FloatReg,
+        src1: FloatReg,
+        src2: FloatReg,
+    );
     fn sub_reg64_reg64_imm32(buf: &mut Vec<'_, u8>, dst: GeneralReg, src1: GeneralReg, imm32: i32);
     fn sub_reg64_reg64_reg64(
         buf: &mut Vec<'_, u8>,
@@ -225,6 +295,13 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    fn xor_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
     fn eq_reg64_reg64_reg64(
         buf: &mut Vec<'_, u8>,
         dst: GeneralReg,
@@ -246,6 +323,13 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    fn gt_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
     fn to_float_freg32_reg64(buf: &mut Vec<'_, u8>, dst: FloatReg, src: GeneralReg);
 
     fn to_float_freg64_reg64(buf: &mut Vec<'_, u8>, dst: FloatReg, src: GeneralReg);
@@ -268,6 +352,35 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    // The `u` comparisons treat both registers as unsigned integers.
+    fn ult_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
+    fn ugt_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
+    fn ulte_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
+    fn ugte_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
     fn ret(buf: &mut Vec<'_, u8>);
 }
 
@@ -538,6 +651,8 @@ impl<
             single_register_integers!() => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 ASM::mov_reg64_reg64(&mut self.buf, dst_reg, CC::GENERAL_RETURN_REGS[0]);
+                // The calling convention leaves the upper bits of small return values undefined.
+                self.extend_int_reg(dst_reg, ret_layout);
             }
             single_register_floats!() => {
                 let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
@@ -622,6 +737,132 @@ impl<
         }
     }
 
+    fn build_expect(
+        &mut self,
+        condition: &Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        layouts: &'a [Layout<'a>],
+    ) {
+        // The failure branch calls into zig and then falls through to the rest of the procedure.
+        // Starting from a clean slate with everything on the stack means both paths agree on storage.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        let target_info = self.storage_manager.target_info();
+
+        let cond_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        match self.storage_manager.stack_offset_and_size(condition) {
+            (base_offset, 8) => ASM::mov_reg64_base32(&mut self.buf, cond_reg, base_offset),
+            (base_offset, size) => {
+                ASM::movzx_reg64_base32(&mut self.buf, cond_reg, base_offset, size as u8)
+            }
+        }
+
+        // Skip over the failure branch if the condition holds.
+        // Since we don't know the offset yet, set it to 0 and overwrite later.
+        let jne_location = self.buf.len();
+        let start_offset = ASM::jne_reg64_imm64_imm32(&mut self.buf, cond_reg, 0, 0);
+        self.free_symbol(&Symbol::DEV_TMP);
+
+        let base_storage = self.storage_manager.clone();
+
+        // Get the shared buffer. It starts with the number of failures and the offset of the next free byte.
+        // The offset of the next free byte is only updated once the whole failure has been written,
+        // so until then it is the offset of the start of this failure.
+        self.build_fn_call(
+            &Symbol::DEV_TMP,
+            bitcode::UTILS_EXPECT_FAILED_START.to_string(),
+            &[],
+            &[],
+            &Layout::u64(),
+        );
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let start_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        ASM::mov_reg64_mem64_offset32(&mut self.buf, start_reg, ptr_reg, 8);
+        let frame_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::add_reg64_reg64_reg64(&mut self.buf, frame_reg, ptr_reg, start_reg);
+        let tmp_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP5);
+
+        // Write the header: region start, region end, and module id, each as a u32.
+        let region_bits = ((region.end().offset as i64) << 32) | region.start().offset as i64;
+        ASM::mov_reg64_imm64(&mut self.buf, tmp_reg, region_bits);
+        ASM::mov_mem64_offset32_reg64(&mut self.buf, frame_reg, 0, tmp_reg);
+        let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
+        ASM::mov_reg64_imm64(&mut self.buf, tmp_reg, module_id as i64);
+        ASM::mov_mem32_offset32_reg32(&mut self.buf, frame_reg, 8, tmp_reg);
+        self.free_symbol(&Symbol::DEV_TMP4);
+        self.free_symbol(&Symbol::DEV_TMP5);
+
+        // After the header come the offsets of every looked up value, followed by the values themselves.
+        // DEV_TMP3 is the offset in the buffer where the next value will be written.
+        const HEADER_SIZE: i32 = 12;
+        ASM::add_reg64_reg64_imm32(
+            &mut self.buf,
+            start_reg,
+            start_reg,
+            HEADER_SIZE + 8 * lookups.len() as i32,
+        );
+        for (i, (sym, layout)) in lookups.iter().zip(layouts.iter()).enumerate() {
+            self.expect_write_offset(HEADER_SIZE + 8 * i as i32);
+            match layout {
+                Layout::Builtin(Builtin::Str) => {
+                    self.expect_clone_to(bitcode::STR_CLONE_TO, sym, layout, None);
+                }
+                Layout::Builtin(Builtin::List(elem_layout)) if elem_layout.safe_to_memcpy() => {
+                    let element_width = elem_layout.stack_size(target_info);
+                    self.expect_clone_to(bitcode::LIST_CLONE_TO, sym, layout, Some(element_width));
+                }
+                _ if layout.safe_to_memcpy() => self.expect_copy(sym, layout),
+                _ => self.expect_clone_shape(sym, layout),
+            }
+        }
+
+        // Update the number of failures and the next free offset.
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        ASM::mov_mem64_offset32_reg64(&mut self.buf, ptr_reg, 8, cursor_reg);
+        let tmp_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::mov_reg64_mem64_offset32(&mut self.buf, tmp_reg, ptr_reg, 0);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, tmp_reg, tmp_reg, 1);
+        ASM::mov_mem64_offset32_reg64(&mut self.buf, ptr_reg, 0, tmp_reg);
+
+        for sym in [Symbol::DEV_TMP, Symbol::DEV_TMP3, Symbol::DEV_TMP4] {
+            self.free_symbol(&sym);
+        }
+
+        // Overwrite the original jne with the correct offset.
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let end_offset = self.buf.len();
+        let jne_offset = end_offset - start_offset;
+        ASM::jne_reg64_imm64_imm32(&mut tmp, cond_reg, 0, jne_offset as i32);
+        for (i, byte) in tmp.iter().enumerate() {
+            self.buf[jne_location + i] = *byte;
+        }
+
+        // Update important storage information to avoid overwrites.
+        let stack_size = self.storage_manager.stack_size();
+        let fn_call_stack_size = self.storage_manager.fn_call_stack_size();
+        self.storage_manager = base_storage;
+        self.storage_manager.update_stack_size(stack_size);
+        self.storage_manager
+            .update_fn_call_stack_size(fn_call_stack_size);
+    }
+
     fn build_join(
         &mut self,
         id: &JoinPointId,
@@ -717,6 +958,7 @@ impl<
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::add_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                self.extend_int_reg(dst_reg, layout);
             }
             Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
                 let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
@@ -736,7 +978,7 @@ impl<
 
     fn build_num_mul(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>) {
         match layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src1_reg = self
                     .storage_manager
@@ -745,25 +987,71 @@ impl<
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::imul_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                self.extend_int_reg(dst_reg, layout);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::mul_freg64_freg64_freg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::mul_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
             x => todo!("NumMul: layout, {:?}", x),
         }
     }
 
+    fn build_num_div(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>) {
+        match layout {
+            Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::div_freg64_freg64_freg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::div_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => todo!("NumDiv: layout, {:?}", x),
+        }
+    }
+
     fn build_num_neg(&mut self, dst: &Symbol, src: &Symbol, layout: &Layout<'a>) {
         match layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
                 ASM::neg_reg64_reg64(&mut self.buf, dst_reg, src_reg);
+                self.extend_int_reg(dst_reg, layout);
             }
             x => todo!("NumNeg: layout, {:?}", x),
         }
     }
 
+    fn build_num_sqrt(&mut self, dst: &Symbol, src: &Symbol, layout: &Layout<'a>) {
+        let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+        let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
+        match layout {
+            Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                ASM::sqrt_freg64_freg64(&mut self.buf, dst_reg, src_reg);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                ASM::sqrt_freg32_freg32(&mut self.buf, dst_reg, src_reg);
+            }
+            x => todo!("NumSqrtUnchecked: layout, {:?}", x),
+        }
+    }
+
     fn build_num_sub(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>) {
         match layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src1_reg = self
                     .storage_manager
@@ -772,14 +1060,93 @@ impl<
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::sub_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                self.extend_int_reg(dst_reg, layout);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg64_freg64_freg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            Layout::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
             x => todo!("NumSub: layout, {:?}", x),
         }
     }
 
+    fn build_num_bitwise_and(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    ) {
+        match layout {
+            Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let src1_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src1);
+                let src2_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src2);
+                ASM::and_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => todo!("NumBitwiseAnd: layout, {:?}", x),
+        }
+    }
+
+    fn build_num_bitwise_or(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    ) {
+        match layout {
+            Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let src1_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src1);
+                let src2_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src2);
+                ASM::or_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => todo!("NumBitwiseOr: layout, {:?}", x),
+        }
+    }
+
+    fn build_num_bitwise_xor(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    ) {
+        match layout {
+            Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let src1_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src1);
+                let src2_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src2);
+                ASM::xor_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => todo!("NumBitwiseXor: layout, {:?}", x),
+        }
+    }
+
     fn build_eq(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, arg_layout: &Layout<'a>) {
         match arg_layout {
-            Layout::Builtin(single_register_int_builtins!()) => {
+            Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src1_reg = self
                     .storage_manager
@@ -795,7 +1162,7 @@ impl<
 
     fn build_neq(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, arg_layout: &Layout<'a>) {
         match arg_layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src1_reg = self
                     .storage_manager
@@ -817,7 +1184,7 @@ impl<
         arg_layout: &Layout<'a>,
     ) {
         match arg_layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(single_register_int_builtins!()) => {
                 let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
                 let src1_reg = self
                     .storage_manager
@@ -825,12 +1192,42 @@ impl<
                 let src2_reg = self
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
-                ASM::lt_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                if matches!(arg_layout, Layout::Builtin(zero_extended_int_builtins!())) {
+                    ASM::ult_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                } else {
+                    ASM::lt_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                }
             }
             x => todo!("NumLt: layout, {:?}", x),
         }
     }
 
+    fn build_num_gt(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        arg_layout: &Layout<'a>,
+    ) {
+        match arg_layout {
+            Layout::Builtin(single_register_int_builtins!()) => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+                let src1_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src1);
+                let src2_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, src2);
+                if matches!(arg_layout, Layout::Builtin(zero_extended_int_builtins!())) {
+                    ASM::ugt_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                } else {
+                    ASM::gt_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                }
+            }
+            x => todo!("NumGt: layout, {:?}", x),
+        }
+    }
+
     fn build_num_to_frac(
         &mut self,
         dst: &Symbol,
@@ -902,7 +1299,11 @@ impl<
                 let src2_reg = self
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
-                ASM::lte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                if matches!(arg_layout, Layout::Builtin(zero_extended_int_builtins!())) {
+                    ASM::ulte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                } else {
+                    ASM::lte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                }
             }
             x => todo!("NumLte: layout, {:?}", x),
         }
@@ -924,7 +1325,11 @@ impl<
                 let src2_reg = self
                     .storage_manager
                     .load_to_general_reg(&mut self.buf, src2);
-                ASM::gte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                if matches!(arg_layout, Layout::Builtin(zero_extended_int_builtins!())) {
+                    ASM::ugte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                } else {
+                    ASM::gte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+                }
             }
             x => todo!("NumGte: layout, {:?}", x),
         }
@@ -1099,6 +1504,39 @@ impl<
         ASM::add_reg64_reg64_imm32(&mut self.buf, dst_reg, CC::BASE_PTR_REG, offset);
     }
 
+    fn build_hash(&mut self, dst: &Symbol, value: &Symbol, seed: &Symbol, layout: &Layout<'a>) {
+        // Like the LLVM and wasm backends, the value is hashed from memory one number, string
+        // or list of numbers at a time, with the hash of the parts before it as the seed.
+        // The running hash lives on the stack in DEV_TMP and everything else is freed to the stack,
+        // so that the branches for the tags of a union all agree on storage.
+        let value_offset = if layout.stack_size(self.storage_manager.target_info()) == 0 {
+            0
+        } else {
+            self.storage_manager
+                .ensure_symbol_on_stack(&mut self.buf, value);
+            self.storage_manager.stack_offset_and_size(value).0
+        };
+
+        let seed_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, seed);
+        let hash_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        ASM::mov_reg64_reg64(&mut self.buf, hash_reg, seed_reg);
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+        let (hash_offset, _) = self.storage_manager.stack_offset_and_size(&Symbol::DEV_TMP);
+
+        self.hash_in_memory(layout, value_offset, hash_offset);
+
+        let hash_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+        ASM::mov_reg64_reg64(&mut self.buf, dst_reg, hash_reg);
+        self.free_symbol(&Symbol::DEV_TMP);
+    }
+
     fn create_empty_array(&mut self, sym: &Symbol) {
         let base_offset = self.storage_manager.claim_stack_area(sym, 24);
         self.storage_manager
@@ -1410,6 +1848,541 @@ impl<
             self.buf[jmp_location as usize + i] = *byte;
         }
     }
+
+    /// Integers narrower than 64 bits are always kept sign or zero extended to the full register,
+    /// because they are spilled and reloaded as 64 bit values.
+    /// 64 bit arithmetic can overflow into the upper bits, so this truncates a result back to
+    /// the width of its layout.
+    fn extend_int_reg(&mut self, reg: GeneralReg, layout: &Layout<'a>) {
+        match layout {
+            Layout::Builtin(Builtin::Int(
+                int_width @ (IntWidth::I8 | IntWidth::I16 | IntWidth::I32),
+            )) => {
+                ASM::movsx_reg64_reg64(&mut self.buf, reg, reg, int_width.stack_size() as u8);
+            }
+            Layout::Builtin(Builtin::Int(
+                int_width @ (IntWidth::U8 | IntWidth::U16 | IntWidth::U32),
+            )) => {
+                ASM::movzx_reg64_reg64(&mut self.buf, reg, reg, int_width.stack_size() as u8);
+            }
+            Layout::Builtin(Builtin::Bool) => {
+                ASM::movzx_reg64_reg64(&mut self.buf, reg, reg, 1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Hashes the value with the given layout that is on the stack at `base_offset`,
+    /// and updates the running hash on the stack at `hash_offset`. See `build_hash`.
+    fn hash_in_memory(&mut self, layout: &Layout<'a>, base_offset: i32, hash_offset: i32) {
+        let target_info = self.storage_manager.target_info();
+
+        match layout {
+            Layout::Builtin(
+                Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+            ) => {
+                let size = layout.stack_size(target_info);
+                let ptr_reg = self
+                    .storage_manager
+                    .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+                ASM::add_reg64_reg64_imm32(&mut self.buf, ptr_reg, CC::BASE_PTR_REG, base_offset);
+                let len_reg = self
+                    .storage_manager
+                    .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+                ASM::mov_reg64_imm64(&mut self.buf, len_reg, size as i64);
+
+                self.hash_call(
+                    bitcode::DICT_HASH,
+                    &[Symbol::DEV_TMP2, Symbol::DEV_TMP3],
+                    &[Layout::u64(), Layout::u64()],
+                    hash_offset,
+                );
+            }
+
+            Layout::Builtin(Builtin::Str) => {
+                // Zig takes the string by value, so it needs a symbol of its own.
+                let size = layout.stack_size(target_info) as i32;
+                let str_offset = self
+                    .storage_manager
+                    .claim_stack_area(&Symbol::DEV_TMP2, size as u32);
+                self.storage_manager
+                    .with_tmp_general_reg(&mut self.buf, |_storage_manager, buf, reg| {
+                        for copied in (0..size).step_by(8) {
+                            ASM::mov_reg64_base32(buf, reg, base_offset + copied);
+                            ASM::mov_base32_reg64(buf, str_offset + copied, reg);
+                        }
+                    });
+
+                self.hash_call(
+                    bitcode::DICT_HASH_STR,
+                    &[Symbol::DEV_TMP2],
+                    &[*layout],
+                    hash_offset,
+                );
+            }
+
+            Layout::Builtin(Builtin::List(
+                elem_layout @ Layout::Builtin(
+                    Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+                ),
+            )) => {
+                let elem_size = elem_layout.stack_size(target_info);
+                let ptr_reg = self
+                    .storage_manager
+                    .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+                ASM::mov_reg64_base32(
+                    &mut self.buf,
+                    ptr_reg,
+                    base_offset + 8 * Builtin::WRAPPER_PTR as i32,
+                );
+                let len_reg = self
+                    .storage_manager
+                    .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+                ASM::mov_reg64_base32(
+                    &mut self.buf,
+                    len_reg,
+                    base_offset + 8 * Builtin::WRAPPER_LEN as i32,
+                );
+                self.storage_manager
+                    .with_tmp_general_reg(&mut self.buf, |_storage_manager, buf, reg| {
+                        ASM::mov_reg64_imm64(buf, reg, elem_size as i64);
+                        ASM::imul_reg64_reg64_reg64(buf, len_reg, len_reg, reg);
+                    });
+
+                self.hash_call(
+                    bitcode::DICT_HASH,
+                    &[Symbol::DEV_TMP2, Symbol::DEV_TMP3],
+                    &[Layout::u64(), Layout::u64()],
+                    hash_offset,
+                );
+            }
+
+            Layout::Struct { field_layouts, .. } => {
+                self.hash_fields(field_layouts, base_offset, hash_offset);
+            }
+
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+                if tags.len() == 1 {
+                    self.hash_fields(tags[0], base_offset, hash_offset);
+                    return;
+                }
+
+                // Hash the tag id first, so that tags with the same payload hash differently
+                let id_offset = base_offset + union_layout.tag_id_offset(target_info).unwrap() as i32;
+                let (id_layout, id_size) = match union_layout.discriminant() {
+                    Discriminant::U16 => (Layout::Builtin(Builtin::Int(IntWidth::U16)), 2),
+                    _ => (Layout::Builtin(Builtin::Int(IntWidth::U8)), 1),
+                };
+                self.hash_in_memory(&id_layout, id_offset, hash_offset);
+
+                // Then the payload of the tag that the value has
+                for (tag_id, fields) in tags.iter().enumerate() {
+                    if fields.is_empty() {
+                        continue;
+                    }
+
+                    // Skip over the payload if the value has a different tag.
+                    // Since we don't know the offset yet, set it to 0 and overwrite later.
+                    let id_reg = self
+                        .storage_manager
+                        .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+                    ASM::movzx_reg64_base32(&mut self.buf, id_reg, id_offset, id_size);
+                    let jne_location = self.buf.len();
+                    let start_offset =
+                        ASM::jne_reg64_imm64_imm32(&mut self.buf, id_reg, tag_id as u64, 0);
+                    self.free_symbol(&Symbol::DEV_TMP2);
+
+                    let base_storage = self.storage_manager.clone();
+                    self.hash_fields(fields, base_offset, hash_offset);
+
+                    // Overwrite the original jne with the correct offset.
+                    let mut tmp = bumpalo::vec![in self.env.arena];
+                    let jne_offset = self.buf.len() - start_offset;
+                    ASM::jne_reg64_imm64_imm32(&mut tmp, id_reg, tag_id as u64, jne_offset as i32);
+                    for (i, byte) in tmp.iter().enumerate() {
+                        self.buf[jne_location + i] = *byte;
+                    }
+
+                    // Update important storage information to avoid overwrites.
+                    let stack_size = self.storage_manager.stack_size();
+                    let fn_call_stack_size = self.storage_manager.fn_call_stack_size();
+                    self.storage_manager = base_storage;
+                    self.storage_manager.update_stack_size(stack_size);
+                    self.storage_manager
+                        .update_fn_call_stack_size(fn_call_stack_size);
+                }
+            }
+
+            x => internal_error!(
+                "Hashing {:?} is not supported. Only numbers, strings, lists of numbers, and records and non-recursive tag unions of those can be hashed.",
+                x
+            ),
+        }
+    }
+
+    /// Hashes the fields of a record or tag payload that starts on the stack at `base_offset`, in order.
+    fn hash_fields(&mut self, field_layouts: &[Layout<'a>], base_offset: i32, hash_offset: i32) {
+        let mut field_offset = base_offset;
+        for field_layout in field_layouts.iter() {
+            self.hash_in_memory(field_layout, field_offset, hash_offset);
+            field_offset += field_layout.stack_size(self.storage_manager.target_info()) as i32;
+        }
+    }
+
+    /// Calls a zig hash function with the running hash (`DEV_TMP`) followed by `args`,
+    /// frees `args`, and stores the result as the new running hash.
+    fn hash_call(
+        &mut self,
+        fn_name: &str,
+        args: &[Symbol],
+        arg_layouts: &[Layout<'a>],
+        hash_offset: i32,
+    ) {
+        let mut call_args = bumpalo::vec![in self.env.arena; Symbol::DEV_TMP];
+        call_args.extend_from_slice(args);
+        let mut call_arg_layouts = bumpalo::vec![in self.env.arena; Layout::u64()];
+        call_arg_layouts.extend_from_slice(arg_layouts);
+
+        self.build_fn_call(
+            &Symbol::DEV_TMP4,
+            fn_name.to_string(),
+            &call_args,
+            &call_arg_layouts,
+            &Layout::u64(),
+        );
+        for arg in args {
+            self.free_symbol(arg);
+        }
+
+        let hash_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::mov_base32_reg64(&mut self.buf, hash_offset, hash_reg);
+        self.free_symbol(&Symbol::DEV_TMP4);
+    }
+
+    /// Records the current expect cursor (`DEV_TMP3`) in the offsets table of the failure being written.
+    /// `DEV_TMP` must hold the pointer to the shared expect buffer.
+    fn expect_write_offset(&mut self, table_offset: i32) {
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        let frame_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::mov_reg64_mem64_offset32(&mut self.buf, frame_reg, ptr_reg, 8);
+        ASM::add_reg64_reg64_reg64(&mut self.buf, frame_reg, ptr_reg, frame_reg);
+        // AArch64 can only encode store offsets that are a multiple of the store size.
+        ASM::add_reg64_reg64_imm32(&mut self.buf, frame_reg, frame_reg, table_offset);
+        ASM::mov_mem64_offset32_reg64(&mut self.buf, frame_reg, 0, cursor_reg);
+        self.free_symbol(&Symbol::DEV_TMP4);
+    }
+
+    /// Copies a value without any pointers to the expect cursor and moves the cursor past it.
+    fn expect_copy(&mut self, sym: &Symbol, layout: &Layout<'a>) {
+        let size = layout.stack_size(self.storage_manager.target_info()) as i32;
+        if size == 0 {
+            return;
+        }
+        let (base_offset, _) = self.storage_manager.stack_offset_and_size(sym);
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        let dst_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::add_reg64_reg64_reg64(&mut self.buf, dst_reg, ptr_reg, cursor_reg);
+        let tmp_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP5);
+        for copied in (0..size).step_by(8) {
+            ASM::mov_reg64_base32(&mut self.buf, tmp_reg, base_offset + copied);
+            ASM::mov_mem64_offset32_reg64(&mut self.buf, dst_reg, copied, tmp_reg);
+        }
+        ASM::add_reg64_reg64_imm32(&mut self.buf, cursor_reg, cursor_reg, size);
+        self.free_symbol(&Symbol::DEV_TMP4);
+        self.free_symbol(&Symbol::DEV_TMP5);
+    }
+
+    /// Clones a refcounted value to the expect cursor with one of the zig `clone_to` builtins.
+    /// The data it points to is written right after it, with pointers replaced by buffer offsets.
+    /// The builtin returns the offset just past everything it wrote, which becomes the new cursor.
+    fn expect_clone_to(
+        &mut self,
+        fn_name: &str,
+        sym: &Symbol,
+        layout: &Layout<'a>,
+        element_width: Option<u32>,
+    ) {
+        let size = layout.stack_size(self.storage_manager.target_info()) as i32;
+        let cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        let extra_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, extra_reg, cursor_reg, size);
+
+        let mut args = bumpalo::vec![in self.env.arena; *sym, Symbol::DEV_TMP, Symbol::DEV_TMP3, Symbol::DEV_TMP4];
+        let mut arg_layouts =
+            bumpalo::vec![in self.env.arena; *layout, Layout::u64(), Layout::u64(), Layout::u64()];
+        if let Some(element_width) = element_width {
+            let width_reg = self
+                .storage_manager
+                .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP5);
+            ASM::mov_reg64_imm64(&mut self.buf, width_reg, element_width as i64);
+            args.push(Symbol::DEV_TMP5);
+            arg_layouts.push(Layout::u64());
+        }
+        self.build_fn_call(
+            &Symbol::DEV_TMP2,
+            fn_name.to_string(),
+            &args,
+            &arg_layouts,
+            &Layout::u64(),
+        );
+        for tmp in [Symbol::DEV_TMP3, Symbol::DEV_TMP4, Symbol::DEV_TMP5] {
+            self.free_symbol(&tmp);
+        }
+
+        let new_cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+        let cursor_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        ASM::mov_reg64_reg64(&mut self.buf, cursor_reg, new_cursor_reg);
+        self.free_symbol(&Symbol::DEV_TMP2);
+    }
+
+    /// Clones a value of any layout to the expect cursor with the zig `expect_clone_to` builtin.
+    /// It walks a description of the layout, see `expect_shape`, which is written to the stack for the call.
+    fn expect_clone_shape(&mut self, sym: &Symbol, layout: &Layout<'a>) {
+        let target_info = self.storage_manager.target_info();
+
+        let mut shape = bumpalo::vec![in self.env.arena; layout.stack_size(target_info)];
+        expect_shape(&mut shape, layout, None, target_info);
+
+        // The shape is read as u32s, but written to the stack 8 bytes at a time.
+        if shape.len() % 2 != 0 {
+            shape.push(0);
+        }
+        let shape_offset = self
+            .storage_manager
+            .claim_stack_area(&Symbol::DEV_TMP5, 4 * shape.len() as u32);
+        let tmp_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+        for (i, words) in shape.chunks(2).enumerate() {
+            let bits = (words[1] as u64) << 32 | words[0] as u64;
+            ASM::mov_reg64_imm64(&mut self.buf, tmp_reg, bits as i64);
+            ASM::mov_base32_reg64(&mut self.buf, shape_offset + 8 * i as i32, tmp_reg);
+        }
+
+        let (value_offset, _) = self.storage_manager.stack_offset_and_size(sym);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, tmp_reg, CC::BASE_PTR_REG, value_offset);
+        let shape_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP6);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, shape_reg, CC::BASE_PTR_REG, shape_offset);
+
+        self.build_fn_call(
+            &Symbol::DEV_TMP4,
+            bitcode::UTILS_EXPECT_CLONE_TO.to_string(),
+            &[
+                Symbol::DEV_TMP2,
+                Symbol::DEV_TMP6,
+                Symbol::DEV_TMP,
+                Symbol::DEV_TMP3,
+            ],
+            &[Layout::u64(), Layout::u64(), Layout::u64(), Layout::u64()],
+            &Layout::u64(),
+        );
+        for tmp in [
+            Symbol::DEV_TMP2,
+            Symbol::DEV_TMP3,
+            Symbol::DEV_TMP5,
+            Symbol::DEV_TMP6,
+        ] {
+            self.free_symbol(&tmp);
+        }
+
+        let new_cursor_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        let cursor_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        ASM::mov_reg64_reg64(&mut self.buf, cursor_reg, new_cursor_reg);
+        self.free_symbol(&Symbol::DEV_TMP4);
+    }
+}
+
+// The shapes that the zig `expect_clone_to` builtin walks. They must be kept in sync with expect.zig.
+const SHAPE_COPY: u32 = 0;
+const SHAPE_STR: u32 = 1;
+const SHAPE_LIST: u32 = 2;
+const SHAPE_STRUCT: u32 = 3;
+const SHAPE_BOX: u32 = 4;
+const SHAPE_NON_RECURSIVE: u32 = 5;
+const SHAPE_RECURSIVE: u32 = 6;
+const SHAPE_NONE: u32 = u32::MAX;
+
+/// Appends the shape of `layout` for `expect_clone_to` and returns the index it starts at.
+/// `recursive` is the shape of the recursive union that a `RecursivePointer` points to.
+fn expect_shape<'a>(
+    shape: &mut Vec<'a, u32>,
+    layout: &Layout<'a>,
+    recursive: Option<u32>,
+    target_info: TargetInfo,
+) -> u32 {
+    let node = shape.len() as u32;
+
+    match layout {
+        _ if layout.safe_to_memcpy() => {
+            shape.extend([SHAPE_COPY, layout.stack_size(target_info)]);
+        }
+        Layout::Builtin(Builtin::Str) => shape.push(SHAPE_STR),
+        Layout::Builtin(Builtin::List(elem_layout)) => {
+            shape.extend([SHAPE_LIST, elem_layout.stack_size(target_info), SHAPE_NONE]);
+            shape[node as usize + 2] = expect_shape(shape, elem_layout, recursive, target_info);
+        }
+        Layout::Struct { field_layouts, .. } => {
+            return expect_struct_shape(shape, field_layouts, recursive, target_info);
+        }
+        Layout::Boxed(inner_layout) => {
+            shape.extend([SHAPE_BOX, inner_layout.stack_size(target_info), SHAPE_NONE]);
+            shape[node as usize + 2] = expect_shape(shape, inner_layout, recursive, target_info);
+        }
+        Layout::LambdaSet(lambda_set) => {
+            return expect_shape(
+                shape,
+                &lambda_set.runtime_representation(),
+                recursive,
+                target_info,
+            );
+        }
+        Layout::RecursivePointer => {
+            return recursive.unwrap_or_else(|| {
+                internal_error!("recursive pointer outside of a recursive union")
+            });
+        }
+        Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+            shape.extend([
+                SHAPE_NON_RECURSIVE,
+                layout.stack_size(target_info),
+                union_layout.tag_id_offset(target_info).unwrap(),
+                union_layout.tag_id_layout().stack_size(target_info),
+            ]);
+            let payloads_start = shape.len();
+            shape.extend(tags.iter().map(|_| SHAPE_NONE));
+            for (i, fields) in tags.iter().enumerate() {
+                shape[payloads_start + i] =
+                    expect_struct_shape(shape, fields, recursive, target_info);
+            }
+        }
+        Layout::Union(union_layout) => {
+            // The payload of every tag, by tag id. The nullable tag has no payload.
+            let mut tags = bumpalo::vec![in shape.bump()];
+            let implicit_tag_id = match union_layout {
+                UnionLayout::NonRecursive(_) => unreachable!(),
+                UnionLayout::Recursive(other_tags) => {
+                    tags.extend(other_tags.iter().map(|fields| Some(*fields)));
+                    0
+                }
+                UnionLayout::NonNullableUnwrapped(fields) => {
+                    tags.push(Some(*fields));
+                    0
+                }
+                UnionLayout::NullableWrapped {
+                    nullable_id,
+                    other_tags,
+                } => {
+                    tags.extend(other_tags.iter().map(|fields| Some(*fields)));
+                    tags.insert(*nullable_id as usize, None);
+                    0
+                }
+                UnionLayout::NullableUnwrapped {
+                    nullable_id,
+                    other_fields,
+                } => {
+                    tags.extend([None, None]);
+                    let other_id = !*nullable_id as usize;
+                    tags[other_id] = Some(*other_fields);
+                    other_id as u32
+                }
+            };
+
+            let tag_id_mask = if union_layout.stores_tag_id_in_pointer(target_info) {
+                UnionLayout::tag_id_pointer_bits_and_mask(target_info).1 as u32
+            } else {
+                0
+            };
+            let (tag_id_offset, tag_id_size) = if union_layout.stores_tag_id_as_data(target_info) {
+                (
+                    union_layout.tag_id_offset(target_info).unwrap(),
+                    union_layout.tag_id_layout().stack_size(target_info),
+                )
+            } else {
+                (0, 0)
+            };
+
+            shape.extend([
+                SHAPE_RECURSIVE,
+                union_layout.data_size_and_alignment(target_info).0,
+                tag_id_offset,
+                tag_id_size,
+                tag_id_mask,
+                implicit_tag_id,
+            ]);
+            let payloads_start = shape.len();
+            shape.extend(tags.iter().map(|_| SHAPE_NONE));
+            for (i, fields) in tags.iter().enumerate() {
+                if let Some(fields) = fields {
+                    shape[payloads_start + i] =
+                        expect_struct_shape(shape, fields, Some(node), target_info);
+                }
+            }
+        }
+        x => internal_error!("cannot clone {:?} for an expect", x),
+    }
+
+    node
+}
+
+fn expect_struct_shape<'a>(
+    shape: &mut Vec<'a, u32>,
+    field_layouts: &[Layout<'a>],
+    recursive: Option<u32>,
+    target_info: TargetInfo,
+) -> u32 {
+    let node = shape.len() as u32;
+
+    if field_layouts.iter().all(|field| field.safe_to_memcpy()) {
+        let size = field_layouts
+            .iter()
+            .map(|field| field.stack_size(target_info))
+            .sum();
+        shape.extend([SHAPE_COPY, size]);
+        return node;
+    }
+
+    shape.extend([SHAPE_STRUCT, field_layouts.len() as u32]);
+    let fields_start = shape.len();
+    for field in field_layouts.iter() {
+        shape.extend([field.stack_size(target_info), SHAPE_NONE]);
+    }
+    for (i, field) in field_layouts.iter().enumerate() {
+        shape[fields_start + 2 * i + 1] = expect_shape(shape, field, recursive, target_info);
+    }
+
+    node
 }
 
 #[macro_export]
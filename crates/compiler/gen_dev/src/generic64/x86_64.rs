@@ -7,7 +7,7 @@ use bumpalo::collections::Vec;
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
-use roc_mono::layout::{Builtin, Layout};
+use roc_mono::layout::{Builtin, Layout, UnionLayout};
 use roc_target::TargetInfo;
 
 const TARGET_INFO: TargetInfo = TargetInfo::default_x86_64();
@@ -252,7 +252,7 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
 
     #[inline(always)]
     fn load_args<'a>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         storage_manager: &mut StorageManager<
             'a,
             X86_64GeneralReg,
@@ -299,8 +299,37 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
                     storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
                     arg_offset += stack_size as i32;
                 }
-                x => {
-                    todo!("Loading args with layout {:?}", x);
+                _ => {
+                    // Small structs, unions, boxes and 128-bit numbers are passed in registers,
+                    // 8 bytes per register, if enough of them are left.
+                    let classes = Self::eightbyte_classes(layout);
+                    if Self::fits_in_param_regs(&classes, general_i, float_i) {
+                        let offset = storage_manager.claim_stack_area(sym, stack_size);
+                        for (i, class) in classes.iter().flatten().enumerate() {
+                            let eightbyte_offset = offset + 8 * i as i32;
+                            match class {
+                                EightbyteClass::Integer => {
+                                    X86_64Assembler::mov_base32_reg64(
+                                        buf,
+                                        eightbyte_offset,
+                                        Self::GENERAL_PARAM_REGS[general_i],
+                                    );
+                                    general_i += 1;
+                                }
+                                EightbyteClass::Sse => {
+                                    X86_64Assembler::mov_base32_freg64(
+                                        buf,
+                                        eightbyte_offset,
+                                        Self::FLOAT_PARAM_REGS[float_i],
+                                    );
+                                    float_i += 1;
+                                }
+                            }
+                        }
+                    } else {
+                        storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
+                        arg_offset += 8 * classes.iter().flatten().count() as i32;
+                    }
                 }
             }
         }
@@ -408,7 +437,47 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
                     tmp_stack_offset += size as i32;
                 }
                 x => {
-                    todo!("calling with arg type, {:?}", x);
+                    // Mirrors `load_args`: up to 16 bytes go in registers.
+                    let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
+                    debug_assert_eq!(base_offset % 8, 0);
+                    let classes = Self::eightbyte_classes(x);
+                    if Self::fits_in_param_regs(&classes, general_i, float_i) {
+                        for (i, class) in classes.iter().flatten().enumerate() {
+                            let eightbyte_offset = base_offset + 8 * i as i32;
+                            match class {
+                                EightbyteClass::Integer => {
+                                    X86_64Assembler::mov_reg64_base32(
+                                        buf,
+                                        Self::GENERAL_PARAM_REGS[general_i],
+                                        eightbyte_offset,
+                                    );
+                                    general_i += 1;
+                                }
+                                EightbyteClass::Sse => {
+                                    X86_64Assembler::mov_freg64_base32(
+                                        buf,
+                                        Self::FLOAT_PARAM_REGS[float_i],
+                                        eightbyte_offset,
+                                    );
+                                    float_i += 1;
+                                }
+                            }
+                        }
+                    } else {
+                        for i in (0..size as i32).step_by(8) {
+                            X86_64Assembler::mov_reg64_base32(
+                                buf,
+                                Self::GENERAL_RETURN_REGS[0],
+                                base_offset + i,
+                            );
+                            X86_64Assembler::mov_stack32_reg64(
+                                buf,
+                                tmp_stack_offset + i,
+                                Self::GENERAL_RETURN_REGS[0],
+                            );
+                        }
+                        tmp_stack_offset += 8 * classes.iter().flatten().count() as i32;
+                    }
                 }
             }
         }
@@ -433,29 +502,30 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
             }
             x if x.stack_size(TARGET_INFO) == 0 => {}
             x if !Self::returns_via_arg_pointer(x) => {
-                let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
+                let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
                 debug_assert_eq!(base_offset % 8, 0);
-                if size <= 8 {
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[0],
-                        base_offset,
-                    );
-                } else if size <= 16 {
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[0],
-                        base_offset,
-                    );
-                    X86_64Assembler::mov_reg64_base32(
-                        buf,
-                        Self::GENERAL_RETURN_REGS[1],
-                        base_offset + 8,
-                    );
-                } else {
-                    internal_error!(
-                        "types that don't return via arg pointer must be less than 16 bytes"
-                    );
+                let mut general_i = 0;
+                let mut float_i = 0;
+                for (i, class) in Self::eightbyte_classes(x).iter().flatten().enumerate() {
+                    let eightbyte_offset = base_offset + 8 * i as i32;
+                    match class {
+                        EightbyteClass::Integer => {
+                            X86_64Assembler::mov_reg64_base32(
+                                buf,
+                                Self::GENERAL_RETURN_REGS[general_i],
+                                eightbyte_offset,
+                            );
+                            general_i += 1;
+                        }
+                        EightbyteClass::Sse => {
+                            X86_64Assembler::mov_freg64_base32(
+                                buf,
+                                Self::FLOAT_RETURN_REGS[float_i],
+                                eightbyte_offset,
+                            );
+                            float_i += 1;
+                        }
+                    }
                 }
             }
             _ => {
@@ -491,19 +561,28 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
             x if !Self::returns_via_arg_pointer(x) => {
                 let size = layout.stack_size(TARGET_INFO);
                 let offset = storage_manager.claim_stack_area(sym, size);
-                if size <= 8 {
-                    X86_64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
-                } else if size <= 16 {
-                    X86_64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
-                    X86_64Assembler::mov_base32_reg64(
-                        buf,
-                        offset + 8,
-                        Self::GENERAL_RETURN_REGS[1],
-                    );
-                } else {
-                    internal_error!(
-                        "types that don't return via arg pointer must be less than 16 bytes"
-                    );
+                let mut general_i = 0;
+                let mut float_i = 0;
+                for (i, class) in Self::eightbyte_classes(x).iter().flatten().enumerate() {
+                    let eightbyte_offset = offset + 8 * i as i32;
+                    match class {
+                        EightbyteClass::Integer => {
+                            X86_64Assembler::mov_base32_reg64(
+                                buf,
+                                eightbyte_offset,
+                                Self::GENERAL_RETURN_REGS[general_i],
+                            );
+                            general_i += 1;
+                        }
+                        EightbyteClass::Sse => {
+                            X86_64Assembler::mov_base32_freg64(
+                                buf,
+                                eightbyte_offset,
+                                Self::FLOAT_RETURN_REGS[float_i],
+                            );
+                            float_i += 1;
+                        }
+                    }
                 }
             }
             _ => {
@@ -521,8 +600,98 @@ impl X86_64SystemV {
         // details here: https://github.com/hjl-tools/x86-psABI/wiki/x86-64-psABI-1.0.pdf
         ret_layout.stack_size(TARGET_INFO) > 16
     }
+
+    /// Classifies the eightbytes of a value of 1 to 16 bytes, the way C compilers do for structs.
+    ///
+    /// An eightbyte that only holds floats goes in a float register, any other eightbyte in a
+    /// general register. Zig builtins and the host follow the same rules, so we must too.
+    fn eightbyte_classes(layout: &Layout) -> Eightbytes {
+        let size = layout.stack_size(TARGET_INFO);
+        debug_assert!((1..=16).contains(&size));
+
+        let mut classes = [None; 2];
+        Self::classify_eightbytes(layout, 0, &mut classes);
+
+        // an eightbyte of only padding has no class; pass it in a general register
+        for class in classes.iter_mut().take(((size + 7) / 8) as usize) {
+            class.get_or_insert(EightbyteClass::Integer);
+        }
+
+        classes
+    }
+
+    fn classify_eightbytes(layout: &Layout, offset: u32, classes: &mut Eightbytes) {
+        match layout {
+            Layout::Builtin(Builtin::Float(FloatWidth::F32 | FloatWidth::F64)) => {
+                classes[(offset / 8) as usize].get_or_insert(EightbyteClass::Sse);
+            }
+            Layout::Struct { field_layouts, .. } => {
+                Self::classify_fields(field_layouts, offset, classes);
+            }
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+                for fields in tags.iter() {
+                    Self::classify_fields(fields, offset, classes);
+                }
+
+                // with a single tag, there is no tag id
+                let tag_id_offset = union_layout
+                    .tag_id_offset(TARGET_INFO)
+                    .filter(|_| tags.len() > 1);
+                if let Some(tag_id_offset) = tag_id_offset {
+                    classes[((offset + tag_id_offset) / 8) as usize] =
+                        Some(EightbyteClass::Integer);
+                }
+            }
+            Layout::LambdaSet(lambda_set) => {
+                Self::classify_eightbytes(&lambda_set.runtime_representation(), offset, classes);
+            }
+            _ => {
+                let size = layout.stack_size(TARGET_INFO);
+                if size > 0 {
+                    for class in
+                        &mut classes[(offset / 8) as usize..=((offset + size - 1) / 8) as usize]
+                    {
+                        *class = Some(EightbyteClass::Integer);
+                    }
+                }
+            }
+        }
+    }
+
+    fn classify_fields(fields: &[Layout], offset: u32, classes: &mut Eightbytes) {
+        let mut field_offset = offset;
+        for field in fields {
+            Self::classify_eightbytes(field, field_offset, classes);
+            field_offset += field.stack_size(TARGET_INFO);
+        }
+    }
+
+    /// Whether the registers that are left can hold all eightbytes of an argument.
+    /// If they can't, the whole argument goes on the stack.
+    fn fits_in_param_regs(classes: &Eightbytes, general_i: usize, float_i: usize) -> bool {
+        let needed = |wanted| {
+            classes
+                .iter()
+                .filter(|class| **class == Some(wanted))
+                .count()
+        };
+
+        general_i + needed(EightbyteClass::Integer) <= Self::GENERAL_PARAM_REGS.len()
+            && float_i + needed(EightbyteClass::Sse) <= Self::FLOAT_PARAM_REGS.len()
+    }
 }
 
+/// The kind of register that an eightbyte of a small argument or return value is passed in,
+/// as in the System V ABI.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EightbyteClass {
+    Integer,
+    Sse,
+}
+
+/// The classes of the eightbytes of a value of up to 16 bytes; `None` past its end
+type Eightbytes = [Option<EightbyteClass>; 2];
+
 impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64WindowsFastcall {
     const BASE_PTR_REG: X86_64GeneralReg = X86_64GeneralReg::RBP;
     const STACK_PTR_REG: X86_64GeneralReg = X86_64GeneralReg::RSP;
@@ -989,6 +1158,23 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         }
     }
 
+    #[inline(always)]
+    fn and_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        if dst == src1 {
+            and_reg64_reg64(buf, dst, src2);
+        } else if dst == src2 {
+            and_reg64_reg64(buf, dst, src1);
+        } else {
+            mov_reg64_reg64(buf, dst, src1);
+            and_reg64_reg64(buf, dst, src2);
+        }
+    }
+
     #[inline(always)]
     fn call(buf: &mut Vec<'_, u8>, relocs: &mut Vec<'_, Relocation>, fn_name: String) {
         buf.extend(&[0xE8, 0x00, 0x00, 0x00, 0x00]);
@@ -998,6 +1184,27 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         });
     }
 
+    #[inline(always)]
+    fn div_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        movss_freg32_freg32(buf, dst, src1);
+        divss_freg32_freg32(buf, dst, src2);
+    }
+    #[inline(always)]
+    fn div_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        movsd_freg64_freg64(buf, dst, src1);
+        divsd_freg64_freg64(buf, dst, src2);
+    }
+
     #[inline(always)]
     fn imul_reg64_reg64_reg64(
         buf: &mut Vec<'_, u8>,
@@ -1111,6 +1318,15 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
     ) {
         mov_base64_offset32_reg64(buf, dst, offset, src)
     }
+    #[inline(always)]
+    fn mov_mem32_offset32_reg32(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        offset: i32,
+        src: X86_64GeneralReg,
+    ) {
+        mov_base32_offset32_reg32(buf, dst, offset, src)
+    }
 
     #[inline(always)]
     fn movsx_reg64_base32(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, offset: i32, size: u8) {
@@ -1118,11 +1334,11 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         if size == 8 {
             Self::mov_reg64_base32(buf, dst, offset);
         } else if size == 4 {
-            todo!("sign extending 4 byte values");
+            movsxd_reg64_base32_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else if size == 2 {
-            todo!("sign extending 2 byte values");
+            movsx_reg64_base16_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else if size == 1 {
-            todo!("sign extending 1 byte values");
+            movsx_reg64_base8_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else {
             internal_error!("Invalid size for sign extension: {}", size);
         }
@@ -1133,9 +1349,9 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         if size == 8 {
             Self::mov_reg64_base32(buf, dst, offset);
         } else if size == 4 {
-            todo!("zero extending 4 byte values");
+            mov_reg32_base32_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else if size == 2 {
-            todo!("zero extending 2 byte values");
+            movzx_reg64_base16_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else if size == 1 {
             movzx_reg64_base8_offset32(buf, dst, X86_64GeneralReg::RBP, offset);
         } else {
@@ -1143,6 +1359,47 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         }
     }
 
+    #[inline(always)]
+    fn movsx_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src: X86_64GeneralReg,
+        size: u8,
+    ) {
+        debug_assert!(size <= 8);
+        if size == 8 {
+            mov_reg64_reg64(buf, dst, src);
+        } else if size == 4 {
+            movsxd_reg64_reg32(buf, dst, src);
+        } else if size == 2 {
+            movsx_reg64_reg16(buf, dst, src);
+        } else if size == 1 {
+            movsx_reg64_reg8(buf, dst, src);
+        } else {
+            internal_error!("Invalid size for sign extension: {}", size);
+        }
+    }
+    #[inline(always)]
+    fn movzx_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src: X86_64GeneralReg,
+        size: u8,
+    ) {
+        debug_assert!(size <= 8);
+        if size == 8 {
+            mov_reg64_reg64(buf, dst, src);
+        } else if size == 4 {
+            mov_reg32_reg32(buf, dst, src);
+        } else if size == 2 {
+            movzx_reg64_reg16(buf, dst, src);
+        } else if size == 1 {
+            movzx_reg64_reg8(buf, dst, src);
+        } else {
+            internal_error!("Invalid size for zero extension: {}", size);
+        }
+    }
+
     #[inline(always)]
     fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, offset: i32) {
         movsd_freg64_base64_offset32(buf, dst, X86_64GeneralReg::RSP, offset)
@@ -1160,12 +1417,71 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         mov_base64_offset32_reg64(buf, X86_64GeneralReg::RSP, offset, src)
     }
 
+    #[inline(always)]
+    fn mul_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        if dst == src1 {
+            mulss_freg32_freg32(buf, dst, src2);
+        } else if dst == src2 {
+            mulss_freg32_freg32(buf, dst, src1);
+        } else {
+            movss_freg32_freg32(buf, dst, src1);
+            mulss_freg32_freg32(buf, dst, src2);
+        }
+    }
+    #[inline(always)]
+    fn mul_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        if dst == src1 {
+            mulsd_freg64_freg64(buf, dst, src2);
+        } else if dst == src2 {
+            mulsd_freg64_freg64(buf, dst, src1);
+        } else {
+            movsd_freg64_freg64(buf, dst, src1);
+            mulsd_freg64_freg64(buf, dst, src2);
+        }
+    }
+
     #[inline(always)]
     fn neg_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
         mov_reg64_reg64(buf, dst, src);
         neg_reg64(buf, dst);
     }
 
+    #[inline(always)]
+    fn or_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        if dst == src1 {
+            or_reg64_reg64(buf, dst, src2);
+        } else if dst == src2 {
+            or_reg64_reg64(buf, dst, src1);
+        } else {
+            mov_reg64_reg64(buf, dst, src1);
+            or_reg64_reg64(buf, dst, src2);
+        }
+    }
+
+    #[inline(always)]
+    fn sqrt_freg32_freg32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+        sqrtss_freg32_freg32(buf, dst, src);
+    }
+    #[inline(always)]
+    fn sqrt_freg64_freg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+        sqrtsd_freg64_freg64(buf, dst, src);
+    }
+
     #[inline(always)]
     fn sub_reg64_reg64_imm32(
         buf: &mut Vec<'_, u8>,
@@ -1187,6 +1503,44 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         sub_reg64_reg64(buf, dst, src2);
     }
 
+    #[inline(always)]
+    fn sub_freg32_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        movss_freg32_freg32(buf, dst, src1);
+        subss_freg32_freg32(buf, dst, src2);
+    }
+    #[inline(always)]
+    fn sub_freg64_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64FloatReg,
+        src1: X86_64FloatReg,
+        src2: X86_64FloatReg,
+    ) {
+        movsd_freg64_freg64(buf, dst, src1);
+        subsd_freg64_freg64(buf, dst, src2);
+    }
+
+    #[inline(always)]
+    fn xor_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        if dst == src1 {
+            xor_reg64_reg64(buf, dst, src2);
+        } else if dst == src2 {
+            xor_reg64_reg64(buf, dst, src1);
+        } else {
+            mov_reg64_reg64(buf, dst, src1);
+            xor_reg64_reg64(buf, dst, src2);
+        }
+    }
+
     #[inline(always)]
     fn eq_reg64_reg64_reg64(
         buf: &mut Vec<'_, u8>,
@@ -1220,6 +1574,17 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         setl_reg64(buf, dst);
     }
 
+    #[inline(always)]
+    fn gt_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        setg_reg64(buf, dst);
+    }
+
     #[inline(always)]
     fn to_float_freg32_reg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64GeneralReg) {
         cvtsi2ss_freg64_reg64(buf, dst, src);
@@ -1262,6 +1627,50 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         setge_reg64(buf, dst);
     }
 
+    #[inline(always)]
+    fn ult_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        setb_reg64(buf, dst);
+    }
+
+    #[inline(always)]
+    fn ugt_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        seta_reg64(buf, dst);
+    }
+
+    #[inline(always)]
+    fn ulte_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        setbe_reg64(buf, dst);
+    }
+
+    #[inline(always)]
+    fn ugte_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        setae_reg64(buf, dst);
+    }
+
     #[inline(always)]
     fn ret(buf: &mut Vec<'_, u8>) {
         ret(buf);
@@ -1334,6 +1743,31 @@ fn extended_binop_reg64_reg64(
     buf.extend(&[rex, op_code1, op_code2, 0xC0 | dst_mod | src_mod]);
 }
 
+#[inline(always)]
+fn sse_binop_freg_freg(
+    prefix: u8,
+    op_code: u8,
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64FloatReg,
+    src: X86_64FloatReg,
+) {
+    let dst_high = dst as u8 > 7;
+    let dst_mod = dst as u8 % 8;
+    let src_high = src as u8 > 7;
+    let src_mod = src as u8 % 8;
+    if dst_high || src_high {
+        buf.extend(&[
+            prefix,
+            0x40 | ((dst_high as u8) << 2) | (src_high as u8),
+            0x0F,
+            op_code,
+            0xC0 | (dst_mod << 3) | (src_mod),
+        ])
+    } else {
+        buf.extend(&[prefix, 0x0F, op_code, 0xC0 | (dst_mod << 3) | (src_mod)])
+    }
+}
+
 // Below here are the functions for all of the assembly instructions.
 // Their names are based on the instruction and operators combined.
 // You should call `buf.reserve()` if you push or extend more than once.
@@ -1424,6 +1858,12 @@ fn and_reg64_imm8(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, imm: i8) {
     buf.extend(&[rex, 0x83, 0xE0 | dst_mod, imm as u8]);
 }
 
+/// `AND r/m64,r64` -> Bitwise logical and r64 to r/m64.
+#[inline(always)]
+fn and_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    binop_reg64_reg64(0x21, buf, dst, src);
+}
+
 /// `CMOVL r64,r/m64` -> Move if less (SF≠ OF).
 #[inline(always)]
 fn cmovl_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
@@ -1457,6 +1897,18 @@ fn test_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64Gen
     binop_reg64_reg64(0x85, buf, dst, src);
 }
 
+/// `DIVSD xmm1,xmm2/m64` -> Divide the low double-precision floating-point value in xmm1 by xmm2/m64.
+#[inline(always)]
+fn divsd_freg64_freg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF2, 0x5E, buf, dst, src);
+}
+
+/// `DIVSS xmm1,xmm2/m32` -> Divide the low single-precision floating-point value in xmm1 by xmm2/m32.
+#[inline(always)]
+fn divss_freg32_freg32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF3, 0x5E, buf, dst, src);
+}
+
 /// `IMUL r64,r/m64` -> Signed Multiply r/m64 to r64.
 #[inline(always)]
 fn imul_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
@@ -1524,20 +1976,44 @@ fn raw_mov_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64
 
 // The following base and stack based operations could be optimized based on how many bytes the offset actually is.
 
-/// `MOV r/m64,r64` -> Move r64 to r/m64, where m64 references a base + offset.
+/// `MOV r/m32,r32` -> Move r32 to r/m32, where m32 references a base + offset.
 #[inline(always)]
-fn mov_base64_offset32_reg64(
+fn mov_base32_offset32_reg32(
     buf: &mut Vec<'_, u8>,
     base: X86_64GeneralReg,
     offset: i32,
     src: X86_64GeneralReg,
 ) {
-    let rex = add_rm_extension(base, REX_W);
+    let rex = add_rm_extension(base, REX);
     let rex = add_reg_extension(src, rex);
     let src_mod = (src as u8 % 8) << 3;
     let base_mod = base as u8 % 8;
     buf.reserve(8);
-    buf.extend(&[rex, 0x89, 0x80 | src_mod | base_mod]);
+    if rex != REX {
+        buf.push(rex);
+    }
+    buf.extend(&[0x89, 0x80 | src_mod | base_mod]);
+    // Using RSP or R12 requires a secondary index byte.
+    if base == X86_64GeneralReg::RSP || base == X86_64GeneralReg::R12 {
+        buf.push(0x24);
+    }
+    buf.extend(&offset.to_le_bytes());
+}
+
+/// `MOV r/m64,r64` -> Move r64 to r/m64, where m64 references a base + offset.
+#[inline(always)]
+fn mov_base64_offset32_reg64(
+    buf: &mut Vec<'_, u8>,
+    base: X86_64GeneralReg,
+    offset: i32,
+    src: X86_64GeneralReg,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(src, rex);
+    let src_mod = (src as u8 % 8) << 3;
+    let base_mod = base as u8 % 8;
+    buf.reserve(8);
+    buf.extend(&[rex, 0x89, 0x80 | src_mod | base_mod]);
     // Using RSP or R12 requires a secondary index byte.
     if base == X86_64GeneralReg::RSP || base == X86_64GeneralReg::R12 {
         buf.push(0x24);
@@ -1587,6 +2063,160 @@ fn movzx_reg64_base8_offset32(
     buf.extend(&offset.to_le_bytes());
 }
 
+// helper function for `movsx`/`movzx` instructions that load from a base + offset.
+#[inline(always)]
+fn extend_reg64_base_offset32_help(
+    op_code: &[u8],
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    let dst_mod = (dst as u8 % 8) << 3;
+    let base_mod = base as u8 % 8;
+    buf.reserve(9);
+    buf.push(rex);
+    buf.extend(op_code);
+    buf.push(0x80 | dst_mod | base_mod);
+    // Using RSP or R12 requires a secondary index byte.
+    if base == X86_64GeneralReg::RSP || base == X86_64GeneralReg::R12 {
+        buf.push(0x24);
+    }
+    buf.extend(&offset.to_le_bytes());
+}
+
+/// `MOVZX r64,r/m16` -> Move r/m16 with zero extention to r64, where m16 references a base + offset.
+#[inline(always)]
+fn movzx_reg64_base16_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    extend_reg64_base_offset32_help(&[0x0F, 0xB7], buf, dst, base, offset);
+}
+
+/// `MOVSX r64,r/m8` -> Move r/m8 with sign extention to r64, where m8 references a base + offset.
+#[inline(always)]
+fn movsx_reg64_base8_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    extend_reg64_base_offset32_help(&[0x0F, 0xBE], buf, dst, base, offset);
+}
+
+/// `MOVSX r64,r/m16` -> Move r/m16 with sign extention to r64, where m16 references a base + offset.
+#[inline(always)]
+fn movsx_reg64_base16_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    extend_reg64_base_offset32_help(&[0x0F, 0xBF], buf, dst, base, offset);
+}
+
+/// `MOVSXD r64,r/m32` -> Move r/m32 with sign extention to r64, where m32 references a base + offset.
+#[inline(always)]
+fn movsxd_reg64_base32_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    extend_reg64_base_offset32_help(&[0x63], buf, dst, base, offset);
+}
+
+/// `MOV r32,r/m32` -> Move r/m32 to r32, zeroing the upper half of r64, where m32 references a base + offset.
+#[inline(always)]
+fn mov_reg32_base32_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX);
+    let rex = add_reg_extension(dst, rex);
+    let dst_mod = (dst as u8 % 8) << 3;
+    let base_mod = base as u8 % 8;
+    buf.reserve(8);
+    if rex != REX {
+        buf.push(rex);
+    }
+    buf.extend(&[0x8B, 0x80 | dst_mod | base_mod]);
+    // Using RSP or R12 requires a secondary index byte.
+    if base == X86_64GeneralReg::RSP || base == X86_64GeneralReg::R12 {
+        buf.push(0x24);
+    }
+    buf.extend(&offset.to_le_bytes());
+}
+
+// helper function for `movsx`/`movzx` instructions between registers.
+#[inline(always)]
+fn extend_reg64_reg_help(
+    op_code: &[u8],
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    src: X86_64GeneralReg,
+) {
+    let rex = add_rm_extension(src, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    let dst_mod = (dst as u8 % 8) << 3;
+    let src_mod = src as u8 % 8;
+    buf.reserve(4);
+    buf.push(rex);
+    buf.extend(op_code);
+    buf.push(0xC0 | dst_mod | src_mod);
+}
+
+/// `MOVZX r64,r/m8` -> Move r/m8 with zero extention to r64.
+#[inline(always)]
+fn movzx_reg64_reg8(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    extend_reg64_reg_help(&[0x0F, 0xB6], buf, dst, src);
+}
+
+/// `MOVZX r64,r/m16` -> Move r/m16 with zero extention to r64.
+#[inline(always)]
+fn movzx_reg64_reg16(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    extend_reg64_reg_help(&[0x0F, 0xB7], buf, dst, src);
+}
+
+/// `MOVSX r64,r/m8` -> Move r/m8 with sign extention to r64.
+#[inline(always)]
+fn movsx_reg64_reg8(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    extend_reg64_reg_help(&[0x0F, 0xBE], buf, dst, src);
+}
+
+/// `MOVSX r64,r/m16` -> Move r/m16 with sign extention to r64.
+#[inline(always)]
+fn movsx_reg64_reg16(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    extend_reg64_reg_help(&[0x0F, 0xBF], buf, dst, src);
+}
+
+/// `MOVSXD r64,r/m32` -> Move r/m32 with sign extention to r64.
+#[inline(always)]
+fn movsxd_reg64_reg32(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    extend_reg64_reg_help(&[0x63], buf, dst, src);
+}
+
+/// `MOV r32,r/m32` -> Move r/m32 to r32, zeroing the upper half of r64.
+#[inline(always)]
+fn mov_reg32_reg32(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    let rex = add_rm_extension(src, REX);
+    let rex = add_reg_extension(dst, rex);
+    let dst_mod = (dst as u8 % 8) << 3;
+    let src_mod = src as u8 % 8;
+    buf.reserve(3);
+    if rex != REX {
+        buf.push(rex);
+    }
+    buf.extend(&[0x8B, 0xC0 | dst_mod | src_mod]);
+}
+
 /// `MOVSD xmm1,xmm2` -> Move scalar double-precision floating-point value from xmm2 to xmm1 register.
 /// This will not generate anything if dst and src are the same.
 #[inline(always)]
@@ -1725,6 +2355,18 @@ fn movsd_freg64_base64_offset32(
     buf.extend(&offset.to_le_bytes());
 }
 
+/// `MULSD xmm1,xmm2/m64` -> Multiply the low double-precision floating-point value in xmm2/m64 by xmm1.
+#[inline(always)]
+fn mulsd_freg64_freg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF2, 0x59, buf, dst, src);
+}
+
+/// `MULSS xmm1,xmm2/m32` -> Multiply the low single-precision floating-point value in xmm2/m32 by xmm1.
+#[inline(always)]
+fn mulss_freg32_freg32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF3, 0x59, buf, dst, src);
+}
+
 /// `NEG r/m64` -> Two's complement negate r/m64.
 #[inline(always)]
 fn neg_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
@@ -1733,6 +2375,12 @@ fn neg_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
     buf.extend(&[rex, 0xF7, 0xD8 | reg_mod]);
 }
 
+/// `OR r/m64,r64` -> Bitwise logical or r64 to r/m64.
+#[inline(always)]
+fn or_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
+    binop_reg64_reg64(0x09, buf, dst, src);
+}
+
 // helper function for `set*` instructions
 #[inline(always)]
 fn set_reg64_help(op_code: u8, buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
@@ -1841,18 +2489,60 @@ fn setle_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
     set_reg64_help(0x9e, buf, reg);
 }
 
+/// `SETG r/m64` -> Set byte if greater (ZF=0 and SF=OF).
+#[inline(always)]
+fn setg_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
+    set_reg64_help(0x9f, buf, reg);
+}
+
 /// `SETGE r/m64` -> Set byte if greater or equal (SF=OF).
 #[inline(always)]
 fn setge_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
     set_reg64_help(0x9d, buf, reg);
 }
 
+/// `SETB r/m64` -> Set byte if below (CF=1).
+#[inline(always)]
+fn setb_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
+    set_reg64_help(0x92, buf, reg);
+}
+
+/// `SETBE r/m64` -> Set byte if below or equal (CF=1 or ZF=1).
+#[inline(always)]
+fn setbe_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
+    set_reg64_help(0x96, buf, reg);
+}
+
+/// `SETA r/m64` -> Set byte if above (CF=0 and ZF=0).
+#[inline(always)]
+fn seta_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
+    set_reg64_help(0x97, buf, reg);
+}
+
+/// `SETAE r/m64` -> Set byte if above or equal (CF=0).
+#[inline(always)]
+fn setae_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
+    set_reg64_help(0x93, buf, reg);
+}
+
 /// `RET` -> Near return to calling procedure.
 #[inline(always)]
 fn ret(buf: &mut Vec<'_, u8>) {
     buf.push(0xC3);
 }
 
+/// `SQRTSD xmm1,xmm2/m64` -> Computes square root of the low double-precision floating-point value in xmm2/m64 and stores the result in xmm1.
+#[inline(always)]
+fn sqrtsd_freg64_freg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF2, 0x51, buf, dst, src);
+}
+
+/// `SQRTSS xmm1,xmm2/m32` -> Computes square root of the low single-precision floating-point value in xmm2/m32 and stores the result in xmm1.
+#[inline(always)]
+fn sqrtss_freg32_freg32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF3, 0x51, buf, dst, src);
+}
+
 /// `SUB r/m64, imm32` -> Subtract imm32 sign-extended to 64-bits from r/m64.
 #[inline(always)]
 fn sub_reg64_imm32(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, imm: i32) {
@@ -1870,6 +2560,18 @@ fn sub_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64Gene
     binop_reg64_reg64(0x29, buf, dst, src);
 }
 
+/// `SUBSD xmm1,xmm2/m64` -> Subtract the low double-precision floating-point value in xmm2/m64 from xmm1.
+#[inline(always)]
+fn subsd_freg64_freg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF2, 0x5C, buf, dst, src);
+}
+
+/// `SUBSS xmm1,xmm2/m32` -> Subtract the low single-precision floating-point value in xmm2/m32 from xmm1.
+#[inline(always)]
+fn subss_freg32_freg32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64FloatReg) {
+    sse_binop_freg_freg(0xF3, 0x5C, buf, dst, src);
+}
+
 /// `POP r64` -> Pop top of stack into r64; increment stack pointer. Cannot encode 32-bit operand size.
 #[inline(always)]
 fn pop_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
@@ -1896,7 +2598,6 @@ fn push_reg64(buf: &mut Vec<'_, u8>, reg: X86_64GeneralReg) {
 
 /// `XOR r/m64,r64` -> Xor r64 to r/m64.
 #[inline(always)]
-#[allow(dead_code)]
 fn xor_reg64_reg64(buf: &mut Vec<'_, u8>, dst: X86_64GeneralReg, src: X86_64GeneralReg) {
     binop_reg64_reg64(0x31, buf, dst, src);
 }
@@ -1931,6 +2632,50 @@ mod tests {
                 X86_64GeneralReg::R15 => "r15b",
             }
         }
+
+        #[allow(dead_code)]
+        fn low_16bits_string(&self) -> &str {
+            match self {
+                X86_64GeneralReg::RAX => "ax",
+                X86_64GeneralReg::RBX => "bx",
+                X86_64GeneralReg::RCX => "cx",
+                X86_64GeneralReg::RDX => "dx",
+                X86_64GeneralReg::RBP => "bp",
+                X86_64GeneralReg::RSP => "sp",
+                X86_64GeneralReg::RDI => "di",
+                X86_64GeneralReg::RSI => "si",
+                X86_64GeneralReg::R8 => "r8w",
+                X86_64GeneralReg::R9 => "r9w",
+                X86_64GeneralReg::R10 => "r10w",
+                X86_64GeneralReg::R11 => "r11w",
+                X86_64GeneralReg::R12 => "r12w",
+                X86_64GeneralReg::R13 => "r13w",
+                X86_64GeneralReg::R14 => "r14w",
+                X86_64GeneralReg::R15 => "r15w",
+            }
+        }
+
+        #[allow(dead_code)]
+        fn low_32bits_string(&self) -> &str {
+            match self {
+                X86_64GeneralReg::RAX => "eax",
+                X86_64GeneralReg::RBX => "ebx",
+                X86_64GeneralReg::RCX => "ecx",
+                X86_64GeneralReg::RDX => "edx",
+                X86_64GeneralReg::RBP => "ebp",
+                X86_64GeneralReg::RSP => "esp",
+                X86_64GeneralReg::RDI => "edi",
+                X86_64GeneralReg::RSI => "esi",
+                X86_64GeneralReg::R8 => "r8d",
+                X86_64GeneralReg::R9 => "r9d",
+                X86_64GeneralReg::R10 => "r10d",
+                X86_64GeneralReg::R11 => "r11d",
+                X86_64GeneralReg::R12 => "r12d",
+                X86_64GeneralReg::R13 => "r13d",
+                X86_64GeneralReg::R14 => "r14d",
+                X86_64GeneralReg::R15 => "r15d",
+            }
+        }
     }
     const TEST_I32: i32 = 0x12345678;
     const TEST_I64: i64 = 0x1234_5678_9ABC_DEF0;
@@ -2046,6 +2791,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_and_reg64_reg64() {
+        disassembler_test!(
+            and_reg64_reg64,
+            |reg1, reg2| format!("and {}, {}", reg1, reg2),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_or_reg64_reg64() {
+        disassembler_test!(
+            or_reg64_reg64,
+            |reg1, reg2| format!("or {}, {}", reg1, reg2),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_divsd_freg64_freg64() {
+        disassembler_test!(
+            divsd_freg64_freg64,
+            |reg1, reg2| format!("divsd {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_divss_freg32_freg32() {
+        disassembler_test!(
+            divss_freg32_freg32,
+            |reg1, reg2| format!("divss {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_mulsd_freg64_freg64() {
+        disassembler_test!(
+            mulsd_freg64_freg64,
+            |reg1, reg2| format!("mulsd {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_mulss_freg32_freg32() {
+        disassembler_test!(
+            mulss_freg32_freg32,
+            |reg1, reg2| format!("mulss {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_subsd_freg64_freg64() {
+        disassembler_test!(
+            subsd_freg64_freg64,
+            |reg1, reg2| format!("subsd {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_subss_freg32_freg32() {
+        disassembler_test!(
+            subss_freg32_freg32,
+            |reg1, reg2| format!("subss {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_sqrtsd_freg64_freg64() {
+        disassembler_test!(
+            sqrtsd_freg64_freg64,
+            |reg1, reg2| format!("sqrtsd {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_sqrtss_freg32_freg32() {
+        disassembler_test!(
+            sqrtss_freg32_freg32,
+            |reg1, reg2| format!("sqrtss {}, {}", reg1, reg2),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
     #[test]
     fn test_xor_reg64_reg64() {
         disassembler_test!(
@@ -2175,6 +3020,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mov_base32_offset32_reg32() {
+        disassembler_test!(
+            mov_base32_offset32_reg32,
+            |reg1, imm, reg2: X86_64GeneralReg| format!(
+                "mov dword ptr [{} + 0x{:x}], {}",
+                reg1,
+                imm,
+                reg2.low_32bits_string()
+            ),
+            ALL_GENERAL_REGS,
+            [TEST_I32],
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_mov_base64_offset32_reg64() {
         disassembler_test!(
@@ -2197,6 +3058,130 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_movzx_reg64_base16_offset32() {
+        disassembler_test!(
+            movzx_reg64_base16_offset32,
+            |reg1, reg2, imm| format!("movzx {}, word ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_movsx_reg64_base8_offset32() {
+        disassembler_test!(
+            movsx_reg64_base8_offset32,
+            |reg1, reg2, imm| format!("movsx {}, byte ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_movsx_reg64_base16_offset32() {
+        disassembler_test!(
+            movsx_reg64_base16_offset32,
+            |reg1, reg2, imm| format!("movsx {}, word ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_movsxd_reg64_base32_offset32() {
+        disassembler_test!(
+            movsxd_reg64_base32_offset32,
+            |reg1, reg2, imm| format!("movsxd {}, dword ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_mov_reg32_base32_offset32() {
+        disassembler_test!(
+            mov_reg32_base32_offset32,
+            |reg1: X86_64GeneralReg, reg2, imm| format!(
+                "mov {}, dword ptr [{} + 0x{:x}]",
+                reg1.low_32bits_string(),
+                reg2,
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_movzx_reg64_reg8() {
+        disassembler_test!(
+            movzx_reg64_reg8,
+            |reg1, reg2: X86_64GeneralReg| format!("movzx {}, {}", reg1, reg2.low_8bits_string()),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_movzx_reg64_reg16() {
+        disassembler_test!(
+            movzx_reg64_reg16,
+            |reg1, reg2: X86_64GeneralReg| format!("movzx {}, {}", reg1, reg2.low_16bits_string()),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_movsx_reg64_reg8() {
+        disassembler_test!(
+            movsx_reg64_reg8,
+            |reg1, reg2: X86_64GeneralReg| format!("movsx {}, {}", reg1, reg2.low_8bits_string()),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_movsx_reg64_reg16() {
+        disassembler_test!(
+            movsx_reg64_reg16,
+            |reg1, reg2: X86_64GeneralReg| format!("movsx {}, {}", reg1, reg2.low_16bits_string()),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_movsxd_reg64_reg32() {
+        disassembler_test!(
+            movsxd_reg64_reg32,
+            |reg1, reg2: X86_64GeneralReg| format!("movsxd {}, {}", reg1, reg2.low_32bits_string()),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_mov_reg32_reg32() {
+        disassembler_test!(
+            mov_reg32_reg32,
+            |reg1: X86_64GeneralReg, reg2: X86_64GeneralReg| format!(
+                "mov {}, {}",
+                reg1.low_32bits_string(),
+                reg2.low_32bits_string()
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_movsd_freg64_freg64() {
         disassembler_test!(
@@ -2304,4 +3289,56 @@ mod tests {
     fn test_push_reg64() {
         disassembler_test!(push_reg64, |reg| format!("push {}", reg), ALL_GENERAL_REGS);
     }
+
+    #[test]
+    fn test_eightbyte_classes() {
+        use EightbyteClass::*;
+
+        let f32 = Layout::Builtin(Builtin::Float(FloatWidth::F32));
+        let f64 = Layout::Builtin(Builtin::Float(FloatWidth::F64));
+        let i64 = Layout::Builtin(Builtin::Int(IntWidth::I64));
+        let u8 = Layout::Builtin(Builtin::Int(IntWidth::U8));
+
+        fn classes(layout: Layout) -> Eightbytes {
+            X86_64SystemV::eightbyte_classes(&layout)
+        }
+
+        // floats go in float registers, like in C
+        assert_eq!(
+            classes(Layout::struct_no_name_order(&[f64, f64])),
+            [Some(Sse), Some(Sse)]
+        );
+        assert_eq!(
+            classes(Layout::struct_no_name_order(&[f32, f32])),
+            [Some(Sse), None]
+        );
+        assert_eq!(
+            classes(Layout::struct_no_name_order(&[f64, i64])),
+            [Some(Sse), Some(Integer)]
+        );
+
+        // an eightbyte with a float and an integer goes in a general register
+        assert_eq!(
+            classes(Layout::struct_no_name_order(&[f64, f32, u8])),
+            [Some(Sse), Some(Integer)]
+        );
+
+        // the tag id of a union is an integer
+        let tags: &[&[Layout]] = &[&[f64], &[f32]];
+        assert_eq!(
+            classes(Layout::Union(UnionLayout::NonRecursive(tags))),
+            [Some(Sse), Some(Integer)]
+        );
+
+        let tags: &[&[Layout]] = &[&[f64, f64]];
+        assert_eq!(
+            classes(Layout::Union(UnionLayout::NonRecursive(tags))),
+            [Some(Sse), Some(Sse)]
+        );
+
+        assert_eq!(
+            classes(Layout::Builtin(Builtin::Int(IntWidth::I128))),
+            [Some(Integer), Some(Integer)]
+        );
+    }
 }
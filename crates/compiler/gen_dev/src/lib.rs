@@ -11,10 +11,11 @@ use roc_module::low_level::{LowLevel, LowLevelWrapperType};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{
-    BranchInfo, CallType, Expr, JoinPointId, ListLiteralElement, Literal, Param, Proc, ProcLayout,
-    SelfRecursive, Stmt,
+    BranchInfo, Call, CallType, Expr, JoinPointId, ListLiteralElement, Literal, Param, Proc,
+    ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{Builtin, Layout, LayoutId, LayoutIds, TagIdIntType, UnionLayout};
+use roc_region::all::Region;
 
 mod generic64;
 mod object_builder;
@@ -27,6 +28,25 @@ pub struct Env<'a> {
     pub exposed_to_host: MutSet<Symbol>,
     pub lazy_literals: bool,
     pub generate_allocators: bool,
+    pub mode: AssemblyBackendMode,
+}
+
+/// What the generated code is going to be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssemblyBackendMode {
+    /// Code for a regular application; expectations are skipped.
+    Binary,
+    /// Code for `roc test`; failed expectations are written to the shared buffer.
+    Test,
+}
+
+impl AssemblyBackendMode {
+    fn runs_expects(&self) -> bool {
+        match self {
+            AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::Test => true,
+        }
+    }
 }
 
 // These relocations likely will need a length.
@@ -130,6 +150,34 @@ trait Backend<'a> {
     /// build_stmt builds a statement and outputs at the end of the buffer.
     fn build_stmt(&mut self, stmt: &Stmt<'a>, ret_layout: &Layout<'a>) {
         match stmt {
+            Stmt::Let(
+                sym,
+                Expr::Call(Call {
+                    call_type:
+                        CallType::LowLevel {
+                            op: LowLevel::RefCountIsUnique,
+                            ..
+                        },
+                    arguments,
+                }),
+                _,
+                following,
+            ) => {
+                // Checking uniqueness needs the refcount pointer of the argument,
+                // so it is expanded into more detailed IR like a Refcounting statement.
+                let structure = arguments[0];
+                let layout = *self.layout_map().get(&structure).unwrap();
+
+                let is_unique_stmt = {
+                    let (env, interns, rc_proc_gen) = self.env_interns_helpers_mut();
+                    let module_id = env.module_id;
+                    let ident_ids = interns.all_ident_ids.get_mut(&module_id).unwrap();
+
+                    rc_proc_gen.expand_is_unique(ident_ids, layout, structure, *sym, following)
+                };
+
+                self.build_stmt(is_unique_stmt, ret_layout)
+            }
             Stmt::Let(sym, expr, layout, following) => {
                 self.build_expr(sym, expr, layout);
                 self.set_layout_map(*sym, layout);
@@ -206,6 +254,21 @@ trait Backend<'a> {
                 self.build_jump(id, args, arg_layouts.into_bump_slice(), ret_layout);
                 self.free_symbols(stmt);
             }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                layouts,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*condition]);
+                    self.load_literal_symbols(lookups);
+                    self.build_expect(condition, *region, lookups, layouts);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(remainder, ret_layout);
+            }
             x => todo!("the statement, {:?}", x),
         }
     }
@@ -219,6 +282,16 @@ trait Backend<'a> {
         ret_layout: &Layout<'a>,
     );
 
+    /// build_expect checks the condition of an expect and, if it does not hold,
+    /// writes the region, module and looked up values into the shared buffer.
+    fn build_expect(
+        &mut self,
+        condition: &Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        layouts: &'a [Layout<'a>],
+    );
+
     // build_join generates a instructions for a join statement.
     fn build_join(
         &mut self,
@@ -389,7 +462,7 @@ trait Backend<'a> {
                 );
                 self.build_num_abs(sym, &args[0], ret_layout)
             }
-            LowLevel::NumAdd => {
+            LowLevel::NumAdd | LowLevel::NumAddWrap => {
                 debug_assert_eq!(
                     2,
                     args.len(),
@@ -426,7 +499,7 @@ trait Backend<'a> {
                 arg_layouts,
                 ret_layout,
             ),
-            LowLevel::NumMul => {
+            LowLevel::NumMul | LowLevel::NumMulWrap => {
                 debug_assert_eq!(
                     2,
                     args.len(),
@@ -442,6 +515,22 @@ trait Backend<'a> {
                 );
                 self.build_num_mul(sym, &args[0], &args[1], ret_layout)
             }
+            LowLevel::NumDivUnchecked => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumDivUnchecked: expected to have exactly two argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], arg_layouts[1],
+                    "NumDivUnchecked: expected all arguments of to have the same layout"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], *ret_layout,
+                    "NumDivUnchecked: expected to have the same argument and return layout"
+                );
+                self.build_num_div(sym, &args[0], &args[1], ret_layout)
+            }
             LowLevel::NumNeg => {
                 debug_assert_eq!(
                     1,
@@ -461,7 +550,38 @@ trait Backend<'a> {
                 arg_layouts,
                 ret_layout,
             ),
-            LowLevel::NumSub => {
+            LowLevel::NumSqrtUnchecked => {
+                debug_assert_eq!(
+                    1,
+                    args.len(),
+                    "NumSqrtUnchecked: expected to have exactly one argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], *ret_layout,
+                    "NumSqrtUnchecked: expected to have the same argument and return layout"
+                );
+                self.build_num_sqrt(sym, &args[0], ret_layout)
+            }
+            LowLevel::NumSin | LowLevel::NumCos | LowLevel::NumLogUnchecked | LowLevel::NumPow => {
+                let intrinsic = match lowlevel {
+                    LowLevel::NumSin => &bitcode::NUM_SIN,
+                    LowLevel::NumCos => &bitcode::NUM_COS,
+                    LowLevel::NumLogUnchecked => &bitcode::NUM_LOG,
+                    LowLevel::NumPow => &bitcode::NUM_POW,
+                    _ => unreachable!(),
+                };
+                match ret_layout {
+                    Layout::Builtin(Builtin::Float(float_width)) => self.build_fn_call(
+                        sym,
+                        intrinsic[*float_width].to_string(),
+                        args,
+                        arg_layouts,
+                        ret_layout,
+                    ),
+                    x => todo!("{:?}: layout, {:?}", lowlevel, x),
+                }
+            }
+            LowLevel::NumSub | LowLevel::NumSubWrap => {
                 debug_assert_eq!(
                     2,
                     args.len(),
@@ -477,6 +597,54 @@ trait Backend<'a> {
                 );
                 self.build_num_sub(sym, &args[0], &args[1], ret_layout)
             }
+            LowLevel::NumBitwiseAnd | LowLevel::And => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumBitwiseAnd: expected to have exactly two argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], arg_layouts[1],
+                    "NumBitwiseAnd: expected all arguments of to have the same layout"
+                );
+                self.build_num_bitwise_and(sym, &args[0], &args[1], ret_layout)
+            }
+            LowLevel::NumBitwiseOr | LowLevel::Or => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumBitwiseOr: expected to have exactly two argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], arg_layouts[1],
+                    "NumBitwiseOr: expected all arguments of to have the same layout"
+                );
+                self.build_num_bitwise_or(sym, &args[0], &args[1], ret_layout)
+            }
+            LowLevel::NumBitwiseXor => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumBitwiseXor: expected to have exactly two argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], arg_layouts[1],
+                    "NumBitwiseXor: expected all arguments of to have the same layout"
+                );
+                self.build_num_bitwise_xor(sym, &args[0], &args[1], ret_layout)
+            }
+            LowLevel::Not => {
+                debug_assert_eq!(1, args.len(), "Not: expected to have exactly one argument");
+                debug_assert_eq!(
+                    Layout::Builtin(Builtin::Bool),
+                    *ret_layout,
+                    "Not: expected to have return layout of type Bool"
+                );
+
+                self.load_literal(&Symbol::DEV_TMP, ret_layout, &Literal::Bool(false));
+                self.build_eq(sym, &args[0], &Symbol::DEV_TMP, ret_layout);
+                self.free_symbol(&Symbol::DEV_TMP)
+            }
             LowLevel::Eq => {
                 debug_assert_eq!(2, args.len(), "Eq: expected to have exactly two argument");
                 debug_assert_eq!(
@@ -524,6 +692,23 @@ trait Backend<'a> {
                 );
                 self.build_num_lt(sym, &args[0], &args[1], &arg_layouts[0])
             }
+            LowLevel::NumGt => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumGt: expected to have exactly two argument"
+                );
+                debug_assert_eq!(
+                    arg_layouts[0], arg_layouts[1],
+                    "NumGt: expected all arguments of to have the same layout"
+                );
+                debug_assert_eq!(
+                    Layout::Builtin(Builtin::Bool),
+                    *ret_layout,
+                    "NumGt: expected to have return layout of type Bool"
+                );
+                self.build_num_gt(sym, &args[0], &args[1], &arg_layouts[0])
+            }
             LowLevel::NumToFrac => {
                 debug_assert_eq!(
                     1,
@@ -612,6 +797,37 @@ trait Backend<'a> {
                 arg_layouts,
                 ret_layout,
             ),
+            LowLevel::StrCountGraphemes => self.build_fn_call(
+                sym,
+                bitcode::STR_COUNT_GRAPEHEME_CLUSTERS.to_string(),
+                args,
+                arg_layouts,
+                ret_layout,
+            ),
+            LowLevel::StrCountUtf8Bytes => self.build_fn_call(
+                sym,
+                bitcode::STR_COUNT_UTF8_BYTES.to_string(),
+                args,
+                arg_layouts,
+                ret_layout,
+            ),
+            LowLevel::NumToStr => {
+                debug_assert_eq!(
+                    1,
+                    args.len(),
+                    "NumToStr: expected to have exactly one argument"
+                );
+                let fn_name = match arg_layouts[0] {
+                    Layout::Builtin(Builtin::Int(int_width)) => {
+                        bitcode::STR_FROM_INT[int_width].to_string()
+                    }
+                    Layout::Builtin(Builtin::Float(float_width)) => {
+                        bitcode::STR_FROM_FLOAT[float_width].to_string()
+                    }
+                    x => todo!("NumToStr: layout, {:?}", x),
+                };
+                self.build_fn_call(sym, fn_name, args, arg_layouts, ret_layout)
+            }
             LowLevel::PtrCast => {
                 debug_assert_eq!(
                     1,
@@ -634,6 +850,14 @@ trait Backend<'a> {
                 arg_layouts,
                 ret_layout,
             ),
+            LowLevel::Hash => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "Hash: expected to have exactly two arguments"
+                );
+                self.build_hash(sym, &args[0], &args[1], &arg_layouts[0])
+            }
             x => todo!("low level, {:?}", x),
         }
     }
@@ -702,12 +926,45 @@ trait Backend<'a> {
     /// build_num_mul stores `src1 * src2` into dst.
     fn build_num_mul(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>);
 
+    /// build_num_div stores `src1 / src2` into dst.
+    fn build_num_div(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>);
+
     /// build_num_neg stores the negated value of src into dst.
     fn build_num_neg(&mut self, dst: &Symbol, src: &Symbol, layout: &Layout<'a>);
 
+    /// build_num_sqrt stores the square root of src into dst.
+    fn build_num_sqrt(&mut self, dst: &Symbol, src: &Symbol, layout: &Layout<'a>);
+
     /// build_num_sub stores the `src1 - src2` difference into dst.
     fn build_num_sub(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>);
 
+    /// build_num_bitwise_and stores the bitwise and of src1 and src2 into dst.
+    fn build_num_bitwise_and(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    );
+
+    /// build_num_bitwise_or stores the bitwise or of src1 and src2 into dst.
+    fn build_num_bitwise_or(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    );
+
+    /// build_num_bitwise_xor stores the bitwise xor of src1 and src2 into dst.
+    fn build_num_bitwise_xor(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        layout: &Layout<'a>,
+    );
+
     /// build_eq stores the result of `src1 == src2` into dst.
     fn build_eq(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, arg_layout: &Layout<'a>);
 
@@ -717,6 +974,9 @@ trait Backend<'a> {
     /// build_num_lt stores the result of `src1 < src2` into dst.
     fn build_num_lt(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, arg_layout: &Layout<'a>);
 
+    /// build_num_gt stores the result of `src1 > src2` into dst.
+    fn build_num_gt(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, arg_layout: &Layout<'a>);
+
     /// build_num_to_frac convert Number to Frac
     fn build_num_to_frac(
         &mut self,
@@ -768,6 +1028,9 @@ trait Backend<'a> {
     /// build_refcount_getptr loads the pointer to the reference count of src into dst.
     fn build_ptr_cast(&mut self, dst: &Symbol, src: &Symbol);
 
+    /// build_hash stores the hash of value, seeded with seed, into dst.
+    fn build_hash(&mut self, dst: &Symbol, value: &Symbol, seed: &Symbol, layout: &Layout<'a>);
+
     /// literal_map gets the map from symbol to literal and layout, used for lazy loading and literal folding.
     fn literal_map(&mut self) -> &mut MutMap<Symbol, (*const Literal<'a>, *const Layout<'a>)>;
 
@@ -932,6 +1195,9 @@ trait Backend<'a> {
                     Expr::UnionAtIndex { structure, .. } => {
                         self.set_last_seen(*structure, stmt);
                     }
+                    Expr::UnionFieldPtrAtIndex { structure, .. } => {
+                        self.set_last_seen(*structure, stmt);
+                    }
                     Expr::Array { elems, .. } => {
                         for elem in *elems {
                             if let ListLiteralElement::Symbol(sym) = elem {
@@ -951,6 +1217,8 @@ trait Backend<'a> {
                         self.set_last_seen(*symbol, stmt);
                     }
                     Expr::EmptyArray => {}
+                    Expr::NullPointer => {}
+                    Expr::Alloca { .. } => {}
                     Expr::RuntimeErrorFunction(_) => {}
                 }
                 self.scan_ast(following);
@@ -1004,7 +1272,18 @@ trait Backend<'a> {
                 }
             }
 
-            Stmt::Expect { .. } => todo!("expect is not implemented in the wasm backend"),
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                self.set_last_seen(*condition, stmt);
+                for sym in *lookups {
+                    self.set_last_seen(*sym, stmt);
+                }
+                self.scan_ast(remainder);
+            }
 
             Stmt::RuntimeError(_) => {}
         }
@@ -0,0 +1,105 @@
+//! Allocation profiling for `--profile-allocations` builds.
+//!
+//! The zig builtins count allocations (see `allocation_profile.zig`), and write a report when the
+//! program exits. The generated code tells them which allocation site is running: a specialized
+//! procedure, and the layout of the value it is building. Reusing a cell instead of allocating
+//! a fresh one (see `insert_reset_reuse`) is counted separately for each site.
+use crate::llvm::bitcode::call_void_bitcode_fn;
+use crate::llvm::build::{Env, Scope};
+use crate::llvm::debug_info;
+use inkwell::module::Linkage;
+use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+use roc_builtins::bitcode;
+use roc_mono::ir::Expr;
+use roc_mono::layout::Layout;
+
+const SITE_PREFIX: &str = "roc_allocation_site.";
+
+/// Switch profiling on or off, just like `configure_refcount_debugging`.
+pub fn configure_allocation_profile(env: &Env<'_, '_, '_>) {
+    if let Some(global) = env.module.get_global(bitcode::UTILS_PROFILE_ALLOCATIONS) {
+        let bool_type = global
+            .as_pointer_value()
+            .get_type()
+            .get_element_type()
+            .into_int_type();
+
+        global.set_initializer(&bool_type.const_int(env.profile_allocations as u64, false));
+        global.set_constant(true);
+        global.set_linkage(Linkage::Internal);
+    }
+}
+
+/// Attribute the allocations made from now on to the procedure and the layout of the value that
+/// the expression builds.
+pub(crate) fn set_allocation_site<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    scope: &Scope<'a, 'ctx>,
+    parent: FunctionValue<'ctx>,
+    layout: &Layout<'a>,
+    expr: &Expr<'a>,
+) {
+    if !env.profile_allocations {
+        return;
+    }
+
+    let proc_name = match scope.proc {
+        Some(symbol) => debug_info::roc_proc_name(env, symbol),
+        None => return,
+    };
+
+    // a site is written like a stack in the "folded" format that flamegraph tools read, so it
+    // must be a single line, and the separator must not appear in the layout
+    let layout_name = layout.to_pretty(200).replace('\n', " ").replace(';', ",");
+    let mut site = format!("{};{}", proc_name, layout_name);
+
+    if let Expr::Reuse { .. } = expr {
+        site.push_str(";reuse");
+    }
+
+    let name = format!(
+        "{}{}.{}",
+        SITE_PREFIX,
+        parent.get_name().to_str().unwrap(),
+        site
+    );
+
+    let global = match env.module.get_global(&name) {
+        Some(global) => global,
+        None => {
+            let i8_type = env.context.i8_type();
+            let bytes: Vec<_> = site
+                .bytes()
+                .chain(std::iter::once(0))
+                .map(|byte| i8_type.const_int(byte as u64, false))
+                .collect();
+
+            let global = env
+                .module
+                .add_global(i8_type.array_type(bytes.len() as u32), None, &name);
+
+            global.set_initializer(&i8_type.const_array(&bytes));
+            global.set_constant(true);
+            global.set_unnamed_addr(true);
+            global.set_linkage(Linkage::Private);
+
+            global
+        }
+    };
+
+    let site_ptr = env.builder.build_pointer_cast(
+        global.as_pointer_value(),
+        env.context.i8_type().ptr_type(AddressSpace::Generic),
+        "allocation_site",
+    );
+
+    call_void_bitcode_fn(env, &[site_ptr.into()], bitcode::UTILS_SET_ALLOCATION_SITE);
+}
+
+/// Count a reuse of the cell that a `reset` handed over, for the current site
+pub(crate) fn record_reuse(env: &Env<'_, '_, '_>) {
+    if env.profile_allocations {
+        call_void_bitcode_fn(env, &[], bitcode::UTILS_RECORD_REUSE);
+    }
+}
@@ -1,7 +1,9 @@
+use crate::llvm::allocation_profile::{self, configure_allocation_profile};
 use crate::llvm::bitcode::{
     call_bitcode_fn, call_bitcode_fn_fixing_for_convention, call_list_bitcode_fn,
     call_str_bitcode_fn, call_void_bitcode_fn, pass_list_or_string_to_zig_32bit, BitcodeReturns,
 };
+use crate::llvm::build_hash::generic_hash;
 use crate::llvm::build_list::{
     self, allocate_list, empty_polymorphic_list, list_append_unsafe, list_capacity, list_concat,
     list_drop_at, list_get_unsafe, list_len, list_map, list_map2, list_map3, list_map4,
@@ -13,6 +15,8 @@ use crate::llvm::compare::{generic_eq, generic_neq};
 use crate::llvm::convert::{
     self, argument_type_from_layout, basic_type_from_builtin, basic_type_from_layout, zig_str_type,
 };
+use crate::llvm::debug_info::{self, DebugSources};
+use crate::llvm::debug_refcounts::{self, configure_refcount_debugging};
 use crate::llvm::expect::clone_to_shared_memory;
 use crate::llvm::refcounting::{
     build_reset, decrement_refcount_layout, increment_refcount_layout, PointerToRefcount,
@@ -132,6 +136,8 @@ pub struct Scope<'a, 'ctx> {
     symbols: ImMap<Symbol, (Layout<'a>, BasicValueEnum<'ctx>)>,
     pub top_level_thunks: ImMap<Symbol, (ProcLayout<'a>, FunctionValue<'ctx>)>,
     join_points: ImMap<JoinPointId, (BasicBlock<'ctx>, &'a [PhiValue<'ctx>])>,
+    /// The procedure being built, to attribute its allocations in `--profile-allocations` builds
+    pub proc: Option<Symbol>,
 }
 
 impl<'a, 'ctx> Scope<'a, 'ctx> {
@@ -216,6 +222,12 @@ pub struct Env<'a, 'ctx, 'env> {
     pub target_info: TargetInfo,
     pub mode: LlvmBackendMode,
     pub exposed_to_host: MutSet<Symbol>,
+    /// Only available when emitting debug info, see `DebugSources`.
+    pub debug_sources: Option<&'env DebugSources<'ctx>>,
+    /// Track allocations to find leaks and double frees, see `debug_refcounts`.
+    pub debug_refcounts: bool,
+    /// Count allocations per procedure and layout, see `allocation_profile`.
+    pub profile_allocations: bool,
 }
 
 #[repr(u32)]
@@ -1509,6 +1521,77 @@ pub fn build_exp_expr<'a, 'ctx, 'env>(
 
             get_tag_id(env, parent, union_layout, argument).into()
         }
+
+        UnionFieldPtrAtIndex {
+            tag_id,
+            structure,
+            index,
+            union_layout,
+        } => {
+            let argument = load_symbol(scope, structure).into_pointer_value();
+
+            // mirrors the pointer arithmetic of `UnionAtIndex`
+            let (field_layouts, data_ptr) = match union_layout {
+                UnionLayout::NonRecursive(_) => {
+                    internal_error!("field pointers are only used for heap-allocated unions")
+                }
+                UnionLayout::Recursive(tag_layouts) => (
+                    tag_layouts[*tag_id as usize],
+                    tag_pointer_clear_tag_id(env, argument),
+                ),
+                UnionLayout::NonNullableUnwrapped(field_layouts) => (*field_layouts, argument),
+                UnionLayout::NullableWrapped {
+                    nullable_id,
+                    other_tags,
+                } => {
+                    debug_assert_ne!(*tag_id, *nullable_id);
+
+                    let tag_index = if *tag_id < *nullable_id {
+                        *tag_id
+                    } else {
+                        tag_id - 1
+                    };
+
+                    (
+                        other_tags[tag_index as usize],
+                        tag_pointer_clear_tag_id(env, argument),
+                    )
+                }
+                UnionLayout::NullableUnwrapped { other_fields, .. } => (*other_fields, argument),
+            };
+
+            let struct_layout = Layout::struct_no_name_order(field_layouts);
+            let struct_type = basic_type_from_layout(env, &struct_layout);
+
+            let struct_ptr = env.builder.build_pointer_cast(
+                data_ptr,
+                struct_type.ptr_type(AddressSpace::Generic),
+                "to_struct_pointer",
+            );
+
+            let field_ptr = env
+                .builder
+                .build_struct_gep(struct_ptr, *index as u32, "field_ptr")
+                .unwrap();
+
+            env.builder
+                .build_ptr_to_int(field_ptr, env.ptr_int(), "field_ptr_to_int")
+                .into()
+        }
+
+        NullPointer => basic_type_from_layout(env, layout)
+            .into_pointer_type()
+            .const_null()
+            .into(),
+
+        Alloca { element_layout } => {
+            let basic_type = basic_type_from_layout(env, element_layout);
+            let ptr = create_entry_block_alloca(env, parent, basic_type, "stack_slot");
+
+            env.builder
+                .build_ptr_to_int(ptr, env.ptr_int(), "stack_slot_to_int")
+                .into()
+        }
     }
 }
 
@@ -1909,6 +1992,8 @@ fn allocate_tag<'a, 'ctx, 'env>(
             let reuse_ptr = {
                 env.builder.position_at_end(else_block);
 
+                allocation_profile::record_reuse(env);
+
                 let cleared = tag_pointer_clear_tag_id(env, ptr);
 
                 env.builder.build_unconditional_branch(cont_block);
@@ -2421,6 +2506,16 @@ pub fn store_roc_value<'a, 'ctx, 'env>(
     }
 }
 
+/// Whether the builtins may allocate, free, or decrement while this expression runs
+fn may_allocate_or_free(expr: &roc_mono::ir::Expr) -> bool {
+    use roc_mono::ir::Expr::*;
+
+    matches!(
+        expr,
+        Call(_) | Tag { .. } | Array { .. } | ExprBox { .. } | Reuse { .. } | Reset { .. }
+    )
+}
+
 pub fn build_exp_stmt<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
     layout_ids: &mut LayoutIds<'a>,
@@ -2448,6 +2543,13 @@ pub fn build_exp_stmt<'a, 'ctx, 'env>(
             for (symbol, expr, layout) in queue {
                 debug_assert!(layout != &Layout::RecursivePointer);
 
+                debug_info::set_let_location(env, parent, *symbol);
+
+                if may_allocate_or_free(expr) {
+                    debug_refcounts::set_refcount_origin(env, parent);
+                    allocation_profile::set_allocation_site(env, scope, parent, layout, expr);
+                }
+
                 let val = build_exp_expr(
                     env,
                     layout_ids,
@@ -2689,6 +2791,8 @@ pub fn build_exp_stmt<'a, 'ctx, 'env>(
         Refcounting(modify, cont) => {
             use ModifyRc::*;
 
+            debug_refcounts::set_refcount_origin(env, parent);
+
             match modify {
                 Inc(symbol, inc_amount) => {
                     let (value, layout) = load_symbol_and_layout(scope, symbol);
@@ -2730,7 +2834,15 @@ pub fn build_exp_stmt<'a, 'ctx, 'env>(
 
                         _ if layout.is_refcounted() => {
                             if value.is_pointer_value() {
-                                let value_ptr = value.into_pointer_value();
+                                let value_ptr = match layout {
+                                    Layout::Union(union_layout)
+                                        if union_layout
+                                            .stores_tag_id_in_pointer(env.target_info) =>
+                                    {
+                                        tag_pointer_clear_tag_id(env, value.into_pointer_value())
+                                    }
+                                    _ => value.into_pointer_value(),
+                                };
 
                                 let then_block = env.context.append_basic_block(parent, "then");
                                 let done_block = env.context.append_basic_block(parent, "done");
@@ -2774,6 +2886,8 @@ pub fn build_exp_stmt<'a, 'ctx, 'env>(
             let bd = env.builder;
             let context = env.context;
 
+            debug_info::set_region_location(env, parent, cond_symbol.module_id(), *region);
+
             let (cond, _cond_layout) = load_symbol_and_layout(scope, cond_symbol);
 
             let condition = bd.build_int_compare(
@@ -4345,6 +4459,9 @@ fn build_procedures_help<'a, 'ctx, 'env>(
     let mut layout_ids = roc_mono::layout::LayoutIds::default();
     let mut scope = Scope::default();
 
+    configure_refcount_debugging(env);
+    configure_allocation_profile(env);
+
     let it = procedures.iter().map(|x| x.1);
 
     let solutions = match roc_alias_analysis::spec_program(opt_level, opt_entry_point, it) {
@@ -4477,7 +4594,7 @@ fn build_proc_header<'a, 'ctx, 'env>(
         Linkage::Internal,
     );
 
-    let subprogram = env.new_subprogram(&fn_name);
+    let subprogram = debug_info::proc_subprogram(env, symbol, &fn_name);
     fn_val.set_subprogram(subprogram);
 
     if env.exposed_to_host.contains(&symbol) {
@@ -4828,6 +4945,10 @@ pub fn build_proc<'a, 'ctx, 'env>(
     builder.position_at_end(entry);
 
     debug_info_init!(env, fn_val);
+    debug_info::set_proc_location(env, fn_val, proc.name.name());
+    debug_refcounts::add_proc_origin(env, fn_val, proc.name.name());
+
+    scope.proc = Some(proc.name.name());
 
     // Add args to scope
     for (arg_val, (layout, arg_symbol)) in fn_val.get_param_iter().zip(args) {
@@ -4850,6 +4971,8 @@ pub fn build_proc<'a, 'ctx, 'env>(
             builder.build_return(Some(&body));
         }
     }
+
+    debug_info::declare_proc_arguments(env, fn_val, entry, proc.name.name(), args);
 }
 
 pub fn verify_fn(fn_val: FunctionValue<'_>) {
@@ -6023,6 +6146,56 @@ fn run_low_level<'a, 'ctx, 'env>(
                 bitcode::LIST_IS_UNIQUE,
             )
         }
+        RefCountIsUnique => {
+            // inserted by `roc_mono::expand_rc`, for a value that is not a null pointer
+            debug_assert_eq!(args.len(), 1);
+
+            let (value, layout) = load_symbol_and_layout(scope, &args[0]);
+            let data_ptr = match layout {
+                Layout::Union(union_layout)
+                    if union_layout.stores_tag_id_in_pointer(env.target_info) =>
+                {
+                    tag_pointer_clear_tag_id(env, value.into_pointer_value())
+                }
+                _ => value.into_pointer_value(),
+            };
+
+            PointerToRefcount::from_ptr_to_data(env, data_ptr)
+                .is_1(env)
+                .into()
+        }
+        PtrStore => {
+            // inserted by tail recursion modulo cons
+            debug_assert_eq!(args.len(), 2);
+
+            let address = load_symbol(scope, &args[0]).into_int_value();
+            let (value, value_layout) = load_symbol_and_layout(scope, &args[1]);
+
+            let ptr = env.builder.build_int_to_ptr(
+                address,
+                env.context.i8_type().ptr_type(AddressSpace::Generic),
+                "to_opaque_ptr",
+            );
+
+            store_roc_value_opaque(env, *value_layout, ptr, value);
+
+            basic_type_from_layout(env, layout).const_zero()
+        }
+        PtrLoad => {
+            // inserted by tail recursion modulo cons
+            debug_assert_eq!(args.len(), 1);
+
+            let address = load_symbol(scope, &args[0]).into_int_value();
+            let basic_type = basic_type_from_layout(env, layout);
+
+            let ptr = env.builder.build_int_to_ptr(
+                address,
+                basic_type.ptr_type(AddressSpace::Generic),
+                "to_ptr",
+            );
+
+            load_roc_value(env, *layout, ptr, "ptr_load")
+        }
         NumToStr => {
             // Num.toStr : Num a -> Str
             debug_assert_eq!(args.len(), 1);
@@ -6372,7 +6545,12 @@ fn run_low_level<'a, 'ctx, 'env>(
             BasicValueEnum::IntValue(bool_val)
         }
         Hash => {
-            unimplemented!()
+            debug_assert_eq!(args.len(), 2);
+
+            let (value, value_layout) = load_symbol_and_layout(scope, &args[0]);
+            let seed = load_symbol(scope, &args[1]).into_int_value();
+
+            BasicValueEnum::IntValue(generic_hash(env, seed, value, value_layout))
         }
 
         ListMap | ListMap2 | ListMap3 | ListMap4 | ListSortWith => {
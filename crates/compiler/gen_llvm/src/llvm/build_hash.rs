@@ -0,0 +1,231 @@
+use crate::llvm::bitcode::{
+    call_bitcode_fn, pass_list_or_string_to_zig_32bit, pass_string_to_zig_64bit,
+};
+use crate::llvm::build::{entry_block_alloca_zerofill, Env};
+use crate::llvm::convert::{basic_type_from_layout, zig_str_type};
+use inkwell::values::{BasicValueEnum, IntValue, PointerValue};
+use inkwell::AddressSpace;
+use roc_builtins::bitcode::{self, IntWidth};
+use roc_error_macros::internal_error;
+use roc_mono::layout::{Builtin, Discriminant, Layout, UnionLayout};
+use roc_target::PtrWidth;
+
+/// Hash a value with a seed, using Zig's implementation of wyhash.
+/// Numbers are hashed by their bytes. Lists are hashed by the bytes of their elements,
+/// which is only valid when the elements don't contain any pointers or padding.
+/// This matches the hashes that the wasm backend computes.
+pub fn generic_hash<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    seed: IntValue<'ctx>,
+    value: BasicValueEnum<'ctx>,
+    layout: &Layout<'a>,
+) -> IntValue<'ctx> {
+    let builder = env.builder;
+
+    let value_ptr = if layout.is_passed_by_reference(env.target_info) {
+        value.into_pointer_value()
+    } else {
+        let alloca =
+            entry_block_alloca_zerofill(env, basic_type_from_layout(env, layout), "hash_value");
+        builder.build_store(alloca, value);
+        alloca
+    };
+    let bytes = builder.build_pointer_cast(
+        value_ptr,
+        env.context.i8_type().ptr_type(AddressSpace::Generic),
+        "hash_value_bytes",
+    );
+
+    // Each part of the value is hashed with the hash of the parts before it as the seed
+    let seed_ptr = entry_block_alloca_zerofill(env, env.context.i64_type().into(), "hash_seed");
+    builder.build_store(seed_ptr, seed);
+
+    hash_in_memory(env, layout, bytes, 0, seed_ptr);
+
+    builder.build_load(seed_ptr, "hash").into_int_value()
+}
+
+/// Hash a value that is stored in memory at `bytes + offset`, and update the seed in
+/// `seed_ptr` to the new hash.
+fn hash_in_memory<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout: &Layout<'a>,
+    bytes: PointerValue<'ctx>,
+    offset: u32,
+    seed_ptr: PointerValue<'ctx>,
+) {
+    let builder = env.builder;
+    let address = |offset: u32| unsafe {
+        builder.build_in_bounds_gep(
+            bytes,
+            &[env.ptr_int().const_int(offset as u64, false)],
+            "hash_address",
+        )
+    };
+
+    match layout {
+        Layout::Builtin(Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal) => {
+            let size = layout.stack_size(env.target_info) as u64;
+            hash_bytes(
+                env,
+                seed_ptr,
+                address(offset),
+                env.ptr_int().const_int(size, false),
+            );
+        }
+
+        Layout::Builtin(Builtin::Str) => {
+            let str_ptr = builder.build_pointer_cast(
+                address(offset),
+                zig_str_type(env).ptr_type(AddressSpace::Generic),
+                "hash_str_ptr",
+            );
+            let seed = builder.build_load(seed_ptr, "seed");
+
+            let hash = match env.target_info.ptr_width() {
+                PtrWidth::Bytes4 => {
+                    let string = builder.build_load(str_ptr, "hash_str").into_struct_value();
+                    let (a, b) = pass_list_or_string_to_zig_32bit(env, string);
+                    call_bitcode_fn(env, &[seed, a.into(), b.into()], bitcode::DICT_HASH_STR)
+                }
+                PtrWidth::Bytes8 => call_bitcode_fn(
+                    env,
+                    &[seed, pass_string_to_zig_64bit(env, str_ptr.into()).into()],
+                    bitcode::DICT_HASH_STR,
+                ),
+            };
+            builder.build_store(seed_ptr, hash);
+        }
+
+        Layout::Builtin(Builtin::List(
+            elem_layout @ Layout::Builtin(
+                Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+            ),
+        )) => {
+            let ptr_bytes = env.target_info.ptr_width() as u32;
+            let elem_size = elem_layout.stack_size(env.target_info) as u64;
+
+            let elements_ptr = builder.build_pointer_cast(
+                address(offset + ptr_bytes * Builtin::WRAPPER_PTR),
+                env.context
+                    .i8_type()
+                    .ptr_type(AddressSpace::Generic)
+                    .ptr_type(AddressSpace::Generic),
+                "hash_list_elements_ptr",
+            );
+            let elements = builder
+                .build_load(elements_ptr, "hash_list_elements")
+                .into_pointer_value();
+
+            let len_ptr = builder.build_pointer_cast(
+                address(offset + ptr_bytes * Builtin::WRAPPER_LEN),
+                env.ptr_int().ptr_type(AddressSpace::Generic),
+                "hash_list_len_ptr",
+            );
+            let len = builder
+                .build_load(len_ptr, "hash_list_len")
+                .into_int_value();
+            let size = builder.build_int_mul(
+                len,
+                env.ptr_int().const_int(elem_size, false),
+                "hash_list_size",
+            );
+
+            hash_bytes(env, seed_ptr, elements, size);
+        }
+
+        Layout::Struct { field_layouts, .. } => {
+            hash_fields(env, field_layouts, bytes, offset, seed_ptr);
+        }
+
+        Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+            if tags.len() == 1 {
+                hash_fields(env, tags[0], bytes, offset, seed_ptr);
+                return;
+            }
+
+            // Hash the tag id first, so that tags with the same payload hash differently
+            let id_offset = offset + union_layout.tag_id_offset(env.target_info).unwrap();
+            let id_layout = Layout::Builtin(Builtin::Int(match union_layout.discriminant() {
+                Discriminant::U16 => IntWidth::U16,
+                _ => IntWidth::U8,
+            }));
+            hash_in_memory(env, &id_layout, bytes, id_offset, seed_ptr);
+
+            // Then the payload of the tag that the value has
+            let id_type = basic_type_from_layout(env, &id_layout).into_int_type();
+            let id_ptr = builder.build_pointer_cast(
+                address(id_offset),
+                id_type.ptr_type(AddressSpace::Generic),
+                "hash_tag_id_ptr",
+            );
+            let tag_id = builder.build_load(id_ptr, "hash_tag_id").into_int_value();
+
+            let parent = builder
+                .get_insert_block()
+                .and_then(|b| b.get_parent())
+                .unwrap();
+            let cont_block = env.context.append_basic_block(parent, "hash_tag_cont");
+
+            let mut cases = std::vec::Vec::with_capacity(tags.len());
+            for (tag_id, fields) in tags.iter().enumerate() {
+                if fields.is_empty() {
+                    continue;
+                }
+
+                let block = env.context.append_basic_block(parent, "hash_tag_payload");
+                cases.push((id_type.const_int(tag_id as u64, false), block, *fields));
+            }
+
+            let switch_cases: std::vec::Vec<_> = cases
+                .iter()
+                .map(|(tag_id, block, _)| (*tag_id, *block))
+                .collect();
+            builder.build_switch(tag_id, cont_block, &switch_cases);
+
+            for (_, block, fields) in cases {
+                builder.position_at_end(block);
+                hash_fields(env, fields, bytes, offset, seed_ptr);
+                builder.build_unconditional_branch(cont_block);
+            }
+
+            builder.position_at_end(cont_block);
+        }
+
+        x => internal_error!(
+            "Hashing {:?} is not supported. Only numbers, strings, lists of numbers, and records and non-recursive tag unions of those can be hashed.",
+            x
+        ),
+    }
+}
+
+/// Hash the fields of a record or tag payload that starts at `bytes + offset`, in order
+fn hash_fields<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    field_layouts: &[Layout<'a>],
+    bytes: PointerValue<'ctx>,
+    offset: u32,
+    seed_ptr: PointerValue<'ctx>,
+) {
+    let mut field_offset = offset;
+
+    for field_layout in field_layouts.iter() {
+        hash_in_memory(env, field_layout, bytes, field_offset, seed_ptr);
+        field_offset += field_layout.stack_size(env.target_info);
+    }
+}
+
+fn hash_bytes<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    seed_ptr: PointerValue<'ctx>,
+    bytes: PointerValue<'ctx>,
+    length: IntValue<'ctx>,
+) {
+    let seed = env.builder.build_load(seed_ptr, "seed");
+    let hash = call_bitcode_fn(
+        env,
+        &[seed, bytes.into(), length.into()],
+        bitcode::DICT_HASH,
+    );
+    env.builder.build_store(seed_ptr, hash);
+}
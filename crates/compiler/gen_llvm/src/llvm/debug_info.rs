@@ -0,0 +1,426 @@
+//! DWARF for `--debug` builds, so that debuggers can map procedures back to `.roc` files.
+//!
+//! All Roc modules end up in a single LLVM module and thus share a compile unit, but every Roc
+//! module gets its own file, and every specialized procedure gets a subprogram in that file with
+//! its original Roc name.
+use inkwell::basic_block::BasicBlock;
+use inkwell::debug_info::{
+    AsDIScope, DIFile, DIFlags, DIFlagsConstants, DISubprogram, DIType, DebugInfoBuilder,
+};
+use inkwell::module::{FlagBehavior, Module};
+use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_collections::all::MutMap;
+use roc_module::ident::ModuleName;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_mono::layout::{Builtin, Layout};
+use roc_region::all::{LineInfo, Region};
+use std::path::PathBuf;
+
+use super::build::Env;
+
+/// The version of the debug info metadata format; LLVM drops debug info without it.
+const DEBUG_METADATA_VERSION: u64 = 3;
+const DWARF_VERSION: u64 = 4;
+
+// DWARF base type encodings
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_UNSIGNED: u32 = 0x08;
+
+/// The source files, definition sites and call sites of a program.
+pub struct DebugSources<'ctx> {
+    files: MutMap<ModuleId, (DIFile<'ctx>, LineInfo)>,
+    file_names: MutMap<ModuleId, String>,
+    def_regions: MutMap<Symbol, Region>,
+    call_regions: MutMap<Symbol, Region>,
+}
+
+impl<'ctx> DebugSources<'ctx> {
+    pub fn new(
+        dibuilder: &DebugInfoBuilder<'ctx>,
+        sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+        def_regions: MutMap<Symbol, Region>,
+        call_regions: MutMap<Symbol, Region>,
+    ) -> Self {
+        let files = sources
+            .iter()
+            .map(|(module_id, (path, src))| {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let directory = match path.parent() {
+                    Some(parent) => parent.to_string_lossy(),
+                    None => ".".into(),
+                };
+
+                let file = dibuilder.create_file(&file_name, &directory);
+
+                (*module_id, (file, LineInfo::new(src)))
+            })
+            .collect();
+
+        let file_names = sources
+            .iter()
+            .map(|(module_id, (path, _))| {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+                (*module_id, file_name.into_owned())
+            })
+            .collect();
+
+        Self {
+            files,
+            file_names,
+            def_regions,
+            call_regions,
+        }
+    }
+
+    /// The file of the module, and the (1-based) line the region starts on
+    fn location(&self, module_id: ModuleId, region: Region) -> Option<(DIFile<'ctx>, u32)> {
+        let (file, line_info) = self.files.get(&module_id)?;
+
+        Some((*file, line_info.convert_pos(region.start()).line + 1))
+    }
+
+    /// The name of the file a procedure was defined in, and the line of its definition
+    pub(crate) fn proc_file_and_line(&self, symbol: Symbol) -> Option<(&str, u32)> {
+        let file_name = self.file_names.get(&symbol.module_id())?;
+        let (_, line) = self.proc_location(symbol)?;
+
+        Some((file_name, line))
+    }
+
+    /// Where a procedure was defined. Anonymous closures have no definition site of their own,
+    /// but we still know which file they are in.
+    fn proc_location(&self, symbol: Symbol) -> Option<(DIFile<'ctx>, u32)> {
+        let module_id = symbol.module_id();
+
+        match self.def_regions.get(&symbol) {
+            Some(region) => self.location(module_id, *region),
+            None => self.files.get(&module_id).map(|(file, _)| (*file, 0)),
+        }
+    }
+}
+
+/// Without these flags, LLVM silently drops all debug info when emitting the object file.
+pub fn add_debug_info_flags(module: &Module) {
+    let context = module.get_context();
+    let flags = [
+        ("Debug Info Version", DEBUG_METADATA_VERSION),
+        ("Dwarf Version", DWARF_VERSION),
+    ];
+
+    for (key, value) in flags {
+        // the builtins may already define these
+        if module.get_flag(key).is_none() {
+            let value = context.i32_type().const_int(value, false);
+            module.add_basic_value_flag(key, FlagBehavior::Warning, value);
+        }
+    }
+}
+
+/// A subprogram for a specialized Roc procedure, named after the Roc definition and placed at
+/// its definition site. Falls back to an anonymous subprogram when we have no sources.
+pub(crate) fn proc_subprogram<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    symbol: Symbol,
+    fn_name: &str,
+) -> DISubprogram<'ctx> {
+    let location = env
+        .debug_sources
+        .and_then(|sources| sources.proc_location(symbol));
+
+    let (file, line) = match location {
+        Some(location) => location,
+        None => return env.new_subprogram(fn_name),
+    };
+
+    let subroutine_type = env.dibuilder.create_subroutine_type(
+        file,
+        /* return type */ None,
+        &[],
+        DIFlags::PUBLIC,
+    );
+
+    let roc_name = roc_proc_name(env, symbol);
+
+    env.dibuilder.create_function(
+        /* scope */ file.as_debug_info_scope(),
+        /* func name */ &roc_name,
+        /* linkage_name */ Some(fn_name),
+        /* file */ file,
+        /* line_no */ line,
+        /* DIType */ subroutine_type,
+        /* is_local_to_unit */ true,
+        /* is_definition */ true,
+        /* scope_line */ line,
+        /* flags */ DIFlags::PUBLIC,
+        /* is_optimized */ false,
+    )
+}
+
+/// The name of a procedure as it is written in Roc, qualified unless it is defined in the app.
+pub(crate) fn roc_proc_name<'a, 'ctx, 'env>(env: &Env<'a, 'ctx, 'env>, symbol: Symbol) -> String {
+    let ident = symbol.as_str(&env.interns);
+
+    match symbol.module_string(&env.interns).as_str() {
+        ModuleName::APP => ident.to_string(),
+        module_name => format!("{}.{}", module_name, ident),
+    }
+}
+
+/// Point the instructions built from now on at the definition of the procedure.
+pub(crate) fn set_proc_location<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    fn_val: FunctionValue<'ctx>,
+    symbol: Symbol,
+) {
+    let location = env
+        .debug_sources
+        .and_then(|sources| sources.proc_location(symbol));
+
+    if let Some((_, line)) = location {
+        set_line(env, fn_val, line);
+    }
+}
+
+/// Point the instructions that compute `symbol` at the call it is the result of, if it is one.
+/// Other instructions keep the location of the call or definition before them.
+pub(crate) fn set_let_location<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    fn_val: FunctionValue<'ctx>,
+    symbol: Symbol,
+) {
+    let location = env.debug_sources.and_then(|sources| {
+        let region = sources.call_regions.get(&symbol)?;
+
+        sources.location(symbol.module_id(), *region)
+    });
+
+    if let Some((_, line)) = location {
+        set_line(env, fn_val, line);
+    }
+}
+
+/// Point the instructions built from now on at the source region.
+pub(crate) fn set_region_location<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    fn_val: FunctionValue<'ctx>,
+    module_id: ModuleId,
+    region: Region,
+) {
+    let location = env
+        .debug_sources
+        .and_then(|sources| sources.location(module_id, region));
+
+    if let Some((_, line)) = location {
+        set_line(env, fn_val, line);
+    }
+}
+
+fn set_line<'a, 'ctx, 'env>(env: &Env<'a, 'ctx, 'env>, fn_val: FunctionValue<'ctx>, line: u32) {
+    if let Some(subprogram) = fn_val.get_subprogram() {
+        let loc = env.dibuilder.create_debug_location(
+            env.context,
+            line,
+            /* column */ 0,
+            subprogram.as_debug_info_scope(),
+            /* inlined_at */ None,
+        );
+        env.builder.set_current_debug_location(env.context, loc);
+    }
+}
+
+/// Describe the arguments of a procedure, so debuggers can show their values.
+///
+/// Must be called after the body is built, because the descriptions are placed before the first
+/// instruction of the entry block.
+pub(crate) fn declare_proc_arguments<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    fn_val: FunctionValue<'ctx>,
+    entry: BasicBlock<'ctx>,
+    symbol: Symbol,
+    args: &[(Layout<'a>, Symbol)],
+) {
+    let location = env
+        .debug_sources
+        .and_then(|sources| sources.proc_location(symbol));
+
+    let ((file, line), subprogram, first_instruction) = match (
+        location,
+        fn_val.get_subprogram(),
+        entry.get_first_instruction(),
+    ) {
+        (Some(location), Some(subprogram), Some(instruction)) => {
+            (location, subprogram, instruction)
+        }
+        _ => return,
+    };
+
+    let scope = subprogram.as_debug_info_scope();
+
+    for (index, (arg_val, (layout, arg_symbol))) in fn_val.get_param_iter().zip(args).enumerate() {
+        let ditype = match layout_type(env, layout) {
+            Some(ditype) => ditype,
+            // TODO describe records and tags
+            None => continue,
+        };
+
+        let variable = env.dibuilder.create_parameter_variable(
+            scope,
+            arg_symbol.as_str(&env.interns),
+            /* arg_no */ index as u32 + 1,
+            file,
+            line,
+            ditype,
+            /* always_preserve */ true,
+            DIFlags::ZERO,
+        );
+
+        let loc = env.dibuilder.create_debug_location(
+            env.context,
+            line,
+            /* column */ 0,
+            scope,
+            /* inlined_at */ None,
+        );
+
+        env.dibuilder
+            .insert_dbg_value_before(arg_val, variable, None, loc, first_instruction);
+    }
+}
+
+fn layout_type<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout: &Layout<'a>,
+) -> Option<DIType<'ctx>> {
+    match layout {
+        Layout::Builtin(Builtin::Str) => {
+            let byte_type = scalar_type(env, &Layout::u8())?;
+
+            collection_type(env, "Str", byte_type)
+        }
+        Layout::Builtin(Builtin::List(element_layout)) => {
+            // elements we cannot describe are shown as bytes
+            let element_type = match scalar_type(env, element_layout) {
+                Some(element_type) => element_type,
+                None => scalar_type(env, &Layout::u8())?,
+            };
+
+            collection_type(env, "List", element_type)
+        }
+        _ => scalar_type(env, layout),
+    }
+}
+
+/// Strings and lists are a pointer to their elements, followed by their length and capacity.
+fn collection_type<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    name: &str,
+    element_type: DIType<'ctx>,
+) -> Option<DIType<'ctx>> {
+    let ptr_bits = env.target_info.ptr_width() as u64 * 8;
+    let file = env.compile_unit.get_file();
+    let scope = env.compile_unit.as_debug_info_scope();
+
+    let nat_type = env
+        .dibuilder
+        .create_basic_type("Nat", ptr_bits, DW_ATE_UNSIGNED, DIFlags::PUBLIC)
+        .ok()?
+        .as_type();
+    let pointer_type = env
+        .dibuilder
+        .create_pointer_type(
+            "elements",
+            element_type,
+            ptr_bits,
+            ptr_bits as u32,
+            AddressSpace::Generic,
+        )
+        .as_type();
+
+    let fields = [
+        ("elements", pointer_type),
+        ("length", nat_type),
+        ("capacity", nat_type),
+    ];
+    let members: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, (field_name, field_type))| {
+            env.dibuilder
+                .create_member_type(
+                    scope,
+                    field_name,
+                    file,
+                    /* line_no */ 0,
+                    ptr_bits,
+                    ptr_bits as u32,
+                    /* offset_in_bits */ index as u64 * ptr_bits,
+                    DIFlags::PUBLIC,
+                    *field_type,
+                )
+                .as_type()
+        })
+        .collect();
+
+    let struct_type = env.dibuilder.create_struct_type(
+        scope,
+        name,
+        file,
+        /* line_no */ 0,
+        /* size_in_bits */ fields.len() as u64 * ptr_bits,
+        /* align_in_bits */ ptr_bits as u32,
+        DIFlags::PUBLIC,
+        /* derived_from */ None,
+        &members,
+        /* runtime_language */ 0,
+        /* vtable_holder */ None,
+        /* unique_id */ "",
+    );
+
+    Some(struct_type.as_type())
+}
+
+fn scalar_type<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout: &Layout<'a>,
+) -> Option<DIType<'ctx>> {
+    let (name, size_in_bits, encoding) = match layout {
+        Layout::Builtin(Builtin::Bool) => ("Bool", 8, DW_ATE_BOOLEAN),
+        Layout::Builtin(Builtin::Int(int_width)) => {
+            let name = match int_width {
+                IntWidth::U8 => "U8",
+                IntWidth::U16 => "U16",
+                IntWidth::U32 => "U32",
+                IntWidth::U64 => "U64",
+                IntWidth::U128 => "U128",
+                IntWidth::I8 => "I8",
+                IntWidth::I16 => "I16",
+                IntWidth::I32 => "I32",
+                IntWidth::I64 => "I64",
+                IntWidth::I128 => "I128",
+            };
+            let encoding = if int_width.is_signed() {
+                DW_ATE_SIGNED
+            } else {
+                DW_ATE_UNSIGNED
+            };
+
+            (name, int_width.stack_size() as u64 * 8, encoding)
+        }
+        Layout::Builtin(Builtin::Float(float_width)) => match float_width {
+            FloatWidth::F32 => ("F32", 32, DW_ATE_FLOAT),
+            FloatWidth::F64 => ("F64", 64, DW_ATE_FLOAT),
+            FloatWidth::F128 => ("F128", 128, DW_ATE_FLOAT),
+        },
+        _ => return None,
+    };
+
+    env.dibuilder
+        .create_basic_type(name, size_in_bits, encoding, DIFlags::PUBLIC)
+        .ok()
+        .map(|basic_type| basic_type.as_type())
+}
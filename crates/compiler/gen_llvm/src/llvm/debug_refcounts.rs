@@ -0,0 +1,101 @@
+//! Leak and double-free detection for `--debug-refcounts` builds.
+//!
+//! The tracking itself lives in the zig builtins (see `debug_refcounts.zig`), and is compiled out
+//! unless we switch it on here. The generated code only has to tell the builtins which procedure
+//! is running, so that a problem can be reported with the procedure that allocated, freed, or
+//! decremented the value.
+use crate::llvm::bitcode::call_void_bitcode_fn;
+use crate::llvm::build::{Env, LlvmBackendMode};
+use crate::llvm::debug_info;
+use inkwell::module::Linkage;
+use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+use roc_builtins::bitcode;
+use roc_module::symbol::Symbol;
+
+const ORIGIN_PREFIX: &str = "roc_refcount_origin.";
+
+/// Switch refcount tracking on or off. The builtins read these globals, but never write them, so
+/// we make them constant; that way LLVM removes the tracking code from normal builds entirely.
+pub fn configure_refcount_debugging(env: &Env<'_, '_, '_>) {
+    let settings = [
+        (bitcode::UTILS_DEBUG_REFCOUNTS, env.debug_refcounts),
+        (
+            bitcode::UTILS_REPORT_LEAKS_AT_EXIT,
+            // `roc test` checks for leaks after every expect instead
+            env.debug_refcounts && matches!(env.mode, LlvmBackendMode::Binary),
+        ),
+    ];
+
+    for (name, value) in settings {
+        if let Some(global) = env.module.get_global(name) {
+            let bool_type = global
+                .as_pointer_value()
+                .get_type()
+                .get_element_type()
+                .into_int_type();
+
+            global.set_initializer(&bool_type.const_int(value as u64, false));
+            global.set_constant(true);
+            global.set_linkage(Linkage::Internal);
+        }
+    }
+}
+
+/// Add a global with a readable description of the procedure, like `Str.concat (Str.roc:12)`.
+pub(crate) fn add_proc_origin(env: &Env<'_, '_, '_>, fn_val: FunctionValue<'_>, symbol: Symbol) {
+    if !env.debug_refcounts {
+        return;
+    }
+
+    let mut description = debug_info::roc_proc_name(env, symbol);
+
+    if let Some((file_name, line)) = env
+        .debug_sources
+        .and_then(|sources| sources.proc_file_and_line(symbol))
+    {
+        // anonymous closures have no definition site of their own
+        match line {
+            0 => description.push_str(&format!(" ({})", file_name)),
+            _ => description.push_str(&format!(" ({}:{})", file_name, line)),
+        }
+    }
+
+    let i8_type = env.context.i8_type();
+    let bytes: Vec<_> = description
+        .bytes()
+        .chain(std::iter::once(0))
+        .map(|byte| i8_type.const_int(byte as u64, false))
+        .collect();
+
+    let name = format!("{}{}", ORIGIN_PREFIX, fn_val.get_name().to_str().unwrap());
+    let global = env
+        .module
+        .add_global(i8_type.array_type(bytes.len() as u32), None, &name);
+
+    global.set_initializer(&i8_type.const_array(&bytes));
+    global.set_constant(true);
+    global.set_unnamed_addr(true);
+    global.set_linkage(Linkage::Private);
+}
+
+/// Tell the builtins that the code built from now on runs in this procedure. Call this before
+/// anything that may allocate, free, or decrement.
+pub(crate) fn set_refcount_origin(env: &Env<'_, '_, '_>, parent: FunctionValue<'_>) {
+    if !env.debug_refcounts {
+        return;
+    }
+
+    let name = format!("{}{}", ORIGIN_PREFIX, parent.get_name().to_str().unwrap());
+
+    // helper functions that we generate ourselves have no origin of their own
+    if let Some(global) = env.module.get_global(&name) {
+        let origin = env.builder.build_pointer_cast(
+            global.as_pointer_value(),
+            env.context.i8_type().ptr_type(AddressSpace::Generic),
+            "refcount_origin",
+        );
+
+        call_void_bitcode_fn(env, &[origin.into()], bitcode::UTILS_SET_REFCOUNT_ORIGIN);
+    }
+}
@@ -1,9 +1,13 @@
+pub mod allocation_profile;
 pub mod bitcode;
 pub mod build;
+pub mod build_hash;
 pub mod build_list;
 pub mod build_str;
 pub mod compare;
 pub mod convert;
+pub mod debug_info;
+pub mod debug_refcounts;
 mod expect;
 pub mod externs;
 pub mod refcounting;
@@ -5,12 +5,13 @@ use code_builder::Align;
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_collections::all::MutMap;
 use roc_error_macros::internal_error;
+use roc_module::ident::ModuleName;
 use roc_module::low_level::{LowLevel, LowLevelWrapperType};
 use roc_module::symbol::{Interns, Symbol};
 use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, REFCOUNT_MAX};
 use roc_mono::ir::{
-    BranchInfo, CallType, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc, Param, Proc,
-    ProcLayout, Stmt,
+    BranchInfo, Call, CallType, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc, Param,
+    Proc, ProcLayout, Stmt,
 };
 use roc_mono::layout::{Builtin, Layout, LayoutIds, TagIdIntType, UnionLayout};
 use roc_std::RocDec;
@@ -463,8 +464,15 @@ impl<'a> WasmBackend<'a> {
             .unwrap();
         let wasm_fn_index = self.fn_index_offset + proc_index as u32;
 
-        let name = String::from_str_in(sym.as_str(self.interns), self.env.arena).into_bump_str();
-        self.module.names.append_function(wasm_fn_index, name);
+        // Use Roc-level names, so that stack traces in browser devtools are readable
+        let ident = sym.as_str(self.interns);
+        let name = match sym.module_string(self.interns).as_str() {
+            ModuleName::APP => String::from_str_in(ident, self.env.arena),
+            module_name => bumpalo::format!(in self.env.arena, "{}.{}", module_name, ident),
+        };
+        self.module
+            .names
+            .append_function(wasm_fn_index, name.into_bump_str());
     }
 
     /// Build a wrapper around a Roc procedure so that it can be called from Zig builtins List.map*
@@ -691,7 +699,11 @@ impl<'a> WasmBackend<'a> {
 
             Stmt::Refcounting(modify, following) => self.stmt_refcounting(modify, following),
 
-            Stmt::Expect { .. } => todo!("expect is not implemented in the wasm backend"),
+            Stmt::Expect {
+                condition,
+                remainder,
+                ..
+            } => self.stmt_expect(*condition, remainder),
 
             Stmt::RuntimeError(msg) => self.stmt_runtime_error(msg),
         }
@@ -723,6 +735,19 @@ impl<'a> WasmBackend<'a> {
                 print!("\nlet {:?} = {}", sym, expr.to_pretty(200));
             }
 
+            if let Expr::Call(Call {
+                call_type:
+                    CallType::LowLevel {
+                        op: LowLevel::RefCountIsUnique,
+                        ..
+                    },
+                arguments,
+            }) = expr
+            {
+                self.stmt_is_unique(*sym, arguments[0], following);
+                return;
+            }
+
             let kind = match following {
                 Stmt::Ret(ret_sym) if *sym == *ret_sym => StoredVarKind::ReturnValue,
                 _ => StoredVarKind::Variable,
@@ -955,6 +980,37 @@ impl<'a> WasmBackend<'a> {
         self.stmt(rc_stmt);
     }
 
+    fn stmt_is_unique(&mut self, is_unique: Symbol, structure: Symbol, following: &'a Stmt<'a>) {
+        let layout = self.storage.symbol_layouts[&structure];
+
+        let ident_ids = self
+            .interns
+            .all_ident_ids
+            .get_mut(&self.env.module_id)
+            .unwrap();
+
+        let expanded = self
+            .helper_proc_gen
+            .expand_is_unique(ident_ids, layout, structure, is_unique, following);
+
+        self.stmt(expanded);
+    }
+
+    fn stmt_expect(&mut self, condition: Symbol, remainder: &'a Stmt<'a>) {
+        if self.env.mode.runs_expects() {
+            // There is no shared memory to report the failure in, so a failed
+            // expectation panics, like the LLVM backend does when targeting Wasm.
+            self.start_block();
+            self.storage
+                .load_symbols(&mut self.code_builder, &[condition]);
+            self.code_builder.br_if(0);
+            self.stmt_runtime_error("An expectation failed!");
+            self.end_block();
+        }
+
+        self.stmt(remainder);
+    }
+
     pub fn stmt_runtime_error(&mut self, msg: &'a str) {
         // Create a zero-terminated version of the message string
         let mut bytes = Vec::with_capacity_in(msg.len() + 1, self.env.arena);
@@ -1019,6 +1075,17 @@ impl<'a> WasmBackend<'a> {
                 index,
             } => self.expr_union_at_index(*structure, *tag_id, union_layout, *index, sym),
 
+            Expr::UnionFieldPtrAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => self.expr_union_field_ptr_at_index(*structure, *tag_id, union_layout, *index),
+
+            Expr::NullPointer => self.code_builder.i32_const(0),
+
+            Expr::Alloca { element_layout } => self.expr_alloca(element_layout),
+
             Expr::ExprBox { symbol: arg_sym } => self.expr_box(sym, *arg_sym, layout, storage),
 
             Expr::ExprUnbox { symbol: arg_sym } => self.expr_unbox(sym, *arg_sym),
@@ -1801,6 +1868,67 @@ impl<'a> WasmBackend<'a> {
         );
     }
 
+    fn expr_union_field_ptr_at_index(
+        &mut self,
+        structure: Symbol,
+        tag_id: TagIdIntType,
+        union_layout: &UnionLayout<'a>,
+        index: u64,
+    ) {
+        use UnionLayout::*;
+
+        debug_assert!(!union_layout.tag_is_null(tag_id));
+
+        let tag_index = tag_id as usize;
+        let field_layouts = match union_layout {
+            NonRecursive(_) => {
+                internal_error!("Field pointers are only used for heap-allocated unions")
+            }
+            Recursive(tags) => tags[tag_index],
+            NonNullableUnwrapped(layouts) => *layouts,
+            NullableWrapped {
+                other_tags,
+                nullable_id,
+            } => {
+                let index = if tag_index > *nullable_id as usize {
+                    tag_index - 1
+                } else {
+                    tag_index
+                };
+                other_tags[index]
+            }
+            NullableUnwrapped { other_fields, .. } => *other_fields,
+        };
+
+        let field_offset: u32 = field_layouts
+            .iter()
+            .take(index as usize)
+            .map(|field_layout| field_layout.stack_size(TARGET_INFO))
+            .sum();
+
+        self.storage
+            .load_symbols(&mut self.code_builder, &[structure]);
+
+        if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+            self.code_builder.i32_const(-4); // 11111111...1100
+            self.code_builder.i32_and();
+        }
+
+        self.code_builder.i32_const(field_offset as i32);
+        self.code_builder.i32_add();
+    }
+
+    fn expr_alloca(&mut self, element_layout: &Layout<'a>) {
+        let (size, alignment) = element_layout.stack_size_and_alignment(TARGET_INFO);
+        let (fp, offset) = self
+            .storage
+            .allocate_anonymous_stack_memory(size, alignment);
+
+        self.code_builder.get_local(fp);
+        self.code_builder.i32_const(offset as i32);
+        self.code_builder.i32_add();
+    }
+
     /*******************************************************************
      * Box
      *******************************************************************/
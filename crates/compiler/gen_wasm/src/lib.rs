@@ -1,6 +1,7 @@
 mod backend;
 mod layout;
 mod low_level;
+pub mod source_map;
 mod storage;
 pub mod wasm_module;
 
@@ -17,7 +18,9 @@ use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{Proc, ProcLayout};
 use roc_mono::layout::LayoutIds;
+use roc_region::all::Region;
 use roc_target::TargetInfo;
+use std::path::PathBuf;
 use wasm_module::parse::ParseError;
 
 use crate::backend::{ProcLookupData, ProcSource, WasmBackend};
@@ -46,12 +49,31 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub stack_bytes: u32,
+    pub mode: WasmBackendMode,
 }
 
 impl Env<'_> {
     pub const DEFAULT_STACK_BYTES: u32 = 1024 * 1024;
 }
 
+/// What the generated code is going to be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmBackendMode {
+    /// Code for a regular application; expectations are skipped.
+    Binary,
+    /// Code for tests; a failed expectation calls roc_panic.
+    Test,
+}
+
+impl WasmBackendMode {
+    fn runs_expects(&self) -> bool {
+        match self {
+            WasmBackendMode::Binary => false,
+            WasmBackendMode::Test => true,
+        }
+    }
+}
+
 /// Parse the preprocessed host binary
 /// If successful, the module can be passed to build_app_binary
 pub fn parse_host<'a>(arena: &'a Bump, host_bytes: &[u8]) -> Result<WasmModule<'a>, ParseError> {
@@ -79,6 +101,33 @@ pub fn build_app_binary<'a>(
     buffer
 }
 
+/// Like `build_app_binary`, but also generate a source map for browser devtools.
+/// The binary gets a `sourceMappingURL` section pointing at `source_map_url`.
+pub fn build_app_binary_with_source_map<'a>(
+    env: &'a Env<'a>,
+    interns: &'a mut Interns,
+    host_module: WasmModule<'a>,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &MutMap<Symbol, Region>,
+    source_map_url: &str,
+) -> (std::vec::Vec<u8>, String) {
+    let (mut wasm_module, called_preload_fns, _, roc_proc_names) =
+        build_app_module_help(env, interns, host_module, procedures);
+
+    wasm_module.eliminate_dead_code(env.arena, called_preload_fns);
+
+    let mut buffer = std::vec::Vec::with_capacity(wasm_module.size());
+    let code_offsets = wasm_module.serialize_with_code_offsets(&mut buffer);
+    source_map::append_source_mapping_url(&mut buffer, source_map_url);
+
+    // Roc procs are generated first, so they are the first code builders
+    let proc_offsets = std::vec::Vec::from_iter(roc_proc_names.into_iter().zip(code_offsets));
+    let source_map = source_map::build_source_map(&proc_offsets, sources, def_regions);
+
+    (buffer, source_map)
+}
+
 /// Generate an unserialized Wasm module
 /// Shared by all consumers of gen_wasm: roc_build, roc_repl_wasm, and test_gen
 /// (roc_repl_wasm and test_gen will add more generated code for a wrapper function
@@ -89,6 +138,19 @@ pub fn build_app_module<'a>(
     host_module: WasmModule<'a>,
     procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
 ) -> (WasmModule<'a>, BitVec<usize>, u32) {
+    let (module, called_preload_fns, main_function_index, _) =
+        build_app_module_help(env, interns, host_module, procedures);
+
+    (module, called_preload_fns, main_function_index)
+}
+
+/// Also returns the names of the Roc procedures, in the order they were generated
+fn build_app_module_help<'a>(
+    env: &'a Env<'a>,
+    interns: &'a mut Interns,
+    host_module: WasmModule<'a>,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> (WasmModule<'a>, BitVec<usize>, u32, Vec<'a, Symbol>) {
     let mut layout_ids = LayoutIds::default();
     let mut procs = Vec::with_capacity_in(procedures.len(), env.arena);
     let mut proc_lookup = Vec::with_capacity_in(procedures.len() * 2, env.arena);
@@ -143,8 +205,10 @@ pub fn build_app_module<'a>(
     }
 
     // Generate procs from user code
+    let mut roc_proc_names = Vec::with_capacity_in(procs.len(), env.arena);
     for proc in procs.iter() {
         backend.build_proc(proc);
+        roc_proc_names.push(proc.name.name());
     }
 
     // Generate specialized helpers for refcounting & equality
@@ -183,7 +247,12 @@ pub fn build_app_module<'a>(
     let main_function_index =
         maybe_main_fn_index.expect("The app must expose at least one value to the host");
 
-    (module, called_preload_fns, main_function_index)
+    (
+        module,
+        called_preload_fns,
+        main_function_index,
+        roc_proc_names,
+    )
 }
 
 pub struct CopyMemoryConfig {
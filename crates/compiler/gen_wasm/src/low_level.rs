@@ -1811,7 +1811,33 @@ impl<'a> LowLevelCall<'a> {
                 }
             }
             NumToFloatChecked => {
-                todo!("implement toF32Checked and toF64Checked");
+                let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
+
+                let ret_width = match self.ret_layout {
+                    Layout::Struct {
+                        field_layouts: &[Layout::Builtin(Builtin::Float(ret_width)), ..],
+                        ..
+                    } => ret_width,
+                    _ => {
+                        internal_error!(
+                            "NumToFloatChecked is not defined for signature {:?} -> {:?}",
+                            arg_layout,
+                            self.ret_layout
+                        );
+                    }
+                };
+
+                match arg_layout {
+                    Layout::Builtin(Builtin::Int(arg_width)) => self.load_args_and_call_zig(
+                        backend,
+                        &bitcode::NUM_TO_FLOAT_CHECKED[ret_width][arg_width],
+                    ),
+                    Layout::Builtin(Builtin::Float(arg_width)) => self.load_args_and_call_zig(
+                        backend,
+                        &bitcode::NUM_TO_FLOAT_CHECKED[ret_width][arg_width],
+                    ),
+                    x => internal_error!("NumToFloatChecked is not defined for {:?}", x),
+                }
             }
             And => {
                 self.load_args(backend);
@@ -1827,13 +1853,52 @@ impl<'a> LowLevelCall<'a> {
             }
             RefCountInc => self.load_args_and_call_zig(backend, bitcode::UTILS_INCREF),
             RefCountDec => self.load_args_and_call_zig(backend, bitcode::UTILS_DECREF),
+            RefCountIsUnique => {
+                internal_error!("RefCountIsUnique should be expanded in the backend's stmt_let")
+            }
 
             PtrCast => {
                 let code_builder = &mut backend.code_builder;
                 backend.storage.load_symbols(code_builder, self.arguments);
             }
+            PtrStore => {
+                let code_builder = &mut backend.code_builder;
+                let ptr_storage = backend.storage.get(&self.arguments[0]).to_owned();
+                let ptr_local_id = match backend.storage.ensure_value_has_local(
+                    code_builder,
+                    self.arguments[0],
+                    ptr_storage,
+                ) {
+                    StoredValue::Local { local_id, .. } => local_id,
+                    _ => internal_error!("A pointer will always be an i32"),
+                };
+
+                backend.storage.copy_value_to_memory(
+                    code_builder,
+                    ptr_local_id,
+                    0,
+                    self.arguments[1],
+                );
+            }
+            PtrLoad => {
+                let code_builder = &mut backend.code_builder;
+                let from_addr_val = match backend.storage.get(&self.arguments[0]) {
+                    StoredValue::Local { local_id, .. } => AddressValue::NotLoaded(*local_id),
+                    _ => {
+                        backend.storage.load_symbols(code_builder, self.arguments);
+                        AddressValue::Loaded
+                    }
+                };
+
+                backend.storage.copy_value_from_memory(
+                    code_builder,
+                    self.ret_symbol,
+                    from_addr_val,
+                    0,
+                );
+            }
 
-            Hash => todo!("{:?}", self.lowlevel),
+            Hash => self.hash(backend),
 
             Eq | NotEq => self.eq_or_neq(backend),
 
@@ -1854,6 +1919,239 @@ impl<'a> LowLevelCall<'a> {
         }
     }
 
+    /// Hash a value with a seed, using Zig's implementation of wyhash
+    /// Numbers are hashed by their bytes. Lists are hashed by the bytes of their elements,
+    /// which is only valid when the elements don't contain any pointers or padding.
+    fn hash(&self, backend: &mut WasmBackend<'a>) {
+        let value = self.arguments[0];
+        let seed = self.arguments[1];
+        let value_layout = backend.storage.symbol_layouts[&value];
+
+        match value_layout {
+            Layout::Builtin(Builtin::Str) => {
+                // Zig arguments              Wasm types
+                //  seed: u64                  i64
+                //  input: RocStr              i64, i32
+                let (num_wasm_args, has_return_val, _) = backend.storage.load_symbols_for_call(
+                    backend.env.arena,
+                    &mut backend.code_builder,
+                    &[seed, value],
+                    self.ret_symbol,
+                    &WasmLayout::new(&self.ret_layout),
+                    CallConv::Zig,
+                );
+                backend.call_host_fn_after_loading_args(
+                    bitcode::DICT_HASH_STR,
+                    num_wasm_args,
+                    has_return_val,
+                );
+            }
+
+            Layout::Builtin(
+                Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+            ) => {
+                let size = value_layout.stack_size(TARGET_INFO);
+                let (local, offset, _) =
+                    ensure_symbol_is_in_memory(backend, value, value_layout, backend.env.arena);
+
+                // Zig arguments              Wasm types
+                //  seed: u64                  i64
+                //  bytes: ?[*]const u8        i32
+                //  length: usize              i32
+                backend
+                    .storage
+                    .load_symbols(&mut backend.code_builder, &[seed]);
+                backend.code_builder.get_local(local);
+                if offset > 0 {
+                    backend.code_builder.i32_const(offset as i32);
+                    backend.code_builder.i32_add();
+                }
+                backend.code_builder.i32_const(size as i32);
+                backend.call_host_fn_after_loading_args(bitcode::DICT_HASH, 3, true);
+            }
+
+            Layout::Builtin(Builtin::List(
+                elem_layout @ Layout::Builtin(
+                    Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+                ),
+            )) => {
+                let elem_size = elem_layout.stack_size(TARGET_INFO);
+                let (local, offset) = match backend.storage.get(&value) {
+                    StoredValue::StackMemory { location, .. } => {
+                        location.local_and_offset(backend.storage.stack_frame_pointer)
+                    }
+                    _ => internal_error!("invalid storage for List"),
+                };
+
+                // Zig arguments              Wasm types
+                //  seed: u64                  i64
+                //  bytes: ?[*]const u8        i32
+                //  length: usize              i32
+                backend
+                    .storage
+                    .load_symbols(&mut backend.code_builder, &[seed]);
+                backend.code_builder.get_local(local);
+                backend
+                    .code_builder
+                    .i32_load(Align::Bytes4, offset + (4 * Builtin::WRAPPER_PTR));
+                backend.code_builder.get_local(local);
+                backend
+                    .code_builder
+                    .i32_load(Align::Bytes4, offset + (4 * Builtin::WRAPPER_LEN));
+                backend.code_builder.i32_const(elem_size as i32);
+                backend.code_builder.i32_mul();
+                backend.call_host_fn_after_loading_args(bitcode::DICT_HASH, 3, true);
+            }
+
+            Layout::Struct { .. } | Layout::Union(UnionLayout::NonRecursive(_)) => {
+                let (local, offset, _) =
+                    ensure_symbol_is_in_memory(backend, value, value_layout, backend.env.arena);
+
+                // Each field is hashed with the hash of the fields before it as the seed
+                let seed_local = backend.storage.create_anonymous_local(ValueType::I64);
+                backend
+                    .storage
+                    .load_symbols(&mut backend.code_builder, &[seed]);
+                backend.code_builder.set_local(seed_local);
+
+                Self::hash_in_memory(backend, &value_layout, local, offset, seed_local);
+
+                backend.code_builder.get_local(seed_local);
+            }
+
+            x => internal_error!(
+                "Hashing {:?} is not supported. Only numbers, strings, lists of numbers, and records and non-recursive tag unions of those can be hashed.",
+                x
+            ),
+        }
+    }
+
+    /// Hash a value that is stored in memory at `local + offset`, and update the seed in
+    /// `seed_local` to the new hash.
+    fn hash_in_memory(
+        backend: &mut WasmBackend<'a>,
+        layout: &Layout<'a>,
+        local: LocalId,
+        offset: u32,
+        seed_local: LocalId,
+    ) {
+        let load_address = |backend: &mut WasmBackend<'a>| {
+            backend.code_builder.get_local(local);
+            if offset > 0 {
+                backend.code_builder.i32_const(offset as i32);
+                backend.code_builder.i32_add();
+            }
+        };
+
+        match layout {
+            Layout::Builtin(
+                Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+            ) => {
+                backend.code_builder.get_local(seed_local);
+                load_address(backend);
+                backend
+                    .code_builder
+                    .i32_const(layout.stack_size(TARGET_INFO) as i32);
+                backend.call_host_fn_after_loading_args(bitcode::DICT_HASH, 3, true);
+                backend.code_builder.set_local(seed_local);
+            }
+
+            Layout::Builtin(Builtin::Str) => {
+                // The RocStr is passed to Zig by its address
+                backend.code_builder.get_local(seed_local);
+                load_address(backend);
+                backend.call_host_fn_after_loading_args(bitcode::DICT_HASH_STR, 2, true);
+                backend.code_builder.set_local(seed_local);
+            }
+
+            Layout::Builtin(Builtin::List(
+                elem_layout @ Layout::Builtin(
+                    Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal,
+                ),
+            )) => {
+                let elem_size = elem_layout.stack_size(TARGET_INFO);
+
+                backend.code_builder.get_local(seed_local);
+                backend.code_builder.get_local(local);
+                backend
+                    .code_builder
+                    .i32_load(Align::Bytes4, offset + (4 * Builtin::WRAPPER_PTR));
+                backend.code_builder.get_local(local);
+                backend
+                    .code_builder
+                    .i32_load(Align::Bytes4, offset + (4 * Builtin::WRAPPER_LEN));
+                backend.code_builder.i32_const(elem_size as i32);
+                backend.code_builder.i32_mul();
+                backend.call_host_fn_after_loading_args(bitcode::DICT_HASH, 3, true);
+                backend.code_builder.set_local(seed_local);
+            }
+
+            Layout::Struct { field_layouts, .. } => {
+                let mut field_offset = offset;
+
+                for field_layout in field_layouts.iter() {
+                    Self::hash_in_memory(backend, field_layout, local, field_offset, seed_local);
+                    field_offset += field_layout.stack_size(TARGET_INFO);
+                }
+            }
+
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+                let hash_fields = |backend: &mut WasmBackend<'a>, fields: &[Layout<'a>]| {
+                    let mut field_offset = offset;
+
+                    for field_layout in fields.iter() {
+                        Self::hash_in_memory(
+                            backend,
+                            field_layout,
+                            local,
+                            field_offset,
+                            seed_local,
+                        );
+                        field_offset += field_layout.stack_size(TARGET_INFO);
+                    }
+                };
+
+                if tags.len() == 1 {
+                    hash_fields(backend, tags[0]);
+                    return;
+                }
+
+                // Hash the tag id first, so that tags with the same payload hash differently
+                let id_offset = offset + union_layout.tag_id_offset(TARGET_INFO).unwrap();
+                let id_layout = Layout::Builtin(Builtin::Int(match union_layout.discriminant() {
+                    roc_mono::layout::Discriminant::U16 => IntWidth::U16,
+                    _ => IntWidth::U8,
+                }));
+                Self::hash_in_memory(backend, &id_layout, local, id_offset, seed_local);
+
+                // Then the payload of the tag that the value has
+                for (tag_id, fields) in tags.iter().enumerate() {
+                    if fields.is_empty() {
+                        continue;
+                    }
+
+                    backend.code_builder.get_local(local);
+                    match union_layout.discriminant() {
+                        roc_mono::layout::Discriminant::U16 => {
+                            backend.code_builder.i32_load16_u(Align::Bytes2, id_offset)
+                        }
+                        _ => backend.code_builder.i32_load8_u(Align::Bytes1, id_offset),
+                    }
+                    backend.code_builder.i32_const(tag_id as i32);
+                    backend.code_builder.i32_eq();
+                    backend.code_builder.if_();
+                    hash_fields(backend, fields);
+                    backend.code_builder.end();
+                }
+            }
+
+            x => internal_error!(
+                "Hashing {:?} is not supported. Only numbers, strings, lists of numbers, and records and non-recursive tag unions of those can be hashed.",
+                x
+            ),
+        }
+    }
+
     /// Equality and inequality
     /// These can operate on any data type (except functions) so they're more complex than other operators.
     fn eq_or_neq(&self, backend: &mut WasmBackend<'a>) {
@@ -0,0 +1,177 @@
+//! Source maps for Wasm modules, so that browser devtools can show `.roc` locations in stack traces.
+//!
+//! Wasm source maps are regular version 3 source maps, with the whole module treated as a single
+//! line, and each "column" being a byte offset into the module.
+//! See https://sourcemaps.info/spec.html
+use roc_collections::all::MutMap;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_region::all::{LineInfo, Region};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::wasm_module::sections::{update_section_size, write_custom_section_header};
+use crate::wasm_module::serialize::SerialBuffer;
+
+/// The custom section that tells devtools where to find the source map
+pub const SOURCE_MAPPING_URL_SECTION: &str = "sourceMappingURL";
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Build a source map, given the module byte offset of each Roc procedure's body
+pub fn build_source_map(
+    code_offsets: &[(Symbol, u32)],
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &MutMap<Symbol, Region>,
+) -> String {
+    // Only list the files that we actually point into
+    let mut source_indices: MutMap<ModuleId, usize> = MutMap::default();
+    let mut source_files: Vec<(&PathBuf, &str, LineInfo)> = Vec::new();
+
+    let mut mappings = String::new();
+    let mut prev = SegmentState::default();
+
+    for (symbol, offset) in code_offsets.iter() {
+        let module_id = symbol.module_id();
+        let location = match (sources.get(&module_id), def_regions.get(symbol)) {
+            (Some((path, src)), Some(region)) => {
+                let source_index = *source_indices.entry(module_id).or_insert_with(|| {
+                    source_files.push((path, &**src, LineInfo::new(src)));
+                    source_files.len() - 1
+                });
+                let line_info = &source_files[source_index].2;
+                let line_col = line_info.convert_pos(region.start());
+
+                Some((
+                    source_index as i64,
+                    line_col.line as i64,
+                    line_col.column as i64,
+                ))
+            }
+            _ => None,
+        };
+
+        if !mappings.is_empty() {
+            mappings.push(',');
+        }
+
+        let column = *offset as i64;
+        encode_vlq(&mut mappings, column - prev.column);
+        prev.column = column;
+
+        // A segment with only a column means "this code has no source location"
+        if let Some((source, line, source_column)) = location {
+            encode_vlq(&mut mappings, source - prev.source);
+            encode_vlq(&mut mappings, line - prev.line);
+            encode_vlq(&mut mappings, source_column - prev.source_column);
+            prev.source = source;
+            prev.line = line;
+            prev.source_column = source_column;
+        }
+    }
+
+    let mut json = String::from("{\"version\":3,\"sources\":[");
+    for (i, (path, _, _)) in source_files.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_json_string(&mut json, &path.to_string_lossy());
+    }
+    json.push_str("],\"sourcesContent\":[");
+    for (i, (_, src, _)) in source_files.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write_json_string(&mut json, src);
+    }
+    json.push_str("],\"names\":[],\"mappings\":");
+    write_json_string(&mut json, &mappings);
+    json.push('}');
+
+    json
+}
+
+/// Point devtools at the source map for this module
+pub fn append_source_mapping_url<T: SerialBuffer>(buffer: &mut T, url: &str) {
+    let header_indices = write_custom_section_header(buffer, SOURCE_MAPPING_URL_SECTION);
+    buffer.encode_u32(url.len() as u32);
+    buffer.append_slice(url.as_bytes());
+    update_section_size(buffer, header_indices);
+}
+
+/// Fields of a mapping segment are encoded relative to the previous segment
+#[derive(Default)]
+struct SegmentState {
+    column: i64,
+    source: i64,
+    line: i64,
+    source_column: i64,
+}
+
+/// Base64 variable-length quantity, with the sign in the lowest bit
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+
+    loop {
+        let mut digit = vlq & 0b1_1111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b10_0000; // continuation bit
+        }
+        out.push(BASE64_CHARS[digit as usize] as char);
+
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vlq(value: i64) -> String {
+        let mut out = String::new();
+        encode_vlq(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn test_encode_vlq() {
+        assert_eq!(vlq(0), "A");
+        assert_eq!(vlq(1), "C");
+        assert_eq!(vlq(-1), "D");
+        assert_eq!(vlq(15), "e");
+        assert_eq!(vlq(16), "gB");
+        assert_eq!(vlq(-16), "hB");
+        assert_eq!(vlq(1234), "ktC");
+    }
+
+    #[test]
+    fn test_json_string() {
+        let mut out = String::new();
+        write_json_string(&mut out, "a \"b\"\\\n\u{1}");
+        assert_eq!(out, r#""a \"b\"\\\n\u0001""#);
+    }
+}
@@ -4,6 +4,7 @@ pub mod opcodes;
 pub mod parse;
 pub mod sections;
 pub mod serialize;
+pub mod wat;
 
 use std::iter::repeat;
 
@@ -57,6 +58,23 @@ impl<'a> WasmModule<'a> {
 
     /// Serialize the module to bytes
     pub fn serialize<T: SerialBuffer>(&self, buffer: &mut T) {
+        self.serialize_until_code(buffer);
+        self.code.serialize(buffer);
+        self.serialize_after_code(buffer);
+    }
+
+    /// Serialize the module to bytes, and return the offset of each Roc function body
+    pub fn serialize_with_code_offsets<T: SerialBuffer>(
+        &self,
+        buffer: &mut T,
+    ) -> std::vec::Vec<u32> {
+        self.serialize_until_code(buffer);
+        let offsets = self.code.serialize_with_offsets(buffer);
+        self.serialize_after_code(buffer);
+        offsets
+    }
+
+    fn serialize_until_code<T: SerialBuffer>(&self, buffer: &mut T) {
         buffer.append_u8(0);
         buffer.append_slice("asm".as_bytes());
         buffer.write_unencoded_u32(Self::WASM_VERSION);
@@ -72,7 +90,9 @@ impl<'a> WasmModule<'a> {
         self.export.serialize(buffer);
         self.start.serialize(buffer);
         self.element.serialize(buffer);
-        self.code.serialize(buffer);
+    }
+
+    fn serialize_after_code<T: SerialBuffer>(&self, buffer: &mut T) {
         self.data.serialize(buffer);
         self.names.serialize(buffer);
     }
@@ -1233,8 +1233,15 @@ impl<'a> CodeSection<'a> {
     }
 }
 
-impl<'a> Serialize for CodeSection<'a> {
-    fn serialize<T: SerialBuffer>(&self, buffer: &mut T) {
+impl<'a> CodeSection<'a> {
+    /// Serialize the section, and return the offset in the buffer of each Roc function body
+    pub fn serialize_with_offsets<T: SerialBuffer>(&self, buffer: &mut T) -> std::vec::Vec<u32> {
+        let mut offsets = std::vec::Vec::with_capacity(self.code_builders.len());
+        self.serialize_help(buffer, |offset| offsets.push(offset as u32));
+        offsets
+    }
+
+    fn serialize_help<T: SerialBuffer, F: FnMut(usize)>(&self, buffer: &mut T, mut on_roc_fn: F) {
         let header_indices = write_section_header(buffer, SectionId::Code);
         buffer.encode_u32(
             self.dead_import_dummy_count + self.preloaded_count + self.code_builders.len() as u32,
@@ -1254,6 +1261,7 @@ impl<'a> Serialize for CodeSection<'a> {
 
         // Roc functions
         for code_builder in self.code_builders.iter() {
+            on_roc_fn(buffer.size());
             code_builder.serialize(buffer);
         }
 
@@ -1261,6 +1269,12 @@ impl<'a> Serialize for CodeSection<'a> {
     }
 }
 
+impl<'a> Serialize for CodeSection<'a> {
+    fn serialize<T: SerialBuffer>(&self, buffer: &mut T) {
+        self.serialize_help(buffer, |_| {});
+    }
+}
+
 /*******************************************************************
  *
  * Data section
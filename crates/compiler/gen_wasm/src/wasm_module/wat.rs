@@ -0,0 +1,1153 @@
+//! Render a Wasm binary in the WebAssembly text format (WAT), for `roc build --emit=wasm-text`
+//! https://webassembly.github.io/spec/core/text/index.html
+//!
+//! We only need to read what our own backend writes: the MVP sections, and the "name" custom
+//! section, which gives the functions readable labels. Other custom sections are just listed.
+//! Host functions may contain instructions that we don't know; their bodies are cut short with
+//! a comment rather than failing the whole rendering.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use super::opcodes::OpCode;
+use super::parse::ParseError;
+use super::sections::{SectionId, Signature};
+use super::WasmModule;
+
+const INDENT: &str = "  ";
+
+impl<'a> WasmModule<'a> {
+    /// Render the module in the WebAssembly text format
+    pub fn to_wat(&self) -> Result<String, ParseError> {
+        let mut buffer = std::vec::Vec::with_capacity(self.size());
+        self.serialize(&mut buffer);
+        binary_to_wat(&buffer)
+    }
+}
+
+/// Render a Wasm binary in the WebAssembly text format
+pub fn binary_to_wat(bytes: &[u8]) -> Result<String, ParseError> {
+    let mut reader = Reader { bytes, cursor: 0 };
+
+    if reader.take(4)? != b"\0asm" {
+        return Err(reader.error("This is not a WebAssembly binary. The header is not valid."));
+    }
+    let version = reader.take(4)?;
+    if version != WasmModule::WASM_VERSION.to_le_bytes() {
+        return Err(reader.error(format!("Unsupported WebAssembly version {:?}", version)));
+    }
+
+    // The names are at the end of the binary, but we need them from the start
+    let mut sections = Vec::new();
+    while !reader.is_at_end() {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let start = reader.cursor;
+        reader.take(size)?;
+        sections.push((id, start, start + size));
+    }
+
+    let mut wat = Wat {
+        out: String::from("(module\n"),
+        types: Vec::new(),
+        function_names: HashMap::new(),
+        imported_fn_count: 0,
+        imported_global_count: 0,
+        defined_fn_types: Vec::new(),
+    };
+
+    for &(id, start, end) in sections.iter() {
+        if id == SectionId::Custom as u8 {
+            let mut section = reader.at(start, end);
+            if section.name()? == "name" {
+                wat.read_function_names(&mut section)?;
+            }
+        }
+    }
+
+    for &(id, start, end) in sections.iter() {
+        let mut section = reader.at(start, end);
+        let section_id = section_id(id).ok_or_else(|| ParseError {
+            offset: start,
+            message: format!("Unknown section ID 0x{:02x}", id),
+        })?;
+
+        match section_id {
+            SectionId::Custom => {
+                let name = section.name()?;
+                if name != "name" {
+                    writeln!(wat.out, "{};; custom section {:?}", INDENT, name).unwrap();
+                }
+            }
+            SectionId::Type => wat.types(&mut section)?,
+            SectionId::Import => wat.imports(&mut section)?,
+            SectionId::Function => {
+                for _ in 0..section.u32()? {
+                    let type_index = section.u32()?;
+                    wat.defined_fn_types.push(type_index);
+                }
+            }
+            SectionId::Table => {
+                for index in 0..section.u32()? {
+                    let table_type = section.table_type()?;
+                    writeln!(wat.out, "{}(table (;{};) {})", INDENT, index, table_type).unwrap();
+                }
+            }
+            SectionId::Memory => {
+                for index in 0..section.u32()? {
+                    let limits = section.limits()?;
+                    writeln!(wat.out, "{}(memory (;{};) {})", INDENT, index, limits).unwrap();
+                }
+            }
+            SectionId::Global => wat.globals(&mut section)?,
+            SectionId::Export => wat.exports(&mut section)?,
+            SectionId::Start => {
+                let start_fn = wat.function_ref(section.u32()?);
+                writeln!(wat.out, "{}(start {})", INDENT, start_fn).unwrap();
+            }
+            SectionId::Element => wat.elements(&mut section)?,
+            SectionId::Code => wat.code(&mut section)?,
+            SectionId::Data => wat.data(&mut section)?,
+            SectionId::DataCount => {}
+        }
+    }
+
+    wat.out.push_str(")\n");
+
+    Ok(wat.out)
+}
+
+struct Wat {
+    out: String,
+    /// The rendered parameters and result of each function type
+    types: Vec<String>,
+    function_names: HashMap<u32, String>,
+    imported_fn_count: u32,
+    imported_global_count: u32,
+    /// The type index of each function defined in the Code section
+    defined_fn_types: Vec<u32>,
+}
+
+impl Wat {
+    fn read_function_names(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        const FUNCTION_NAMES: u8 = 1;
+
+        let mut used = HashSet::new();
+
+        while !section.is_at_end() {
+            let subsection_id = section.u8()?;
+            let size = section.u32()? as usize;
+            let start = section.cursor;
+            section.take(size)?;
+            let mut subsection = section.at(start, start + size);
+
+            if subsection_id != FUNCTION_NAMES {
+                continue;
+            }
+
+            for _ in 0..subsection.u32()? {
+                let index = subsection.u32()?;
+                let mut name = identifier(&subsection.name()?);
+
+                // Identifiers must be unique, but names from different object files may clash
+                if !used.insert(name.clone()) {
+                    name = format!("{}.{}", name, index);
+                    used.insert(name.clone());
+                }
+
+                self.function_names.insert(index, name);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn function_ref(&self, index: u32) -> String {
+        match self.function_names.get(&index) {
+            Some(name) => format!("${}", name),
+            None => index.to_string(),
+        }
+    }
+
+    /// The label, index and type of a function, like `$main (;3;) (type 1) (param i32)`
+    fn function_header(&self, index: u32, type_index: u32) -> String {
+        let mut header = String::new();
+        if let Some(name) = self.function_names.get(&index) {
+            write!(header, "${} ", name).unwrap();
+        }
+        write!(header, "(;{};) (type {})", index, type_index).unwrap();
+
+        if let Some(signature) = self.types.get(type_index as usize) {
+            header.push_str(signature);
+        }
+
+        header
+    }
+
+    fn types(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        for index in 0..section.u32()? {
+            let form = section.u8()?;
+            if form != Signature::SEPARATOR {
+                return Err(section.error(format!("Invalid function type 0x{:02x}", form)));
+            }
+
+            let mut signature = String::new();
+
+            let param_count = section.u32()?;
+            if param_count > 0 {
+                signature.push_str(" (param");
+                for _ in 0..param_count {
+                    write!(signature, " {}", section.value_type()?).unwrap();
+                }
+                signature.push(')');
+            }
+
+            let result_count = section.u32()?;
+            if result_count > 0 {
+                signature.push_str(" (result");
+                for _ in 0..result_count {
+                    write!(signature, " {}", section.value_type()?).unwrap();
+                }
+                signature.push(')');
+            }
+
+            writeln!(
+                self.out,
+                "{}(type (;{};) (func{}))",
+                INDENT, index, signature
+            )
+            .unwrap();
+            self.types.push(signature);
+        }
+
+        Ok(())
+    }
+
+    fn imports(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        let mut table_count = 0;
+        let mut memory_count = 0;
+
+        for _ in 0..section.u32()? {
+            let module = section.name()?;
+            let name = section.name()?;
+
+            let description = match section.u8()? {
+                0 => {
+                    let type_index = section.u32()?;
+                    let header = self.function_header(self.imported_fn_count, type_index);
+                    self.imported_fn_count += 1;
+                    format!("(func {})", header)
+                }
+                1 => {
+                    table_count += 1;
+                    format!("(table (;{};) {})", table_count - 1, section.table_type()?)
+                }
+                2 => {
+                    memory_count += 1;
+                    format!("(memory (;{};) {})", memory_count - 1, section.limits()?)
+                }
+                3 => {
+                    self.imported_global_count += 1;
+                    format!(
+                        "(global (;{};) {})",
+                        self.imported_global_count - 1,
+                        section.global_type()?
+                    )
+                }
+                kind => return Err(section.error(format!("Invalid import kind 0x{:02x}", kind))),
+            };
+
+            writeln!(
+                self.out,
+                "{}(import {} {} {})",
+                INDENT,
+                string_literal(module.as_bytes()),
+                string_literal(name.as_bytes()),
+                description
+            )
+            .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn globals(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        for i in 0..section.u32()? {
+            let global_type = section.global_type()?;
+            let init = self.const_expr(section)?;
+            writeln!(
+                self.out,
+                "{}(global (;{};) {} ({}))",
+                INDENT,
+                self.imported_global_count + i,
+                global_type,
+                init
+            )
+            .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn exports(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        for _ in 0..section.u32()? {
+            let name = section.name()?;
+            let kind = section.u8()?;
+            let index = section.u32()?;
+
+            let target = match kind {
+                0 => format!("func {}", self.function_ref(index)),
+                1 => format!("table {}", index),
+                2 => format!("memory {}", index),
+                3 => format!("global {}", index),
+                _ => return Err(section.error(format!("Invalid export kind 0x{:02x}", kind))),
+            };
+
+            writeln!(
+                self.out,
+                "{}(export {} ({}))",
+                INDENT,
+                string_literal(name.as_bytes()),
+                target
+            )
+            .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn elements(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        for index in 0..section.u32()? {
+            // We only write the MVP format, for the function table
+            let format = section.u8()?;
+            if format != 0 {
+                return Err(section.error(format!("Unsupported element segment format {}", format)));
+            }
+
+            let offset = self.const_expr(section)?;
+            write!(self.out, "{}(elem (;{};) ({}) func", INDENT, index, offset).unwrap();
+            for _ in 0..section.u32()? {
+                let function = self.function_ref(section.u32()?);
+                write!(self.out, " {}", function).unwrap();
+            }
+            self.out.push_str(")\n");
+        }
+
+        Ok(())
+    }
+
+    fn data(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        for index in 0..section.u32()? {
+            let offset = match section.u32()? {
+                0 => Some(self.const_expr(section)?),
+                1 => None,
+                2 => {
+                    let _memory_index = section.u32()?;
+                    Some(self.const_expr(section)?)
+                }
+                mode => return Err(section.error(format!("Invalid data segment mode {}", mode))),
+            };
+
+            let size = section.u32()? as usize;
+            let init = section.take(size)?;
+
+            write!(self.out, "{}(data (;{};)", INDENT, index).unwrap();
+            if let Some(offset) = offset {
+                write!(self.out, " ({})", offset).unwrap();
+            }
+            writeln!(self.out, " {})", string_literal(init)).unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// A constant expression, like the initial value of a global. Returns it without parentheses.
+    fn const_expr(&self, section: &mut Reader) -> Result<String, ParseError> {
+        let mut instructions = Vec::new();
+
+        loop {
+            let offset = section.cursor;
+            match opcode(section.u8()?) {
+                Some(OpCode::END) => break,
+                Some(op) => instructions.push(self.instruction(op, section)?),
+                None => return Err(section.error_at(offset, "Invalid constant expression")),
+            }
+        }
+
+        Ok(instructions.join(") ("))
+    }
+
+    fn code(&mut self, section: &mut Reader) -> Result<(), ParseError> {
+        let count = section.u32()?;
+        if count as usize != self.defined_fn_types.len() {
+            return Err(section.error(format!(
+                "The Code section has {} functions, but the Function section has {}",
+                count,
+                self.defined_fn_types.len()
+            )));
+        }
+
+        for i in 0..count {
+            let size = section.u32()? as usize;
+            let start = section.cursor;
+            section.take(size)?;
+            let mut body = section.at(start, start + size);
+
+            let index = self.imported_fn_count + i;
+            let header = self.function_header(index, self.defined_fn_types[i as usize]);
+            writeln!(self.out, "{}(func {}", INDENT, header).unwrap();
+
+            self.function_body(&mut body)?;
+
+            writeln!(self.out, "{})", INDENT).unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn function_body(&mut self, body: &mut Reader) -> Result<(), ParseError> {
+        let local_groups = body.u32()?;
+        if local_groups > 0 {
+            write!(self.out, "{0}{0}(local", INDENT).unwrap();
+            for _ in 0..local_groups {
+                let count = body.u32()?;
+                let value_type = body.value_type()?;
+                for _ in 0..count {
+                    write!(self.out, " {}", value_type).unwrap();
+                }
+            }
+            self.out.push_str(")\n");
+        }
+
+        // the function body itself is a block, closed by the final END
+        let mut depth = 1;
+
+        while !body.is_at_end() {
+            let offset = body.cursor;
+            let op = match opcode(body.u8()?) {
+                Some(op) => op,
+                None => {
+                    writeln!(
+                        self.out,
+                        "{0}{0};; unknown instruction 0x{1:02x}, skipping the remaining {2} bytes",
+                        INDENT,
+                        body.bytes[offset],
+                        body.bytes.len() - offset
+                    )
+                    .unwrap();
+                    return Ok(());
+                }
+            };
+
+            if matches!(op, OpCode::END | OpCode::ELSE) {
+                depth -= 1;
+            }
+
+            if depth == 0 {
+                break;
+            }
+
+            let instruction = self.instruction(op, body)?;
+            writeln!(
+                self.out,
+                "{}{}{}",
+                INDENT,
+                INDENT.repeat(depth),
+                instruction
+            )
+            .unwrap();
+
+            if matches!(op, OpCode::BLOCK | OpCode::LOOP | OpCode::IF | OpCode::ELSE) {
+                depth += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// An instruction and its immediates
+    fn instruction(&self, op: OpCode, reader: &mut Reader) -> Result<String, ParseError> {
+        use OpCode::*;
+
+        let name = instruction_name(op);
+
+        let text = match op {
+            BLOCK | LOOP | IF => {
+                let offset = reader.cursor;
+                match reader.u8()? {
+                    0x40 => name.to_string(),
+                    byte => match value_type_name(byte) {
+                        Some(value_type) => format!("{} (result {})", name, value_type),
+                        None => {
+                            reader.cursor = offset;
+                            format!("{} (type {})", name, reader.i64()?)
+                        }
+                    },
+                }
+            }
+            BR | BRIF => format!("{} {}", name, reader.u32()?),
+            BRTABLE => {
+                let mut text = name.to_string();
+                // the labels, then the default label
+                for _ in 0..=reader.u32()? {
+                    write!(text, " {}", reader.u32()?).unwrap();
+                }
+                text
+            }
+            CALL => format!("{} {}", name, self.function_ref(reader.u32()?)),
+            CALLINDIRECT => {
+                let type_index = reader.u32()?;
+                let _table_index = reader.u32()?;
+                format!("{} (type {})", name, type_index)
+            }
+            GETLOCAL | SETLOCAL | TEELOCAL | GETGLOBAL | SETGLOBAL => {
+                format!("{} {}", name, reader.u32()?)
+            }
+            I32LOAD | I64LOAD | F32LOAD | F64LOAD | I32LOAD8S | I32LOAD8U | I32LOAD16S
+            | I32LOAD16U | I64LOAD8S | I64LOAD8U | I64LOAD16S | I64LOAD16U | I64LOAD32S
+            | I64LOAD32U | I32STORE | I64STORE | F32STORE | F64STORE | I32STORE8 | I32STORE16
+            | I64STORE8 | I64STORE16 | I64STORE32 => {
+                let align_log2 = reader.u32()?;
+                let offset = reader.u32()?;
+                let mut text = name.to_string();
+                if offset != 0 {
+                    write!(text, " offset={}", offset).unwrap();
+                }
+                write!(text, " align={}", 1u64 << align_log2.min(63)).unwrap();
+                text
+            }
+            CURRENTMEMORY | GROWMEMORY => {
+                let _memory_index = reader.u8()?;
+                name.to_string()
+            }
+            I32CONST => format!("{} {}", name, reader.i64()? as i32),
+            I64CONST => format!("{} {}", name, reader.i64()?),
+            F32CONST => {
+                let bits = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+                format!(
+                    "{} {}",
+                    name,
+                    float_literal(f32::from_bits(bits) as f64, false)
+                )
+            }
+            F64CONST => {
+                let bits = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+                format!("{} {}", name, float_literal(f64::from_bits(bits), true))
+            }
+            _ => name.to_string(),
+        };
+
+        Ok(text)
+    }
+}
+
+/// A cursor into part of the binary, which never reads past its end
+struct Reader<'b> {
+    bytes: &'b [u8],
+    cursor: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn at(&self, start: usize, end: usize) -> Reader<'b> {
+        Reader {
+            bytes: &self.bytes[..end],
+            cursor: start,
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.cursor >= self.bytes.len()
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.cursor, message)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            offset,
+            message: message.into(),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'b [u8], ParseError> {
+        let end = self.cursor + len;
+        if end > self.bytes.len() {
+            return Err(self.error("Unexpected end of the binary"));
+        }
+
+        let slice = &self.bytes[self.cursor..end];
+        self.cursor = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, ParseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ParseError> {
+        let offset = self.cursor;
+        let mut value: u64 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return u32::try_from(value)
+                    .map_err(|_| self.error_at(offset, "Invalid LEB-128 encoding of a u32"));
+            }
+        }
+        Err(self.error_at(offset, "Invalid LEB-128 encoding of a u32"))
+    }
+
+    /// A signed LEB-128 integer. Also used for i32, which is encoded the same way.
+    fn i64(&mut self) -> Result<i64, ParseError> {
+        let offset = self.cursor;
+        let mut value: i64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+            if shift >= 70 {
+                return Err(self.error_at(offset, "Invalid LEB-128 encoding of an i64"));
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn value_type(&mut self) -> Result<&'static str, ParseError> {
+        let offset = self.cursor;
+        let byte = self.u8()?;
+        value_type_name(byte)
+            .ok_or_else(|| self.error_at(offset, format!("Invalid value type 0x{:02x}", byte)))
+    }
+
+    fn limits(&mut self) -> Result<String, ParseError> {
+        match self.u8()? {
+            0 => Ok(self.u32()?.to_string()),
+            1 => Ok(format!("{} {}", self.u32()?, self.u32()?)),
+            kind => Err(self.error(format!("Invalid limits 0x{:02x}", kind))),
+        }
+    }
+
+    fn table_type(&mut self) -> Result<String, ParseError> {
+        let ref_type = match self.u8()? {
+            0x70 => "funcref",
+            0x6f => "externref",
+            byte => return Err(self.error(format!("Invalid reference type 0x{:02x}", byte))),
+        };
+        Ok(format!("{} {}", self.limits()?, ref_type))
+    }
+
+    fn global_type(&mut self) -> Result<String, ParseError> {
+        let value_type = self.value_type()?;
+        match self.u8()? {
+            0 => Ok(value_type.to_string()),
+            _ => Ok(format!("(mut {})", value_type)),
+        }
+    }
+}
+
+fn value_type_name(byte: u8) -> Option<&'static str> {
+    match byte {
+        0x7f => Some("i32"),
+        0x7e => Some("i64"),
+        0x7d => Some("f32"),
+        0x7c => Some("f64"),
+        _ => None,
+    }
+}
+
+fn section_id(byte: u8) -> Option<SectionId> {
+    match byte {
+        0..=12 => Some(unsafe { std::mem::transmute::<u8, SectionId>(byte) }),
+        _ => None,
+    }
+}
+
+/// Only the bytes that are listed in `OpCode` are valid to convert
+fn opcode(byte: u8) -> Option<OpCode> {
+    match byte {
+        0x00..=0x05 | 0x0b..=0x11 | 0x1a | 0x1b | 0x20..=0x24 | 0x28..=0xbf => {
+            Some(unsafe { std::mem::transmute::<u8, OpCode>(byte) })
+        }
+        _ => None,
+    }
+}
+
+/// Characters that are allowed in a WAT identifier, like `$main`
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '0'..='9' | 'a'..='z' | 'A'..='Z' => c,
+            '!' | '#' | '$' | '%' | '&' | '\'' | '*' | '+' | '-' | '.' | '/' | ':' | '<' | '='
+            | '>' | '?' | '@' | '\\' | '^' | '_' | '`' | '|' | '~' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn string_literal(bytes: &[u8]) -> String {
+    let mut literal = String::with_capacity(bytes.len() + 2);
+    literal.push('"');
+    for &byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(literal, "\\{}", byte as char).unwrap(),
+            0x20..=0x7e => literal.push(byte as char),
+            _ => write!(literal, "\\{:02x}", byte).unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn float_literal(value: f64, is_f64: bool) -> String {
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+
+    if value.is_nan() {
+        // keep the payload, so the text means exactly the same bits
+        let payload = if is_f64 {
+            value.to_bits() & ((1 << 52) - 1)
+        } else {
+            ((value as f32).to_bits() & ((1 << 23) - 1)) as u64
+        };
+        format!("{}nan:0x{:x}", sign, payload)
+    } else if value.is_infinite() {
+        format!("{}inf", sign)
+    } else if is_f64 {
+        format!("{:?}", value)
+    } else {
+        format!("{:?}", value as f32)
+    }
+}
+
+fn instruction_name(op: OpCode) -> &'static str {
+    use OpCode::*;
+
+    match op {
+        UNREACHABLE => "unreachable",
+        NOP => "nop",
+        BLOCK => "block",
+        LOOP => "loop",
+        IF => "if",
+        ELSE => "else",
+        END => "end",
+        BR => "br",
+        BRIF => "br_if",
+        BRTABLE => "br_table",
+        RETURN => "return",
+        CALL => "call",
+        CALLINDIRECT => "call_indirect",
+        DROP => "drop",
+        SELECT => "select",
+        GETLOCAL => "local.get",
+        SETLOCAL => "local.set",
+        TEELOCAL => "local.tee",
+        GETGLOBAL => "global.get",
+        SETGLOBAL => "global.set",
+        I32LOAD => "i32.load",
+        I64LOAD => "i64.load",
+        F32LOAD => "f32.load",
+        F64LOAD => "f64.load",
+        I32LOAD8S => "i32.load8_s",
+        I32LOAD8U => "i32.load8_u",
+        I32LOAD16S => "i32.load16_s",
+        I32LOAD16U => "i32.load16_u",
+        I64LOAD8S => "i64.load8_s",
+        I64LOAD8U => "i64.load8_u",
+        I64LOAD16S => "i64.load16_s",
+        I64LOAD16U => "i64.load16_u",
+        I64LOAD32S => "i64.load32_s",
+        I64LOAD32U => "i64.load32_u",
+        I32STORE => "i32.store",
+        I64STORE => "i64.store",
+        F32STORE => "f32.store",
+        F64STORE => "f64.store",
+        I32STORE8 => "i32.store8",
+        I32STORE16 => "i32.store16",
+        I64STORE8 => "i64.store8",
+        I64STORE16 => "i64.store16",
+        I64STORE32 => "i64.store32",
+        CURRENTMEMORY => "memory.size",
+        GROWMEMORY => "memory.grow",
+        I32CONST => "i32.const",
+        I64CONST => "i64.const",
+        F32CONST => "f32.const",
+        F64CONST => "f64.const",
+        I32EQZ => "i32.eqz",
+        I32EQ => "i32.eq",
+        I32NE => "i32.ne",
+        I32LTS => "i32.lt_s",
+        I32LTU => "i32.lt_u",
+        I32GTS => "i32.gt_s",
+        I32GTU => "i32.gt_u",
+        I32LES => "i32.le_s",
+        I32LEU => "i32.le_u",
+        I32GES => "i32.ge_s",
+        I32GEU => "i32.ge_u",
+        I64EQZ => "i64.eqz",
+        I64EQ => "i64.eq",
+        I64NE => "i64.ne",
+        I64LTS => "i64.lt_s",
+        I64LTU => "i64.lt_u",
+        I64GTS => "i64.gt_s",
+        I64GTU => "i64.gt_u",
+        I64LES => "i64.le_s",
+        I64LEU => "i64.le_u",
+        I64GES => "i64.ge_s",
+        I64GEU => "i64.ge_u",
+        F32EQ => "f32.eq",
+        F32NE => "f32.ne",
+        F32LT => "f32.lt",
+        F32GT => "f32.gt",
+        F32LE => "f32.le",
+        F32GE => "f32.ge",
+        F64EQ => "f64.eq",
+        F64NE => "f64.ne",
+        F64LT => "f64.lt",
+        F64GT => "f64.gt",
+        F64LE => "f64.le",
+        F64GE => "f64.ge",
+        I32CLZ => "i32.clz",
+        I32CTZ => "i32.ctz",
+        I32POPCNT => "i32.popcnt",
+        I32ADD => "i32.add",
+        I32SUB => "i32.sub",
+        I32MUL => "i32.mul",
+        I32DIVS => "i32.div_s",
+        I32DIVU => "i32.div_u",
+        I32REMS => "i32.rem_s",
+        I32REMU => "i32.rem_u",
+        I32AND => "i32.and",
+        I32OR => "i32.or",
+        I32XOR => "i32.xor",
+        I32SHL => "i32.shl",
+        I32SHRS => "i32.shr_s",
+        I32SHRU => "i32.shr_u",
+        I32ROTL => "i32.rotl",
+        I32ROTR => "i32.rotr",
+        I64CLZ => "i64.clz",
+        I64CTZ => "i64.ctz",
+        I64POPCNT => "i64.popcnt",
+        I64ADD => "i64.add",
+        I64SUB => "i64.sub",
+        I64MUL => "i64.mul",
+        I64DIVS => "i64.div_s",
+        I64DIVU => "i64.div_u",
+        I64REMS => "i64.rem_s",
+        I64REMU => "i64.rem_u",
+        I64AND => "i64.and",
+        I64OR => "i64.or",
+        I64XOR => "i64.xor",
+        I64SHL => "i64.shl",
+        I64SHRS => "i64.shr_s",
+        I64SHRU => "i64.shr_u",
+        I64ROTL => "i64.rotl",
+        I64ROTR => "i64.rotr",
+        F32ABS => "f32.abs",
+        F32NEG => "f32.neg",
+        F32CEIL => "f32.ceil",
+        F32FLOOR => "f32.floor",
+        F32TRUNC => "f32.trunc",
+        F32NEAREST => "f32.nearest",
+        F32SQRT => "f32.sqrt",
+        F32ADD => "f32.add",
+        F32SUB => "f32.sub",
+        F32MUL => "f32.mul",
+        F32DIV => "f32.div",
+        F32MIN => "f32.min",
+        F32MAX => "f32.max",
+        F32COPYSIGN => "f32.copysign",
+        F64ABS => "f64.abs",
+        F64NEG => "f64.neg",
+        F64CEIL => "f64.ceil",
+        F64FLOOR => "f64.floor",
+        F64TRUNC => "f64.trunc",
+        F64NEAREST => "f64.nearest",
+        F64SQRT => "f64.sqrt",
+        F64ADD => "f64.add",
+        F64SUB => "f64.sub",
+        F64MUL => "f64.mul",
+        F64DIV => "f64.div",
+        F64MIN => "f64.min",
+        F64MAX => "f64.max",
+        F64COPYSIGN => "f64.copysign",
+        I32WRAPI64 => "i32.wrap_i64",
+        I32TRUNCSF32 => "i32.trunc_f32_s",
+        I32TRUNCUF32 => "i32.trunc_f32_u",
+        I32TRUNCSF64 => "i32.trunc_f64_s",
+        I32TRUNCUF64 => "i32.trunc_f64_u",
+        I64EXTENDSI32 => "i64.extend_i32_s",
+        I64EXTENDUI32 => "i64.extend_i32_u",
+        I64TRUNCSF32 => "i64.trunc_f32_s",
+        I64TRUNCUF32 => "i64.trunc_f32_u",
+        I64TRUNCSF64 => "i64.trunc_f64_s",
+        I64TRUNCUF64 => "i64.trunc_f64_u",
+        F32CONVERTSI32 => "f32.convert_i32_s",
+        F32CONVERTUI32 => "f32.convert_i32_u",
+        F32CONVERTSI64 => "f32.convert_i64_s",
+        F32CONVERTUI64 => "f32.convert_i64_u",
+        F32DEMOTEF64 => "f32.demote_f64",
+        F64CONVERTSI32 => "f64.convert_i32_s",
+        F64CONVERTUI32 => "f64.convert_i32_u",
+        F64CONVERTSI64 => "f64.convert_i64_s",
+        F64CONVERTUI64 => "f64.convert_i64_u",
+        F64PROMOTEF32 => "f64.promote_f32",
+        I32REINTERPRETF32 => "i32.reinterpret_f32",
+        I64REINTERPRETF64 => "i64.reinterpret_f64",
+        F32REINTERPRETI32 => "f32.reinterpret_i32",
+        F64REINTERPRETI64 => "f64.reinterpret_i64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_to_wat() {
+        let bytes: &[u8] = &[
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+            0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f, // type 0: i32 -> i32
+            0x03, 0x02, 0x01, 0x00, // function 0 has type 0
+            0x07, 0x08, 0x01, 0x04, b'm', b'a', b'i', b'n', 0x00, 0x00, // export "main"
+            0x0a, 0x10, 0x01, 0x0e, 0x01, 0x01, 0x7f, // code: 1 local
+            0x20, 0x00, // local.get 0
+            0x04, 0x7f, 0x41, 0x7f, 0x05, 0x41, 0x2a, 0x0b, // if (result i32) -1 else 42 end
+            0x0b, // end of function
+            0x00, 0x0d, 0x04, b'n', b'a', b'm', b'e', // custom section "name"
+            0x01, 0x06, 0x01, 0x00, 0x03, b'a', b'd', b'd', // function names
+        ];
+
+        let expected = concat!(
+            "(module\n",
+            "  (type (;0;) (func (param i32) (result i32)))\n",
+            "  (export \"main\" (func $add))\n",
+            "  (func $add (;0;) (type 0) (param i32) (result i32)\n",
+            "    (local i32)\n",
+            "    local.get 0\n",
+            "    if (result i32)\n",
+            "      i32.const -1\n",
+            "    else\n",
+            "      i32.const 42\n",
+            "    end\n",
+            "  )\n",
+            ")\n",
+        );
+
+        assert_eq!(binary_to_wat(bytes).unwrap(), expected);
+    }
+
+    /// The sections of a binary without the custom ones, whose encoding depends on the tool
+    fn standard_sections(bytes: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut reader = Reader { bytes, cursor: 8 };
+        let mut sections = Vec::new();
+
+        while !reader.is_at_end() {
+            let id = reader.u8().unwrap();
+            let size = reader.u32().unwrap() as usize;
+            let section = reader.take(size).unwrap();
+            if id != SectionId::Custom as u8 {
+                sections.push((id, section));
+            }
+        }
+
+        sections
+    }
+
+    /// Assemble `src` with the reference implementation, render the binary, and check that
+    /// assembling the rendering gives the same binary back. Returns the rendering.
+    fn assert_round_trip(src: &str) -> String {
+        let bytes = wat::parse_str(src).unwrap();
+        let rendered = binary_to_wat(&bytes).unwrap();
+
+        let reassembled = wat::parse_str(&rendered)
+            .unwrap_or_else(|e| panic!("{}\n\nThe rendering is not valid WAT:\n{}", e, rendered));
+
+        assert_eq!(
+            standard_sections(&reassembled),
+            standard_sections(&bytes),
+            "{}",
+            rendered
+        );
+        assert_eq!(binary_to_wat(&reassembled).unwrap(), rendered);
+
+        rendered
+    }
+
+    #[test]
+    fn round_trip_control_flow() {
+        let rendered = assert_round_trip(
+            r#"
+            (module
+              (type $t (func (param i32) (result i32)))
+              (table 2 funcref)
+              (elem (i32.const 0) $double $choose)
+              (func $double (type $t)
+                local.get 0
+                i32.const 2
+                i32.mul)
+              (func $choose (param i32) (result i32) (local i64 f64)
+                block
+                  loop
+                    local.get 0
+                    br_if 1
+                    local.get 0
+                    i32.const 1
+                    i32.add
+                    local.tee 0
+                    i32.const 10
+                    i32.lt_s
+                    br_if 0
+                  end
+                end
+                block (result i32)
+                  local.get 0
+                  br_table 0 0 0
+                end
+                if (result i32)
+                  i32.const 1
+                  i32.const 0
+                  call_indirect (type $t)
+                else
+                  local.get 0
+                  call $double
+                  return
+                end
+                i32.const 7
+                local.get 0
+                select
+                drop
+                nop
+                unreachable))
+            "#,
+        );
+
+        assert!(rendered.contains("    br_table 0 0 0\n"), "{}", rendered);
+        assert!(rendered.contains("(elem (;0;) (i32.const 0) func $double $choose)"));
+        assert!(rendered.contains("(local i64 f64)"));
+    }
+
+    #[test]
+    fn round_trip_memory_and_globals() {
+        let rendered = assert_round_trip(
+            r#"
+            (module
+              (import "env" "memory" (memory 1))
+              (import "env" "log" (func $log (param i32 i32)))
+              (import "env" "__stack_pointer" (global (mut i32)))
+              (global i64 (i64.const -1))
+              (global (mut f32) (f32.const 1.5))
+              (func $store (param i32)
+                global.get 0
+                i32.const 16
+                i32.sub
+                global.set 0
+                local.get 0
+                i64.load offset=8 align=4
+                i64.store32 offset=4
+                local.get 0
+                i32.load8_u
+                local.get 0
+                i32.load16_s offset=2 align=1
+                call $log
+                memory.size
+                memory.grow
+                drop)
+              (data (i32.const 1024) "Hello\00\ff\"world\"")
+              (export "store" (func $store))
+              (export "sp" (global 0)))
+            "#,
+        );
+
+        assert!(rendered.contains("(import \"env\" \"memory\" (memory (;0;) 1))"));
+        assert!(rendered.contains("(global (;1;) i64 (i64.const -1))"));
+        assert!(
+            rendered.contains("i64.load offset=8 align=4\n"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn round_trip_numbers() {
+        assert_round_trip(
+            r#"
+            (module
+              (func $numbers (result f64)
+                i32.const -2147483648
+                i32.const 2147483647
+                i32.rem_u
+                i64.extend_i32_u
+                i64.const -9223372036854775808
+                i64.rotl
+                f32.convert_i64_s
+                f32.const -0x1p-149
+                f32.const inf
+                f32.min
+                f32.const nan
+                f32.max
+                f32.copysign
+                f64.promote_f32
+                f64.const 0.1
+                f64.const -inf
+                f64.const 1.7976931348623157e308
+                f64.add
+                f64.div
+                f64.sqrt
+                f64.add
+                i32.trunc_f64_s
+                f64.convert_i32_u
+                i64.reinterpret_f64
+                f64.reinterpret_i64))
+            "#,
+        );
+    }
+
+    #[test]
+    fn unnamed_and_clashing_functions() {
+        let mut bytes = wat::parse_str(
+            r#"
+            (module
+              (func (result i32) i32.const 1)
+              (func $a (result i32) call 0)
+              (func $b (result i32) call 1))
+            "#,
+        )
+        .unwrap();
+
+        // Names from different object files can clash after linking. Rename `b` to `a`.
+        let name_b = bytes.len() - bytes.iter().rev().position(|&b| b == b'b').unwrap() - 1;
+        bytes[name_b] = b'a';
+
+        let rendered = binary_to_wat(&bytes).unwrap();
+
+        assert!(
+            rendered.contains("(func (;0;) (type 0) (result i32)"),
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.contains("(func $a (;1;) (type 0) (result i32)"),
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.contains("(func $a.2 (;2;) (type 0) (result i32)"),
+            "{}",
+            rendered
+        );
+        assert!(rendered.contains("    call $a\n"), "{}", rendered);
+        assert!(wat::parse_str(&rendered).is_ok(), "{}", rendered);
+    }
+}
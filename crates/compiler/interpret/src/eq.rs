@@ -0,0 +1,101 @@
+//! Structural equality, for the `Eq` and `NotEq` lowlevels.
+use roc_mono::layout::{Builtin, Layout, UnionLayout};
+
+use crate::memory::{read, str_bytes, RocSeq};
+use crate::num::read_float;
+use crate::tags::{field_offset, layout_size, read_tag_id, resolve_field, tag_fields, union_data};
+
+/// # Safety
+/// Both pointers must point to values of the given layout
+pub(crate) unsafe fn values_equal(
+    a: *const u8,
+    b: *const u8,
+    layout: &Layout,
+    when_recursive: Option<&UnionLayout>,
+) -> bool {
+    match layout {
+        Layout::Builtin(builtin) => match builtin {
+            Builtin::Float(width) => read_float(a, *width) == read_float(b, *width),
+            Builtin::Bool => (read::<u8>(a) != 0) == (read::<u8>(b) != 0),
+            Builtin::Int(_) | Builtin::Decimal => {
+                let size = layout_size(layout);
+                std::slice::from_raw_parts(a, size) == std::slice::from_raw_parts(b, size)
+            }
+            Builtin::Str => str_bytes(a) == str_bytes(b),
+            Builtin::List(elem_layout) => {
+                let a = RocSeq::read_list(a);
+                let b = RocSeq::read_list(b);
+                let elem_size = layout_size(elem_layout);
+
+                a.len == b.len
+                    && (0..a.len).all(|i| {
+                        values_equal(
+                            a.ptr.add(i * elem_size),
+                            b.ptr.add(i * elem_size),
+                            elem_layout,
+                            when_recursive,
+                        )
+                    })
+            }
+        },
+        Layout::Struct { field_layouts, .. } => fields_equal(a, b, field_layouts, when_recursive),
+        Layout::LambdaSet(lambda_set) => {
+            values_equal(a, b, &lambda_set.runtime_representation(), when_recursive)
+        }
+        Layout::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+            let tag_id = read_tag_id(a, union_layout);
+
+            tag_id == read_tag_id(b, union_layout)
+                && fields_equal(a, b, tag_fields(union_layout, tag_id), when_recursive)
+        }
+        Layout::Union(union_layout) => {
+            let tag_id = read_tag_id(a, union_layout);
+
+            if tag_id != read_tag_id(b, union_layout) {
+                return false;
+            }
+
+            let a_data = union_data(a, union_layout);
+            let b_data = union_data(b, union_layout);
+
+            if a_data == b_data {
+                return true;
+            }
+
+            let fields = tag_fields(union_layout, tag_id);
+
+            fields.iter().enumerate().all(|(i, field)| {
+                let offset = field_offset(fields, i);
+
+                values_equal(
+                    a_data.add(offset),
+                    b_data.add(offset),
+                    &resolve_field(field, union_layout),
+                    Some(union_layout),
+                )
+            })
+        }
+        Layout::Boxed(inner) => values_equal(
+            read::<*const u8>(a),
+            read::<*const u8>(b),
+            inner,
+            when_recursive,
+        ),
+        Layout::RecursivePointer => match when_recursive {
+            Some(union_layout) => values_equal(a, b, &Layout::Union(*union_layout), when_recursive),
+            None => roc_error_macros::internal_error!("recursive pointer outside of a union"),
+        },
+    }
+}
+
+unsafe fn fields_equal(
+    a: *const u8,
+    b: *const u8,
+    field_layouts: &[Layout],
+    when_recursive: Option<&UnionLayout>,
+) -> bool {
+    field_layouts.iter().enumerate().all(|(i, field)| {
+        let offset = field_offset(field_layouts, i);
+        values_equal(a.add(offset), b.add(offset), field, when_recursive)
+    })
+}
@@ -0,0 +1,267 @@
+//! Failed expects, serialized the way the LLVM backend writes them to shared memory.
+//!
+//! The buffer starts with a header of two `usize`s: the number of failures, and the offset at
+//! which the next failure will be written. Every failure is a frame with the region of the
+//! expect, its module, the offsets of the looked-up values and then the values themselves.
+//! Pointers inside those values are replaced by offsets into the buffer, which is what
+//! `roc_repl_expect` expects when it renders the failures.
+use roc_module::symbol::ModuleId;
+use roc_mono::layout::{Builtin, Layout, UnionLayout};
+use roc_region::all::Region;
+
+use crate::memory::{read, str_allocation, str_bytes, RocSeq, PTR_SIZE, STR_SIZE};
+use crate::tags::{field_offset, layout_size, read_tag_id, resolve_field, tag_fields, union_data};
+use crate::TARGET_INFO;
+
+const START_OFFSET: usize = 2 * PTR_SIZE;
+
+pub(crate) struct ExpectBuffer {
+    bytes: Vec<u8>,
+}
+
+impl ExpectBuffer {
+    pub(crate) fn new() -> Self {
+        let mut buffer = Self { bytes: Vec::new() };
+        buffer.clear();
+
+        buffer
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.bytes.clear();
+        self.bytes.resize(START_OFFSET, 0);
+        self.put(PTR_SIZE, &START_OFFSET.to_ne_bytes());
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.get_usize(0)
+    }
+
+    /// # Safety
+    /// Every pointer in `lookups` must point to a value of its layout
+    pub(crate) unsafe fn push_failure(
+        &mut self,
+        region: Region,
+        module_id: ModuleId,
+        lookups: &[(*const u8, Layout)],
+    ) {
+        let mut offset = self.get_usize(PTR_SIZE);
+
+        let region_bytes: [u8; 8] = std::mem::transmute(region);
+        let module_id_bytes: [u8; 4] = std::mem::transmute(module_id);
+
+        offset = self.put(offset, &region_bytes);
+        offset = self.put(offset, &module_id_bytes);
+
+        let lookup_offsets = offset;
+        offset += lookups.len() * PTR_SIZE;
+
+        for (i, (value, layout)) in lookups.iter().enumerate() {
+            self.put(lookup_offsets + i * PTR_SIZE, &offset.to_ne_bytes());
+
+            let extra_offset = offset + layout_size(layout);
+            offset = self.clone_value(offset, extra_offset, *value, layout, None);
+        }
+
+        let count = self.count();
+        self.put(0, &(count + 1).to_ne_bytes());
+        self.put(PTR_SIZE, &offset.to_ne_bytes());
+    }
+
+    fn get_usize(&self, offset: usize) -> usize {
+        let mut bytes = [0; PTR_SIZE];
+        bytes.copy_from_slice(&self.bytes[offset..][..PTR_SIZE]);
+
+        usize::from_ne_bytes(bytes)
+    }
+
+    /// Write the bytes at the offset, growing the buffer if needed. Returns the offset just
+    /// after the bytes.
+    fn put(&mut self, offset: usize, bytes: &[u8]) -> usize {
+        let end = offset + bytes.len();
+
+        if self.bytes.len() < end {
+            self.bytes.resize(end, 0);
+        }
+
+        self.bytes[offset..end].copy_from_slice(bytes);
+
+        end
+    }
+
+    /// Clone a value to `offset`, with the data it points to stored from `extra_offset` onwards.
+    /// Returns the offset after the last byte of pointed-to data.
+    unsafe fn clone_value(
+        &mut self,
+        offset: usize,
+        extra_offset: usize,
+        value: *const u8,
+        layout: &Layout,
+        when_recursive: Option<&UnionLayout>,
+    ) -> usize {
+        match layout {
+            Layout::Builtin(Builtin::Str) => match str_allocation(value) {
+                None => {
+                    self.put(offset, std::slice::from_raw_parts(value, STR_SIZE));
+
+                    extra_offset
+                }
+                Some(_) => {
+                    let bytes = str_bytes(value);
+                    self.put_seq_header(offset, extra_offset, bytes.len());
+
+                    self.put(extra_offset, bytes)
+                }
+            },
+            Layout::Builtin(Builtin::List(elem_layout)) => {
+                let list = RocSeq::read_list(value);
+                let elem_size = layout_size(elem_layout);
+                self.put_seq_header(offset, extra_offset, list.len);
+
+                let mut rest_offset = extra_offset + list.len * elem_size;
+                self.put(rest_offset, &[]);
+
+                for i in 0..list.len {
+                    rest_offset = self.clone_value(
+                        extra_offset + i * elem_size,
+                        rest_offset,
+                        list.ptr.add(i * elem_size),
+                        elem_layout,
+                        when_recursive,
+                    );
+                }
+
+                rest_offset
+            }
+            Layout::Builtin(_) => {
+                self.put(
+                    offset,
+                    std::slice::from_raw_parts(value, layout_size(layout)),
+                );
+
+                extra_offset
+            }
+            Layout::Struct { field_layouts, .. } => {
+                self.clone_fields(offset, extra_offset, value, field_layouts, when_recursive)
+            }
+            Layout::LambdaSet(lambda_set) => self.clone_value(
+                offset,
+                extra_offset,
+                value,
+                &lambda_set.runtime_representation(),
+                when_recursive,
+            ),
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+                // the tag id is copied along with the payload
+                self.put(
+                    offset,
+                    std::slice::from_raw_parts(value, layout_size(layout)),
+                );
+
+                let fields = tag_fields(union_layout, read_tag_id(value, union_layout));
+
+                self.clone_fields(offset, extra_offset, value, fields, when_recursive)
+            }
+            Layout::Union(union_layout) => {
+                self.clone_recursive(offset, extra_offset, value, union_layout)
+            }
+            Layout::Boxed(inner) => {
+                self.put(offset, &extra_offset.to_ne_bytes());
+
+                self.clone_value(
+                    extra_offset,
+                    extra_offset + layout_size(inner),
+                    read::<*const u8>(value),
+                    inner,
+                    when_recursive,
+                )
+            }
+            Layout::RecursivePointer => match when_recursive {
+                Some(union_layout) => {
+                    self.clone_recursive(offset, extra_offset, value, union_layout)
+                }
+                None => roc_error_macros::internal_error!("recursive pointer outside of a union"),
+            },
+        }
+    }
+
+    unsafe fn clone_fields(
+        &mut self,
+        offset: usize,
+        mut extra_offset: usize,
+        value: *const u8,
+        field_layouts: &[Layout],
+        when_recursive: Option<&UnionLayout>,
+    ) -> usize {
+        for (i, field) in field_layouts.iter().enumerate() {
+            let field_offset = field_offset(field_layouts, i);
+
+            extra_offset = self.clone_value(
+                offset + field_offset,
+                extra_offset,
+                value.add(field_offset),
+                field,
+                when_recursive,
+            );
+        }
+
+        extra_offset
+    }
+
+    unsafe fn clone_recursive(
+        &mut self,
+        offset: usize,
+        extra_offset: usize,
+        value: *const u8,
+        union_layout: &UnionLayout,
+    ) -> usize {
+        let data = union_data(value, union_layout);
+
+        if data.is_null() {
+            self.put(offset, &0usize.to_ne_bytes());
+
+            return extra_offset;
+        }
+
+        let tag_id = read_tag_id(value, union_layout);
+
+        if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+            // offsets are not aligned, so the tag id can't go in the low bits
+            self.put(offset, &(tag_id as u32).to_ne_bytes());
+            self.put(offset + 4, &(extra_offset as u32).to_ne_bytes());
+        } else {
+            self.put(offset, &extra_offset.to_ne_bytes());
+        }
+
+        let (data_size, _) = union_layout.data_size_and_alignment(TARGET_INFO);
+        let data_size = data_size as usize;
+        self.put(extra_offset, std::slice::from_raw_parts(data, data_size));
+
+        let fields = tag_fields(union_layout, tag_id);
+        let mut rest_offset = extra_offset + data_size;
+
+        for (i, field) in fields.iter().enumerate() {
+            let field_offset = field_offset(fields, i);
+
+            rest_offset = self.clone_value(
+                extra_offset + field_offset,
+                rest_offset,
+                data.add(field_offset),
+                &resolve_field(field, union_layout),
+                Some(union_layout),
+            );
+        }
+
+        rest_offset
+    }
+
+    fn put_seq_header(&mut self, offset: usize, elements_offset: usize, len: usize) {
+        self.put(offset, &elements_offset.to_ne_bytes());
+        self.put(offset + PTR_SIZE, &len.to_ne_bytes());
+        self.put(offset + 2 * PTR_SIZE, &len.to_ne_bytes());
+    }
+}
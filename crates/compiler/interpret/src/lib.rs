@@ -0,0 +1,709 @@
+//! An interpreter for the monomorphized IR.
+//!
+//! Running Roc code with the LLVM or dev backends means generating machine code and linking
+//! it into a dylib, which is slow and needs a working linker. The interpreter walks the `Proc`s
+//! that come out of monomorphization instead, which makes it a good fit for the REPL and for
+//! `roc test` in environments without LLVM.
+//!
+//! Values are stored in host memory, with the same layout that the 64-bit backends use. That
+//! means the code that reads the results of compiled programs (e.g. `jit_to_ast`) can read the
+//! results of interpreted programs too.
+#![warn(clippy::dbg_macro)]
+// See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
+#![allow(clippy::large_enum_variant)]
+
+use bumpalo::Bump;
+use roc_collections::all::MutMap;
+use roc_error_macros::internal_error;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{
+    CallType, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc, Param, Proc, ProcLayout,
+    Stmt,
+};
+use roc_mono::layout::{Builtin, Layout, TagIdIntType, UnionLayout};
+use roc_target::TargetInfo;
+use std::fmt;
+
+mod eq;
+mod expect;
+mod low_level;
+mod memory;
+mod num;
+mod refcount;
+mod tags;
+
+use expect::ExpectBuffer;
+use memory::{copy, read, write, write_str, Heap, RocSeq, Stack};
+use num::{dec_from_f64, write_float, IntValue};
+use refcount::RcOp;
+use tags::{field_offset, layout_alignment, layout_size, tag_fields, union_data, write_tag_id};
+
+/// Programs are interpreted as if they were compiled for a 64-bit host
+pub(crate) const TARGET_INFO: TargetInfo = TargetInfo::default_x86_64();
+
+/// Space for local variables. This is only reserved, not committed, until it's used.
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Every Roc call is a native call in the interpreter, so deep recursion has to stop before it
+/// overflows the native stack.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Enough native stack for `MAX_CALL_DEPTH` nested calls, even in a debug build of the
+/// compiler. Like `STACK_SIZE`, this is only reserved until it's used.
+const NATIVE_STACK_SIZE: usize = 1024 * 1024 * 1024;
+
+/// Run `f` on a thread with enough native stack for the interpreter. The main thread usually
+/// has only a few megabytes, which deeply recursive programs run out of well before they reach
+/// the call depth that the interpreter allows.
+pub fn with_native_stack<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let thread = std::thread::Builder::new()
+        .name("roc_interpret".to_string())
+        .stack_size(NATIVE_STACK_SIZE)
+        .spawn(f)
+        .expect("failed to spawn the interpreter thread");
+
+    match thread.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpError {
+    /// The program crashed, e.g. because an integer overflowed
+    Panic(String),
+    /// The program recursed too deeply
+    StackOverflow,
+    /// The program needs something that the interpreter does not implement yet
+    Unsupported(String),
+    /// There is no procedure with this name and layout
+    UnknownProc(Symbol),
+}
+
+impl fmt::Display for InterpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpError::Panic(message) => write!(f, "{}", message),
+            InterpError::StackOverflow => write!(f, "stack overflow"),
+            InterpError::Unsupported(what) => {
+                write!(f, "the interpreter does not support {} yet", what)
+            }
+            InterpError::UnknownProc(symbol) => {
+                write!(f, "there is no procedure named {:?}", symbol)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InterpError {}
+
+pub(crate) fn panic_with<T>(message: &str) -> Result<T, InterpError> {
+    Err(InterpError::Panic(message.to_string()))
+}
+
+/// A value in memory, and its layout
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Value<'a> {
+    pub ptr: *mut u8,
+    pub layout: Layout<'a>,
+}
+
+/// The local variables of a procedure call
+#[derive(Default)]
+struct Frame<'a> {
+    values: MutMap<Symbol, Value<'a>>,
+}
+
+impl<'a> Frame<'a> {
+    fn get(&self, symbol: Symbol) -> Value<'a> {
+        match self.values.get(&symbol) {
+            Some(value) => *value,
+            None => internal_error!("symbol {:?} is not defined", symbol),
+        }
+    }
+
+    fn bind(&mut self, symbol: Symbol, ptr: *mut u8, layout: Layout<'a>) {
+        self.values.insert(symbol, Value { ptr, layout });
+    }
+}
+
+/// How a statement finished
+enum Flow<'a> {
+    Return(Symbol),
+    Jump(JoinPointId, &'a [Symbol]),
+}
+
+pub struct Interpreter<'a> {
+    procs: &'a MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    stack: Stack,
+    heap: Heap,
+    expects: ExpectBuffer,
+    call_depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(arena: &'a Bump, procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>) -> Self {
+        Self {
+            procs: arena.alloc(procedures),
+            stack: Stack::new(STACK_SIZE),
+            heap: Heap::default(),
+            expects: ExpectBuffer::new(),
+            call_depth: 0,
+        }
+    }
+
+    /// Run a procedure that takes no arguments, e.g. the main function of the REPL or a
+    /// top-level expect. The result stays valid until the next call to `run_thunk`.
+    pub fn run_thunk(
+        &mut self,
+        symbol: Symbol,
+        layout: ProcLayout<'a>,
+    ) -> Result<*const u8, InterpError> {
+        debug_assert!(layout.arguments.is_empty());
+
+        self.stack.reset(0);
+        self.call_depth = 0;
+
+        let proc = self.find_proc(symbol, layout)?;
+        let dest = self.alloc_value(&proc.ret_layout)?;
+
+        self.call(proc, &[], dest)?;
+
+        Ok(dest)
+    }
+
+    /// The expects that failed since the last call to `clear_expect_failures`, in the format
+    /// that the LLVM backend writes to the shared memory of `roc test`
+    pub fn expect_failures(&self) -> &[u8] {
+        self.expects.bytes()
+    }
+
+    pub fn expect_failure_count(&self) -> usize {
+        self.expects.count()
+    }
+
+    pub fn clear_expect_failures(&mut self) {
+        self.expects.clear()
+    }
+
+    /// The number of heap allocations that are still alive. Useful to find refcounting bugs.
+    pub fn live_allocations(&self) -> usize {
+        self.heap.live_allocations()
+    }
+
+    fn find_proc(
+        &self,
+        symbol: Symbol,
+        layout: ProcLayout<'a>,
+    ) -> Result<&'a Proc<'a>, InterpError> {
+        let procs: &'a MutMap<_, Proc<'a>> = self.procs;
+
+        procs
+            .get(&(symbol, layout))
+            .ok_or(InterpError::UnknownProc(symbol))
+    }
+
+    pub(crate) fn alloc_value(&mut self, layout: &Layout<'a>) -> Result<*mut u8, InterpError> {
+        self.stack
+            .alloc(layout_size(layout), layout_alignment(layout))
+    }
+
+    /// Call a procedure, writing its result to `dest`. The arguments are copied into the frame
+    /// of the callee, so they may live anywhere.
+    pub(crate) fn call(
+        &mut self,
+        proc: &'a Proc<'a>,
+        args: &[*const u8],
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        debug_assert_eq!(proc.args.len(), args.len());
+
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(InterpError::StackOverflow);
+        }
+
+        let mark = self.stack.mark();
+        let mut frame = Frame::default();
+
+        let result = self.call_help(proc, args, dest, &mut frame);
+
+        self.stack.reset(mark);
+
+        result
+    }
+
+    fn call_help(
+        &mut self,
+        proc: &'a Proc<'a>,
+        args: &[*const u8],
+        dest: *mut u8,
+        frame: &mut Frame<'a>,
+    ) -> Result<(), InterpError> {
+        for ((layout, symbol), arg) in proc.args.iter().zip(args) {
+            let ptr = self.alloc_value(layout)?;
+            unsafe { copy(ptr, *arg, layout_size(layout)) };
+            frame.bind(*symbol, ptr, *layout);
+        }
+
+        self.call_depth += 1;
+        let flow = self.exec(frame, &proc.body);
+        self.call_depth -= 1;
+
+        match flow? {
+            Flow::Return(symbol) => {
+                let value = frame.get(symbol);
+                unsafe { copy(dest, value.ptr, layout_size(&proc.ret_layout)) };
+
+                Ok(())
+            }
+            Flow::Jump(id, _) => internal_error!("jump to unknown join point {:?}", id),
+        }
+    }
+
+    fn exec(
+        &mut self,
+        frame: &mut Frame<'a>,
+        mut stmt: &'a Stmt<'a>,
+    ) -> Result<Flow<'a>, InterpError> {
+        loop {
+            match stmt {
+                Stmt::Let(symbol, expr, layout, continuation) => {
+                    let ptr = self.alloc_value(layout)?;
+                    self.eval(frame, expr, layout, ptr)?;
+                    frame.bind(*symbol, ptr, *layout);
+
+                    stmt = continuation;
+                }
+                Stmt::Switch {
+                    cond_symbol,
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    let cond = unsafe { read_switch_value(frame.get(*cond_symbol)) };
+
+                    stmt = match branches.iter().find(|(value, _, _)| *value == cond) {
+                        Some((_, _, branch)) => branch,
+                        None => default_branch.1,
+                    };
+                }
+                Stmt::Ret(symbol) => return Ok(Flow::Return(*symbol)),
+                Stmt::Refcounting(modify, continuation) => {
+                    let (symbol, op) = match *modify {
+                        ModifyRc::Inc(symbol, amount) => (symbol, RcOp::Inc(amount as isize)),
+                        ModifyRc::Dec(symbol) => (symbol, RcOp::Dec),
+                        ModifyRc::DecRef(symbol) => (symbol, RcOp::DecRef),
+                    };
+
+                    let value = frame.get(symbol);
+                    unsafe { self.modify_refcount(value.ptr, &value.layout, op) };
+
+                    stmt = continuation;
+                }
+                Stmt::Expect {
+                    condition,
+                    region,
+                    lookups,
+                    layouts,
+                    remainder,
+                } => {
+                    let is_true = unsafe { read::<u8>(frame.get(*condition).ptr) } != 0;
+
+                    if !is_true {
+                        let values: std::vec::Vec<_> = lookups
+                            .iter()
+                            .zip(layouts.iter())
+                            .map(|(symbol, layout)| (frame.get(*symbol).ptr as *const u8, *layout))
+                            .collect();
+
+                        unsafe {
+                            self.expects
+                                .push_failure(*region, condition.module_id(), &values)
+                        };
+                    }
+
+                    // the expect owns its lookups, see `roc_mono::inc_dec`
+                    for (symbol, layout) in lookups.iter().zip(layouts.iter()) {
+                        unsafe { self.decrement(frame.get(*symbol).ptr, layout) };
+                    }
+
+                    stmt = remainder;
+                }
+                Stmt::Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                } => return self.exec_join(frame, *id, parameters, body, remainder),
+                Stmt::Jump(id, arguments) => return Ok(Flow::Jump(*id, arguments)),
+                Stmt::RuntimeError(message) => return panic_with(message),
+            }
+        }
+    }
+
+    /// Join points are loops: every jump to the join point runs its body again, with the
+    /// parameters overwritten, until the body returns or jumps somewhere else.
+    fn exec_join(
+        &mut self,
+        frame: &mut Frame<'a>,
+        id: JoinPointId,
+        parameters: &'a [Param<'a>],
+        body: &'a Stmt<'a>,
+        remainder: &'a Stmt<'a>,
+    ) -> Result<Flow<'a>, InterpError> {
+        let mut slots = std::vec::Vec::with_capacity(parameters.len());
+
+        for param in parameters {
+            let ptr = self.alloc_value(&param.layout)?;
+            frame.bind(param.symbol, ptr, param.layout);
+            slots.push(ptr);
+        }
+
+        let mark = self.stack.mark();
+        let mut flow = self.exec(frame, remainder)?;
+
+        loop {
+            match flow {
+                Flow::Jump(jump_id, arguments) if jump_id == id => {
+                    // The arguments may be stored in the part of the stack we are about to reuse,
+                    // or even in the parameter slots themselves, so copy them out first.
+                    let mut bytes = std::vec::Vec::new();
+
+                    for argument in arguments.iter() {
+                        let value = frame.get(*argument);
+                        let size = layout_size(&value.layout);
+                        bytes.extend_from_slice(unsafe {
+                            std::slice::from_raw_parts(value.ptr, size)
+                        });
+                    }
+
+                    let mut offset = 0;
+                    for (param, slot) in parameters.iter().zip(slots.iter()) {
+                        let size = layout_size(&param.layout);
+                        unsafe { copy(*slot, bytes[offset..].as_ptr(), size) };
+                        offset += size;
+                    }
+
+                    self.stack.reset(mark);
+                    flow = self.exec(frame, body)?;
+                }
+                other => return Ok(other),
+            }
+        }
+    }
+
+    fn eval(
+        &mut self,
+        frame: &Frame<'a>,
+        expr: &'a Expr<'a>,
+        layout: &Layout<'a>,
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        match expr {
+            Expr::Literal(literal) => unsafe { self.write_literal(literal, layout, dest) },
+            Expr::Call(call) => {
+                let args = |symbols: &[Symbol]| -> std::vec::Vec<Value<'a>> {
+                    symbols.iter().map(|s| frame.get(*s)).collect()
+                };
+
+                match &call.call_type {
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    } => {
+                        let proc_layout = ProcLayout {
+                            arguments: arg_layouts,
+                            result: **ret_layout,
+                            captures_niche: name.captures_niche(),
+                        };
+                        let proc = self.find_proc(name.name(), proc_layout)?;
+                        let arg_ptrs: std::vec::Vec<_> = args(call.arguments)
+                            .iter()
+                            .map(|arg| arg.ptr as *const u8)
+                            .collect();
+
+                        self.call(proc, &arg_ptrs, dest)
+                    }
+                    CallType::LowLevel { op, .. } => {
+                        self.call_low_level(*op, &args(call.arguments), layout, dest)
+                    }
+                    CallType::HigherOrder(higher_order) => {
+                        // the arguments are the lists, the function, and its closure data
+                        let function_index = higher_order.op.function_index();
+                        let lists = args(&call.arguments[..function_index]);
+                        let closure = frame.get(call.arguments[function_index + 1]);
+
+                        self.call_higher_order(higher_order, &lists, closure, layout, dest)
+                    }
+                    CallType::Foreign { foreign_symbol, .. } => Err(InterpError::Unsupported(
+                        format!("calling the foreign function {:?}", foreign_symbol),
+                    )),
+                }
+            }
+            Expr::Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+            } => unsafe { self.build_tag(frame, tag_layout, *tag_id, arguments, None, dest) },
+            Expr::Reuse {
+                symbol,
+                tag_layout,
+                tag_id,
+                arguments,
+                ..
+            } => unsafe {
+                let reused = read::<*mut u8>(frame.get(*symbol).ptr);
+                self.build_tag(frame, tag_layout, *tag_id, arguments, Some(reused), dest)
+            },
+            Expr::Reset { symbol, .. } => unsafe {
+                let reused = self.reset(frame.get(*symbol));
+                write(dest, reused);
+
+                Ok(())
+            },
+            Expr::Struct(fields) => {
+                let mut offset = 0;
+
+                for field in fields.iter() {
+                    let value = frame.get(*field);
+                    let size = layout_size(&value.layout);
+                    unsafe { copy(dest.add(offset), value.ptr, size) };
+                    offset += size;
+                }
+
+                Ok(())
+            }
+            Expr::StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => {
+                let value = frame.get(*structure);
+                let offset = field_offset(field_layouts, *index as usize);
+                unsafe { copy(dest, value.ptr.add(offset), layout_size(layout)) };
+
+                Ok(())
+            }
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            } => unsafe {
+                let tag_id = tags::read_tag_id(frame.get(*structure).ptr, union_layout);
+
+                match layout_size(layout) {
+                    1 => write(dest, tag_id as u8),
+                    _ => write(dest, tag_id),
+                }
+
+                Ok(())
+            },
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => unsafe {
+                let data = union_data(frame.get(*structure).ptr, union_layout);
+                let fields = tag_fields(union_layout, *tag_id);
+                let offset = field_offset(fields, *index as usize);
+                copy(dest, data.add(offset), layout_size(layout));
+
+                Ok(())
+            },
+            Expr::UnionFieldPtrAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => unsafe {
+                let data = union_data(frame.get(*structure).ptr, union_layout);
+                let fields = tag_fields(union_layout, *tag_id);
+                let offset = field_offset(fields, *index as usize);
+                write(dest, data.add(offset));
+
+                Ok(())
+            },
+            Expr::NullPointer => unsafe {
+                write(dest, std::ptr::null_mut::<u8>());
+
+                Ok(())
+            },
+            Expr::Alloca { element_layout } => unsafe {
+                // the slot lives as long as the frame of the current procedure
+                let slot = self.alloc_value(element_layout)?;
+                write(dest, slot);
+
+                Ok(())
+            },
+            Expr::Array { elem_layout, elems } => unsafe {
+                let elem_size = layout_size(elem_layout);
+
+                let ptr = if elems.is_empty() {
+                    std::ptr::null_mut()
+                } else {
+                    self.heap.allocate_with_refcount(
+                        elem_size * elems.len(),
+                        layout_alignment(elem_layout) as u32,
+                    )
+                };
+
+                for (i, elem) in elems.iter().enumerate() {
+                    let elem_ptr = ptr.add(i * elem_size);
+
+                    match elem {
+                        ListLiteralElement::Literal(literal) => {
+                            self.write_literal(literal, elem_layout, elem_ptr)?
+                        }
+                        ListLiteralElement::Symbol(symbol) => {
+                            copy(elem_ptr, frame.get(*symbol).ptr, elem_size)
+                        }
+                    }
+                }
+
+                RocSeq {
+                    ptr,
+                    len: elems.len(),
+                    capacity: elems.len(),
+                }
+                .write_list(dest);
+
+                Ok(())
+            },
+            Expr::EmptyArray => unsafe {
+                RocSeq::EMPTY.write_list(dest);
+
+                Ok(())
+            },
+            Expr::ExprBox { symbol } => unsafe {
+                let value = frame.get(*symbol);
+                let inner = match layout {
+                    Layout::Boxed(inner) => **inner,
+                    _ => value.layout,
+                };
+
+                let ptr = self.heap.allocate_with_refcount(
+                    layout_size(&inner),
+                    inner.allocation_alignment_bytes(TARGET_INFO),
+                );
+                copy(ptr, value.ptr, layout_size(&inner));
+                write(dest, ptr);
+
+                Ok(())
+            },
+            Expr::ExprUnbox { symbol } => unsafe {
+                let ptr = read::<*const u8>(frame.get(*symbol).ptr);
+                copy(dest, ptr, layout_size(layout));
+
+                Ok(())
+            },
+            Expr::RuntimeErrorFunction(message) => panic_with(message),
+        }
+    }
+
+    unsafe fn write_literal(
+        &mut self,
+        literal: &Literal<'a>,
+        layout: &Layout<'a>,
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        match literal {
+            Literal::Int(bytes) | Literal::U128(bytes) => match layout {
+                Layout::Builtin(Builtin::Float(width)) => {
+                    write_float(dest, *width, i128::from_ne_bytes(*bytes) as f64)
+                }
+                _ => copy(dest, bytes.as_ptr(), layout_size(layout)),
+            },
+            Literal::Float(float) => match layout {
+                Layout::Builtin(Builtin::Float(width)) => write_float(dest, *width, *float),
+                Layout::Builtin(Builtin::Decimal) => write(dest, dec_from_f64(*float)),
+                _ => internal_error!("float literal with layout {:?}", layout),
+            },
+            Literal::Decimal(bytes) => copy(dest, bytes.as_ptr(), bytes.len()),
+            Literal::Bool(value) => write(dest, *value as u8),
+            Literal::Byte(value) => write(dest, *value),
+            Literal::Str(string) => write_str(&mut self.heap, dest, string.as_bytes()),
+        }
+
+        Ok(())
+    }
+
+    /// Build a tag. Recursive tags are allocated on the heap, unless an allocation is reused.
+    unsafe fn build_tag(
+        &mut self,
+        frame: &Frame<'a>,
+        union_layout: &UnionLayout<'a>,
+        tag_id: TagIdIntType,
+        arguments: &[Symbol],
+        reused: Option<*mut u8>,
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        let fields = tag_fields(union_layout, tag_id);
+
+        let write_fields = |data: *mut u8| {
+            let mut offset = 0;
+
+            for (argument, field) in arguments.iter().zip(fields.iter()) {
+                let size = layout_size(field);
+                copy(data.add(offset), frame.get(*argument).ptr, size);
+                offset += size;
+            }
+        };
+
+        match union_layout {
+            UnionLayout::NonRecursive(_) => {
+                std::ptr::write_bytes(dest, 0, layout_size(&Layout::Union(*union_layout)));
+                write_fields(dest);
+                write_tag_id(dest, union_layout, tag_id);
+            }
+            _ if union_layout.tag_is_null(tag_id) => {
+                write(dest, 0usize);
+            }
+            _ => {
+                let (data_size, _) = union_layout.data_size_and_alignment(TARGET_INFO);
+
+                let data = match reused {
+                    Some(ptr) if !ptr.is_null() => ptr,
+                    _ => self.heap.allocate_with_refcount(
+                        data_size as usize,
+                        union_layout.allocation_alignment_bytes(TARGET_INFO),
+                    ),
+                };
+
+                std::ptr::write_bytes(data, 0, data_size as usize);
+                write_fields(data);
+
+                if union_layout.stores_tag_id_as_data(TARGET_INFO) {
+                    write_tag_id(data, union_layout, tag_id);
+                }
+
+                let mut ptr = data as usize;
+                if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+                    ptr |= tag_id as usize;
+                }
+
+                write(dest, ptr);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Switches compare against `u64`s, with signed integers sign-extended
+unsafe fn read_switch_value(value: Value) -> u64 {
+    match value.layout {
+        Layout::Builtin(Builtin::Int(width)) => IntValue::read(value.ptr, width).bits() as u64,
+        layout => match layout_size(&layout) {
+            1 => read::<u8>(value.ptr) as u64,
+            2 => read::<u16>(value.ptr) as u64,
+            4 => read::<u32>(value.ptr) as u64,
+            _ => read::<u64>(value.ptr),
+        },
+    }
+}
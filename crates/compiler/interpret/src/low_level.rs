@@ -0,0 +1,1270 @@
+//! Lowlevel operations, implemented in Rust.
+//!
+//! These behave like the Zig builtins and the code that the LLVM backend generates inline.
+//! Arguments that `roc_mono::borrow::lowlevel_borrow_signature` marks as owned are consumed
+//! here: they are either reused for the result or decremented.
+use roc_error_macros::internal_error;
+use roc_module::low_level::LowLevel;
+use roc_mono::ir::{HigherOrderLowLevel, ProcLayout};
+use roc_mono::layout::{Builtin, Layout};
+use roc_mono::low_level::HigherOrder;
+use std::cmp::Ordering;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::eq::values_equal;
+use crate::memory::{
+    copy, decrement_refcount, is_unique, read, str_allocation, str_as_str, str_bytes, str_capacity,
+    write, write_str, write_str_with_capacity, RocSeq, STR_SIZE,
+};
+use crate::num::{
+    dec_div, dec_from_f64, dec_mul, dec_to_f64, dec_to_string, float_to_string, num_kind,
+    read_float, with_float_type, with_int_type, with_signed_int_type, write_float, write_int_bits,
+    IntValue, NumKind, DEC_ONE,
+};
+use crate::tags::{field_offset, layout_alignment, layout_size, union_data};
+use crate::{panic_with, InterpError, Interpreter, Value};
+
+const STR_LAYOUT: Layout<'static> = Layout::Builtin(Builtin::Str);
+
+/// The tags of `[EQ, GT, LT]`
+const ORDERING_EQ: u8 = 0;
+const ORDERING_GT: u8 = 1;
+const ORDERING_LT: u8 = 2;
+
+/// The tags of `Utf8ByteProblem`
+const CODEPOINT_TOO_LARGE: u8 = 0;
+const ENCODES_SURROGATE_HALF: u8 = 1;
+const EXPECTED_CONTINUATION: u8 = 2;
+const INVALID_START_BYTE: u8 = 3;
+const OVERLONG_ENCODING: u8 = 4;
+const UNEXPECTED_END_OF_SEQUENCE: u8 = 5;
+
+fn list_elem_layout<'a>(layout: &Layout<'a>) -> Layout<'a> {
+    match layout {
+        Layout::Builtin(Builtin::List(elem_layout)) => **elem_layout,
+        _ => internal_error!("expected a list layout, got {:?}", layout),
+    }
+}
+
+fn struct_fields<'a>(layout: &Layout<'a>) -> &'a [Layout<'a>] {
+    match layout {
+        Layout::Struct { field_layouts, .. } => field_layouts,
+        _ => internal_error!("expected a struct layout, got {:?}", layout),
+    }
+}
+
+/// Checked operations return `{ a : value, b : Bool }`, with the fields sorted by alignment.
+/// Returns the layout and offset of the value, and the offset of the flag.
+fn value_and_flag<'a>(layout: &Layout<'a>) -> (Layout<'a>, usize, usize) {
+    let fields = struct_fields(layout);
+
+    let flag_index = fields
+        .iter()
+        .position(|field| matches!(field, Layout::Builtin(Builtin::Bool)))
+        .unwrap_or_else(|| internal_error!("no Bool field in {:?}", layout));
+    let value_index = 1 - flag_index;
+
+    (
+        fields[value_index],
+        field_offset(fields, value_index),
+        field_offset(fields, flag_index),
+    )
+}
+
+/// The offset of the first field with the given layout
+fn offset_of(fields: &[Layout], layout: Layout) -> usize {
+    let index = fields
+        .iter()
+        .position(|field| *field == layout)
+        .unwrap_or_else(|| internal_error!("no {:?} field in {:?}", layout, fields));
+
+    field_offset(fields, index)
+}
+
+fn int_width(layout: &Layout) -> roc_builtins::bitcode::IntWidth {
+    match layout {
+        Layout::Builtin(Builtin::Int(width)) => *width,
+        _ => internal_error!("expected an integer layout, got {:?}", layout),
+    }
+}
+
+/// # Safety
+/// `value` must be a number
+unsafe fn read_as_f64(value: Value) -> f64 {
+    match num_kind(&value.layout) {
+        Some(NumKind::Int(width)) => IntValue::read(value.ptr, width).to_f64(),
+        Some(NumKind::Float(width)) => read_float(value.ptr, width),
+        Some(NumKind::Dec) => dec_to_f64(read(value.ptr)),
+        None => internal_error!("expected a number, got {:?}", value.layout),
+    }
+}
+
+/// # Safety
+/// `value` must be a number
+unsafe fn num_to_string(value: Value) -> String {
+    match num_kind(&value.layout) {
+        Some(NumKind::Int(width)) => match IntValue::read(value.ptr, width) {
+            IntValue::Signed(int) => int.to_string(),
+            IntValue::Unsigned(int) => int.to_string(),
+        },
+        Some(NumKind::Float(width)) => float_to_string(read_float(value.ptr, width)),
+        Some(NumKind::Dec) => dec_to_string(read(value.ptr)),
+        None => internal_error!("expected a number, got {:?}", value.layout),
+    }
+}
+
+/// Why the bytes at the start of `bytes` are not valid UTF-8, following Zig's `std.unicode`
+fn utf8_problem(bytes: &[u8]) -> u8 {
+    let first = bytes[0];
+
+    let length = match first {
+        0b0000_0000..=0b0111_1111 => 1,
+        0b1100_0000..=0b1101_1111 => 2,
+        0b1110_0000..=0b1110_1111 => 3,
+        0b1111_0000..=0b1111_0111 => 4,
+        _ => return INVALID_START_BYTE,
+    };
+
+    if bytes.len() < length {
+        return UNEXPECTED_END_OF_SEQUENCE;
+    }
+
+    let mut codepoint = match length {
+        2 => first as u32 & 0b0001_1111,
+        3 => first as u32 & 0b0000_1111,
+        _ => first as u32 & 0b0000_0111,
+    };
+
+    for byte in &bytes[1..length] {
+        if byte & 0b1100_0000 != 0b1000_0000 {
+            return EXPECTED_CONTINUATION;
+        }
+
+        codepoint = (codepoint << 6) | (*byte as u32 & 0b0011_1111);
+    }
+
+    let smallest = match length {
+        2 => 0x80,
+        3 => 0x800,
+        _ => 0x10000,
+    };
+
+    if codepoint < smallest {
+        OVERLONG_ENCODING
+    } else if (0xD800..=0xDFFF).contains(&codepoint) {
+        ENCODES_SURROGATE_HALF
+    } else if codepoint > 0x10FFFF {
+        CODEPOINT_TOO_LARGE
+    } else {
+        INVALID_START_BYTE
+    }
+}
+
+fn scalar_to_bytes(scalar: u32) -> Result<std::vec::Vec<u8>, InterpError> {
+    match char::from_u32(scalar) {
+        Some(c) => Ok(c.to_string().into_bytes()),
+        None => panic_with("invalid Unicode scalar value"),
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    pub(crate) fn call_low_level(
+        &mut self,
+        op: LowLevel,
+        args: &[Value<'a>],
+        ret_layout: &Layout<'a>,
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        use LowLevel::*;
+
+        unsafe {
+            match op {
+                StrConcat => self.str_append(args[0].ptr, str_bytes(args[1].ptr), dest),
+                StrJoinWith => {
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let separator = str_bytes(args[1].ptr);
+                    let mut bytes = std::vec::Vec::new();
+
+                    for i in 0..list.len {
+                        if i > 0 {
+                            bytes.extend_from_slice(separator);
+                        }
+
+                        bytes.extend_from_slice(str_bytes(list.ptr.add(i * STR_SIZE)));
+                    }
+
+                    write_str(&mut self.heap, dest, &bytes);
+                }
+                StrIsEmpty => write(dest, str_bytes(args[0].ptr).is_empty()),
+                StrStartsWith => write(
+                    dest,
+                    str_bytes(args[0].ptr).starts_with(str_bytes(args[1].ptr)),
+                ),
+                StrStartsWithScalar => {
+                    let prefix = scalar_to_bytes(read(args[1].ptr))?;
+                    write(dest, str_bytes(args[0].ptr).starts_with(&prefix))
+                }
+                StrEndsWith => write(
+                    dest,
+                    str_bytes(args[0].ptr).ends_with(str_bytes(args[1].ptr)),
+                ),
+                StrSplit => {
+                    let string = str_as_str(args[0].ptr);
+                    let delimiter = str_as_str(args[1].ptr);
+
+                    let pieces: std::vec::Vec<&str> = if delimiter.is_empty() {
+                        vec![string]
+                    } else {
+                        string.split(delimiter).collect()
+                    };
+
+                    let mut list = self.new_list(&STR_LAYOUT, pieces.len());
+                    for (i, piece) in pieces.iter().enumerate() {
+                        write_str(&mut self.heap, list.ptr.add(i * STR_SIZE), piece.as_bytes());
+                    }
+                    list.len = pieces.len();
+                    list.write_list(dest);
+                }
+                StrCountGraphemes => write(dest, str_as_str(args[0].ptr).graphemes(true).count()),
+                StrCountUtf8Bytes => write(dest, str_bytes(args[0].ptr).len()),
+                StrFromInt | StrFromFloat | NumToStr => {
+                    let string = num_to_string(args[0]);
+                    write_str(&mut self.heap, dest, string.as_bytes());
+                }
+                StrFromUtf8Range => self.str_from_utf8_range(args, ret_layout, dest),
+                StrToUtf8 => {
+                    let bytes = str_bytes(args[0].ptr);
+                    self.write_list_of(dest, &Layout::u8(), bytes);
+                    self.decrement(args[0].ptr, &STR_LAYOUT);
+                }
+                StrRepeat => {
+                    let bytes = str_bytes(args[0].ptr).repeat(read::<usize>(args[1].ptr));
+                    write_str(&mut self.heap, dest, &bytes);
+                }
+                StrTrim | StrTrimLeft | StrTrimRight => {
+                    let string = str_as_str(args[0].ptr);
+
+                    let trimmed = match op {
+                        StrTrim => string.trim(),
+                        StrTrimLeft => string.trim_start(),
+                        _ => string.trim_end(),
+                    };
+
+                    if trimmed.len() == string.len() {
+                        copy(dest, args[0].ptr, STR_SIZE);
+                    } else {
+                        write_str(&mut self.heap, dest, trimmed.as_bytes());
+                        self.decrement(args[0].ptr, &STR_LAYOUT);
+                    }
+                }
+                StrToNum => str_to_num(args[0], ret_layout, dest),
+                StrToScalars => {
+                    let scalars: std::vec::Vec<u32> =
+                        str_as_str(args[0].ptr).chars().map(|c| c as u32).collect();
+                    self.write_list_of(dest, &Layout::u32(), &scalars);
+                }
+                StrGetUnsafe => write(dest, str_bytes(args[0].ptr)[read::<usize>(args[1].ptr)]),
+                StrSubstringUnsafe => {
+                    let start = read::<usize>(args[1].ptr);
+                    let length = read::<usize>(args[2].ptr);
+                    let bytes = &str_bytes(args[0].ptr)[start..][..length];
+
+                    write_str(&mut self.heap, dest, bytes);
+                }
+                StrReserve => {
+                    let len = str_bytes(args[0].ptr).len();
+                    let capacity = len + read::<usize>(args[1].ptr);
+
+                    if str_capacity(args[0].ptr) >= capacity && self.str_is_unique(args[0].ptr) {
+                        copy(dest, args[0].ptr, STR_SIZE);
+                    } else {
+                        let bytes = str_bytes(args[0].ptr).to_vec();
+                        write_str_with_capacity(&mut self.heap, dest, &bytes, capacity);
+                        self.decrement(args[0].ptr, &STR_LAYOUT);
+                    }
+                }
+                StrAppendScalar => {
+                    let bytes = scalar_to_bytes(read(args[1].ptr))?;
+                    self.str_append(args[0].ptr, &bytes, dest);
+                }
+                StrGetScalarUnsafe => {
+                    let index = read::<usize>(args[1].ptr);
+                    let c = str_as_str(args[0].ptr)[index..].chars().next().unwrap();
+
+                    let fields = struct_fields(ret_layout);
+                    write(dest.add(offset_of(fields, Layout::u32())), c as u32);
+                    write(
+                        dest.add(offset_of(fields, Layout::usize(crate::TARGET_INFO))),
+                        c.len_utf8(),
+                    );
+                }
+                StrGetCapacity => write(dest, str_capacity(args[0].ptr)),
+
+                ListLen => write(dest, RocSeq::read_list(args[0].ptr).len),
+                ListGetCapacity => write(dest, RocSeq::read_list(args[0].ptr).capacity),
+                ListWithCapacity => {
+                    let elem_layout = list_elem_layout(ret_layout);
+                    self.new_list(&elem_layout, read(args[0].ptr))
+                        .write_list(dest);
+                }
+                ListReserve => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let capacity = list.len + read::<usize>(args[1].ptr);
+
+                    self.make_unique(list, &elem_layout, capacity)
+                        .write_list(dest);
+                }
+                ListAppendUnsafe => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let mut list = self.make_unique(list, &elem_layout, list.len + 1);
+
+                    copy(list.ptr.add(list.len * elem_size), args[1].ptr, elem_size);
+                    list.len += 1;
+                    list.write_list(dest);
+                }
+                ListPrepend => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let mut list = self.make_unique(list, &elem_layout, list.len + 1);
+
+                    copy(list.ptr.add(elem_size), list.ptr, list.len * elem_size);
+                    copy(list.ptr, args[1].ptr, elem_size);
+                    list.len += 1;
+                    list.write_list(dest);
+                }
+                ListGetUnsafe => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let index = read::<usize>(args[1].ptr);
+
+                    copy(dest, list.ptr.add(index * elem_size), elem_size);
+                    self.increment(dest, &elem_layout, 1);
+                }
+                ListReplaceUnsafe => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let list = self.make_unique(list, &elem_layout, 0);
+                    let elem_ptr = list.ptr.add(read::<usize>(args[1].ptr) * elem_size);
+
+                    // the element comes first if it is aligned more strictly than the list
+                    let (list_offset, elem_offset) =
+                        if layout_alignment(&elem_layout) > crate::memory::PTR_SIZE {
+                            (elem_size, 0)
+                        } else {
+                            (0, crate::memory::LIST_SIZE)
+                        };
+
+                    copy(dest.add(elem_offset), elem_ptr, elem_size);
+                    copy(elem_ptr, args[2].ptr, elem_size);
+                    list.write_list(dest.add(list_offset));
+                }
+                ListConcat => {
+                    let elem_layout = list_elem_layout(ret_layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let first = RocSeq::read_list(args[0].ptr);
+                    let second = RocSeq::read_list(args[1].ptr);
+
+                    if second.len == 0 {
+                        self.decrement(args[1].ptr, &args[1].layout);
+                        first.write_list(dest);
+                    } else {
+                        let mut list =
+                            self.make_unique(first, &elem_layout, first.len + second.len);
+
+                        copy(
+                            list.ptr.add(list.len * elem_size),
+                            second.ptr,
+                            second.len * elem_size,
+                        );
+                        list.len += second.len;
+                        self.release_list(second, &elem_layout);
+
+                        list.write_list(dest);
+                    }
+                }
+                ListSublist => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let start = read::<usize>(args[1].ptr).min(list.len);
+                    let len = read::<usize>(args[2].ptr).min(list.len - start);
+
+                    let mut list = self.make_unique(list, &elem_layout, 0);
+
+                    for i in (0..start).chain(start + len..list.len) {
+                        self.decrement(list.ptr.add(i * elem_size), &elem_layout);
+                    }
+
+                    copy(list.ptr, list.ptr.add(start * elem_size), len * elem_size);
+                    list.len = len;
+                    list.write_list(dest);
+                }
+                ListDropAt => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let index = read::<usize>(args[1].ptr);
+
+                    if index >= list.len {
+                        list.write_list(dest);
+                    } else {
+                        let mut list = self.make_unique(list, &elem_layout, 0);
+                        let elem_ptr = list.ptr.add(index * elem_size);
+
+                        self.decrement(elem_ptr, &elem_layout);
+                        copy(
+                            elem_ptr,
+                            elem_ptr.add(elem_size),
+                            (list.len - index - 1) * elem_size,
+                        );
+                        list.len -= 1;
+                        list.write_list(dest);
+                    }
+                }
+                ListSwap => {
+                    let elem_layout = list_elem_layout(&args[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let i = read::<usize>(args[1].ptr);
+                    let j = read::<usize>(args[2].ptr);
+
+                    if i >= list.len || j >= list.len {
+                        list.write_list(dest);
+                    } else {
+                        let list = self.make_unique(list, &elem_layout, 0);
+                        std::ptr::swap_nonoverlapping(
+                            list.ptr.add(i * elem_size),
+                            list.ptr.add(j * elem_size),
+                            if i == j { 0 } else { elem_size },
+                        );
+                        list.write_list(dest);
+                    }
+                }
+                ListIsUnique => write(dest, is_unique(RocSeq::read_list(args[0].ptr).ptr)),
+
+                NumAdd | NumAddWrap | NumAddSaturated | NumSub | NumSubWrap | NumSubSaturated
+                | NumMul | NumMulWrap | NumMulSaturated | NumDivUnchecked | NumDivCeilUnchecked
+                | NumRemUnchecked | NumPow | NumPowInt | NumBitwiseAnd | NumBitwiseXor
+                | NumBitwiseOr => num_binop(op, args[0], args[1], dest)?,
+                NumAddChecked | NumSubChecked | NumMulChecked => {
+                    num_checked(op, args[0], args[1], ret_layout, dest)?
+                }
+                NumShiftLeftBy | NumShiftRightBy | NumShiftRightZfBy => {
+                    num_shift(op, args[0], args[1], dest)
+                }
+                NumGt | NumGte | NumLt | NumLte => {
+                    let ordering = num_compare(args[0], args[1])?;
+
+                    let result = match op {
+                        NumGt => ordering == Some(Ordering::Greater),
+                        NumGte => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                        NumLt => ordering == Some(Ordering::Less),
+                        _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    };
+
+                    write(dest, result);
+                }
+                NumCompare => {
+                    let tag_id = match num_compare(args[0], args[1])? {
+                        Some(Ordering::Greater) => ORDERING_GT,
+                        Some(Ordering::Less) => ORDERING_LT,
+                        Some(Ordering::Equal) | None => ORDERING_EQ,
+                    };
+
+                    write(dest, tag_id);
+                }
+                NumIsMultipleOf => {
+                    let width = int_width(&args[0].layout);
+
+                    let result = with_int_type!(width, T, {
+                        let x: T = read(args[0].ptr);
+                        let y: T = read(args[1].ptr);
+
+                        // `T::MIN % -1` overflows, but every integer is a multiple of -1
+                        match x.checked_rem(y) {
+                            Some(remainder) => remainder == 0,
+                            None => y != 0 || x == 0,
+                        }
+                    });
+
+                    write(dest, result);
+                }
+                NumAbs | NumNeg => num_abs_or_neg(op, args[0], dest)?,
+                NumSin | NumCos | NumSqrtUnchecked | NumLogUnchecked | NumAtan | NumAcos
+                | NumAsin => {
+                    let f: fn(f64) -> f64 = match op {
+                        NumSin => f64::sin,
+                        NumCos => f64::cos,
+                        NumSqrtUnchecked => f64::sqrt,
+                        NumLogUnchecked => f64::ln,
+                        NumAtan => f64::atan,
+                        NumAcos => f64::acos,
+                        _ => f64::asin,
+                    };
+
+                    write_frac(f(read_as_f64(args[0])), ret_layout, dest);
+                }
+                NumRound | NumCeiling | NumFloor => {
+                    let width = int_width(ret_layout);
+
+                    match num_kind(&args[0].layout) {
+                        Some(NumKind::Dec) => {
+                            let x: i128 = read(args[0].ptr);
+
+                            let result = match op {
+                                NumFloor => x.div_euclid(DEC_ONE),
+                                NumCeiling => -(-x).div_euclid(DEC_ONE),
+                                _ if x >= 0 => x.saturating_add(DEC_ONE / 2) / DEC_ONE,
+                                _ => x.saturating_sub(DEC_ONE / 2) / DEC_ONE,
+                            };
+
+                            write_int_bits(dest, width, result as u128);
+                        }
+                        _ => {
+                            let x = read_as_f64(args[0]);
+
+                            let result = match op {
+                                NumFloor => x.floor(),
+                                NumCeiling => x.ceil(),
+                                _ => x.round(),
+                            };
+
+                            with_int_type!(width, T, { write(dest, result as T) });
+                        }
+                    }
+                }
+                NumToFrac | NumToFloatCast => num_to_frac(args[0], ret_layout, dest)?,
+                NumIsFinite => {
+                    let result = match num_kind(&args[0].layout) {
+                        Some(NumKind::Float(width)) => read_float(args[0].ptr, width).is_finite(),
+                        _ => true,
+                    };
+
+                    write(dest, result);
+                }
+                NumBytesToU16 | NumBytesToU32 => {
+                    let list = RocSeq::read_list(args[0].ptr);
+                    let bytes = list.ptr.add(read::<usize>(args[1].ptr));
+
+                    match op {
+                        NumBytesToU16 => write(dest, u16::from_le_bytes(read(bytes))),
+                        _ => write(dest, u32::from_le_bytes(read(bytes))),
+                    }
+                }
+                NumIntCast => {
+                    let value = IntValue::read(args[0].ptr, int_width(&args[0].layout));
+                    write_int_bits(dest, int_width(ret_layout), value.bits());
+                }
+                NumToIntChecked => {
+                    let (value_layout, value_offset, flag_offset) = value_and_flag(ret_layout);
+                    let width = int_width(&value_layout);
+                    let value = IntValue::read(args[0].ptr, int_width(&args[0].layout));
+                    let fits = value.fits_in(width);
+
+                    write_int_bits(
+                        dest.add(value_offset),
+                        width,
+                        if fits { value.bits() } else { 0 },
+                    );
+                    write(dest.add(flag_offset), !fits);
+                }
+                NumToFloatChecked => {
+                    let (value_layout, value_offset, flag_offset) = value_and_flag(ret_layout);
+                    let value = read_as_f64(args[0]);
+
+                    let out_of_bounds = match value_layout {
+                        Layout::Builtin(Builtin::Float(width)) => {
+                            write_float(dest.add(value_offset), width, value);
+
+                            let written = read_float(dest.add(value_offset), width);
+                            value.is_finite() && !written.is_finite()
+                        }
+                        _ => internal_error!("expected a float layout, got {:?}", value_layout),
+                    };
+
+                    write(dest.add(flag_offset), out_of_bounds);
+                }
+
+                Eq | NotEq => {
+                    let equal = values_equal(args[0].ptr, args[1].ptr, &args[0].layout, None);
+                    write(dest, equal == (op == Eq));
+                }
+                And => write(
+                    dest,
+                    read::<u8>(args[0].ptr) != 0 && read::<u8>(args[1].ptr) != 0,
+                ),
+                Or => write(
+                    dest,
+                    read::<u8>(args[0].ptr) != 0 || read::<u8>(args[1].ptr) != 0,
+                ),
+                Not => write(dest, read::<u8>(args[0].ptr) == 0),
+
+                Hash => return Err(InterpError::Unsupported("hashing".to_string())),
+                Unreachable => return panic_with("reached unreachable code"),
+
+                ListMap | ListMap2 | ListMap3 | ListMap4 | ListSortWith => {
+                    internal_error!("{:?} is a higher-order lowlevel", op)
+                }
+                RefCountIsUnique => match args[0].layout {
+                    Layout::Union(union_layout) => {
+                        write(dest, is_unique(union_data(args[0].ptr, &union_layout)))
+                    }
+                    Layout::Boxed(_) => write(dest, is_unique(read(args[0].ptr))),
+                    other => internal_error!("{:?} on a value of layout {:?}", op, other),
+                },
+
+                PtrStore => {
+                    let ptr = read::<*mut u8>(args[0].ptr);
+                    copy(ptr, args[1].ptr, layout_size(&args[1].layout));
+                }
+                PtrLoad => {
+                    let ptr = read::<*const u8>(args[0].ptr);
+                    copy(dest, ptr, layout_size(ret_layout));
+                }
+
+                PtrCast | RefCountInc | RefCountDec | BoxExpr | UnboxExpr => {
+                    internal_error!("{:?} does not appear in the mono IR that we interpret", op)
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn call_higher_order(
+        &mut self,
+        higher_order: &'a HigherOrderLowLevel<'a>,
+        lists: &[Value<'a>],
+        closure: Value<'a>,
+        ret_layout: &Layout<'a>,
+        dest: *mut u8,
+    ) -> Result<(), InterpError> {
+        let passed = &higher_order.passed_function;
+
+        let proc_layout = ProcLayout {
+            arguments: passed.argument_layouts,
+            result: passed.return_layout,
+            captures_niche: passed.name.captures_niche(),
+        };
+        let proc = self.find_proc(passed.name.name(), proc_layout)?;
+
+        // the closure data is only passed if the lambda set needs a runtime representation
+        let arity = higher_order.op.function_arity();
+        let closure = if passed.argument_layouts.len() > arity {
+            Some(closure)
+        } else {
+            None
+        };
+
+        // each call consumes the closure data if the function owns it
+        let call_args = |interp: &mut Self, mut args: std::vec::Vec<*const u8>| {
+            if let Some(closure) = closure {
+                if passed.owns_captured_environment {
+                    unsafe { interp.increment(closure.ptr, &closure.layout, 1) };
+                }
+
+                args.push(closure.ptr);
+            }
+
+            args
+        };
+
+        unsafe {
+            match higher_order.op {
+                HigherOrder::ListMap { .. }
+                | HigherOrder::ListMap2 { .. }
+                | HigherOrder::ListMap3 { .. }
+                | HigherOrder::ListMap4 { .. } => {
+                    let inputs: std::vec::Vec<(RocSeq, usize)> = lists
+                        .iter()
+                        .map(|list| {
+                            let elem_layout = list_elem_layout(&list.layout);
+                            (RocSeq::read_list(list.ptr), layout_size(&elem_layout))
+                        })
+                        .collect();
+
+                    // like the Zig builtins, stop at the end of the shortest list
+                    let len = inputs.iter().map(|(list, _)| list.len).min().unwrap_or(0);
+
+                    let output_layout = list_elem_layout(ret_layout);
+                    let output_size = layout_size(&output_layout);
+                    let mut output = self.new_list(&output_layout, len);
+
+                    for i in 0..len {
+                        let args = inputs
+                            .iter()
+                            .map(|(list, elem_size)| list.ptr.add(i * elem_size) as *const u8)
+                            .collect();
+                        let args = call_args(self, args);
+
+                        self.call(proc, &args, output.ptr.add(i * output_size))?;
+                        output.len = i + 1;
+                    }
+
+                    output.write_list(dest);
+                }
+                HigherOrder::ListSortWith { .. } => {
+                    let elem_layout = list_elem_layout(&lists[0].layout);
+                    let elem_size = layout_size(&elem_layout);
+                    let list = RocSeq::read_list(lists[0].ptr);
+                    let list = self.make_unique(list, &elem_layout, 0);
+
+                    let result_slot = self.alloc_value(&Layout::u8())?;
+                    let mut error = None;
+
+                    let mut order: std::vec::Vec<usize> = (0..list.len).collect();
+                    order.sort_by(|a, b| {
+                        if error.is_some() {
+                            return Ordering::Equal;
+                        }
+
+                        let args = vec![
+                            list.ptr.add(a * elem_size) as *const u8,
+                            list.ptr.add(b * elem_size) as *const u8,
+                        ];
+                        let args = call_args(self, args);
+
+                        match self.call(proc, &args, result_slot) {
+                            Ok(()) => match read::<u8>(result_slot) {
+                                ORDERING_GT => Ordering::Greater,
+                                ORDERING_LT => Ordering::Less,
+                                _ => Ordering::Equal,
+                            },
+                            Err(e) => {
+                                error = Some(e);
+                                Ordering::Equal
+                            }
+                        }
+                    });
+
+                    if let Some(e) = error {
+                        return Err(e);
+                    }
+
+                    let sorted: std::vec::Vec<u8> = order
+                        .iter()
+                        .flat_map(|i| {
+                            std::slice::from_raw_parts(list.ptr.add(i * elem_size), elem_size)
+                        })
+                        .copied()
+                        .collect();
+                    copy(list.ptr, sorted.as_ptr(), sorted.len());
+
+                    list.write_list(dest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    unsafe fn new_list(&mut self, elem_layout: &Layout<'a>, capacity: usize) -> RocSeq {
+        if capacity == 0 {
+            RocSeq::EMPTY
+        } else {
+            let ptr = self.heap.allocate_with_refcount(
+                layout_size(elem_layout) * capacity,
+                layout_alignment(elem_layout) as u32,
+            );
+
+            RocSeq {
+                ptr,
+                len: 0,
+                capacity,
+            }
+        }
+    }
+
+    unsafe fn write_list_of<T: Copy>(
+        &mut self,
+        dest: *mut u8,
+        elem_layout: &Layout<'a>,
+        elems: &[T],
+    ) {
+        debug_assert_eq!(layout_size(elem_layout), std::mem::size_of::<T>());
+
+        let mut list = self.new_list(elem_layout, elems.len());
+        copy(
+            list.ptr,
+            elems.as_ptr().cast(),
+            std::mem::size_of_val(elems),
+        );
+        list.len = elems.len();
+
+        list.write_list(dest);
+    }
+
+    /// A list with the same elements that we can update in place, with at least the given
+    /// capacity. Consumes the original list.
+    unsafe fn make_unique(
+        &mut self,
+        list: RocSeq,
+        elem_layout: &Layout<'a>,
+        min_capacity: usize,
+    ) -> RocSeq {
+        let unique = is_unique(list.ptr);
+
+        if unique && list.capacity >= min_capacity {
+            return list;
+        }
+
+        // grow geometrically, so that appending in a loop is not quadratic
+        let capacity = if unique {
+            min_capacity.max(2 * list.capacity)
+        } else {
+            min_capacity.max(list.len)
+        };
+
+        let mut new_list = self.new_list(elem_layout, capacity);
+        copy(new_list.ptr, list.ptr, list.len * layout_size(elem_layout));
+        new_list.len = list.len;
+
+        self.release_list(list, elem_layout);
+
+        new_list
+    }
+
+    /// Give up a list whose elements were copied somewhere else. The elements are not
+    /// decremented, because the copies now own them.
+    unsafe fn release_list(&mut self, list: RocSeq, elem_layout: &Layout<'a>) {
+        if is_unique(list.ptr) {
+            self.heap.free(list.ptr);
+        } else {
+            if elem_layout.contains_refcounted() {
+                let elem_size = layout_size(elem_layout);
+
+                for i in 0..list.len {
+                    self.increment(list.ptr.add(i * elem_size), elem_layout, 1);
+                }
+            }
+
+            // not unique, so this is never the last reference
+            decrement_refcount(list.ptr);
+        }
+    }
+
+    unsafe fn str_is_unique(&self, string: *const u8) -> bool {
+        match str_allocation(string) {
+            Some(seq) => is_unique(seq.ptr),
+            None => true,
+        }
+    }
+
+    /// Append bytes to an owned string, in place if possible
+    unsafe fn str_append(&mut self, string: *const u8, extra: &[u8], dest: *mut u8) {
+        let old = str_bytes(string);
+        let new_len = old.len() + extra.len();
+
+        match str_allocation(string) {
+            Some(seq) if is_unique(seq.ptr) && seq.capacity >= new_len => {
+                copy(seq.ptr.add(seq.len), extra.as_ptr(), extra.len());
+
+                RocSeq {
+                    len: new_len,
+                    ..seq
+                }
+                .write_list(dest);
+            }
+            allocation => {
+                let capacity = match allocation {
+                    Some(seq) if is_unique(seq.ptr) => new_len.max(2 * seq.capacity),
+                    _ => new_len,
+                };
+
+                let mut bytes = std::vec::Vec::with_capacity(new_len);
+                bytes.extend_from_slice(old);
+                bytes.extend_from_slice(extra);
+
+                write_str_with_capacity(&mut self.heap, dest, &bytes, capacity);
+                self.decrement(string, &STR_LAYOUT);
+            }
+        }
+    }
+
+    /// `Str.fromUtf8Range`, which returns a `FromUtf8Result`
+    unsafe fn str_from_utf8_range(
+        &mut self,
+        args: &[Value<'a>],
+        ret_layout: &Layout<'a>,
+        dest: *mut u8,
+    ) {
+        let list = RocSeq::read_list(args[0].ptr);
+        let start = read::<usize>(args[1].ptr);
+        let count = read::<usize>(args[2].ptr);
+
+        let bytes = if count == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(list.ptr.add(start), count)
+        };
+
+        let fields = struct_fields(ret_layout);
+        let string_dest = dest.add(offset_of(fields, STR_LAYOUT));
+        let index_dest = dest.add(offset_of(fields, Layout::usize(crate::TARGET_INFO)));
+        let is_ok_dest = dest.add(offset_of(fields, Layout::bool()));
+        let problem_dest = dest.add(offset_of(fields, Layout::u8()));
+
+        match std::str::from_utf8(bytes) {
+            Ok(_) => {
+                write_str(&mut self.heap, string_dest, bytes);
+                write(index_dest, 0usize);
+                write(is_ok_dest, true);
+                write(problem_dest, 0u8);
+            }
+            Err(error) => {
+                let index = error.valid_up_to();
+
+                RocSeq::EMPTY.write_list(string_dest);
+                write(index_dest, start + index);
+                write(is_ok_dest, false);
+                write(problem_dest, utf8_problem(&bytes[index..]));
+            }
+        }
+
+        self.decrement(args[0].ptr, &args[0].layout);
+    }
+}
+
+/// `Str.toNum`, which returns `{ aresult : num, berrorcode : U8 }`
+unsafe fn str_to_num(string: Value, ret_layout: &Layout, dest: *mut u8) {
+    let fields = struct_fields(ret_layout);
+    let result_layout = fields[0];
+    let error_dest = dest.add(field_offset(fields, 1));
+    let string = str_as_str(string.ptr);
+
+    let ok = match num_kind(&result_layout) {
+        Some(NumKind::Int(width)) => with_int_type!(width, T, {
+            match string.parse::<T>() {
+                Ok(value) => {
+                    write(dest, value);
+                    true
+                }
+                Err(_) => false,
+            }
+        }),
+        Some(NumKind::Float(width)) => match string.parse::<f64>() {
+            Ok(value) => {
+                write_float(dest, width, value);
+                true
+            }
+            Err(_) => false,
+        },
+        Some(NumKind::Dec) => match roc_std::RocDec::from_str(string) {
+            Some(value) => {
+                write(dest, value.to_ne_bytes());
+                true
+            }
+            None => false,
+        },
+        None => internal_error!("Str.toNum returning {:?}", result_layout),
+    };
+
+    write(error_dest, if ok { 0u8 } else { 1u8 });
+}
+
+unsafe fn write_frac(value: f64, layout: &Layout, dest: *mut u8) {
+    match num_kind(layout) {
+        Some(NumKind::Float(width)) => write_float(dest, width, value),
+        Some(NumKind::Dec) => write(dest, dec_from_f64(value)),
+        _ => internal_error!("expected a fractional layout, got {:?}", layout),
+    }
+}
+
+unsafe fn num_to_frac(value: Value, ret_layout: &Layout, dest: *mut u8) -> Result<(), InterpError> {
+    match (num_kind(&value.layout), num_kind(ret_layout)) {
+        (Some(NumKind::Int(width)), Some(NumKind::Dec)) => {
+            let dec = match IntValue::read(value.ptr, width) {
+                IntValue::Signed(int) => int.checked_mul(DEC_ONE),
+                IntValue::Unsigned(int) => i128::try_from(int)
+                    .ok()
+                    .and_then(|int| int.checked_mul(DEC_ONE)),
+            };
+
+            match dec {
+                Some(dec) => write(dest, dec),
+                None => return panic_with("integer does not fit in a decimal"),
+            }
+        }
+        (Some(NumKind::Dec), Some(NumKind::Dec)) => copy(dest, value.ptr, 16),
+        _ => write_frac(read_as_f64(value), ret_layout, dest),
+    }
+
+    Ok(())
+}
+
+unsafe fn num_compare(a: Value, b: Value) -> Result<Option<Ordering>, InterpError> {
+    let ordering = match num_kind(&a.layout) {
+        Some(NumKind::Int(width)) => with_int_type!(width, T, {
+            let x: T = read(a.ptr);
+            let y: T = read(b.ptr);
+            x.partial_cmp(&y)
+        }),
+        Some(NumKind::Float(width)) => {
+            read_float(a.ptr, width).partial_cmp(&read_float(b.ptr, width))
+        }
+        Some(NumKind::Dec) => read::<i128>(a.ptr).partial_cmp(&read::<i128>(b.ptr)),
+        None => internal_error!("comparing non-numbers of layout {:?}", a.layout),
+    };
+
+    Ok(ordering)
+}
+
+unsafe fn num_binop(op: LowLevel, a: Value, b: Value, dest: *mut u8) -> Result<(), InterpError> {
+    use LowLevel::*;
+
+    match num_kind(&a.layout) {
+        Some(NumKind::Int(width)) => with_int_type!(width, T, {
+            let x: T = read(a.ptr);
+            let y: T = read(b.ptr);
+
+            let checked = |result: Option<T>, message: &str| match result {
+                Some(result) => Ok(result),
+                None => panic_with(message),
+            };
+
+            let result: T = match op {
+                NumAdd => checked(x.checked_add(y), "integer addition overflowed!")?,
+                NumAddWrap => x.wrapping_add(y),
+                NumAddSaturated => x.saturating_add(y),
+                NumSub => checked(x.checked_sub(y), "integer subtraction overflowed!")?,
+                NumSubWrap => x.wrapping_sub(y),
+                NumSubSaturated => x.saturating_sub(y),
+                NumMul => checked(x.checked_mul(y), "integer multiplication overflowed!")?,
+                NumMulWrap => x.wrapping_mul(y),
+                NumMulSaturated => x.saturating_mul(y),
+                NumDivUnchecked | NumDivCeilUnchecked | NumRemUnchecked if y == 0 => {
+                    return panic_with("integer division by zero");
+                }
+                NumDivUnchecked => x.wrapping_div(y),
+                NumDivCeilUnchecked => {
+                    let zero: T = 0;
+                    let quotient = x.wrapping_div(y);
+                    let remainder = x.wrapping_rem(y);
+
+                    if remainder != 0 && (remainder > zero) == (y > zero) {
+                        quotient + 1
+                    } else {
+                        quotient
+                    }
+                }
+                NumRemUnchecked => x.wrapping_rem(y),
+                NumPowInt => {
+                    let exponent =
+                        u32::try_from(IntValue::read(b.ptr, width).bits()).unwrap_or(u32::MAX);
+                    checked(
+                        x.checked_pow(exponent),
+                        "integer exponentiation overflowed!",
+                    )?
+                }
+                NumBitwiseAnd => x & y,
+                NumBitwiseXor => x ^ y,
+                NumBitwiseOr => x | y,
+                _ => internal_error!("{:?} on integers", op),
+            };
+
+            write(dest, result);
+        }),
+        Some(NumKind::Float(width)) => with_float_type!(width, T, {
+            let x: T = read(a.ptr);
+            let y: T = read(b.ptr);
+
+            let result: T = match op {
+                NumAdd | NumAddWrap | NumAddSaturated => x + y,
+                NumSub | NumSubWrap | NumSubSaturated => x - y,
+                NumMul | NumMulWrap | NumMulSaturated => x * y,
+                NumDivUnchecked => x / y,
+                NumRemUnchecked => x % y,
+                NumPow => x.powf(y),
+                _ => internal_error!("{:?} on floats", op),
+            };
+
+            write(dest, result);
+        }),
+        Some(NumKind::Dec) => {
+            let x: i128 = read(a.ptr);
+            let y: i128 = read(b.ptr);
+
+            let checked = |result: Option<i128>, message: &str| match result {
+                Some(result) => Ok(result),
+                None => panic_with(message),
+            };
+
+            let result = match op {
+                NumAdd | NumAddWrap => checked(x.checked_add(y), "decimal addition overflowed")?,
+                NumAddSaturated => x.saturating_add(y),
+                NumSub | NumSubWrap => checked(x.checked_sub(y), "decimal subtraction overflowed")?,
+                NumSubSaturated => x.saturating_sub(y),
+                NumMul | NumMulWrap | NumMulSaturated => {
+                    checked(dec_mul(x, y), "decimal multiplication overflowed")?
+                }
+                NumDivUnchecked | NumRemUnchecked if y == 0 => {
+                    return panic_with("decimal division by zero");
+                }
+                NumDivUnchecked => checked(dec_div(x, y), "decimal division overflowed")?,
+                NumRemUnchecked => x % y,
+                NumPow => dec_from_f64(dec_to_f64(x).powf(dec_to_f64(y))),
+                _ => internal_error!("{:?} on decimals", op),
+            };
+
+            write(dest, result);
+        }
+        None => internal_error!("{:?} on non-numbers of layout {:?}", op, a.layout),
+    }
+
+    Ok(())
+}
+
+unsafe fn num_checked(
+    op: LowLevel,
+    a: Value,
+    b: Value,
+    ret_layout: &Layout,
+    dest: *mut u8,
+) -> Result<(), InterpError> {
+    use LowLevel::*;
+
+    let (_, value_offset, flag_offset) = value_and_flag(ret_layout);
+    let value_dest = dest.add(value_offset);
+
+    let overflowed = match num_kind(&a.layout) {
+        Some(NumKind::Int(width)) => with_int_type!(width, T, {
+            let x: T = read(a.ptr);
+            let y: T = read(b.ptr);
+
+            let (result, overflowed) = match op {
+                NumAddChecked => x.overflowing_add(y),
+                NumSubChecked => x.overflowing_sub(y),
+                _ => x.overflowing_mul(y),
+            };
+
+            write(value_dest, result);
+            overflowed
+        }),
+        Some(NumKind::Float(width)) => with_float_type!(width, T, {
+            let x: T = read(a.ptr);
+            let y: T = read(b.ptr);
+
+            let result = match op {
+                NumAddChecked => x + y,
+                NumSubChecked => x - y,
+                _ => x * y,
+            };
+
+            write(value_dest, result);
+            result.is_infinite()
+        }),
+        Some(NumKind::Dec) => {
+            let x: i128 = read(a.ptr);
+            let y: i128 = read(b.ptr);
+
+            let result = match op {
+                NumAddChecked => x.checked_add(y),
+                NumSubChecked => x.checked_sub(y),
+                _ => dec_mul(x, y),
+            };
+
+            write(value_dest, result.unwrap_or(0));
+            result.is_none()
+        }
+        None => internal_error!("{:?} on non-numbers of layout {:?}", op, a.layout),
+    };
+
+    write(dest.add(flag_offset), overflowed);
+
+    Ok(())
+}
+
+/// Shifts take the amount first, i.e. `Num.shiftLeftBy 1 x` is `x << 1`. Shifting right by
+/// `Num.shiftRightBy` always copies the sign bit, like LLVM's `ashr`.
+unsafe fn num_shift(op: LowLevel, amount: Value, value: Value, dest: *mut u8) {
+    let width = int_width(&value.layout);
+    let bits = 8 * width.stack_size();
+    let amount = IntValue::read(amount.ptr, int_width(&amount.layout)).bits();
+    let value = IntValue::read(value.ptr, width).bits();
+
+    let result = match op {
+        LowLevel::NumShiftLeftBy if amount < bits as u128 => value << amount,
+        LowLevel::NumShiftLeftBy => 0,
+        LowLevel::NumShiftRightBy => {
+            let unused = 128 - bits;
+            let signed = ((value << unused) as i128) >> unused;
+
+            (signed >> amount.min(bits as u128 - 1)) as u128
+        }
+        _ => {
+            let mask = u128::MAX >> (128 - bits);
+
+            if amount < bits as u128 {
+                (value & mask) >> amount
+            } else {
+                0
+            }
+        }
+    };
+
+    write_int_bits(dest, width, result);
+}
+
+unsafe fn num_abs_or_neg(op: LowLevel, value: Value, dest: *mut u8) -> Result<(), InterpError> {
+    const ABS_OVERFLOW: &str =
+        "integer absolute overflowed because its argument is the minimum value";
+    const NEG_OVERFLOW: &str =
+        "integer negation overflowed because its argument is the minimum value";
+
+    let is_abs = op == LowLevel::NumAbs;
+
+    match num_kind(&value.layout) {
+        Some(NumKind::Int(width)) => with_signed_int_type!(
+            width,
+            T,
+            {
+                let x: T = read(value.ptr);
+                let result = if is_abs {
+                    x.checked_abs()
+                } else {
+                    x.checked_neg()
+                };
+
+                match result {
+                    Some(result) => write(dest, result),
+                    None => return panic_with(if is_abs { ABS_OVERFLOW } else { NEG_OVERFLOW }),
+                }
+            },
+            {
+                let is_zero = IntValue::read(value.ptr, width).bits() == 0;
+
+                if !is_abs && !is_zero {
+                    return panic_with(NEG_OVERFLOW);
+                }
+
+                copy(dest, value.ptr, width.stack_size() as usize);
+            }
+        ),
+        Some(NumKind::Float(width)) => {
+            let x = read_float(value.ptr, width);
+            write_float(dest, width, if is_abs { x.abs() } else { -x });
+        }
+        Some(NumKind::Dec) => {
+            let x: i128 = read(value.ptr);
+            let result = if is_abs {
+                x.checked_abs()
+            } else {
+                x.checked_neg()
+            };
+
+            match result {
+                Some(result) => write(dest, result),
+                None => return panic_with(if is_abs { ABS_OVERFLOW } else { NEG_OVERFLOW }),
+            }
+        }
+        None => internal_error!("{:?} on non-numbers of layout {:?}", op, value.layout),
+    }
+
+    Ok(())
+}
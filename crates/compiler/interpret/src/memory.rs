@@ -0,0 +1,329 @@
+//! The memory of an interpreted program.
+//!
+//! Values live in real host memory, laid out exactly like the 64-bit backends lay them out.
+//! That way pointers inside values can be followed by `ReplAppMemory` implementations as-is,
+//! and the Zig conventions for refcounts, strings and lists carry over unchanged.
+use roc_collections::all::MutMap;
+use std::alloc::{alloc_zeroed, dealloc, Layout as AllocLayout};
+
+use crate::InterpError;
+
+pub(crate) const PTR_SIZE: usize = 8;
+
+/// The refcount of a value with exactly one reference
+pub(crate) const REFCOUNT_ONE: isize = isize::MIN;
+
+/// The refcount of a value that lives for the whole program, e.g. a constant
+const REFCOUNT_STATIC: isize = 0;
+
+/// Both `Str` and `List` are `{ pointer, length, capacity }`
+pub(crate) const STR_SIZE: usize = 3 * PTR_SIZE;
+pub(crate) const LIST_SIZE: usize = 3 * PTR_SIZE;
+
+/// Strings shorter than this are stored inline, in the bytes of the struct itself
+const SMALL_STR_MAX_LENGTH: usize = STR_SIZE - 1;
+
+/// # Safety
+/// `ptr` must be valid for reading `size_of::<T>()` bytes
+pub(crate) unsafe fn read<T: Copy>(ptr: *const u8) -> T {
+    ptr.cast::<T>().read_unaligned()
+}
+
+/// # Safety
+/// `ptr` must be valid for writing `size_of::<T>()` bytes
+pub(crate) unsafe fn write<T>(ptr: *mut u8, value: T) {
+    ptr.cast::<T>().write_unaligned(value)
+}
+
+/// # Safety
+/// Both pointers must be valid for `size` bytes
+pub(crate) unsafe fn copy(dst: *mut u8, src: *const u8, size: usize) {
+    // zero-sized values may live at null or dangling pointers, e.g. in an empty list
+    if size == 0 {
+        return;
+    }
+
+    // values may be copied onto themselves, e.g. when jumping to a join point
+    std::ptr::copy(src, dst, size)
+}
+
+/// Space for local variables. Every call and every join point pushes a frame, which is popped
+/// again by resetting the stack to a mark.
+pub(crate) struct Stack {
+    base: *mut u8,
+    size: usize,
+    used: usize,
+}
+
+impl Stack {
+    const ALIGNMENT: usize = 16;
+
+    pub(crate) fn new(size: usize) -> Self {
+        let layout = AllocLayout::from_size_align(size, Self::ALIGNMENT).unwrap();
+        let base = unsafe { alloc_zeroed(layout) };
+
+        if base.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        Self {
+            base,
+            size,
+            used: 0,
+        }
+    }
+
+    pub(crate) fn alloc(&mut self, size: usize, alignment: usize) -> Result<*mut u8, InterpError> {
+        let alignment = alignment.max(1);
+        let start = (self.used + alignment - 1) / alignment * alignment;
+        let end = start + size;
+
+        if end > self.size {
+            return Err(InterpError::StackOverflow);
+        }
+
+        self.used = end;
+
+        Ok(unsafe { self.base.add(start) })
+    }
+
+    pub(crate) fn mark(&self) -> usize {
+        self.used
+    }
+
+    pub(crate) fn reset(&mut self, mark: usize) {
+        debug_assert!(mark <= self.used);
+        self.used = mark;
+    }
+}
+
+impl Drop for Stack {
+    fn drop(&mut self) {
+        let layout = AllocLayout::from_size_align(self.size, Self::ALIGNMENT).unwrap();
+        unsafe { dealloc(self.base, layout) };
+    }
+}
+
+/// Refcounted allocations. The refcount is stored in the word right before the data.
+#[derive(Default)]
+pub(crate) struct Heap {
+    /// data pointer => (start of the allocation, its layout)
+    allocations: MutMap<usize, (usize, AllocLayout)>,
+}
+
+impl Heap {
+    /// Allocate memory for `data_bytes` bytes of data, with a refcount of one
+    pub(crate) fn allocate_with_refcount(&mut self, data_bytes: usize, alignment: u32) -> *mut u8 {
+        let alignment = (alignment as usize).max(PTR_SIZE);
+        let extra_bytes = alignment;
+
+        let layout = AllocLayout::from_size_align(extra_bytes + data_bytes, alignment).unwrap();
+
+        unsafe {
+            let allocation = alloc_zeroed(layout);
+
+            if allocation.is_null() {
+                std::alloc::handle_alloc_error(layout);
+            }
+
+            let data = allocation.add(extra_bytes);
+            write(data.sub(PTR_SIZE), REFCOUNT_ONE);
+
+            self.allocations
+                .insert(data as usize, (allocation as usize, layout));
+
+            data
+        }
+    }
+
+    pub(crate) fn free(&mut self, data: *mut u8) {
+        match self.allocations.remove(&(data as usize)) {
+            Some((allocation, layout)) => unsafe { dealloc(allocation as *mut u8, layout) },
+            None => {
+                // a static value, or memory that we did not allocate
+            }
+        }
+    }
+
+    /// The number of allocations that were not freed yet
+    pub(crate) fn live_allocations(&self) -> usize {
+        self.allocations.len()
+    }
+}
+
+impl Drop for Heap {
+    fn drop(&mut self) {
+        for (_, (allocation, layout)) in self.allocations.drain() {
+            unsafe { dealloc(allocation as *mut u8, layout) };
+        }
+    }
+}
+
+/// # Safety
+/// `data` must be null or point to the data of a refcounted allocation
+pub(crate) unsafe fn increment_refcount(data: *const u8, amount: isize) {
+    if data.is_null() {
+        return;
+    }
+
+    let refcount_ptr = data.sub(PTR_SIZE) as *mut u8;
+    let refcount: isize = read(refcount_ptr);
+
+    if refcount != REFCOUNT_STATIC {
+        write(refcount_ptr, refcount.wrapping_add(amount));
+    }
+}
+
+/// Decrement the refcount, and return whether that was the last reference. The caller is
+/// responsible for freeing the memory in that case.
+///
+/// # Safety
+/// `data` must be null or point to the data of a refcounted allocation
+pub(crate) unsafe fn decrement_refcount(data: *const u8) -> bool {
+    if data.is_null() {
+        return false;
+    }
+
+    let refcount_ptr = data.sub(PTR_SIZE) as *mut u8;
+    let refcount: isize = read(refcount_ptr);
+
+    match refcount {
+        REFCOUNT_STATIC => false,
+        REFCOUNT_ONE => true,
+        _ => {
+            write(refcount_ptr, refcount.wrapping_sub(1));
+            false
+        }
+    }
+}
+
+/// # Safety
+/// `data` must be null or point to the data of a refcounted allocation
+pub(crate) unsafe fn is_unique(data: *const u8) -> bool {
+    data.is_null() || read::<isize>(data.sub(PTR_SIZE)) == REFCOUNT_ONE
+}
+
+/// A `Str` or `List`, as stored in memory
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RocSeq {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl RocSeq {
+    pub(crate) const EMPTY: Self = Self {
+        ptr: std::ptr::null_mut(),
+        len: 0,
+        capacity: 0,
+    };
+
+    /// # Safety
+    /// `ptr` must point to a `List`
+    pub(crate) unsafe fn read_list(ptr: *const u8) -> Self {
+        Self {
+            ptr: read(ptr),
+            len: read(ptr.add(PTR_SIZE)),
+            capacity: read(ptr.add(2 * PTR_SIZE)),
+        }
+    }
+
+    /// # Safety
+    /// `ptr` must be valid for writing a `List`
+    pub(crate) unsafe fn write_list(self, ptr: *mut u8) {
+        write(ptr, self.ptr);
+        write(ptr.add(PTR_SIZE), self.len);
+        write(ptr.add(2 * PTR_SIZE), self.capacity);
+    }
+}
+
+/// Strings are either small, with their bytes stored inline and the length in the last byte,
+/// or big, in which case they are laid out like a list of bytes.
+///
+/// # Safety
+/// `ptr` must point to a `Str`
+pub(crate) unsafe fn str_is_small(ptr: *const u8) -> bool {
+    read::<i8>(ptr.add(STR_SIZE - 1)) < 0
+}
+
+/// The heap allocation of a string, if it has one
+///
+/// # Safety
+/// `ptr` must point to a `Str`
+pub(crate) unsafe fn str_allocation(ptr: *const u8) -> Option<RocSeq> {
+    if str_is_small(ptr) {
+        None
+    } else {
+        Some(RocSeq::read_list(ptr)).filter(|seq| !seq.ptr.is_null())
+    }
+}
+
+/// # Safety
+/// `ptr` must point to a `Str` that outlives the returned slice
+pub(crate) unsafe fn str_bytes<'b>(ptr: *const u8) -> &'b [u8] {
+    if str_is_small(ptr) {
+        let len = (read::<u8>(ptr.add(STR_SIZE - 1)) & 0b0111_1111) as usize;
+        std::slice::from_raw_parts(ptr, len)
+    } else {
+        let seq = RocSeq::read_list(ptr);
+
+        if seq.len == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(seq.ptr, seq.len)
+        }
+    }
+}
+
+/// # Safety
+/// `ptr` must point to a `Str` that outlives the returned slice
+pub(crate) unsafe fn str_as_str<'b>(ptr: *const u8) -> &'b str {
+    std::str::from_utf8_unchecked(str_bytes(ptr))
+}
+
+/// # Safety
+/// `ptr` must point to a `Str`
+pub(crate) unsafe fn str_capacity(ptr: *const u8) -> usize {
+    if str_is_small(ptr) {
+        SMALL_STR_MAX_LENGTH
+    } else {
+        RocSeq::read_list(ptr).capacity
+    }
+}
+
+/// Write a new string with the given bytes, allocating if they do not fit inline
+///
+/// # Safety
+/// `dst` must be valid for writing a `Str`
+pub(crate) unsafe fn write_str(heap: &mut Heap, dst: *mut u8, bytes: &[u8]) {
+    write_str_with_capacity(heap, dst, bytes, bytes.len())
+}
+
+/// # Safety
+/// `dst` must be valid for writing a `Str`
+pub(crate) unsafe fn write_str_with_capacity(
+    heap: &mut Heap,
+    dst: *mut u8,
+    bytes: &[u8],
+    capacity: usize,
+) {
+    let capacity = capacity.max(bytes.len());
+
+    if bytes.is_empty() && capacity == 0 {
+        RocSeq::EMPTY.write_list(dst);
+    } else if capacity <= SMALL_STR_MAX_LENGTH {
+        std::ptr::write_bytes(dst, 0, STR_SIZE);
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, bytes.len());
+        write(dst.add(STR_SIZE - 1), bytes.len() as u8 | 0b1000_0000);
+    } else {
+        let ptr = heap.allocate_with_refcount(capacity, PTR_SIZE as u32);
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+
+        RocSeq {
+            ptr,
+            len: bytes.len(),
+            capacity,
+        }
+        .write_list(dst);
+    }
+}
@@ -0,0 +1,370 @@
+//! Numbers of every width, and the fixed-point arithmetic of `Dec`.
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_mono::layout::{Builtin, Layout};
+
+use crate::memory::{read, write};
+
+/// Run `$body` with `$t` bound to the Rust type of the integer width
+macro_rules! with_int_type {
+    ($width:expr, $t:ident, $body:block) => {{
+        use roc_builtins::bitcode::IntWidth;
+
+        match $width {
+            IntWidth::U8 => {
+                type $t = u8;
+                $body
+            }
+            IntWidth::U16 => {
+                type $t = u16;
+                $body
+            }
+            IntWidth::U32 => {
+                type $t = u32;
+                $body
+            }
+            IntWidth::U64 => {
+                type $t = u64;
+                $body
+            }
+            IntWidth::U128 => {
+                type $t = u128;
+                $body
+            }
+            IntWidth::I8 => {
+                type $t = i8;
+                $body
+            }
+            IntWidth::I16 => {
+                type $t = i16;
+                $body
+            }
+            IntWidth::I32 => {
+                type $t = i32;
+                $body
+            }
+            IntWidth::I64 => {
+                type $t = i64;
+                $body
+            }
+            IntWidth::I128 => {
+                type $t = i128;
+                $body
+            }
+        }
+    }};
+}
+
+/// Like `with_int_type`, for operations that only make sense on signed integers
+macro_rules! with_signed_int_type {
+    ($width:expr, $t:ident, $body:block, $unsigned:block) => {{
+        use roc_builtins::bitcode::IntWidth;
+
+        match $width {
+            IntWidth::I8 => {
+                type $t = i8;
+                $body
+            }
+            IntWidth::I16 => {
+                type $t = i16;
+                $body
+            }
+            IntWidth::I32 => {
+                type $t = i32;
+                $body
+            }
+            IntWidth::I64 => {
+                type $t = i64;
+                $body
+            }
+            IntWidth::I128 => {
+                type $t = i128;
+                $body
+            }
+            IntWidth::U8 | IntWidth::U16 | IntWidth::U32 | IntWidth::U64 | IntWidth::U128 => {
+                $unsigned
+            }
+        }
+    }};
+}
+
+/// Run `$body` with `$t` bound to the Rust type of the float width
+macro_rules! with_float_type {
+    ($width:expr, $t:ident, $body:block) => {{
+        use roc_builtins::bitcode::FloatWidth;
+
+        match $width {
+            FloatWidth::F32 => {
+                type $t = f32;
+                $body
+            }
+            FloatWidth::F64 => {
+                type $t = f64;
+                $body
+            }
+            FloatWidth::F128 => {
+                return Err($crate::InterpError::Unsupported("F128".to_string()));
+            }
+        }
+    }};
+}
+
+pub(crate) use {with_float_type, with_int_type, with_signed_int_type};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NumKind {
+    Int(IntWidth),
+    Float(FloatWidth),
+    Dec,
+}
+
+pub(crate) fn num_kind(layout: &Layout) -> Option<NumKind> {
+    match layout {
+        Layout::Builtin(Builtin::Int(width)) => Some(NumKind::Int(*width)),
+        Layout::Builtin(Builtin::Float(width)) => Some(NumKind::Float(*width)),
+        Layout::Builtin(Builtin::Decimal) => Some(NumKind::Dec),
+        _ => None,
+    }
+}
+
+/// An integer of any width, without losing the sign or the top bit of `U128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IntValue {
+    Signed(i128),
+    Unsigned(u128),
+}
+
+impl IntValue {
+    /// # Safety
+    /// `ptr` must point to an integer of the given width
+    pub(crate) unsafe fn read(ptr: *const u8, width: IntWidth) -> Self {
+        with_int_type!(width, T, {
+            let value: T = read(ptr);
+
+            if width.is_signed() {
+                IntValue::Signed(value as i128)
+            } else {
+                IntValue::Unsigned(value as u128)
+            }
+        })
+    }
+
+    /// Two's complement bits, sign-extended to 128 bits
+    pub(crate) fn bits(self) -> u128 {
+        match self {
+            IntValue::Signed(value) => value as u128,
+            IntValue::Unsigned(value) => value,
+        }
+    }
+
+    pub(crate) fn to_f64(self) -> f64 {
+        match self {
+            IntValue::Signed(value) => value as f64,
+            IntValue::Unsigned(value) => value as f64,
+        }
+    }
+
+    /// Whether the value can be represented in the given width without changing it
+    pub(crate) fn fits_in(self, width: IntWidth) -> bool {
+        let (min, max): (i128, u128) =
+            with_int_type!(width, T, { (T::MIN as i128, T::MAX as u128) });
+
+        match self {
+            IntValue::Signed(value) => value >= min && (value < 0 || value as u128 <= max),
+            IntValue::Unsigned(value) => value <= max,
+        }
+    }
+}
+
+/// Write the lowest bytes of the (sign-extended) bits, i.e. truncate or extend to the width
+///
+/// # Safety
+/// `dst` must be valid for writing an integer of the given width
+pub(crate) unsafe fn write_int_bits(dst: *mut u8, width: IntWidth, bits: u128) {
+    let bytes = bits.to_le_bytes();
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), dst, width.stack_size() as usize);
+}
+
+/// # Safety
+/// `ptr` must point to a float of the given width
+pub(crate) unsafe fn read_float(ptr: *const u8, width: FloatWidth) -> f64 {
+    match width {
+        FloatWidth::F32 => read::<f32>(ptr) as f64,
+        FloatWidth::F64 | FloatWidth::F128 => read::<f64>(ptr),
+    }
+}
+
+/// # Safety
+/// `dst` must be valid for writing a float of the given width
+pub(crate) unsafe fn write_float(dst: *mut u8, width: FloatWidth, value: f64) {
+    match width {
+        FloatWidth::F32 => write(dst, value as f32),
+        FloatWidth::F64 | FloatWidth::F128 => write(dst, value),
+    }
+}
+
+/// Format a float the way Zig's `{d}` does
+pub(crate) fn float_to_string(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// A `Dec` is an `i128` that counts in units of 10^-18
+pub(crate) const DEC_ONE: i128 = 1_000_000_000_000_000_000;
+const DEC_DECIMAL_PLACES: usize = 18;
+
+pub(crate) fn dec_from_f64(value: f64) -> i128 {
+    (value * DEC_ONE as f64) as i128
+}
+
+pub(crate) fn dec_to_f64(value: i128) -> f64 {
+    value as f64 / DEC_ONE as f64
+}
+
+/// Format a `Dec` like the Zig builtins do, always with a decimal point
+pub(crate) fn dec_to_string(value: i128) -> String {
+    let magnitude = value.unsigned_abs();
+    let whole = magnitude / DEC_ONE as u128;
+    let fraction = magnitude % DEC_ONE as u128;
+
+    let sign = if value < 0 { "-" } else { "" };
+
+    if fraction == 0 {
+        format!("{}{}.0", sign, whole)
+    } else {
+        let digits = format!("{:0width$}", fraction, width = DEC_DECIMAL_PLACES);
+        format!("{}{}.{}", sign, whole, digits.trim_end_matches('0'))
+    }
+}
+
+/// Multiply two `Dec`s, or `None` if the result does not fit
+pub(crate) fn dec_mul(a: i128, b: i128) -> Option<i128> {
+    let is_negative = (a < 0) != (b < 0);
+    let product = U256::mul(a.unsigned_abs(), b.unsigned_abs());
+    let magnitude = product.div_u128(DEC_ONE as u128).to_u128()?;
+
+    apply_sign(magnitude, is_negative)
+}
+
+/// Divide two `Dec`s, or `None` if the result does not fit. The divisor must not be zero.
+pub(crate) fn dec_div(a: i128, b: i128) -> Option<i128> {
+    let is_negative = (a < 0) != (b < 0);
+    let numerator = U256::mul(a.unsigned_abs(), DEC_ONE as u128);
+    let magnitude = numerator.div_u128(b.unsigned_abs()).to_u128()?;
+
+    apply_sign(magnitude, is_negative)
+}
+
+fn apply_sign(magnitude: u128, is_negative: bool) -> Option<i128> {
+    if !is_negative {
+        i128::try_from(magnitude).ok()
+    } else if magnitude == i128::MIN.unsigned_abs() {
+        Some(i128::MIN)
+    } else {
+        i128::try_from(magnitude).ok().map(|magnitude| -magnitude)
+    }
+}
+
+/// Just enough of a 256-bit unsigned integer for `Dec` multiplication and division
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct U256 {
+    hi: u128,
+    lo: u128,
+}
+
+impl U256 {
+    fn mul(a: u128, b: u128) -> Self {
+        const MASK: u128 = u64::MAX as u128;
+
+        let (a_hi, a_lo) = (a >> 64, a & MASK);
+        let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+        let lo_lo = a_lo * b_lo;
+        let hi_lo = a_hi * b_lo;
+        let lo_hi = a_lo * b_hi;
+        let hi_hi = a_hi * b_hi;
+
+        let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+
+        Self {
+            hi: hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64),
+            lo: (middle << 64) | (lo_lo & MASK),
+        }
+    }
+
+    /// Schoolbook long division, one bit at a time
+    fn div_u128(self, divisor: u128) -> Self {
+        let mut quotient = Self { hi: 0, lo: 0 };
+        let mut remainder: u128 = 0;
+
+        for i in (0..256).rev() {
+            let bit = if i >= 128 {
+                (self.hi >> (i - 128)) & 1
+            } else {
+                (self.lo >> i) & 1
+            };
+
+            let overflowed = remainder >> 127 == 1;
+            remainder = (remainder << 1) | bit;
+
+            if overflowed || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+
+                if i >= 128 {
+                    quotient.hi |= 1 << (i - 128);
+                } else {
+                    quotient.lo |= 1 << i;
+                }
+            }
+        }
+
+        quotient
+    }
+
+    fn to_u128(self) -> Option<u128> {
+        if self.hi == 0 {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> i128 {
+        roc_std::RocDec::from_str_to_i128_unsafe(s)
+    }
+
+    #[test]
+    fn dec_arithmetic() {
+        assert_eq!(dec_mul(dec("1.5"), dec("-2.25")), Some(dec("-3.375")));
+        assert_eq!(
+            dec_mul(dec("170141183460469231731"), dec("0.5")),
+            Some(dec("85070591730234615865.5"))
+        );
+        assert_eq!(dec_mul(dec("170141183460469231731"), dec("2")), None);
+        assert_eq!(dec_div(dec("1"), dec("3")), Some(333_333_333_333_333_333));
+        assert_eq!(dec_div(dec("-7.5"), dec("2.5")), Some(dec("-3")));
+    }
+
+    #[test]
+    fn dec_strings() {
+        assert_eq!(dec_to_string(0), "0.0");
+        assert_eq!(dec_to_string(dec("42")), "42.0");
+        assert_eq!(dec_to_string(dec("-0.125")), "-0.125");
+    }
+
+    #[test]
+    fn int_ranges() {
+        assert!(IntValue::Signed(-1).fits_in(IntWidth::I8));
+        assert!(!IntValue::Signed(-1).fits_in(IntWidth::U64));
+        assert!(IntValue::Unsigned(u64::MAX as u128).fits_in(IntWidth::U64));
+        assert!(!IntValue::Unsigned(u64::MAX as u128).fits_in(IntWidth::I64));
+        assert!(!IntValue::Signed(256).fits_in(IntWidth::U8));
+    }
+}
@@ -0,0 +1,291 @@
+//! Reference counting, following the layout of a value.
+//!
+//! The backends generate a helper procedure per layout for this (see `roc_mono::code_gen_help`).
+//! The interpreter walks the layout instead. Decrementing uses an explicit worklist rather
+//! than recursion, so freeing a long linked list does not overflow the native stack.
+use roc_mono::layout::{Builtin, Layout, UnionLayout};
+
+use crate::memory::{
+    decrement_refcount, increment_refcount, is_unique, read, str_allocation, RocSeq,
+};
+use crate::tags::{field_offset, layout_size, read_tag_id, resolve_field, tag_fields, union_data};
+use crate::{Interpreter, Value};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RcOp {
+    Inc(isize),
+    /// Decrement, and free the value and decrement its children if it was the last reference
+    Dec,
+    /// Decrement, but only free the value itself; its children were moved out already
+    DecRef,
+}
+
+enum Work<'a> {
+    Dec(*const u8, Layout<'a>, Option<UnionLayout<'a>>),
+    Free(*mut u8),
+}
+
+impl<'a> Interpreter<'a> {
+    /// # Safety
+    /// `value` must point to a value of the given layout
+    pub(crate) unsafe fn modify_refcount(
+        &mut self,
+        value: *const u8,
+        layout: &Layout<'a>,
+        op: RcOp,
+    ) {
+        match op {
+            RcOp::Inc(amount) => increment(value, layout, amount, None),
+            RcOp::Dec => self.decrement(value, layout),
+            RcOp::DecRef => self.decrement_top_level(value, layout),
+        }
+    }
+
+    /// # Safety
+    /// `value` must point to a value of the given layout
+    pub(crate) unsafe fn increment(
+        &mut self,
+        value: *const u8,
+        layout: &Layout<'a>,
+        amount: isize,
+    ) {
+        increment(value, layout, amount, None)
+    }
+
+    /// # Safety
+    /// `value` must point to a value of the given layout
+    pub(crate) unsafe fn decrement(&mut self, value: *const u8, layout: &Layout<'a>) {
+        self.decrement_in(value, layout, None)
+    }
+
+    unsafe fn decrement_in(
+        &mut self,
+        value: *const u8,
+        layout: &Layout<'a>,
+        when_recursive: Option<UnionLayout<'a>>,
+    ) {
+        let mut work = vec![Work::Dec(value, *layout, when_recursive)];
+
+        while let Some(item) = work.pop() {
+            match item {
+                Work::Free(data) => self.heap.free(data),
+                Work::Dec(value, layout, when_recursive) => {
+                    decrement_help(value, layout, when_recursive, &mut work)
+                }
+            }
+        }
+    }
+
+    unsafe fn decrement_top_level(&mut self, value: *const u8, layout: &Layout<'a>) {
+        let data = match layout {
+            Layout::Builtin(Builtin::Str) => match str_allocation(value) {
+                Some(seq) => seq.ptr,
+                None => return,
+            },
+            Layout::Builtin(Builtin::List(_)) => RocSeq::read_list(value).ptr,
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+                // not heap-allocated, so this is the same as decrementing the children
+                let fields = tag_fields(union_layout, read_tag_id(value, union_layout));
+
+                for (i, field) in fields.iter().enumerate() {
+                    self.decrement_top_level(value.add(field_offset(fields, i)), field);
+                }
+
+                return;
+            }
+            Layout::Union(union_layout) => union_data(value, union_layout),
+            Layout::Boxed(_) => read(value),
+            Layout::Struct { field_layouts, .. } => {
+                for (i, field) in field_layouts.iter().enumerate() {
+                    self.decrement_top_level(value.add(field_offset(field_layouts, i)), field);
+                }
+
+                return;
+            }
+            Layout::LambdaSet(lambda_set) => {
+                return self.decrement_top_level(value, &lambda_set.runtime_representation());
+            }
+            Layout::Builtin(_) | Layout::RecursivePointer => return,
+        };
+
+        if decrement_refcount(data) {
+            self.heap.free(data);
+        }
+    }
+
+    /// The `Reset` of a value that is about to be replaced by a value of the same layout. If
+    /// this was the only reference, the children are decremented and the allocation is returned
+    /// for reuse. Otherwise the value is decremented and the result is null.
+    ///
+    /// # Safety
+    /// `value.ptr` must point to a value of `value.layout`
+    pub(crate) unsafe fn reset(&mut self, value: Value<'a>) -> *mut u8 {
+        match value.layout {
+            Layout::Union(union_layout)
+                if !matches!(union_layout, UnionLayout::NonRecursive(_)) =>
+            {
+                let data = union_data(value.ptr, &union_layout);
+
+                if data.is_null() {
+                    std::ptr::null_mut()
+                } else if is_unique(data) {
+                    let tag_id = read_tag_id(value.ptr, &union_layout);
+                    let fields = tag_fields(&union_layout, tag_id);
+
+                    for (i, field) in fields.iter().enumerate() {
+                        let field_ptr = data.add(field_offset(fields, i));
+                        let field = resolve_field(field, &union_layout);
+                        self.decrement_in(field_ptr, &field, Some(union_layout));
+                    }
+
+                    data
+                } else {
+                    self.decrement(value.ptr, &value.layout);
+
+                    std::ptr::null_mut()
+                }
+            }
+            _ => {
+                self.decrement(value.ptr, &value.layout);
+
+                std::ptr::null_mut()
+            }
+        }
+    }
+}
+
+unsafe fn increment(
+    value: *const u8,
+    layout: &Layout,
+    amount: isize,
+    when_recursive: Option<&UnionLayout>,
+) {
+    match layout {
+        Layout::Builtin(Builtin::Str) => {
+            if let Some(seq) = str_allocation(value) {
+                increment_refcount(seq.ptr, amount)
+            }
+        }
+        Layout::Builtin(Builtin::List(_)) => {
+            increment_refcount(RocSeq::read_list(value).ptr, amount)
+        }
+        Layout::Builtin(_) => {}
+        Layout::Struct { field_layouts, .. } => {
+            for (i, field) in field_layouts.iter().enumerate() {
+                let offset = field_offset(field_layouts, i);
+                increment(value.add(offset), field, amount, when_recursive);
+            }
+        }
+        Layout::LambdaSet(lambda_set) => increment(
+            value,
+            &lambda_set.runtime_representation(),
+            amount,
+            when_recursive,
+        ),
+        Layout::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+            let tag_id = read_tag_id(value, union_layout);
+            let fields = tag_fields(union_layout, tag_id);
+
+            for (i, field) in fields.iter().enumerate() {
+                let offset = field_offset(fields, i);
+                increment(value.add(offset), field, amount, when_recursive);
+            }
+        }
+        Layout::Union(union_layout) => increment_refcount(union_data(value, union_layout), amount),
+        Layout::Boxed(_) => increment_refcount(read::<*const u8>(value), amount),
+        Layout::RecursivePointer => match when_recursive {
+            Some(union_layout) => increment_refcount(union_data(value, union_layout), amount),
+            None => roc_error_macros::internal_error!("recursive pointer outside of a union"),
+        },
+    }
+}
+
+unsafe fn decrement_help<'a>(
+    value: *const u8,
+    layout: Layout<'a>,
+    when_recursive: Option<UnionLayout<'a>>,
+    work: &mut std::vec::Vec<Work<'a>>,
+) {
+    match layout {
+        Layout::Builtin(Builtin::Str) => {
+            if let Some(seq) = str_allocation(value) {
+                if decrement_refcount(seq.ptr) {
+                    work.push(Work::Free(seq.ptr));
+                }
+            }
+        }
+        Layout::Builtin(Builtin::List(elem_layout)) => {
+            let seq = RocSeq::read_list(value);
+
+            if decrement_refcount(seq.ptr) {
+                // freed after the elements, because those are popped first
+                work.push(Work::Free(seq.ptr));
+
+                if elem_layout.contains_refcounted() {
+                    let elem_size = layout_size(elem_layout);
+
+                    for i in 0..seq.len {
+                        work.push(Work::Dec(
+                            seq.ptr.add(i * elem_size),
+                            *elem_layout,
+                            when_recursive,
+                        ));
+                    }
+                }
+            }
+        }
+        Layout::Builtin(_) => {}
+        Layout::Struct { field_layouts, .. } => {
+            for (i, field) in field_layouts.iter().enumerate() {
+                let offset = field_offset(field_layouts, i);
+                work.push(Work::Dec(value.add(offset), *field, when_recursive));
+            }
+        }
+        Layout::LambdaSet(lambda_set) => work.push(Work::Dec(
+            value,
+            lambda_set.runtime_representation(),
+            when_recursive,
+        )),
+        Layout::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+            let tag_id = read_tag_id(value, &union_layout);
+            let fields = tag_fields(&union_layout, tag_id);
+
+            for (i, field) in fields.iter().enumerate() {
+                let offset = field_offset(fields, i);
+                work.push(Work::Dec(value.add(offset), *field, when_recursive));
+            }
+        }
+        Layout::Union(union_layout) => {
+            let data = union_data(value, &union_layout);
+
+            if decrement_refcount(data) {
+                let tag_id = read_tag_id(value, &union_layout);
+                let fields = tag_fields(&union_layout, tag_id);
+
+                work.push(Work::Free(data));
+
+                for (i, field) in fields.iter().enumerate() {
+                    let offset = field_offset(fields, i);
+                    let field = resolve_field(field, &union_layout);
+                    work.push(Work::Dec(data.add(offset), field, Some(union_layout)));
+                }
+            }
+        }
+        Layout::Boxed(inner) => {
+            let data = read::<*mut u8>(value);
+
+            if decrement_refcount(data) {
+                work.push(Work::Free(data));
+                work.push(Work::Dec(data, *inner, when_recursive));
+            }
+        }
+        Layout::RecursivePointer => match when_recursive {
+            Some(union_layout) => work.push(Work::Dec(
+                value,
+                Layout::Union(union_layout),
+                when_recursive,
+            )),
+            None => roc_error_macros::internal_error!("recursive pointer outside of a union"),
+        },
+    }
+}
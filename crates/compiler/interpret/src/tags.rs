@@ -0,0 +1,138 @@
+//! Where the tag id and the payload of a tag union are stored.
+use roc_mono::layout::{Layout, TagIdIntType, UnionLayout};
+
+use crate::memory::{read, write};
+use crate::TARGET_INFO;
+
+/// Recursive unions with few enough tags store the tag id in the low bits of their pointer
+const TAG_ID_POINTER_MASK: usize = 0b111;
+
+pub(crate) fn layout_size(layout: &Layout) -> usize {
+    layout.stack_size(TARGET_INFO) as usize
+}
+
+pub(crate) fn layout_alignment(layout: &Layout) -> usize {
+    layout.alignment_bytes(TARGET_INFO) as usize
+}
+
+/// Fields are laid out in order, without padding; they are already sorted by alignment
+pub(crate) fn field_offset(field_layouts: &[Layout], index: usize) -> usize {
+    field_layouts[..index].iter().map(layout_size).sum()
+}
+
+/// The fields of a tag, with recursive pointers left as they are
+pub(crate) fn tag_fields<'a>(
+    union_layout: &UnionLayout<'a>,
+    tag_id: TagIdIntType,
+) -> &'a [Layout<'a>] {
+    match *union_layout {
+        UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => tags[tag_id as usize],
+        UnionLayout::NonNullableUnwrapped(fields) => fields,
+        UnionLayout::NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => {
+            if tag_id == nullable_id {
+                &[]
+            } else if tag_id < nullable_id {
+                other_tags[tag_id as usize]
+            } else {
+                other_tags[tag_id as usize - 1]
+            }
+        }
+        UnionLayout::NullableUnwrapped {
+            nullable_id,
+            other_fields,
+        } => {
+            if (tag_id != 0) == nullable_id {
+                &[]
+            } else {
+                other_fields
+            }
+        }
+    }
+}
+
+/// Fields that point back to the union itself have the layout of the union
+pub(crate) fn resolve_field<'a>(layout: &Layout<'a>, union_layout: &UnionLayout<'a>) -> Layout<'a> {
+    match layout {
+        Layout::RecursivePointer => Layout::Union(*union_layout),
+        other => *other,
+    }
+}
+
+/// Remove the tag id from a pointer to the heap-allocated data of a recursive union
+pub(crate) fn clear_tag_id(ptr: usize, union_layout: &UnionLayout) -> *mut u8 {
+    if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+        (ptr & !TAG_ID_POINTER_MASK) as *mut u8
+    } else {
+        ptr as *mut u8
+    }
+}
+
+/// Where the payload of the tag union value at `value` lives, if it has one
+///
+/// # Safety
+/// `value` must point to a value of the union layout
+pub(crate) unsafe fn union_data(value: *const u8, union_layout: &UnionLayout) -> *mut u8 {
+    match union_layout {
+        UnionLayout::NonRecursive(_) => value as *mut u8,
+        _ => clear_tag_id(read::<usize>(value), union_layout),
+    }
+}
+
+/// # Safety
+/// `value` must point to a value of the union layout
+pub(crate) unsafe fn read_tag_id(value: *const u8, union_layout: &UnionLayout) -> TagIdIntType {
+    let read_stored_tag_id = |data: *const u8| {
+        let offset = union_layout.tag_id_offset(TARGET_INFO).unwrap() as usize;
+
+        match union_layout.tag_id_layout().stack_size(TARGET_INFO) {
+            1 => read::<u8>(data.add(offset)) as TagIdIntType,
+            _ => read::<u16>(data.add(offset)) as TagIdIntType,
+        }
+    };
+
+    match union_layout {
+        UnionLayout::NonRecursive(_) => read_stored_tag_id(value),
+        UnionLayout::Recursive(_) => {
+            let ptr = read::<usize>(value);
+
+            if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+                (ptr & TAG_ID_POINTER_MASK) as TagIdIntType
+            } else {
+                read_stored_tag_id(ptr as *const u8)
+            }
+        }
+        UnionLayout::NonNullableUnwrapped(_) => 0,
+        UnionLayout::NullableWrapped { nullable_id, .. } => {
+            let ptr = read::<usize>(value);
+
+            if ptr == 0 {
+                *nullable_id
+            } else if union_layout.stores_tag_id_in_pointer(TARGET_INFO) {
+                (ptr & TAG_ID_POINTER_MASK) as TagIdIntType
+            } else {
+                read_stored_tag_id(ptr as *const u8)
+            }
+        }
+        UnionLayout::NullableUnwrapped { nullable_id, .. } => {
+            let is_null = read::<usize>(value) == 0;
+
+            (is_null == *nullable_id) as TagIdIntType
+        }
+    }
+}
+
+/// Store the tag id in the data of a union that does not keep it in its pointer
+///
+/// # Safety
+/// `data` must point to the (payload) data of the union layout
+pub(crate) unsafe fn write_tag_id(data: *mut u8, union_layout: &UnionLayout, tag_id: TagIdIntType) {
+    let offset = union_layout.tag_id_offset(TARGET_INFO).unwrap() as usize;
+
+    match union_layout.tag_id_layout().stack_size(TARGET_INFO) {
+        1 => write(data.add(offset), tag_id as u8),
+        _ => write(data.add(offset), tag_id as u16),
+    }
+}
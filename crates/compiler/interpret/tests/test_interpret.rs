@@ -0,0 +1,354 @@
+#[macro_use]
+extern crate indoc;
+
+#[cfg(test)]
+mod test_interpret {
+    use bumpalo::Bump;
+    use roc_interpret::{with_native_stack, InterpError, Interpreter};
+    use roc_load::{ExecutionMode, LoadConfig, Threading};
+    use roc_reporting::report::RenderTarget;
+    use roc_std::{RocList, RocStr};
+    use roc_target::TargetInfo;
+    use std::path::PathBuf;
+
+    fn promote_expr_to_module(src: &str) -> String {
+        let mut buffer = String::from("app \"test\" provides [main] to \"./platform\"\n\nmain =\n");
+
+        for line in src.lines() {
+            // indent the body!
+            buffer.push_str("    ");
+            buffer.push_str(line);
+            buffer.push('\n');
+        }
+
+        buffer
+    }
+
+    /// Interpret `main`, and read its result with `read`. Also returns the number of heap
+    /// allocations that are still alive afterwards, which should only be the ones that the
+    /// result points to.
+    fn interpret<T, F>(src: &'static str, read: F) -> Result<(T, usize), InterpError>
+    where
+        T: Send + 'static,
+        F: FnOnce(*const u8) -> T + Send + 'static,
+    {
+        with_native_stack(move || {
+            let arena = Bump::new();
+            let module_src = promote_expr_to_module(src);
+
+            let load_config = LoadConfig {
+                target_info: TargetInfo::default_x86_64(),
+                render: RenderTarget::Generic,
+                threading: Threading::Single,
+                exec_mode: ExecutionMode::Executable,
+                inline_and_fold: false,
+                record_call_regions: false,
+                collect_package_modules: false,
+            };
+            let loaded = roc_load::load_and_monomorphize_from_str(
+                &arena,
+                PathBuf::from("Test.roc"),
+                arena.alloc(module_src),
+                PathBuf::from("fake/test/path"),
+                Default::default(),
+                load_config,
+            );
+
+            let mut loaded = match loaded {
+                Ok(x) => x,
+                Err(roc_load::LoadingProblem::FormattedReport(report)) => {
+                    panic!("{}", report)
+                }
+                Err(e) => panic!("{:?}", e),
+            };
+
+            let main_fn_symbol = *loaded.exposed_to_host.values.keys().next().unwrap();
+            let (_, main_fn_layout) = *loaded
+                .procedures
+                .keys()
+                .find(|(symbol, _)| *symbol == main_fn_symbol)
+                .unwrap();
+
+            let procedures = std::mem::take(&mut loaded.procedures);
+            let mut interpreter = Interpreter::new(&arena, procedures);
+
+            let result = interpreter.run_thunk(main_fn_symbol, main_fn_layout)?;
+            let value = read(result);
+
+            Ok((value, interpreter.live_allocations()))
+        })
+    }
+
+    fn read_i64(ptr: *const u8) -> i64 {
+        unsafe { *(ptr as *const i64) }
+    }
+
+    fn read_str(ptr: *const u8) -> String {
+        unsafe { &*(ptr as *const RocStr) }.as_str().to_string()
+    }
+
+    fn read_i64_list(ptr: *const u8) -> Vec<i64> {
+        unsafe { &*(ptr as *const RocList<i64>) }
+            .as_slice()
+            .to_vec()
+    }
+
+    fn assert_evals_to_i64(src: &'static str, expected: i64) {
+        let (value, live) = interpret(src, read_i64).unwrap();
+
+        assert_eq!(value, expected);
+        assert_eq!(live, 0, "the program leaked {} allocations", live);
+    }
+
+    #[test]
+    fn num_lowlevels() {
+        assert_evals_to_i64("1 + 2 * 3 - 4", 3);
+        assert_evals_to_i64("Num.abs -42 + Num.rem 17 5", 44);
+        assert_evals_to_i64("Num.toI64 (Num.bitwiseAnd 0b1100u8 0b1010u8)", 8);
+        assert_evals_to_i64("if 3 < 4 && 5 >= 5 then 1 else 0", 1);
+    }
+
+    #[test]
+    fn integer_overflow_panics() {
+        let result = interpret("Num.maxI64 + 1", read_i64);
+
+        assert!(matches!(result, Err(InterpError::Panic(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn str_lowlevels() {
+        let (value, _) = interpret(
+            indoc!(
+                r#"
+                greeting = Str.concat "Hello, " "this string is too long to be small"
+
+                Str.joinWith [greeting, Num.toStr 42] " "
+                "#
+            ),
+            read_str,
+        )
+        .unwrap();
+
+        assert_eq!(value, "Hello, this string is too long to be small 42");
+    }
+
+    #[test]
+    fn list_lowlevels() {
+        let (value, live) = interpret(
+            indoc!(
+                r#"
+                [1, 2, 3]
+                    |> List.append 4
+                    |> List.map (\x -> x * 10)
+                    |> List.dropFirst
+                "#
+            ),
+            read_i64_list,
+        )
+        .unwrap();
+
+        assert_eq!(value, [20, 30, 40]);
+        // only the list that is returned is still alive
+        assert_eq!(live, 1);
+    }
+
+    #[test]
+    fn refcounted_values_are_freed() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                list = List.repeat "a string that is too long to be small" 10
+
+                copy = list
+
+                Num.toI64 (List.len list + List.len copy)
+                "#
+            ),
+            20,
+        );
+    }
+
+    #[test]
+    fn refcounted_values_in_closures_are_freed() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                suffix = "a string that is too long to be small"
+
+                addSuffix = \str -> Str.concat str suffix
+
+                List.map ["a", "b", "c"] addSuffix
+                    |> List.map Str.countUtf8Bytes
+                    |> List.sum
+                    |> Num.toI64
+                "#
+            ),
+            3 * 38,
+        );
+    }
+
+    #[test]
+    fn join_points() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                x : I64
+                x = 7
+
+                y =
+                    when x is
+                        1 -> 10
+                        7 -> 70
+                        _ -> 0
+
+                z = if y > 50 then y + 1 else y - 1
+
+                z * 2
+                "#
+            ),
+            142,
+        );
+    }
+
+    #[test]
+    fn tail_calls() {
+        // deep enough that this would overflow the call depth if tail calls were not loops
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                sum : I64, I64 -> I64
+                sum = \n, acc ->
+                    if n == 0 then acc else sum (n - 1) (acc + n)
+
+                sum 1_000_000 0
+                "#
+            ),
+            500_000_500_000,
+        );
+    }
+
+    #[test]
+    fn deep_recursion_is_a_stack_overflow() {
+        let result = interpret(
+            indoc!(
+                r#"
+                count : I64 -> I64
+                count = \n ->
+                    if n == 0 then 0 else 1 + count (n - 1)
+
+                count 1_000_000
+                "#
+            ),
+            read_i64,
+        );
+
+        assert_eq!(result.err(), Some(InterpError::StackOverflow));
+    }
+
+    #[test]
+    fn non_recursive_unions() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                Shape : [Circle I64, Rect I64 I64, Empty]
+
+                area : Shape -> I64
+                area = \shape ->
+                    when shape is
+                        Circle r -> 3 * r * r
+                        Rect w h -> w * h
+                        Empty -> 0
+
+                shapes : List Shape
+                shapes = [Circle 2, Rect 3 4, Empty]
+
+                List.walk shapes 0 \total, shape -> total + area shape
+                "#
+            ),
+            24,
+        );
+    }
+
+    #[test]
+    fn recursive_unions() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                ConsList a : [Cons a (ConsList a), Nil]
+
+                build : I64, ConsList I64 -> ConsList I64
+                build = \n, acc ->
+                    if n == 0 then acc else build (n - 1) (Cons n acc)
+
+                total : ConsList I64, I64 -> I64
+                total = \list, acc ->
+                    when list is
+                        Cons x rest -> total rest (acc + x)
+                        Nil -> acc
+
+                total (build 100 Nil) 0
+                "#
+            ),
+            5050,
+        );
+    }
+
+    #[test]
+    fn nullable_unwrapped_unions() {
+        assert_evals_to_i64(
+            indoc!(
+                r#"
+                Tree : [Node Tree I64 Tree, Leaf]
+
+                insert : Tree, I64 -> Tree
+                insert = \tree, x ->
+                    when tree is
+                        Leaf -> Node Leaf x Leaf
+                        Node left y right ->
+                            if x < y then
+                                Node (insert left x) y right
+                            else
+                                Node left y (insert right x)
+
+                depth : Tree -> I64
+                depth = \tree ->
+                    when tree is
+                        Leaf -> 0
+                        Node left _ right ->
+                            l = depth left
+                            r = depth right
+
+                            if l > r then 1 + l else 1 + r
+
+                [5, 3, 8, 1, 4, 9]
+                    |> List.walk Leaf insert
+                    |> depth
+                "#
+            ),
+            3,
+        );
+    }
+
+    #[test]
+    fn result_of_str() {
+        let (value, _) = interpret(
+            indoc!(
+                r#"
+                parse : Str -> Result I64 Str
+                parse = \str ->
+                    when Str.toI64 str is
+                        Ok n -> Ok n
+                        Err _ -> Err "not a number: \(str)"
+
+                when parse "forty-two" is
+                    Ok _ -> "ok"
+                    Err message -> message
+                "#
+            ),
+            read_str,
+        )
+        .unwrap();
+
+        assert_eq!(value, "not a number: forty-two");
+    }
+}
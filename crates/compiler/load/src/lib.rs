@@ -19,6 +19,7 @@ pub use roc_load_internal::file::{
     EntryPoint, ExecutionMode, Expectations, LoadConfig, LoadResult, LoadStart, LoadedModule,
     LoadingProblem, MonomorphizedModule, Phase, Threading,
 };
+pub use roc_load_internal::unused::{find_unused, PackageModules};
 
 #[allow(clippy::too_many_arguments)]
 fn load<'a>(
@@ -51,6 +52,9 @@ pub fn load_single_threaded<'a>(
         cached_subs,
         render,
         exec_mode,
+        false,
+        false,
+        false,
     )
 }
 
@@ -1,4 +1,5 @@
 use crate::docs::ModuleDocumentation;
+use crate::unused::PackageModules;
 use bumpalo::Bump;
 use crossbeam::channel::{bounded, Sender};
 use crossbeam::deque::{Injector, Stealer, Worker};
@@ -18,6 +19,7 @@ use roc_constrain::module::constrain_module;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
 use roc_debug_flags::{
+    ROC_CHECK_MONO_IR, ROC_PRINT_IR_AFTER_EXPAND_RC, ROC_PRINT_IR_AFTER_INLINING,
     ROC_PRINT_IR_AFTER_REFCOUNT, ROC_PRINT_IR_AFTER_RESET_REUSE, ROC_PRINT_IR_AFTER_SPECIALIZATION,
     ROC_PRINT_LOAD_LOG,
 };
@@ -29,6 +31,8 @@ use roc_module::symbol::{
     IdentIds, IdentIdsByModule, Interns, ModuleId, ModuleIds, PQModuleName, PackageModuleIds,
     PackageQualified, Symbol,
 };
+#[cfg(debug_assertions)]
+use roc_mono::debug::CheckStage;
 use roc_mono::ir::{
     CapturedSymbols, ExternalSpecializations, PartialProc, Proc, ProcLayout, Procs, ProcsBase,
     UpdateModeIds,
@@ -38,8 +42,8 @@ use roc_parse::ast::{self, Defs, ExtractSpaces, Spaced, StrLiteral, TypeAnnotati
 use roc_parse::header::{ExposedName, ImportsEntry, PackageEntry, PlatformHeader, To, TypedIdent};
 use roc_parse::header::{HeaderFor, ModuleNameEnum, PackageName};
 use roc_parse::ident::UppercaseIdent;
-use roc_parse::module::module_defs;
-use roc_parse::parser::{FileError, Parser, SyntaxError};
+use roc_parse::module::parse_module_defs;
+use roc_parse::parser::{FileError, SyntaxError};
 use roc_region::all::{LineInfo, Loc, Region};
 use roc_reporting::report::RenderTarget;
 use roc_solve::module::{extract_module_owned_implementations, Solved, SolvedModule};
@@ -123,6 +127,15 @@ pub struct LoadConfig {
     pub render: RenderTarget,
     pub threading: Threading,
     pub exec_mode: ExecutionMode,
+    /// Inline small procedures and fold constants before refcounts are inserted. The LLVM
+    /// backend leaves this to LLVM, but the dev and wasm backends benefit from it.
+    pub inline_and_fold: bool,
+    /// Remember where in the source each call is, for `MonomorphizedModule::call_regions`.
+    /// Only the in-place update report and debug info need them.
+    pub record_call_regions: bool,
+    /// Collect what `unused::find_unused` needs into `LoadedModule::package_modules`.
+    /// Only `roc check --unused` asks for it.
+    pub collect_package_modules: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -162,6 +175,7 @@ struct ModuleCache<'a> {
     imports: MutMap<ModuleId, MutSet<ModuleId>>,
     top_level_thunks: MutMap<ModuleId, MutSet<Symbol>>,
     documentation: MutMap<ModuleId, ModuleDocumentation>,
+    syntax_problems: MutMap<ModuleId, Vec<FileError<'a, SyntaxError<'a>>>>,
     can_problems: MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: MutMap<ModuleId, Vec<TypeError>>,
 
@@ -209,6 +223,7 @@ impl Default for ModuleCache<'_> {
             imports: Default::default(),
             top_level_thunks: Default::default(),
             documentation: Default::default(),
+            syntax_problems: Default::default(),
             can_problems: Default::default(),
             type_problems: Default::default(),
             sources: Default::default(),
@@ -443,9 +458,17 @@ fn start_phase<'a>(
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
 
+                // the expectations of a builtin module only run when that module itself is tested
+                let execution_mode = match state.exec_mode {
+                    ExecutionMode::Test if module_id.is_builtin() && module_id != state.root_id => {
+                        ExecutionMode::Executable
+                    }
+                    exec_mode => exec_mode,
+                };
+
                 BuildTask::BuildPendingSpecializations {
                     layout_cache,
-                    execution_mode: state.exec_mode,
+                    execution_mode,
                     module_id,
                     module_timing,
                     solved_subs,
@@ -568,6 +591,7 @@ fn start_phase<'a>(
                     // TODO: awful, how can we get rid of the clone?
                     exposed_by_module: state.exposed_types.clone(),
                     derived_module,
+                    record_call_regions: state.record_call_regions,
                 }
             }
         }
@@ -594,6 +618,8 @@ pub struct LoadedModule {
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub documentation: MutMap<ModuleId, ModuleDocumentation>,
     pub abilities_store: AbilitiesStore,
+    /// Empty unless the load was asked to `collect_package_modules`
+    pub package_modules: PackageModules,
 }
 
 impl LoadedModule {
@@ -699,6 +725,10 @@ pub struct MonomorphizedModule<'a> {
     pub type_problems: MutMap<ModuleId, Vec<TypeError>>,
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub toplevel_expects: VecMap<Symbol, Region>,
+    /// Where top-level definitions are, used to emit debug info
+    pub def_regions: MutMap<Symbol, Region>,
+    /// Where calls are, keyed by the symbol their result is assigned to
+    pub call_regions: MutMap<Symbol, Region>,
     pub entry_point: EntryPoint<'a>,
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
@@ -759,6 +789,7 @@ struct ParsedModule<'a> {
     exposed_ident_ids: IdentIds,
     exposed_imports: MutMap<Ident, (Symbol, Region)>,
     parsed_defs: Defs<'a>,
+    syntax_problems: Vec<FileError<'a, SyntaxError<'a>>>,
     module_name: ModuleNameEnum<'a>,
     symbols_from_requires: Vec<(Loc<Symbol>, Loc<TypeAnnotation<'a>>)>,
     header_for: HeaderFor<'a>,
@@ -804,6 +835,7 @@ enum Msg<'a> {
         module_timing: ModuleTiming,
         abilities_store: AbilitiesStore,
         toplevel_expects: VecMap<Symbol, Region>,
+        def_regions: MutMap<Symbol, Region>,
     },
     MadeSpecializations {
         module_id: ModuleId,
@@ -812,6 +844,7 @@ enum Msg<'a> {
         external_specializations_requested: BumpMap<ModuleId, ExternalSpecializations<'a>>,
         procs_base: ProcsBase<'a>,
         procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+        call_regions: MutMap<Symbol, Region>,
         update_mode_ids: UpdateModeIds,
         module_timing: ModuleTiming,
         subs: Subs,
@@ -891,6 +924,8 @@ struct State<'a> {
     pub dependencies: Dependencies<'a>,
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub toplevel_expects: VecMap<Symbol, Region>,
+    pub def_regions: MutMap<Symbol, Region>,
+    pub call_regions: MutMap<Symbol, Region>,
     pub exposed_to_host: ExposedToHost,
 
     /// This is the "final" list of IdentIds, after canonicalization and constraint gen
@@ -920,6 +955,9 @@ struct State<'a> {
 
     pub render: RenderTarget,
     pub exec_mode: ExecutionMode,
+    pub inline_and_fold: bool,
+    pub record_call_regions: bool,
+    pub collect_package_modules: bool,
 
     /// All abilities across all modules.
     pub world_abilities: WorldAbilities,
@@ -948,6 +986,9 @@ impl<'a> State<'a> {
         render: RenderTarget,
         number_of_workers: usize,
         exec_mode: ExecutionMode,
+        inline_and_fold: bool,
+        record_call_regions: bool,
+        collect_package_modules: bool,
     ) -> Self {
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
 
@@ -964,6 +1005,8 @@ impl<'a> State<'a> {
             dependencies,
             procedures: MutMap::default(),
             toplevel_expects: VecMap::default(),
+            def_regions: MutMap::default(),
+            call_regions: MutMap::default(),
             exposed_to_host: ExposedToHost::default(),
             exposed_types,
             arc_modules,
@@ -978,6 +1021,9 @@ impl<'a> State<'a> {
             cached_subs: Arc::new(Mutex::new(cached_subs)),
             render,
             exec_mode,
+            inline_and_fold,
+            record_call_regions,
+            collect_package_modules,
             make_specializations_pass: MakeSpecializationsPass::Pass(1),
             world_abilities: Default::default(),
         }
@@ -1115,6 +1161,7 @@ enum BuildTask<'a> {
         exposed_by_module: ExposedByModule,
         world_abilities: WorldAbilities,
         derived_module: SharedDerivedModule,
+        record_call_regions: bool,
     },
 }
 
@@ -1190,6 +1237,9 @@ pub fn load_and_typecheck_str<'a>(
         render,
         threading,
         exec_mode: ExecutionMode::Check,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: false,
     };
 
     match load(arena, load_start, exposed_types, cached_subs, load_config)? {
@@ -1437,6 +1487,9 @@ pub fn load<'a>(
             cached_subs,
             load_config.render,
             load_config.exec_mode,
+            load_config.inline_and_fold,
+            load_config.record_call_regions,
+            load_config.collect_package_modules,
         ),
         Threads::Many(threads) => load_multi_threaded(
             arena,
@@ -1447,6 +1500,9 @@ pub fn load<'a>(
             load_config.render,
             threads,
             load_config.exec_mode,
+            load_config.inline_and_fold,
+            load_config.record_call_regions,
+            load_config.collect_package_modules,
         ),
     }
 }
@@ -1461,6 +1517,9 @@ pub fn load_single_threaded<'a>(
     cached_subs: MutMap<ModuleId, (Subs, Vec<(Symbol, Variable)>)>,
     render: RenderTarget,
     exec_mode: ExecutionMode,
+    inline_and_fold: bool,
+    record_call_regions: bool,
+    collect_package_modules: bool,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
         arc_modules,
@@ -1488,6 +1547,9 @@ pub fn load_single_threaded<'a>(
         render,
         number_of_workers,
         exec_mode,
+        inline_and_fold,
+        record_call_regions,
+        collect_package_modules,
     );
 
     // We'll add tasks to this, and then worker threads will take tasks from it.
@@ -1557,6 +1619,11 @@ fn state_thread_step<'a>(
                     // We're done! There should be no more messages pending.
                     debug_assert!(msg_rx.is_empty());
 
+                    if !state.module_cache.syntax_problems.is_empty() {
+                        let buf = to_syntax_problems_report(state);
+                        return Err(LoadingProblem::FormattedReport(buf));
+                    }
+
                     let exposed_aliases_by_symbol = exposed_aliases_by_symbol
                         .into_iter()
                         .map(|(k, (_, v))| (k, v))
@@ -1583,6 +1650,11 @@ fn state_thread_step<'a>(
                     // We're done! There should be no more messages pending.
                     debug_assert!(msg_rx.is_empty());
 
+                    if !state.module_cache.syntax_problems.is_empty() {
+                        let buf = to_syntax_problems_report(state);
+                        return Err(LoadingProblem::FormattedReport(buf));
+                    }
+
                     let monomorphized = finish_specialization(state, subs, exposed_to_host)?;
 
                     Ok(ControlFlow::Break(LoadResult::Monomorphized(monomorphized)))
@@ -1663,6 +1735,9 @@ fn load_multi_threaded<'a>(
     render: RenderTarget,
     available_threads: usize,
     exec_mode: ExecutionMode,
+    inline_and_fold: bool,
+    record_call_regions: bool,
+    collect_package_modules: bool,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
         arc_modules,
@@ -1705,6 +1780,9 @@ fn load_multi_threaded<'a>(
         render,
         num_workers,
         exec_mode,
+        inline_and_fold,
+        record_call_regions,
+        collect_package_modules,
     );
 
     // an arena for every worker, stored in an arena-allocated bumpalo vec to make the lifetimes work
@@ -1974,6 +2052,19 @@ macro_rules! debug_print_ir {
     };
 }
 
+macro_rules! debug_check_ir {
+    ($state:expr, $arena:expr, $stage:expr) => {
+        dbg_do!(ROC_CHECK_MONO_IR, {
+            let stage = $stage;
+            let problems = roc_mono::debug::check_procs($arena, &$state.procedures, stage);
+
+            if !problems.is_empty() {
+                panic!("{}", roc_mono::debug::format_problems(stage, &problems));
+            }
+        })
+    };
+}
+
 /// Report modules that are imported, but from which nothing is used
 fn report_unused_imported_modules<'a>(
     state: &mut State<'a>,
@@ -2239,12 +2330,21 @@ fn update<'a>(
 
             Ok(state)
         }
-        Parsed(parsed) => {
+        Parsed(mut parsed) => {
             state
                 .module_cache
                 .sources
                 .insert(parsed.module_id, (parsed.module_path.clone(), parsed.src));
 
+            if !parsed.syntax_problems.is_empty() {
+                let syntax_problems = std::mem::take(&mut parsed.syntax_problems);
+
+                state
+                    .module_cache
+                    .syntax_problems
+                    .insert(parsed.module_id, syntax_problems);
+            }
+
             // If this was an app module, set the output path to be
             // the module's declared "name".
             //
@@ -2442,6 +2542,8 @@ fn update<'a>(
                         .typechecked
                         .insert(module_id, typechecked);
                 } else {
+                    // kept so tools like the language server can look into dependencies too
+                    state.declarations_by_id.insert(module_id, decls);
                     state.constrained_ident_ids.insert(module_id, ident_ids);
                     state.timings.insert(module_id, module_timing);
                 }
@@ -2460,12 +2562,14 @@ fn update<'a>(
             module_timing,
             abilities_store,
             toplevel_expects,
+            def_regions,
         } => {
             log!("found specializations for {:?}", module_id);
 
             let subs = solved_subs.into_inner();
 
             state.toplevel_expects.extend(toplevel_expects);
+            state.def_regions.extend(def_regions);
 
             state
                 .module_cache
@@ -2503,6 +2607,7 @@ fn update<'a>(
             subs,
             procs_base,
             procedures,
+            call_regions,
             external_specializations_requested,
             module_timing,
             layout_cache,
@@ -2516,6 +2621,7 @@ fn update<'a>(
             let _ = layout_cache;
 
             state.procedures.extend(procedures);
+            state.call_regions.extend(call_regions);
             state.module_cache.late_specializations.insert(
                 module_id,
                 LateSpecializationsModule {
@@ -2605,9 +2711,23 @@ fn update<'a>(
                     log!("specializations complete from {:?}", module_id);
 
                     debug_print_ir!(state, ROC_PRINT_IR_AFTER_SPECIALIZATION);
+                    debug_check_ir!(state, arena, CheckStage::Specialization);
 
                     let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
+                    if state.inline_and_fold {
+                        Proc::inline_and_fold(
+                            arena,
+                            module_id,
+                            ident_ids,
+                            &mut update_mode_ids,
+                            &mut state.procedures,
+                        );
+
+                        debug_print_ir!(state, ROC_PRINT_IR_AFTER_INLINING);
+                        debug_check_ir!(state, arena, CheckStage::Inlining);
+                    }
+
                     Proc::insert_reset_reuse_operations(
                         arena,
                         module_id,
@@ -2617,8 +2737,10 @@ fn update<'a>(
                     );
 
                     debug_print_ir!(state, ROC_PRINT_IR_AFTER_RESET_REUSE);
+                    debug_check_ir!(state, arena, CheckStage::ResetReuse);
 
-                    Proc::insert_refcount_operations(
+                    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+                    let borrow_params = Proc::insert_refcount_operations(
                         arena,
                         module_id,
                         ident_ids,
@@ -2627,15 +2749,18 @@ fn update<'a>(
                     );
 
                     debug_print_ir!(state, ROC_PRINT_IR_AFTER_REFCOUNT);
+                    debug_check_ir!(state, arena, CheckStage::Refcount(borrow_params));
 
-                    // This is not safe with the new non-recursive RC updates that we do for tag unions
-                    //
-                    // Proc::optimize_refcount_operations(
-                    //     arena,
-                    //     module_id,
-                    //     &mut ident_ids,
-                    //     &mut state.procedures,
-                    // );
+                    Proc::expand_and_cancel_refcounts(
+                        arena,
+                        module_id,
+                        ident_ids,
+                        &mut update_mode_ids,
+                        &mut state.procedures,
+                    );
+
+                    debug_print_ir!(state, ROC_PRINT_IR_AFTER_EXPAND_RC);
+                    debug_check_ir!(state, arena, CheckStage::ExpandRc);
 
                     // use the subs of the root module;
                     // this is used in the repl to find the type of `main`
@@ -2778,6 +2903,8 @@ fn finish_specialization(
 
     let State {
         toplevel_expects,
+        def_regions,
+        call_regions,
         procedures,
         module_cache,
         output_path,
@@ -2876,6 +3003,8 @@ fn finish_specialization(
         sources,
         timings: state.timings,
         toplevel_expects,
+        def_regions,
+        call_regions,
     })
 }
 
@@ -2891,10 +3020,22 @@ fn finish(
     documentation: MutMap<ModuleId, ModuleDocumentation>,
     abilities_store: AbilitiesStore,
 ) -> LoadedModule {
-    let module_ids = Arc::try_unwrap(state.arc_modules)
+    let package_module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
-        .into_inner()
-        .into_module_ids();
+        .into_inner();
+
+    let package_modules = if state.collect_package_modules {
+        package_modules(
+            &package_module_ids,
+            &state.module_cache,
+            &state.declarations_by_id,
+            &state.exposed_symbols_by_module,
+        )
+    } else {
+        PackageModules::default()
+    };
+
+    let module_ids = package_module_ids.into_module_ids();
 
     // Associate the ident IDs from the derived synth module
     let (_, derived_synth_ident_ids) = Arc::try_unwrap(state.derived_module)
@@ -2938,7 +3079,72 @@ fn finish(
         timings: state.timings,
         documentation,
         abilities_store,
+        package_modules,
+    }
+}
+
+/// Collect what [`find_unused`](crate::unused::find_unused) needs to know about the modules of
+/// the root module's package. Those are the modules without a package shorthand.
+fn package_modules(
+    package_module_ids: &PackageModuleIds,
+    module_cache: &ModuleCache,
+    declarations_by_id: &MutMap<ModuleId, Declarations>,
+    exposed_symbols_by_module: &MutMap<ModuleId, VecSet<Symbol>>,
+) -> PackageModules {
+    let mut package = PackageModules::default();
+
+    for module_id in module_cache.sources.keys() {
+        match package_module_ids.get_name(*module_id) {
+            Some(PQModuleName::Unqualified(_)) if !module_id.is_builtin() => {
+                package.modules.insert(*module_id);
+            }
+            _ => continue,
+        }
+
+        if let Some(exposed) = exposed_symbols_by_module.get(module_id) {
+            package.exposed_symbols.insert(*module_id, exposed.clone());
+        }
+
+        // these also have the aliases the module imports
+        if let Some(aliases) = module_cache.aliases.get(module_id) {
+            package.aliases.extend(
+                aliases
+                    .iter()
+                    .filter(|(symbol, _)| symbol.module_id() == *module_id)
+                    .map(|(symbol, (_, alias))| (*symbol, alias.clone())),
+            );
+        }
+
+        if let Some(abilities_store) = module_cache.pending_abilities.get(module_id) {
+            for (ability, members) in abilities_store.iter_abilities() {
+                if ability.module_id() != *module_id {
+                    continue;
+                }
+
+                let members = members
+                    .iter()
+                    .map(|member| {
+                        let region = abilities_store.root_ability_members()[member].region;
+
+                        (*member, region)
+                    })
+                    .collect();
+
+                package.abilities.insert(ability, members);
+            }
+
+            // `specializes` only has the implementations named after the member they implement
+            if let Some(decls) = declarations_by_id.get(module_id) {
+                for loc_symbol in decls.symbols.iter() {
+                    if let Some(impl_key) = abilities_store.impl_key(loc_symbol.value) {
+                        package.implementations.insert(loc_symbol.value, *impl_key);
+                    }
+                }
+            }
+        }
     }
+
+    package
 }
 
 /// Load a `platform` module
@@ -4567,14 +4773,13 @@ fn parse<'a>(arena: &'a Bump, header: ModuleHeader<'a>) -> Result<Msg<'a>, Loadi
     let parse_start = Instant::now();
     let source = header.parse_state.original_bytes();
     let parse_state = header.parse_state;
-    let parsed_defs = match module_defs().parse(arena, parse_state) {
-        Ok((_, success, _state)) => success,
-        Err((_, fail, state)) => {
-            return Err(LoadingProblem::ParsingFailed(
-                fail.into_file_error(header.module_path, &state),
-            ));
-        }
-    };
+    // Keep going after syntax errors, so that we can report all of them at once; the defs
+    // that did not parse become runtime errors.
+    let (parsed_defs, syntax_problems) = parse_module_defs(arena, parse_state);
+    let syntax_problems = syntax_problems
+        .into_iter()
+        .map(|problem| problem.into_file_error(header.module_path.clone()))
+        .collect();
 
     // Record the parse end time once, to avoid checking the time a second time
     // immediately afterward (for the beginning of canonicalization).
@@ -4612,6 +4817,7 @@ fn parse<'a>(arena: &'a Bump, header: ModuleHeader<'a>) -> Result<Msg<'a>, Loadi
         exposed_ident_ids,
         exposed_imports,
         parsed_defs,
+        syntax_problems,
         symbols_from_requires,
         header_for,
     };
@@ -4673,9 +4879,11 @@ fn make_specializations<'a>(
     world_abilities: WorldAbilities,
     exposed_by_module: &ExposedByModule,
     derived_module: SharedDerivedModule,
+    record_call_regions: bool,
 ) -> Msg<'a> {
     let make_specializations_start = Instant::now();
     let mut update_mode_ids = UpdateModeIds::new();
+    let mut call_regions = MutMap::default();
     // do the thing
     let mut mono_env = roc_mono::ir::Env {
         arena,
@@ -4686,6 +4894,11 @@ fn make_specializations<'a>(
         update_mode_ids: &mut update_mode_ids,
         // call_specialization_counter=0 is reserved
         call_specialization_counter: 1,
+        call_regions: if record_call_regions {
+            Some(&mut call_regions)
+        } else {
+            None
+        },
         abilities: AbilitiesView::World(&world_abilities),
         exposed_by_module,
         derived_module: &derived_module,
@@ -4729,6 +4942,7 @@ fn make_specializations<'a>(
         layout_cache,
         procs_base: restored_procs_base,
         procedures,
+        call_regions,
         update_mode_ids,
         subs,
         external_specializations_requested,
@@ -4758,6 +4972,12 @@ fn build_pending_specializations<'a>(
     let mut module_thunks = bumpalo::collections::Vec::new_in(arena);
     let mut toplevel_expects = VecMap::default();
 
+    let def_regions = declarations
+        .symbols
+        .iter()
+        .map(|loc_symbol| (loc_symbol.value, loc_symbol.region))
+        .collect();
+
     let mut procs_base = ProcsBase {
         partial_procs: BumpMap::default(),
         module_thunks: &[],
@@ -4777,6 +4997,8 @@ fn build_pending_specializations<'a>(
         update_mode_ids: &mut update_mode_ids,
         // call_specialization_counter=0 is reserved
         call_specialization_counter: 1,
+        // procedures are only specialized later on, in `make_specializations`
+        call_regions: None,
         // NB: for getting pending specializations the module view is enough because we only need
         // to know the types and abilities in our modules. Only for building *all* specializations
         // do we need a global view.
@@ -5162,6 +5384,7 @@ fn build_pending_specializations<'a>(
         module_timing,
         abilities_store,
         toplevel_expects,
+        def_regions,
     }
 }
 
@@ -5210,6 +5433,8 @@ fn load_derived_partial_procs<'a>(
             update_mode_ids: &mut update_mode_ids,
             // call_specialization_counter=0 is reserved
             call_specialization_counter: 1,
+            // derived code has no source to point at
+            call_regions: None,
             // NB: for getting pending specializations the module view is enough because we only need
             // to know the types and abilities in our modules. Only for building *all* specializations
             // do we need a global view.
@@ -5387,6 +5612,7 @@ fn run_task<'a>(
             world_abilities,
             exposed_by_module,
             derived_module,
+            record_call_regions,
         } => Ok(make_specializations(
             arena,
             module_id,
@@ -5400,6 +5626,7 @@ fn run_task<'a>(
             world_abilities,
             &exposed_by_module,
             derived_module,
+            record_call_regions,
         )),
     }?;
 
@@ -5492,6 +5719,22 @@ fn to_parse_problem_report<'a>(
     mut module_ids: ModuleIds,
     all_ident_ids: IdentIdsByModule,
     render: RenderTarget,
+) -> String {
+    let module_id = module_ids.get_or_insert(&"find module name somehow?".into());
+
+    let interns = Interns {
+        module_ids,
+        all_ident_ids,
+    };
+
+    parse_problem_report_help(problem, module_id, &interns, render)
+}
+
+fn parse_problem_report_help<'a>(
+    problem: FileError<'a, SyntaxError<'a>>,
+    module_id: ModuleId,
+    interns: &Interns,
+    render: RenderTarget,
 ) -> String {
     use roc_reporting::report::{parse_problem, RocDocAllocator, DEFAULT_PALETTE};
 
@@ -5501,15 +5744,8 @@ fn to_parse_problem_report<'a>(
     // let mut src_lines: Vec<&str> = problem.prefix.lines().collect();
     // src_lines.extend(src.lines().skip(1));
 
-    let module_id = module_ids.get_or_insert(&"find module name somehow?".into());
-
-    let interns = Interns {
-        module_ids,
-        all_ident_ids,
-    };
-
     // Report parsing and canonicalization problems
-    let alloc = RocDocAllocator::new(&src_lines, module_id, &interns);
+    let alloc = RocDocAllocator::new(&src_lines, module_id, interns);
 
     let starting_line = 0;
 
@@ -5531,6 +5767,34 @@ fn to_parse_problem_report<'a>(
     buf
 }
 
+/// Report the syntax errors of all modules, each module's in the order they appear in its source.
+fn to_syntax_problems_report(state: State) -> String {
+    let mut problems: Vec<_> = state
+        .module_cache
+        .syntax_problems
+        .into_values()
+        .flatten()
+        .collect();
+
+    // sorting is stable, so this keeps the problems of a module in order
+    problems.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+    let mut module_ids = (*state.arc_modules).lock().clone().into_module_ids();
+    let module_id = module_ids.get_or_insert(&"find module name somehow?".into());
+
+    let interns = Interns {
+        module_ids,
+        all_ident_ids: state.constrained_ident_ids,
+    };
+
+    let reports: Vec<_> = problems
+        .into_iter()
+        .map(|problem| parse_problem_report_help(problem, module_id, &interns, state.render))
+        .collect();
+
+    reports.join("\n\n")
+}
+
 fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> String {
     use roc_reporting::report::{Report, RocDocAllocator, Severity, DEFAULT_PALETTE};
     use ven_pretty::DocAllocator;
@@ -0,0 +1,342 @@
+//! Find code that the program never uses, across all the modules of the root module's package.
+//!
+//! Canonicalization already reports definitions that nothing in their own module refers to,
+//! but it can't know whether an exposed value is imported anywhere, nor whether the code that
+//! uses a definition is used itself. This walks the references from the entry points of the
+//! program: what the root module provides or exposes, and its top-level expects. Everything in
+//! the package that this walk does not reach is unused.
+
+use crate::file::LoadedModule;
+use roc_can::abilities::ImplKey;
+use roc_can::def::Annotation;
+use roc_can::expr::{DeclarationTag, Declarations, Expr, OpaqueWrapFunctionData};
+use roc_can::pattern::Pattern;
+use roc_can::traverse::{symbols_introduced_from_pattern, walk_expr, walk_pattern, Visitor};
+use roc_collections::{MutMap, MutSet, VecSet};
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_problem::can::Problem;
+use roc_region::all::Region;
+use roc_types::subs::Variable;
+use roc_types::types::Alias;
+
+/// What the analysis needs to know about the modules of the root module's package, beyond
+/// their declarations.
+#[derive(Debug, Default)]
+pub struct PackageModules {
+    /// The modules of the root module's package. Modules of other packages, like the
+    /// platform, are not analyzed.
+    pub modules: MutSet<ModuleId>,
+    pub exposed_symbols: MutMap<ModuleId, VecSet<Symbol>>,
+    /// The aliases and opaque types the modules define
+    pub aliases: MutMap<Symbol, Alias>,
+    /// The members of the abilities the modules define, with their regions
+    pub abilities: MutMap<Symbol, Vec<(Symbol, Region)>>,
+    /// The ability implementations the modules define, by the symbol of the implementation
+    pub implementations: MutMap<Symbol, ImplKey>,
+}
+
+/// The problems of the code that the program never uses, by module.
+///
+/// These are exposed values and types that nothing reachable uses, ability implementations for
+/// opaque types that nothing reachable uses as that ability, and top-level definitions that are
+/// only used by unreachable code. Definitions that aren't used at all are already reported as
+/// [`Problem::UnusedDef`] during canonicalization, so they are not reported again.
+///
+/// The module must be loaded with `LoadConfig::collect_package_modules`, otherwise there is
+/// nothing to analyze and nothing is reported.
+pub fn find_unused(loaded: &LoadedModule) -> MutMap<ModuleId, Vec<Problem>> {
+    let package = &loaded.package_modules;
+
+    // where every top-level value of the package is declared
+    let mut definitions: MutMap<Symbol, (ModuleId, usize, Region)> = MutMap::default();
+
+    for module_id in package.modules.iter() {
+        if let Some(decls) = loaded.declarations_by_id.get(module_id) {
+            for (index, tag) in decls.declarations.iter().enumerate() {
+                match tag {
+                    DeclarationTag::Value
+                    | DeclarationTag::Function(_)
+                    | DeclarationTag::Recursive(_)
+                    | DeclarationTag::TailRecursive(_) => {
+                        let loc_symbol = decls.symbols[index];
+
+                        definitions
+                            .insert(loc_symbol.value, (*module_id, index, loc_symbol.region));
+                    }
+                    DeclarationTag::Destructure(destructure_index) => {
+                        let destructure = &decls.destructs[destructure_index.index() as usize];
+
+                        for loc_symbol in symbols_introduced_from_pattern(&destructure.loc_pattern)
+                        {
+                            definitions
+                                .insert(loc_symbol.value, (*module_id, index, loc_symbol.region));
+                        }
+                    }
+                    DeclarationTag::Expectation
+                    | DeclarationTag::ExpectationFx
+                    | DeclarationTag::MutualRecursion { .. } => {}
+                }
+            }
+        }
+    }
+
+    let mut reachable = Reachable {
+        loaded,
+        definitions: &definitions,
+        reached: MutSet::default(),
+        stack: Vec::new(),
+    };
+
+    if let Some(exposed) = package.exposed_symbols.get(&loaded.module_id) {
+        reachable.reach_all(exposed.iter().copied());
+    }
+
+    reachable.reach_all(loaded.exposed_to_host.keys().copied());
+
+    for module_id in package.modules.iter() {
+        if let Some(decls) = loaded.declarations_by_id.get(module_id) {
+            for (index, tag) in decls.declarations.iter().enumerate() {
+                if let DeclarationTag::Expectation | DeclarationTag::ExpectationFx = tag {
+                    let references = references(decls, index);
+
+                    reachable.reach_all(references);
+                }
+            }
+        }
+    }
+
+    reachable.walk();
+
+    let reached = reachable.reached;
+
+    let already_unused: MutSet<Symbol> = loaded
+        .can_problems
+        .values()
+        .flatten()
+        .filter_map(|problem| match problem {
+            Problem::UnusedDef(symbol, _) => Some(*symbol),
+            _ => None,
+        })
+        .collect();
+
+    let is_exposed = |symbol: Symbol| {
+        package
+            .exposed_symbols
+            .get(&symbol.module_id())
+            .map_or(false, |exposed| exposed.contains(&symbol))
+    };
+
+    let unused = |symbol: Symbol, region: Region| {
+        if is_exposed(symbol) {
+            Problem::UnusedExposed(symbol, region)
+        } else {
+            Problem::UnreachableDef(symbol, region)
+        }
+    };
+
+    let mut problems: Vec<(Region, Problem)> = Vec::new();
+
+    for (symbol, (_, _, region)) in definitions.iter() {
+        if reached.contains(symbol) || already_unused.contains(symbol) {
+            continue;
+        }
+
+        match package.implementations.get(symbol) {
+            Some(impl_key) => problems.push((
+                *region,
+                Problem::UnusedImplementation {
+                    opaque: impl_key.opaque,
+                    ability_member: impl_key.ability_member,
+                    region: *region,
+                },
+            )),
+            None => problems.push((*region, unused(*symbol, *region))),
+        }
+    }
+
+    for (symbol, alias) in package.aliases.iter() {
+        if !reached.contains(symbol) && !already_unused.contains(symbol) {
+            problems.push((alias.region, unused(*symbol, alias.region)));
+        }
+    }
+
+    for (ability, members) in package.abilities.iter() {
+        if !reached.contains(ability) && !already_unused.contains(ability) {
+            // the definition of an ability isn't kept, but the definitions of its members are
+            let region = members
+                .iter()
+                .map(|(_, region)| *region)
+                .reduce(|start, end| Region::span_across(&start, &end))
+                .unwrap_or_default();
+
+            problems.push((region, unused(*ability, region)));
+        }
+    }
+
+    problems.sort_by_key(|(region, _)| *region);
+
+    let mut by_module: MutMap<ModuleId, Vec<Problem>> = MutMap::default();
+
+    for (_, problem) in problems {
+        let symbol = match &problem {
+            Problem::UnusedExposed(symbol, _) | Problem::UnreachableDef(symbol, _) => *symbol,
+            Problem::UnusedImplementation { opaque, .. } => *opaque,
+            _ => unreachable!(),
+        };
+
+        by_module
+            .entry(symbol.module_id())
+            .or_default()
+            .push(problem);
+    }
+
+    by_module
+}
+
+struct Reachable<'a> {
+    loaded: &'a LoadedModule,
+    definitions: &'a MutMap<Symbol, (ModuleId, usize, Region)>,
+    reached: MutSet<Symbol>,
+    stack: Vec<Symbol>,
+}
+
+impl Reachable<'_> {
+    /// Whether the program may use a symbol. Builtins and other packages are not analyzed, so
+    /// they may always be used.
+    fn is_reached(&self, symbol: Symbol) -> bool {
+        !self
+            .loaded
+            .package_modules
+            .modules
+            .contains(&symbol.module_id())
+            || self.reached.contains(&symbol)
+    }
+
+    fn reach_all(&mut self, symbols: impl IntoIterator<Item = Symbol>) {
+        for symbol in symbols {
+            if self
+                .loaded
+                .package_modules
+                .modules
+                .contains(&symbol.module_id())
+                && self.reached.insert(symbol)
+            {
+                self.stack.push(symbol);
+            }
+        }
+    }
+
+    fn walk(&mut self) {
+        let package = &self.loaded.package_modules;
+
+        while let Some(symbol) = self.stack.pop() {
+            if let Some((module_id, index, _)) = self.definitions.get(&symbol) {
+                let decls = &self.loaded.declarations_by_id[module_id];
+
+                self.reach_all(references(decls, *index));
+            }
+
+            if let Some(alias) = package.aliases.get(&symbol) {
+                self.reach_all(alias.typ.symbols());
+            }
+
+            // Using a member needs the whole ability, and an opaque type that has an ability
+            // needs to implement all of its members.
+            if let Some(members) = package.abilities.get(&symbol) {
+                self.reach_all(members.iter().map(|(member, _)| *member));
+            }
+
+            for (ability, members) in package.abilities.iter() {
+                if members.iter().any(|(member, _)| *member == symbol) {
+                    self.reach_all([*ability]);
+                }
+            }
+
+            // An implementation is used when both the opaque type and the ability are used
+            let implementations: Vec<Symbol> = package
+                .implementations
+                .iter()
+                .filter(|(_, impl_key)| {
+                    (impl_key.opaque == symbol || impl_key.ability_member == symbol)
+                        && self.is_reached(impl_key.opaque)
+                        && self.is_reached(impl_key.ability_member)
+                })
+                .map(|(implementation, _)| *implementation)
+                .collect();
+
+            self.reach_all(implementations);
+        }
+    }
+}
+
+/// The values and types that a top-level declaration refers to
+fn references(decls: &Declarations, index: usize) -> Vec<Symbol> {
+    let mut collector = Collector {
+        symbols: Vec::new(),
+    };
+
+    match decls.declarations[index] {
+        DeclarationTag::Function(function_index)
+        | DeclarationTag::Recursive(function_index)
+        | DeclarationTag::TailRecursive(function_index) => {
+            let function_def = &decls.function_bodies[function_index.index() as usize];
+
+            for (_, _, loc_pattern) in function_def.value.arguments.iter() {
+                collector.visit_pattern(&loc_pattern.value, loc_pattern.region, None);
+            }
+        }
+        DeclarationTag::Destructure(destructure_index) => {
+            let loc_pattern = &decls.destructs[destructure_index.index() as usize].loc_pattern;
+
+            collector.visit_pattern(&loc_pattern.value, loc_pattern.region, None);
+        }
+        DeclarationTag::MutualRecursion { .. } => return Vec::new(),
+        DeclarationTag::Value | DeclarationTag::Expectation | DeclarationTag::ExpectationFx => {}
+    }
+
+    let loc_expr = &decls.expressions[index];
+
+    collector.visit_expr(&loc_expr.value, loc_expr.region, decls.variables[index]);
+
+    if let Some(annotation) = &decls.annotations[index] {
+        collector.visit_annotation(annotation);
+    }
+
+    return collector.symbols;
+
+    struct Collector {
+        symbols: Vec<Symbol>,
+    }
+
+    impl Visitor for Collector {
+        fn visit_annotation(&mut self, annotation: &Annotation) {
+            self.symbols.extend(annotation.signature.symbols());
+        }
+
+        fn visit_expr(&mut self, expr: &Expr, _region: Region, var: Variable) {
+            match expr {
+                Expr::Var(symbol) | Expr::AbilityMember(symbol, _, _) => self.symbols.push(*symbol),
+                Expr::OpaqueRef { name, .. } => self.symbols.push(*name),
+                Expr::OpaqueWrapFunction(OpaqueWrapFunctionData { opaque_name, .. }) => {
+                    self.symbols.push(*opaque_name)
+                }
+                _ => {}
+            }
+
+            walk_expr(self, expr, var);
+        }
+
+        fn visit_pattern(
+            &mut self,
+            pattern: &Pattern,
+            _region: Region,
+            _opt_var: Option<Variable>,
+        ) {
+            if let Pattern::UnwrappedOpaque { opaque, .. } = pattern {
+                self.symbols.push(*opaque);
+            }
+
+            walk_pattern(self, pattern);
+        }
+    }
+}
@@ -46,6 +46,9 @@ fn load_and_typecheck(
         render: RenderTarget::Generic,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Check,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: true,
     };
 
     match roc_load_internal::file::load(
@@ -598,6 +601,55 @@ fn parse_problem() {
     }
 }
 
+#[test]
+fn parse_problems_in_several_defs() {
+    let modules = vec![(
+        "Main",
+        indoc!(
+            r#"
+                interface Main exposes [main] imports []
+
+                main = [
+
+                other = 1
+
+                more = { a: 1, b: if, c: 3 }
+                "#
+        ),
+    )];
+
+    match multiple_modules("parse_problems_in_several_defs", modules) {
+        Err(report) => assert_eq!(
+            report,
+            indoc!(
+                r#"
+                    ── UNFINISHED LIST ─────────────────── tmp/parse_problems_in_several_defs/Main ─
+
+                    I cannot find the end of this list:
+
+                    3│  main = [
+                                ^
+
+                    You could change it to something like [1, 2, 3] or even just [].
+                    Anything where there is an open and a close square bracket, and where
+                    the elements of the list are separated by commas.
+
+                    Note: I may be confused by indentation
+
+                    ── MISSING EXPRESSION ──────────────── tmp/parse_problems_in_several_defs/Main ─
+
+                    I am partway through parsing a definition, but I got stuck here:
+
+                    7│  more = { a: 1, b: if, c: 3 }
+                                          ^
+
+                    I was expecting to see an expression like 42 or "hello"."#
+            )
+        ),
+        Ok(_) => unreachable!("we expect failure here"),
+    }
+}
+
 #[test]
 #[should_panic(expected = "FILE NOT FOUND")]
 fn file_not_found() {
@@ -872,3 +924,173 @@ fn issue_2863_module_type_does_not_exist() {
         Ok(_) => unreachable!("we expect failure here"),
     }
 }
+
+#[test]
+fn unused_across_modules() {
+    let modules = vec![
+        (
+            "Dep",
+            indoc!(
+                r#"
+                    interface Dep
+                        exposes [double, triple, Age, Name, fromYears, Describe, describe]
+                        imports []
+
+                    Describe has
+                        describe : a -> Str | a has Describe
+
+                    Age := U32 has [Describe { describe: describeAge }]
+
+                    describeAge = \@Age n -> Num.toStr n
+
+                    Name := Str has [Describe { describe: describeName }]
+
+                    describeName = \@Name s -> s
+
+                    fromYears : U32 -> Age
+                    fromYears = \n -> @Age n
+
+                    double = \n -> n * 2
+
+                    triple = \n -> timesThree n
+
+                    timesThree = \n -> n * 3
+                    "#
+            ),
+        ),
+        (
+            "Main",
+            indoc!(
+                r#"
+                    interface Main exposes [main] imports [Dep]
+
+                    main = Str.concat (Dep.describe (Dep.fromYears 3)) (Num.toStr (Dep.double 2))
+                    "#
+            ),
+        ),
+    ];
+
+    let loaded_module = multiple_modules("unused_across_modules", modules).unwrap();
+    let mut unused = roc_load_internal::unused::find_unused(&loaded_module);
+
+    assert_eq!(unused.len(), 1);
+
+    let dep = loaded_module
+        .interns
+        .module_ids
+        .get_id(&ModuleName::from("Dep"))
+        .unwrap();
+    let (filepath, src) = loaded_module.sources.get(&dep).unwrap();
+
+    let report = format_can_problems(
+        unused.remove(&dep).unwrap(),
+        dep,
+        &loaded_module.interns,
+        filepath.clone(),
+        src,
+    );
+
+    assert_eq!(
+        report,
+        indoc!(
+            r#"
+            ── UNUSED EXPOSED DEFINITION ─────────────── tmp/unused_across_modules/Dep.roc ─
+
+            `Name` is exposed, but nothing that the program uses needs it.
+
+            12│  Name := Str has [Describe { describe: describeName }]
+                 ^^^^
+
+            If no other module will need `Name`, you can remove it from `exposes`, or
+            remove it altogether.
+
+            ── UNUSED IMPLEMENTATION ─────────────────── tmp/unused_across_modules/Dep.roc ─
+
+            This implementation of `describe` for `Name` is never used:
+
+            14│  describeName = \@Name s -> s
+                 ^^^^^^^^^^^^
+
+            Nothing the program uses needs `Name` to have this ability, so the
+            ability can be removed from its `has` clause.
+
+            ── UNUSED EXPOSED DEFINITION ─────────────── tmp/unused_across_modules/Dep.roc ─
+
+            `triple` is exposed, but nothing that the program uses needs it.
+
+            21│  triple = \n -> timesThree n
+                 ^^^^^^
+
+            If no other module will need `triple`, you can remove it from `exposes`,
+            or remove it altogether.
+
+            ── UNREACHABLE DEFINITION ────────────────── tmp/unused_across_modules/Dep.roc ─
+
+            `timesThree` is only used by code that the program never uses:
+
+            23│  timesThree = \n -> n * 3
+                 ^^^^^^^^^^
+
+            Once that code is gone, this definition can be removed too.
+            "#
+        ),
+        "\n{}",
+        report
+    );
+}
+
+#[test]
+fn test_mode_only_collects_expects_of_the_tested_module() {
+    use std::io::Write;
+
+    let arena = Bump::new();
+    let dir = roc_test_utils::TmpDir::new("tmp/test_mode_only_collects_expects");
+    let file_path = dir.path().join("Expects.roc");
+
+    let source = indoc!(
+        r#"
+        interface Expects
+            exposes []
+            imports []
+
+        expect Str.isEmpty ""
+
+        expect Str.countUtf8Bytes "roc" == 3
+        "#
+    );
+    let mut file = std::fs::File::create(&file_path).unwrap();
+    writeln!(file, "{}", source).unwrap();
+
+    let load_start = LoadStart::from_path(&arena, file_path, RenderTarget::Generic).unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        render: RenderTarget::Generic,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Test,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: true,
+    };
+
+    // the builtins are compiled as part of this load, and Str has expects of its own
+    let loaded = match roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        load_config,
+    ) {
+        Ok(LoadResult::Monomorphized(module)) => module,
+        Ok(LoadResult::TypeChecked(_)) => unreachable!(),
+        Err(problem) => panic!("{:?}", problem),
+    };
+
+    let home = loaded.module_id;
+    let expect_modules: Vec<_> = loaded
+        .toplevel_expects
+        .iter()
+        .map(|(symbol, _)| symbol.module_id())
+        .collect();
+
+    assert_eq!(expect_modules, vec![home, home]);
+}
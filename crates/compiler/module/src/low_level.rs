@@ -107,6 +107,9 @@ pub enum LowLevel {
     PtrCast,
     RefCountInc,
     RefCountDec,
+    RefCountIsUnique,
+    PtrStore,
+    PtrLoad,
     BoxExpr,
     UnboxExpr,
     Unreachable,
@@ -207,10 +210,12 @@ macro_rules! map_symbol_to_lowlevel {
                 LowLevel::NumDivUnchecked => unreachable!(),
 
                 // these are used internally and not tied to a symbol
-                LowLevel::Hash => unimplemented!(),
                 LowLevel::PtrCast => unimplemented!(),
                 LowLevel::RefCountInc => unimplemented!(),
                 LowLevel::RefCountDec => unimplemented!(),
+                LowLevel::RefCountIsUnique => unimplemented!(),
+                LowLevel::PtrStore => unimplemented!(),
+                LowLevel::PtrLoad => unimplemented!(),
 
                 // these are not implemented, not sure why
                 LowLevel::StrFromInt => unimplemented!(),
@@ -311,5 +316,6 @@ map_symbol_to_lowlevel! {
     And <= BOOL_AND,
     Or <= BOOL_OR,
     Not <= BOOL_NOT,
+    Hash <= DICT_HASH_TEST_ONLY,
     Unreachable <= LIST_UNREACHABLE,
 }
@@ -1009,6 +1009,9 @@ define_builtins! {
         31 ATTR_INVALID: "#attr_invalid"
 
         32 CLONE: "#clone" // internal function that clones a value into a buffer
+
+        // used in dev backend
+        33 DEV_TMP6: "#dev_tmp6"
     }
     // Fake module for synthesizing and storing derived implementations
     1 DERIVED_SYNTH: "#Derived" => {
@@ -1341,6 +1344,7 @@ define_builtins! {
 
         15 DICT_WITH_CAPACITY: "withCapacity"
         16 DICT_CAPACITY: "capacity"
+        17 DICT_HASH_TEST_ONLY: "hashTestOnly"
     }
     9 SET: "Set" => {
         0 SET_SET: "Set" // the Set.Set type alias
@@ -671,12 +671,15 @@ impl<'a> BorrowInfState<'a> {
                 self.own_var(*x);
                 self.own_args_if_param(ys);
             }
-            EmptyArray => {
+            EmptyArray | NullPointer => {
                 self.own_var(z);
             }
 
             Call(call) => self.collect_call(param_map, z, call),
 
+            // these produce addresses, which are not refcounted
+            UnionFieldPtrAtIndex { .. } | Alloca { .. } => {}
+
             Literal(_) | RuntimeErrorFunction(_) => {}
 
             StructAtIndex { structure: x, .. } => {
@@ -930,11 +933,15 @@ pub fn lowlevel_borrow_signature(arena: &Bump, op: LowLevel) -> &[bool] {
 
         ListIsUnique => arena.alloc_slice_copy(&[borrowed]),
 
+        // the stored value moves into the memory the pointer points to
+        PtrStore => arena.alloc_slice_copy(&[irrelevant, owned]),
+        PtrLoad => arena.alloc_slice_copy(&[irrelevant]),
+
         BoxExpr | UnboxExpr => {
             unreachable!("These lowlevel operations are turned into mono Expr's")
         }
 
-        PtrCast | RefCountInc | RefCountDec => {
+        PtrCast | RefCountInc | RefCountDec | RefCountIsUnique => {
             unreachable!("Only inserted *after* borrow checking: {:?}", op);
         }
     }
@@ -151,6 +151,22 @@ impl<'a> CodeGenHelp<'a> {
         (rc_stmt, ctx.new_linker_data)
     }
 
+    /// Expand a `let is_unique = RefCountIsUnique structure` into IR that reads the refcount.
+    /// The structure must be a pointer to a refcounted allocation, and not null.
+    pub fn expand_is_unique(
+        &mut self,
+        ident_ids: &mut IdentIds,
+        layout: Layout<'a>,
+        structure: Symbol,
+        is_unique: Symbol,
+        following: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let stmt =
+            refcount::is_unique_stmt(self, ident_ids, layout, structure, is_unique, following);
+
+        self.arena.alloc(stmt)
+    }
+
     pub fn call_reset_refcount(
         &mut self,
         ident_ids: &mut IdentIds,
@@ -348,6 +348,68 @@ pub fn refcount_reset_proc_body<'a>(
     rc_ptr_stmt
 }
 
+/// Compare the refcount of a heap-allocated value to 1, binding the result to `is_unique`
+pub fn is_unique_stmt<'a>(
+    root: &CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    layout: Layout<'a>,
+    structure: Symbol,
+    is_unique: Symbol,
+    following: &'a Stmt<'a>,
+) -> Stmt<'a> {
+    let rc_ptr = root.create_symbol(ident_ids, "rc_ptr");
+    let rc = root.create_symbol(ident_ids, "rc");
+    let refcount_1 = root.create_symbol(ident_ids, "refcount_1");
+
+    let is_unique_stmt = let_lowlevel(
+        root.arena,
+        LAYOUT_BOOL,
+        is_unique,
+        Eq,
+        &[rc, refcount_1],
+        following,
+    );
+
+    let refcount_1_encoded = match root.target_info.ptr_width() {
+        PtrWidth::Bytes4 => i32::MIN as i128,
+        PtrWidth::Bytes8 => i64::MIN as i128,
+    }
+    .to_ne_bytes();
+    let refcount_1_stmt = Stmt::Let(
+        refcount_1,
+        Expr::Literal(Literal::Int(refcount_1_encoded)),
+        root.layout_isize,
+        root.arena.alloc(is_unique_stmt),
+    );
+
+    let rc_expr = Expr::UnionAtIndex {
+        structure: rc_ptr,
+        tag_id: 0,
+        union_layout: root.union_refcount,
+        index: 0,
+    };
+    let rc_stmt = Stmt::Let(
+        rc,
+        rc_expr,
+        root.layout_isize,
+        root.arena.alloc(refcount_1_stmt),
+    );
+
+    let mask_lower_bits = match layout {
+        Layout::Union(union_layout) => union_layout.stores_tag_id_in_pointer(root.target_info),
+        _ => false,
+    };
+
+    rc_ptr_from_data_ptr(
+        root,
+        ident_ids,
+        structure,
+        rc_ptr,
+        mask_lower_bits,
+        root.arena.alloc(rc_stmt),
+    )
+}
+
 // Check if refcounting is implemented yet. In the long term, this will be deleted.
 // In the short term, it helps us to skip refcounting and let it leak, so we can make
 // progress incrementally. Kept in sync with generate_procs using assertions.
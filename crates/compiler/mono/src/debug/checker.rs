@@ -0,0 +1,534 @@
+//! Structural checks: scoping of symbols and join points, and agreement of layouts.
+
+use super::ProblemKind;
+use crate::ir::{CallType, Expr, JoinPointId, ListLiteralElement, Param, Proc, ProcLayout, Stmt};
+use crate::layout::{Builtin, Layout, TagIdIntType, UnionLayout};
+use roc_collections::all::MutMap;
+use roc_module::symbol::Symbol;
+
+pub fn check_proc<'a>(
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    proc: &Proc<'a>,
+) -> Vec<ProblemKind<'a>> {
+    let mut ctx = Ctx {
+        procs,
+        ret_layout: proc.ret_layout,
+        symbols: MutMap::default(),
+        join_points: MutMap::default(),
+        problems: Vec::new(),
+    };
+
+    for (layout, symbol) in proc.args {
+        ctx.symbols.insert(*symbol, *layout);
+    }
+
+    ctx.check_stmt(&proc.body);
+
+    ctx.problems
+}
+
+struct Ctx<'a, 'r> {
+    procs: &'r MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ret_layout: Layout<'a>,
+    /// the symbols in scope, with their layout
+    symbols: MutMap<Symbol, Layout<'a>>,
+    /// the join points in scope, with their parameters
+    join_points: MutMap<JoinPointId, &'a [Param<'a>]>,
+    problems: Vec<ProblemKind<'a>>,
+}
+
+impl<'a, 'r> Ctx<'a, 'r> {
+    fn check_stmt(&mut self, stmt: &Stmt<'a>) {
+        use Stmt::*;
+
+        // let-chains can be very long, so walk them with a loop rather than recursion
+        let mut stmt = stmt;
+        let mut defined = Vec::new();
+
+        while let Let(symbol, expr, layout, cont) = stmt {
+            self.check_expr(*symbol, expr, *layout);
+            defined.push((*symbol, self.symbols.insert(*symbol, *layout)));
+            stmt = cont;
+        }
+
+        match stmt {
+            Let(..) => unreachable!("handled above"),
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout: _,
+            } => {
+                self.check_symbol_layout(*cond_symbol, *cond_layout, "the switch");
+
+                for (_, _, branch) in branches.iter() {
+                    self.check_stmt(branch);
+                }
+
+                self.check_stmt(default_branch.1);
+            }
+            Ret(symbol) => {
+                self.check_symbol_layout(*symbol, self.ret_layout, "the return of the proc");
+            }
+            Refcounting(modify, cont) => {
+                let symbol = modify.get_symbol();
+
+                if let Some(layout) = self.lookup(symbol) {
+                    if !layout.contains_refcounted() {
+                        self.problems
+                            .push(ProblemKind::RefcountOnNonRefcounted { symbol });
+                    }
+                }
+
+                self.check_stmt(cont);
+            }
+            Expect {
+                condition,
+                lookups,
+                layouts,
+                remainder,
+                ..
+            } => {
+                self.check_symbol_layout(*condition, Layout::bool(), "the expect condition");
+
+                for (symbol, layout) in lookups.iter().zip(layouts.iter()) {
+                    self.check_symbol_layout(*symbol, *layout, "the expect lookup");
+                }
+
+                self.check_stmt(remainder);
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let shadowed_join_point = self.join_points.insert(*id, parameters);
+
+                let shadowed: Vec<_> = parameters
+                    .iter()
+                    .map(|param| {
+                        (
+                            param.symbol,
+                            self.symbols.insert(param.symbol, param.layout),
+                        )
+                    })
+                    .collect();
+
+                // the body may jump to its own join point, to form a loop
+                self.check_stmt(body);
+
+                self.restore(shadowed);
+
+                self.check_stmt(remainder);
+
+                match shadowed_join_point {
+                    Some(old) => self.join_points.insert(*id, old),
+                    None => self.join_points.remove(id),
+                };
+            }
+            Jump(id, arguments) => match self.join_points.get(id).copied() {
+                Some(parameters) => {
+                    if parameters.len() != arguments.len() {
+                        self.problems.push(ProblemKind::JumpArity {
+                            id: *id,
+                            expected: parameters.len(),
+                            found: arguments.len(),
+                        });
+                    }
+
+                    for (symbol, param) in arguments.iter().zip(parameters.iter()) {
+                        self.check_symbol_layout(*symbol, param.layout, "the join point parameter");
+                    }
+                }
+                None => {
+                    self.problems
+                        .push(ProblemKind::UndefinedJoinPoint { id: *id });
+
+                    for symbol in arguments.iter() {
+                        self.lookup(*symbol);
+                    }
+                }
+            },
+            RuntimeError(_) => {}
+        }
+
+        self.restore(defined);
+    }
+
+    fn restore(&mut self, shadowed: Vec<(Symbol, Option<Layout<'a>>)>) {
+        for (symbol, old) in shadowed.into_iter().rev() {
+            match old {
+                Some(layout) => self.symbols.insert(symbol, layout),
+                None => self.symbols.remove(&symbol),
+            };
+        }
+    }
+
+    fn check_expr(&mut self, symbol: Symbol, expr: &Expr<'a>, layout: Layout<'a>) {
+        use Expr::*;
+
+        match expr {
+            Literal(_) | EmptyArray | NullPointer | Alloca { .. } | RuntimeErrorFunction(_) => {}
+            Call(call) => {
+                match &call.call_type {
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    } => {
+                        let proc_layout = ProcLayout {
+                            arguments: arg_layouts,
+                            result: **ret_layout,
+                            captures_niche: name.captures_niche(),
+                        };
+
+                        self.check_callee(name.name(), proc_layout);
+                        self.check_layout(symbol, layout, **ret_layout, "the call result");
+
+                        for (argument, arg_layout) in call.arguments.iter().zip(arg_layouts.iter())
+                        {
+                            self.check_symbol_layout(*argument, *arg_layout, "the call argument");
+                        }
+
+                        return;
+                    }
+                    CallType::Foreign { ret_layout, .. } => {
+                        self.check_layout(symbol, layout, **ret_layout, "the foreign call result");
+                    }
+                    CallType::HigherOrder(higher_order) => {
+                        let passed_function = &higher_order.passed_function;
+
+                        self.check_callee(
+                            passed_function.name.name(),
+                            ProcLayout {
+                                arguments: passed_function.argument_layouts,
+                                result: passed_function.return_layout,
+                                captures_niche: passed_function.name.captures_niche(),
+                            },
+                        );
+
+                        // the function argument names a proc, not a value
+                        let function_index = higher_order.op.function_index();
+
+                        for (i, argument) in call.arguments.iter().enumerate() {
+                            if i != function_index {
+                                self.lookup(*argument);
+                            }
+                        }
+
+                        return;
+                    }
+                    CallType::LowLevel { .. } => {}
+                }
+
+                for argument in call.arguments.iter() {
+                    self.lookup(*argument);
+                }
+            }
+            Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+            }
+            | Reuse {
+                tag_layout,
+                tag_id,
+                arguments,
+                ..
+            } => {
+                if let Reuse { symbol: token, .. } = expr {
+                    self.lookup(*token);
+                }
+
+                self.check_layout(symbol, layout, Layout::Union(*tag_layout), "the tag");
+
+                match tag_field_layouts(tag_layout, *tag_id) {
+                    Some(field_layouts) if field_layouts.len() == arguments.len() => {
+                        for (argument, field_layout) in arguments.iter().zip(field_layouts.iter()) {
+                            let expected = field_or_union(*field_layout, *tag_layout);
+                            self.check_symbol_layout(*argument, expected, "the tag payload");
+                        }
+                    }
+                    _ => {
+                        for argument in arguments.iter() {
+                            self.lookup(*argument);
+                        }
+                    }
+                }
+            }
+            Struct(arguments) => match normalize(layout) {
+                Layout::Struct { field_layouts, .. } if field_layouts.len() == arguments.len() => {
+                    for (argument, field_layout) in arguments.iter().zip(field_layouts.iter()) {
+                        self.check_symbol_layout(*argument, *field_layout, "the struct field");
+                    }
+                }
+                _ => {
+                    for argument in arguments.iter() {
+                        self.lookup(*argument);
+                    }
+                }
+            },
+            StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => {
+                if let Some(structure_layout) = self.lookup(*structure) {
+                    if let Layout::Struct {
+                        field_layouts: actual,
+                        ..
+                    } = normalize(structure_layout)
+                    {
+                        if !fields_agree(actual, field_layouts) {
+                            self.mismatch(
+                                *structure,
+                                structure_layout,
+                                Layout::struct_no_name_order(field_layouts),
+                                "the struct access",
+                            );
+                        }
+                    }
+                }
+
+                if let Some(field_layout) = field_layouts.get(*index as usize) {
+                    self.check_layout(symbol, layout, *field_layout, "the struct access");
+                }
+            }
+            GetTagId {
+                structure,
+                union_layout,
+            } => {
+                self.check_symbol_layout(*structure, Layout::Union(*union_layout), "GetTagId");
+            }
+            UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => {
+                self.check_symbol_layout(
+                    *structure,
+                    Layout::Union(*union_layout),
+                    "the union access",
+                );
+
+                if let Some(field_layouts) = tag_field_layouts(union_layout, *tag_id) {
+                    if let Some(field_layout) = field_layouts.get(*index as usize) {
+                        let expected = field_or_union(*field_layout, *union_layout);
+                        self.check_layout(symbol, layout, expected, "the union access");
+                    }
+                }
+            }
+            UnionFieldPtrAtIndex {
+                structure,
+                union_layout,
+                ..
+            } => {
+                self.check_symbol_layout(
+                    *structure,
+                    Layout::Union(*union_layout),
+                    "the union field pointer",
+                );
+            }
+            Array { elem_layout, elems } => {
+                if let Layout::Builtin(Builtin::List(actual)) = layout {
+                    self.check_layout(symbol, *actual, *elem_layout, "the list literal");
+                }
+
+                for elem in elems.iter() {
+                    if let ListLiteralElement::Symbol(element) = elem {
+                        self.check_symbol_layout(*element, *elem_layout, "the list element");
+                    }
+                }
+            }
+            ExprBox { symbol: boxed } => match layout {
+                Layout::Boxed(inner) => self.check_symbol_layout(*boxed, *inner, "the box"),
+                _ => {
+                    self.lookup(*boxed);
+                }
+            },
+            ExprUnbox { symbol: boxed } => {
+                if let Some(Layout::Boxed(inner)) = self.lookup(*boxed) {
+                    self.check_layout(symbol, layout, *inner, "the unbox");
+                }
+            }
+            Reset { symbol: reset, .. } => {
+                self.lookup(*reset);
+            }
+        }
+    }
+
+    fn check_callee(&mut self, name: Symbol, proc_layout: ProcLayout<'a>) {
+        if !self.procs.contains_key(&(name, proc_layout)) {
+            self.problems
+                .push(ProblemKind::UnknownCallee { name, proc_layout });
+        }
+    }
+
+    /// The layout of a symbol in scope; reports a problem if it is not in scope
+    fn lookup(&mut self, symbol: Symbol) -> Option<Layout<'a>> {
+        let layout = self.symbols.get(&symbol).copied();
+
+        if layout.is_none() {
+            self.problems.push(ProblemKind::UndefinedSymbol { symbol });
+        }
+
+        layout
+    }
+
+    fn check_symbol_layout(&mut self, symbol: Symbol, expected: Layout<'a>, context: &'static str) {
+        if let Some(found) = self.lookup(symbol) {
+            if !layouts_agree(found, expected) {
+                self.mismatch(symbol, found, expected, context);
+            }
+        }
+    }
+
+    /// Check the layout a let binds against the layout its expression produces
+    fn check_layout(
+        &mut self,
+        symbol: Symbol,
+        found: Layout<'a>,
+        expected: Layout<'a>,
+        context: &'static str,
+    ) {
+        if !layouts_agree(found, expected) {
+            self.mismatch(symbol, found, expected, context);
+        }
+    }
+
+    fn mismatch(
+        &mut self,
+        symbol: Symbol,
+        found: Layout<'a>,
+        expected: Layout<'a>,
+        context: &'static str,
+    ) {
+        self.problems.push(ProblemKind::LayoutMismatch {
+            symbol,
+            found,
+            expected,
+            context,
+        });
+    }
+}
+
+/// The payload layouts of a constructor, or `None` for the null constructor
+fn tag_field_layouts<'a>(
+    union_layout: &UnionLayout<'a>,
+    tag_id: TagIdIntType,
+) -> Option<&'a [Layout<'a>]> {
+    if union_layout.tag_is_null(tag_id) {
+        return None;
+    }
+
+    match union_layout {
+        UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => {
+            tags.get(tag_id as usize).copied()
+        }
+        UnionLayout::NonNullableUnwrapped(fields) => Some(fields),
+        UnionLayout::NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => {
+            let index = if tag_id < *nullable_id {
+                tag_id
+            } else {
+                tag_id - 1
+            };
+
+            other_tags.get(index as usize).copied()
+        }
+        UnionLayout::NullableUnwrapped { other_fields, .. } => Some(other_fields),
+    }
+}
+
+fn field_or_union<'a>(field_layout: Layout<'a>, union_layout: UnionLayout<'a>) -> Layout<'a> {
+    match field_layout {
+        Layout::RecursivePointer => Layout::Union(union_layout),
+        other => other,
+    }
+}
+
+/// Values of a lambda set are represented by the lambda set's runtime representation
+fn normalize(layout: Layout) -> Layout {
+    match layout {
+        Layout::LambdaSet(lambda_set) => lambda_set.runtime_representation(),
+        other => other,
+    }
+}
+
+/// Whether a value of one layout can be used where the other is expected. Besides equal layouts,
+/// this accepts a lambda set for its runtime representation, a recursive pointer for a recursive
+/// union, structs that only differ in the order hash of their fields, and a list of void for any
+/// list.
+fn layouts_agree(a: Layout, b: Layout) -> bool {
+    use Layout::*;
+
+    if a == b {
+        return true;
+    }
+
+    match (normalize(a), normalize(b)) {
+        (RecursivePointer, Union(union_layout)) | (Union(union_layout), RecursivePointer) => {
+            !matches!(union_layout, UnionLayout::NonRecursive(_))
+        }
+        (RecursivePointer, RecursivePointer) => true,
+        (
+            Struct {
+                field_layouts: xs, ..
+            },
+            Struct {
+                field_layouts: ys, ..
+            },
+        ) => fields_agree(xs, ys),
+        (Boxed(x), Boxed(y)) => layouts_agree(*x, *y),
+        (Builtin(crate::layout::Builtin::List(x)), Builtin(crate::layout::Builtin::List(y))) => {
+            // an empty list literal whose element type was never fixed has void elements
+            *x == Layout::VOID || *y == Layout::VOID || layouts_agree(*x, *y)
+        }
+        (Union(x), Union(y)) => unions_agree(x, y),
+        (x, y) => x == y,
+    }
+}
+
+fn fields_agree(xs: &[Layout], ys: &[Layout]) -> bool {
+    xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|(x, y)| layouts_agree(*x, *y))
+}
+
+fn tags_agree(xs: &[&[Layout]], ys: &[&[Layout]]) -> bool {
+    xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|(x, y)| fields_agree(x, y))
+}
+
+fn unions_agree(a: UnionLayout, b: UnionLayout) -> bool {
+    use UnionLayout::*;
+
+    match (a, b) {
+        (NonRecursive(xs), NonRecursive(ys)) | (Recursive(xs), Recursive(ys)) => tags_agree(xs, ys),
+        (NonNullableUnwrapped(xs), NonNullableUnwrapped(ys)) => fields_agree(xs, ys),
+        (
+            NullableWrapped {
+                nullable_id: a_id,
+                other_tags: xs,
+            },
+            NullableWrapped {
+                nullable_id: b_id,
+                other_tags: ys,
+            },
+        ) => a_id == b_id && tags_agree(xs, ys),
+        (
+            NullableUnwrapped {
+                nullable_id: a_id,
+                other_fields: xs,
+            },
+            NullableUnwrapped {
+                nullable_id: b_id,
+                other_fields: ys,
+            },
+        ) => a_id == b_id && fields_agree(xs, ys),
+        _ => false,
+    }
+}
@@ -0,0 +1,354 @@
+//! Debugging tools for the mono IR.
+//!
+//! The checker in this module validates that the procs produced by a mono stage are well-formed:
+//! symbols are defined before they are used, layouts agree wherever a value flows, calls and
+//! jumps match the signature of their target, and (once refcount instructions are inserted)
+//! every owned value is consumed exactly once on each path.
+//!
+//! Transformation bugs otherwise only show up as a backend crash or a wrong answer. Set
+//! `ROC_CHECK_MONO_IR=1` to run the checker after every stage of the loader.
+
+use crate::borrow::ParamMap;
+use crate::ir::{JoinPointId, Proc, ProcLayout};
+use crate::layout::Layout;
+use bumpalo::Bump;
+use roc_collections::all::MutMap;
+use roc_module::symbol::Symbol;
+use std::fmt;
+
+mod checker;
+mod refcount;
+
+/// The stage of the mono pipeline after which procs are checked
+#[derive(Clone, Copy, Debug)]
+pub enum CheckStage<'a> {
+    Specialization,
+    Inlining,
+    ResetReuse,
+    /// The borrow signatures are needed to know which arguments a proc owns
+    Refcount(&'a ParamMap<'a>),
+    ExpandRc,
+}
+
+impl<'a> CheckStage<'a> {
+    fn name(&self) -> &'static str {
+        match self {
+            CheckStage::Specialization => "specialization",
+            CheckStage::Inlining => "inlining",
+            CheckStage::ResetReuse => "reset/reuse",
+            CheckStage::Refcount(_) => "refcount insertion",
+            CheckStage::ExpandRc => "refcount expansion",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem<'a> {
+    /// The proc in which the problem occurs
+    pub proc: Symbol,
+    pub proc_layout: ProcLayout<'a>,
+    pub kind: ProblemKind<'a>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProblemKind<'a> {
+    /// A symbol is used, but not defined at that point
+    UndefinedSymbol { symbol: Symbol },
+    /// A value of one layout is used where another layout is expected
+    LayoutMismatch {
+        symbol: Symbol,
+        found: Layout<'a>,
+        expected: Layout<'a>,
+        context: &'static str,
+    },
+    /// A call to a proc that does not exist with this layout
+    UnknownCallee {
+        name: Symbol,
+        proc_layout: ProcLayout<'a>,
+    },
+    /// A jump to a join point that is not in scope
+    UndefinedJoinPoint { id: JoinPointId },
+    /// A jump passes a different number of arguments than the join point has parameters
+    JumpArity {
+        id: JoinPointId,
+        expected: usize,
+        found: usize,
+    },
+    /// An inc or dec of a value whose layout is not refcounted
+    RefcountOnNonRefcounted { symbol: Symbol },
+    /// A value is consumed (or decremented) on a path where it is not owned
+    ConsumedWithoutOwnership { symbol: Symbol },
+    /// An owned value is not consumed before the end of a path
+    Leaked { symbol: Symbol },
+    /// Jumps to the same join point hand over different ownership of a value the join point uses
+    InconsistentJumps {
+        id: JoinPointId,
+        symbol: Symbol,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl<'a> fmt::Display for Problem<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ProblemKind::*;
+
+        write!(f, "in proc {:?}: ", self.proc)?;
+
+        match &self.kind {
+            UndefinedSymbol { symbol } => write!(f, "{:?} is used before it is defined", symbol),
+            LayoutMismatch {
+                symbol,
+                found,
+                expected,
+                context,
+            } => write!(
+                f,
+                "{:?} has layout {:?}, but {} expects {:?}",
+                symbol, found, context, expected
+            ),
+            UnknownCallee { name, proc_layout } => write!(
+                f,
+                "call to {:?}, but there is no proc with layout {:?}",
+                name, proc_layout
+            ),
+            UndefinedJoinPoint { id } => write!(f, "jump to {:?}, which is not in scope", id.0),
+            JumpArity {
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "jump to {:?} with {} arguments, but it has {} parameters",
+                id.0, found, expected
+            ),
+            RefcountOnNonRefcounted { symbol } => write!(
+                f,
+                "refcount operation on {:?}, which is not refcounted",
+                symbol
+            ),
+            ConsumedWithoutOwnership { symbol } => write!(
+                f,
+                "{:?} is consumed on a path where it is not owned",
+                symbol
+            ),
+            Leaked { symbol } => write!(f, "{:?} is owned but not consumed on some path", symbol),
+            InconsistentJumps {
+                id,
+                symbol,
+                expected,
+                found,
+            } => write!(
+                f,
+                "jumps to {:?} disagree on the ownership of {:?}: {} versus {} references",
+                id.0, symbol, expected, found
+            ),
+        }
+    }
+}
+
+/// Check all procs, returning the problems found in the order they were found
+pub fn check_procs<'a>(
+    arena: &'a Bump,
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    stage: CheckStage<'a>,
+) -> Vec<Problem<'a>> {
+    let mut problems = Vec::new();
+
+    for ((symbol, proc_layout), proc) in procs.iter() {
+        let mut kinds = checker::check_proc(procs, proc);
+
+        // the balance of refcounts is only exact right after insertion; later passes may move
+        // ownership between values in ways that only make sense at runtime
+        if let CheckStage::Refcount(param_map) = stage {
+            kinds.extend(refcount::check_proc(arena, param_map, proc, *proc_layout));
+        }
+
+        for kind in kinds {
+            let problem = Problem {
+                proc: *symbol,
+                proc_layout: *proc_layout,
+                kind,
+            };
+
+            // the same problem is often reached along several paths
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+    }
+
+    problems
+}
+
+/// A human-readable report of the problems found after the given stage
+pub fn format_problems(stage: CheckStage, problems: &[Problem]) -> String {
+    let mut buf = format!(
+        "mono IR is not well-formed after {} ({} problems):\n",
+        stage.name(),
+        problems.len()
+    );
+
+    for problem in problems {
+        buf.push_str(&format!("    {}\n", problem));
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::borrow::infer_borrow;
+    use crate::ir::{Expr, HostExposedLayouts, Literal, ModifyRc, Param, SelfRecursive, Stmt};
+    use crate::layout::{Builtin, CapturesNiche, LambdaName};
+    use roc_builtins::bitcode::IntWidth;
+    use roc_module::symbol::{IdentIds, ModuleId};
+
+    const I64: Layout = Layout::Builtin(Builtin::Int(IntWidth::I64));
+    const LIST_I64: Layout = Layout::Builtin(Builtin::List(&I64));
+
+    fn symbols<const N: usize>(names: [&str; N]) -> [Symbol; N] {
+        let mut ident_ids = IdentIds::default();
+
+        names.map(|name| Symbol::new(ModuleId::ATTR, ident_ids.add_str(name)))
+    }
+
+    /// A proc without arguments that returns an I64
+    fn check<'a>(
+        arena: &'a Bump,
+        name: Symbol,
+        body: Stmt<'a>,
+        refcounted: bool,
+    ) -> (Vec<Problem<'a>>, CheckStage<'a>) {
+        let proc_layout = ProcLayout {
+            arguments: &[],
+            result: I64,
+            captures_niche: CapturesNiche::no_niche(),
+        };
+
+        let proc = Proc {
+            name: LambdaName::no_niche(name),
+            args: &[],
+            body,
+            closure_data_layout: None,
+            ret_layout: I64,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            must_own_arguments: false,
+            host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        };
+
+        let stage = if refcounted {
+            // borrow inference runs before refcounts are inserted. The proc has no arguments, so
+            // its borrow signature does not depend on the body
+            let mut procs = MutMap::default();
+            let before = Proc {
+                body: Stmt::RuntimeError("refcounts are not inserted yet"),
+                ..proc.clone()
+            };
+            procs.insert((name, proc_layout), before);
+
+            CheckStage::Refcount(arena.alloc(infer_borrow(arena, &procs)))
+        } else {
+            CheckStage::Specialization
+        };
+
+        let mut procs = MutMap::default();
+        procs.insert((name, proc_layout), proc);
+
+        (check_procs(arena, &procs, stage), stage)
+    }
+
+    fn int_literal<'a>() -> Expr<'a> {
+        Expr::Literal(Literal::Int(0i128.to_ne_bytes()))
+    }
+
+    #[test]
+    fn undefined_symbol() {
+        let arena = Bump::new();
+        let [name, undefined] = symbols(["f", "undefined"]);
+        let (problems, stage) = check(&arena, name, Stmt::Ret(undefined), false);
+
+        let kinds: Vec<_> = problems.iter().map(|p| &p.kind).collect();
+        assert_eq!(kinds, [&ProblemKind::UndefinedSymbol { symbol: undefined }]);
+
+        let report = format_problems(stage, &problems);
+        assert!(report.starts_with("mono IR is not well-formed after specialization (1 problems):"));
+        assert!(report.contains("is used before it is defined"));
+    }
+
+    #[test]
+    fn jump_arity() {
+        let arena = Bump::new();
+        let [name, join_point, param, argument] = symbols(["f", "jp", "param", "argument"]);
+        let id = JoinPointId(join_point);
+
+        let jump = Stmt::Jump(id, arena.alloc([argument, argument]));
+        let body = Stmt::Join {
+            id,
+            parameters: arena.alloc([Param {
+                symbol: param,
+                borrow: false,
+                layout: I64,
+            }]),
+            body: arena.alloc(Stmt::Ret(param)),
+            remainder: arena.alloc(Stmt::Let(argument, int_literal(), I64, arena.alloc(jump))),
+        };
+
+        let (problems, stage) = check(&arena, name, body, false);
+
+        let kinds: Vec<_> = problems.iter().map(|p| &p.kind).collect();
+        assert_eq!(
+            kinds,
+            [&ProblemKind::JumpArity {
+                id,
+                expected: 1,
+                found: 2
+            }]
+        );
+
+        let report = format_problems(stage, &problems);
+        assert!(report.contains("with 2 arguments, but it has 1 parameters"));
+    }
+
+    #[test]
+    fn leaked_list() {
+        let arena = Bump::new();
+        let [name, list, result] = symbols(["f", "list", "result"]);
+
+        // the list is created, but never decremented
+        let ret = Stmt::Let(result, int_literal(), I64, arena.alloc(Stmt::Ret(result)));
+        let body = Stmt::Let(list, Expr::EmptyArray, LIST_I64, arena.alloc(ret));
+
+        let (problems, stage) = check(&arena, name, body, true);
+
+        let kinds: Vec<_> = problems.iter().map(|p| &p.kind).collect();
+        assert_eq!(kinds, [&ProblemKind::Leaked { symbol: list }]);
+
+        let report = format_problems(stage, &problems);
+        assert!(report.starts_with("mono IR is not well-formed after refcount insertion"));
+        assert!(report.contains("is owned but not consumed on some path"));
+    }
+
+    #[test]
+    fn decremented_twice() {
+        let arena = Bump::new();
+        let [name, list, result] = symbols(["f", "list", "result"]);
+
+        let ret = Stmt::Let(result, int_literal(), I64, arena.alloc(Stmt::Ret(result)));
+        let second = Stmt::Refcounting(ModifyRc::Dec(list), arena.alloc(ret));
+        let first = Stmt::Refcounting(ModifyRc::Dec(list), arena.alloc(second));
+        let body = Stmt::Let(list, Expr::EmptyArray, LIST_I64, arena.alloc(first));
+
+        let (problems, stage) = check(&arena, name, body, true);
+
+        let kinds: Vec<_> = problems.iter().map(|p| &p.kind).collect();
+        assert_eq!(
+            kinds,
+            [&ProblemKind::ConsumedWithoutOwnership { symbol: list }]
+        );
+
+        let report = format_problems(stage, &problems);
+        assert!(report.contains("is consumed on a path where it is not owned"));
+    }
+}
@@ -0,0 +1,351 @@
+//! Refcount balance: after refcount insertion, every reference a proc owns must be consumed
+//! exactly once on each path through the proc.
+//!
+//! We count the references each value owns. Fresh values own one reference, and so do the
+//! arguments that a proc or join point does not borrow. An `inc` adds references; a `dec`, a
+//! `decref`, and passing a value to an owned argument each take one away. Projections out of a
+//! structure borrow from it, so they start out owning nothing.
+//!
+//! The zig builtins for higher-order lowlevels manage the refcounts of their data themselves, so
+//! values passed to them are no longer tracked.
+
+use super::ProblemKind;
+use crate::borrow::{foreign_borrow_signature, lowlevel_borrow_signature, ParamMap};
+use crate::inc_dec::{collect_stmt, JPLiveVarMap};
+use crate::ir::{CallType, Expr, JoinPointId, ModifyRc, Param, Proc, ProcLayout, Stmt};
+use crate::layout::Layout;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+/// The number of references owned by each tracked value
+type Owned = MutMap<Symbol, u64>;
+
+pub fn check_proc<'a>(
+    arena: &'a Bump,
+    param_map: &'a ParamMap<'a>,
+    proc: &Proc<'a>,
+    proc_layout: ProcLayout<'a>,
+) -> Vec<ProblemKind<'a>> {
+    let mut ctx = Ctx {
+        arena,
+        param_map,
+        jp_live_vars: MutMap::default(),
+        jp_params: MutMap::default(),
+        jp_entry: MutMap::default(),
+        problems: Vec::new(),
+    };
+
+    let mut owned = Owned::default();
+
+    match param_map.get_symbol(proc.name.name(), proc_layout) {
+        Some(params) => bind_params(&mut owned, params),
+        None => {
+            // procs that are not in the param map own all their arguments
+            for (layout, symbol) in proc.args {
+                define(&mut owned, *symbol, layout, 1);
+            }
+        }
+    }
+
+    ctx.visit_stmt(&proc.body, owned);
+
+    ctx.problems
+}
+
+struct Ctx<'a> {
+    arena: &'a Bump,
+    param_map: &'a ParamMap<'a>,
+    /// the values that are live when jumping to a join point
+    jp_live_vars: JPLiveVarMap,
+    jp_params: MutMap<JoinPointId, &'a [Param<'a>]>,
+    /// what the first jump to a join point hands over; other jumps must agree
+    jp_entry: MutMap<JoinPointId, Owned>,
+    problems: Vec<ProblemKind<'a>>,
+}
+
+impl<'a> Ctx<'a> {
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>, mut owned: Owned) {
+        use Stmt::*;
+
+        // let-chains can be very long, so walk them with a loop rather than recursion
+        let mut stmt = stmt;
+
+        while let Let(symbol, expr, layout, cont) = stmt {
+            self.visit_expr(&mut owned, *symbol, expr, layout);
+            stmt = cont;
+        }
+
+        match stmt {
+            Let(..) => unreachable!("handled above"),
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    self.visit_stmt(branch, owned.clone());
+                }
+
+                self.visit_stmt(default_branch.1, owned);
+            }
+            Ret(symbol) => {
+                self.consume(&mut owned, *symbol);
+                self.check_leaks(&owned, &MutSet::default());
+            }
+            Refcounting(modify, cont) => {
+                match modify {
+                    ModifyRc::Inc(symbol, n) => {
+                        if let Some(count) = owned.get_mut(symbol) {
+                            *count += n;
+                        }
+                    }
+                    ModifyRc::Dec(symbol) | ModifyRc::DecRef(symbol) => {
+                        self.consume(&mut owned, *symbol);
+                    }
+                }
+
+                self.visit_stmt(cont, owned);
+            }
+            Expect {
+                lookups, remainder, ..
+            } => {
+                for symbol in lookups.iter() {
+                    self.consume(&mut owned, *symbol);
+                }
+
+                self.visit_stmt(remainder, owned);
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let mut live_vars = collect_stmt(body, &self.jp_live_vars, MutSet::default());
+                for param in parameters.iter() {
+                    live_vars.remove(&param.symbol);
+                }
+
+                self.jp_live_vars.insert(*id, live_vars);
+                self.jp_params.insert(*id, parameters);
+
+                self.visit_stmt(remainder, owned);
+
+                // a join point that is never jumped to is dead code
+                if let Some(mut entry) = self.jp_entry.get(id).cloned() {
+                    bind_params(&mut entry, parameters);
+
+                    self.visit_stmt(body, entry);
+                }
+            }
+            Jump(id, arguments) => {
+                let parameters = match self.jp_params.get(id) {
+                    Some(parameters) => *parameters,
+                    None => return,
+                };
+
+                for (symbol, param) in arguments.iter().zip(parameters.iter()) {
+                    if !param.borrow {
+                        self.consume(&mut owned, *symbol);
+                    }
+                }
+
+                // the join point takes over the references of the values it uses
+                let live_vars = self.jp_live_vars[id].clone();
+                self.check_leaks(&owned, &live_vars);
+
+                let entry: Owned = owned
+                    .into_iter()
+                    .filter(|(symbol, _)| live_vars.contains(symbol))
+                    .collect();
+
+                match self.jp_entry.get(id) {
+                    Some(expected) => {
+                        let mut symbols: Vec<_> = entry.keys().copied().collect();
+                        symbols.sort();
+
+                        for symbol in symbols {
+                            let found = entry[&symbol];
+                            let expected = expected.get(&symbol).copied().unwrap_or(found);
+
+                            if found != expected {
+                                self.problems.push(ProblemKind::InconsistentJumps {
+                                    id: *id,
+                                    symbol,
+                                    expected,
+                                    found,
+                                });
+                            }
+                        }
+                    }
+                    None => {
+                        self.jp_entry.insert(*id, entry);
+                    }
+                }
+            }
+            RuntimeError(_) => {
+                // the program crashes here, so leaks do not matter
+            }
+        }
+    }
+
+    fn visit_expr(&mut self, owned: &mut Owned, symbol: Symbol, expr: &Expr<'a>, layout: &Layout) {
+        use Expr::*;
+
+        let references = match expr {
+            Literal(_)
+            | EmptyArray
+            | NullPointer
+            | Alloca { .. }
+            | RuntimeErrorFunction(_)
+            | GetTagId { .. }
+            | UnionFieldPtrAtIndex { .. } => 1,
+
+            StructAtIndex { .. } | UnionAtIndex { .. } | ExprUnbox { .. } => 0,
+
+            Tag { arguments, .. } | Struct(arguments) => {
+                for argument in arguments.iter() {
+                    self.consume(owned, *argument);
+                }
+
+                1
+            }
+            Array { elems, .. } => {
+                for argument in elems.iter().filter_map(|e| e.to_symbol()) {
+                    self.consume(owned, argument);
+                }
+
+                1
+            }
+            ExprBox { symbol: boxed } => {
+                self.consume(owned, *boxed);
+
+                1
+            }
+            Reuse {
+                symbol: token,
+                arguments,
+                ..
+            } => {
+                self.consume(owned, *token);
+
+                for argument in arguments.iter() {
+                    self.consume(owned, *argument);
+                }
+
+                1
+            }
+            Reset { symbol: reset, .. } => {
+                // the reset token takes over the reference
+                self.consume(owned, *reset);
+
+                1
+            }
+            Call(call) => {
+                self.visit_call(owned, call);
+
+                1
+            }
+        };
+
+        define(owned, symbol, layout, references);
+    }
+
+    fn visit_call(&mut self, owned: &mut Owned, call: &crate::ir::Call<'a>) {
+        let arguments = call.arguments;
+
+        let borrow_signature: &[bool] = match &call.call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                ..
+            } => {
+                let proc_layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: **ret_layout,
+                    captures_niche: name.captures_niche(),
+                };
+
+                match self.param_map.get_symbol(name.name(), proc_layout) {
+                    Some(params) => self
+                        .arena
+                        .alloc_slice_fill_iter(params.iter().map(|param| param.borrow)),
+                    None => {
+                        // the structural check reports the unknown callee
+                        untrack(owned, arguments);
+                        return;
+                    }
+                }
+            }
+            CallType::Foreign { .. } => foreign_borrow_signature(self.arena, arguments.len()),
+            CallType::LowLevel { op, .. } => match op {
+                LowLevel::PtrCast
+                | LowLevel::RefCountInc
+                | LowLevel::RefCountDec
+                | LowLevel::RefCountIsUnique => return,
+                _ => lowlevel_borrow_signature(self.arena, *op),
+            },
+            CallType::HigherOrder(higher_order) => {
+                untrack(owned, &arguments[..higher_order.op.function_index()]);
+                return;
+            }
+        };
+
+        for (argument, borrowed) in arguments.iter().zip(borrow_signature.iter()) {
+            if !borrowed {
+                self.consume(owned, *argument);
+            }
+        }
+    }
+
+    fn consume(&mut self, owned: &mut Owned, symbol: Symbol) {
+        if let Some(count) = owned.get_mut(&symbol) {
+            match count.checked_sub(1) {
+                Some(new) => *count = new,
+                None => self
+                    .problems
+                    .push(ProblemKind::ConsumedWithoutOwnership { symbol }),
+            }
+        }
+    }
+
+    /// Report the values that still own references, apart from those that are handed over
+    fn check_leaks(&mut self, owned: &Owned, handed_over: &MutSet<Symbol>) {
+        let mut leaked: Vec<_> = owned
+            .iter()
+            .filter(|(symbol, count)| **count > 0 && !handed_over.contains(symbol))
+            .map(|(symbol, _)| *symbol)
+            .collect();
+
+        leaked.sort();
+
+        for symbol in leaked {
+            self.problems.push(ProblemKind::Leaked { symbol });
+        }
+    }
+}
+
+fn bind_params(owned: &mut Owned, params: &[Param]) {
+    for param in params {
+        let references = if param.borrow { 0 } else { 1 };
+        define(owned, param.symbol, &param.layout, references);
+    }
+}
+
+/// Track a new value, if it is refcounted
+fn define(owned: &mut Owned, symbol: Symbol, layout: &Layout, references: u64) {
+    if layout.contains_refcounted() {
+        owned.insert(symbol, references);
+    } else {
+        owned.remove(&symbol);
+    }
+}
+
+fn untrack(owned: &mut Owned, symbols: &[Symbol]) {
+    for symbol in symbols {
+        owned.remove(symbol);
+    }
+}
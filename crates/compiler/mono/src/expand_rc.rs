@@ -1,672 +1,630 @@
-/// UNUSED
-///
-/// but kept for future reference
-///
-///
-//    pub fn optimize_refcount_operations<'i, T>(
-//        arena: &'a Bump,
-//        home: ModuleId,
-//        ident_ids: &'i mut IdentIds,
-//        procs: &mut MutMap<T, Proc<'a>>,
-//    ) {
-//        use crate::expand_rc;
-//
-//        let deferred = expand_rc::Deferred {
-//            inc_dec_map: Default::default(),
-//            assignments: Vec::new_in(arena),
-//            decrefs: Vec::new_in(arena),
-//        };
-//
-//        let mut env = expand_rc::Env {
-//            home,
-//            arena,
-//            ident_ids,
-//            layout_map: Default::default(),
-//            alias_map: Default::default(),
-//            constructor_map: Default::default(),
-//            deferred,
-//        };
-//
-//        for (_, proc) in procs.iter_mut() {
-//            let b = expand_rc::expand_and_cancel_proc(
-//                &mut env,
-//                arena.alloc(proc.body.clone()),
-//                proc.args,
-//            );
-//            proc.body = b.clone();
-//        }
-//    }
-use crate::ir::{BranchInfo, Expr, ModifyRc, Stmt};
-use crate::layout::{Layout, UnionLayout};
+//! Expansion and cancellation of refcount operations.
+//!
+//! This pass is heavily inspired by the "drop specialization" of the Perceus paper
+//!
+//! https://www.microsoft.com/en-us/research/uploads/prod/2020/11/perceus-tr-v1.pdf
+//!
+//! With how we insert RC instructions, this pattern is very common:
+//!
+//!     when xs is
+//!         Cons x rest ->
+//!             inc x;
+//!             inc rest;
+//!             dec xs;
+//!             ...
+//!
+//! If `xs` is unique, the `dec xs` frees it and decrements its fields, undoing the increments we
+//! just did. This pass specializes the `dec` for the constructor that we know `xs` has here:
+//!
+//!     when xs is
+//!         Cons x rest ->
+//!             let u = lowlevel RefCountIsUnique xs;
+//!             join jp =
+//!                 ...
+//!             in
+//!             if u then
+//!                 decref xs;
+//!                 jump jp
+//!             else
+//!                 inc x;
+//!                 inc rest;
+//!                 decref xs;
+//!                 jump jp
+//!
+//! In the unique case, the `inc` of every field cancels against the `dec` of that field, and
+//! `decref` frees the cell without touching its fields. Fields that were not incremented are
+//! decremented explicitly, loading them with a `UnionAtIndex` if the program never bound them.
+//!
+//! Values that live on the stack (structs and non-recursive unions) have no refcount of their
+//! own, so for them the `dec` is expanded without a uniqueness check. A `dec` of the null
+//! constructor of a nullable union is dropped altogether.
+//!
+//! We learn the constructor of a value from the `BranchInfo` of a switch, or from a
+//! `UnionAtIndex` that reads one of its fields. To find matching pairs, increments are pushed down
+//! past statements that cannot change any refcount, and emitted right before the first statement
+//! that could.
+//!
+//! This pass runs after refcount insertion.
+
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, JoinPointId, ModifyRc, Proc, Stmt, UpdateModeIds,
+};
+use crate::layout::{Layout, TagIdIntType, UnionLayout};
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
-// use linked_hash_map::LinkedHashMap;
 use roc_collections::all::MutMap;
+use roc_module::low_level::LowLevel;
 use roc_module::symbol::{IdentIds, ModuleId, Symbol};
 
-// This file is heavily inspired by the Perceus paper
-//
-// https://www.microsoft.com/en-us/research/uploads/prod/2020/11/perceus-tr-v1.pdf
-//
-// With how we insert RC instructions, this pattern is very common:
-//
-//  when xs is
-//      Cons x xx ->
-//          inc x;
-//          inc xx;
-//          dec xs;
-//          ...
-//
-// This pattern is very inefficient, because it will first increment the tail (recursively),
-// and then decrement it again. We can see this more clearly if we inline/specialize the `dec xs`
-//
-//  when xs is
-//      Cons x xx ->
-//          inc x;
-//          inc xx;
-//          dec x;
-//          dec xx;
-//          decref xs
-//          ...
-//
-// Here `decref` non-recursively decrements (and possibly frees) `xs`. Now the idea is that we can
-// fuse `inc x; dec x` by just doing nothing: they cancel out
-//
-// We can do slightly more, in the `Nil` case
-//
-//  when xs is
-//      ...
-//      Nil ->
-//          dec xs;
-//          accum
-//
-// Here we know that `Nil` is represented by NULL (a linked list has a NullableUnwrapped layout),
-// so we  can just drop the `dec xs`
-//
-// # complications
-//
-// Let's work through the `Cons x xx` example
-//
-// First we need to know the constructor of `xs` in the particular block. This information would
-// normally be lost when we compile pattern matches, but we keep it in the `BranchInfo` field of
-// switch branches. here we also store the symbol that was switched on, and the layout of that
-// symbol.
-//
-// Next, we need to know that `x` and `xx` alias the head and tail of `xs`. We store that
-// information when encountering a `AccessAtIndex` into `xs`.
-//
-// In most cases these two pieces of information are enough. We keep track of a
-// `LinkedHashMap<Symbol, i64>`: `LinkedHashMap` remembers insertion order, which is crucial here.
-// The `i64` value represents the increment (positive value) or decrement (negative value). When
-// the value is 0, increments and decrements have cancelled out and we just emit nothing.
-//
-// We need to do slightly more work in the case of
-//
-//  when xs is
-//      Cons _ xx ->
-//          recurse xx (1 + accum)
-//
-// In this case, the head is not bound. That's OK when the list elements are not refcounted (or
-// contain anything refcounted). But when they do, we can't expand the `dec xs` because there is no
-// way to reference the head element.
-//
-// Our refcounting mechanism can't deal well with unused variables (it'll leak their memory). But
-// we can insert the access after RC instructions have been inserted. So in the above case we
-// actually get
-//
-//  when xs is
-//      Cons _ xx ->
-//          let v1 = AccessAtIndex 1 xs
-//          inc v1;
-//          let xx = AccessAtIndex 2 xs
-//          inc xx;
-//          dec v1;
-//          dec xx;
-//          decref xs;
-//          recurse xx (1 + accum)
-//
-// Here we see another problem: the increments and decrements cannot be fused immediately.
-// Therefore we add a rule that we can "push down" increments and decrements past
-//
-//  - `Let`s binding a `AccessAtIndex`
-//  - refcount operations
-//
-// This allows the aforementioned `LinkedHashMap` to accumulate all changes, and then emit
-// all (uncancelled) modifications at once before any "non-push-downable-stmt", hence:
-//
-//  when xs is
-//      Cons _ xx ->
-//          let v1 = AccessAtIndex 1 xs
-//          let xx = AccessAtIndex 2 xs
-//          dec v1;
-//          decref xs;
-//          recurse xx (1 + accum)
-
-pub struct Env<'a, 'i> {
-    /// bump allocator
-    pub arena: &'a Bump,
-
-    /// required for creating new `Symbol`s
-    pub home: ModuleId,
-    pub ident_ids: &'i mut IdentIds,
-
-    /// layout of the symbol
-    pub layout_map: MutMap<Symbol, Layout<'a>>,
-
-    /// record for each symbol, the aliases of its fields
-    pub alias_map: MutMap<Symbol, MutMap<u64, Symbol>>,
-
-    /// for a symbol (found in a `when x is`), record in which branch we are
-    pub constructor_map: MutMap<Symbol, u64>,
-
-    /// increments and decrements deferred until later
-    pub deferred: Deferred<'a>,
+pub fn expand_and_cancel_proc<'a, 'i>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    proc: &mut Proc<'a>,
+) {
+    let mut env = Env {
+        arena,
+        home,
+        ident_ids,
+        update_mode_ids,
+        ret_layout: proc.ret_layout,
+    };
+
+    let mut context = Context::default();
+
+    for (layout, symbol) in proc.args {
+        context.layouts.insert(*symbol, *layout);
+    }
+
+    let mut pending = Pending::new();
+
+    let body = arena.alloc(proc.body.clone());
+    proc.body = expand_and_cancel(&mut env, &mut context, &mut pending, body).clone();
 }
 
-#[derive(Debug)]
-pub struct Deferred<'a> {
-    pub inc_dec_map: LinkedHashMap<Symbol, i64>,
-    pub assignments: Vec<'a, (Symbol, Expr<'a>, Layout<'a>)>,
-    pub decrefs: Vec<'a, Symbol>,
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    /// The layout that every branch of the current procedure returns
+    ret_layout: Layout<'a>,
 }
 
 impl<'a, 'i> Env<'a, 'i> {
-    fn insert_branch_info(&mut self, info: &BranchInfo<'a>) {
-        match info {
-            BranchInfo::Constructor {
-                layout,
-                scrutinee,
-                tag_id,
-            } => {
-                self.constructor_map.insert(*scrutinee, *tag_id as u64);
-                self.layout_map.insert(*scrutinee, *layout);
-            }
-            BranchInfo::None => (),
-        }
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
     }
 
-    fn remove_branch_info(&mut self, info: &BranchInfo) {
-        match info {
-            BranchInfo::Constructor { scrutinee, .. } => {
-                self.constructor_map.remove(scrutinee);
-                self.layout_map.remove(scrutinee);
-            }
-            BranchInfo::None => (),
-        }
+    fn join_point_id(&mut self) -> JoinPointId {
+        JoinPointId(self.unique_symbol())
     }
+}
 
-    fn try_insert_struct_info(&mut self, symbol: Symbol, layout: &Layout<'a>) {
-        use Layout::*;
+/// What we know about the symbols in scope
+#[derive(Clone, Default)]
+struct Context<'a> {
+    layouts: MutMap<Symbol, Layout<'a>>,
+    /// The tag id of unions whose constructor is known
+    constructors: MutMap<Symbol, TagIdIntType>,
+    /// For each structure, the symbols that its fields are bound to
+    fields: MutMap<Symbol, MutMap<u64, Symbol>>,
+}
 
-        if let Struct(fields) = layout {
-            self.constructor_map.insert(symbol, 0);
-            self.layout_map.insert(symbol, Layout::Struct(fields));
-        }
+impl<'a> Context<'a> {
+    fn field_alias(&self, structure: Symbol, index: u64) -> Option<Symbol> {
+        self.fields
+            .get(&structure)
+            .and_then(|fields| fields.get(&index))
+            .copied()
     }
+}
 
-    fn insert_struct_info(&mut self, symbol: Symbol, fields: &'a [Layout<'a>]) {
-        self.constructor_map.insert(symbol, 0);
-        self.layout_map.insert(symbol, Layout::Struct(fields));
-    }
+/// Increments that have not been emitted yet, in the order we encountered them
+type Pending = std::vec::Vec<(Symbol, u64)>;
 
-    fn remove_struct_info(&mut self, symbol: Symbol) {
-        self.constructor_map.remove(&symbol);
-        self.layout_map.remove(&symbol);
+fn add_pending(pending: &mut Pending, symbol: Symbol, amount: u64) {
+    match pending.iter_mut().find(|(s, _)| *s == symbol) {
+        Some((_, n)) => *n += amount,
+        None => pending.push((symbol, amount)),
     }
+}
 
-    pub fn unique_symbol(&mut self) -> Symbol {
-        let ident_id = self.ident_ids.gen_unique();
+/// Cancel one pending increment of `symbol`, returning whether there was one
+fn cancel_pending(pending: &mut Pending, symbol: Symbol) -> bool {
+    match pending.iter().position(|(s, _)| *s == symbol) {
+        Some(i) => {
+            if pending[i].1 == 1 {
+                pending.remove(i);
+            } else {
+                pending[i].1 -= 1;
+            }
 
-        Symbol::new(self.home, ident_id)
+            true
+        }
+        None => false,
     }
-    #[allow(dead_code)]
-    fn manual_unique_symbol(home: ModuleId, ident_ids: &mut IdentIds) -> Symbol {
-        let ident_id = ident_ids.gen_unique();
+}
 
-        Symbol::new(home, ident_id)
+fn emit_incs<'a>(arena: &'a Bump, incs: &[(Symbol, u64)], mut stmt: &'a Stmt<'a>) -> &'a Stmt<'a> {
+    for (symbol, amount) in incs.iter().rev() {
+        stmt = arena.alloc(Stmt::Refcounting(ModifyRc::Inc(*symbol, *amount), stmt));
     }
+
+    stmt
+}
+
+/// Increments can be pushed past these expressions, because they do not change (or depend on)
+/// any refcount
+fn can_push_inc_through(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Literal(_)
+            | Expr::StructAtIndex { .. }
+            | Expr::UnionAtIndex { .. }
+            | Expr::GetTagId { .. }
+    )
 }
 
-fn layout_for_constructor<'a>(
-    _arena: &'a Bump,
-    layout: &Layout<'a>,
-    constructor: u64,
-) -> ConstructorLayout<&'a [Layout<'a>]> {
-    use ConstructorLayout::*;
-    use Layout::*;
-
-    match layout {
-        Union(variant) => {
-            use UnionLayout::*;
-            match variant {
-                NullableUnwrapped {
-                    nullable_id,
-                    other_fields,
+fn expand_and_cancel<'a>(
+    env: &mut Env<'a, '_>,
+    context: &mut Context<'a>,
+    pending: &mut Pending,
+    stmt: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    use Stmt::*;
+
+    let arena = env.arena;
+
+    match stmt {
+        Refcounting(ModifyRc::Inc(symbol, amount), continuation) => {
+            add_pending(pending, *symbol, *amount);
+
+            expand_and_cancel(env, context, pending, continuation)
+        }
+        Refcounting(ModifyRc::Dec(symbol), continuation) => {
+            expand_dec(env, context, pending, *symbol, continuation)
+        }
+        Let(symbol, expr, layout, continuation) => {
+            context.layouts.insert(*symbol, *layout);
+
+            match expr {
+                Expr::UnionAtIndex {
+                    structure,
+                    tag_id,
+                    index,
+                    ..
                 } => {
-                    if (constructor > 0) == *nullable_id {
-                        ConstructorLayout::IsNull
-                    } else {
-                        ConstructorLayout::HasFields(other_fields)
-                    }
+                    context.constructors.insert(*structure, *tag_id);
+                    context
+                        .fields
+                        .entry(*structure)
+                        .or_default()
+                        .entry(*index)
+                        .or_insert(*symbol);
                 }
-                NullableWrapped {
-                    nullable_id,
-                    other_tags,
+                Expr::StructAtIndex {
+                    structure, index, ..
                 } => {
-                    if constructor as i64 == *nullable_id {
-                        ConstructorLayout::IsNull
-                    } else {
-                        ConstructorLayout::HasFields(other_tags[constructor as usize])
-                    }
-                }
-                NonRecursive(fields) | Recursive(fields) => HasFields(fields[constructor as usize]),
-                NonNullableUnwrapped(fields) => {
-                    debug_assert_eq!(constructor, 0);
-                    HasFields(fields)
+                    context
+                        .fields
+                        .entry(*structure)
+                        .or_default()
+                        .entry(*index)
+                        .or_insert(*symbol);
                 }
+                _ => {}
+            }
+
+            if can_push_inc_through(expr) {
+                let continuation = expand_and_cancel(env, context, pending, continuation);
+
+                arena.alloc(Let(*symbol, expr.clone(), *layout, continuation))
+            } else {
+                let incs = std::mem::take(pending);
+                let continuation = expand_and_cancel(env, context, pending, continuation);
+                let stmt = arena.alloc(Let(*symbol, expr.clone(), *layout, continuation));
+
+                emit_incs(arena, &incs, stmt)
             }
         }
-        Struct(fields) => {
-            debug_assert_eq!(constructor, 0);
-            HasFields(fields)
-        }
-        other => unreachable!("weird layout {:?}", other),
-    }
-}
+        Switch {
+            cond_symbol,
+            cond_layout,
+            branches,
+            default_branch,
+            ret_layout,
+        } => {
+            let incs = std::mem::take(pending);
+
+            let mut new_branches = Vec::with_capacity_in(branches.len(), arena);
+
+            for (tag, info, branch) in branches.iter() {
+                let mut branch_context = context.clone();
+                learn_constructor(&mut branch_context, info);
+
+                let branch =
+                    expand_and_cancel(env, &mut branch_context, &mut Pending::new(), branch);
+                new_branches.push((*tag, info.clone(), branch.clone()));
+            }
 
-fn work_for_constructor<'a>(
-    env: &mut Env<'a, '_>,
-    symbol: &Symbol,
-) -> ConstructorLayout<Vec<'a, Symbol>> {
-    use ConstructorLayout::*;
+            let default_branch = {
+                let (info, branch) = default_branch;
 
-    let mut result = Vec::new_in(env.arena);
+                let mut branch_context = context.clone();
+                learn_constructor(&mut branch_context, info);
 
-    let constructor = match env.constructor_map.get(symbol) {
-        None => return ConstructorLayout::Unknown,
-        Some(v) => *v,
-    };
-    let full_layout = match env.layout_map.get(symbol) {
-        None => return ConstructorLayout::Unknown,
-        Some(v) => v,
-    };
+                let branch =
+                    expand_and_cancel(env, &mut branch_context, &mut Pending::new(), branch);
 
-    let field_aliases = env.alias_map.get(symbol);
-
-    match layout_for_constructor(env.arena, full_layout, constructor) {
-        Unknown => Unknown,
-        IsNull => IsNull,
-        HasFields(constructor_layout) => {
-            // figure out if there is at least one aliased refcounted field. Only then
-            // does it make sense to inline the decrement
-            let at_least_one_aliased = (|| {
-                for (i, field_layout) in constructor_layout.iter().enumerate() {
-                    if field_layout.contains_refcounted()
-                        && field_aliases.and_then(|map| map.get(&(i as u64))).is_some()
-                    {
-                        return true;
-                    }
-                }
-                false
-            })();
-
-            // for each field, if it has refcounted content, check if it has an alias
-            // if so, use the alias, otherwise load the field.
-            for (i, field_layout) in constructor_layout.iter().enumerate() {
-                if field_layout.contains_refcounted() {
-                    match field_aliases.and_then(|map| map.get(&(i as u64))) {
-                        Some(alias_symbol) => {
-                            // the field was bound in a pattern match
-                            result.push(*alias_symbol);
-                        }
-                        None if at_least_one_aliased => {
-                            // the field was not bound in a pattern match
-                            // we have to extract it now, but we only extract it
-                            // if at least one field is aliased.
-
-                            todo!("get the tag id");
-                            /*
-                            let expr = Expr::AccessAtIndex {
-                                index: i as u64,
-                                field_layouts: constructor_layout,
-                                structure: *symbol,
-                                wrapped: todo!("get the tag id"),
-                            };
-
-                            // create a fresh symbol for this field
-                            let alias_symbol = Env::manual_unique_symbol(env.home, env.ident_ids);
-
-                            let layout = if let Layout::RecursivePointer = field_layout {
-                                *full_layout
-                            } else {
-                                *field_layout
-                            };
-
-                            env.deferred.assignments.push((alias_symbol, expr, layout));
-                            result.push(alias_symbol);
-                            */
-                        }
-                        None => {
-                            // if all refcounted fields were unaliased, generate a normal decrement
-                            // of the whole structure (less code generated this way)
-                            return ConstructorLayout::Unknown;
-                        }
-                    }
+                (info.clone(), branch)
+            };
+
+            let stmt = arena.alloc(Switch {
+                cond_symbol: *cond_symbol,
+                cond_layout: *cond_layout,
+                branches: new_branches.into_bump_slice(),
+                default_branch,
+                ret_layout: *ret_layout,
+            });
+
+            emit_incs(arena, &incs, stmt)
+        }
+        Join {
+            id,
+            parameters,
+            body,
+            remainder,
+        } => {
+            let incs = std::mem::take(pending);
+
+            let body = {
+                let mut body_context = context.clone();
+
+                for param in parameters.iter() {
+                    body_context.layouts.insert(param.symbol, param.layout);
                 }
-            }
-            ConstructorLayout::HasFields(result)
+
+                expand_and_cancel(env, &mut body_context, &mut Pending::new(), body)
+            };
+
+            let remainder = expand_and_cancel(env, context, pending, remainder);
+
+            let stmt = arena.alloc(Join {
+                id: *id,
+                parameters,
+                body,
+                remainder,
+            });
+
+            emit_incs(arena, &incs, stmt)
         }
-    }
-}
+        Expect {
+            condition,
+            region,
+            lookups,
+            layouts,
+            remainder,
+        } => {
+            let incs = std::mem::take(pending);
+            let remainder = expand_and_cancel(env, context, pending, remainder);
+
+            let stmt = arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups,
+                layouts,
+                remainder,
+            });
 
-fn can_push_inc_through(stmt: &Stmt) -> bool {
-    use Stmt::*;
+            emit_incs(arena, &incs, stmt)
+        }
+        Refcounting(modify @ ModifyRc::DecRef(_), continuation) => {
+            let incs = std::mem::take(pending);
+            let continuation = expand_and_cancel(env, context, pending, continuation);
+            let stmt = arena.alloc(Refcounting(*modify, continuation));
 
-    match stmt {
-        Let(_, expr, _, _) => {
-            // we can always delay an increment/decrement until after a field access
-            matches!(expr, Expr::StructAtIndex { .. } | Expr::Literal(_))
+            emit_incs(arena, &incs, stmt)
         }
+        Ret(_) | Jump(_, _) | RuntimeError(_) => {
+            let incs = std::mem::take(pending);
 
-        Refcounting(ModifyRc::Inc(_, _), _) => true,
-        Refcounting(ModifyRc::Dec(_), _) => true,
+            emit_incs(arena, &incs, stmt)
+        }
+    }
+}
 
-        _ => false,
+fn learn_constructor<'a>(context: &mut Context<'a>, info: &BranchInfo<'a>) {
+    if let BranchInfo::Constructor {
+        scrutinee, tag_id, ..
+    } = info
+    {
+        context.constructors.insert(*scrutinee, *tag_id);
     }
 }
 
-#[derive(Debug)]
-enum ConstructorLayout<T> {
-    IsNull,
-    HasFields(T),
-    Unknown,
+/// How to decrement the fields of a value whose constructor we know
+enum FieldDec<'a> {
+    /// The field was bound to this symbol
+    Bound(Symbol),
+    /// The field was never bound; we load it into a fresh symbol first
+    Load(Symbol, Expr<'a>, Layout<'a>),
 }
 
-pub fn expand_and_cancel_proc<'a>(
+fn expand_dec<'a>(
     env: &mut Env<'a, '_>,
-    stmt: &'a Stmt<'a>,
-    arguments: &'a [(Layout<'a>, Symbol)],
+    context: &mut Context<'a>,
+    pending: &mut Pending,
+    symbol: Symbol,
+    continuation: &'a Stmt<'a>,
 ) -> &'a Stmt<'a> {
-    let mut introduced = Vec::new_in(env.arena);
+    // `inc x; dec x` cancels out
+    if cancel_pending(pending, symbol) {
+        return expand_and_cancel(env, context, pending, continuation);
+    }
 
-    for (layout, symbol) in arguments {
-        if let Layout::Struct(fields) = layout {
-            env.insert_struct_info(*symbol, fields);
+    let layout = context.layouts.get(&symbol).copied();
+    let constructor = context.constructors.get(&symbol).copied();
+
+    match (layout, constructor) {
+        (Some(Layout::Struct { field_layouts, .. }), _) => {
+            let fields: std::vec::Vec<_> = field_layouts
+                .iter()
+                .enumerate()
+                .map(|(i, field_layout)| {
+                    let access = Expr::StructAtIndex {
+                        index: i as u64,
+                        field_layouts,
+                        structure: symbol,
+                    };
+
+                    (*field_layout, access)
+                })
+                .collect();
+
+            // expanding a struct only pays off when an increment cancels
+            if has_pending_field(context, pending, symbol, fields.len()) {
+                expand_stack_dec(env, context, pending, symbol, fields, continuation)
+            } else {
+                keep_dec(env, context, pending, symbol, continuation)
+            }
+        }
+        (Some(Layout::Union(union_layout @ UnionLayout::NonRecursive(tags))), Some(tag_id)) => {
+            let fields = tags[tag_id as usize]
+                .iter()
+                .enumerate()
+                .map(|(i, field_layout)| {
+                    let access = Expr::UnionAtIndex {
+                        structure: symbol,
+                        tag_id,
+                        union_layout,
+                        index: i as u64,
+                    };
+
+                    (*field_layout, access)
+                })
+                .collect();
+
+            expand_stack_dec(env, context, pending, symbol, fields, continuation)
+        }
+        (Some(Layout::Union(union_layout)), Some(tag_id)) => {
+            if union_layout.tag_is_null(tag_id) {
+                // the null constructor is not heap-allocated, so there is nothing to decrement
+                return expand_and_cancel(env, context, pending, continuation);
+            }
 
-            introduced.push(*symbol);
+            let fields: std::vec::Vec<_> = (0..tag_field_count(&union_layout, tag_id))
+                .map(|i| {
+                    let access = Expr::UnionAtIndex {
+                        structure: symbol,
+                        tag_id,
+                        union_layout,
+                        index: i as u64,
+                    };
+
+                    (union_layout.layout_at(tag_id, i), access)
+                })
+                .collect();
+
+            if has_pending_field(context, pending, symbol, fields.len()) {
+                expand_heap_dec(env, context, pending, symbol, fields, continuation)
+            } else {
+                keep_dec(env, context, pending, symbol, continuation)
+            }
         }
+        _ => keep_dec(env, context, pending, symbol, continuation),
     }
+}
 
-    let result = expand_and_cancel(env, stmt);
+fn keep_dec<'a>(
+    env: &mut Env<'a, '_>,
+    context: &mut Context<'a>,
+    pending: &mut Pending,
+    symbol: Symbol,
+    continuation: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    let arena = env.arena;
 
-    for symbol in introduced {
-        env.remove_struct_info(symbol);
-    }
+    let incs = std::mem::take(pending);
+    let continuation = expand_and_cancel(env, context, pending, continuation);
+    let stmt = arena.alloc(Stmt::Refcounting(ModifyRc::Dec(symbol), continuation));
 
-    result
+    emit_incs(arena, &incs, stmt)
 }
 
-fn expand_and_cancel<'a>(env: &mut Env<'a, '_>, stmt: &'a Stmt<'a>) -> &'a Stmt<'a> {
-    use Stmt::*;
-
-    let mut deferred = Deferred {
-        inc_dec_map: Default::default(),
-        assignments: Vec::new_in(env.arena),
-        decrefs: Vec::new_in(env.arena),
-    };
-
-    if !can_push_inc_through(stmt) {
-        std::mem::swap(&mut deferred, &mut env.deferred);
+fn tag_field_count(union_layout: &UnionLayout, tag_id: TagIdIntType) -> usize {
+    match union_layout {
+        UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => {
+            tags[tag_id as usize].len()
+        }
+        UnionLayout::NonNullableUnwrapped(fields) => fields.len(),
+        UnionLayout::NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => {
+            let index = if tag_id < *nullable_id {
+                tag_id
+            } else {
+                tag_id - 1
+            };
+
+            other_tags[index as usize].len()
+        }
+        UnionLayout::NullableUnwrapped { other_fields, .. } => other_fields.len(),
     }
+}
 
-    let mut result = {
-        match stmt {
-            Let(mut symbol, expr, layout, cont) => {
-                env.layout_map.insert(symbol, *layout);
-
-                let mut expr = expr;
-                let mut layout = layout;
-                let mut cont = cont;
-
-                // prevent long chains of `Let`s from blowing the stack
-                let mut literal_stack = Vec::new_in(env.arena);
-
-                while !matches!(
-                    &expr,
-                    Expr::StructAtIndex { .. } | Expr::Struct(_) | Expr::Call(_)
-                ) {
-                    if let Stmt::Let(symbol1, expr1, layout1, cont1) = cont {
-                        literal_stack.push((symbol, expr.clone(), *layout));
-
-                        symbol = *symbol1;
-                        expr = expr1;
-                        layout = layout1;
-                        cont = cont1;
-                    } else {
-                        break;
-                    }
-                }
-
-                let new_cont;
-
-                match &expr {
-                    Expr::StructAtIndex {
-                        structure,
-                        index,
-                        field_layouts,
-                    } => {
-                        let entry = env
-                            .alias_map
-                            .entry(*structure)
-                            .or_insert_with(MutMap::default);
-
-                        entry.insert(*index, symbol);
-
-                        env.layout_map
-                            .insert(*structure, Layout::Struct(field_layouts));
-
-                        // if the field is a struct, we know its constructor too!
-                        let field_layout = &field_layouts[*index as usize];
-                        env.try_insert_struct_info(symbol, field_layout);
-
-                        new_cont = expand_and_cancel(env, cont);
-
-                        env.remove_struct_info(symbol);
-
-                        // make sure to remove the alias, so other branches don't use it by accident
-                        env.alias_map
-                            .get_mut(structure)
-                            .and_then(|map| map.remove(index));
-                    }
-                    Expr::Struct(_) => {
-                        if let Layout::Struct(fields) = layout {
-                            env.insert_struct_info(symbol, fields);
-
-                            new_cont = expand_and_cancel(env, cont);
-
-                            env.remove_struct_info(symbol);
-                        } else {
-                            new_cont = expand_and_cancel(env, cont);
-                        }
-                    }
-                    Expr::Call(_) => {
-                        if let Layout::Struct(fields) = layout {
-                            env.insert_struct_info(symbol, fields);
-
-                            new_cont = expand_and_cancel(env, cont);
-
-                            env.remove_struct_info(symbol);
-                        } else {
-                            new_cont = expand_and_cancel(env, cont);
-                        }
-                    }
-                    _ => {
-                        new_cont = expand_and_cancel(env, cont);
-                    }
-                }
+fn has_pending_field(
+    context: &Context,
+    pending: &Pending,
+    structure: Symbol,
+    field_count: usize,
+) -> bool {
+    (0..field_count).any(|i| match context.field_alias(structure, i as u64) {
+        Some(alias) => pending.iter().any(|(s, _)| *s == alias),
+        None => false,
+    })
+}
 
-                let stmt = Let(symbol, expr.clone(), *layout, new_cont);
-                let mut stmt = &*env.arena.alloc(stmt);
+/// Determine how to decrement each refcounted field of `structure`. Fields with a pending
+/// increment are left out, and that increment is cancelled.
+fn field_decs<'a>(
+    env: &mut Env<'a, '_>,
+    context: &Context<'a>,
+    pending: &mut Pending,
+    structure: Symbol,
+    fields: std::vec::Vec<(Layout<'a>, Expr<'a>)>,
+) -> std::vec::Vec<FieldDec<'a>> {
+    let mut decs = std::vec::Vec::new();
+
+    for (i, (field_layout, access)) in fields.into_iter().enumerate() {
+        if !field_layout.contains_refcounted() {
+            continue;
+        }
 
-                for (symbol, expr, layout) in literal_stack.into_iter().rev() {
-                    stmt = env.arena.alloc(Stmt::Let(symbol, expr, layout, stmt));
+        match context.field_alias(structure, i as u64) {
+            Some(alias) => {
+                if !cancel_pending(pending, alias) {
+                    decs.push(FieldDec::Bound(alias));
                 }
-
-                stmt
             }
+            None => {
+                let field_symbol = env.unique_symbol();
+                decs.push(FieldDec::Load(field_symbol, access, field_layout));
+            }
+        }
+    }
 
-            Switch {
-                cond_symbol,
-                cond_layout,
-                ret_layout,
-                branches,
-                default_branch,
-            } => {
-                let mut new_branches = Vec::with_capacity_in(branches.len(), env.arena);
+    decs
+}
 
-                for (id, info, branch) in branches.iter() {
-                    env.insert_branch_info(info);
+fn emit_field_decs<'a>(
+    arena: &'a Bump,
+    decs: std::vec::Vec<FieldDec<'a>>,
+    mut stmt: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    for dec in decs.into_iter().rev() {
+        stmt = match dec {
+            FieldDec::Bound(symbol) => arena.alloc(Stmt::Refcounting(ModifyRc::Dec(symbol), stmt)),
+            FieldDec::Load(symbol, access, layout) => {
+                let dec = arena.alloc(Stmt::Refcounting(ModifyRc::Dec(symbol), stmt));
+                arena.alloc(Stmt::Let(symbol, access, layout, dec))
+            }
+        };
+    }
 
-                    let branch = expand_and_cancel(env, branch);
+    stmt
+}
 
-                    env.remove_branch_info(info);
+/// A struct or non-recursive union has no refcount of its own: decrementing it means
+/// decrementing its fields.
+fn expand_stack_dec<'a>(
+    env: &mut Env<'a, '_>,
+    context: &mut Context<'a>,
+    pending: &mut Pending,
+    symbol: Symbol,
+    fields: std::vec::Vec<(Layout<'a>, Expr<'a>)>,
+    continuation: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    let decs = field_decs(env, context, pending, symbol, fields);
 
-                    env.constructor_map.remove(cond_symbol);
+    if decs.is_empty() {
+        // nothing needs to happen, so we can keep pushing the remaining increments down
+        return expand_and_cancel(env, context, pending, continuation);
+    }
 
-                    new_branches.push((*id, info.clone(), branch.clone()));
-                }
+    let incs = std::mem::take(pending);
+    let continuation = expand_and_cancel(env, context, pending, continuation);
+    let stmt = emit_field_decs(env.arena, decs, continuation);
 
-                env.insert_branch_info(&default_branch.0);
-                let new_default = (
-                    default_branch.0.clone(),
-                    expand_and_cancel(env, default_branch.1),
-                );
-                env.remove_branch_info(&default_branch.0);
-
-                let stmt = Switch {
-                    cond_symbol: *cond_symbol,
-                    cond_layout: *cond_layout,
-                    ret_layout: *ret_layout,
-                    branches: new_branches.into_bump_slice(),
-                    default_branch: new_default,
-                };
-
-                &*env.arena.alloc(stmt)
-            }
-            Refcounting(ModifyRc::DecRef(symbol), cont) => {
-                // decref the current cell
-                env.deferred.decrefs.push(*symbol);
+    emit_incs(env.arena, &incs, stmt)
+}
 
-                expand_and_cancel(env, cont)
-            }
+/// A heap-allocated union is only freed if it is unique. In that case the increments of its
+/// fields cancel out; otherwise they must still happen.
+fn expand_heap_dec<'a>(
+    env: &mut Env<'a, '_>,
+    context: &mut Context<'a>,
+    pending: &mut Pending,
+    symbol: Symbol,
+    fields: std::vec::Vec<(Layout<'a>, Expr<'a>)>,
+    continuation: &'a Stmt<'a>,
+) -> &'a Stmt<'a> {
+    let arena = env.arena;
 
-            Refcounting(ModifyRc::Dec(symbol), cont) => {
-                use ConstructorLayout::*;
-
-                match work_for_constructor(env, symbol) {
-                    HasFields(dec_symbols) => {
-                        // we can inline the decrement
-
-                        // decref the current cell
-                        env.deferred.decrefs.push(*symbol);
-
-                        // and record decrements for all the fields
-                        for dec_symbol in dec_symbols {
-                            let count = env.deferred.inc_dec_map.entry(dec_symbol).or_insert(0);
-                            *count -= 1;
-                        }
-                    }
-                    Unknown => {
-                        // we can't inline the decrement; just record it
-                        let count = env.deferred.inc_dec_map.entry(*symbol).or_insert(0);
-                        *count -= 1;
-                    }
-                    IsNull => {
-                        // we decrement a value represented as `NULL` at runtime;
-                        // we can drop this decrement completely
-                    }
-                }
+    // split off the increments of the fields of `symbol`; those are the ones that may cancel
+    let field_aliases: std::vec::Vec<_> = (0..fields.len())
+        .filter_map(|i| context.field_alias(symbol, i as u64))
+        .collect();
 
-                expand_and_cancel(env, cont)
-            }
+    let (mut field_incs, other_incs): (Pending, Pending) = std::mem::take(pending)
+        .into_iter()
+        .partition(|(s, _)| field_aliases.contains(s));
 
-            Refcounting(ModifyRc::Inc(symbol, inc_amount), cont) => {
-                let count = env.deferred.inc_dec_map.entry(*symbol).or_insert(0);
-                *count += *inc_amount as i64;
+    let jp_id = env.join_point_id();
+    let jump = arena.alloc(Stmt::Jump(jp_id, &[]));
+    let decref = arena.alloc(Stmt::Refcounting(ModifyRc::DecRef(symbol), jump));
 
-                expand_and_cancel(env, cont)
-            }
+    let else_branch = emit_incs(arena, &field_incs, decref);
 
-            Join {
-                id,
-                parameters,
-                body: continuation,
-                remainder,
-            } => {
-                let continuation = expand_and_cancel(env, continuation);
-                let remainder = expand_and_cancel(env, remainder);
-
-                let stmt = Join {
-                    id: *id,
-                    parameters,
-                    body: continuation,
-                    remainder,
-                };
-
-                env.arena.alloc(stmt)
-            }
+    let then_branch = {
+        let decs = field_decs(env, context, &mut field_incs, symbol, fields);
+        let stmt = emit_field_decs(arena, decs, decref);
 
-            Ret(_) | Jump(_, _) | RuntimeError(_) => stmt,
-        }
+        emit_incs(arena, &field_incs, stmt)
     };
 
-    for symbol in deferred.decrefs {
-        let stmt = Refcounting(ModifyRc::DecRef(symbol), result);
-        result = env.arena.alloc(stmt);
-    }
-
-    // do all decrements
-    for (symbol, amount) in deferred.inc_dec_map.iter().rev() {
-        use std::cmp::Ordering;
-        match amount.cmp(&0) {
-            Ordering::Equal => {
-                // do nothing else
-            }
-            Ordering::Greater => {
-                // do nothing yet
-            }
-            Ordering::Less => {
-                // the RC insertion should not double decrement in a block
-                debug_assert_eq!(*amount, -1);
-
-                // insert missing decrements
-                let stmt = Refcounting(ModifyRc::Dec(*symbol), result);
-                result = env.arena.alloc(stmt);
-            }
-        }
-    }
-
-    for (symbol, amount) in deferred.inc_dec_map.into_iter().rev() {
-        use std::cmp::Ordering;
-        match amount.cmp(&0) {
-            Ordering::Equal => {
-                // do nothing else
-            }
-            Ordering::Greater => {
-                // insert missing increments
-                let stmt = Refcounting(ModifyRc::Inc(symbol, amount as u64), result);
-                result = env.arena.alloc(stmt);
-            }
-            Ordering::Less => {
-                // already done
-            }
-        }
-    }
+    let is_unique = env.unique_symbol();
+
+    let switch = Stmt::if_then_else(
+        arena,
+        is_unique,
+        env.ret_layout,
+        then_branch.clone(),
+        else_branch,
+    );
+
+    let body = expand_and_cancel(env, context, pending, continuation);
+
+    let join = arena.alloc(Stmt::Join {
+        id: jp_id,
+        parameters: &[],
+        body,
+        remainder: arena.alloc(switch),
+    });
+
+    let call = Call {
+        call_type: CallType::LowLevel {
+            op: LowLevel::RefCountIsUnique,
+            update_mode: env.update_mode_ids.next_id(),
+        },
+        arguments: arena.alloc([symbol]),
+    };
 
-    for (symbol, expr, layout) in deferred.assignments {
-        let stmt = Stmt::Let(symbol, expr, layout, result);
-        result = env.arena.alloc(stmt);
-    }
+    let stmt = arena.alloc(Stmt::Let(is_unique, Expr::Call(call), Layout::bool(), join));
 
-    result
+    emit_incs(arena, &other_incs, stmt)
 }
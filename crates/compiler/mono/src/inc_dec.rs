@@ -193,7 +193,7 @@ pub fn occurring_variables_expr(expr: &Expr<'_>, result: &mut MutSet<Symbol>) {
             result.insert(*symbol);
         }
 
-        EmptyArray | RuntimeErrorFunction(_) | Literal(_) => {}
+        EmptyArray | NullPointer | Alloca { .. } | RuntimeErrorFunction(_) | Literal(_) => {}
 
         GetTagId {
             structure: symbol, ..
@@ -201,6 +201,12 @@ pub fn occurring_variables_expr(expr: &Expr<'_>, result: &mut MutSet<Symbol>) {
             result.insert(*symbol);
         }
 
+        UnionFieldPtrAtIndex {
+            structure: symbol, ..
+        } => {
+            result.insert(*symbol);
+        }
+
         UnionAtIndex {
             structure: symbol, ..
         } => {
@@ -858,7 +864,9 @@ impl<'a> Context<'a> {
             StructAtIndex { structure: x, .. } => {
                 let b = self.add_dec_if_needed(x, b, b_live_vars);
                 let info_x = self.get_var_info(x);
-                let b = if info_x.consume {
+                // a projection that is never used must not be incremented, because nothing
+                // would decrement it again
+                let b = if info_x.consume && b_live_vars.contains(&z) {
                     self.add_inc(z, 1, b)
                 } else {
                     b
@@ -870,7 +878,7 @@ impl<'a> Context<'a> {
             GetTagId { structure: x, .. } => {
                 let b = self.add_dec_if_needed(x, b, b_live_vars);
                 let info_x = self.get_var_info(x);
-                let b = if info_x.consume {
+                let b = if info_x.consume && b_live_vars.contains(&z) {
                     self.add_inc(z, 1, b)
                 } else {
                     b
@@ -882,7 +890,7 @@ impl<'a> Context<'a> {
             UnionAtIndex { structure: x, .. } => {
                 let b = self.add_dec_if_needed(x, b, b_live_vars);
                 let info_x = self.get_var_info(x);
-                let b = if info_x.consume {
+                let b = if info_x.consume && b_live_vars.contains(&z) {
                     self.add_inc(z, 1, b)
                 } else {
                     b
@@ -891,6 +899,13 @@ impl<'a> Context<'a> {
                 self.arena.alloc(Stmt::Let(z, v, l, b))
             }
 
+            UnionFieldPtrAtIndex { structure: x, .. } => {
+                // the address is not refcounted, so the structure is only borrowed
+                let b = self.add_dec_if_needed(x, b, b_live_vars);
+
+                self.arena.alloc(Stmt::Let(z, v, l, b))
+            }
+
             ExprBox { symbol: x } => {
                 // mimics Tag
                 self.add_inc_before_consume_all(
@@ -904,7 +919,7 @@ impl<'a> Context<'a> {
                 // mimics UnionAtIndex
                 let b = self.add_dec_if_needed(x, b, b_live_vars);
                 let info_x = self.get_var_info(x);
-                let b = if info_x.consume {
+                let b = if info_x.consume && b_live_vars.contains(&z) {
                     self.add_inc(z, 1, b)
                 } else {
                     b
@@ -913,7 +928,12 @@ impl<'a> Context<'a> {
                 self.arena.alloc(Stmt::Let(z, v, l, b))
             }
 
-            EmptyArray | Literal(_) | Reset { .. } | RuntimeErrorFunction(_) => {
+            EmptyArray
+            | NullPointer
+            | Alloca { .. }
+            | Literal(_)
+            | Reset { .. }
+            | RuntimeErrorFunction(_) => {
                 // EmptyArray is always stack-allocated
                 // function pointers are persistent
                 self.arena.alloc(Stmt::Let(z, v, l, b))
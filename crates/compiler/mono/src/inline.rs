@@ -0,0 +1,902 @@
+//! Inlining of small procedures, and constant folding.
+//!
+//! The LLVM backend gets both of these from LLVM's optimizer, but the dev and wasm backends
+//! translate the mono IR more or less directly. This pass inlines calls to small procedures that
+//! do not call other procedures themselves (typically builtins that wrap a lowlevel), evaluates
+//! lowlevel calls whose arguments are all literals, and picks the branch of a switch on a literal.
+//!
+//! It runs before reset/reuse and refcount insertion, so there are no refcounting operations to
+//! keep consistent.
+
+use crate::inc_dec::occurring_variables_expr;
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, JoinPointId, ListLiteralElement, Literal, Param, Proc,
+    ProcLayout, SelfRecursive, Stmt, UpdateModeIds,
+};
+use crate::layout::{Builtin, Layout};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_collections::all::{MutMap, MutSet};
+use roc_error_macros::internal_error;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use std::cmp::Ordering;
+
+/// Procedures with at most this many statements are inlined
+const INLINE_THRESHOLD: usize = 8;
+
+/// Inlining can make a procedure small enough to be inlined itself, so we go over all procedures
+/// a couple of times. Every round only inlines procedures that do not call anything.
+const MAX_ROUNDS: usize = 3;
+
+pub fn inline_and_fold<'a, 'i>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    for _ in 0..MAX_ROUNDS {
+        let inlinable: MutMap<_, _> = procs
+            .iter()
+            .filter(|(_, proc)| is_inlinable(arena, proc))
+            .map(|(key, proc)| (*key, proc.clone()))
+            .collect();
+
+        let mut env = Env {
+            arena,
+            home,
+            ident_ids,
+            update_mode_ids,
+            inlinable: &inlinable,
+            constants: MutMap::default(),
+            inlined_any: false,
+        };
+
+        for proc in procs.values_mut() {
+            env.constants.clear();
+
+            let body = optimize_stmt(&mut env, &proc.body);
+            proc.body = remove_unused_literals(arena, &body, &mut MutSet::default());
+        }
+
+        if !env.inlined_any {
+            break;
+        }
+    }
+}
+
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    update_mode_ids: &'i mut UpdateModeIds,
+    inlinable: &'i MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    /// The symbols in the current procedure that are bound to a literal
+    constants: MutMap<Symbol, (Literal<'a>, Layout<'a>)>,
+    inlined_any: bool,
+}
+
+impl<'a, 'i> Env<'a, 'i> {
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
+    }
+}
+
+fn is_inlinable<'a>(arena: &'a Bump, proc: &Proc<'a>) -> bool {
+    let mut budget = INLINE_THRESHOLD;
+    let mut layouts = proc
+        .args
+        .iter()
+        .map(|(layout, symbol)| (*symbol, *layout))
+        .collect();
+
+    matches!(proc.is_self_recursive, SelfRecursive::NotSelfRecursive)
+        && is_small_leaf(arena, &proc.body, &mut budget, &mut layouts)
+}
+
+/// Is this statement small, and free of calls to other procedures? Expects are not inlined
+/// either, because their module is found from the symbol of their condition.
+///
+/// Refcount insertion never increments the arguments of a lowlevel, so a lowlevel that owns a
+/// refcounted argument can only consume a parameter of the procedure it is in. Inlining would
+/// make it consume a value of the caller.
+fn is_small_leaf<'a>(
+    arena: &'a Bump,
+    stmt: &Stmt<'a>,
+    budget: &mut usize,
+    layouts: &mut MutMap<Symbol, Layout<'a>>,
+) -> bool {
+    if *budget == 0 {
+        return false;
+    }
+
+    *budget -= 1;
+
+    match stmt {
+        Stmt::Let(symbol, expr, layout, cont) => {
+            let is_leaf = match expr {
+                Expr::Call(Call {
+                    call_type: CallType::LowLevel { op, .. },
+                    arguments,
+                }) => {
+                    let signature = crate::borrow::lowlevel_borrow_signature(arena, *op);
+
+                    signature
+                        .iter()
+                        .zip(arguments.iter())
+                        .all(|(borrowed, argument)| {
+                            let refcounted = layouts.get(argument).map(Layout::contains_refcounted);
+
+                            *borrowed || refcounted == Some(false)
+                        })
+                }
+                Expr::Call(Call {
+                    call_type: CallType::ByName { .. } | CallType::HigherOrder(_),
+                    ..
+                }) => false,
+                Expr::Reset { .. } | Expr::Reuse { .. } => false,
+                _ => true,
+            };
+
+            layouts.insert(*symbol, *layout);
+
+            is_leaf && is_small_leaf(arena, cont, budget, layouts)
+        }
+        Stmt::Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            branches
+                .iter()
+                .all(|(_, _, branch)| is_small_leaf(arena, branch, budget, layouts))
+                && is_small_leaf(arena, default_branch.1, budget, layouts)
+        }
+        Stmt::Join {
+            parameters,
+            body,
+            remainder,
+            ..
+        } => {
+            for param in parameters.iter() {
+                layouts.insert(param.symbol, param.layout);
+            }
+
+            is_small_leaf(arena, body, budget, layouts)
+                && is_small_leaf(arena, remainder, budget, layouts)
+        }
+        Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::RuntimeError(_) => true,
+        Stmt::Expect { .. } | Stmt::Refcounting(_, _) => false,
+    }
+}
+
+fn optimize_stmt<'a>(env: &mut Env<'a, '_>, stmt: &Stmt<'a>) -> Stmt<'a> {
+    let arena = env.arena;
+
+    match stmt {
+        Stmt::Let(symbol, expr, layout, cont) => {
+            if let Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                arguments,
+            }) = expr
+            {
+                let proc_layout =
+                    ProcLayout::new(arena, arg_layouts, name.captures_niche(), **ret_layout);
+
+                if let Some(proc) = env.inlinable.get(&(name.name(), proc_layout)) {
+                    env.inlined_any = true;
+
+                    let inlined = inline_call(env, proc, arguments, *symbol, *layout, cont);
+
+                    return optimize_stmt(env, &inlined);
+                }
+            }
+
+            let expr = match expr {
+                Expr::Literal(literal) => {
+                    env.constants.insert(*symbol, (*literal, *layout));
+
+                    expr.clone()
+                }
+                Expr::Call(Call {
+                    call_type: CallType::LowLevel { op, .. },
+                    arguments,
+                }) => match fold_low_level(env, *op, arguments, layout) {
+                    Some(literal) => {
+                        env.constants.insert(*symbol, (literal, *layout));
+
+                        Expr::Literal(literal)
+                    }
+                    None => expr.clone(),
+                },
+                _ => expr.clone(),
+            };
+
+            let cont = optimize_stmt(env, cont);
+
+            Stmt::Let(*symbol, expr, *layout, arena.alloc(cont))
+        }
+        Stmt::Switch {
+            cond_symbol,
+            cond_layout,
+            branches,
+            default_branch,
+            ret_layout,
+        } => {
+            let known = env
+                .constants
+                .get(cond_symbol)
+                .and_then(|(literal, _)| switch_value(literal));
+
+            if let Some(value) = known {
+                let taken = match branches.iter().find(|(id, _, _)| *id == value) {
+                    Some((_, _, branch)) => branch,
+                    None => default_branch.1,
+                };
+
+                return optimize_stmt(env, taken);
+            }
+
+            let mut new_branches = Vec::with_capacity_in(branches.len(), arena);
+
+            for (id, info, branch) in branches.iter() {
+                new_branches.push((*id, info.clone(), optimize_stmt(env, branch)));
+            }
+
+            let default = optimize_stmt(env, default_branch.1);
+
+            Stmt::Switch {
+                cond_symbol: *cond_symbol,
+                cond_layout: *cond_layout,
+                branches: new_branches.into_bump_slice(),
+                default_branch: (default_branch.0.clone(), arena.alloc(default)),
+                ret_layout: *ret_layout,
+            }
+        }
+        Stmt::Refcounting(modify, cont) => {
+            Stmt::Refcounting(*modify, arena.alloc(optimize_stmt(env, cont)))
+        }
+        Stmt::Expect {
+            condition,
+            region,
+            lookups,
+            layouts,
+            remainder,
+        } => Stmt::Expect {
+            condition: *condition,
+            region: *region,
+            lookups,
+            layouts,
+            remainder: arena.alloc(optimize_stmt(env, remainder)),
+        },
+        Stmt::Join {
+            id,
+            parameters,
+            body,
+            remainder,
+        } => {
+            let body = optimize_stmt(env, body);
+            let remainder = optimize_stmt(env, remainder);
+
+            Stmt::Join {
+                id: *id,
+                parameters,
+                body: arena.alloc(body),
+                remainder: arena.alloc(remainder),
+            }
+        }
+        Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::RuntimeError(_) => stmt.clone(),
+    }
+}
+
+/// Replace `let result = proc arguments in cont` with the body of `proc`. All symbols that the
+/// body binds are renamed, so the same procedure can be inlined several times.
+///
+/// If the body returns in just one place, and not one of its arguments, the returned symbol is
+/// renamed to `result` and `cont` goes where the `ret` was. Otherwise every `ret` becomes a jump
+/// to a new join point, with `cont` as its body.
+fn inline_call<'a>(
+    env: &mut Env<'a, '_>,
+    proc: &Proc<'a>,
+    arguments: &[Symbol],
+    result: Symbol,
+    layout: Layout<'a>,
+    cont: &'a Stmt<'a>,
+) -> Stmt<'a> {
+    let arena = env.arena;
+    let mut renames = MutMap::default();
+
+    for ((_, param), argument) in proc.args.iter().zip(arguments) {
+        renames.insert(*param, *argument);
+    }
+
+    let mut returned = None;
+    let returns = count_returns(&proc.body, &mut returned);
+
+    match returned {
+        Some(returned) if returns == 1 && !renames.contains_key(&returned) => {
+            renames.insert(returned, result);
+
+            rename_stmt(env, &mut renames, &proc.body, &mut |_| cont.clone())
+        }
+        _ => {
+            let id = JoinPointId(env.unique_symbol());
+
+            let body = rename_stmt(env, &mut renames, &proc.body, &mut |returned| {
+                Stmt::Jump(id, arena.alloc([returned]))
+            });
+
+            let param = Param {
+                symbol: result,
+                borrow: false,
+                layout,
+            };
+
+            Stmt::Join {
+                id,
+                parameters: arena.alloc([param]),
+                body: cont,
+                remainder: arena.alloc(body),
+            }
+        }
+    }
+}
+
+fn count_returns(stmt: &Stmt, returned: &mut Option<Symbol>) -> usize {
+    match stmt {
+        Stmt::Let(_, _, _, cont) | Stmt::Refcounting(_, cont) => count_returns(cont, returned),
+        Stmt::Expect { remainder, .. } => count_returns(remainder, returned),
+        Stmt::Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            let in_branches: usize = branches
+                .iter()
+                .map(|(_, _, branch)| count_returns(branch, returned))
+                .sum();
+
+            in_branches + count_returns(default_branch.1, returned)
+        }
+        Stmt::Join {
+            body, remainder, ..
+        } => count_returns(body, returned) + count_returns(remainder, returned),
+        Stmt::Ret(symbol) => {
+            *returned = Some(*symbol);
+
+            1
+        }
+        Stmt::Jump(_, _) | Stmt::RuntimeError(_) => 0,
+    }
+}
+
+fn rename(renames: &MutMap<Symbol, Symbol>, symbol: Symbol) -> Symbol {
+    renames.get(&symbol).copied().unwrap_or(symbol)
+}
+
+fn rename_all<'a>(
+    arena: &'a Bump,
+    renames: &MutMap<Symbol, Symbol>,
+    symbols: &[Symbol],
+) -> &'a [Symbol] {
+    Vec::from_iter_in(symbols.iter().map(|s| rename(renames, *s)), arena).into_bump_slice()
+}
+
+/// A fresh symbol for a symbol that the inlined body binds
+fn rename_binder(env: &mut Env, renames: &mut MutMap<Symbol, Symbol>, symbol: Symbol) -> Symbol {
+    match renames.get(&symbol) {
+        Some(renamed) => *renamed,
+        None => {
+            let renamed = env.unique_symbol();
+            renames.insert(symbol, renamed);
+
+            renamed
+        }
+    }
+}
+
+fn rename_stmt<'a>(
+    env: &mut Env<'a, '_>,
+    renames: &mut MutMap<Symbol, Symbol>,
+    stmt: &Stmt<'a>,
+    on_return: &mut dyn FnMut(Symbol) -> Stmt<'a>,
+) -> Stmt<'a> {
+    let arena = env.arena;
+
+    match stmt {
+        Stmt::Let(symbol, expr, layout, cont) => {
+            let expr = rename_expr(env, renames, expr);
+            let symbol = rename_binder(env, renames, *symbol);
+            let cont = rename_stmt(env, renames, cont, on_return);
+
+            Stmt::Let(symbol, expr, *layout, arena.alloc(cont))
+        }
+        Stmt::Switch {
+            cond_symbol,
+            cond_layout,
+            branches,
+            default_branch,
+            ret_layout,
+        } => {
+            let rename_info = |info: &BranchInfo<'a>, renames: &MutMap<Symbol, Symbol>| match info {
+                BranchInfo::None => BranchInfo::None,
+                BranchInfo::Constructor {
+                    scrutinee,
+                    layout,
+                    tag_id,
+                } => BranchInfo::Constructor {
+                    scrutinee: rename(renames, *scrutinee),
+                    layout: *layout,
+                    tag_id: *tag_id,
+                },
+            };
+
+            let mut new_branches = Vec::with_capacity_in(branches.len(), arena);
+
+            for (id, info, branch) in branches.iter() {
+                let info = rename_info(info, renames);
+                let branch = rename_stmt(env, renames, branch, on_return);
+
+                new_branches.push((*id, info, branch));
+            }
+
+            let default_info = rename_info(&default_branch.0, renames);
+            let default = rename_stmt(env, renames, default_branch.1, on_return);
+
+            Stmt::Switch {
+                cond_symbol: rename(renames, *cond_symbol),
+                cond_layout: *cond_layout,
+                branches: new_branches.into_bump_slice(),
+                default_branch: (default_info, arena.alloc(default)),
+                ret_layout: *ret_layout,
+            }
+        }
+        Stmt::Ret(symbol) => on_return(rename(renames, *symbol)),
+        Stmt::Join {
+            id,
+            parameters,
+            body,
+            remainder,
+        } => {
+            let new_id = JoinPointId(rename_binder(env, renames, id.0));
+
+            let mut new_parameters = Vec::with_capacity_in(parameters.len(), arena);
+
+            for param in parameters.iter() {
+                new_parameters.push(Param {
+                    symbol: rename_binder(env, renames, param.symbol),
+                    ..*param
+                });
+            }
+
+            let body = rename_stmt(env, renames, body, on_return);
+            let remainder = rename_stmt(env, renames, remainder, on_return);
+
+            Stmt::Join {
+                id: new_id,
+                parameters: new_parameters.into_bump_slice(),
+                body: arena.alloc(body),
+                remainder: arena.alloc(remainder),
+            }
+        }
+        Stmt::Jump(id, arguments) => Stmt::Jump(
+            JoinPointId(rename(renames, id.0)),
+            rename_all(arena, renames, arguments),
+        ),
+        Stmt::RuntimeError(_) => stmt.clone(),
+        Stmt::Expect { .. } | Stmt::Refcounting(_, _) => {
+            internal_error!("expects and refcounting are never inlined")
+        }
+    }
+}
+
+fn rename_expr<'a>(
+    env: &mut Env<'a, '_>,
+    renames: &MutMap<Symbol, Symbol>,
+    expr: &Expr<'a>,
+) -> Expr<'a> {
+    let arena = env.arena;
+
+    match expr {
+        Expr::Literal(_)
+        | Expr::EmptyArray
+        | Expr::NullPointer
+        | Expr::Alloca { .. }
+        | Expr::RuntimeErrorFunction(_) => expr.clone(),
+        Expr::Call(Call {
+            call_type,
+            arguments,
+        }) => {
+            let call_type = match call_type {
+                // every call site needs its own update mode, for the alias analysis
+                CallType::LowLevel { op, .. } => CallType::LowLevel {
+                    op: *op,
+                    update_mode: env.update_mode_ids.next_id(),
+                },
+                CallType::Foreign { .. } => call_type.clone(),
+                CallType::ByName { .. } | CallType::HigherOrder(_) => {
+                    internal_error!("procedures that call other procedures are never inlined")
+                }
+            };
+
+            Expr::Call(Call {
+                call_type,
+                arguments: rename_all(arena, renames, arguments),
+            })
+        }
+        Expr::Tag {
+            tag_layout,
+            tag_id,
+            arguments,
+        } => Expr::Tag {
+            tag_layout: *tag_layout,
+            tag_id: *tag_id,
+            arguments: rename_all(arena, renames, arguments),
+        },
+        Expr::Struct(fields) => Expr::Struct(rename_all(arena, renames, fields)),
+        Expr::StructAtIndex {
+            index,
+            field_layouts,
+            structure,
+        } => Expr::StructAtIndex {
+            index: *index,
+            field_layouts,
+            structure: rename(renames, *structure),
+        },
+        Expr::GetTagId {
+            structure,
+            union_layout,
+        } => Expr::GetTagId {
+            structure: rename(renames, *structure),
+            union_layout: *union_layout,
+        },
+        Expr::UnionAtIndex {
+            structure,
+            tag_id,
+            union_layout,
+            index,
+        } => Expr::UnionAtIndex {
+            structure: rename(renames, *structure),
+            tag_id: *tag_id,
+            union_layout: *union_layout,
+            index: *index,
+        },
+        Expr::UnionFieldPtrAtIndex {
+            structure,
+            tag_id,
+            union_layout,
+            index,
+        } => Expr::UnionFieldPtrAtIndex {
+            structure: rename(renames, *structure),
+            tag_id: *tag_id,
+            union_layout: *union_layout,
+            index: *index,
+        },
+        Expr::Array { elem_layout, elems } => {
+            let elems = elems.iter().map(|elem| match elem {
+                ListLiteralElement::Literal(_) => *elem,
+                ListLiteralElement::Symbol(symbol) => {
+                    ListLiteralElement::Symbol(rename(renames, *symbol))
+                }
+            });
+
+            Expr::Array {
+                elem_layout: *elem_layout,
+                elems: Vec::from_iter_in(elems, arena).into_bump_slice(),
+            }
+        }
+        Expr::ExprBox { symbol } => Expr::ExprBox {
+            symbol: rename(renames, *symbol),
+        },
+        Expr::ExprUnbox { symbol } => Expr::ExprUnbox {
+            symbol: rename(renames, *symbol),
+        },
+        Expr::Reset { .. } | Expr::Reuse { .. } => {
+            internal_error!("reset and reuse are never inlined")
+        }
+    }
+}
+
+/// The value of a lowlevel call, if all its arguments are literals and the result can be
+/// computed at compile time. Operations that would crash at runtime, e.g. because they overflow,
+/// are left alone.
+fn fold_low_level<'a>(
+    env: &Env<'a, '_>,
+    op: LowLevel,
+    arguments: &[Symbol],
+    layout: &Layout<'a>,
+) -> Option<Literal<'a>> {
+    use LowLevel::*;
+
+    let mut args = std::vec::Vec::with_capacity(arguments.len());
+
+    for argument in arguments {
+        args.push(*env.constants.get(argument)?);
+    }
+
+    match (op, args.as_slice()) {
+        (And, [(Literal::Bool(a), _), (Literal::Bool(b), _)]) => Some(Literal::Bool(*a && *b)),
+        (Or, [(Literal::Bool(a), _), (Literal::Bool(b), _)]) => Some(Literal::Bool(*a || *b)),
+        (Not, [(Literal::Bool(a), _)]) => Some(Literal::Bool(!a)),
+        (Eq | NotEq, [(a, arg_layout), (b, _)]) => {
+            let equal = compare(a, b, arg_layout)? == Ordering::Equal;
+
+            Some(Literal::Bool(equal == (op == Eq)))
+        }
+        (NumGt | NumGte | NumLt | NumLte, [(a, arg_layout), (b, _)]) => {
+            let ordering = compare(a, b, arg_layout)?;
+
+            let result = match op {
+                NumGt => ordering == Ordering::Greater,
+                NumGte => ordering != Ordering::Less,
+                NumLt => ordering == Ordering::Less,
+                _ => ordering != Ordering::Greater,
+            };
+
+            Some(Literal::Bool(result))
+        }
+        (
+            NumAdd | NumAddWrap | NumSub | NumSubWrap | NumMul | NumMulWrap | NumBitwiseAnd
+            | NumBitwiseOr | NumBitwiseXor,
+            [(a, _), (b, _)],
+        ) => match layout {
+            Layout::Builtin(Builtin::Int(width)) => {
+                let (a, b) = (int_value(a, *width)?, int_value(b, *width)?);
+
+                let result = match op {
+                    NumAdd => a.checked_add(b).filter(|n| in_range(*n, *width))?,
+                    NumSub => a.checked_sub(b).filter(|n| in_range(*n, *width))?,
+                    NumMul => a.checked_mul(b).filter(|n| in_range(*n, *width))?,
+                    NumAddWrap => wrap(a.wrapping_add(b), *width),
+                    NumSubWrap => wrap(a.wrapping_sub(b), *width),
+                    NumMulWrap => wrap(a.wrapping_mul(b), *width),
+                    NumBitwiseAnd => a & b,
+                    NumBitwiseOr => a | b,
+                    _ => a ^ b,
+                };
+
+                Some(Literal::Int(result.to_ne_bytes()))
+            }
+            Layout::Builtin(Builtin::Float(width)) => {
+                let (a, b) = (float_value(a, *width)?, float_value(b, *width)?);
+
+                let result = match op {
+                    NumAdd => a + b,
+                    NumSub => a - b,
+                    NumMul => a * b,
+                    _ => return None,
+                };
+
+                Some(Literal::Float(round_to_width(result, *width)))
+            }
+            _ => None,
+        },
+        (NumNeg, [(a, _)]) => match layout {
+            Layout::Builtin(Builtin::Int(width)) => {
+                let negated = int_value(a, *width)?.checked_neg()?;
+
+                in_range(negated, *width).then(|| Literal::Int(negated.to_ne_bytes()))
+            }
+            Layout::Builtin(Builtin::Float(width)) => {
+                Some(Literal::Float(-float_value(a, *width)?))
+            }
+            _ => None,
+        },
+        (NumIntCast, [(a, Layout::Builtin(Builtin::Int(from)))]) => match layout {
+            Layout::Builtin(Builtin::Int(to)) => {
+                let value = int_value(a, *from)?;
+
+                Some(Literal::Int(wrap(value, *to).to_ne_bytes()))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn compare(a: &Literal, b: &Literal, layout: &Layout) -> Option<Ordering> {
+    match (a, b, layout) {
+        (Literal::Int(_), Literal::Int(_), Layout::Builtin(Builtin::Int(width))) => {
+            Some(int_value(a, *width)?.cmp(&int_value(b, *width)?))
+        }
+        (Literal::Float(_), Literal::Float(_), Layout::Builtin(Builtin::Float(width))) => {
+            float_value(a, *width)?.partial_cmp(&float_value(b, *width)?)
+        }
+        (Literal::Bool(a), Literal::Bool(b), _) => Some(a.cmp(b)),
+        (Literal::Byte(a), Literal::Byte(b), _) => Some(a.cmp(b)),
+        (Literal::Str(a), Literal::Str(b), _) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Integers of all widths but `U128` are stored as an `i128` in a `Literal::Int`.
+/// We leave `U128` alone; its values don't all fit in an `i128`.
+fn int_value(literal: &Literal, width: IntWidth) -> Option<i128> {
+    match literal {
+        Literal::Int(bytes) if width != IntWidth::U128 => Some(i128::from_ne_bytes(*bytes)),
+        _ => None,
+    }
+}
+
+fn int_bits(width: IntWidth) -> u32 {
+    width.stack_size() * 8
+}
+
+fn in_range(value: i128, width: IntWidth) -> bool {
+    let bits = int_bits(width);
+
+    match (width.is_signed(), bits) {
+        (_, 128) => true,
+        (true, _) => {
+            let max = (1i128 << (bits - 1)) - 1;
+            (-max - 1..=max).contains(&value)
+        }
+        (false, _) => (0..(1i128 << bits)).contains(&value),
+    }
+}
+
+/// Truncate to the width, like an integer that overflowed at runtime
+fn wrap(value: i128, width: IntWidth) -> i128 {
+    let bits = int_bits(width);
+
+    if bits == 128 {
+        return value;
+    }
+
+    let truncated = value & ((1i128 << bits) - 1);
+
+    if width.is_signed() && truncated >= 1i128 << (bits - 1) {
+        truncated - (1i128 << bits)
+    } else {
+        truncated
+    }
+}
+
+/// Float literals are stored as an `f64`, even for an `F32`. Values of an `F32` are rounded
+/// first, so they compare (and add up) like they do at runtime.
+fn float_value(literal: &Literal, width: FloatWidth) -> Option<f64> {
+    match literal {
+        Literal::Float(value) => match width {
+            FloatWidth::F32 => Some(round_to_width(*value, width)),
+            FloatWidth::F64 => Some(*value),
+            FloatWidth::F128 => None,
+        },
+        _ => None,
+    }
+}
+
+fn round_to_width(value: f64, width: FloatWidth) -> f64 {
+    match width {
+        FloatWidth::F32 => value as f32 as f64,
+        FloatWidth::F64 | FloatWidth::F128 => value,
+    }
+}
+
+fn switch_value(literal: &Literal) -> Option<u64> {
+    match literal {
+        Literal::Int(bytes) => u64::try_from(i128::from_ne_bytes(*bytes)).ok(),
+        Literal::Bool(value) => Some(*value as u64),
+        Literal::Byte(value) => Some(*value as u64),
+        _ => None,
+    }
+}
+
+/// Folding leaves behind literals that nothing refers to anymore
+fn remove_unused_literals<'a>(
+    arena: &'a Bump,
+    stmt: &Stmt<'a>,
+    used: &mut MutSet<Symbol>,
+) -> Stmt<'a> {
+    match stmt {
+        Stmt::Let(symbol, expr, layout, cont) => {
+            let cont = remove_unused_literals(arena, cont, used);
+
+            if matches!(expr, Expr::Literal(_)) && !used.contains(symbol) {
+                cont
+            } else {
+                occurring_variables_expr(expr, used);
+
+                Stmt::Let(*symbol, expr.clone(), *layout, arena.alloc(cont))
+            }
+        }
+        Stmt::Switch {
+            cond_symbol,
+            cond_layout,
+            branches,
+            default_branch,
+            ret_layout,
+        } => {
+            let mut new_branches = Vec::with_capacity_in(branches.len(), arena);
+
+            for (id, info, branch) in branches.iter() {
+                let branch = remove_unused_literals(arena, branch, used);
+                new_branches.push((*id, info.clone(), branch));
+            }
+
+            let default = remove_unused_literals(arena, default_branch.1, used);
+
+            for info in branches
+                .iter()
+                .map(|(_, info, _)| info)
+                .chain([&default_branch.0])
+            {
+                if let BranchInfo::Constructor { scrutinee, .. } = info {
+                    used.insert(*scrutinee);
+                }
+            }
+
+            used.insert(*cond_symbol);
+
+            Stmt::Switch {
+                cond_symbol: *cond_symbol,
+                cond_layout: *cond_layout,
+                branches: new_branches.into_bump_slice(),
+                default_branch: (default_branch.0.clone(), arena.alloc(default)),
+                ret_layout: *ret_layout,
+            }
+        }
+        Stmt::Refcounting(modify, cont) => {
+            let cont = remove_unused_literals(arena, cont, used);
+            used.insert(modify.get_symbol());
+
+            Stmt::Refcounting(*modify, arena.alloc(cont))
+        }
+        Stmt::Expect {
+            condition,
+            region,
+            lookups,
+            layouts,
+            remainder,
+        } => {
+            let remainder = remove_unused_literals(arena, remainder, used);
+
+            used.insert(*condition);
+            used.extend(lookups.iter().copied());
+
+            Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups,
+                layouts,
+                remainder: arena.alloc(remainder),
+            }
+        }
+        Stmt::Join {
+            id,
+            parameters,
+            body,
+            remainder,
+        } => {
+            let body = remove_unused_literals(arena, body, used);
+            let remainder = remove_unused_literals(arena, remainder, used);
+
+            Stmt::Join {
+                id: *id,
+                parameters,
+                body: arena.alloc(body),
+                remainder: arena.alloc(remainder),
+            }
+        }
+        Stmt::Ret(symbol) => {
+            used.insert(*symbol);
+
+            stmt.clone()
+        }
+        Stmt::Jump(_, arguments) => {
+            used.extend(arguments.iter().copied());
+
+            stmt.clone()
+        }
+        Stmt::RuntimeError(_) => stmt.clone(),
+    }
+}
@@ -15,8 +15,8 @@ use roc_collections::VecMap;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
 use roc_debug_flags::{
-    ROC_PRINT_IR_AFTER_REFCOUNT, ROC_PRINT_IR_AFTER_RESET_REUSE, ROC_PRINT_IR_AFTER_SPECIALIZATION,
-    ROC_PRINT_RUNTIME_ERROR_GEN,
+    ROC_PRINT_IR_AFTER_EXPAND_RC, ROC_PRINT_IR_AFTER_INLINING, ROC_PRINT_IR_AFTER_REFCOUNT,
+    ROC_PRINT_IR_AFTER_RESET_REUSE, ROC_PRINT_IR_AFTER_SPECIALIZATION, ROC_PRINT_RUNTIME_ERROR_GEN,
 };
 use roc_derive::SharedDerivedModule;
 use roc_error_macros::{internal_error, todo_abilities};
@@ -24,7 +24,7 @@ use roc_exhaustive::{Ctor, CtorName, RenderAs, TagId};
 use roc_late_solve::{resolve_ability_specialization, AbilitiesView, Resolved, UnificationFailed};
 use roc_module::ident::{ForeignSymbol, Lowercase, TagName};
 use roc_module::low_level::LowLevel;
-use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_module::symbol::{IdentIds, Interns, ModuleId, Symbol};
 use roc_problem::can::{RuntimeError, ShadowKind};
 use roc_region::all::{Loc, Region};
 use roc_std::RocDec;
@@ -41,12 +41,18 @@ pub fn pretty_print_ir_symbols() -> bool {
     dbg_do!(ROC_PRINT_IR_AFTER_SPECIALIZATION, {
         return true;
     });
+    dbg_do!(ROC_PRINT_IR_AFTER_INLINING, {
+        return true;
+    });
     dbg_do!(ROC_PRINT_IR_AFTER_RESET_REUSE, {
         return true;
     });
     dbg_do!(ROC_PRINT_IR_AFTER_REFCOUNT, {
         return true;
     });
+    dbg_do!(ROC_PRINT_IR_AFTER_EXPAND_RC, {
+        return true;
+    });
     false
 }
 
@@ -337,7 +343,12 @@ pub enum Parens {
 }
 
 impl<'a> Proc<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D, _parens: Parens) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        interns: Option<&'b Interns>,
+        _parens: Parens,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -346,38 +357,38 @@ impl<'a> Proc<'a> {
         let args_doc = self
             .args
             .iter()
-            .map(|(_, symbol)| symbol_to_doc(alloc, *symbol));
+            .map(|(_, symbol)| symbol_to_doc(alloc, interns, *symbol));
 
-        if pretty_print_ir_symbols() {
+        if pretty_print_ir_symbols() || interns.is_some() {
             alloc
                 .text("procedure : ")
-                .append(symbol_to_doc(alloc, self.name.name()))
+                .append(symbol_to_doc(alloc, interns, self.name.name()))
                 .append(" ")
                 .append(self.ret_layout.to_doc(alloc, Parens::NotNeeded))
                 .append(alloc.hardline())
                 .append(alloc.text("procedure = "))
-                .append(symbol_to_doc(alloc, self.name.name()))
+                .append(symbol_to_doc(alloc, interns, self.name.name()))
                 .append(" (")
                 .append(alloc.intersperse(args_doc, ", "))
                 .append("):")
                 .append(alloc.hardline())
-                .append(self.body.to_doc(alloc).indent(4))
+                .append(self.body.to_doc(alloc, interns).indent(4))
         } else {
             alloc
                 .text("procedure ")
-                .append(symbol_to_doc(alloc, self.name.name()))
+                .append(symbol_to_doc(alloc, interns, self.name.name()))
                 .append(" (")
                 .append(alloc.intersperse(args_doc, ", "))
                 .append("):")
                 .append(alloc.hardline())
-                .append(self.body.to_doc(alloc).indent(4))
+                .append(self.body.to_doc(alloc, interns).indent(4))
         }
     }
 
     pub fn to_pretty(&self, width: usize) -> String {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, ()>(&allocator, Parens::NotNeeded)
+        self.to_doc::<_, ()>(&allocator, None, Parens::NotNeeded)
             .1
             .render(width, &mut w)
             .unwrap();
@@ -391,7 +402,7 @@ impl<'a> Proc<'a> {
         ident_ids: &'i mut IdentIds,
         update_mode_ids: &'i mut UpdateModeIds,
         procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
-    ) {
+    ) -> &'a crate::borrow::ParamMap<'a> {
         let borrow_params = arena.alloc(crate::borrow::infer_borrow(arena, procs));
 
         crate::inc_dec::visit_procs(
@@ -402,6 +413,8 @@ impl<'a> Proc<'a> {
             borrow_params,
             procs,
         );
+
+        borrow_params
     }
 
     pub fn insert_reset_reuse_operations<'i>(
@@ -423,6 +436,28 @@ impl<'a> Proc<'a> {
         }
     }
 
+    pub fn expand_and_cancel_refcounts<'i>(
+        arena: &'a Bump,
+        home: ModuleId,
+        ident_ids: &'i mut IdentIds,
+        update_mode_ids: &'i mut UpdateModeIds,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        for (_, proc) in procs.iter_mut() {
+            crate::expand_rc::expand_and_cancel_proc(arena, home, ident_ids, update_mode_ids, proc);
+        }
+    }
+
+    pub fn inline_and_fold<'i>(
+        arena: &'a Bump,
+        home: ModuleId,
+        ident_ids: &'i mut IdentIds,
+        update_mode_ids: &'i mut UpdateModeIds,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::inline::inline_and_fold(arena, home, ident_ids, update_mode_ids, procs)
+    }
+
     fn make_tail_recursive(&mut self, env: &mut Env<'a, '_>) {
         let mut args = Vec::with_capacity_in(self.args.len(), env.arena);
         let mut proc_args = Vec::with_capacity_in(self.args.len(), env.arena);
@@ -435,14 +470,29 @@ impl<'a> Proc<'a> {
 
         use self::SelfRecursive::*;
         if let SelfRecursive(id) = self.is_self_recursive {
-            let transformed = crate::tail_recursion::make_tail_recursive(
+            let args = args.into_bump_slice();
+
+            let transformed = crate::tail_recursion::make_tail_recursive_modulo_cons(
                 env.arena,
+                env.home,
+                env.ident_ids,
+                env.target_info,
                 id,
                 self.name,
                 self.body.clone(),
-                args.into_bump_slice(),
+                args,
                 self.ret_layout,
-            );
+            )
+            .or_else(|| {
+                crate::tail_recursion::make_tail_recursive(
+                    env.arena,
+                    id,
+                    self.name,
+                    self.body.clone(),
+                    args,
+                    self.ret_layout,
+                )
+            });
 
             if let Some(with_tco) = transformed {
                 self.body = with_tco;
@@ -1295,6 +1345,10 @@ pub struct Env<'a, 'i> {
     pub target_info: TargetInfo,
     pub update_mode_ids: &'i mut UpdateModeIds,
     pub call_specialization_counter: u32,
+    /// Where calls are in the source, keyed by the symbol their result is assigned to. Used to
+    /// point diagnostics about a call (like the in-place update report) at the source. `None`
+    /// when nothing needs them.
+    pub call_regions: Option<&'i mut MutMap<Symbol, Region>>,
     // TODO: WorldAbilities and exposed_by_module share things, think about how to combine them
     pub abilities: AbilitiesView<'i>,
     pub exposed_by_module: &'i ExposedByModule,
@@ -1456,7 +1510,11 @@ pub enum BranchInfo<'a> {
 }
 
 impl<'a> BranchInfo<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        interns: Option<&'b Interns>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1472,7 +1530,7 @@ impl<'a> BranchInfo<'a> {
             } if pretty_print_ir_symbols() => alloc
                 .hardline()
                 .append("    BranchInfo: { scrutinee: ")
-                .append(symbol_to_doc(alloc, *scrutinee))
+                .append(symbol_to_doc(alloc, interns, *scrutinee))
                 .append(", tag_id: ")
                 .append(format!("{}", tag_id))
                 .append("} "),
@@ -1505,7 +1563,11 @@ pub enum ModifyRc {
 }
 
 impl ModifyRc {
-    pub fn to_doc<'a, D, A>(self, alloc: &'a D) -> DocBuilder<'a, D, A>
+    pub fn to_doc<'a, D, A>(
+        self,
+        alloc: &'a D,
+        interns: Option<&'a Interns>,
+    ) -> DocBuilder<'a, D, A>
     where
         D: DocAllocator<'a, A>,
         D::Doc: Clone,
@@ -1516,20 +1578,20 @@ impl ModifyRc {
         match self {
             Inc(symbol, 1) => alloc
                 .text("inc ")
-                .append(symbol_to_doc(alloc, symbol))
+                .append(symbol_to_doc(alloc, interns, symbol))
                 .append(";"),
             Inc(symbol, n) => alloc
                 .text("inc ")
                 .append(alloc.text(format!("{} ", n)))
-                .append(symbol_to_doc(alloc, symbol))
+                .append(symbol_to_doc(alloc, interns, symbol))
                 .append(";"),
             Dec(symbol) => alloc
                 .text("dec ")
-                .append(symbol_to_doc(alloc, symbol))
+                .append(symbol_to_doc(alloc, interns, symbol))
                 .append(";"),
             DecRef(symbol) => alloc
                 .text("decref ")
-                .append(symbol_to_doc(alloc, symbol))
+                .append(symbol_to_doc(alloc, interns, symbol))
                 .append(";"),
         }
     }
@@ -1589,7 +1651,11 @@ pub struct Call<'a> {
 }
 
 impl<'a> Call<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        interns: Option<&'b Interns>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1603,19 +1669,19 @@ impl<'a> Call<'a> {
             CallType::ByName { name, .. } => {
                 let it = std::iter::once(name.name())
                     .chain(arguments.iter().copied())
-                    .map(|s| symbol_to_doc(alloc, s));
+                    .map(|s| symbol_to_doc(alloc, interns, s));
 
                 alloc.text("CallByName ").append(alloc.intersperse(it, " "))
             }
             LowLevel { op: lowlevel, .. } => {
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text(format!("lowlevel {:?} ", lowlevel))
                     .append(alloc.intersperse(it, " "))
             }
             HigherOrder(higher_order) => {
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text(format!("lowlevel {:?} ", higher_order.op))
@@ -1624,7 +1690,7 @@ impl<'a> Call<'a> {
             Foreign {
                 ref foreign_symbol, ..
             } => {
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text(format!("foreign {:?} ", foreign_symbol.as_str()))
@@ -1763,6 +1829,24 @@ pub enum Expr<'a> {
         index: u64,
     },
 
+    /// The address (as a usize) of a field of a heap-allocated tag union, so that it can be
+    /// written later with `LowLevel::PtrStore`. Used by tail recursion modulo cons.
+    UnionFieldPtrAtIndex {
+        structure: Symbol,
+        tag_id: TagIdIntType,
+        union_layout: UnionLayout<'a>,
+        index: u64,
+    },
+
+    /// A placeholder value for a recursive pointer field that will be overwritten before
+    /// the value it is stored in can be observed
+    NullPointer,
+
+    /// Reserve stack space for one value of the given layout, returning its address (as a usize)
+    Alloca {
+        element_layout: Layout<'a>,
+    },
+
     Array {
         elem_layout: Layout<'a>,
         elems: &'a [ListLiteralElement<'a>],
@@ -1815,6 +1899,42 @@ impl<'a> Literal<'a> {
     }
 }
 
+/// Pretty-print procs with the names of their symbols, sorted so that the output is stable.
+/// Unlike `Proc::to_pretty`, this gives readable output in release builds of the compiler too.
+pub fn procs_to_pretty<'a, 'p, I>(procs: I, interns: &Interns, width: usize) -> String
+where
+    'a: 'p,
+    I: IntoIterator<Item = &'p Proc<'a>>,
+{
+    let allocator = BoxAllocator;
+
+    let mut rendered: std::vec::Vec<String> = procs
+        .into_iter()
+        .map(|proc| {
+            let mut w = std::vec::Vec::new();
+            proc.to_doc::<_, ()>(&allocator, Some(interns), Parens::NotNeeded)
+                .1
+                .render(width, &mut w)
+                .unwrap();
+            w.push(b'\n');
+            String::from_utf8(w).unwrap()
+        })
+        .collect();
+
+    rendered.sort();
+    rendered.join("\n")
+}
+
+fn interned_symbol_name(interns: &Interns, symbol: Symbol) -> Option<String> {
+    let module_name = interns.module_ids.get_name(symbol.module_id())?;
+    let ident = interns
+        .all_ident_ids
+        .get(&symbol.module_id())?
+        .get_name(symbol.ident_id())?;
+
+    Some(format!("{}.{}", module_name.as_str(), ident))
+}
+
 pub(crate) fn symbol_to_doc_string(symbol: Symbol) -> String {
     use roc_module::ident::ModuleName;
 
@@ -1832,26 +1952,41 @@ pub(crate) fn symbol_to_doc_string(symbol: Symbol) -> String {
     }
 }
 
-fn symbol_to_doc<'b, D, A>(alloc: &'b D, symbol: Symbol) -> DocBuilder<'b, D, A>
+fn symbol_to_doc<'b, D, A>(
+    alloc: &'b D,
+    interns: Option<&Interns>,
+    symbol: Symbol,
+) -> DocBuilder<'b, D, A>
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
     A: Clone,
 {
-    alloc.text(symbol_to_doc_string(symbol))
+    match interns.and_then(|interns| interned_symbol_name(interns, symbol)) {
+        Some(name) => alloc.text(name),
+        None => alloc.text(symbol_to_doc_string(symbol)),
+    }
 }
 
-fn join_point_to_doc<'b, D, A>(alloc: &'b D, symbol: JoinPointId) -> DocBuilder<'b, D, A>
+fn join_point_to_doc<'b, D, A>(
+    alloc: &'b D,
+    interns: Option<&Interns>,
+    symbol: JoinPointId,
+) -> DocBuilder<'b, D, A>
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
     A: Clone,
 {
-    symbol_to_doc(alloc, symbol.0)
+    symbol_to_doc(alloc, interns, symbol.0)
 }
 
 impl<'a> Expr<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        interns: Option<&'b Interns>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1862,7 +1997,7 @@ impl<'a> Expr<'a> {
         match self {
             Literal(lit) => lit.to_doc(alloc),
 
-            Call(call) => call.to_doc(alloc),
+            Call(call) => call.to_doc(alloc, interns),
 
             Tag {
                 tag_id, arguments, ..
@@ -1872,7 +2007,7 @@ impl<'a> Expr<'a> {
                     .append(alloc.text(tag_id.to_string()))
                     .append(")");
 
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 doc_tag
                     .append(alloc.space())
@@ -1890,11 +2025,11 @@ impl<'a> Expr<'a> {
                     .append(alloc.text(tag_id.to_string()))
                     .append(")");
 
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text("Reuse ")
-                    .append(symbol_to_doc(alloc, *symbol))
+                    .append(symbol_to_doc(alloc, interns, *symbol))
                     .append(alloc.space())
                     .append(format!("{:?}", update_mode))
                     .append(alloc.space())
@@ -1911,7 +2046,7 @@ impl<'a> Expr<'a> {
             )),
 
             Struct(args) => {
-                let it = args.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = args.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text("Struct {")
@@ -1921,7 +2056,7 @@ impl<'a> Expr<'a> {
             Array { elems, .. } => {
                 let it = elems.iter().map(|e| match e {
                     ListLiteralElement::Literal(l) => l.to_doc(alloc),
-                    ListLiteralElement::Symbol(s) => symbol_to_doc(alloc, *s),
+                    ListLiteralElement::Symbol(s) => symbol_to_doc(alloc, interns, *s),
                 });
 
                 alloc
@@ -1935,17 +2070,21 @@ impl<'a> Expr<'a> {
                 index, structure, ..
             } => alloc
                 .text(format!("StructAtIndex {} ", index))
-                .append(symbol_to_doc(alloc, *structure)),
+                .append(symbol_to_doc(alloc, interns, *structure)),
 
             RuntimeErrorFunction(s) => alloc.text(format!("ErrorFunction {}", s)),
 
             GetTagId { structure, .. } => alloc
                 .text("GetTagId ")
-                .append(symbol_to_doc(alloc, *structure)),
+                .append(symbol_to_doc(alloc, interns, *structure)),
 
-            ExprBox { symbol, .. } => alloc.text("Box ").append(symbol_to_doc(alloc, *symbol)),
+            ExprBox { symbol, .. } => alloc
+                .text("Box ")
+                .append(symbol_to_doc(alloc, interns, *symbol)),
 
-            ExprUnbox { symbol, .. } => alloc.text("Unbox ").append(symbol_to_doc(alloc, *symbol)),
+            ExprUnbox { symbol, .. } => alloc
+                .text("Unbox ")
+                .append(symbol_to_doc(alloc, interns, *symbol)),
 
             UnionAtIndex {
                 tag_id,
@@ -1954,14 +2093,30 @@ impl<'a> Expr<'a> {
                 ..
             } => alloc
                 .text(format!("UnionAtIndex (Id {}) (Index {}) ", tag_id, index))
-                .append(symbol_to_doc(alloc, *structure)),
+                .append(symbol_to_doc(alloc, interns, *structure)),
+
+            UnionFieldPtrAtIndex {
+                tag_id,
+                structure,
+                index,
+                ..
+            } => alloc
+                .text(format!(
+                    "UnionFieldPtrAtIndex (Id {}) (Index {}) ",
+                    tag_id, index
+                ))
+                .append(symbol_to_doc(alloc, interns, *structure)),
+
+            NullPointer => alloc.text("NullPointer"),
+
+            Alloca { .. } => alloc.text("Alloca"),
         }
     }
 
     pub fn to_pretty(&self, width: usize) -> String {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, ()>(&allocator)
+        self.to_doc::<_, ()>(&allocator, None)
             .1
             .render(width, &mut w)
             .unwrap();
@@ -1981,7 +2136,11 @@ impl<'a> Stmt<'a> {
         from_can(env, var, can_expr, procs, layout_cache)
     }
 
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        interns: Option<&'b Interns>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1992,19 +2151,19 @@ impl<'a> Stmt<'a> {
         match self {
             Let(symbol, expr, layout, cont) => alloc
                 .text("let ")
-                .append(symbol_to_doc(alloc, *symbol))
+                .append(symbol_to_doc(alloc, interns, *symbol))
                 .append(" : ")
                 .append(layout.to_doc(alloc, Parens::NotNeeded))
                 .append(" = ")
-                .append(expr.to_doc(alloc))
+                .append(expr.to_doc(alloc, interns))
                 .append(";")
                 .append(alloc.hardline())
-                .append(cont.to_doc(alloc)),
+                .append(cont.to_doc(alloc, interns)),
 
             Refcounting(modify, cont) => modify
-                .to_doc(alloc)
+                .to_doc(alloc, interns)
                 .append(alloc.hardline())
-                .append(cont.to_doc(alloc)),
+                .append(cont.to_doc(alloc, interns)),
 
             Expect {
                 condition,
@@ -2012,14 +2171,14 @@ impl<'a> Stmt<'a> {
                 ..
             } => alloc
                 .text("expect ")
-                .append(symbol_to_doc(alloc, *condition))
+                .append(symbol_to_doc(alloc, interns, *condition))
                 .append(";")
                 .append(alloc.hardline())
-                .append(remainder.to_doc(alloc)),
+                .append(remainder.to_doc(alloc, interns)),
 
             Ret(symbol) => alloc
                 .text("ret ")
-                .append(symbol_to_doc(alloc, *symbol))
+                .append(symbol_to_doc(alloc, interns, *symbol))
                 .append(";"),
 
             Switch {
@@ -2033,23 +2192,23 @@ impl<'a> Stmt<'a> {
                         let fail = default_branch.1;
                         alloc
                             .text("if ")
-                            .append(symbol_to_doc(alloc, *cond_symbol))
+                            .append(symbol_to_doc(alloc, interns, *cond_symbol))
                             .append(" then")
-                            .append(info.to_doc(alloc))
+                            .append(info.to_doc(alloc, interns))
                             .append(alloc.hardline())
-                            .append(pass.to_doc(alloc).indent(4))
+                            .append(pass.to_doc(alloc, interns).indent(4))
                             .append(alloc.hardline())
                             .append(alloc.text("else"))
-                            .append(default_branch.0.to_doc(alloc))
+                            .append(default_branch.0.to_doc(alloc, interns))
                             .append(alloc.hardline())
-                            .append(fail.to_doc(alloc).indent(4))
+                            .append(fail.to_doc(alloc, interns).indent(4))
                     }
 
                     _ => {
                         let default_doc = alloc
                             .text("default:")
                             .append(alloc.hardline())
-                            .append(default_branch.1.to_doc(alloc).indent(4))
+                            .append(default_branch.1.to_doc(alloc, interns).indent(4))
                             .indent(4);
 
                         let branches_docs = branches
@@ -2058,14 +2217,14 @@ impl<'a> Stmt<'a> {
                                 alloc
                                     .text(format!("case {}:", tag))
                                     .append(alloc.hardline())
-                                    .append(expr.to_doc(alloc).indent(4))
+                                    .append(expr.to_doc(alloc, interns).indent(4))
                                     .indent(4)
                             })
                             .chain(std::iter::once(default_doc));
                         //
                         alloc
                             .text("switch ")
-                            .append(symbol_to_doc(alloc, *cond_symbol))
+                            .append(symbol_to_doc(alloc, interns, *cond_symbol))
                             .append(":")
                             .append(alloc.hardline())
                             .append(alloc.intersperse(
@@ -2085,29 +2244,31 @@ impl<'a> Stmt<'a> {
                 body: continuation,
                 remainder,
             } => {
-                let it = parameters.iter().map(|p| symbol_to_doc(alloc, p.symbol));
+                let it = parameters
+                    .iter()
+                    .map(|p| symbol_to_doc(alloc, interns, p.symbol));
 
                 alloc.intersperse(
                     vec![
                         alloc
                             .text("joinpoint ")
-                            .append(join_point_to_doc(alloc, *id))
+                            .append(join_point_to_doc(alloc, interns, *id))
                             .append(" ".repeat(parameters.len().min(1)))
                             .append(alloc.intersperse(it, alloc.space()))
                             .append(":"),
-                        continuation.to_doc(alloc).indent(4),
+                        continuation.to_doc(alloc, interns).indent(4),
                         alloc.text("in"),
-                        remainder.to_doc(alloc),
+                        remainder.to_doc(alloc, interns),
                     ],
                     alloc.hardline(),
                 )
             }
             Jump(id, arguments) => {
-                let it = arguments.iter().map(|s| symbol_to_doc(alloc, *s));
+                let it = arguments.iter().map(|s| symbol_to_doc(alloc, interns, *s));
 
                 alloc
                     .text("jump ")
-                    .append(join_point_to_doc(alloc, *id))
+                    .append(join_point_to_doc(alloc, interns, *id))
                     .append(" ".repeat(arguments.len().min(1)))
                     .append(alloc.intersperse(it, alloc.space()))
                     .append(";")
@@ -2118,7 +2279,7 @@ impl<'a> Stmt<'a> {
     pub fn to_pretty(&self, width: usize) -> String {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, ()>(&allocator)
+        self.to_doc::<_, ()>(&allocator, None)
             .1
             .render(width, &mut w)
             .unwrap();
@@ -3782,7 +3943,7 @@ pub fn with_hole<'a>(
         SingleQuote(character) => Stmt::Let(
             assigned,
             Expr::Literal(Literal::Int((character as i128).to_ne_bytes())),
-            Layout::int_width(IntWidth::I32),
+            Layout::int_width(IntWidth::U32),
             hole,
         ),
         LetNonRec(def, cont) => from_can_let(
@@ -4757,6 +4918,10 @@ pub fn with_hole<'a>(
         Call(boxed, loc_args, _) => {
             let (fn_var, loc_expr, _lambda_set_var, _ret_var) = *boxed;
 
+            if let Some(call_regions) = env.call_regions.as_mut() {
+                call_regions.insert(assigned, loc_expr.region);
+            }
+
             // even if a call looks like it's by name, it may in fact be by-pointer.
             // E.g. in `(\f, x -> f x)` the call is in fact by pointer.
             // So we check the function name against the list of partial procedures,
@@ -6607,6 +6772,23 @@ fn substitute_in_expr<'a>(
             }),
             None => None,
         },
+
+        UnionFieldPtrAtIndex {
+            structure,
+            tag_id,
+            index,
+            union_layout,
+        } => match substitute(subs, *structure) {
+            Some(structure) => Some(UnionFieldPtrAtIndex {
+                structure,
+                tag_id: *tag_id,
+                index: *index,
+                union_layout: *union_layout,
+            }),
+            None => None,
+        },
+
+        NullPointer | Alloca { .. } => None,
     }
 }
 
@@ -8330,7 +8512,7 @@ fn from_can_pattern_help<'a>(
         StrLiteral(v) => Ok(Pattern::StrLiteral(v.clone())),
         SingleQuote(c) => Ok(Pattern::IntLiteral(
             (*c as i128).to_ne_bytes(),
-            IntWidth::I32,
+            IntWidth::U32,
         )),
         Shadowed(region, ident, _new_symbol) => Err(RuntimeError::Shadowing {
             original_region: *region,
@@ -1766,6 +1766,16 @@ impl<'a> Layout<'a> {
         }
     }
 
+    pub fn to_pretty(self, width: usize) -> String {
+        let allocator = ven_pretty::BoxAllocator;
+        let mut w = std::vec::Vec::new();
+        self.to_doc::<_, ()>(&allocator, Parens::NotNeeded)
+            .1
+            .render(width, &mut w)
+            .unwrap();
+        String::from_utf8(w).unwrap()
+    }
+
     /// Used to build a `Layout::Struct` where the field name order is irrelevant.
     pub fn struct_no_name_order(field_layouts: &'a [Layout]) -> Self {
         if field_layouts.is_empty() {
@@ -4,7 +4,10 @@
 
 pub mod borrow;
 pub mod code_gen_help;
+pub mod debug;
+pub mod expand_rc;
 pub mod inc_dec;
+pub mod inline;
 pub mod ir;
 pub mod layout;
 pub mod layout_soa;
@@ -275,6 +275,9 @@ fn insert_reset<'a>(
             | Struct(_)
             | Array { .. }
             | EmptyArray
+            | UnionFieldPtrAtIndex { .. }
+            | NullPointer
+            | Alloca { .. }
             | Reuse { .. }
             | Reset { .. }
             | RuntimeErrorFunction(_) => break,
@@ -703,8 +706,9 @@ fn has_live_var_expr<'a>(expr: &'a Expr<'a>, needle: Symbol) -> bool {
         | Expr::Struct(fields) => fields.iter().any(|s| *s == needle),
         Expr::StructAtIndex { structure, .. }
         | Expr::GetTagId { structure, .. }
-        | Expr::UnionAtIndex { structure, .. } => *structure == needle,
-        Expr::EmptyArray => false,
+        | Expr::UnionAtIndex { structure, .. }
+        | Expr::UnionFieldPtrAtIndex { structure, .. } => *structure == needle,
+        Expr::EmptyArray | Expr::NullPointer | Expr::Alloca { .. } => false,
         Expr::Reuse {
             symbol, arguments, ..
         } => needle == *symbol || arguments.iter().any(|s| *s == needle),
@@ -1,10 +1,14 @@
 #![allow(clippy::manual_map)]
 
-use crate::ir::{CallType, Expr, JoinPointId, Param, Stmt};
-use crate::layout::{LambdaName, Layout};
+use crate::inc_dec::occurring_variables_expr;
+use crate::ir::{Call, CallType, Expr, JoinPointId, Param, Stmt, UpdateModeId};
+use crate::layout::{LambdaName, Layout, TagIdIntType, UnionLayout};
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
-use roc_module::symbol::Symbol;
+use roc_collections::all::MutSet;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_target::TargetInfo;
 
 /// Make tail calls into loops (using join points)
 ///
@@ -278,3 +282,447 @@ fn insert_jumps<'a>(
         RuntimeError(_) => None,
     }
 }
+
+/// Make functions that build a recursive tag union with a recursive call in the hole of a
+/// constructor into loops. This is tail recursion modulo cons (TRMC).
+///
+/// e.g.
+///
+/// > map = \xs, f -> when xs is
+/// >     Nil -> Nil
+/// >     Cons x rest -> Cons (f x) (map rest f)
+///
+/// becomes
+///
+/// ```elm
+/// map xs1 f1 =
+///     let slot = Alloca
+///     let joinpoint j xs f hole =
+///             when xs is
+///                 Nil ->
+///                     PtrStore hole Nil
+///                     PtrLoad slot
+///                 Cons x rest ->
+///                     let cell = Cons (f x) NullPointer
+///                     let newHole = UnionFieldPtrAtIndex 1 cell
+///                     PtrStore hole cell
+///                     jump j rest f newHole
+///     in
+///         jump j xs1 f1 slot
+/// ```
+///
+/// Every cell is allocated before its tail is computed, and the recursive call becomes a jump
+/// that fills in the tail of the cell. The final result is read back from the `slot` that holds
+/// the first cell. Regular tail calls in the same function become jumps that pass the hole on.
+#[allow(clippy::too_many_arguments)]
+pub fn make_tail_recursive_modulo_cons<'a>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    target_info: TargetInfo,
+    id: JoinPointId,
+    needle: LambdaName<'a>,
+    stmt: Stmt<'a>,
+    args: &'a [(Layout<'a>, Symbol, Symbol)],
+    ret_layout: Layout<'a>,
+) -> Option<Stmt<'a>> {
+    let union_layout = match ret_layout {
+        Layout::Union(UnionLayout::NonRecursive(_)) => return None,
+        Layout::Union(union_layout) => union_layout,
+        _ => return None,
+    };
+
+    let mut env = TrmcEnv {
+        arena,
+        home,
+        ident_ids,
+        target_info,
+        id,
+        needle,
+        needle_arguments: args,
+        union_layout,
+        slot: Symbol::ATTR_ATTR,
+    };
+
+    let allocated = arena.alloc(stmt);
+
+    if !env.has_cons_site(allocated) {
+        return None;
+    }
+
+    env.slot = env.unique_symbol();
+    let hole = env.unique_symbol();
+
+    let new_stmt = env.transform(allocated, hole);
+
+    let usize_layout = Layout::usize(target_info);
+
+    let params = Vec::from_iter_in(
+        args.iter()
+            .map(|(layout, symbol, _)| Param {
+                symbol: *symbol,
+                layout: *layout,
+                borrow: true,
+            })
+            .chain(std::iter::once(Param {
+                symbol: hole,
+                layout: usize_layout,
+                borrow: true,
+            })),
+        arena,
+    )
+    .into_bump_slice();
+
+    let jump_args = Vec::from_iter_in(
+        args.iter().map(|t| t.2).chain(std::iter::once(env.slot)),
+        arena,
+    )
+    .into_bump_slice();
+
+    let jump = arena.alloc(Stmt::Jump(id, jump_args));
+
+    let join = arena.alloc(Stmt::Join {
+        id,
+        remainder: jump,
+        parameters: params,
+        body: new_stmt,
+    });
+
+    Some(Stmt::Let(
+        env.slot,
+        Expr::Alloca {
+            element_layout: ret_layout,
+        },
+        usize_layout,
+        join,
+    ))
+}
+
+struct TrmcEnv<'a, 'i> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    target_info: TargetInfo,
+    id: JoinPointId,
+    needle: LambdaName<'a>,
+    needle_arguments: &'a [(Layout<'a>, Symbol, Symbol)],
+    union_layout: UnionLayout<'a>,
+    /// Stack slot that holds the final result
+    slot: Symbol,
+}
+
+/// A recursive call whose result is only used in one recursive field of a constructor
+/// that is then returned:
+///
+/// ```text
+/// let r = f a b;
+/// <lets that do not use r>
+/// let c = Cons x r;
+/// ret c;
+/// ```
+struct ConsSite<'a> {
+    arguments: &'a [Symbol],
+    /// the lets between the call and the constructor
+    lets: std::vec::Vec<&'a Stmt<'a>>,
+    cell: Symbol,
+    cell_layout: Layout<'a>,
+    tag_id: TagIdIntType,
+    tag_arguments: &'a [Symbol],
+    index: usize,
+}
+
+impl<'a, 'i> TrmcEnv<'a, 'i> {
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
+    }
+
+    /// Is this a call to the function itself, with the same layout?
+    fn is_self_call(&self, expr: &Expr<'a>) -> Option<&'a [Symbol]> {
+        match expr {
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                arguments,
+            }) => {
+                let it = self.needle_arguments.iter().map(|t| &t.0);
+
+                let is_equal_function = self.needle == *name
+                    && it.eq(arg_layouts.iter())
+                    && **ret_layout == Layout::Union(self.union_layout);
+
+                if is_equal_function {
+                    Some(arguments)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn cons_site(&self, stmt: &'a Stmt<'a>) -> Option<ConsSite<'a>> {
+        let (call_symbol, arguments, mut cont) = match stmt {
+            Stmt::Let(symbol, expr, _, cont) => (*symbol, self.is_self_call(expr)?, *cont),
+            _ => return None,
+        };
+
+        let mut lets = std::vec::Vec::new();
+        let mut occurring = MutSet::default();
+
+        while let Stmt::Let(symbol, expr, layout, next) = cont {
+            match expr {
+                Expr::Tag {
+                    tag_layout,
+                    tag_id,
+                    arguments: tag_arguments,
+                } if *tag_layout == self.union_layout
+                    && matches!(next, Stmt::Ret(s) if s == symbol) =>
+                {
+                    let mut positions = tag_arguments
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, s)| **s == call_symbol);
+
+                    return match (positions.next(), positions.next()) {
+                        (Some((index, _)), None) => Some(ConsSite {
+                            arguments,
+                            lets,
+                            cell: *symbol,
+                            cell_layout: *layout,
+                            tag_id: *tag_id,
+                            tag_arguments,
+                            index,
+                        }),
+                        _ => None,
+                    };
+                }
+                _ => {
+                    occurring.clear();
+                    occurring_variables_expr(expr, &mut occurring);
+
+                    if occurring.contains(&call_symbol) {
+                        return None;
+                    }
+
+                    lets.push(cont);
+                    cont = next;
+                }
+            }
+        }
+
+        None
+    }
+
+    fn has_cons_site(&self, stmt: &'a Stmt<'a>) -> bool {
+        use Stmt::*;
+
+        if self.cons_site(stmt).is_some() {
+            return true;
+        }
+
+        match stmt {
+            Let(_, _, _, cont) | Refcounting(_, cont) => self.has_cons_site(cont),
+            Expect { remainder, .. } => self.has_cons_site(remainder),
+            Join {
+                body, remainder, ..
+            } => self.has_cons_site(body) || self.has_cons_site(remainder),
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                branches.iter().any(|(_, _, b)| self.has_cons_site(b))
+                    || self.has_cons_site(default_branch.1)
+            }
+            Ret(_) | Jump(_, _) | RuntimeError(_) => false,
+        }
+    }
+
+    fn jump(&self, arguments: &[Symbol], hole: Symbol) -> &'a Stmt<'a> {
+        let arguments = Vec::from_iter_in(
+            arguments.iter().copied().chain(std::iter::once(hole)),
+            self.arena,
+        );
+
+        self.arena
+            .alloc(Stmt::Jump(self.id, arguments.into_bump_slice()))
+    }
+
+    fn ptr_store(&mut self, hole: Symbol, value: Symbol, cont: &'a Stmt<'a>) -> &'a Stmt<'a> {
+        let call = Call {
+            call_type: CallType::LowLevel {
+                op: LowLevel::PtrStore,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            },
+            arguments: self.arena.alloc([hole, value]),
+        };
+
+        let unit = self.unique_symbol();
+
+        self.arena
+            .alloc(Stmt::Let(unit, Expr::Call(call), Layout::UNIT, cont))
+    }
+
+    /// Rewrite the body of the function so it fills in `hole` instead of returning
+    fn transform(&mut self, stmt: &'a Stmt<'a>, hole: Symbol) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        if let Some(site) = self.cons_site(stmt) {
+            let ConsSite {
+                arguments,
+                lets,
+                cell,
+                cell_layout,
+                tag_id,
+                tag_arguments,
+                index,
+            } = site;
+
+            let null = self.unique_symbol();
+            let new_hole = self.unique_symbol();
+
+            let mut tag_arguments = Vec::from_iter_in(tag_arguments.iter().copied(), arena);
+            tag_arguments[index] = null;
+
+            let jump = self.jump(arguments, new_hole);
+            let store = self.ptr_store(hole, cell, jump);
+
+            let field_ptr = Expr::UnionFieldPtrAtIndex {
+                structure: cell,
+                tag_id,
+                union_layout: self.union_layout,
+                index: index as u64,
+            };
+            let usize_layout = Layout::usize(self.target_info);
+            let stmt = arena.alloc(Let(new_hole, field_ptr, usize_layout, store));
+
+            let tag = Expr::Tag {
+                tag_layout: self.union_layout,
+                tag_id,
+                arguments: tag_arguments.into_bump_slice(),
+            };
+            let stmt = arena.alloc(Let(cell, tag, cell_layout, stmt));
+
+            let mut stmt = arena.alloc(Let(null, Expr::NullPointer, cell_layout, stmt));
+
+            for other in lets.into_iter().rev() {
+                if let Let(symbol, expr, layout, _) = other {
+                    stmt = arena.alloc(Let(*symbol, expr.clone(), *layout, stmt));
+                }
+            }
+
+            return stmt;
+        }
+
+        match stmt {
+            Let(symbol, expr, _, Ret(rsym))
+                if symbol == rsym && self.is_self_call(expr).is_some() =>
+            {
+                // a regular tail call passes the hole on
+                self.jump(self.is_self_call(expr).unwrap(), hole)
+            }
+            Let(symbol, expr, layout, cont) => {
+                let cont = self.transform(cont, hole);
+
+                arena.alloc(Let(*symbol, expr.clone(), *layout, cont))
+            }
+            Refcounting(modify, cont) => {
+                let cont = self.transform(cont, hole);
+
+                arena.alloc(Refcounting(*modify, cont))
+            }
+            Expect {
+                condition,
+                region,
+                lookups,
+                layouts,
+                remainder,
+            } => {
+                let remainder = self.transform(remainder, hole);
+
+                arena.alloc(Expect {
+                    condition: *condition,
+                    region: *region,
+                    lookups,
+                    layouts,
+                    remainder,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let body = self.transform(body, hole);
+                let remainder = self.transform(remainder, hole);
+
+                arena.alloc(Join {
+                    id: *id,
+                    parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let mut new_branches = Vec::with_capacity_in(branches.len(), arena);
+
+                for (label, info, branch) in branches.iter() {
+                    let branch = self.transform(branch, hole);
+                    new_branches.push((*label, info.clone(), branch.clone()));
+                }
+
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.transform(default_branch.1, hole),
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: new_branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Ret(symbol) => {
+                // write the final value into the hole, and return the first cell
+                let result = self.unique_symbol();
+
+                let call = Call {
+                    call_type: CallType::LowLevel {
+                        op: LowLevel::PtrLoad,
+                        update_mode: UpdateModeId::BACKEND_DUMMY,
+                    },
+                    arguments: arena.alloc([self.slot]),
+                };
+
+                let ret = arena.alloc(Ret(result));
+                let load = arena.alloc(Let(
+                    result,
+                    Expr::Call(call),
+                    Layout::Union(self.union_layout),
+                    ret,
+                ));
+
+                self.ptr_store(hole, *symbol, load)
+            }
+            Jump(_, _) | RuntimeError(_) => stmt,
+        }
+    }
+}
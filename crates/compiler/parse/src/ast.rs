@@ -2,6 +2,7 @@ use std::fmt::Debug;
 
 use crate::header::{AppHeader, HostedHeader, InterfaceHeader, PlatformHeader};
 use crate::ident::Ident;
+use crate::parser::SyntaxError;
 use bumpalo::collections::{String, Vec};
 use bumpalo::Bump;
 use roc_collections::soa::{EitherIndex, Index, Slice};
@@ -230,6 +231,10 @@ pub enum Expr<'a> {
     // Problems
     MalformedIdent(&'a str, crate::ident::BadIdent),
     MalformedClosure,
+    /// Source code that did not parse, and the syntax error it ran into. The parser only makes
+    /// these when it recovers from errors; it then continues after the code, e.g. at the next
+    /// top-level def or item of a collection.
+    Malformed(&'a str, &'a SyntaxError<'a>),
     // Both operators were non-associative, e.g. (True == False == False).
     // We should tell the author to disambiguate by grouping them with parens.
     PrecedenceConflict(&'a PrecedenceConflict<'a>),
@@ -0,0 +1,786 @@
+use crate::ast::{AssignedField, Defs, Expr, TypeDef, ValueDef};
+use crate::keyword;
+use crate::module::{parse_header, parse_module_defs};
+use crate::state::State;
+use roc_region::all::{Loc, Region};
+use std::ops::Range;
+
+/// A lossless concrete syntax tree of a module.
+///
+/// Every byte of the source is in exactly one token, including whitespace, newlines and
+/// comments, so printing the tree gives back the source byte for byte. The nodes come from the
+/// regions of the parsed module; code that does not parse is still there, as tokens of the
+/// nearest node around it. This makes it a good fit for tools that rewrite source code and
+/// must leave everything they don't touch as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    /// In preorder, so the root comes first and each node comes before its descendants
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Token {
+    pub fn range(&self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Spaces, tabs and carriage returns that don't end a line
+    Whitespace,
+    Newline,
+    /// `# ...`, up to but not including the newline
+    LineComment,
+    /// `## ...`, up to but not including the newline
+    DocComment,
+    LowercaseIdent,
+    UppercaseIdent,
+    Keyword,
+    Number,
+    /// A string literal, including its quotes and any interpolations in it
+    Str,
+    SingleQuote,
+    Operator,
+    /// One of `( ) [ ] { } ,`
+    Punctuation,
+    /// A character that can't start any other token
+    Unknown,
+}
+
+impl TokenKind {
+    /// Whether the token only separates other tokens, and has no meaning of its own
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace
+                | TokenKind::Newline
+                | TokenKind::LineComment
+                | TokenKind::DocComment
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<Element>,
+    tokens: Range<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Module,
+    Header,
+    Def,
+    Expr,
+    Pattern,
+    TypeAnnotation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    Node(NodeId),
+    Token(TokenId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenId(u32);
+
+impl<'a> Cst<'a> {
+    /// Parse a module, header and all. This never fails: syntax errors just leave out the
+    /// nodes of the code that did not parse.
+    pub fn parse(src: &'a str) -> Cst<'a> {
+        let arena = bumpalo::Bump::new();
+        let tokens = tokenize(src.as_bytes());
+        let mut ranges = Vec::new();
+
+        if let Ok((_, state)) = parse_header(&arena, State::new(src.as_bytes())) {
+            ranges.push((NodeKind::Header, 0, state.pos().offset));
+
+            let (defs, _) = parse_module_defs(&arena, state);
+            push_defs(&defs, &mut ranges);
+        }
+
+        Cst::build(src, tokens, ranges)
+    }
+
+    fn build(src: &'a str, tokens: Vec<Token>, ranges: Vec<(NodeKind, u32, u32)>) -> Cst<'a> {
+        let root = Node {
+            kind: NodeKind::Module,
+            children: Vec::new(),
+            tokens: 0..tokens.len() as u32,
+        };
+
+        // Turn byte ranges into token ranges without the trivia at either end. Keep parents
+        // before their children, and drop ranges that don't line up with tokens.
+        let mut candidates: Vec<(NodeKind, Range<u32>)> = ranges
+            .into_iter()
+            .filter_map(|(kind, start, end)| {
+                let first = tokens.binary_search_by_key(&start, |t| t.start).ok()?;
+                let last = tokens.binary_search_by_key(&end, |t| t.end).ok()?;
+                let mut token_range = first as u32..last as u32 + 1;
+
+                while token_range.start < token_range.end
+                    && tokens[token_range.start as usize].kind.is_trivia()
+                {
+                    token_range.start += 1;
+                }
+
+                while token_range.start < token_range.end
+                    && tokens[token_range.end as usize - 1].kind.is_trivia()
+                {
+                    token_range.end -= 1;
+                }
+
+                if token_range.is_empty() {
+                    None
+                } else {
+                    Some((kind, token_range))
+                }
+            })
+            .collect();
+
+        candidates.sort_by_key(|(_, token_range)| {
+            (token_range.start, std::cmp::Reverse(token_range.end))
+        });
+
+        let mut nodes = vec![root];
+        let mut stack: Vec<usize> = vec![0];
+        let mut next_token = 0;
+
+        for (kind, token_range) in candidates {
+            // close the nodes that end before this one starts
+            while nodes[*stack.last().unwrap()].tokens.end <= token_range.start {
+                let closed = stack.pop().unwrap();
+                push_tokens(&mut nodes[closed], &mut next_token);
+            }
+
+            let parent = *stack.last().unwrap();
+
+            if token_range.end > nodes[parent].tokens.end {
+                // crosses the end of its parent; the regions of the AST were off
+                continue;
+            }
+
+            let id = nodes.len();
+
+            push_tokens_until(&mut nodes[parent], &mut next_token, token_range.start);
+            nodes[parent]
+                .children
+                .push(Element::Node(NodeId(id as u32)));
+            nodes.push(Node {
+                kind,
+                children: Vec::new(),
+                tokens: token_range,
+            });
+            stack.push(id);
+        }
+
+        while let Some(closed) = stack.pop() {
+            push_tokens(&mut nodes[closed], &mut next_token);
+        }
+
+        Cst { src, tokens, nodes }
+    }
+
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+
+    pub fn token(&self, id: TokenId) -> &Token {
+        &self.tokens[id.0 as usize]
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = (TokenId, &Token)> {
+        self.tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| (TokenId(index as u32), token))
+    }
+
+    /// The tokens that overlap a byte range, e.g. the region of an AST node, in source order
+    pub fn tokens_in(&self, range: Range<usize>) -> impl Iterator<Item = (TokenId, &Token)> {
+        let first = self
+            .tokens
+            .partition_point(|token| (token.end as usize) <= range.start);
+
+        self.tokens[first..]
+            .iter()
+            .enumerate()
+            .take_while(move |(_, token)| (token.start as usize) < range.end)
+            .map(move |(index, token)| (TokenId((first + index) as u32), token))
+    }
+
+    /// All nodes in preorder, i.e. in the order they start in the source, outer ones first
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (NodeId(index as u32), node))
+    }
+
+    /// The byte range of a node. It starts and ends with a token that is not trivia, unless
+    /// it's the root, which spans the whole source.
+    pub fn node_range(&self, id: NodeId) -> Range<usize> {
+        let tokens = &self.node(id).tokens;
+
+        if tokens.is_empty() {
+            return 0..0;
+        }
+
+        let start = self.tokens[tokens.start as usize].start as usize;
+        let end = self.tokens[tokens.end as usize - 1].end as usize;
+
+        start..end
+    }
+
+    pub fn node_text(&self, id: NodeId) -> &'a str {
+        &self.src[self.node_range(id)]
+    }
+
+    pub fn token_text(&self, id: TokenId) -> &'a str {
+        &self.src[self.token(id).range()]
+    }
+
+    /// The comments right before a node, e.g. the doc comment of a def. Comments that are
+    /// separated from the node by a blank line are not included.
+    pub fn comments_before(&self, id: NodeId) -> Vec<TokenId> {
+        let mut comments = Vec::new();
+        let mut newlines = 0;
+        let mut index = self.node(id).tokens.start as usize;
+
+        while index > 0 {
+            index -= 1;
+
+            match self.tokens[index].kind {
+                TokenKind::Whitespace => {}
+                TokenKind::Newline => {
+                    newlines += 1;
+
+                    if newlines > 1 {
+                        break;
+                    }
+                }
+                TokenKind::LineComment | TokenKind::DocComment => {
+                    newlines = 0;
+                    comments.push(TokenId(index as u32));
+                }
+                _ => break,
+            }
+        }
+
+        comments.reverse();
+        comments
+    }
+
+    /// Print the tree. This gives back the source it was parsed from, byte for byte.
+    pub fn print(&self) -> String {
+        let mut buf = String::with_capacity(self.src.len());
+
+        self.print_help(self.root(), &mut buf);
+
+        buf
+    }
+
+    fn print_help(&self, id: NodeId, buf: &mut String) {
+        for child in self.node(id).children.iter() {
+            match child {
+                Element::Node(node) => self.print_help(*node, buf),
+                Element::Token(token) => buf.push_str(self.token_text(*token)),
+            }
+        }
+    }
+
+    /// Start rewriting nodes and tokens of this tree.
+    pub fn rewriter(&self) -> Rewriter<'_, 'a> {
+        Rewriter {
+            cst: self,
+            edits: Vec::new(),
+        }
+    }
+}
+
+fn push_tokens(node: &mut Node, next_token: &mut u32) {
+    let end = node.tokens.end;
+
+    push_tokens_until(node, next_token, end)
+}
+
+fn push_tokens_until(node: &mut Node, next_token: &mut u32, end: u32) {
+    while *next_token < end {
+        node.children.push(Element::Token(TokenId(*next_token)));
+        *next_token += 1;
+    }
+}
+
+/// Rewrites the source of a `Cst`, leaving the bytes around the edits exactly as they were.
+///
+/// Edits are applied in source order. An edit inside of code that another edit replaces or
+/// removes is ignored, and insertions at the same place keep the order they were made in.
+#[derive(Debug)]
+pub struct Rewriter<'b, 'a> {
+    cst: &'b Cst<'a>,
+    edits: Vec<Edit>,
+}
+
+#[derive(Debug)]
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+impl<'b, 'a> Rewriter<'b, 'a> {
+    pub fn replace(&mut self, id: NodeId, text: impl Into<String>) -> &mut Self {
+        let range = self.cst.node_range(id);
+
+        self.edit(range, text.into())
+    }
+
+    pub fn replace_token(&mut self, id: TokenId, text: impl Into<String>) -> &mut Self {
+        let range = self.cst.token(id).range();
+
+        self.edit(range, text.into())
+    }
+
+    pub fn remove(&mut self, id: NodeId) -> &mut Self {
+        self.replace(id, String::new())
+    }
+
+    pub fn insert_before(&mut self, id: NodeId, text: impl Into<String>) -> &mut Self {
+        let start = self.cst.node_range(id).start;
+
+        self.edit(start..start, text.into())
+    }
+
+    pub fn insert_after(&mut self, id: NodeId, text: impl Into<String>) -> &mut Self {
+        let end = self.cst.node_range(id).end;
+
+        self.edit(end..end, text.into())
+    }
+
+    fn edit(&mut self, range: Range<usize>, text: String) -> &mut Self {
+        self.edits.push(Edit { range, text });
+
+        self
+    }
+
+    /// The rewritten source
+    pub fn finish(&self) -> String {
+        let src = self.cst.src;
+        let mut edits: Vec<&Edit> = self.edits.iter().collect();
+
+        // Insertions go before replacements at the same place, and outer replacements before
+        // the ones inside of them. Sorting is stable, so insertions stay in order.
+        edits.sort_by_key(|edit| {
+            (
+                edit.range.start,
+                !edit.range.is_empty(),
+                std::cmp::Reverse(edit.range.end),
+            )
+        });
+
+        let mut buf = String::with_capacity(src.len());
+        let mut copied_until = 0;
+
+        for edit in edits {
+            if edit.range.start < copied_until {
+                continue;
+            }
+
+            buf.push_str(&src[copied_until..edit.range.start]);
+            buf.push_str(&edit.text);
+            copied_until = edit.range.end;
+        }
+
+        buf.push_str(&src[copied_until..]);
+
+        buf
+    }
+}
+
+// TOKENS
+
+fn tokenize(bytes: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let (kind, len) = next_token(&bytes[index..]);
+
+        tokens.push(Token {
+            kind,
+            start: index as u32,
+            end: (index + len) as u32,
+        });
+
+        index += len;
+    }
+
+    tokens
+}
+
+/// The kind and length of the token at the start of `bytes`, which must not be empty
+fn next_token(bytes: &[u8]) -> (TokenKind, usize) {
+    const OPERATORS: [&[u8]; 12] = [
+        b"...", b"|>", b"->", b"<-", b"==", b"!=", b"<=", b">=", b"&&", b"||", b"//", b":=",
+    ];
+
+    match bytes[0] {
+        b'\n' => (TokenKind::Newline, 1),
+        b'\r' if bytes.get(1) == Some(&b'\n') => (TokenKind::Newline, 2),
+        b' ' | b'\t' | b'\r' => {
+            let len = bytes
+                .iter()
+                .take_while(|b| matches!(b, b' ' | b'\t'))
+                .count();
+
+            (TokenKind::Whitespace, len.max(1))
+        }
+        b'#' => {
+            let len = line_len(bytes);
+
+            if bytes.starts_with(b"##") {
+                (TokenKind::DocComment, len)
+            } else {
+                (TokenKind::LineComment, len)
+            }
+        }
+        b'"' => (TokenKind::Str, str_len(bytes)),
+        b'\'' => (TokenKind::SingleQuote, single_quote_len(bytes)),
+        b'0'..=b'9' => {
+            let mut len = 1;
+
+            while let Some(&byte) = bytes.get(len) {
+                match byte {
+                    b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'_' => len += 1,
+                    b'.' if matches!(bytes.get(len + 1), Some(b'0'..=b'9')) => len += 1,
+                    _ => break,
+                }
+            }
+
+            (TokenKind::Number, len)
+        }
+        b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+            let len = bytes
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                .count();
+            let word = &bytes[..len];
+
+            if word == keyword::EXPECT.as_bytes() && bytes.starts_with(b"expect-fx") {
+                (TokenKind::Keyword, keyword::EXPECT_FX.len())
+            } else if keyword::KEYWORDS.iter().any(|k| k.as_bytes() == word) {
+                (TokenKind::Keyword, len)
+            } else if bytes[0].is_ascii_uppercase() {
+                (TokenKind::UppercaseIdent, len)
+            } else {
+                (TokenKind::LowercaseIdent, len)
+            }
+        }
+        b'(' | b')' | b'[' | b']' | b'{' | b'}' | b',' => (TokenKind::Punctuation, 1),
+        _ => {
+            if let Some(op) = OPERATORS.iter().find(|op| bytes.starts_with(op)) {
+                (TokenKind::Operator, op.len())
+            } else if b"+-*/\\=<>|&^%!:.?@".contains(&bytes[0]) {
+                (TokenKind::Operator, 1)
+            } else {
+                (TokenKind::Unknown, utf8_char_len(bytes))
+            }
+        }
+    }
+}
+
+/// The length up to the end of the line, not including the newline
+fn line_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|b| matches!(b, b'\n' | b'\r'))
+        .unwrap_or(bytes.len())
+}
+
+fn utf8_char_len(bytes: &[u8]) -> usize {
+    let len = match bytes[0] {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    };
+
+    len.min(bytes.len())
+}
+
+/// The length of a string literal, including interpolations. One that does not end stops at
+/// the end of its line, or of the input for a multiline string.
+fn str_len(bytes: &[u8]) -> usize {
+    if bytes.starts_with(b"\"\"\"") {
+        return match bytes[3..].windows(3).position(|w| w == b"\"\"\"") {
+            Some(index) => 3 + index + 3,
+            None => bytes.len(),
+        };
+    }
+
+    let mut index = 1;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'"' => return index + 1,
+            b'\n' | b'\r' => return index,
+            b'\\' if bytes.get(index + 1) == Some(&b'(') => {
+                index += 2 + interpolation_len(&bytes[index + 2..]);
+            }
+            b'\\' => index += 2,
+            _ => index += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// The length of the code in a string interpolation, including its closing paren
+fn interpolation_len(bytes: &[u8]) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'(' => depth += 1,
+            b')' if depth == 0 => return index + 1,
+            b')' => depth -= 1,
+            b'"' => {
+                index += str_len(&bytes[index..]);
+                continue;
+            }
+            b'\n' | b'\r' => return index,
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    bytes.len()
+}
+
+fn single_quote_len(bytes: &[u8]) -> usize {
+    let mut index = 1;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'\'' => return index + 1,
+            b'\n' | b'\r' => return index,
+            b'\\' => index += 2,
+            _ => index += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+// NODES
+
+type Ranges = Vec<(NodeKind, u32, u32)>;
+
+fn push_region(kind: NodeKind, region: Region, ranges: &mut Ranges) {
+    ranges.push((kind, region.start().offset, region.end().offset));
+}
+
+fn push_defs(defs: &Defs, ranges: &mut Ranges) {
+    for (index, def) in defs.defs().enumerate() {
+        push_region(NodeKind::Def, defs.regions[index], ranges);
+
+        match def {
+            Ok(type_def) => push_type_def(type_def, ranges),
+            Err(value_def) => push_value_def(value_def, ranges),
+        }
+    }
+}
+
+fn push_type_def(type_def: &TypeDef, ranges: &mut Ranges) {
+    let header = match type_def {
+        TypeDef::Alias { header, ann } => {
+            push_region(NodeKind::TypeAnnotation, ann.region, ranges);
+            header
+        }
+        TypeDef::Opaque { header, typ, .. } => {
+            push_region(NodeKind::TypeAnnotation, typ.region, ranges);
+            header
+        }
+        TypeDef::Ability {
+            header, members, ..
+        } => {
+            for member in members.iter() {
+                push_region(NodeKind::TypeAnnotation, member.typ.region, ranges);
+            }
+
+            header
+        }
+    };
+
+    for var in header.vars.iter() {
+        push_region(NodeKind::Pattern, var.region, ranges);
+    }
+}
+
+fn push_value_def(value_def: &ValueDef, ranges: &mut Ranges) {
+    match value_def {
+        ValueDef::Annotation(pattern, ann) => {
+            push_region(NodeKind::Pattern, pattern.region, ranges);
+            push_region(NodeKind::TypeAnnotation, ann.region, ranges);
+        }
+        ValueDef::Body(pattern, body) => {
+            push_region(NodeKind::Pattern, pattern.region, ranges);
+            push_expr(body, ranges);
+        }
+        ValueDef::AnnotatedBody {
+            ann_pattern,
+            ann_type,
+            body_pattern,
+            body_expr,
+            ..
+        } => {
+            push_region(NodeKind::Pattern, ann_pattern.region, ranges);
+            push_region(NodeKind::TypeAnnotation, ann_type.region, ranges);
+            push_region(NodeKind::Pattern, body_pattern.region, ranges);
+            push_expr(body_expr, ranges);
+        }
+        ValueDef::Expect { condition, .. } | ValueDef::ExpectFx { condition, .. } => {
+            push_expr(condition, ranges)
+        }
+    }
+}
+
+fn push_expr(loc_expr: &Loc<Expr>, ranges: &mut Ranges) {
+    push_region(NodeKind::Expr, loc_expr.region, ranges);
+    push_sub_exprs(&loc_expr.value, ranges);
+}
+
+fn push_sub_exprs(expr: &Expr, ranges: &mut Ranges) {
+    fn push_field(field: &AssignedField<Expr>, ranges: &mut Ranges) {
+        match field {
+            AssignedField::RequiredValue(_, _, value)
+            | AssignedField::OptionalValue(_, _, value) => push_expr(value, ranges),
+            AssignedField::SpaceBefore(field, _) | AssignedField::SpaceAfter(field, _) => {
+                push_field(field, ranges)
+            }
+            AssignedField::LabelOnly(_) | AssignedField::Malformed(_) => {}
+        }
+    }
+
+    match expr {
+        Expr::Access(inner, _)
+        | Expr::SpaceBefore(inner, _)
+        | Expr::SpaceAfter(inner, _)
+        | Expr::ParensAround(inner) => push_sub_exprs(inner, ranges),
+        Expr::UnaryOp(inner, _) => push_expr(inner, ranges),
+        Expr::List(items) => {
+            for item in items.iter() {
+                push_expr(item, ranges);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields.iter() {
+                push_field(&field.value, ranges);
+            }
+        }
+        Expr::RecordUpdate { update, fields } => {
+            push_expr(update, ranges);
+
+            for field in fields.iter() {
+                push_field(&field.value, ranges);
+            }
+        }
+        Expr::Closure(patterns, body) => {
+            for pattern in patterns.iter() {
+                push_region(NodeKind::Pattern, pattern.region, ranges);
+            }
+
+            push_expr(body, ranges);
+        }
+        Expr::Defs(defs, final_expr) => {
+            push_defs(defs, ranges);
+            push_expr(final_expr, ranges);
+        }
+        Expr::Backpassing(patterns, first, second) => {
+            for pattern in patterns.iter() {
+                push_region(NodeKind::Pattern, pattern.region, ranges);
+            }
+
+            push_expr(first, ranges);
+            push_expr(second, ranges);
+        }
+        Expr::Expect(condition, continuation) => {
+            push_expr(condition, ranges);
+            push_expr(continuation, ranges);
+        }
+        Expr::Apply(function, args, _) => {
+            push_expr(function, ranges);
+
+            for arg in args.iter() {
+                push_expr(arg, ranges);
+            }
+        }
+        Expr::BinOps(pairs, last) => {
+            for (operand, _) in pairs.iter() {
+                push_expr(operand, ranges);
+            }
+
+            push_expr(last, ranges);
+        }
+        Expr::If(branches, final_else) => {
+            for (condition, then) in branches.iter() {
+                push_expr(condition, ranges);
+                push_expr(then, ranges);
+            }
+
+            push_expr(final_else, ranges);
+        }
+        Expr::When(condition, branches) => {
+            push_expr(condition, ranges);
+
+            for branch in branches.iter() {
+                for pattern in branch.patterns.iter() {
+                    push_region(NodeKind::Pattern, pattern.region, ranges);
+                }
+
+                if let Some(guard) = &branch.guard {
+                    push_expr(guard, ranges);
+                }
+
+                push_expr(&branch.value, ranges);
+            }
+        }
+        Expr::PrecedenceConflict(conflict) => push_sub_exprs(&conflict.expr.value, ranges),
+        Expr::Float(_)
+        | Expr::Num(_)
+        | Expr::NonBase10Int { .. }
+        | Expr::Str(_)
+        | Expr::AccessorFunction(_)
+        | Expr::SingleQuote(_)
+        | Expr::Var { .. }
+        | Expr::Underscore(_)
+        | Expr::Tag(_)
+        | Expr::OpaqueRef(_)
+        | Expr::MalformedIdent(_, _)
+        | Expr::MalformedClosure
+        | Expr::Malformed(_, _) => {}
+    }
+}
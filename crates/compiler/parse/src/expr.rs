@@ -10,7 +10,7 @@ use crate::keyword;
 use crate::parser::{
     self, backtrackable, optional, sep_by1, sep_by1_e, specialize, specialize_ref, then,
     trailing_sep_by0, word1, word2, EExpect, EExpr, EIf, EInParens, ELambda, EList, ENumber,
-    EPattern, ERecord, EString, EType, EWhen, Either, ParseResult, Parser,
+    EPattern, ERecord, EString, EType, EWhen, Either, ParseResult, Parser, SyntaxError,
 };
 use crate::pattern::{loc_closure_param, loc_has_parser};
 use crate::state::State;
@@ -1547,8 +1547,9 @@ fn parse_expr_operator<'a>(
                     }
                 }
             }
-            Err((NoProgress, expr, e)) => {
-                todo!("{:?} {:?}", expr, e)
+            Err((NoProgress, fail, state)) => {
+                // an operator without an expression after it, e.g. `(2 +)`
+                Err((MadeProgress, fail, state))
             }
         },
     }
@@ -1865,6 +1866,7 @@ fn expr_to_pattern_help<'a>(arena: &'a Bump, expr: &Expr<'a>) -> Result<Pattern<
         | Expr::When(_, _)
         | Expr::Expect(_, _)
         | Expr::MalformedClosure
+        | Expr::Malformed(_, _)
         | Expr::PrecedenceConflict { .. }
         | Expr::RecordUpdate { .. }
         | Expr::UnaryOp(_, _) => Err(()),
@@ -2488,7 +2490,9 @@ fn list_literal_help<'a>(min_indent: u32) -> impl Parser<'a, Expr<'a>, EList<'a>
             word1(b'[', EList::Open),
             specialize_ref(
                 EList::Expr,
-                move |a, s| parse_loc_expr_no_multi_backpassing(min_indent, a, s)
+                collection_item(b']', move |a, s| {
+                    parse_loc_expr_no_multi_backpassing(min_indent, a, s)
+                })
             ),
             word1(b',', EList::End),
             word1(b']', EList::End),
@@ -2506,6 +2510,99 @@ fn list_literal_help<'a>(min_indent: u32) -> impl Parser<'a, Expr<'a>, EList<'a>
     }
 }
 
+/// When recovering from syntax errors, an item of a collection that does not parse becomes an
+/// `Expr::Malformed` of its source code, up to the next `,` or the closing brace.
+fn collection_item<'a, P>(closing_brace: u8, parser: P) -> impl Parser<'a, Loc<Expr<'a>>, EExpr<'a>>
+where
+    P: Parser<'a, Loc<Expr<'a>>, EExpr<'a>>,
+{
+    move |arena: &'a Bump, state: State<'a>| {
+        let initial = state.clone();
+
+        match parser.parse(arena, state) {
+            Err((progress, fail, state)) if initial.recover => {
+                let bytes = initial.bytes();
+                let len = match malformed_item_len(bytes, closing_brace) {
+                    Some(len) => len,
+                    None => return Err((progress, fail, state)),
+                };
+
+                // SAFETY: the item ends at an ASCII character, so at a char boundary
+                let string = unsafe { std::str::from_utf8_unchecked(&bytes[..len]) }.trim_end();
+
+                if string.is_empty() {
+                    return Err((progress, fail, state));
+                }
+
+                let mut state = initial.clone();
+                for byte in string.bytes() {
+                    state = match byte {
+                        b'\n' => state.advance_newline(),
+                        _ => state.advance(1),
+                    };
+                }
+
+                let problem = arena.alloc(SyntaxError::Expr(fail, initial.pos()));
+                let region = Region::new(initial.pos(), state.pos());
+
+                Ok((
+                    MadeProgress,
+                    Loc::at(region, Expr::Malformed(string, problem)),
+                    state,
+                ))
+            }
+            result => result,
+        }
+    }
+}
+
+/// The length of a collection item that does not parse: up to the next `,` or `closing_brace`
+/// that is not nested in brackets, strings or comments. There is none when some other bracket
+/// closes first, or a new line starts at the very first column, like a top-level def does.
+fn malformed_item_len(bytes: &[u8], closing_brace: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth > 0 => depth -= 1,
+            b',' if depth == 0 => return Some(index),
+            _ if byte == closing_brace && depth == 0 => return Some(index),
+            b')' | b']' | b'}' => return None,
+            b'"' => {
+                index += 1;
+                while let Some(&byte) = bytes.get(index) {
+                    match byte {
+                        b'"' | b'\n' => break,
+                        b'\\' => index += 2,
+                        _ => index += 1,
+                    }
+                }
+                if bytes.get(index) == Some(&b'"') {
+                    index += 1;
+                }
+                continue;
+            }
+            b'#' => {
+                while !matches!(bytes.get(index), None | Some(b'\n')) {
+                    index += 1;
+                }
+                continue;
+            }
+            b'\n' => match bytes.get(index + 1) {
+                Some(b' ' | b'\n' | b'\r' | b')' | b']' | b'}') | None => {}
+                Some(_) => return None,
+            },
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
 pub fn record_value_field<'a>(
     min_indent: u32,
 ) -> impl Parser<'a, AssignedField<'a, Expr<'a>>, ERecord<'a>> {
@@ -2528,9 +2625,12 @@ pub fn record_value_field<'a>(
                 word1(b'?', ERecord::QuestionMark)
             ),
             space0_before_e(
-                specialize_ref(ERecord::Expr, move |a, s| {
-                    parse_loc_expr_no_multi_backpassing(min_indent, a, s)
-                }),
+                specialize_ref(
+                    ERecord::Expr,
+                    collection_item(b'}', move |a, s| {
+                        parse_loc_expr_no_multi_backpassing(min_indent, a, s)
+                    })
+                ),
                 min_indent,
                 ERecord::IndentEnd,
             )
@@ -6,6 +6,7 @@
 pub mod parser;
 pub mod ast;
 pub mod blankspace;
+pub mod cst;
 pub mod expr;
 pub mod header;
 pub mod ident;
@@ -1,4 +1,4 @@
-use crate::ast::{Collection, CommentOrNewline, Defs, Module, Spaced};
+use crate::ast::{Collection, CommentOrNewline, Defs, Expr, Module, Spaced, ValueDef};
 use crate::blankspace::{space0_around_ee, space0_before_e, space0_e};
 use crate::header::{
     package_entry, package_name, AppHeader, ExposedName, HostedHeader, ImportsEntry,
@@ -14,7 +14,7 @@ use crate::parser::{
 use crate::state::State;
 use crate::string_literal;
 use crate::type_annotation;
-use roc_region::all::{Loc, Position};
+use roc_region::all::{Loc, Position, Region};
 
 fn end_of_file<'a>() -> impl Parser<'a, (), SyntaxError<'a>> {
     |_arena, state: State<'a>| {
@@ -38,6 +38,365 @@ pub fn module_defs<'a>() -> impl Parser<'a, Defs<'a>, SyntaxError<'a>> {
     )
 }
 
+/// Parse the defs of a module, recovering from syntax errors rather than stopping at the first
+/// one.
+///
+/// A top-level def that does not parse is left out, unless it starts with `pattern =`, in
+/// which case its body becomes an `Expr::Malformed`. Items of lists and records that do not
+/// parse become `Expr::Malformed` too. Parsing continues at the next top-level def, and all
+/// syntax errors are returned in the order they appear in the source.
+pub fn parse_module_defs<'a>(
+    arena: &'a bumpalo::Bump,
+    state: State<'a>,
+) -> (Defs<'a>, Vec<SourceError<'a, SyntaxError<'a>>>) {
+    let state = state.recovering();
+    let bytes = state.original_bytes();
+    let mut defs = Defs::default();
+    let mut problems = Vec::new();
+    let mut start = state.pos().offset as usize;
+
+    loop {
+        let rest = if start == state.pos().offset as usize {
+            state.clone()
+        } else {
+            state.at_line_start(start)
+        };
+
+        let rest_fail = match module_defs().parse(arena, rest.clone()) {
+            Ok((_, parsed, _)) => {
+                append_defs(&mut defs, &parsed, &mut problems, &state);
+                return (defs, problems);
+            }
+            Err((_, fail, _)) => fail,
+        };
+
+        // Look for the longest run of top-level defs that does parse. The whole rest of the
+        // module does not, and no defs at all always do.
+        let mut ends = vec![start];
+        ends.extend(def_starts(bytes, start));
+        ends.push(bytes.len());
+
+        let mut lo = 0;
+        let mut hi = ends.len() - 1;
+
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+
+            if module_defs().parse(arena, rest.truncate(ends[mid])).is_ok() {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        if let Ok((_, parsed, _)) = module_defs().parse(arena, rest.truncate(ends[lo])) {
+            append_defs(&mut defs, &parsed, &mut problems, &state);
+        }
+
+        // The def from ends[lo] to ends[hi] is broken; parse it on its own to find its error.
+        let (chunk_start, chunk_end) = (ends[lo], ends[hi]);
+        let chunk = if chunk_start == start {
+            rest.truncate(chunk_end)
+        } else {
+            state.at_line_start(chunk_start).truncate(chunk_end)
+        };
+
+        let fail = match module_defs().parse(arena, chunk.clone()) {
+            Err((_, fail, _)) => fail,
+            Ok(_) => rest_fail,
+        };
+
+        let fail: &'a SyntaxError<'a> = arena.alloc(fail);
+
+        if let Some((loc_pattern, body)) = malformed_body(arena, chunk, fail) {
+            push_malformed_body(arena, &mut defs, loc_pattern, body);
+        }
+
+        problems.push(fail.clone().into_source_error(&state));
+
+        if chunk_end == bytes.len() {
+            return (defs, problems);
+        }
+
+        start = chunk_end;
+    }
+}
+
+/// The offsets of the lines after `start` that begin a top-level def, i.e. that start with a
+/// character that can begin a def in the first column. Lines in multiline strings are skipped.
+fn def_starts(bytes: &[u8], start: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut in_block_string = false;
+    let mut index = start;
+
+    while index < bytes.len() {
+        let line_start = index == 0 || bytes[index - 1] == b'\n';
+
+        if in_block_string {
+            if bytes[index..].starts_with(b"\"\"\"") {
+                in_block_string = false;
+                index += 3;
+            } else {
+                index += 1;
+            }
+
+            continue;
+        }
+
+        if line_start && index > start {
+            match bytes[index] {
+                b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'{' | b'@' => starts.push(index),
+                _ => {}
+            }
+        }
+
+        match bytes[index] {
+            b'#' => {
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    index += 1;
+                }
+            }
+            b'"' if bytes[index..].starts_with(b"\"\"\"") => {
+                in_block_string = true;
+                index += 3;
+            }
+            b'"' => {
+                index += 1;
+
+                while index < bytes.len() && !matches!(bytes[index], b'"' | b'\n') {
+                    if bytes[index] == b'\\' {
+                        index += 1;
+                    }
+
+                    index += 1;
+                }
+
+                if index < bytes.len() && bytes[index] == b'"' {
+                    index += 1;
+                }
+            }
+            _ => {
+                index += 1;
+            }
+        }
+    }
+
+    starts
+}
+
+/// If the broken def in `chunk` starts with `pattern =`, that pattern and the rest of the def
+/// as an `Expr::Malformed`.
+fn malformed_body<'a>(
+    arena: &'a bumpalo::Bump,
+    chunk: State<'a>,
+    fail: &'a SyntaxError<'a>,
+) -> Option<(Loc<crate::ast::Pattern<'a>>, Loc<Expr<'a>>)> {
+    use crate::parser::EPattern;
+
+    let (_, _, state) = space0_e(0, EPattern::IndentStart)
+        .parse(arena, chunk)
+        .ok()?;
+    let (_, loc_pattern, state) = crate::pattern::loc_pattern_help(0)
+        .parse(arena, state)
+        .ok()?;
+    let (_, _, state) = space0_e(0, EPattern::IndentEnd).parse(arena, state).ok()?;
+
+    let rest = state.bytes();
+
+    if rest.first() != Some(&b'=') || matches!(rest.get(1), Some(b'=') | Some(b'>')) {
+        return None;
+    }
+
+    let text = std::str::from_utf8(&rest[1..]).ok()?;
+    let leading = text.len() - text.trim_start().len();
+    let text = text.trim();
+
+    let start = state.pos().offset as usize + 1 + leading;
+    let region = Region::new(
+        Position::new(start as u32),
+        Position::new((start + text.len()) as u32),
+    );
+
+    Some((loc_pattern, Loc::at(region, Expr::Malformed(text, fail))))
+}
+
+fn push_malformed_body<'a>(
+    arena: &'a bumpalo::Bump,
+    defs: &mut Defs<'a>,
+    loc_pattern: Loc<crate::ast::Pattern<'a>>,
+    body: Loc<Expr<'a>>,
+) {
+    let region = Region::span_across(&loc_pattern.region, &body.region);
+
+    match defs.last() {
+        Some(Err(ValueDef::Annotation(ann_pattern, ann_type))) => {
+            // join the body with its annotation, like the parser does
+            let value_def = ValueDef::AnnotatedBody {
+                ann_pattern: arena.alloc(*ann_pattern),
+                ann_type: arena.alloc(*ann_type),
+                comment: None,
+                body_pattern: arena.alloc(loc_pattern),
+                body_expr: arena.alloc(body),
+            };
+
+            let region = Region::span_across(&ann_pattern.region, &region);
+
+            defs.replace_with_value_def(defs.tags.len() - 1, value_def, region)
+        }
+        _ => {
+            let value_def = ValueDef::Body(arena.alloc(loc_pattern), arena.alloc(body));
+
+            defs.push_value_def(value_def, region, &[], &[])
+        }
+    }
+}
+
+/// Add the defs of `from` to `defs`, and the syntax errors of the `Expr::Malformed`s in them
+/// to `problems`.
+fn append_defs<'a>(
+    defs: &mut Defs<'a>,
+    from: &Defs<'a>,
+    problems: &mut Vec<SourceError<'a, SyntaxError<'a>>>,
+    state: &State<'a>,
+) {
+    let mut errors = Vec::new();
+
+    for (index, def) in from.defs().enumerate() {
+        let region = from.regions[index];
+        let before = &from.spaces[from.space_before[index].indices()];
+        let after = &from.spaces[from.space_after[index].indices()];
+
+        match def {
+            Ok(type_def) => defs.push_type_def(*type_def, region, before, after),
+            Err(value_def) => {
+                value_def_errors(value_def, &mut errors);
+                defs.push_value_def(*value_def, region, before, after)
+            }
+        }
+    }
+
+    problems.extend(
+        errors
+            .into_iter()
+            .map(|error| error.clone().into_source_error(state)),
+    );
+}
+
+fn value_def_errors<'a>(value_def: &ValueDef<'a>, errors: &mut Vec<&'a SyntaxError<'a>>) {
+    match value_def {
+        ValueDef::Annotation(_, _) => {}
+        ValueDef::Body(_, body_expr) | ValueDef::AnnotatedBody { body_expr, .. } => {
+            expr_errors(&body_expr.value, errors)
+        }
+        ValueDef::Expect { condition, .. } | ValueDef::ExpectFx { condition, .. } => {
+            expr_errors(&condition.value, errors)
+        }
+    }
+}
+
+fn expr_errors<'a>(expr: &Expr<'a>, errors: &mut Vec<&'a SyntaxError<'a>>) {
+    use crate::ast::AssignedField;
+
+    fn field_errors<'a>(
+        field: &AssignedField<'a, Expr<'a>>,
+        errors: &mut Vec<&'a SyntaxError<'a>>,
+    ) {
+        match field {
+            AssignedField::RequiredValue(_, _, value)
+            | AssignedField::OptionalValue(_, _, value) => expr_errors(&value.value, errors),
+            AssignedField::SpaceBefore(field, _) | AssignedField::SpaceAfter(field, _) => {
+                field_errors(field, errors)
+            }
+            AssignedField::LabelOnly(_) | AssignedField::Malformed(_) => {}
+        }
+    }
+
+    match expr {
+        Expr::Malformed(_, error) => errors.push(error),
+        Expr::Access(inner, _)
+        | Expr::SpaceBefore(inner, _)
+        | Expr::SpaceAfter(inner, _)
+        | Expr::ParensAround(inner) => expr_errors(inner, errors),
+        Expr::UnaryOp(inner, _) | Expr::Closure(_, inner) => expr_errors(&inner.value, errors),
+        Expr::List(items) => {
+            for item in items.iter() {
+                expr_errors(&item.value, errors);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields.iter() {
+                field_errors(&field.value, errors);
+            }
+        }
+        Expr::RecordUpdate { update, fields } => {
+            expr_errors(&update.value, errors);
+
+            for field in fields.iter() {
+                field_errors(&field.value, errors);
+            }
+        }
+        Expr::Defs(defs, final_expr) => {
+            for def in defs.defs() {
+                if let Err(value_def) = def {
+                    value_def_errors(value_def, errors);
+                }
+            }
+
+            expr_errors(&final_expr.value, errors);
+        }
+        Expr::Backpassing(_, first, second) | Expr::Expect(first, second) => {
+            expr_errors(&first.value, errors);
+            expr_errors(&second.value, errors);
+        }
+        Expr::Apply(function, args, _) => {
+            expr_errors(&function.value, errors);
+
+            for arg in args.iter() {
+                expr_errors(&arg.value, errors);
+            }
+        }
+        Expr::BinOps(pairs, last) => {
+            for (operand, _) in pairs.iter() {
+                expr_errors(&operand.value, errors);
+            }
+
+            expr_errors(&last.value, errors);
+        }
+        Expr::If(branches, final_else) => {
+            for (condition, then) in branches.iter() {
+                expr_errors(&condition.value, errors);
+                expr_errors(&then.value, errors);
+            }
+
+            expr_errors(&final_else.value, errors);
+        }
+        Expr::When(condition, branches) => {
+            expr_errors(&condition.value, errors);
+
+            for branch in branches.iter() {
+                if let Some(guard) = &branch.guard {
+                    expr_errors(&guard.value, errors);
+                }
+
+                expr_errors(&branch.value.value, errors);
+            }
+        }
+        Expr::PrecedenceConflict(conflict) => expr_errors(&conflict.expr.value, errors),
+        Expr::Float(_)
+        | Expr::Num(_)
+        | Expr::NonBase10Int { .. }
+        | Expr::Str(_)
+        | Expr::AccessorFunction(_)
+        | Expr::SingleQuote(_)
+        | Expr::Var { .. }
+        | Expr::Underscore(_)
+        | Expr::Tag(_)
+        | Expr::OpaqueRef(_)
+        | Expr::MalformedIdent(_, _)
+        | Expr::MalformedClosure => {}
+    }
+}
+
 pub fn parse_header<'a>(
     arena: &'a bumpalo::Bump,
     state: State<'a>,
@@ -14,6 +14,10 @@ pub struct State<'a> {
 
     /// Position of the start of the current line
     pub(crate) line_start: Position,
+
+    /// Whether collections turn items that do not parse into `Expr::Malformed`, rather than
+    /// failing altogether
+    pub(crate) recover: bool,
 }
 
 impl<'a> State<'a> {
@@ -22,6 +26,32 @@ impl<'a> State<'a> {
             original_bytes: bytes,
             offset: 0,
             line_start: Position::zero(),
+            recover: false,
+        }
+    }
+
+    /// Parse collections like this state was made with `State::new`, but turn their items that
+    /// do not parse into `Expr::Malformed` rather than failing altogether.
+    pub fn recovering(mut self) -> State<'a> {
+        self.recover = true;
+        self
+    }
+
+    /// The state at `offset`, which must be the start of a line, e.g. to continue parsing after
+    /// code that could not be parsed.
+    pub(crate) fn at_line_start(&self, offset: usize) -> State<'a> {
+        State {
+            offset,
+            line_start: Position::new(offset as u32),
+            ..self.clone()
+        }
+    }
+
+    /// The state with the input cut off at `end`, e.g. to parse only the defs before it.
+    pub(crate) fn truncate(&self, end: usize) -> State<'a> {
+        State {
+            original_bytes: &self.original_bytes[..end],
+            ..self.clone()
         }
     }
 
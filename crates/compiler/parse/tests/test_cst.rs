@@ -0,0 +1,284 @@
+#[macro_use]
+extern crate indoc;
+
+#[cfg(test)]
+mod test_cst {
+    use roc_parse::cst::{Cst, Element, NodeKind, TokenKind};
+    use roc_test_utils::workspace_root;
+
+    fn texts_of_kind(cst: &Cst, kind: NodeKind) -> Vec<String> {
+        cst.nodes()
+            .filter(|(_, node)| node.kind == kind)
+            .map(|(id, _)| cst.node_text(id).to_string())
+            .collect()
+    }
+
+    fn assert_children_cover_nodes(cst: &Cst) {
+        for (id, node) in cst.nodes() {
+            let mut text = String::new();
+
+            for child in node.children.iter() {
+                match child {
+                    Element::Node(child) => text.push_str(cst.node_text(*child)),
+                    Element::Token(token) => text.push_str(cst.token_text(*token)),
+                }
+            }
+
+            assert_eq!(text, cst.node_text(id));
+        }
+    }
+
+    #[test]
+    fn round_trip_every_roc_file() {
+        let root = workspace_root();
+        let mut count = 0;
+
+        let entries = walkdir::WalkDir::new(&root)
+            .into_iter()
+            .filter_entry(|entry| !matches!(entry.file_name().to_str(), Some("target" | ".git")));
+
+        for entry in entries {
+            let entry = entry.unwrap();
+            let path = entry.path();
+
+            if path.extension() == Some(std::ffi::OsStr::new("roc")) {
+                count += 1;
+
+                let src = std::fs::read_to_string(path).unwrap();
+                let cst = Cst::parse(&src);
+
+                assert_eq!(cst.print(), src, "{} did not round-trip", path.display());
+                assert_children_cover_nodes(&cst);
+            }
+        }
+
+        assert!(
+            count > 0,
+            "Expecting to find at least 1 .roc file under {}",
+            root.display()
+        );
+    }
+
+    #[test]
+    fn defs_exprs_and_patterns() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [x, f] imports []
+
+            x : Num *
+            x = 1
+
+            f = \a -> a + x
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        assert_eq!(
+            texts_of_kind(&cst, NodeKind::Header),
+            ["interface Foo exposes [x, f] imports []"]
+        );
+        assert_eq!(
+            texts_of_kind(&cst, NodeKind::Def),
+            ["x : Num *\nx = 1", "f = \\a -> a + x"]
+        );
+        assert_eq!(texts_of_kind(&cst, NodeKind::TypeAnnotation), ["Num *"]);
+        assert_eq!(texts_of_kind(&cst, NodeKind::Pattern), ["x", "x", "f", "a"]);
+        assert_eq!(
+            texts_of_kind(&cst, NodeKind::Expr),
+            ["1", "\\a -> a + x", "a + x", "a", "x"]
+        );
+        assert_eq!(cst.print(), src);
+    }
+
+    #[test]
+    fn whitespace_and_comments_are_tokens() {
+        let src = "interface Foo exposes [] imports []\n\n## The answer\nx = 42 # not 43\r\n";
+        let cst = Cst::parse(src);
+
+        let kinds: Vec<_> = cst
+            .tokens()
+            .skip_while(|(_, token)| token.kind != TokenKind::DocComment)
+            .map(|(id, token)| (token.kind, cst.token_text(id)))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (TokenKind::DocComment, "## The answer"),
+                (TokenKind::Newline, "\n"),
+                (TokenKind::LowercaseIdent, "x"),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Operator, "="),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Number, "42"),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::LineComment, "# not 43"),
+                (TokenKind::Newline, "\r\n"),
+            ]
+        );
+
+        let (def, _) = cst
+            .nodes()
+            .find(|(_, node)| node.kind == NodeKind::Def)
+            .unwrap();
+        let comments: Vec<_> = cst
+            .comments_before(def)
+            .into_iter()
+            .map(|id| cst.token_text(id))
+            .collect();
+
+        assert_eq!(comments, ["## The answer"]);
+        assert_eq!(cst.print(), src);
+    }
+
+    #[test]
+    fn tokens_in_a_region() {
+        let src = "interface Foo exposes [] imports []\n\ntotal = List.sum numbers\n";
+        let cst = Cst::parse(src);
+
+        let start = src.find("List").unwrap();
+        let texts: Vec<_> = cst
+            .tokens_in(start + 2..start + 8)
+            .map(|(id, _)| cst.token_text(id))
+            .collect();
+
+        assert_eq!(texts, ["List", ".", "sum"]);
+        assert_eq!(cst.tokens_in(start..start).count(), 0);
+    }
+
+    #[test]
+    fn strings_with_interpolation_are_one_token() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            greeting = "Hi, \(Str.concat "dear " name)!"
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        let strings: Vec<_> = cst
+            .tokens()
+            .filter(|(_, token)| token.kind == TokenKind::Str)
+            .map(|(id, _)| cst.token_text(id))
+            .collect();
+
+        assert_eq!(strings, [r#""Hi, \(Str.concat "dear " name)!""#]);
+        assert_eq!(cst.print(), src);
+    }
+
+    #[test]
+    fn syntax_errors_keep_the_source() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            x = [1, 2
+
+            y = 2
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        assert_eq!(texts_of_kind(&cst, NodeKind::Def), ["x = [1, 2", "y = 2"]);
+        assert_eq!(cst.print(), src);
+    }
+
+    #[test]
+    fn children_cover_the_node() {
+        let src = indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main =
+                # add them up
+                List.sum [1, 2, 3]
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        assert_children_cover_nodes(&cst);
+        assert_eq!(cst.print(), src);
+    }
+
+    #[test]
+    fn rewrite_nodes_and_tokens() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [total] imports []
+
+            # the total
+            total = List.sum numbers
+
+            numbers = [1, 2, 3]
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        let mut rewriter = cst.rewriter();
+
+        for (id, token) in cst.tokens() {
+            if token.kind == TokenKind::LowercaseIdent && cst.token_text(id) == "numbers" {
+                rewriter.replace_token(id, "nums");
+            }
+        }
+
+        let (list, _) = cst
+            .nodes()
+            .find(|(id, node)| node.kind == NodeKind::Expr && cst.node_text(*id) == "[1, 2, 3]")
+            .unwrap();
+
+        rewriter.replace(list, "List.range 1 4");
+
+        assert_eq!(
+            rewriter.finish(),
+            indoc!(
+                r#"
+                interface Foo exposes [total] imports []
+
+                # the total
+                total = List.sum nums
+
+                nums = List.range 1 4
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn edits_in_replaced_code_are_ignored() {
+        let src = indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            x = 1 + 2
+            "#
+        );
+        let cst = Cst::parse(src);
+
+        let exprs: Vec<_> = cst
+            .nodes()
+            .filter(|(_, node)| node.kind == NodeKind::Expr)
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut rewriter = cst.rewriter();
+
+        rewriter
+            .insert_before(exprs[0], "(")
+            .insert_after(exprs[0], ")")
+            .replace(exprs[1], "10")
+            .replace(exprs[0], "3");
+
+        assert_eq!(
+            rewriter.finish(),
+            indoc!(
+                r#"
+                interface Foo exposes [] imports []
+
+                x = (3)
+                "#
+            )
+        );
+    }
+}
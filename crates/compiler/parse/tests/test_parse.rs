@@ -19,7 +19,7 @@ mod test_parse {
     use roc_parse::ast::StrLiteral::*;
     use roc_parse::ast::StrSegment::*;
     use roc_parse::ast::{self, EscapedChar};
-    use roc_parse::module::module_defs;
+    use roc_parse::module::{module_defs, parse_module_defs};
     use roc_parse::parser::{Parser, SyntaxError};
     use roc_parse::state::State;
     use roc_parse::test_helpers::parse_expr_with;
@@ -833,6 +833,123 @@ mod test_parse {
         }
     }
 
+    #[test]
+    fn recover_at_top_level_defs() {
+        let arena = &Bump::new();
+        let src = indoc!(
+            r#"
+            a = 1
+
+            b = foo (
+
+            c : Str
+            c = if
+
+            d = [1, 2
+
+            e = 3
+            "#
+        );
+
+        let (defs, problems) = parse_module_defs(arena, State::new(src.as_bytes()));
+
+        let bodies: std::vec::Vec<_> = defs
+            .defs()
+            .map(|def| match def {
+                Err(ast::ValueDef::Body(pattern, body)) => (pattern.value, body.value),
+                Err(ast::ValueDef::AnnotatedBody {
+                    body_pattern,
+                    body_expr,
+                    ..
+                }) => (body_pattern.value, body_expr.value),
+                other => panic!("unexpected def {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(bodies.len(), 5);
+        assert_eq!(bodies[0].0, ast::Pattern::Identifier("a"));
+        assert_eq!(bodies[0].1, Num("1"));
+        assert!(matches!(bodies[1].1, Malformed("foo (", _)));
+        assert!(matches!(bodies[2].1, Malformed("if", _)));
+        assert!(matches!(bodies[3].1, Malformed("[1, 2", _)));
+        assert_eq!(bodies[4].0, ast::Pattern::Identifier("e"));
+        assert_eq!(bodies[4].1, Num("3"));
+        assert_eq!(problems.len(), 3);
+    }
+
+    #[test]
+    fn recover_in_collections() {
+        let arena = &Bump::new();
+        let src = indoc!(
+            r#"
+            x = [1, (2 +), 3]
+
+            y = { a: 1, b: if, c: 3 }
+            "#
+        );
+
+        let (defs, problems) = parse_module_defs(arena, State::new(src.as_bytes()));
+
+        let bodies: std::vec::Vec<_> = defs
+            .defs()
+            .map(|def| match def {
+                Err(ast::ValueDef::Body(_, body)) => body.value,
+                other => panic!("unexpected def {:?}", other),
+            })
+            .collect();
+
+        match bodies[0] {
+            List(items) => {
+                assert_eq!(items.len(), 3);
+                assert!(matches!(items.items[1].value, Malformed("(2 +)", _)));
+                assert_eq!(items.items[2].value, Num("3"));
+            }
+            other => panic!("unexpected body {:?}", other),
+        }
+
+        match bodies[1] {
+            Record(fields) => {
+                assert_eq!(fields.len(), 3);
+                assert!(matches!(
+                    fields.items[1].value,
+                    ast::AssignedField::RequiredValue(
+                        _,
+                        _,
+                        Loc {
+                            value: Malformed("if", _),
+                            ..
+                        }
+                    )
+                ));
+            }
+            other => panic!("unexpected body {:?}", other),
+        }
+
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn recover_without_syntax_errors() {
+        let arena = &Bump::new();
+        let src = indoc!(
+            r#"
+            # the answer
+            x = [1, 2, 3]
+
+            y = { a: 1 }
+            "#
+        );
+
+        let expected = module_defs()
+            .parse(arena, State::new(src.as_bytes()))
+            .map(|tuple| tuple.1)
+            .unwrap();
+        let (actual, problems) = parse_module_defs(arena, State::new(src.as_bytes()));
+
+        assert_eq!(actual, expected);
+        assert!(problems.is_empty());
+    }
+
     #[test]
     fn parse_expr_size() {
         assert_eq!(std::mem::size_of::<roc_parse::ast::Expr>(), 40);
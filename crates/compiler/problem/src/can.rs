@@ -32,6 +32,18 @@ pub enum ShadowKind {
 pub enum Problem {
     UnusedDef(Symbol, Region),
     UnusedImport(ModuleId, Region),
+    /// A value or type that is exposed, but that nothing reachable from the entry points of the
+    /// program uses. Only the whole-program analysis of `roc check --unused` reports this.
+    UnusedExposed(Symbol, Region),
+    /// A top-level definition that is used, but only by code that is unreachable itself
+    UnreachableDef(Symbol, Region),
+    /// An implementation of an ability member that nothing reachable uses, because either the
+    /// opaque type or the ability is unreachable
+    UnusedImplementation {
+        opaque: Symbol,
+        ability_member: Symbol,
+        region: Region,
+    },
     ExposedButNotDefined(Symbol),
     UnknownGeneratesWith(Loc<Ident>),
     /// First symbol is the name of the closure with that argument
@@ -178,6 +190,98 @@ pub enum Problem {
     },
 }
 
+impl Problem {
+    /// The region of the source code the problem is about, if there is one.
+    pub fn region(&self) -> Option<Region> {
+        use Problem::*;
+
+        match self {
+            UnusedDef(_, region)
+            | UnusedImport(_, region)
+            | UnusedExposed(_, region)
+            | UnreachableDef(_, region)
+            | UnusedImplementation { region, .. }
+            | UnusedArgument(_, _, _, region)
+            | UnsupportedPattern(_, region)
+            | CyclicAlias(_, region, _, _)
+            | InvalidInterpolation(region)
+            | InvalidHexadecimal(region)
+            | InvalidUnicodeCodePt(region)
+            | AbilityUsedAsType(_, _, region)
+            | NestedSpecialization(_, region)
+            | IllegalDerivedAbility(region)
+            | NotAnAbility(region)
+            | NoIdentifiersIntroduced(region)
+            | InvalidAliasRigid { region, .. }
+            | InvalidExtensionType { region, .. }
+            | HasClauseIsNotAbility { region }
+            | IllegalHasClause { region }
+            | AbilityMemberMissingHasClause { region, .. }
+            | AbilityNotOnToplevel { region }
+            | ImplementationNotFound { region, .. }
+            | NotAnAbilityMember { region, .. }
+            | OptionalAbilityImpl { region, .. }
+            | QualifiedAbilityImpl { region }
+            | AbilityImplNotIdent { region }
+            | ImplementsNonRequired { region, .. }
+            | DoesNotImplementAbility { region, .. }
+            | NotBoundInAllPatterns { region, .. }
+            | PhantomTypeArgument {
+                variable_region: region,
+                ..
+            }
+            | UnboundTypeVariable {
+                one_occurrence: region,
+                ..
+            }
+            | DuplicateRecordFieldValue {
+                field_region: region,
+                ..
+            }
+            | DuplicateRecordFieldType {
+                field_region: region,
+                ..
+            }
+            | InvalidOptionalValue {
+                field_region: region,
+                ..
+            }
+            | DuplicateTag {
+                tag_region: region, ..
+            }
+            | SignatureDefMismatch {
+                def_pattern: region,
+                ..
+            }
+            | NestedDatatype {
+                def_region: region, ..
+            }
+            | AbilityHasTypeVariables {
+                variables_region: region,
+                ..
+            }
+            | AbilityMemberMultipleBoundVars {
+                span_has_clauses: region,
+                ..
+            }
+            | DuplicateImpl {
+                duplicate: region, ..
+            }
+            | OverloadedSpecialization {
+                overload: region, ..
+            } => Some(*region),
+            UnknownGeneratesWith(loc_ident) => Some(loc_ident.region),
+            Shadowing { shadow, .. } => Some(shadow.region),
+            PrecedenceProblem(self::PrecedenceProblem::BothNonAssociative(region, _, _)) => {
+                Some(*region)
+            }
+            BadRecursion(cycle) => cycle.first().map(|entry| entry.symbol_region),
+            RuntimeError(runtime_error) => runtime_error.region(),
+            ExposedButNotDefined(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ExtensionTypeKind {
     Record,
@@ -308,6 +412,8 @@ pub enum RuntimeError {
     MalformedIdentifier(Box<str>, roc_parse::ident::BadIdent, Region),
     MalformedTypeName(Box<str>, Region),
     MalformedClosure(Region),
+    /// Code that did not parse. The syntax error itself is reported when parsing.
+    MalformedSyntax(Region),
     InvalidRecordUpdate {
         region: Region,
     },
@@ -341,6 +447,57 @@ pub enum RuntimeError {
 }
 
 impl RuntimeError {
+    /// The region of the source code the error is about, if there is one.
+    pub fn region(&self) -> Option<Region> {
+        use RuntimeError::*;
+
+        match self {
+            UnsupportedPattern(region)
+            | MalformedPattern(_, region)
+            | OpaqueAppliedToMultipleArgs(region)
+            | InvalidPrecedence(_, region)
+            | MalformedIdentifier(_, _, region)
+            | MalformedTypeName(_, region)
+            | MalformedClosure(region)
+            | MalformedSyntax(region)
+            | InvalidFloat(_, region, _)
+            | InvalidInt(_, _, region, _)
+            | InvalidInterpolation(region)
+            | InvalidHexadecimal(region)
+            | InvalidUnicodeCodePt(region)
+            | EmptySingleQuote(region)
+            | MultipleCharsInSingleQuote(region)
+            | DegenerateBranch(region)
+            | ValueNotExposed { region, .. }
+            | ModuleNotImported { region, .. }
+            | InvalidRecordUpdate { region }
+            | InvalidOptionalValue {
+                field_region: region,
+                ..
+            }
+            | OpaqueOutsideScope {
+                referenced_region: region,
+                ..
+            } => Some(*region),
+            Shadowing {
+                shadow: loc_ident, ..
+            }
+            | LookupNotInScope(loc_ident, _)
+            | OpaqueNotDefined {
+                usage: loc_ident, ..
+            }
+            | OpaqueNotApplied(loc_ident) => Some(loc_ident.region),
+            CircularDef(cycle) => cycle.first().map(|entry| entry.symbol_region),
+            UnresolvedTypeVar
+            | ErroneousType
+            | NonExhaustivePattern
+            | NoImplementationNamed { .. }
+            | NoImplementation
+            | VoidValue
+            | ExposedButNotDefined(_) => None,
+        }
+    }
+
     pub fn runtime_message(self) -> String {
         use RuntimeError::*;
 
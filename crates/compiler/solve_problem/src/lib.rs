@@ -31,6 +31,44 @@ pub enum TypeError {
     },
 }
 
+impl TypeError {
+    /// The region of the source code the error is about, if there is one.
+    pub fn region(&self) -> Option<Region> {
+        use roc_types::types::Problem;
+        use TypeError::*;
+
+        match self {
+            BadExpr(region, ..)
+            | BadPattern(region, ..)
+            | CircularType(region, ..)
+            | BadExprMissingAbility(region, ..)
+            | BadPatternMissingAbility(region, ..)
+            | StructuralSpecialization { region, .. }
+            | WrongSpecialization { region, .. } => Some(*region),
+            CircularDef(cycle) => cycle.first().map(|entry| entry.symbol_region),
+            BadType(problem) => match problem {
+                Problem::CircularType(_, _, region)
+                | Problem::CyclicAlias(_, region, _)
+                | Problem::BadTypeArguments { region, .. }
+                | Problem::HasClauseIsNotAbility(region) => Some(*region),
+                Problem::Shadowed(_, loc_ident) => Some(loc_ident.region),
+                Problem::CanonicalizationProblem
+                | Problem::UnrecognizedIdent(_)
+                | Problem::InvalidModule
+                | Problem::SolvedTypeError => None,
+            },
+            UnfulfilledAbility(Unfulfilled::OpaqueUnderivable { derive_region, .. }) => {
+                Some(*derive_region)
+            }
+            UnfulfilledAbility(_) | UnexposedLookup(_) => None,
+            Exhaustive(roc_exhaustive::Error::Incomplete(region, ..)) => Some(*region),
+            Exhaustive(roc_exhaustive::Error::Redundant { branch_region, .. }) => {
+                Some(*branch_region)
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Unfulfilled {
     /// No claimed implementation of an ability for an opaque type.
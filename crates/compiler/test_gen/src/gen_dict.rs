@@ -1,15 +1,16 @@
-#![cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#![cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 
 #[cfg(feature = "gen-llvm")]
 use crate::helpers::llvm::assert_evals_to;
 
-// #[cfg(feature = "gen-dev")]
-// use crate::helpers::dev::assert_evals_to;
+#[cfg(feature = "gen-dev")]
+use crate::helpers::dev::assert_evals_to;
 
 #[cfg(feature = "gen-wasm")]
 use crate::helpers::wasm::assert_evals_to;
 
 use indoc::indoc;
+#[allow(unused_imports)]
 use roc_std::{RocList, RocStr};
 
 #[test]
@@ -549,3 +550,147 @@ fn walk_sum_keys() {
         i64
     );
 }
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_int_is_deterministic() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.hashTestOnly 1234i64 42 == Dict.hashTestOnly 1234i64 42
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_int_depends_on_value_and_seed() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            a = Dict.hashTestOnly 1234i64 42
+            b = Dict.hashTestOnly 1235i64 42
+            c = Dict.hashTestOnly 1234i64 43
+
+            a != b && a != c
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_small_int_matches_its_byte() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.hashTestOnly 7u8 0 == Dict.hashTestOnly [7u8] 0
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn hash_str_matches_its_bytes() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            short = "hello"
+            long = "a string long enough to be allocated on the heap"
+
+            Dict.hashTestOnly short 99 == Dict.hashTestOnly (Str.toUtf8 short) 99
+                && Dict.hashTestOnly long 99 == Dict.hashTestOnly (Str.toUtf8 long) 99
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_str_depends_on_contents() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.hashTestOnly "hello" 0 != Dict.hashTestOnly "world" 0
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_list_of_floats() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.hashTestOnly [1.5f64, 2.5] 0 != Dict.hashTestOnly [2.5f64, 1.5] 0
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_record_chains_its_fields() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Dict.hashTestOnly { a: 1u8, b: 2u8 } 5 == Dict.hashTestOnly 2u8 (Dict.hashTestOnly 1u8 5)
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_nested_record_with_str() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            a = Dict.hashTestOnly { name: "Roc", inner: { x: 1u8, y: 2u8 } } 0
+            b = Dict.hashTestOnly { name: "Roc", inner: { x: 2u8, y: 1u8 } } 0
+
+            a != b && a == Dict.hashTestOnly { name: "Roc", inner: { x: 1u8, y: 2u8 } } 0
+            "#
+        ),
+        true,
+        bool
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn hash_tag_union_includes_the_tag() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            x : [A U8, B U8]
+            x = A 1
+
+            y : [A U8, B U8]
+            y = B 1
+
+            Dict.hashTestOnly x 0 != Dict.hashTestOnly y 0
+                && Dict.hashTestOnly x 0 == Dict.hashTestOnly 1u8 (Dict.hashTestOnly 0u8 0)
+            "#
+        ),
+        true,
+        bool
+    );
+}
@@ -484,7 +484,7 @@ fn f64_sqrt() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn f64_log() {
     assert_evals_to!(
         indoc!(
@@ -735,7 +735,7 @@ fn gen_wrap_add_nums() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_div_f64() {
     assert_evals_to!(
         indoc!(
@@ -926,7 +926,7 @@ fn gen_dec_neq() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_wrap_int_neq() {
     assert_evals_to!(
         indoc!(
@@ -1065,7 +1065,7 @@ fn gen_sub_dec() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_sub_f64() {
     assert_evals_to!(
         indoc!(
@@ -1265,14 +1265,14 @@ fn gen_is_even() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn sin() {
     assert_evals_to!("Num.sin 0", 0.0, f64);
     assert_evals_to!("Num.sin 1.41421356237", 0.9877659459922529, f64);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn cos() {
     assert_evals_to!("Num.cos 0", 1.0, f64);
     assert_evals_to!("Num.cos 3.14159265359", -1.0, f64);
@@ -1286,7 +1286,7 @@ fn tan() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn bitwise_and() {
     assert_evals_to!("Num.bitwiseAnd 20 20", 20, i64);
     assert_evals_to!("Num.bitwiseAnd 25 10", 8, i64);
@@ -1294,7 +1294,7 @@ fn bitwise_and() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn bitwise_xor() {
     assert_evals_to!("Num.bitwiseXor 20 20", 0, i64);
     assert_evals_to!("Num.bitwiseXor 15 14", 1, i64);
@@ -1303,14 +1303,14 @@ fn bitwise_xor() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn bitwise_or() {
     assert_evals_to!("Num.bitwiseOr 1 1", 1, i64);
     assert_evals_to!("Num.bitwiseOr 1 2", 3, i64);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lt_u8() {
     assert_evals_to!("1u8 < 2u8", true, bool);
     assert_evals_to!("1u8 < 1u8", false, bool);
@@ -1320,7 +1320,7 @@ fn lt_u8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lte_u8() {
     assert_evals_to!("1u8 <= 1u8", true, bool);
     assert_evals_to!("2u8 <= 1u8", false, bool);
@@ -1330,7 +1330,7 @@ fn lte_u8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gt_u8() {
     assert_evals_to!("2u8 > 1u8", true, bool);
     assert_evals_to!("2u8 > 2u8", false, bool);
@@ -1340,7 +1340,7 @@ fn gt_u8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gte_u8() {
     assert_evals_to!("1u8 >= 1u8", true, bool);
     assert_evals_to!("1u8 >= 2u8", false, bool);
@@ -1350,7 +1350,7 @@ fn gte_u8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lt_u64() {
     assert_evals_to!("1u64 < 2u64", true, bool);
     assert_evals_to!("1u64 < 1u64", false, bool);
@@ -1360,7 +1360,7 @@ fn lt_u64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lte_u64() {
     assert_evals_to!("1u64 <= 1u64", true, bool);
     assert_evals_to!("2u64 <= 1u64", false, bool);
@@ -1370,7 +1370,7 @@ fn lte_u64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gt_u64() {
     assert_evals_to!("2u64 > 1u64", true, bool);
     assert_evals_to!("2u64 > 2u64", false, bool);
@@ -1380,7 +1380,7 @@ fn gt_u64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gte_u64() {
     assert_evals_to!("1u64 >= 1u64", true, bool);
     assert_evals_to!("1u64 >= 2u64", false, bool);
@@ -1390,7 +1390,7 @@ fn gte_u64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lt_i64() {
     assert_evals_to!("1 < 2", true, bool);
     assert_evals_to!("1 < 1", false, bool);
@@ -1399,7 +1399,7 @@ fn lt_i64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn lte_i64() {
     assert_evals_to!("1 <= 1", true, bool);
     assert_evals_to!("2 <= 1", false, bool);
@@ -1408,7 +1408,7 @@ fn lte_i64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gt_i64() {
     assert_evals_to!("2 > 1", true, bool);
     assert_evals_to!("2 > 2", false, bool);
@@ -1417,7 +1417,7 @@ fn gt_i64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gte_i64() {
     assert_evals_to!("1 >= 1", true, bool);
     assert_evals_to!("1 >= 2", false, bool);
@@ -1476,7 +1476,7 @@ fn gen_order_of_arithmetic_ops() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_order_of_arithmetic_ops_complex_float() {
     assert_evals_to!(
         indoc!(
@@ -1583,7 +1583,7 @@ fn neg_min_int_overflow() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_wrap_int_neg() {
     assert_evals_to!(
         indoc!(
@@ -1729,7 +1729,7 @@ fn float_compare() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn pow() {
     assert_evals_to!("Num.pow 2.0 2.0", 4.0, f64);
 }
@@ -1802,7 +1802,7 @@ fn int_add_checked() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn int_add_wrap() {
     assert_evals_to!(
         indoc!(
@@ -1877,7 +1877,7 @@ fn int_sub_overflow() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn int_sub_wrap() {
     assert_evals_to!(
         indoc!(
@@ -2032,6 +2032,46 @@ fn int_mul_wrap() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_int_add_wrap() {
+    assert_evals_to!("Num.addWrap 255u8 1u8", 0, u8);
+    assert_evals_to!("Num.addWrap 127i8 1i8", -128, i8);
+    assert_evals_to!("Num.addWrap 65535u16 2u16", 1, u16);
+    assert_evals_to!("Num.addWrap 4294967295u32 1u32", 0, u32);
+    assert_evals_to!("Num.addWrap 18446744073709551615u64 1u64", 0, u64);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_int_sub_wrap() {
+    assert_evals_to!("Num.subWrap 0u8 1u8", 255, u8);
+    assert_evals_to!("Num.subWrap -128i8 1i8", 127, i8);
+    assert_evals_to!("Num.subWrap 0u64 1u64", u64::MAX, u64);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_int_mul_wrap() {
+    assert_evals_to!("Num.mulWrap 16u8 17u8", 16, u8);
+    assert_evals_to!("Num.mulWrap 64i8 2i8", -128, i8);
+    assert_evals_to!("Num.mulWrap -128i8 -1i8", -128, i8);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_int_wrapped_results_compare_at_their_width() {
+    // The wrapped result must not keep the bits that overflowed out of the width
+    assert_evals_to!("Num.addWrap 255u8 2u8 < 2u8", true, bool);
+    assert_evals_to!("Num.subWrap 0u8 1u8 > 254u8", true, bool);
+    assert_evals_to!("Num.addWrap 127i8 1i8 < 0i8", true, bool);
+    assert_evals_to!("Num.mulWrap 16u8 16u8 == 0u8", true, bool);
+    assert_evals_to!("9223372036854775808u64 > 1u64", true, bool);
+    assert_evals_to!("9223372036854775808u64 >= 1u64", true, bool);
+    assert_evals_to!("1u64 < 9223372036854775808u64", true, bool);
+    assert_evals_to!("1u64 <= 9223372036854775808u64", true, bool);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn int_mul_checked() {
@@ -2723,6 +2763,55 @@ to_int_checked_tests! {
     )
 }
 
+macro_rules! to_float_checked_tests {
+    ($($fn:expr, $typ:ty, ($($test_name:ident, $input:expr, $output:expr)*))*) => {$($(
+        #[test]
+        #[cfg(any(feature = "gen-wasm"))]
+        fn $test_name() {
+            let sentinel = 23.0;
+            // Some n = Ok n, None = OutOfBounds
+            let expected = match $output.into() {
+                None => sentinel,
+                Some(n) => {
+                    assert_ne!(n, sentinel);
+                    n
+                }
+            };
+            let input = format!("Result.withDefault ({} {}) {}", $fn, $input, sentinel);
+            assert_evals_to!(&input, expected, $typ)
+        }
+    )*)*}
+}
+
+to_float_checked_tests! {
+    "Num.toF32Checked", f32, (
+        to_f32_checked_from_i8,                 "-15i8",        -15.0
+        to_f32_checked_from_i64,                "15i64",        15.0
+        to_f32_checked_from_i128,               "15i128",       15.0
+        to_f32_checked_from_u8,                 "15u8",         15.0
+        to_f32_checked_from_u32,                "15u32",        15.0
+        to_f32_checked_from_u128,               "15u128",       15.0
+        to_f32_checked_from_u128_oob,           "Num.maxU128",  None
+        to_f32_checked_from_nat,                "15nat",        15.0
+        to_f32_checked_from_f32,                "1.5f32",       1.5
+        to_f32_checked_from_f64_fits,           "1.5f64",       1.5
+        to_f32_checked_from_f64_oob_pos,        "Num.maxF64",   None
+        to_f32_checked_from_f64_oob_neg,        "Num.minF64",   None
+    )
+    "Num.toF64Checked", f64, (
+        to_f64_checked_from_i8,                 "-15i8",        -15.0
+        to_f64_checked_from_i64,                "15i64",        15.0
+        to_f64_checked_from_i128,               "15i128",       15.0
+        to_f64_checked_from_u8,                 "15u8",         15.0
+        to_f64_checked_from_u32,                "15u32",        15.0
+        to_f64_checked_from_u128,               "Num.maxU128",  340282366920938463463374607431768211455.0
+        to_f64_checked_from_nat,                "15nat",        15.0
+        to_f64_checked_from_f32,                "1.5f32",       1.5
+        to_f64_checked_from_f64,                "1.5f64",       1.5
+        to_f64_checked_from_f64_max,            "Num.maxF64",   1.7976931348623157e308
+    )
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn is_multiple_of_signed() {
@@ -2969,7 +3058,7 @@ fn when_on_i16() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str() {
     use roc_std::RocStr;
 
@@ -2993,7 +3082,7 @@ fn num_to_str() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_u8() {
     use roc_std::RocStr;
 
@@ -3006,7 +3095,7 @@ fn num_to_str_u8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_u16() {
     use roc_std::RocStr;
 
@@ -3023,7 +3112,7 @@ fn num_to_str_u16() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_u32() {
     use roc_std::RocStr;
 
@@ -3040,7 +3129,7 @@ fn num_to_str_u32() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_u64() {
     use roc_std::RocStr;
 
@@ -3057,7 +3146,7 @@ fn num_to_str_u64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_i8() {
     use roc_std::RocStr;
 
@@ -3075,7 +3164,7 @@ fn num_to_str_i8() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_i16() {
     use roc_std::RocStr;
 
@@ -3101,7 +3190,7 @@ fn num_to_str_i16() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_i32() {
     use roc_std::RocStr;
 
@@ -3127,7 +3216,7 @@ fn num_to_str_i32() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_i64() {
     use roc_std::RocStr;
 
@@ -3153,7 +3242,7 @@ fn num_to_str_i64() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_f32() {
     use roc_std::RocStr;
 
@@ -3177,7 +3266,7 @@ fn num_to_str_f32() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn num_to_str_f64() {
     use roc_std::RocStr;
 
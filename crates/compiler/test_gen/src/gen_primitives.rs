@@ -830,6 +830,117 @@ fn linked_list_map() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn linked_list_map_modulo_cons_million() {
+    // `range` and `map` are not tail recursive, but they are tail recursive modulo cons.
+    // Without turning them into loops, this would overflow the stack.
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            LinkedList a : [Nil, Cons a (LinkedList a)]
+
+            range : I64, I64 -> LinkedList I64
+            range = \i, n ->
+                if i > n then
+                    Nil
+                else
+                    Cons i (range (i + 1) n)
+
+            map : (a -> b), LinkedList a -> LinkedList b
+            map = \f, list ->
+                when list is
+                    Nil -> Nil
+                    Cons x rest -> Cons (f x) (map f rest)
+
+            sum : LinkedList I64, I64 -> I64
+            sum = \list, acc ->
+                when list is
+                    Nil -> acc
+                    Cons x rest -> sum rest (acc + x)
+
+            main =
+                sum (map (\x -> 2 * x) (range 1 1_000_000)) 0
+            "#
+        ),
+        1_000_001_000_000,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn linked_list_append_modulo_cons_million() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            LinkedList a : [Nil, Cons a (LinkedList a)]
+
+            repeat : a, I64 -> LinkedList a
+            repeat = \x, n ->
+                if n == 0 then
+                    Nil
+                else
+                    Cons x (repeat x (n - 1))
+
+            append : LinkedList a, LinkedList a -> LinkedList a
+            append = \xs, ys ->
+                when xs is
+                    Nil -> ys
+                    Cons x rest -> Cons x (append rest ys)
+
+            len : LinkedList a, I64 -> I64
+            len = \list, acc ->
+                when list is
+                    Nil -> acc
+                    Cons _ rest -> len rest (acc + 1)
+
+            main =
+                len (append (repeat "a" 1_000_000) (repeat "b" 10)) 0
+            "#
+        ),
+        1_000_010,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn non_nullable_union_modulo_cons_million() {
+    // the tag id of this union is stored in the pointer
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            Chain : [Last I64, Link I64 Chain]
+
+            build : I64, I64 -> Chain
+            build = \i, n ->
+                if i == n then
+                    Last i
+                else
+                    Link i (build (i + 1) n)
+
+            sum : Chain, I64 -> I64
+            sum = \chain, acc ->
+                when chain is
+                    Last x -> acc + x
+                    Link x rest -> sum rest (acc + x)
+
+            main =
+                sum (build 1 1_000_000) 0
+            "#
+        ),
+        500_000_500_000,
+        i64
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn when_nested_maybe() {
@@ -3884,3 +3995,79 @@ fn local_binding_aliases_function_inferred() {
         RocList<std::convert::Infallible>
     );
 }
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn passing_expect_continues() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            x = 1
+
+            expect x == 1
+
+            x
+            "#
+        ),
+        1,
+        i64
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-wasm")]
+#[should_panic(expected = "An expectation failed!")]
+fn failed_expect_panics_in_wasm_tests() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            x = 1
+
+            expect x == 2
+
+            x
+            "#
+        ),
+        1,
+        i64
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-llvm")]
+fn debug_info_points_at_calls_and_arguments() {
+    let llvm_ir = crate::helpers::llvm::llvm_ir_with_debug_info(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        greet : Str, I64 -> Str
+        greet = \name, times ->
+            if times > 0 then
+                Str.concat name "!"
+            else
+                name
+
+        main =
+            greet "Roc" 3
+        "#
+    ));
+
+    for expected in [
+        // the procedure, at its definition
+        r#"DISubprogram(name: "greet""#,
+        "line: 4,",
+        // the arguments, including the ones that are not a single number
+        r#"DILocalVariable(name: "name", arg: 1"#,
+        r#"DILocalVariable(name: "times", arg: 2"#,
+        r#"DICompositeType(tag: DW_TAG_structure_type, name: "Str""#,
+        // the call to `Str.concat`
+        "DILocation(line: 6,",
+    ] {
+        assert!(
+            llvm_ir.contains(expected),
+            "missing {:?} in the debug info:\n\n{}",
+            expected,
+            llvm_ir
+        );
+    }
+}
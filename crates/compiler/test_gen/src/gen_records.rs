@@ -1044,3 +1044,73 @@ fn generalized_accessor() {
         RocStr
     );
 }
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_record_arguments() {
+    // small records are passed in registers by the dev backend, until it runs out of them
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            Point : { x : I64, y : I64 }
+
+            sum : Point, { a : I64 }, Point, Point, Point -> I64
+            sum = \p, { a }, q, r, s -> p.x + p.y + a + q.x + q.y + r.x + r.y + s.x + s.y
+
+            main =
+                sum { x: 1, y: 2 } { a: 3 } { x: 4, y: 5 } { x: 6, y: 7 } { x: 8, y: 9 }
+            "#
+        ),
+        45,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_record_return() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            swap : { x : I64, y : I64 } -> { x : I64, y : I64 }
+            swap = \{ x, y } -> { x: y, y: x }
+
+            main =
+                p = swap { x: 3, y: 10 }
+
+                p.x - p.y
+            "#
+        ),
+        7,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn small_float_record_arguments() {
+    // records of floats are passed and returned in float registers, like in C
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            Vec2 : { x : F64, y : F64 }
+
+            add : Vec2, { scale : F64, count : I64 }, Vec2 -> Vec2
+            add = \p, { scale, count }, q -> { x: p.x + q.x * scale, y: p.y + q.y * Num.toF64 count }
+
+            main =
+                v = add { x: 1.5, y: 2.0 } { scale: 2.0, count: 3 } { x: 0.25, y: 1.0 }
+
+                v.x + v.y
+            "#
+        ),
+        7.0,
+        f64
+    );
+}
@@ -55,6 +55,9 @@ pub fn helper(
         render: roc_reporting::report::RenderTarget::ColorTerminal,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -180,6 +183,7 @@ pub fn helper(
         exposed_to_host: exposed_to_host.values.keys().copied().collect(),
         lazy_literals,
         generate_allocators: true, // Needed for testing, since we don't have a platform
+        mode: roc_gen_dev::AssemblyBackendMode::Test,
     };
 
     let target = target_lexicon::Triple::host();
@@ -6,6 +6,7 @@ use roc_build::link::llvm_module_to_dylib;
 use roc_build::program::FunctionIterator;
 use roc_collections::all::MutSet;
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_gen_llvm::llvm::debug_info::{add_debug_info_flags, DebugSources};
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_load::{EntryPoint, ExecutionMode, LoadConfig, Threading};
 use roc_mono::ir::OptLevel;
@@ -71,6 +72,9 @@ fn create_llvm_module<'a>(
         render: RenderTarget::ColorTerminal,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        inline_and_fold: false,
+        record_call_regions: config.emit_debug_info,
+        collect_package_modules: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -90,6 +94,17 @@ fn create_llvm_module<'a>(
         Err(e) => panic!("{:?}", e),
     };
 
+    // the sources are reported on below, so the debug info needs its own copy
+    let debug_info_sources = if config.emit_debug_info {
+        Some((
+            loaded.sources.clone(),
+            std::mem::take(&mut loaded.def_regions),
+            std::mem::take(&mut loaded.call_regions),
+        ))
+    } else {
+        None
+    };
+
     use roc_load::MonomorphizedModule;
     let MonomorphizedModule {
         procedures,
@@ -181,6 +196,10 @@ fn create_llvm_module<'a>(
 
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
 
+    let debug_sources = debug_info_sources.map(|(sources, def_regions, call_regions)| {
+        DebugSources::new(&dibuilder, &sources, def_regions, call_regions)
+    });
+
     // mark our zig-defined builtins as internal
     use inkwell::attributes::{Attribute, AttributeLoc};
     use inkwell::module::Linkage;
@@ -221,10 +240,15 @@ fn create_llvm_module<'a>(
         mode: config.mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: debug_sources.as_ref(),
+        debug_refcounts: false,
+        profile_allocations: false,
     };
 
     // strip Zig debug stuff
-    module.strip_debug_info();
+    if !config.emit_debug_info {
+        module.strip_debug_info();
+    }
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
     // platform to provide them.
@@ -257,8 +281,12 @@ fn create_llvm_module<'a>(
 
     env.dibuilder.finalize();
 
-    // strip all debug info: we don't use it at the moment and causes weird validation issues
-    module.strip_debug_info();
+    if config.emit_debug_info {
+        add_debug_info_flags(module);
+    } else {
+        // strip all debug info: we don't use it at the moment and causes weird validation issues
+        module.strip_debug_info();
+    }
 
     // Uncomment this to see the module's un-optimized LLVM instruction output:
     // env.module.print_to_stderr();
@@ -298,9 +326,32 @@ pub struct HelperConfig {
     pub mode: LlvmBackendMode,
     pub ignore_problems: bool,
     pub add_debug_info: bool,
+    pub emit_debug_info: bool,
     pub opt_level: OptLevel,
 }
 
+/// Build a program with the debug info of `roc build --debug`, and return the LLVM IR.
+/// Panics if LLVM does not accept the debug info.
+#[allow(dead_code)]
+pub fn llvm_ir_with_debug_info(src: &str) -> String {
+    let arena = bumpalo::Bump::new();
+    let context = inkwell::context::Context::create();
+    let target = target_lexicon::Triple::host();
+
+    let config = HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        add_debug_info: false,
+        emit_debug_info: true,
+        ignore_problems: false,
+        opt_level: OptLevel::Normal,
+    };
+
+    let (_main_fn_name, _delayed_errors, module) =
+        create_llvm_module(&arena, src, config, &context, &target);
+
+    module.print_to_string().to_string()
+}
+
 #[allow(dead_code)]
 #[inline(never)]
 pub fn helper<'a>(
@@ -505,6 +556,7 @@ where
     let config = HelperConfig {
         mode: LlvmBackendMode::WasmGenTest,
         add_debug_info: false,
+        emit_debug_info: false,
         ignore_problems,
         opt_level: OPT_LEVEL,
     };
@@ -554,6 +606,7 @@ macro_rules! assert_llvm_evals_to {
         let config = $crate::helpers::llvm::HelperConfig {
             mode: LlvmBackendMode::GenTest,
             add_debug_info: false,
+            emit_debug_info: false,
             ignore_problems: $ignore_problems,
             opt_level: $crate::helpers::llvm::OPT_LEVEL,
         };
@@ -22,4 +22,7 @@ pub mod wasm_str;
 #[cfg(feature = "gen-wasm")]
 pub mod wasm_linking;
 
+#[cfg(feature = "gen-wasm")]
+pub mod wasm_wat;
+
 pub use helpers::platform_functions::*;
@@ -1,7 +1,7 @@
 #![cfg(feature = "gen-wasm")]
 
 use bumpalo::Bump;
-use roc_gen_wasm::Env;
+use roc_gen_wasm::{Env, WasmBackendMode};
 use std::fs;
 use std::process::Command;
 
@@ -162,6 +162,7 @@ impl<'a> BackendInputs<'a> {
             module_id,
             exposed_to_host,
             stack_bytes: Env::DEFAULT_STACK_BYTES,
+            mode: WasmBackendMode::Binary,
         };
 
         // Identifier stuff for the backend
@@ -0,0 +1,96 @@
+#![cfg(feature = "gen-wasm")]
+
+//! Check that `roc build --emit=wasm-text` renders the modules of real programs, including the
+//! builtins and the test host, as valid WAT that assembles back to an equivalent module
+
+use crate::helpers::wasm::compile_to_wasm_bytes;
+use indoc::indoc;
+use roc_gen_wasm::wasm32_result::Wasm32Result;
+use roc_gen_wasm::wasm_module::wat::binary_to_wat;
+use roc_std::{RocList, RocStr};
+use std::marker::PhantomData;
+
+fn assert_valid_wat<T: Wasm32Result>(src: &str) {
+    let arena = bumpalo::Bump::new();
+    let bytes = compile_to_wasm_bytes(&arena, src, PhantomData::<T>);
+
+    let rendered = binary_to_wat(&bytes).unwrap();
+
+    // The modules are too big to print in the test output
+    let save = || {
+        let path = std::env::temp_dir().join("roc_test_gen.wat");
+        std::fs::write(&path, &rendered).unwrap();
+        path
+    };
+
+    let reassembled = wat::parse_str(&rendered).unwrap_or_else(|e| {
+        panic!(
+            "{}\n\nThe rendering in {} is not valid WAT",
+            e,
+            save().display()
+        )
+    });
+
+    // Assembling renames and re-encodes some things, but the rendering should not change
+    if binary_to_wat(&reassembled).unwrap() != rendered {
+        panic!(
+            "The rendering in {} does not assemble back to the same module",
+            save().display()
+        );
+    }
+}
+
+#[test]
+fn wat_of_numbers() {
+    assert_valid_wat::<i64>(indoc!(
+        r#"
+        x : I64
+        x = 1234
+
+        Num.abs (x - 5000) * 2 + Num.round 2.5f64
+        "#
+    ));
+}
+
+#[test]
+fn wat_of_strings() {
+    assert_valid_wat::<RocStr>(indoc!(
+        r#"
+        Str.joinWith ["Hello", "World", Num.toStr 42] ", "
+        "#
+    ));
+}
+
+#[test]
+fn wat_of_lists_and_closures() {
+    assert_valid_wat::<RocList<i64>>(indoc!(
+        r#"
+        offset = 10
+
+        [1, 2, 3]
+            |> List.map (\x -> x + offset)
+            |> List.keepIf (\x -> x > 11)
+            |> List.sortWith Num.compare
+        "#
+    ));
+}
+
+#[test]
+fn wat_of_recursive_unions() {
+    assert_valid_wat::<i64>(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        ConsList a : [Cons a (ConsList a), Nil]
+
+        len : ConsList a -> I64
+        len = \list ->
+            when list is
+                Cons _ rest -> 1 + len rest
+                Nil -> 0
+
+        main : I64
+        main = len (Cons 1 (Cons 2 Nil))
+        "#
+    ));
+}
@@ -73,12 +73,15 @@ fn promote_expr_to_module(src: &str) -> String {
     buffer
 }
 
-fn compiles_to_ir(test_name: &str, src: &str) {
+fn compiles_to_ir(test_name: &str, src: &str, inline_and_fold: bool) {
     use bumpalo::Bump;
     use std::path::PathBuf;
 
     let arena = &Bump::new();
 
+    // validate the IR after every mono stage, not just the snapshot of the final result
+    std::env::set_var(roc_debug_flags::ROC_CHECK_MONO_IR, "1");
+
     let filename = PathBuf::from("Test.roc");
     let src_dir = PathBuf::from("fake/test/path");
 
@@ -98,6 +101,9 @@ fn compiles_to_ir(test_name: &str, src: &str) {
         threading: Threading::Single,
         render: roc_reporting::report::RenderTarget::Generic,
         exec_mode: ExecutionMode::Executable,
+        inline_and_fold,
+        record_call_regions: false,
+        collect_package_modules: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -1922,3 +1928,206 @@ fn issue_3669() {
         "#
     )
 }
+
+#[mono_test(inline_and_fold)]
+fn inline_num_wrappers() {
+    indoc!(
+        r#"
+        x = 5 + 4 + 3
+        y = x * 2 - 1
+
+        y + 100
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_fold_comparison_branch() {
+    indoc!(
+        r#"
+        limit = 10
+
+        if 3 + 4 < limit then
+            1u8
+        else
+            2u8
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_fold_bool_operations() {
+    indoc!(
+        r#"
+        a = 1 == 1
+        b = 2 > 3
+
+        if (a && !b) || b then
+            "yes"
+        else
+            "no"
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_nested_wrappers() {
+    indoc!(
+        r#"
+        double : I64 -> I64
+        double = \n -> n + n
+
+        quadruple : I64 -> I64
+        quadruple = \n -> double (double n)
+
+        quadruple 3
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_keeps_overflowing_arithmetic() {
+    indoc!(
+        r#"
+        x : U8
+        x = 200
+
+        x + 100
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_non_constant_arguments() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        add : I64, I64 -> I64
+        add = \a, b -> a + b
+
+        main : I64 -> I64
+        main = \n -> add n (add 1 2)
+        "#
+    )
+}
+
+#[mono_test(inline_and_fold)]
+fn inline_keeps_owning_lowlevel_wrappers() {
+    indoc!(
+        r#"
+        greeting = "Hello, World! This is a long string."
+
+        List.append [greeting] greeting
+        "#
+    )
+}
+
+#[mono_test]
+fn expand_rc_linked_list_tail() {
+    indoc!(
+        r#"
+        ConsList a : [Cons a (ConsList a), Nil]
+
+        list : ConsList Str
+        list = Cons "first element, long enough for the heap" (Cons "second" Nil)
+
+        when list is
+            Cons _ rest -> rest
+            Nil -> Nil
+        "#
+    )
+}
+
+#[mono_test]
+fn expand_rc_tree_fields() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        Tree : [Leaf, Node Tree I64 Tree]
+
+        sum : Tree -> I64
+        sum = \tree ->
+            when tree is
+                Leaf -> 0
+                Node left value right -> sum left + value + sum right
+
+        main : Tree -> I64
+        main = \tree -> sum tree
+        "#
+    )
+}
+
+#[mono_test]
+fn expand_rc_record_field() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main : { name : Str, tags : List Str } -> Str
+        main = \record -> record.name
+        "#
+    )
+}
+
+#[mono_test]
+fn tail_recursion_modulo_cons_map() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        ConsList a : [Cons a (ConsList a), Nil]
+
+        map : ConsList a, (a -> b) -> ConsList b
+        map = \list, f ->
+            when list is
+                Nil -> Nil
+                Cons x rest -> Cons (f x) (map rest f)
+
+        main : ConsList I64 -> ConsList I64
+        main = \list -> map list (\x -> x + 1)
+        "#
+    )
+}
+
+#[mono_test]
+fn tail_recursion_modulo_cons_tree() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        Tree : [Leaf, Node Tree I64 Tree]
+
+        # only one of the recursive calls can become a jump, the other stays a regular call
+        increment : Tree -> Tree
+        increment = \tree ->
+            when tree is
+                Leaf -> Leaf
+                Node left value right -> Node (increment left) (value + 1) (increment right)
+
+        main : Tree -> Tree
+        main = \tree -> increment tree
+        "#
+    )
+}
+
+#[mono_test]
+fn single_field_wrappers_erased_in_signatures() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        Meters := F64
+
+        # the record, the opaque type and the tag all have the layout of what they wrap
+        scale : { factor : F64 }, Meters, [Offset F64] -> Meters
+        scale = \{ factor }, @Meters m, Offset o -> @Meters (m * factor + o)
+
+        main =
+            @Meters m = scale { factor: 2.0 } (@Meters 3.0) (Offset 1.0)
+
+            m
+        "#
+    )
+}
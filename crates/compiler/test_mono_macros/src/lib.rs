@@ -4,10 +4,17 @@ use proc_macro::TokenStream;
 use quote::quote;
 
 #[proc_macro_attribute]
-pub fn mono_test(_args: TokenStream, item: TokenStream) -> TokenStream {
+pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
     let task_fn = syn::parse_macro_input!(item as syn::ItemFn);
 
-    let args = task_fn.sig.inputs.clone();
+    // `#[mono_test(inline_and_fold)]` also snapshots the IR after inlining and constant folding
+    let inline_and_fold = args.iter().any(|arg| match arg {
+        syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.is_ident("inline_and_fold"),
+        _ => false,
+    });
+
+    let inputs = task_fn.sig.inputs.clone();
 
     let name = task_fn.sig.ident.clone();
     let name_str = name.to_string();
@@ -16,12 +23,20 @@ pub fn mono_test(_args: TokenStream, item: TokenStream) -> TokenStream {
     let visibility = &task_fn.vis;
     let attributes = task_fn.attrs;
 
+    let inlined = if inline_and_fold {
+        let inlined_name = format!("{}.inlined", name_str);
+
+        quote! { compiles_to_ir(#inlined_name, #body, true); }
+    } else {
+        quote! {}
+    };
+
     let result = quote! {
         #[test]
         #(#attributes)*
-        #visibility fn #name(#args) {
-            compiles_to_ir(#name_str, #body);
-
+        #visibility fn #name(#inputs) {
+            compiles_to_ir(#name_str, #body, false);
+            #inlined
         }
     };
     result.into()
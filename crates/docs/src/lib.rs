@@ -437,6 +437,9 @@ pub fn load_modules_for_files(filenames: Vec<PathBuf>) -> Vec<LoadedModule> {
             render: roc_reporting::report::RenderTarget::ColorTerminal,
             threading: Threading::AllAvailable,
             exec_mode: ExecutionMode::Check,
+            inline_and_fold: false,
+            record_call_regions: false,
+            collect_package_modules: false,
         };
         match roc_load::load_and_typecheck(&arena, filename, Default::default(), load_config) {
             Ok(loaded) => modules.push(loaded),
@@ -0,0 +1,113 @@
+use roc_ast::lang::core::expr::expr2::{ClosureExtra, Expr2};
+use roc_ast::lang::core::pattern::Pattern2;
+use roc_ast::mem_pool::pool_vec::PoolVec;
+use roc_can::expr::Recursive;
+
+use crate::editor::ed_error::EdResult;
+use crate::editor::mvc::app_update::InputOutcome;
+use crate::editor::mvc::ed_model::EdModel;
+use crate::editor::mvc::ed_update::get_node_context;
+use crate::editor::mvc::ed_update::NodeContext;
+
+// `\_ -> Blank`, the caret is put on the `_`
+pub fn start_new_closure(ed_model: &mut EdModel) -> EdResult<InputOutcome> {
+    let NodeContext {
+        old_caret_pos: _,
+        curr_mark_node_id: _,
+        curr_mark_node,
+        parent_id_opt: _,
+        ast_node_id,
+    } = get_node_context(ed_model)?;
+
+    let is_blank_node = curr_mark_node.is_blank();
+
+    let env = &mut ed_model.module.env;
+
+    let arg_pattern_id = env.pool.add(Pattern2::Underscore);
+    let args = PoolVec::new(
+        vec![(env.var_store.fresh(), arg_pattern_id)].into_iter(),
+        env.pool,
+    );
+
+    let extra = ClosureExtra {
+        return_type: env.var_store.fresh(),
+        captured_symbols: PoolVec::empty(env.pool),
+        closure_type: env.var_store.fresh(),
+        closure_ext_var: env.var_store.fresh(),
+    };
+
+    let expr2_node = Expr2::Closure {
+        args,
+        uniq_symbol: env.gen_unique_symbol(),
+        body_id: env.pool.add(Expr2::Blank),
+        function_type: env.var_store.fresh(),
+        recursive: Recursive::NotRecursive,
+        extra: env.pool.add(extra),
+    };
+
+    env.pool.set(ast_node_id.to_expr_id()?, expr2_node);
+
+    if is_blank_node {
+        // move past the backslash
+        ed_model.simple_move_carets_right(1);
+
+        Ok(InputOutcome::Accepted)
+    } else {
+        Ok(InputOutcome::Ignored)
+    }
+}
+
+#[cfg(test)]
+pub mod test_closure_update {
+    use crate::editor::mvc::ed_harness::{assert_key_steps, chars, ctrl, key, KeyEvent};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    // `val = ` followed by the typed chars
+    fn in_def(typed: &str) -> Vec<KeyEvent> {
+        let mut key_events = chars("val");
+        key_events.extend(vec![key(Right), key(Right), key(Right)]);
+        key_events.extend(chars(typed));
+
+        key_events
+    }
+
+    #[test]
+    fn start_closure() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["┃"],
+            vec![
+                (in_def("\\"), ovec!["val = \\┃_ ->  ", "", ""]),
+                (chars("x"), ovec!["val = \\x┃ ->  ", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = \\┃_ ->  ", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn start_closure_in_body() -> Result<(), String> {
+        let mut key_events = in_def("\\x");
+        key_events.extend(vec![key(Right), key(Right), key(Right), key(Right)]);
+
+        assert_key_steps(
+            ovec!["┃"],
+            vec![
+                (key_events, ovec!["val = \\x -> ┃ ", "", ""]),
+                (chars("\\"), ovec!["val = \\x -> \\┃_ ->  ", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = \\x -> ┃ ", "", ""]),
+            ],
+        )
+    }
+}
@@ -0,0 +1,147 @@
+//! Drives an EdModel with the key events the window would send it, no window or GPU is needed.
+//! Code and caret are checked with the DSL of ed_model_to_dsl, e.g. `val = 5┃`.
+
+use crate::editor::ed_error::print_err;
+use crate::editor::ed_error::EdResult;
+use crate::editor::mvc::ed_model::test_ed_model::ed_model_from_dsl;
+use crate::editor::mvc::ed_model::test_ed_model::ed_model_to_dsl;
+use crate::editor::mvc::ed_model::test_ed_model::init_model_refs;
+use crate::editor::mvc::ed_model::EdModel;
+use crate::editor::mvc::ed_update::handle_new_char;
+use crate::editor::resources::strings::nr_hello_world_lines;
+use crate::window::keyboard_input::no_mods;
+use crate::window::keyboard_input::Modifiers;
+use bumpalo::Bump;
+use roc_module::symbol::ModuleIds;
+use threadpool::ThreadPool;
+use winit::event::VirtualKeyCode;
+
+#[derive(Debug)]
+pub enum KeyEvent {
+    Char(char),                     // like winit's ReceivedCharacter
+    Key(Modifiers, VirtualKeyCode), // like winit's KeyboardInput
+}
+
+// every char of the str is typed
+pub fn chars(typed: &str) -> Vec<KeyEvent> {
+    typed.chars().map(KeyEvent::Char).collect()
+}
+
+pub fn key(virtual_keycode: VirtualKeyCode) -> KeyEvent {
+    KeyEvent::Key(no_mods(), virtual_keycode)
+}
+
+// ctrl on linux and windows, cmd on macOS
+pub fn ctrl(virtual_keycode: VirtualKeyCode) -> KeyEvent {
+    KeyEvent::Key(ctrl_mods(false), virtual_keycode)
+}
+
+pub fn ctrl_shift(virtual_keycode: VirtualKeyCode) -> KeyEvent {
+    KeyEvent::Key(ctrl_mods(true), virtual_keycode)
+}
+
+fn ctrl_mods(shift: bool) -> Modifiers {
+    Modifiers {
+        shift,
+        ctrl: cfg!(not(target_os = "macos")),
+        alt: false,
+        logo: cfg!(target_os = "macos"),
+    }
+}
+
+pub fn send_key_event(
+    key_event: &KeyEvent,
+    ed_model: &mut EdModel,
+    thread_pool: &mut ThreadPool,
+) -> EdResult<()> {
+    match key_event {
+        KeyEvent::Char(ch) => {
+            handle_new_char(ch, ed_model)?;
+        }
+        KeyEvent::Key(modifiers, virtual_keycode) => {
+            ed_model.ed_handle_key_down(modifiers, *virtual_keycode, thread_pool)?
+        }
+    }
+
+    Ok(())
+}
+
+// Create ed_model from pre_lines DSL, then for every step: send all key events of the step
+// and check if the code and caret (without header) match the expected lines of the step.
+pub fn assert_key_steps(
+    pre_lines: Vec<String>,
+    steps: Vec<(Vec<KeyEvent>, Vec<String>)>,
+) -> Result<(), String> {
+    let mut code_str = pre_lines.join("\n").replace('┃', "");
+
+    let mut model_refs = init_model_refs();
+    let code_arena = Bump::new();
+    let module_ids = ModuleIds::default();
+
+    let mut ed_model = ed_model_from_dsl(
+        &mut code_str,
+        pre_lines,
+        &mut model_refs,
+        &module_ids,
+        &code_arena,
+    )?;
+
+    let mut thread_pool = ThreadPool::new(1);
+
+    for (step_nr, (key_events, expected_lines)) in steps.into_iter().enumerate() {
+        for key_event in key_events.iter() {
+            if let Err(e) = send_key_event(key_event, &mut ed_model, &mut thread_pool) {
+                print_err(&e);
+                return Err(format!("{} at {:?} in step {}", e, key_event, step_nr));
+            }
+        }
+
+        let mut post_lines = ed_model_to_dsl(&ed_model).map_err(|e| e.to_string())?;
+        post_lines.drain(0..nr_hello_world_lines()); // remove header for clean tests
+
+        assert_eq!(post_lines, expected_lines, "step {}", step_nr);
+    }
+
+    Ok(())
+}
+
+pub fn assert_key_seq(
+    pre_lines: Vec<String>,
+    expected_post_lines: Vec<String>,
+    key_events: Vec<KeyEvent>,
+) -> Result<(), String> {
+    assert_key_steps(pre_lines, vec![(key_events, expected_post_lines)])
+}
+
+#[cfg(test)]
+pub mod test_ed_harness {
+    use crate::editor::mvc::ed_harness::{assert_key_seq, chars, key};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    #[test]
+    fn type_and_move_caret() -> Result<(), String> {
+        let mut key_events = chars("ab");
+        key_events.extend(vec![key(Right), key(Right), key(Right)]);
+        key_events.extend(chars("5"));
+
+        assert_key_seq(ovec!["┃"], ovec!["ab = 5┃", "", ""], key_events)?;
+
+        let key_events = vec![key(Left), key(Left), key(Home)];
+        assert_key_seq(ovec!["val = 12┃"], ovec!["┃val = 12", "", ""], key_events)?;
+
+        Ok(())
+    }
+}
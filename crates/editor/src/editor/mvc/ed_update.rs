@@ -8,12 +8,15 @@ use crate::editor::ed_error::EdResult;
 use crate::editor::ed_error::{MissingSelectionSnafu, RocCheckFailedSnafu};
 use crate::editor::grid_node_map::GridNodeMap;
 use crate::editor::mvc::app_update::InputOutcome;
+use crate::editor::mvc::closure_update::start_new_closure;
 use crate::editor::mvc::ed_model::EdModel;
 use crate::editor::mvc::ed_model::SelectedBlock;
+use crate::editor::mvc::if_update::start_new_if;
 use crate::editor::mvc::int_update::start_new_int;
 use crate::editor::mvc::int_update::update_int;
 use crate::editor::mvc::list_update::{add_blank_child, start_new_list};
 use crate::editor::mvc::lookup_update::update_invalid_lookup;
+use crate::editor::mvc::pattern_update::{add_underscore_pattern, update_pattern};
 use crate::editor::mvc::record_update::start_new_record;
 use crate::editor::mvc::record_update::update_empty_record;
 use crate::editor::mvc::record_update::update_record_colon;
@@ -22,6 +25,7 @@ use crate::editor::mvc::string_update::start_new_string;
 use crate::editor::mvc::string_update::update_small_string;
 use crate::editor::mvc::string_update::update_string;
 use crate::editor::mvc::tld_value_update::{start_new_tld_value, update_tld_val_name};
+use crate::editor::mvc::when_update::{add_when_branch, start_new_when};
 #[cfg(feature = "with_sound")]
 use crate::editor::sound::play_sound;
 use crate::ui::text::caret_w_select::CaretWSelect;
@@ -58,6 +62,7 @@ use roc_code_markup::slow_pool::SlowPool;
 use roc_collections::all::MutMap;
 use roc_module::ident::Lowercase;
 use roc_module::symbol::Symbol;
+use roc_parse::keyword;
 use roc_region::all::Region;
 use roc_solve::module::Solved;
 use roc_types::pretty_print::name_and_print_var;
@@ -70,7 +75,7 @@ use VirtualKeyCode::*;
 
 use super::break_line::break_line;
 use super::break_line::insert_new_blank;
-use super::let_update::start_new_let_value;
+use super::let_update::{get_blank_let_value_name, start_new_let_value, update_let_value_name};
 
 /// ed_update.rs contains all functions that change the ed_model.
 /// Additions and deletions of new characters to the editor are handled here.
@@ -539,6 +544,16 @@ impl<'a> EdModel<'a> {
                 }
             }
 
+            Z => {
+                if modifiers.cmd_or_ctrl() {
+                    if modifiers.shift {
+                        self.redo()?
+                    } else {
+                        self.undo()?
+                    }
+                }
+            }
+
             Home => self.move_caret_home(modifiers)?,
             End => self.move_caret_end(modifiers)?,
 
@@ -562,6 +577,8 @@ impl<'a> EdModel<'a> {
     // If no expression is selected, this function will select one to guide the user to using backspace in a projectional editing way
     fn backspace(&mut self) -> EdResult<()> {
         if let Some(sel_block) = &self.selected_block_opt {
+            let prev_state = self.save_state();
+
             let expr2_level_mark_node = self.mark_node_pool.get(sel_block.mark_node_id);
             let newlines_at_end = expr2_level_mark_node.get_newlines_at_end();
 
@@ -600,6 +617,8 @@ impl<'a> EdModel<'a> {
             )?;
 
             self.set_sel_none();
+
+            self.push_undo_step(prev_state);
         } else {
             self.select_expr()?;
         };
@@ -652,7 +671,7 @@ impl<'a> EdModel<'a> {
     }
 
     /// update MarkupNode's, grid_node_map, code_lines after the AST has been updated
-    fn post_process_ast_update(&mut self) -> EdResult<()> {
+    pub fn post_process_ast_update(&mut self) -> EdResult<()> {
         //dbg!("{}",self.module.ast.ast_to_string(self.module.env.pool));
 
         let markup_ids_tup = ast_to_mark_nodes(
@@ -947,6 +966,7 @@ pub fn handle_new_char_expr(
             '"' => start_new_string(ed_model)?,
             '{' => start_new_record(ed_model)?,
             '0'..='9' => start_new_int(ed_model, ch)?,
+            '\\' => start_new_closure(ed_model)?,
             '[' => {
                 // this can also be a tag union or become a set, assuming list for now
                 start_new_list(ed_model)?
@@ -994,6 +1014,10 @@ pub fn handle_new_char_expr(
                         InputOutcome::Ignored
                     }
                 }
+                Expr2::LetValue { .. } => update_let_value_name(curr_mark_node_id, ed_model, ch)?,
+                Expr2::When { .. } | Expr2::Closure { .. } => {
+                    update_pattern(ed_model, curr_mark_node_id, expr_id, ch)?
+                }
                 _ => InputOutcome::Ignored,
             }
         } else if ch.is_ascii_alphanumeric() {
@@ -1064,6 +1088,12 @@ pub fn handle_new_char_expr(
                                 } => {
                                     todo!("multiple record fields")
                                 }
+                                Expr2::Closure { .. } => add_underscore_pattern(
+                                    ed_model,
+                                    prev_mark_node_id,
+                                    parent_expr_id,
+                                    nodes::COMMA.len(),
+                                )?,
                                 _ => InputOutcome::Ignored,
                             }
                         }
@@ -1072,6 +1102,31 @@ pub fn handle_new_char_expr(
                     InputOutcome::Ignored
                 }
             }
+        } else if *ch == '|' {
+            match expr_ref {
+                Expr2::When { .. } => {
+                    add_underscore_pattern(ed_model, prev_mark_node_id, expr_id, nodes::PIPE.len())?
+                }
+                _ => InputOutcome::Ignored,
+            }
+        } else if *ch == ' ' {
+            // `if ` or `when ` typed as the name of a new let value starts an if or when
+            if curr_mark_node.get_content() == EQUALS {
+                let prev_mark_node = ed_model.mark_node_pool.get(prev_mark_node_id);
+
+                match get_blank_let_value_name(expr_id, ed_model)? {
+                    Some(val_name) if val_name == prev_mark_node.get_content() => {
+                        match val_name.as_str() {
+                            keyword::IF => start_new_if(ed_model, expr_id)?,
+                            keyword::WHEN => start_new_when(ed_model, expr_id)?,
+                            _ => InputOutcome::Ignored,
+                        }
+                    }
+                    _ => InputOutcome::Ignored,
+                }
+            } else {
+                InputOutcome::Ignored
+            }
         } else if "\"{[".contains(*ch) {
             let prev_mark_node = ed_model.mark_node_pool.get(prev_mark_node_id);
 
@@ -1082,7 +1137,7 @@ pub fn handle_new_char_expr(
                 // insert a Blank first, this results in cleaner code
                 add_blank_child(new_child_index, new_ast_child_index, ed_model)?;
                 ed_model.post_process_ast_update()?;
-                handle_new_char(received_char, ed_model)?
+                handle_new_char_help(received_char, ed_model)?
             } else {
                 InputOutcome::Ignored
             }
@@ -1165,11 +1220,19 @@ pub fn handle_new_char_diff_mark_nodes_prev_is_expr(
                 // insert a Blank first, this results in cleaner code
                 add_blank_child(new_child_index, new_ast_child_index, ed_model)?;
                 ed_model.post_process_ast_update()?;
-                handle_new_char(received_char, ed_model)?
+                handle_new_char_help(received_char, ed_model)?
             } else {
                 InputOutcome::Ignored
             }
         }
+        Expr2::LetValue { .. } => update_let_value_name(prev_mark_node_id, ed_model, ch)?,
+        Expr2::When { .. } | Expr2::Closure { .. } => {
+            // the caret is at the end of a pattern or at the start of one
+            match update_pattern(ed_model, prev_mark_node_id, prev_expr_id, ch)? {
+                InputOutcome::Accepted => InputOutcome::Accepted,
+                _ => update_pattern(ed_model, curr_mark_node_id, curr_expr_id, ch)?,
+            }
+        }
         _ => match curr_expr_ref {
             Expr2::EmptyRecord => {
                 let sibling_ids = curr_mark_node.get_sibling_ids(&ed_model.mark_node_pool);
@@ -1184,7 +1247,20 @@ pub fn handle_new_char_diff_mark_nodes_prev_is_expr(
 }
 
 // updates the ed_model based on the char the user just typed if the result would be syntactically correct.
+// Accepted edits can be undone with ctrl+z.
 pub fn handle_new_char(received_char: &char, ed_model: &mut EdModel) -> EdResult<InputOutcome> {
+    let prev_state = ed_model.save_state();
+
+    let input_outcome = handle_new_char_help(received_char, ed_model)?;
+
+    if let InputOutcome::Accepted = input_outcome {
+        ed_model.push_undo_step(prev_state);
+    }
+
+    Ok(input_outcome)
+}
+
+fn handle_new_char_help(received_char: &char, ed_model: &mut EdModel) -> EdResult<InputOutcome> {
     //dbg!("{}", ed_model.module.ast.ast_to_string(ed_model.module.env.pool));
 
     let input_outcome = match received_char {
@@ -1219,7 +1295,10 @@ pub fn handle_new_char(received_char: &char, ed_model: &mut EdModel) -> EdResult
 
                     } else { //no MarkupNode at the current position
                             if *received_char == '\r' {
-                                break_line(ed_model)?
+                                match add_when_branch(ed_model)? {
+                                    InputOutcome::Accepted => InputOutcome::Accepted,
+                                    _ => break_line(ed_model)?,
+                                }
                             } else {
                                 let prev_mark_node_id_opt = ed_model.get_prev_mark_node_id()?;
                                 if let Some(prev_mark_node_id) = prev_mark_node_id_opt {
@@ -1245,7 +1324,7 @@ pub fn handle_new_char(received_char: &char, ed_model: &mut EdModel) -> EdResult
                                                     ed_model.post_process_ast_update()?;
                                                 }
                                             }
-                                            handle_new_char(received_char, ed_model)?
+                                            handle_new_char_help(received_char, ed_model)?
                                         }
                                         _ => {
                                             InputOutcome::Ignored
@@ -2576,6 +2655,150 @@ pub mod test_ed_update {
         Ok(())
     }
 
+    #[test]
+    fn test_let_value() -> Result<(), String> {
+        assert_insert_seq_in_def(add_nls(ovec!["ab┃ =  ", "", ""]), "ab")?;
+        assert_insert_seq_in_def(add_nls(ovec!["c137┃ =  ", "", ""]), "c137")?;
+        assert_insert_seq_in_def(add_nls(ovec!["ac┃b =  ", "", ""]), "ab🡰c")?;
+
+        // a name can not start with a digit
+        assert_insert_seq_in_def(add_nls(ovec!["┃a =  ", "", ""]), "a🡰1")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_if() -> Result<(), String> {
+        assert_insert_seq_in_def(add_nls(ovec!["if ┃  then   else  "]), "if ")?;
+        assert_insert_seq_in_def(add_nls(ovec!["if 1┃ then   else  "]), "if 1")?;
+        assert_insert_seq_in_def(
+            add_nls(ovec!["if 1 then \"yes\" else { ┃ }"]),
+            "if 1🡲🡲🡲🡲🡲🡲\"yes🡲🡲🡲🡲🡲🡲🡲{",
+        )?;
+        assert_insert_seq_in_def(
+            add_nls(ovec!["if if ┃  then   else   then   else  "]),
+            "if if ",
+        )?;
+
+        assert_insert_seq(
+            ovec!["val = if 1 then ┃2 else 3"],
+            add_nls(ovec!["val = if 1 then 4┃2 else 3"]),
+            "4",
+        )?;
+        assert_insert_seq(
+            ovec!["val = if 1 then 2 else if 3 then 4 else ┃5"],
+            add_nls(ovec!["val = if 1 then 2 else if 3 then 4 else 6┃5"]),
+            "6",
+        )?;
+
+        // only `if` and `when` followed by a space become an expression
+        assert_insert_seq_in_def(add_nls(ovec!["iff┃ =  ", "", ""]), "iff ")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_when() -> Result<(), String> {
+        assert_insert_seq_in_def(add_nls(ovec!["when ┃  is", "    _ ->  "]), "when ")?;
+        assert_insert_seq_in_def(add_nls(ovec!["when 5┃ is", "    _ ->  "]), "when 5")?;
+
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    ┃_ -> 1"],
+            add_nls(ovec!["val = when 5 is", "    Ok┃ -> 1"]),
+            "Ok",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    _┃ -> 1"],
+            add_nls(ovec!["val = when 5 is", "    x┃ -> 1"]),
+            "x",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    ┃_ -> 1"],
+            add_nls(ovec!["val = when 5 is", "    120┃ -> 1"]),
+            "120",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    Fo┃o -> 1"],
+            add_nls(ovec!["val = when 5 is", "    Fox┃o -> 1"]),
+            "x",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    A┃ -> 1"],
+            add_nls(ovec!["val = when 5 is", "    A | B┃ -> 1"]),
+            "|B",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    1 | 2 -> \"a\"", "    ┃_ -> \"b\""],
+            add_nls(ovec![
+                "val = when 5 is",
+                "    1 | 2 -> \"a\"",
+                "    y┃ -> \"b\""
+            ]),
+            "y",
+        )?;
+        assert_insert_seq(
+            ovec!["val = when 5 is", "    1 | 2 -> \"┃a\"", "    _ -> \"b\""],
+            add_nls(ovec![
+                "val = when 5 is",
+                "    1 | 2 -> \"b┃a\"",
+                "    _ -> \"b\""
+            ]),
+            "b",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_when() -> Result<(), String> {
+        assert_insert_seq_ignore_nls(ovec!["val = when 5 is", "    0┃ -> 1"], "01")?;
+        assert_insert_seq_ignore_nls(ovec!["val = when 5 is", "    ┃a -> 1"], "0")?;
+        assert_insert_seq_ignore_nls(ovec!["val = when 5 is", "    a┃ -> 1"], "{}()[]-><-_\",")?;
+        assert_insert_seq_ignore_nls(ovec!["val = when 5 is", "    ┃a -> 1"], "{}()[]-><-_\",|")?;
+        assert_insert_seq_ignore_nls(ovec!["val = when 5 is", "    Foo -┃> 1"], IGNORE_CHARS)?;
+        assert_insert_seq_ignore_nls(ovec!["val = wh┃en 5 is", "    Foo -> 1"], IGNORE_CHARS)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_closure() -> Result<(), String> {
+        assert_insert_in_def_nls(ovec!["\\┃_ ->  "], '\\')?;
+        assert_insert_seq_in_def(add_nls(ovec!["\\a1┃ ->  "]), "\\a1")?;
+        assert_insert_seq_in_def(add_nls(ovec!["\\x, y┃ ->  "]), "\\x,y")?;
+        assert_insert_seq_in_def(add_nls(ovec!["\\x -> 5┃"]), "\\x🡲🡲🡲🡲5")?;
+        assert_insert_seq_in_def(add_nls(ovec!["\\x -> \\┃_ ->  "]), "\\x🡲🡲🡲🡲\\")?;
+
+        assert_insert_seq(
+            ovec!["val = \\x┃, y -> 5"],
+            add_nls(ovec!["val = \\xz┃, y -> 5"]),
+            "z",
+        )?;
+        assert_insert_seq(
+            ovec!["val = \\x┃, y -> 5"],
+            add_nls(ovec!["val = \\x, ┃_, y -> 5"]),
+            ",",
+        )?;
+        assert_insert_seq(
+            ovec!["val = \\x, y┃ -> 5"],
+            add_nls(ovec!["val = \\x, y, z┃ -> 5"]),
+            ",z",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_closure() -> Result<(), String> {
+        // closure arguments can only be identifiers
+        assert_insert_seq_ignore_nls(ovec!["val = \\┃x -> 5"], "X1{}()[]-><-_\",|")?;
+        assert_insert_seq_ignore_nls(ovec!["val = \\x┃ -> 5"], "|")?;
+        assert_insert_seq_ignore_nls(ovec!["val = \\x -┃> 5"], IGNORE_CHARS)?;
+        assert_insert_seq_ignore_nls(ovec!["val = \\┃_ -> 5"], "X1")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_enter() -> Result<(), String> {
         assert_insert_seq(
@@ -0,0 +1,93 @@
+use roc_ast::lang::core::expr::expr2::{Expr2, ExprId};
+use roc_ast::mem_pool::pool_vec::PoolVec;
+
+use crate::editor::ed_error::EdResult;
+use crate::editor::mvc::app_update::InputOutcome;
+use crate::editor::mvc::ed_model::EdModel;
+
+// replaces the expression at if_expr_id with `if Blank then Blank else Blank`
+// and puts the caret on the condition.
+pub fn start_new_if(ed_model: &mut EdModel, if_expr_id: ExprId) -> EdResult<InputOutcome> {
+    let pool = &mut ed_model.module.env.pool;
+
+    let cond_id = pool.add(Expr2::Blank);
+    let then_id = pool.add(Expr2::Blank);
+    let final_else = pool.add(Expr2::Blank);
+
+    let branches = PoolVec::new(vec![(cond_id, then_id)].into_iter(), pool);
+
+    let expr2_node = Expr2::If {
+        cond_var: ed_model.module.env.var_store.fresh(),
+        expr_var: ed_model.module.env.var_store.fresh(),
+        branches,
+        final_else,
+    };
+
+    ed_model.module.env.pool.set(if_expr_id, expr2_node);
+
+    // the caret is right after `if`, skip the space
+    ed_model.simple_move_carets_right(1);
+
+    Ok(InputOutcome::Accepted)
+}
+
+#[cfg(test)]
+pub mod test_if_update {
+    use crate::editor::mvc::ed_harness::{assert_key_steps, chars, ctrl, key, KeyEvent};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    // `val = ` followed by the typed chars
+    fn in_def(typed: &str) -> Vec<KeyEvent> {
+        let mut key_events = chars("val");
+        key_events.extend(vec![key(Right), key(Right), key(Right)]);
+        key_events.extend(chars(typed));
+
+        key_events
+    }
+
+    #[test]
+    fn start_if() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["┃"],
+            vec![
+                (in_def("if "), ovec!["val = if ┃  then   else  ", "", ""]),
+                (chars("1"), ovec!["val = if 1┃ then   else  ", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = if ┃  then   else  ", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn start_if_in_condition() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["┃"],
+            vec![
+                (
+                    in_def("if if "),
+                    ovec!["val = if if ┃  then   else   then   else  ", "", ""],
+                ),
+                (
+                    chars("2"),
+                    ovec!["val = if if 2┃ then   else   then   else  ", "", ""],
+                ),
+                (
+                    vec![ctrl(Z)],
+                    ovec!["val = if if ┃  then   else   then   else  ", "", ""],
+                ),
+            ],
+        )
+    }
+}
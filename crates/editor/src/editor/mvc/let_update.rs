@@ -1,13 +1,15 @@
-use roc_ast::lang::core::expr::expr2::Expr2;
+use roc_ast::lang::core::expr::expr2::{Expr2, ExprId};
 use roc_ast::lang::core::pattern::Pattern2;
 use roc_ast::lang::core::val_def::ValueDef;
+use roc_code_markup::slow_pool::MarkNodeId;
 use roc_module::symbol::Symbol;
 
-use crate::editor::ed_error::EdResult;
+use crate::editor::ed_error::{EdResult, FailedToUpdateIdentIdNameSnafu};
 use crate::editor::mvc::app_update::InputOutcome;
 use crate::editor::mvc::ed_model::EdModel;
 use crate::editor::mvc::ed_update::get_node_context;
 use crate::editor::mvc::ed_update::NodeContext;
+use crate::ui::text::lines::SelectableLines;
 
 pub fn start_new_let_value(ed_model: &mut EdModel, new_char: &char) -> EdResult<InputOutcome> {
     let NodeContext {
@@ -62,67 +64,41 @@ pub fn start_new_let_value(ed_model: &mut EdModel, new_char: &char) -> EdResult<
     }
 }
 
-// TODO reenable this for updating non-top level value defs
-/*
-pub fn update_let_value(
+// add new_char to the name of a let value, the name's IdentId stays the same so its symbol does not change
+pub fn update_let_value_name(
     val_name_mn_id: MarkNodeId,
-    def_id: NodeId<ValueDef>,
-    body_id: NodeId<Expr2>,
     ed_model: &mut EdModel,
     new_char: &char,
 ) -> EdResult<InputOutcome> {
-    if new_char.is_ascii_alphanumeric() {
-        let old_caret_pos = ed_model.get_caret();
+    let val_name_mn = ed_model.mark_node_pool.get(val_name_mn_id);
 
-        // update markup
-        let val_name_mn_mut = ed_model.mark_node_pool.get_mut(val_name_mn_id);
-        let content_str_mut = val_name_mn_mut.get_content_mut()?;
+    if new_char.is_ascii_alphanumeric() && val_name_mn.is_all_alphanumeric() {
+        let old_caret_pos = ed_model.get_caret();
 
-        let old_val_name = content_str_mut.clone();
+        let mut val_name_str = val_name_mn.get_content();
+        let old_val_name = val_name_str.clone();
 
         let node_caret_offset = ed_model
             .grid_node_map
             .get_offset_to_node_id(old_caret_pos, val_name_mn_id)?;
 
-        if node_caret_offset <= content_str_mut.len() {
-            content_str_mut.insert(node_caret_offset, *new_char);
+        // a name can not start with a digit
+        if node_caret_offset <= val_name_str.len()
+            && !(node_caret_offset == 0 && new_char.is_ascii_digit())
+        {
+            val_name_str.insert(node_caret_offset, *new_char);
 
-            // update ast
-            let value_def = ed_model.module.env.pool.get(def_id);
-            let value_ident_pattern_id = value_def.get_pattern_id();
-
-            // TODO no unwrap
-            let ident_id = ed_model
+            let update_val_name_res = ed_model
                 .module
                 .env
                 .ident_ids
-                .update_key(&old_val_name, content_str_mut)
-                .unwrap();
-
-            let new_var_symbol = Symbol::new(ed_model.module.env.home, ident_id);
+                .update_key(&old_val_name, &val_name_str);
 
-            ed_model
-                .module
-                .env
-                .pool
-                .set(value_ident_pattern_id, Pattern2::Identifier(new_var_symbol));
+            if let Err(err_str) = update_val_name_res {
+                FailedToUpdateIdentIdNameSnafu { err_str }.fail()?;
+            }
 
-            ed_model
-                .module
-                .env
-                .pool
-                .set(body_id, Expr2::Var(new_var_symbol));
-
-            // update GridNodeMap and CodeLines
-            ed_model.insert_between_line(
-                old_caret_pos.line,
-                old_caret_pos.column,
-                &new_char.to_string(),
-                val_name_mn_id,
-            )?;
-
-            // update caret
-            ed_model.simple_move_carets_right(1);
+            ed_model.simple_move_caret_right(old_caret_pos, 1);
 
             Ok(InputOutcome::Accepted)
         } else {
@@ -132,4 +108,29 @@ pub fn update_let_value(
         Ok(InputOutcome::Ignored)
     }
 }
-*/
+
+// the name of the let value if its value is still Blank, e.g. `if` for `if = Blank`
+pub fn get_blank_let_value_name(
+    let_expr_id: ExprId,
+    ed_model: &EdModel,
+) -> EdResult<Option<String>> {
+    let pool = &ed_model.module.env.pool;
+
+    if let Expr2::LetValue { def_id, .. } = pool.get(let_expr_id) {
+        let value_def = pool.get(*def_id);
+
+        if let Expr2::Blank = pool.get(value_def.get_expr_id()) {
+            if let Pattern2::Identifier(symbol) = pool.get(value_def.get_pattern_id()) {
+                let val_name = ed_model
+                    .module
+                    .env
+                    .ident_ids
+                    .get_name_str_res(symbol.ident_id())?;
+
+                return Ok(Some(val_name.to_owned()));
+            }
+        }
+    }
+
+    Ok(None)
+}
@@ -1,13 +1,20 @@
 pub mod app_model;
 pub mod app_update;
 mod break_line;
+mod closure_update;
+#[cfg(test)]
+pub mod ed_harness;
 pub mod ed_model;
 pub mod ed_update;
 pub mod ed_view;
+mod if_update;
 mod int_update;
 mod let_update;
 mod list_update;
 mod lookup_update;
+mod pattern_update;
 mod record_update;
 mod string_update;
 pub mod tld_value_update;
+pub mod undo_redo;
+mod when_update;
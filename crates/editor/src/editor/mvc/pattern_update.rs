@@ -0,0 +1,374 @@
+use roc_ast::lang::core::ast::ASTNodeId;
+use roc_ast::lang::core::expr::expr2::{Expr2, ExprId};
+use roc_ast::lang::core::pattern::Pattern2;
+use roc_ast::mem_pool::pool::NodeId;
+use roc_ast::mem_pool::pool_str::PoolStr;
+use roc_ast::mem_pool::pool_vec::PoolVec;
+use roc_ast::mem_pool::shallow_clone::ShallowClone;
+use roc_code_markup::markup::nodes::MarkupNode;
+use roc_code_markup::slow_pool::MarkNodeId;
+use roc_module::symbol::Symbol;
+
+use crate::editor::ed_error::EdResult;
+use crate::editor::mvc::app_update::InputOutcome;
+use crate::editor::mvc::ed_model::EdModel;
+use crate::ui::text::lines::SelectableLines;
+
+// which patterns are allowed depends on where the pattern is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternContext {
+    WhenBranch {
+        branch_index: usize,
+        pattern_index: usize,
+    },
+    ClosureArg {
+        arg_index: usize,
+    },
+}
+
+// The id of the pattern that is displayed by the MarkupNode, if the MarkupNode displays a pattern
+// of a when branch or an argument of a closure. expr_id should be the id of the when or closure.
+pub fn get_pattern_id(
+    pattern_mark_node_id: MarkNodeId,
+    expr_id: ExprId,
+    ed_model: &EdModel,
+) -> EdResult<Option<(NodeId<Pattern2>, PatternContext)>> {
+    let pool = &ed_model.module.env.pool;
+
+    if !matches!(
+        ed_model.mark_node_pool.get(pattern_mark_node_id),
+        MarkupNode::Text { .. }
+    ) {
+        return Ok(None);
+    }
+
+    let (parent_id, child_index) =
+        match get_index_in_parent(pattern_mark_node_id, expr_id, ed_model)? {
+            Some(parent_tup) => parent_tup,
+            None => return Ok(None),
+        };
+
+    // patterns are always at odd indices, they are separated by ` | ` or `, `
+    if child_index % 2 == 0 {
+        return Ok(None);
+    }
+
+    let pattern_id_opt = match pool.get(expr_id) {
+        Expr2::When { branches, .. } => {
+            // children of a when: `when `, condition, ` is`, branches
+            let branch_index = match get_index_in_parent(parent_id, expr_id, ed_model)? {
+                Some((_, index_in_when)) if index_in_when >= 3 => index_in_when - 3,
+                _ => return Ok(None),
+            };
+            let pattern_index = (child_index - 1) / 2;
+
+            branches
+                .iter(pool)
+                .nth(branch_index)
+                .and_then(|when_branch| when_branch.patterns.iter_node_ids().nth(pattern_index))
+                .map(|pattern_id| {
+                    (
+                        pattern_id,
+                        PatternContext::WhenBranch {
+                            branch_index,
+                            pattern_index,
+                        },
+                    )
+                })
+        }
+        Expr2::Closure { args, .. } => {
+            let arg_index = (child_index - 1) / 2;
+
+            args.iter(pool)
+                .nth(arg_index)
+                .map(|(_, pattern_id)| (*pattern_id, PatternContext::ClosureArg { arg_index }))
+        }
+        _ => None,
+    };
+
+    Ok(pattern_id_opt)
+}
+
+// the parent and the index of the MarkupNode in the children of its parent,
+// if the parent also corresponds to the AST node with expr_id
+fn get_index_in_parent(
+    mark_node_id: MarkNodeId,
+    expr_id: ExprId,
+    ed_model: &EdModel,
+) -> EdResult<Option<(MarkNodeId, usize)>> {
+    let mark_node = ed_model.mark_node_pool.get(mark_node_id);
+
+    if let Some(parent_id) = mark_node.get_parent_id_opt() {
+        if ed_model.mark_id_ast_id_map.get(parent_id)? == ASTNodeId::AExprId(expr_id) {
+            let child_index_opt = ed_model
+                .mark_node_pool
+                .get(parent_id)
+                .get_children_ids()
+                .iter()
+                .position(|child_id| *child_id == mark_node_id);
+
+            return Ok(child_index_opt.map(|child_index| (parent_id, child_index)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Insert new_char in the pattern displayed by pattern_mark_node_id at the caret position.
+// An `_` is replaced by new_char.
+pub fn update_pattern(
+    ed_model: &mut EdModel,
+    pattern_mark_node_id: MarkNodeId,
+    expr_id: ExprId,
+    new_char: &char,
+) -> EdResult<InputOutcome> {
+    if !new_char.is_ascii_alphanumeric() {
+        return Ok(InputOutcome::Ignored);
+    }
+
+    let (pattern_id, pattern_context) =
+        match get_pattern_id(pattern_mark_node_id, expr_id, ed_model)? {
+            Some(pattern_tup) => pattern_tup,
+            None => return Ok(InputOutcome::Ignored),
+        };
+
+    let old_caret_pos = ed_model.get_caret();
+    let node_caret_offset = ed_model
+        .grid_node_map
+        .get_offset_to_node_id(old_caret_pos, pattern_mark_node_id)?;
+
+    let old_pattern_str = ed_model
+        .mark_node_pool
+        .get(pattern_mark_node_id)
+        .get_content();
+
+    if node_caret_offset > old_pattern_str.len() {
+        return Ok(InputOutcome::Ignored);
+    }
+
+    let (new_pattern_str, caret_move) =
+        if let Pattern2::Underscore = ed_model.module.env.pool.get(pattern_id) {
+            // the caret ends up right after new_char
+            (new_char.to_string(), 1 - node_caret_offset)
+        } else {
+            let mut new_pattern_str = old_pattern_str;
+            new_pattern_str.insert(node_caret_offset, *new_char);
+
+            (new_pattern_str, 1)
+        };
+
+    if let Some(new_pattern) = str_to_pattern(&new_pattern_str, pattern_context, ed_model) {
+        ed_model.module.env.pool.set(pattern_id, new_pattern);
+
+        ed_model.simple_move_caret_right(old_caret_pos, caret_move);
+
+        Ok(InputOutcome::Accepted)
+    } else {
+        Ok(InputOutcome::Ignored)
+    }
+}
+
+// Only simple patterns can be typed: identifiers, tags without arguments and positive integers.
+// Closure arguments can only be identifiers.
+fn str_to_pattern(
+    pattern_str: &str,
+    pattern_context: PatternContext,
+    ed_model: &mut EdModel,
+) -> Option<Pattern2> {
+    let first_char = pattern_str.chars().next()?;
+
+    if !pattern_str.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+        None
+    } else if first_char.is_ascii_lowercase() {
+        let ident_id = ed_model.module.env.ident_ids.add_str(pattern_str);
+        let symbol = Symbol::new(ed_model.module.env.home, ident_id);
+
+        Some(Pattern2::Identifier(symbol))
+    } else if let PatternContext::ClosureArg { .. } = pattern_context {
+        None
+    } else if first_char.is_ascii_uppercase() {
+        let env = &mut ed_model.module.env;
+
+        Some(Pattern2::Tag {
+            whole_var: env.var_store.fresh(),
+            ext_var: env.var_store.fresh(),
+            tag_name: PoolStr::new(pattern_str, env.pool),
+            arguments: PoolVec::empty(env.pool),
+        })
+    } else if pattern_str.chars().all(|ch| ch.is_ascii_digit()) {
+        // 00, 01 are not valid ints
+        if pattern_str.len() > 1 && first_char == '0' {
+            None
+        } else {
+            let number = pattern_str.parse::<i64>().ok()?;
+
+            Some(Pattern2::NumLiteral(
+                ed_model.module.env.var_store.fresh(),
+                number,
+            ))
+        }
+    } else {
+        None
+    }
+}
+
+// inserts a new `_` pattern in a when branch or a new `_` closure argument after the
+// pattern displayed by pattern_mark_node_id, the caret is moved to the new pattern.
+pub fn add_underscore_pattern(
+    ed_model: &mut EdModel,
+    pattern_mark_node_id: MarkNodeId,
+    expr_id: ExprId,
+    separator_len: usize,
+) -> EdResult<InputOutcome> {
+    let pattern_context = match get_pattern_id(pattern_mark_node_id, expr_id, ed_model)? {
+        Some((_, pattern_context)) => pattern_context,
+        None => return Ok(InputOutcome::Ignored),
+    };
+
+    let pool = &mut ed_model.module.env.pool;
+
+    match (pool.get(expr_id), pattern_context) {
+        (
+            Expr2::When { branches, .. },
+            PatternContext::WhenBranch {
+                branch_index,
+                pattern_index,
+            },
+        ) => {
+            // safe unwrap, get_pattern_id checked the branch exists
+            let when_branch = branches.iter(pool).nth(branch_index).unwrap();
+            let mut new_patterns: Vec<Pattern2> = when_branch
+                .patterns
+                .iter(pool)
+                .map(|pattern| pattern.shallow_clone())
+                .collect();
+
+            new_patterns.insert(pattern_index + 1, Pattern2::Underscore);
+
+            let new_patterns = PoolVec::new(new_patterns.into_iter(), pool);
+
+            // safe unwrap, get_pattern_id checked the branch exists
+            let branch_id = branches.iter_node_ids().nth(branch_index).unwrap();
+            pool.get_mut(branch_id).patterns = new_patterns;
+        }
+        (Expr2::Closure { args, .. }, PatternContext::ClosureArg { arg_index }) => {
+            let mut new_args: Vec<(_, _)> = args.iter(pool).copied().collect();
+
+            let new_pattern_id = pool.add(Pattern2::Underscore);
+            new_args.insert(
+                arg_index + 1,
+                (ed_model.module.env.var_store.fresh(), new_pattern_id),
+            );
+
+            let pool = &mut ed_model.module.env.pool;
+            let new_args = PoolVec::new(new_args.into_iter(), pool);
+
+            if let Expr2::Closure { args, .. } = pool.get_mut(expr_id) {
+                *args = new_args;
+            }
+        }
+        _ => return Ok(InputOutcome::Ignored),
+    }
+
+    ed_model.simple_move_carets_right(separator_len);
+
+    Ok(InputOutcome::Accepted)
+}
+
+#[cfg(test)]
+pub mod test_pattern_update {
+    use crate::editor::mvc::ed_harness::{assert_key_seq, assert_key_steps, chars, ctrl};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    #[test]
+    fn replace_underscore() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = when 5 is", "    ┃_ -> 1"],
+            vec![
+                (
+                    chars("Ok"),
+                    ovec!["val = when 5 is", "    Ok┃ -> 1", "", ""],
+                ),
+                (
+                    vec![ctrl(Z)],
+                    ovec!["val = when 5 is", "    O┃ -> 1", "", ""],
+                ),
+                (
+                    vec![ctrl(Z)],
+                    ovec!["val = when 5 is", "    ┃_ -> 1", "", ""],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn insert_in_number() -> Result<(), String> {
+        assert_key_seq(
+            ovec!["val = when 5 is", "    ┃0 -> 1"],
+            ovec!["val = when 5 is", "    1┃0 -> 1", "", ""],
+            chars("1"),
+        )?;
+
+        // 01 is not a valid int
+        assert_key_seq(
+            ovec!["val = when 5 is", "    0┃ -> 1"],
+            ovec!["val = when 5 is", "    0┃ -> 1", "", ""],
+            chars("1"),
+        )
+    }
+
+    #[test]
+    fn closure_arg_is_identifier() -> Result<(), String> {
+        assert_key_seq(
+            ovec!["val = \\┃_ -> 5"],
+            ovec!["val = \\x┃ -> 5", "", ""],
+            chars("X1x"),
+        )
+    }
+
+    #[test]
+    fn add_when_pattern() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = when 5 is", "    A┃ -> 1"],
+            vec![
+                (
+                    chars("|"),
+                    ovec!["val = when 5 is", "    A | ┃_ -> 1", "", ""],
+                ),
+                (
+                    chars("B"),
+                    ovec!["val = when 5 is", "    A | B┃ -> 1", "", ""],
+                ),
+                (
+                    vec![ctrl(Z), ctrl(Z)],
+                    ovec!["val = when 5 is", "    A┃ -> 1", "", ""],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn add_closure_arg() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = \\x┃ -> 5"],
+            vec![
+                (chars(","), ovec!["val = \\x, ┃_ -> 5", "", ""]),
+                (chars("y"), ovec!["val = \\x, y┃ -> 5", "", ""]),
+                (vec![ctrl(Z), ctrl(Z)], ovec!["val = \\x┃ -> 5", "", ""]),
+            ],
+        )
+    }
+}
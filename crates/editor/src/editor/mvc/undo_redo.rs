@@ -0,0 +1,263 @@
+use super::ed_model::EdModel;
+use crate::editor::ed_error::EdResult;
+use crate::ui::text::caret_w_select::CaretWSelect;
+use nonempty::NonEmpty;
+use roc_ast::lang::core::def::def2::DefId;
+use roc_ast::mem_pool::pool::{Pool, PoolDiff, PoolSnapshot};
+use roc_module::symbol::IdentIds;
+
+// older edits are forgotten once the undo history reaches this length
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// Everything but the AST nodes that an undo or redo has to restore, saved right before an edit.
+/// MarkupNodes, code_lines and grid_node_map are rebuilt from the AST when a step is undone.
+#[derive(Debug)]
+pub struct EdState {
+    def_ids: Vec<DefId>,
+    ident_ids: IdentIds,
+    interns_ident_ids_opt: Option<IdentIds>,
+    carets: NonEmpty<CaretWSelect>,
+}
+
+/// An edit that can be undone (or an undo that can be redone): the AST nodes it overwrote and the
+/// state from before it.
+#[derive(Debug)]
+struct EdStep {
+    pool_diff: PoolDiff,
+    state: EdState,
+}
+
+/// Undo and redo history of an EdModel, every accepted edit that changed the code is one step.
+#[derive(Debug)]
+pub struct UndoRedo {
+    undo_stack: Vec<EdStep>,
+    redo_stack: Vec<EdStep>,
+    // the AST nodes as of the last step, to find out which nodes the next edit changes
+    pool_snapshot: PoolSnapshot,
+}
+
+impl UndoRedo {
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            pool_snapshot: pool.snapshot(),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+impl<'a> EdModel<'a> {
+    // call before an edit, and pass the result to push_undo_step afterwards
+    pub fn save_state(&self) -> EdState {
+        let module = &self.module;
+
+        EdState {
+            def_ids: module.ast.def_ids.clone(),
+            ident_ids: module.env.ident_ids.clone(),
+            interns_ident_ids_opt: self
+                .loaded_module
+                .interns
+                .all_ident_ids
+                .get(&module.env.home)
+                .cloned(),
+            carets: self.caret_w_select_vec.clone().map(|caret_tup| caret_tup.0),
+        }
+    }
+
+    // call after an edit was accepted, prev_state should be saved before the edit
+    pub fn push_undo_step(&mut self, prev_state: EdState) {
+        let pool_diff = self
+            .module
+            .env
+            .pool
+            .update_snapshot(&mut self.undo_redo.pool_snapshot);
+
+        // e.g. selecting an expression with backspace does not change the code
+        if pool_diff.is_empty()
+            && prev_state.def_ids == self.module.ast.def_ids
+            && prev_state.ident_ids == self.module.env.ident_ids
+        {
+            return;
+        }
+
+        let undo_stack = &mut self.undo_redo.undo_stack;
+
+        if undo_stack.len() == MAX_UNDO_STEPS {
+            undo_stack.remove(0);
+        }
+
+        undo_stack.push(EdStep {
+            pool_diff,
+            state: prev_state,
+        });
+        self.undo_redo.redo_stack.clear();
+    }
+
+    // ctrl+z
+    pub fn undo(&mut self) -> EdResult<()> {
+        if let Some(step) = self.undo_redo.undo_stack.pop() {
+            let redo_step = self.apply_step(step)?;
+
+            self.undo_redo.redo_stack.push(redo_step);
+        }
+
+        Ok(())
+    }
+
+    // ctrl+shift+z
+    pub fn redo(&mut self) -> EdResult<()> {
+        if let Some(step) = self.undo_redo.redo_stack.pop() {
+            let undo_step = self.apply_step(step)?;
+
+            self.undo_redo.undo_stack.push(undo_step);
+        }
+
+        Ok(())
+    }
+
+    // returns the step that reverts this one
+    fn apply_step(&mut self, step: EdStep) -> EdResult<EdStep> {
+        let home = self.module.env.home;
+        let curr_state = self.save_state();
+
+        let pool_diff = self
+            .module
+            .env
+            .pool
+            .apply_diff(&step.pool_diff, &mut self.undo_redo.pool_snapshot);
+
+        let state = step.state;
+        self.module.ast.def_ids = state.def_ids;
+        self.module.env.ident_ids = state.ident_ids;
+
+        if let Some(interns_ident_ids) = state.interns_ident_ids_opt {
+            self.loaded_module
+                .interns
+                .all_ident_ids
+                .insert(home, interns_ident_ids);
+        }
+
+        self.post_process_ast_update()?;
+
+        self.caret_w_select_vec = state.carets.map(|caret_w_sel| (caret_w_sel, None));
+        self.selected_block_opt = None;
+        self.dirty = true;
+
+        Ok(EdStep {
+            pool_diff,
+            state: curr_state,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test_undo_redo {
+    use crate::editor::mvc::ed_harness::{assert_key_steps, chars, ctrl, ctrl_shift, key};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    #[test]
+    fn undo_redo_int() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = 1┃"],
+            vec![
+                (chars("23"), ovec!["val = 123┃", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = 12┃", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = 1┃", "", ""]),
+                // nothing left to undo
+                (vec![ctrl(Z)], ovec!["val = 1┃", "", ""]),
+                (vec![ctrl_shift(Z)], ovec!["val = 12┃", "", ""]),
+                (vec![ctrl_shift(Z)], ovec!["val = 123┃", "", ""]),
+                // nothing left to redo
+                (vec![ctrl_shift(Z)], ovec!["val = 123┃", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn undo_restores_caret() -> Result<(), String> {
+        let mut key_events = chars("2");
+        key_events.push(key(Home));
+
+        assert_key_steps(
+            ovec!["val = 1┃"],
+            vec![
+                (key_events, ovec!["┃val = 12", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = 1┃", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn undo_tld_value() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["┃"],
+            vec![
+                (chars("ab"), ovec!["ab┃ =  ", "", ""]),
+                (vec![ctrl(Z)], ovec!["a┃ =  ", "", ""]),
+                (vec![ctrl_shift(Z)], ovec!["ab┃ =  ", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn undo_backspace() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = \"hello\"┃"],
+            vec![
+                // first backspace selects the string, second one replaces it with a blank
+                (chars("\u{8}\u{8}"), ovec!["val = ┃ ", "", ""]),
+                // selecting the string did not change the code, so it is not an undo step
+                (vec![ctrl(Z)], ovec!["val = ┃❮\"hello\"❯", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = ┃❮\"hello\"❯", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn undo_list_elt() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = [ ┃ ]"],
+            vec![
+                (chars("1"), ovec!["val = [ 1┃ ]", "", ""]),
+                // adding the blank list element before the 1 is not a separate step
+                (vec![ctrl(Z)], ovec!["val = [ ┃ ]", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = [ ┃ ]", "", ""]),
+            ],
+        )
+    }
+
+    #[test]
+    fn new_edit_clears_redo() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = 1┃"],
+            vec![
+                (chars("2"), ovec!["val = 12┃", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = 1┃", "", ""]),
+                (chars("3"), ovec!["val = 13┃", "", ""]),
+                (vec![ctrl_shift(Z)], ovec!["val = 13┃", "", ""]),
+                (vec![ctrl(Z)], ovec!["val = 1┃", "", ""]),
+            ],
+        )
+    }
+}
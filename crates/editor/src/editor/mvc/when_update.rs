@@ -0,0 +1,240 @@
+use roc_ast::lang::core::ast::ASTNodeId;
+use roc_ast::lang::core::expr::expr2::{Expr2, ExprId, WhenBranch};
+use roc_ast::lang::core::pattern::Pattern2;
+use roc_ast::mem_pool::pool_vec::PoolVec;
+use roc_ast::mem_pool::shallow_clone::ShallowClone;
+use roc_code_markup::markup::nodes::MarkupNode;
+
+use crate::editor::ed_error::EdResult;
+use crate::editor::mvc::app_update::InputOutcome;
+use crate::editor::mvc::ed_model::EdModel;
+use crate::ui::text::lines::SelectableLines;
+use crate::ui::text::text_pos::TextPos;
+
+// replaces the expression at when_expr_id with
+// ```
+// when Blank is
+//     _ -> Blank
+// ```
+// and puts the caret on the condition.
+pub fn start_new_when(ed_model: &mut EdModel, when_expr_id: ExprId) -> EdResult<InputOutcome> {
+    let pool = &mut ed_model.module.env.pool;
+
+    let cond = pool.add(Expr2::Blank);
+    let first_branch = new_when_branch(ed_model);
+    let branches = PoolVec::new(vec![first_branch].into_iter(), ed_model.module.env.pool);
+
+    let expr2_node = Expr2::When {
+        cond_var: ed_model.module.env.var_store.fresh(),
+        expr_var: ed_model.module.env.var_store.fresh(),
+        branches,
+        cond,
+    };
+
+    ed_model.module.env.pool.set(when_expr_id, expr2_node);
+
+    // the caret is right after `when`, skip the space
+    ed_model.simple_move_carets_right(1);
+
+    Ok(InputOutcome::Accepted)
+}
+
+// `_ -> Blank`
+fn new_when_branch(ed_model: &mut EdModel) -> WhenBranch {
+    let pool = &mut ed_model.module.env.pool;
+
+    let body = pool.add(Expr2::Blank);
+
+    WhenBranch {
+        patterns: PoolVec::new(vec![Pattern2::Underscore].into_iter(), pool),
+        body,
+        guard: None,
+    }
+}
+
+// If the caret is at the end of a when branch, a new `_ -> Blank` branch is added after it
+// and the caret is put on its `_`. Returns Ignored if the caret is not at the end of a branch.
+pub fn add_when_branch(ed_model: &mut EdModel) -> EdResult<InputOutcome> {
+    let old_caret_pos = ed_model.get_caret();
+
+    let prev_mark_node_id = match ed_model.get_prev_mark_node_id()? {
+        Some(prev_mark_node_id) => prev_mark_node_id,
+        None => return Ok(InputOutcome::Ignored),
+    };
+
+    // walk up the MarkupNode tree as long as the node is the last child, until we find a branch
+    let mut mark_node_id = prev_mark_node_id;
+
+    while let Some(parent_id) = ed_model
+        .mark_node_pool
+        .get(mark_node_id)
+        .get_parent_id_opt()
+    {
+        let parent = ed_model.mark_node_pool.get(parent_id);
+        let siblings = parent.get_children_ids();
+
+        if siblings.last() != Some(&mark_node_id) {
+            break;
+        }
+
+        if let Some(grand_parent_id) = parent.get_parent_id_opt() {
+            if let ASTNodeId::AExprId(when_expr_id) =
+                ed_model.mark_id_ast_id_map.get(grand_parent_id)?
+            {
+                let grand_parent = ed_model.mark_node_pool.get(grand_parent_id);
+
+                // children of a when: `when `, condition, ` is`, branches
+                let index_in_when = grand_parent
+                    .get_children_ids()
+                    .iter()
+                    .position(|child_id| *child_id == parent_id);
+
+                let is_branch = matches!(
+                    ed_model.module.env.pool.get(when_expr_id),
+                    Expr2::When { .. }
+                ) && ed_model.mark_id_ast_id_map.get(parent_id)?
+                    == ASTNodeId::AExprId(when_expr_id);
+
+                match index_in_when {
+                    Some(index_in_when) if is_branch && index_in_when >= 3 => {
+                        let indent_len = match parent
+                            .get_children_ids()
+                            .first()
+                            .map(|child_id| ed_model.mark_node_pool.get(*child_id))
+                        {
+                            Some(indent_mn @ MarkupNode::Indent { .. }) => {
+                                indent_mn.get_content().len()
+                            }
+                            _ => 0,
+                        };
+
+                        insert_when_branch(ed_model, when_expr_id, index_in_when - 3 + 1);
+
+                        ed_model.set_caret(TextPos {
+                            line: old_caret_pos.line + 1,
+                            column: indent_len,
+                        });
+
+                        return Ok(InputOutcome::Accepted);
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        mark_node_id = parent_id;
+    }
+
+    Ok(InputOutcome::Ignored)
+}
+
+fn insert_when_branch(ed_model: &mut EdModel, when_expr_id: ExprId, new_branch_index: usize) {
+    let new_branch = new_when_branch(ed_model);
+
+    let pool = &mut ed_model.module.env.pool;
+
+    if let Expr2::When { branches, .. } = pool.get(when_expr_id) {
+        let mut new_branches: Vec<WhenBranch> = branches
+            .iter(pool)
+            .map(|when_branch| WhenBranch {
+                patterns: when_branch.patterns.shallow_clone(),
+                body: when_branch.body,
+                guard: when_branch.guard,
+            })
+            .collect();
+
+        new_branches.insert(new_branch_index, new_branch);
+
+        let new_branches = PoolVec::new(new_branches.into_iter(), pool);
+
+        if let Expr2::When { branches, .. } = pool.get_mut(when_expr_id) {
+            *branches = new_branches;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test_when_update {
+    use crate::editor::mvc::ed_harness::{assert_key_steps, chars, ctrl, key};
+    use winit::event::VirtualKeyCode::*;
+
+    // to create Vec<String> from list of &str
+    macro_rules! ovec {
+        ( $( $x:expr ),* ) => {
+            {
+                vec![
+                    $(
+                        $x.to_owned(),
+                    )*
+                ]
+            }
+        };
+    }
+
+    #[test]
+    fn enter_adds_branch() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = when 5 is", "    _ -> 1┃"],
+            vec![
+                (
+                    chars("\r"),
+                    ovec!["val = when 5 is", "    _ -> 1", "    ┃_ ->  ", "", ""],
+                ),
+                (
+                    chars("Ok"),
+                    ovec!["val = when 5 is", "    _ -> 1", "    Ok┃ ->  ", "", ""],
+                ),
+                (
+                    vec![ctrl(Z), ctrl(Z), ctrl(Z)],
+                    ovec!["val = when 5 is", "    _ -> 1┃", "", ""],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn enter_between_branches() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = when 5 is", "    A -> 1┃", "    B -> 2"],
+            vec![(
+                chars("\r"),
+                ovec![
+                    "val = when 5 is",
+                    "    A -> 1",
+                    "    ┃_ ->  ",
+                    "    B -> 2",
+                    "",
+                    ""
+                ],
+            )],
+        )
+    }
+
+    #[test]
+    fn type_when_with_two_branches() -> Result<(), String> {
+        let mut key_events = chars("val");
+        key_events.extend(vec![key(Right), key(Right), key(Right)]);
+        key_events.extend(chars("when 5"));
+        key_events.extend(vec![key(Down), key(Home)]);
+        key_events.extend(chars("Ok"));
+        // the caret should be on the Blank
+        key_events.extend(vec![key(End), key(Left)]);
+        key_events.extend(chars("1\r2"));
+
+        assert_key_steps(
+            ovec!["┃"],
+            vec![(
+                key_events,
+                ovec!["val = when 5 is", "    Ok -> 1", "    2┃ ->  ", "", ""],
+            )],
+        )
+    }
+
+    #[test]
+    fn enter_after_is_does_not_add_branch() -> Result<(), String> {
+        assert_key_steps(
+            ovec!["val = when 5 is┃", "    _ -> 1"],
+            vec![(chars("\r"), ovec!["val = when 5 is", "    _ -> 1", "┃", ""])],
+        )
+    }
+}
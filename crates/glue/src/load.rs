@@ -88,6 +88,9 @@ pub fn load_types(
             render: RenderTarget::Generic,
             threading,
             exec_mode: ExecutionMode::Check,
+            inline_and_fold: false,
+            record_call_regions: false,
+            collect_package_modules: false,
         },
     )
     .unwrap_or_else(|problem| match problem {
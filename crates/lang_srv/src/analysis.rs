@@ -0,0 +1,320 @@
+//! Loading a document with `roc_load`, and answering questions about the result.
+use crate::convert::LineIndex;
+use bumpalo::Bump;
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, Hover, HoverContents, Location, MarkupContent, MarkupKind,
+    NumberOrString, Url,
+};
+use roc_can::traverse::{find_closest_type_at, symbol_occurrences, SymbolOccurrence};
+use roc_load::{LoadedModule, LoadingProblem};
+use roc_module::symbol::ModuleId;
+use roc_region::all::{LineInfo, Region};
+use roc_reporting::report::{
+    can_problem, type_problem, CiWrite, RenderTarget, Report, RocDocAllocator, Severity,
+};
+use roc_target::TargetInfo;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+const DIAGNOSTIC_SOURCE: &str = "roc";
+
+/// The result of loading a document.
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// `None` if loading failed, e.g. because of a syntax error
+    module: Option<LoadedModule>,
+}
+
+pub fn analyze(path: &Path, source: &str) -> Analysis {
+    // the compiler still panics on some programs; that should not take the server down with it
+    match catch_unwind(AssertUnwindSafe(|| analyze_help(path, source))) {
+        Ok(analysis) => analysis,
+        Err(_) => Analysis {
+            diagnostics: vec![Diagnostic {
+                severity: Some(DiagnosticSeverity::Error),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: "The compiler crashed while checking this file.".to_string(),
+                ..Diagnostic::default()
+            }],
+            module: None,
+        },
+    }
+}
+
+fn analyze_help(path: &Path, source: &str) -> Analysis {
+    let arena = Bump::new();
+    let src_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let loaded = roc_load::load_and_typecheck_str(
+        &arena,
+        path.to_path_buf(),
+        arena.alloc_str(source),
+        src_dir,
+        Default::default(),
+        TargetInfo::default_x86_64(),
+        RenderTarget::Generic,
+    );
+
+    match loaded {
+        Ok(mut module) => Analysis {
+            diagnostics: problem_diagnostics(&mut module),
+            module: Some(module),
+        },
+        Err(LoadingProblem::FormattedReport(report)) => Analysis {
+            diagnostics: vec![formatted_report_diagnostic(source, &report)],
+            module: None,
+        },
+        Err(LoadingProblem::FileProblem { filename, error }) => Analysis {
+            diagnostics: vec![Diagnostic {
+                severity: Some(DiagnosticSeverity::Error),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: format!("I could not read {}: {:?}", filename.display(), error),
+                ..Diagnostic::default()
+            }],
+            module: None,
+        },
+        Err(other) => Analysis {
+            diagnostics: vec![Diagnostic {
+                severity: Some(DiagnosticSeverity::Error),
+                source: Some(DIAGNOSTIC_SOURCE.to_string()),
+                message: format!("Loading this file failed: {:?}", other),
+                ..Diagnostic::default()
+            }],
+            module: None,
+        },
+    }
+}
+
+/// The problems of the module that was loaded, not those of its dependencies. Those are
+/// reported when they are opened themselves.
+fn problem_diagnostics(module: &mut LoadedModule) -> Vec<Diagnostic> {
+    let home = module.module_id;
+    let mut diagnostics = Vec::new();
+
+    let (path, src) = match module.sources.get(&home) {
+        Some(source) => source,
+        None => return diagnostics,
+    };
+
+    let src_lines: Vec<&str> = src.split('\n').collect();
+    let lines = LineInfo::new(src);
+    let line_index = LineIndex::new(src);
+    let alloc = RocDocAllocator::new(&src_lines, home, &module.interns);
+
+    for problem in module.can_problems.remove(&home).unwrap_or_default() {
+        let region = problem.region();
+        let report = can_problem(&alloc, &lines, path.clone(), problem);
+
+        diagnostics.push(report_diagnostic(report, &line_index, region));
+    }
+
+    for problem in module.type_problems.remove(&home).unwrap_or_default() {
+        let region = problem.region();
+
+        if let Some(report) = type_problem(&alloc, &lines, path.clone(), problem) {
+            diagnostics.push(report_diagnostic(report, &line_index, region));
+        }
+    }
+
+    diagnostics
+}
+
+fn report_diagnostic(
+    report: Report<'_>,
+    line_index: &LineIndex,
+    region: Option<Region>,
+) -> Diagnostic {
+    let severity = match report.severity {
+        Severity::RuntimeError => DiagnosticSeverity::Error,
+        Severity::Warning => DiagnosticSeverity::Warning,
+    };
+
+    // only render the body; the editor shows the file already, and the title goes in the code
+    let mut message = String::new();
+    report
+        .doc
+        .1
+        .render_raw(70, &mut CiWrite::new(&mut message))
+        .expect("<buffer is not a utf-8 encoded string>");
+
+    Diagnostic {
+        range: line_index.to_lsp_range(region.unwrap_or_else(Region::zero)),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(report.title)),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message: message.trim().to_string(),
+        ..Diagnostic::default()
+    }
+}
+
+/// Syntax errors and the like only come back as a rendered report. We put the diagnostic on the
+/// first line of source code that the report shows.
+fn formatted_report_diagnostic(source: &str, report: &str) -> Diagnostic {
+    let mut report_lines = report.trim().lines();
+
+    // the header looks like `── TITLE ───── path/to/File.roc ─`
+    let header = report_lines.next().unwrap_or_default();
+    let title = header
+        .trim_start_matches(|c| c == '─' || c == ' ')
+        .split(" ─")
+        .next()
+        .unwrap_or_default();
+
+    let body: Vec<&str> = report_lines.collect();
+
+    // source code is shown with a gutter, like ` 4│  x = `
+    let line = body.iter().find_map(|line| {
+        let (number, _) = line.split_once('│')?;
+
+        number.trim().parse::<u32>().ok()
+    });
+
+    let range = match line {
+        Some(number) => LineIndex::new(source).line_range(number.saturating_sub(1)),
+        None => lsp_types::Range::default(),
+    };
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::Error),
+        code: Some(NumberOrString::String(title.to_string())),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message: body.join("\n").trim().to_string(),
+        ..Diagnostic::default()
+    }
+}
+
+impl Analysis {
+    pub fn hover(&mut self, position: lsp_types::Position) -> Option<Hover> {
+        let module = self.module.as_mut()?;
+        let home = module.module_id;
+        let (_, src) = module.sources.get(&home)?;
+        let line_index = LineIndex::new(src);
+        let position = line_index.to_roc_position(position);
+
+        // only the types of the module that was loaded are still around
+        let decls = module.declarations_by_id.get(&home)?;
+        let (region, var) = find_closest_type_at(position, decls)?;
+
+        let typ = name_and_print_var(
+            var,
+            module.solved.inner_mut(),
+            home,
+            &module.interns,
+            DebugPrint::NOTHING,
+        );
+
+        let symbol = symbol_occurrences(decls)
+            .into_iter()
+            .find(|occurrence| occurrence.region == region);
+
+        let signature = match symbol {
+            Some(occurrence) => format!("{} : {}", occurrence.symbol.as_str(&module.interns), typ),
+            None => typ,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```roc\n{}\n```", signature),
+            }),
+            range: Some(line_index.to_lsp_range(region)),
+        })
+    }
+
+    pub fn definition(&self, position: lsp_types::Position) -> Option<Location> {
+        let module = self.module.as_ref()?;
+        let occurrence = self.symbol_at(position)?;
+        let symbol = occurrence.symbol;
+
+        // builtins are not loaded from source, so we cannot point at them
+        let decls = module.declarations_by_id.get(&symbol.module_id())?;
+        let definition = symbol_occurrences(decls)
+            .into_iter()
+            .find(|other| other.symbol == symbol && other.is_definition)?;
+
+        self.location(symbol.module_id(), definition.region)
+    }
+
+    pub fn references(
+        &self,
+        position: lsp_types::Position,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        let module = match self.module.as_ref() {
+            Some(module) => module,
+            None => return Vec::new(),
+        };
+        let symbol = match self.symbol_at(position) {
+            Some(occurrence) => occurrence.symbol,
+            None => return Vec::new(),
+        };
+
+        let mut module_ids: Vec<ModuleId> = module.declarations_by_id.keys().copied().collect();
+        // the order of a hash map is not stable; show the references file by file
+        module_ids.sort_by_key(|id| module.sources.get(id).map(|(path, _)| path.clone()));
+
+        let mut locations = Vec::new();
+
+        for module_id in module_ids {
+            let decls = &module.declarations_by_id[&module_id];
+
+            // declarations are sorted by their dependencies, not by where they are in the file
+            let mut regions: Vec<Region> = symbol_occurrences(decls)
+                .into_iter()
+                .filter(|occurrence| {
+                    occurrence.symbol == symbol
+                        && (include_declaration || !occurrence.is_definition)
+                })
+                .map(|occurrence| occurrence.region)
+                .collect();
+            regions.sort();
+
+            locations.extend(
+                regions
+                    .into_iter()
+                    .filter_map(|region| self.location(module_id, region)),
+            );
+        }
+
+        locations
+    }
+
+    /// The innermost symbol that is defined or referenced at the position
+    fn symbol_at(&self, position: lsp_types::Position) -> Option<SymbolOccurrence> {
+        let module = self.module.as_ref()?;
+        let home = module.module_id;
+        let (_, src) = module.sources.get(&home)?;
+        let position = Region::from_pos(LineIndex::new(src).to_roc_position(position));
+
+        symbol_occurrences(module.declarations_by_id.get(&home)?)
+            .into_iter()
+            .filter(|occurrence| occurrence.region.contains(&position))
+            .min_by_key(|occurrence| region_size(occurrence.region))
+    }
+
+    fn location(&self, module_id: ModuleId, region: Region) -> Option<Location> {
+        let module = self.module.as_ref()?;
+        let (path, src) = module.sources.get(&module_id)?;
+
+        Some(Location {
+            uri: path_to_uri(path)?,
+            range: LineIndex::new(src).to_lsp_range(region),
+        })
+    }
+}
+
+fn region_size(region: Region) -> u32 {
+    region.end().offset - region.start().offset
+}
+
+fn path_to_uri(path: &Path) -> Option<Url> {
+    let path: PathBuf = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+
+    Url::from_file_path(path).ok()
+}
@@ -0,0 +1,84 @@
+//! Converting between the byte offsets of `roc_region`, and the positions of LSP, which count
+//! lines, and UTF-16 code units within a line.
+use roc_region::all::{Position, Region};
+
+pub struct LineIndex<'a> {
+    src: &'a str,
+    /// The byte offset of the start of every line
+    line_starts: Vec<u32>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        let mut line_starts = vec![0];
+
+        for (offset, byte) in src.bytes().enumerate() {
+            if byte == b'\n' {
+                line_starts.push(offset as u32 + 1);
+            }
+        }
+
+        Self { src, line_starts }
+    }
+
+    pub fn to_lsp_position(&self, position: Position) -> lsp_types::Position {
+        let offset = (position.offset as usize).min(self.src.len()) as u32;
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let line_start = self.line_starts[line] as usize;
+
+        let character = self.src[line_start..offset as usize]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+
+        lsp_types::Position::new(line as u32, character as u32)
+    }
+
+    pub fn to_lsp_range(&self, region: Region) -> lsp_types::Range {
+        lsp_types::Range::new(
+            self.to_lsp_position(region.start()),
+            self.to_lsp_position(region.end()),
+        )
+    }
+
+    /// Positions past the end of a line or of the file are moved to the end of it.
+    pub fn to_roc_position(&self, position: lsp_types::Position) -> Position {
+        let line = position.line as usize;
+
+        if line >= self.line_starts.len() {
+            return Position::new(self.src.len() as u32);
+        }
+
+        let line_start = self.line_starts[line] as usize;
+        let line_end = match self.line_starts.get(line + 1) {
+            Some(next_start) => *next_start as usize - 1,
+            None => self.src.len(),
+        };
+
+        let mut character = 0;
+        let mut offset = line_start;
+
+        for ch in self.src[line_start..line_end].chars() {
+            if character >= position.character as usize {
+                break;
+            }
+
+            character += ch.len_utf16();
+            offset += ch.len_utf8();
+        }
+
+        Position::new(offset as u32)
+    }
+
+    /// The range covering all of a line, not including its newline
+    pub fn line_range(&self, line: u32) -> lsp_types::Range {
+        let line = (line as usize).min(self.line_starts.len() - 1);
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(next_start) => *next_start - 1,
+            None => self.src.len() as u32,
+        };
+
+        self.to_lsp_range(Region::new(Position::new(start), Position::new(end)))
+    }
+}
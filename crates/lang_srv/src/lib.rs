@@ -0,0 +1,22 @@
+//! A language server for Roc, started with `roc lsp`.
+//!
+//! It speaks the [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! over stdin and stdout, and handles one message at a time. Whenever a document is opened or
+//! changed, we load it again with `roc_load` and publish its problems as diagnostics. Hover,
+//! go-to-definition and find-references then use the solved types and canonical symbols of that
+//! load.
+mod analysis;
+mod convert;
+mod rpc;
+mod server;
+
+pub use server::run;
+
+/// Run the language server on stdin and stdout. Returns the exit code: 0 if the client asked
+/// us to shut down before exiting, 1 otherwise.
+pub fn run_stdio() -> std::io::Result<i32> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+
+    run(stdin.lock(), stdout.lock())
+}
@@ -0,0 +1,101 @@
+//! JSON-RPC messages, framed with a `Content-Length` header like the base protocol of LSP says.
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+pub enum Message {
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+    /// A response to a request of ours. We never send requests, so these are ignored.
+    Response,
+}
+
+/// Read the next message, or `None` if the input is closed.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut content_length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            // headers are separated from the content by an empty line, but be lenient about
+            // empty lines that come before the headers
+            if content_length.is_some() {
+                break;
+            }
+        } else if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(invalid_data)?);
+            }
+        } else {
+            return Err(invalid_data(format!("malformed header {:?}", header)));
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    input.read_exact(&mut content)?;
+
+    let mut value: Value = serde_json::from_slice(&content).map_err(invalid_data)?;
+
+    let params = value
+        .get_mut("params")
+        .map(Value::take)
+        .unwrap_or(Value::Null);
+    let method = value
+        .get("method")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let message = match (value.get_mut("id").map(Value::take), method) {
+        (Some(id), Some(method)) => Message::Request { id, method, params },
+        (None, Some(method)) => Message::Notification { method, params },
+        (_, None) => Message::Response,
+    };
+
+    Ok(Some(message))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn invalid_data<E: ToString>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
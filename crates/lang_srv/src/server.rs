@@ -0,0 +1,201 @@
+use crate::analysis::{analyze, Analysis};
+use crate::rpc::{self, Message, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Notification,
+    PublishDiagnostics,
+};
+use lsp_types::request::{GotoDefinition, HoverRequest, Initialize, References, Request, Shutdown};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    GotoDefinitionParams, GotoDefinitionResponse, HoverParams, HoverProviderCapability,
+    InitializeResult, OneOf, PublishDiagnosticsParams, ReferenceParams, ServerCapabilities,
+    ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use roc_collections::all::MutMap;
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(Default)]
+struct Server {
+    documents: MutMap<Url, Analysis>,
+    shutdown_requested: bool,
+}
+
+/// Serve one client until it sends `exit`, or closes the input. Returns the exit code.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<i32> {
+    let mut server = Server::default();
+
+    while let Some(message) = rpc::read_message(&mut input)? {
+        match message {
+            Message::Request { id, method, params } => {
+                let response = server.handle_request(id, &method, params);
+
+                rpc::write_message(&mut output, &response)?;
+            }
+            Message::Notification { method, .. } if method == Exit::METHOD => {
+                return Ok(if server.shutdown_requested { 0 } else { 1 });
+            }
+            Message::Notification { method, params } => {
+                for notification in server.handle_notification(&method, params) {
+                    rpc::write_message(&mut output, &notification)?;
+                }
+            }
+            Message::Response => {}
+        }
+    }
+
+    // the client went away without telling us
+    Ok(1)
+}
+
+impl Server {
+    fn handle_request(&mut self, id: Value, method: &str, params: Value) -> Value {
+        if self.shutdown_requested {
+            return rpc::error_response(id, INVALID_REQUEST, "the server is shutting down");
+        }
+
+        let result = match method {
+            Initialize::METHOD => Ok(Some(initialize_result())),
+            Shutdown::METHOD => {
+                self.shutdown_requested = true;
+
+                Ok(None)
+            }
+            HoverRequest::METHOD => parse_params(params).map(|params: HoverParams| {
+                let position = params.text_document_position_params;
+
+                self.documents
+                    .get_mut(&position.text_document.uri)
+                    .and_then(|analysis| analysis.hover(position.position))
+                    .map(|hover| serde_json::to_value(hover).unwrap())
+            }),
+            GotoDefinition::METHOD => parse_params(params).map(|params: GotoDefinitionParams| {
+                let position = params.text_document_position_params;
+
+                self.documents
+                    .get(&position.text_document.uri)
+                    .and_then(|analysis| analysis.definition(position.position))
+                    .map(|location| {
+                        serde_json::to_value(GotoDefinitionResponse::Scalar(location)).unwrap()
+                    })
+            }),
+            References::METHOD => parse_params(params).map(|params: ReferenceParams| {
+                let position = params.text_document_position;
+
+                self.documents
+                    .get(&position.text_document.uri)
+                    .map(|analysis| {
+                        analysis.references(position.position, params.context.include_declaration)
+                    })
+                    .map(|locations| serde_json::to_value(locations).unwrap())
+            }),
+            _ => {
+                let message = format!("unsupported method {}", method);
+
+                return rpc::error_response(id, METHOD_NOT_FOUND, &message);
+            }
+        };
+
+        match result {
+            Ok(result) => rpc::response(id, result.unwrap_or(Value::Null)),
+            Err(message) => rpc::error_response(id, INVALID_PARAMS, &message),
+        }
+    }
+
+    /// Returns the notifications to send back.
+    fn handle_notification(&mut self, method: &str, params: Value) -> Vec<Value> {
+        match method {
+            DidOpenTextDocument::METHOD => match parse_params(params) {
+                Ok(DidOpenTextDocumentParams { text_document }) => {
+                    let version = text_document.version;
+
+                    vec![self.update(text_document.uri, &text_document.text, version)]
+                }
+                Err(_) => Vec::new(),
+            },
+            DidChangeTextDocument::METHOD => match parse_params(params) {
+                Ok(DidChangeTextDocumentParams {
+                    text_document,
+                    content_changes,
+                }) => {
+                    // we only ask for full documents, so the last change has all of the text
+                    match content_changes.last() {
+                        Some(change) => {
+                            vec![self.update(
+                                text_document.uri,
+                                &change.text,
+                                text_document.version,
+                            )]
+                        }
+                        None => Vec::new(),
+                    }
+                }
+                Err(_) => Vec::new(),
+            },
+            DidCloseTextDocument::METHOD => match parse_params(params) {
+                Ok(DidCloseTextDocumentParams { text_document }) => {
+                    self.documents.remove(&text_document.uri);
+
+                    // clear the diagnostics of the file, the editor will not show them anymore
+                    vec![publish_diagnostics(text_document.uri, Vec::new(), None)]
+                }
+                Err(_) => Vec::new(),
+            },
+            // e.g. `initialized` and `textDocument/didSave`, which we have no use for
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(&mut self, uri: Url, text: &str, version: i32) -> Value {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|()| PathBuf::from(uri.path()));
+        let analysis = analyze(&path, text);
+        let notification =
+            publish_diagnostics(uri.clone(), analysis.diagnostics.clone(), Some(version));
+
+        self.documents.insert(uri, analysis);
+
+        notification
+    }
+}
+
+fn initialize_result() -> Value {
+    let result = InitializeResult {
+        capabilities: ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::Full)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            ..ServerCapabilities::default()
+        },
+        server_info: Some(ServerInfo {
+            name: "roc".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+
+    serde_json::to_value(result).unwrap()
+}
+
+fn publish_diagnostics(
+    uri: Url,
+    diagnostics: Vec<lsp_types::Diagnostic>,
+    version: Option<i32>,
+) -> Value {
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version,
+    };
+
+    rpc::notification(
+        PublishDiagnostics::METHOD,
+        serde_json::to_value(params).unwrap(),
+    )
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, String> {
+    serde_json::from_value(params).map_err(|error| format!("invalid params: {}", error))
+}
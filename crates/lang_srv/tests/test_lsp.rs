@@ -0,0 +1,436 @@
+#[macro_use]
+extern crate pretty_assertions;
+#[macro_use]
+extern crate indoc;
+
+#[cfg(test)]
+mod test_lsp {
+    use lsp_types::Url;
+    use serde_json::{json, Value};
+    use std::io::{BufRead, Cursor, Read};
+
+    /// Play the part of an editor: send all the messages, then read back everything the server
+    /// said. The server handles one message at a time, so there is no need to interleave.
+    fn session(messages: &[Value]) -> (i32, Vec<Value>) {
+        let mut input = Vec::new();
+
+        for message in messages {
+            let content = message.to_string();
+
+            input.extend(format!("Content-Length: {}\r\n\r\n{}", content.len(), content).bytes());
+        }
+
+        let mut output = Vec::new();
+        let exit_code = roc_lang_srv::run(Cursor::new(input), &mut output).unwrap();
+
+        let mut replies = Vec::new();
+        let mut output = Cursor::new(output);
+
+        loop {
+            let mut header = String::new();
+
+            if output.read_line(&mut header).unwrap() == 0 {
+                break;
+            }
+
+            let length: usize = header
+                .trim()
+                .strip_prefix("Content-Length: ")
+                .unwrap()
+                .parse()
+                .unwrap();
+
+            let mut separator = String::new();
+            output.read_line(&mut separator).unwrap();
+            assert_eq!(separator, "\r\n");
+
+            let mut content = vec![0; length];
+            output.read_exact(&mut content).unwrap();
+
+            replies.push(serde_json::from_slice(&content).unwrap());
+        }
+
+        (exit_code, replies)
+    }
+
+    fn test_uri() -> Url {
+        Url::from_file_path(std::env::temp_dir().join("roc-lsp-test").join("Test.roc")).unwrap()
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    /// Open the source, send the requests, and shut down. Returns the published diagnostics,
+    /// and the results of the requests.
+    fn with_document(source: &str, requests: &[(&str, Value)]) -> (Vec<Value>, Vec<Value>) {
+        let mut messages = vec![
+            request(0, "initialize", json!({ "capabilities": {} })),
+            notification("initialized", json!({})),
+            notification(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": {
+                        "uri": test_uri(),
+                        "languageId": "roc",
+                        "version": 1,
+                        "text": source,
+                    }
+                }),
+            ),
+        ];
+
+        for (index, (method, params)) in requests.iter().enumerate() {
+            messages.push(request(index as u64 + 1, method, params.clone()));
+        }
+
+        let shutdown_id = requests.len() as u64 + 1;
+        messages.push(request(shutdown_id, "shutdown", Value::Null));
+        messages.push(notification("exit", Value::Null));
+
+        let (exit_code, replies) = session(&messages);
+        assert_eq!(exit_code, 0);
+
+        let mut diagnostics = Vec::new();
+        let mut results = Vec::new();
+
+        for reply in replies {
+            if reply["method"] == "textDocument/publishDiagnostics" {
+                assert_eq!(reply["params"]["uri"], json!(test_uri()));
+                diagnostics = reply["params"]["diagnostics"].as_array().unwrap().clone();
+            } else {
+                let id = reply["id"].as_u64().unwrap();
+
+                if id != 0 && id != shutdown_id {
+                    results.push(reply["result"].clone());
+                }
+            }
+        }
+
+        (diagnostics, results)
+    }
+
+    fn at(line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": test_uri() },
+            "position": { "line": line, "character": character },
+        })
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+        json!({
+            "start": { "line": start.0, "character": start.1 },
+            "end": { "line": end.0, "character": end.1 },
+        })
+    }
+
+    const DOUBLE: &str = indoc!(
+        r#"
+        interface Test
+            exposes [main]
+            imports []
+
+        double : I64 -> I64
+        double = \n -> n * 2
+
+        main = double (double 21)
+        "#
+    );
+
+    #[test]
+    fn initialize_advertises_capabilities() {
+        let (exit_code, replies) = session(&[
+            request(0, "initialize", json!({ "capabilities": {} })),
+            request(1, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+        ]);
+
+        assert_eq!(exit_code, 0);
+
+        let capabilities = &replies[0]["result"]["capabilities"];
+        assert_eq!(capabilities["textDocumentSync"], json!(1));
+        assert_eq!(capabilities["hoverProvider"], json!(true));
+        assert_eq!(capabilities["definitionProvider"], json!(true));
+        assert_eq!(capabilities["referencesProvider"], json!(true));
+    }
+
+    #[test]
+    fn exit_without_shutdown() {
+        let (exit_code, _) = session(&[notification("exit", Value::Null)]);
+
+        assert_eq!(exit_code, 1);
+    }
+
+    #[test]
+    fn unknown_request() {
+        let (_, replies) = session(&[request(7, "textDocument/rename", json!({}))]);
+
+        assert_eq!(replies[0]["id"], json!(7));
+        assert_eq!(replies[0]["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    fn no_diagnostics() {
+        let (diagnostics, _) = with_document(DOUBLE, &[]);
+
+        assert_eq!(diagnostics, Vec::<Value>::new());
+    }
+
+    #[test]
+    fn type_mismatch_diagnostic() {
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [main]
+                imports []
+
+            main : Str
+            main = 1 + 2
+            "#
+        );
+
+        let (diagnostics, _) = with_document(source, &[]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["code"], json!("TYPE MISMATCH"));
+        assert_eq!(diagnostics[0]["severity"], json!(1));
+        assert_eq!(diagnostics[0]["source"], json!("roc"));
+        assert_eq!(diagnostics[0]["range"], range((5, 7), (5, 12)));
+    }
+
+    #[test]
+    fn unused_def_warning() {
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [main]
+                imports []
+
+            main =
+                unused = 1
+
+                2
+            "#
+        );
+
+        let (diagnostics, _) = with_document(source, &[]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["code"], json!("UNUSED DEFINITION"));
+        assert_eq!(diagnostics[0]["severity"], json!(2));
+        assert_eq!(diagnostics[0]["range"], range((5, 4), (5, 10)));
+    }
+
+    #[test]
+    fn syntax_error_diagnostic() {
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [main]
+                imports []
+
+            main = [1, 2
+            "#
+        );
+
+        let (diagnostics, results) = with_document(source, &[("textDocument/hover", at(4, 0))]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["severity"], json!(1));
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], json!(4));
+
+        // there is nothing to answer questions with
+        assert_eq!(results, vec![Value::Null]);
+    }
+
+    #[test]
+    fn hover() {
+        let (_, results) = with_document(
+            DOUBLE,
+            &[
+                ("textDocument/hover", at(7, 9)),
+                ("textDocument/hover", at(5, 10)),
+                ("textDocument/hover", at(7, 23)),
+            ],
+        );
+
+        assert_eq!(
+            results[0],
+            json!({
+                "contents": { "kind": "markdown", "value": "```roc\ndouble : I64 -> I64\n```" },
+                "range": range((7, 7), (7, 13)),
+            })
+        );
+        assert_eq!(
+            results[1]["contents"]["value"],
+            json!("```roc\nn : I64\n```")
+        );
+        assert_eq!(results[2]["contents"]["value"], json!("```roc\nI64\n```"));
+    }
+
+    #[test]
+    fn definition() {
+        let (_, results) = with_document(
+            DOUBLE,
+            &[
+                ("textDocument/definition", at(7, 16)),
+                ("textDocument/definition", at(5, 15)),
+                ("textDocument/definition", at(7, 23)),
+            ],
+        );
+
+        assert_eq!(
+            results[0],
+            json!({ "uri": test_uri(), "range": range((5, 0), (5, 6)) })
+        );
+        assert_eq!(
+            results[1],
+            json!({ "uri": test_uri(), "range": range((5, 10), (5, 11)) })
+        );
+        assert_eq!(results[2], Value::Null);
+    }
+
+    #[test]
+    fn references() {
+        let (_, results) = with_document(
+            DOUBLE,
+            &[
+                (
+                    "textDocument/references",
+                    json!({
+                        "textDocument": { "uri": test_uri() },
+                        "position": { "line": 7, "character": 8 },
+                        "context": { "includeDeclaration": true },
+                    }),
+                ),
+                (
+                    "textDocument/references",
+                    json!({
+                        "textDocument": { "uri": test_uri() },
+                        "position": { "line": 7, "character": 8 },
+                        "context": { "includeDeclaration": false },
+                    }),
+                ),
+            ],
+        );
+
+        let definition = json!({ "uri": test_uri(), "range": range((5, 0), (5, 6)) });
+        let outer = json!({ "uri": test_uri(), "range": range((7, 7), (7, 13)) });
+        let inner = json!({ "uri": test_uri(), "range": range((7, 15), (7, 21)) });
+
+        assert_eq!(results[0], json!([definition, outer, inner]));
+        assert_eq!(results[1], json!([outer, inner]));
+    }
+
+    #[test]
+    fn definition_in_imported_module() {
+        let dir = std::env::temp_dir().join("roc-lsp-test-imports");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let dep_path = dir.join("Dep.roc");
+        std::fs::write(
+            &dep_path,
+            indoc!(
+                r#"
+                interface Dep
+                    exposes [answer]
+                    imports []
+
+                answer = 42
+                "#
+            ),
+        )
+        .unwrap();
+
+        let uri = Url::from_file_path(dir.join("Test.roc")).unwrap();
+        let source = indoc!(
+            r#"
+            interface Test
+                exposes [main]
+                imports [Dep]
+
+            main = Dep.answer + 1
+            "#
+        );
+
+        let (_, replies) = session(&[
+            request(0, "initialize", json!({ "capabilities": {} })),
+            notification(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": { "uri": uri, "languageId": "roc", "version": 1, "text": source }
+                }),
+            ),
+            request(
+                1,
+                "textDocument/definition",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "position": { "line": 4, "character": 12 },
+                }),
+            ),
+        ]);
+
+        let definition = replies
+            .iter()
+            .find(|reply| reply["id"] == json!(1))
+            .unwrap();
+
+        assert_eq!(
+            definition["result"],
+            json!({
+                "uri": Url::from_file_path(dep_path).unwrap(),
+                "range": range((4, 0), (4, 6)),
+            })
+        );
+    }
+
+    #[test]
+    fn changes_are_checked_again() {
+        let uri = test_uri();
+        let (_, replies) = session(&[
+            request(0, "initialize", json!({ "capabilities": {} })),
+            notification(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": { "uri": uri, "languageId": "roc", "version": 1, "text": DOUBLE }
+                }),
+            ),
+            notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri, "version": 2 },
+                    "contentChanges": [{ "text": DOUBLE.replace("21", "\"21\"") }],
+                }),
+            ),
+            notification(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": uri } }),
+            ),
+            request(1, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+        ]);
+
+        let published: Vec<(Value, usize)> = replies
+            .iter()
+            .filter(|reply| reply["method"] == "textDocument/publishDiagnostics")
+            .map(|reply| {
+                let params = &reply["params"];
+
+                (
+                    params["version"].clone(),
+                    params["diagnostics"].as_array().unwrap().len(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            published,
+            vec![(json!(1), 0), (json!(2), 1), (Value::Null, 0)]
+        );
+    }
+}
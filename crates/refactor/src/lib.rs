@@ -0,0 +1,608 @@
+//! Refactorings that change every module of a package they affect, for `roc refactor` and for
+//! editors.
+//!
+//! Renaming finds the uses of a definition by the canonical symbols of a `roc_load` load, so
+//! it follows qualified uses like `Foo.bar` and leaves other things with the same name alone.
+//! Canonicalization does not keep regions for everything though: the names in the header, in
+//! types and in the implementations of abilities come from the parsed AST instead. The source is
+//! then rewritten with the lossless syntax tree of `roc_parse::cst`, so all formatting and
+//! comments stay exactly as they were.
+mod syntax;
+
+use bumpalo::Bump;
+use roc_can::traverse::symbol_occurrences;
+use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+use roc_module::ident::Ident;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_parse::cst::{Cst, TokenId, TokenKind};
+use roc_parse::keyword;
+use roc_problem::can::{Problem, ShadowKind};
+use roc_region::all::{LineInfo, Loc, Position, Region};
+use roc_reporting::report::{can_problem, RenderTarget, RocDocAllocator, DEFAULT_PALETTE};
+use roc_target::TargetInfo;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use syntax::{module_sites, Site};
+
+/// A module that a refactoring changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedModule {
+    pub path: PathBuf,
+    /// In source order, and they don't overlap
+    pub edits: Vec<Edit>,
+    /// The source with all edits applied
+    pub source: String,
+}
+
+/// Replace the text of a region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub region: Region,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub enum RenameError<'a> {
+    Loading(LoadingProblem<'a>),
+    /// No module of the package has a top-level definition by this name
+    NotFound(String),
+    /// The new name can't be given to the definition, e.g. a lowercase name to a type
+    InvalidName(String),
+    /// After renaming, the new name would shadow names that are in scope already. These are the
+    /// rendered reports canonicalization would give.
+    Shadowing(Vec<String>),
+}
+
+/// Load the package of `roc_file`, and rename the top-level definition `target`, which is
+/// either `Module.name`, or just `name` for a definition in `roc_file` itself. This does not
+/// write any files.
+pub fn rename_in_package<'a>(
+    arena: &'a Bump,
+    roc_file: PathBuf,
+    target: &str,
+    new_name: &str,
+    render: RenderTarget,
+    threading: Threading,
+) -> Result<Vec<ChangedModule>, RenameError<'a>> {
+    let load_config = LoadConfig {
+        // only used for reporting problems
+        target_info: TargetInfo::default_x86_64(),
+        render,
+        threading,
+        exec_mode: ExecutionMode::Check,
+        inline_and_fold: false,
+        record_call_regions: false,
+        collect_package_modules: false,
+    };
+
+    let loaded = roc_load::load_and_typecheck(arena, roc_file, Default::default(), load_config)
+        .map_err(RenameError::Loading)?;
+
+    let symbol =
+        find_symbol(&loaded, target).ok_or_else(|| RenameError::NotFound(target.to_string()))?;
+
+    rename(&loaded, symbol, new_name, render)
+}
+
+/// The symbol of a top-level definition, given as `Module.name`, or as just `name` for a
+/// definition in the module that was loaded. Builtins are not part of the package, so they
+/// are never found.
+pub fn find_symbol(loaded: &LoadedModule, target: &str) -> Option<Symbol> {
+    let (module_id, name) = match target.rsplit_once('.') {
+        Some((module_name, name)) => (loaded.interns.module_ids.get_id(&module_name.into())?, name),
+        None => (loaded.module_id, target),
+    };
+
+    if module_id.is_builtin() || !loaded.sources.contains_key(&module_id) {
+        return None;
+    }
+
+    let ident_id = loaded.interns.all_ident_ids.get(&module_id)?.get_id(name)?;
+
+    Some(Symbol::new(module_id, ident_id))
+}
+
+/// What kind of definition is renamed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind<'a> {
+    Value,
+    AbilityMember { ability: &'a str },
+    Type(ShadowKind),
+}
+
+struct Module<'a> {
+    id: ModuleId,
+    /// Empty for an app
+    name: &'a str,
+    path: &'a Path,
+    src: &'a str,
+    sites: Vec<Site<'a>>,
+}
+
+/// Rename a top-level value, ability member, alias, opaque type or ability in every module of
+/// the package that was loaded. This refuses to rename if the new name would shadow another
+/// name somewhere, since canonicalization would then report the shadowing, or worse, a use
+/// of the definition would now refer to the other name.
+pub fn rename<'a>(
+    loaded: &LoadedModule,
+    symbol: Symbol,
+    new_name: &str,
+    render: RenderTarget,
+) -> Result<Vec<ChangedModule>, RenameError<'a>> {
+    let interns = &loaded.interns;
+    let old_name = symbol.as_str(interns);
+    let home = symbol.module_id();
+    let arena = Bump::new();
+
+    let mut modules: Vec<Module> = loaded
+        .sources
+        .iter()
+        .filter(|(id, _)| !id.is_builtin())
+        .map(|(id, (path, src))| Module {
+            id: *id,
+            name: interns.module_name(*id).as_str(),
+            path,
+            src,
+            sites: module_sites(&arena, src),
+        })
+        .collect();
+    // the order of a hash map is not stable
+    modules.sort_by(|a, b| a.path.cmp(b.path));
+
+    let not_found =
+        || RenameError::NotFound(symbol.fully_qualified(interns, home).as_str().to_string());
+    let defining_module = modules
+        .iter()
+        .find(|module| module.id == home)
+        .ok_or_else(not_found)?;
+    let kind = definition_kind(loaded, defining_module, symbol).ok_or_else(not_found)?;
+
+    check_name(kind, new_name)?;
+
+    if new_name == old_name {
+        return Ok(Vec::new());
+    }
+
+    // where the definition is in scope without being qualified, and where it is named there
+    let mut in_scope: Vec<(&Module, Region)> = Vec::new();
+
+    for module in modules.iter() {
+        if module.id == home {
+            in_scope.push((module, definition_region(loaded, module, symbol, kind)));
+        } else if let Some(region) = imported_region(module, defining_module, old_name) {
+            in_scope.push((module, region));
+        }
+    }
+
+    let reports: Vec<String> = in_scope
+        .iter()
+        .filter_map(|(module, shadow_region)| {
+            let original_region = existing_definition(loaded, module, kind, new_name)?;
+            let shadow_kind = match kind {
+                Kind::Type(shadow_kind) => shadow_kind,
+                Kind::Value | Kind::AbilityMember { .. } => ShadowKind::Variable,
+            };
+            let problem = Problem::Shadowing {
+                original_region,
+                shadow: Loc::at(*shadow_region, Ident::from(new_name)),
+                kind: shadow_kind,
+            };
+
+            Some(render_problem(loaded, module, problem, render))
+        })
+        .collect();
+
+    if !reports.is_empty() {
+        return Err(RenameError::Shadowing(reports));
+    }
+
+    let is_in_scope = |module: &Module| in_scope.iter().any(|(other, _)| other.id == module.id);
+    let refers_to_defining_module = |module: &Module, qualifier: &str, name: &str| {
+        if qualifier.is_empty() {
+            module.id == home || imported_region(module, defining_module, name).is_some()
+        } else {
+            is_module_name(qualifier, defining_module.name)
+        }
+    };
+
+    let mut changed = Vec::new();
+
+    for module in modules.iter() {
+        let cst = Cst::parse(module.src);
+        let mut renamer = Renamer {
+            cst: &cst,
+            old_name,
+            new_name,
+            edits: BTreeMap::new(),
+        };
+
+        if let Kind::Value | Kind::AbilityMember { .. } = kind {
+            if let Some(decls) = loaded.declarations_by_id.get(&module.id) {
+                for occurrence in symbol_occurrences(decls) {
+                    if occurrence.symbol == symbol {
+                        renamer.rename_in(occurrence.region, 0);
+                    }
+                }
+            }
+        }
+
+        let definitions: Vec<Region> = match loaded.declarations_by_id.get(&module.id) {
+            Some(decls) if module.id == home => symbol_occurrences(decls)
+                .into_iter()
+                .filter(|occurrence| occurrence.symbol == symbol && occurrence.is_definition)
+                .map(|occurrence| occurrence.region)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for site in module.sites.iter() {
+            match *site {
+                Site::Exposed { name, region } if module.id == home && name == old_name => {
+                    renamer.rename_in(region, 0);
+                }
+                Site::Imported {
+                    module: from,
+                    name,
+                    region,
+                } if from == defining_module.name && name == old_name => {
+                    renamer.rename_in(region, 0);
+                }
+                Site::Annotation { name, region, body } if name == old_name => {
+                    let annotates_definition = match body {
+                        Some(body) => definitions.contains(&body),
+                        // an annotation without a body has no symbol to check
+                        None => module.id == home && kind == Kind::Value,
+                    };
+
+                    if annotates_definition {
+                        renamer.rename_in(region, 0);
+                    }
+                }
+                Site::TypeDef {
+                    name,
+                    region,
+                    top_level: true,
+                    ..
+                } if module.id == home && matches!(kind, Kind::Type(_)) && name == old_name => {
+                    renamer.rename_in(region, 0);
+                }
+                Site::Type {
+                    module: qualifier,
+                    name,
+                    region,
+                } if matches!(kind, Kind::Type(_)) && name == old_name => {
+                    let refers_to_definition =
+                        refers_to_defining_module(module, qualifier, old_name);
+
+                    if refers_to_definition {
+                        // skip the qualifier, which might have the same name, like `Dict.Dict`
+                        let skip = if qualifier.is_empty() {
+                            0
+                        } else {
+                            qualifier.len() + 1
+                        };
+
+                        renamer.rename_in(region, skip);
+                    }
+                }
+                Site::AbilityMember {
+                    ability,
+                    name,
+                    region,
+                } if module.id == home
+                    && kind == (Kind::AbilityMember { ability })
+                    && name == old_name =>
+                {
+                    renamer.rename_in(region, 0);
+                }
+                Site::Impl {
+                    ability_module,
+                    ability,
+                    label,
+                    value,
+                    ..
+                } => match kind {
+                    Kind::AbilityMember { ability: member_of }
+                        if label.value == old_name
+                            && ability == member_of
+                            && refers_to_defining_module(module, ability_module, ability) =>
+                    {
+                        match value {
+                            Some(_) => renamer.rename_in(label.region, 0),
+                            // `Eq { eq }` uses the value `eq` that is defined for it, which
+                            // keeps its name
+                            None => renamer
+                                .replace_in(label.region, format!("{}: {}", new_name, old_name)),
+                        }
+                    }
+                    Kind::Value if is_in_scope(module) => match value {
+                        Some(value) if value.value == old_name => {
+                            renamer.rename_in(value.region, 0);
+                        }
+                        None if module.id == home && label.value == old_name => {
+                            renamer.replace_in(label.region, format!("{}: {}", old_name, new_name))
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if module.id == home && kind == Kind::Type(ShadowKind::Opaque) {
+            renamer.rename_opaque_refs();
+        }
+
+        if !renamer.edits.is_empty() {
+            changed.push(renamer.finish(module.path));
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Where the module imports a name from the defining module without a qualifier, if it does
+fn imported_region(module: &Module, defining_module: &Module, name: &str) -> Option<Region> {
+    module.sites.iter().find_map(|site| match *site {
+        Site::Imported {
+            module: from,
+            name: imported,
+            region,
+        } if from == defining_module.name && imported == name => Some(region),
+        _ => None,
+    })
+}
+
+/// A qualifier can have the shorthand of a package in front, like `pf.Task`
+fn is_module_name(qualifier: &str, module_name: &str) -> bool {
+    match qualifier.strip_suffix(module_name) {
+        Some("") => true,
+        Some(shorthand) => shorthand.ends_with('.') && !module_name.is_empty(),
+        None => false,
+    }
+}
+
+fn definition_kind<'a>(
+    loaded: &LoadedModule,
+    module: &Module<'a>,
+    symbol: Symbol,
+) -> Option<Kind<'a>> {
+    let name = symbol.as_str(&loaded.interns);
+    let decls = loaded.declarations_by_id.get(&symbol.module_id())?;
+
+    // a value can have the name of an ability member, if it implements the member
+    if decls
+        .symbols
+        .iter()
+        .any(|loc_symbol| loc_symbol.value == symbol)
+    {
+        return Some(Kind::Value);
+    }
+
+    module.sites.iter().find_map(|site| match *site {
+        Site::TypeDef {
+            name: defined,
+            kind,
+            top_level: true,
+            ..
+        } if defined == name => Some(Kind::Type(kind)),
+        Site::AbilityMember {
+            ability,
+            name: defined,
+            ..
+        } if defined == name => Some(Kind::AbilityMember { ability }),
+        _ => None,
+    })
+}
+
+fn check_name<'a>(kind: Kind, new_name: &str) -> Result<(), RenameError<'a>> {
+    let is_type = matches!(kind, Kind::Type(_));
+    let invalid = |reason: &str| Err(RenameError::InvalidName(format!("{} {}", new_name, reason)));
+
+    let first = match new_name.chars().next() {
+        Some(first) => first,
+        None => return invalid("is empty"),
+    };
+
+    if !new_name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        invalid("can only have letters and digits")
+    } else if is_type && !first.is_ascii_uppercase() {
+        invalid("does not start with an uppercase letter, like the names of types")
+    } else if !is_type && !first.is_ascii_lowercase() {
+        invalid("does not start with a lowercase letter, like the names of values")
+    } else if keyword::KEYWORDS.contains(&new_name) {
+        invalid("is a keyword")
+    } else if Symbol::default_in_scope().contains_key(&Ident::from(new_name)) {
+        invalid("is the name of a builtin that is always in scope")
+    } else {
+        Ok(())
+    }
+}
+
+/// Where the definition is, in the module that defines it
+fn definition_region(loaded: &LoadedModule, module: &Module, symbol: Symbol, kind: Kind) -> Region {
+    let name = symbol.as_str(&loaded.interns);
+
+    let region = module.sites.iter().find_map(|site| match (*site, kind) {
+        (
+            Site::TypeDef {
+                name: defined,
+                region,
+                top_level: true,
+                ..
+            },
+            Kind::Type(_),
+        )
+        | (
+            Site::AbilityMember {
+                name: defined,
+                region,
+                ..
+            },
+            Kind::AbilityMember { .. },
+        ) if defined == name => Some(region),
+        _ => None,
+    });
+
+    let value_region = || {
+        loaded
+            .declarations_by_id
+            .get(&module.id)?
+            .symbols
+            .iter()
+            .find(|loc_symbol| loc_symbol.value == symbol)
+            .map(|loc_symbol| loc_symbol.region)
+    };
+
+    region.or_else(value_region).unwrap_or_else(Region::zero)
+}
+
+/// Where a name is defined in a module already, if it is
+fn existing_definition(
+    loaded: &LoadedModule,
+    module: &Module,
+    kind: Kind,
+    name: &str,
+) -> Option<Region> {
+    let from_sites = module.sites.iter().find_map(|site| match (*site, kind) {
+        (
+            Site::Imported {
+                name: defined,
+                region,
+                ..
+            },
+            _,
+        )
+        | (
+            Site::TypeDef {
+                name: defined,
+                region,
+                ..
+            },
+            Kind::Type(_),
+        )
+        | (
+            Site::AbilityMember {
+                name: defined,
+                region,
+                ..
+            },
+            Kind::Value | Kind::AbilityMember { .. },
+        ) if defined == name => Some(region),
+        _ => None,
+    });
+
+    if from_sites.is_some() || matches!(kind, Kind::Type(_)) {
+        return from_sites;
+    }
+
+    symbol_occurrences(loaded.declarations_by_id.get(&module.id)?)
+        .into_iter()
+        .filter(|occurrence| {
+            occurrence.is_definition && occurrence.symbol.as_str(&loaded.interns) == name
+        })
+        .map(|occurrence| occurrence.region)
+        .min()
+}
+
+fn render_problem(
+    loaded: &LoadedModule,
+    module: &Module,
+    problem: Problem,
+    render: RenderTarget,
+) -> String {
+    let src_lines: Vec<&str> = module.src.split('\n').collect();
+    let lines = LineInfo::new(module.src);
+    let alloc = RocDocAllocator::new(&src_lines, module.id, &loaded.interns);
+
+    let report = can_problem(&alloc, &lines, module.path.to_path_buf(), problem);
+
+    let mut buf = String::new();
+    let palette = DEFAULT_PALETTE;
+
+    report.render(render, &mut buf, &alloc, &palette);
+
+    buf
+}
+
+struct Renamer<'b, 'a> {
+    cst: &'b Cst<'a>,
+    old_name: &'b str,
+    new_name: &'b str,
+    /// By token, so a name that is found twice is only changed once
+    edits: BTreeMap<TokenId, String>,
+}
+
+impl<'b, 'a> Renamer<'b, 'a> {
+    /// Rename the first token in the region that has the old name, after skipping `skip`
+    /// bytes of the region
+    fn rename_in(&mut self, region: Region, skip: usize) {
+        let new_name = self.new_name.to_string();
+
+        self.replace_in(region_after(region, skip), new_name)
+    }
+
+    fn replace_in(&mut self, region: Region, text: String) {
+        let start = region.start().offset as usize;
+        let end = region.end().offset as usize;
+
+        let token = self.cst.tokens_in(start..end).find(|(id, token)| {
+            matches!(
+                token.kind,
+                TokenKind::LowercaseIdent | TokenKind::UppercaseIdent
+            ) && self.cst.token_text(*id) == self.old_name
+        });
+
+        if let Some((id, _)) = token {
+            self.edits.insert(id, text);
+        }
+    }
+
+    /// `@Age` wraps and unwraps the opaque type `Age`; this can only be done in the module that
+    /// defines it.
+    fn rename_opaque_refs(&mut self) {
+        let mut after_at = false;
+
+        for (id, token) in self.cst.tokens() {
+            if after_at
+                && token.kind == TokenKind::UppercaseIdent
+                && self.cst.token_text(id) == self.old_name
+            {
+                self.edits.insert(id, self.new_name.to_string());
+            }
+
+            after_at = token.kind == TokenKind::Operator && self.cst.token_text(id) == "@";
+        }
+    }
+
+    fn finish(self, path: &Path) -> ChangedModule {
+        let mut rewriter = self.cst.rewriter();
+        let mut edits = Vec::with_capacity(self.edits.len());
+
+        for (id, text) in self.edits {
+            let range = self.cst.token(id).range();
+
+            edits.push(Edit {
+                region: Region::new(
+                    Position::new(range.start as u32),
+                    Position::new(range.end as u32),
+                ),
+                text: text.clone(),
+            });
+            rewriter.replace_token(id, text);
+        }
+
+        ChangedModule {
+            path: path.to_path_buf(),
+            edits,
+            source: rewriter.finish(),
+        }
+    }
+}
+
+fn region_after(region: Region, skip: usize) -> Region {
+    let start = (region.start().offset + skip as u32).min(region.end().offset);
+
+    Region::new(Position::new(start), region.end())
+}
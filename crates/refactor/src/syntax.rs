@@ -0,0 +1,445 @@
+//! The names in the source of a module that canonicalization does not keep a region for: the
+//! header, types, annotations, and the implementations an opaque type gives for an ability.
+use bumpalo::Bump;
+use roc_parse::ast::{
+    AssignedField, Defs, Expr, ExtractSpaces, HasAbilities, HasAbility, Module, Pattern, Spaced,
+    Tag, TypeAnnotation, TypeDef, TypeHeader, ValueDef,
+};
+use roc_parse::header::{ExposedName, ImportsEntry};
+use roc_parse::module::{parse_header, parse_module_defs};
+use roc_parse::state::State;
+use roc_problem::can::ShadowKind;
+use roc_region::all::{Loc, Region};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Site<'a> {
+    /// In the `exposes` or `provides` list of the header
+    Exposed { name: &'a str, region: Region },
+    /// In the names an entry of `imports` brings into scope, e.g. `bar` in `Foo.{ bar }`
+    Imported {
+        module: &'a str,
+        name: &'a str,
+        region: Region,
+    },
+    /// A type in an annotation; `module` is empty unless it is qualified, like `Dict.Dict`
+    Type {
+        module: &'a str,
+        name: &'a str,
+        region: Region,
+    },
+    /// The name of an alias, opaque type or ability where it is defined
+    TypeDef {
+        name: &'a str,
+        region: Region,
+        kind: ShadowKind,
+        top_level: bool,
+    },
+    /// The name of an ability member where it is defined, e.g. `hash` in `Hash has hash : ...`
+    AbilityMember {
+        ability: &'a str,
+        name: &'a str,
+        region: Region,
+    },
+    /// The name in an annotation like `foo : Str`. `body` is the region of the pattern of the
+    /// definition it annotates, if it comes with one.
+    Annotation {
+        name: &'a str,
+        region: Region,
+        body: Option<Region>,
+    },
+    /// An implementation an opaque type gives for an ability member, e.g. `eq: myEq` in
+    /// `has [Eq { eq: myEq }]`. `value` is `None` for the shorthand `Eq { eq }`.
+    Impl {
+        ability_module: &'a str,
+        ability: &'a str,
+        label: Loc<&'a str>,
+        value: Option<Loc<&'a str>>,
+        region: Region,
+    },
+}
+
+/// Parse a module, and collect its sites. Parts that do not parse have none.
+pub fn module_sites<'a>(arena: &'a Bump, src: &'a str) -> Vec<Site<'a>> {
+    let mut sites = Vec::new();
+
+    if let Ok((module, state)) = parse_header(arena, State::new(src.as_bytes())) {
+        header_sites(&module, &mut sites);
+
+        let (defs, _) = parse_module_defs(arena, state);
+        // the defs borrow from the arena, but `Defs` itself does not
+        let defs = arena.alloc(defs);
+
+        defs_sites(defs, true, &mut sites);
+    }
+
+    sites
+}
+
+fn header_sites<'a>(module: &Module<'a>, sites: &mut Vec<Site<'a>>) {
+    match module {
+        Module::Interface { header } => {
+            exposed_sites(header.exposes.items, sites);
+            imports_sites(header.imports.items, sites);
+        }
+        Module::Hosted { header } => {
+            exposed_sites(header.exposes.items, sites);
+            imports_sites(header.imports.items, sites);
+        }
+        Module::App { header } => {
+            exposed_sites(header.provides.items, sites);
+            imports_sites(header.imports.items, sites);
+
+            if let Some(provides_types) = &header.provides_types {
+                for entry in provides_types.items {
+                    sites.push(Site::Exposed {
+                        name: (*unspaced(&entry.value)).into(),
+                        region: entry.region,
+                    });
+                }
+            }
+        }
+        Module::Platform { header } => {
+            exposed_sites(header.provides.items, sites);
+            imports_sites(header.imports.items, sites);
+        }
+    }
+}
+
+fn exposed_sites<'a>(exposes: &[Loc<Spaced<'a, ExposedName<'a>>>], sites: &mut Vec<Site<'a>>) {
+    for entry in exposes {
+        sites.push(Site::Exposed {
+            name: (*unspaced(&entry.value)).into(),
+            region: entry.region,
+        });
+    }
+}
+
+fn imports_sites<'a>(entries: &'a [Loc<Spaced<'a, ImportsEntry<'a>>>], sites: &mut Vec<Site<'a>>) {
+    for entry in entries {
+        let (module, exposes) = match unspaced(&entry.value) {
+            ImportsEntry::Module(module, exposes) => (module, exposes),
+            ImportsEntry::Package(_, module, exposes) => (module, exposes),
+        };
+
+        for exposed in exposes.items {
+            sites.push(Site::Imported {
+                module: (*module).into(),
+                name: (*unspaced(&exposed.value)).into(),
+                region: exposed.region,
+            });
+        }
+    }
+}
+
+fn unspaced<'a, 'b, T>(spaced: &'b Spaced<'a, T>) -> &'b T {
+    let mut spaced = spaced;
+
+    loop {
+        match spaced {
+            Spaced::Item(item) => return item,
+            Spaced::SpaceBefore(inner, _) | Spaced::SpaceAfter(inner, _) => spaced = inner,
+        }
+    }
+}
+
+fn defs_sites<'a>(defs: &'a Defs<'a>, top_level: bool, sites: &mut Vec<Site<'a>>) {
+    for def in defs.defs() {
+        match def {
+            Ok(type_def) => type_def_sites(type_def, top_level, sites),
+            Err(value_def) => value_def_sites(value_def, sites),
+        }
+    }
+}
+
+fn type_def_sites<'a>(type_def: &'a TypeDef<'a>, top_level: bool, sites: &mut Vec<Site<'a>>) {
+    let push_header = |header: &TypeHeader<'a>, kind, sites: &mut Vec<Site<'a>>| {
+        sites.push(Site::TypeDef {
+            name: header.name.value,
+            region: header.name.region,
+            kind,
+            top_level,
+        })
+    };
+
+    match type_def {
+        TypeDef::Alias { header, ann } => {
+            push_header(header, ShadowKind::Alias, sites);
+            type_sites(ann, sites);
+        }
+        TypeDef::Opaque {
+            header,
+            typ,
+            derived,
+        } => {
+            push_header(header, ShadowKind::Opaque, sites);
+            type_sites(typ, sites);
+
+            if let Some(derived) = derived {
+                has_abilities_sites(&derived.value, sites);
+            }
+        }
+        TypeDef::Ability {
+            header, members, ..
+        } => {
+            push_header(header, ShadowKind::Ability, sites);
+
+            for member in members.iter() {
+                sites.push(Site::AbilityMember {
+                    ability: header.name.value,
+                    name: unspaced::<&str>(&member.name.value),
+                    region: member.name.region,
+                });
+                type_sites(&member.typ, sites);
+            }
+        }
+    }
+}
+
+fn has_abilities_sites<'a>(has_abilities: &'a HasAbilities<'a>, sites: &mut Vec<Site<'a>>) {
+    for has_ability in has_abilities.collection().items {
+        let (ability, impls) = match has_ability.value.extract_spaces().item {
+            HasAbility::HasAbility { ability, impls } => (ability, impls),
+            _ => continue,
+        };
+
+        type_sites(&ability, sites);
+
+        let (ability_module, ability) = match ability.value.extract_spaces().item {
+            TypeAnnotation::Apply(module, name, []) => (module, name),
+            _ => continue,
+        };
+
+        let impls = match impls {
+            Some(impls) => impls.value.extract_spaces().item,
+            None => continue,
+        };
+
+        for loc_impl in impls.items {
+            let (label, value) = match loc_impl.value.extract_spaces().item {
+                AssignedField::LabelOnly(label) => (label, None),
+                AssignedField::RequiredValue(label, _, value) => {
+                    match value.value.extract_spaces().item {
+                        Expr::Var {
+                            module_name: "",
+                            ident,
+                        } => (label, Some(Loc::at(value.region, ident))),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            sites.push(Site::Impl {
+                ability_module,
+                ability,
+                label,
+                value,
+                region: loc_impl.region,
+            });
+        }
+    }
+}
+
+fn value_def_sites<'a>(value_def: &'a ValueDef<'a>, sites: &mut Vec<Site<'a>>) {
+    match value_def {
+        ValueDef::Annotation(pattern, ann) => {
+            annotation_sites(pattern, None, sites);
+            type_sites(ann, sites);
+        }
+        ValueDef::Body(_, body) => expr_sites(body, sites),
+        ValueDef::AnnotatedBody {
+            ann_pattern,
+            ann_type,
+            body_pattern,
+            body_expr,
+            ..
+        } => {
+            annotation_sites(ann_pattern, Some(body_pattern.region), sites);
+            type_sites(ann_type, sites);
+            expr_sites(body_expr, sites);
+        }
+        ValueDef::Expect { condition, .. } | ValueDef::ExpectFx { condition, .. } => {
+            expr_sites(condition, sites)
+        }
+    }
+}
+
+fn annotation_sites<'a>(
+    pattern: &Loc<Pattern<'a>>,
+    body: Option<Region>,
+    sites: &mut Vec<Site<'a>>,
+) {
+    if let Pattern::Identifier(name) = pattern.value.extract_spaces().item {
+        sites.push(Site::Annotation {
+            name,
+            region: pattern.region,
+            body,
+        });
+    }
+}
+
+fn type_sites<'a>(ann: &Loc<TypeAnnotation<'a>>, sites: &mut Vec<Site<'a>>) {
+    match ann.value.extract_spaces().item {
+        TypeAnnotation::Function(args, ret) => {
+            for arg in args.iter() {
+                type_sites(arg, sites);
+            }
+
+            type_sites(ret, sites);
+        }
+        TypeAnnotation::Apply(module, name, args) => {
+            sites.push(Site::Type {
+                module,
+                name,
+                region: ann.region,
+            });
+
+            for arg in args.iter() {
+                type_sites(arg, sites);
+            }
+        }
+        TypeAnnotation::As(inner, _, _) => type_sites(inner, sites),
+        TypeAnnotation::Record { fields, ext } => {
+            for field in fields.items {
+                match field.value.extract_spaces().item {
+                    AssignedField::RequiredValue(_, _, value)
+                    | AssignedField::OptionalValue(_, _, value) => type_sites(value, sites),
+                    _ => {}
+                }
+            }
+
+            if let Some(ext) = ext {
+                type_sites(ext, sites);
+            }
+        }
+        TypeAnnotation::TagUnion { ext, tags } => {
+            for tag in tags.items {
+                if let Tag::Apply { args, .. } = tag.value.extract_spaces().item {
+                    for arg in args.iter() {
+                        type_sites(arg, sites);
+                    }
+                }
+            }
+
+            if let Some(ext) = ext {
+                type_sites(ext, sites);
+            }
+        }
+        TypeAnnotation::Where(inner, clauses) => {
+            type_sites(inner, sites);
+
+            for clause in clauses.iter() {
+                type_sites(&clause.value.ability, sites);
+            }
+        }
+        TypeAnnotation::BoundVariable(_)
+        | TypeAnnotation::Inferred
+        | TypeAnnotation::Wildcard
+        | TypeAnnotation::Malformed(_)
+        | TypeAnnotation::SpaceBefore(..)
+        | TypeAnnotation::SpaceAfter(..) => {}
+    }
+}
+
+/// Only nested defs can have sites, so this just looks for those
+fn expr_sites<'a>(loc_expr: &'a Loc<Expr<'a>>, sites: &mut Vec<Site<'a>>) {
+    sub_expr_sites(&loc_expr.value, sites)
+}
+
+fn sub_expr_sites<'a>(expr: &'a Expr<'a>, sites: &mut Vec<Site<'a>>) {
+    fn field_sites<'a>(field: &'a AssignedField<'a, Expr<'a>>, sites: &mut Vec<Site<'a>>) {
+        match field {
+            AssignedField::RequiredValue(_, _, value)
+            | AssignedField::OptionalValue(_, _, value) => expr_sites(value, sites),
+            AssignedField::SpaceBefore(field, _) | AssignedField::SpaceAfter(field, _) => {
+                field_sites(field, sites)
+            }
+            AssignedField::LabelOnly(_) | AssignedField::Malformed(_) => {}
+        }
+    }
+
+    match expr {
+        Expr::Access(inner, _)
+        | Expr::SpaceBefore(inner, _)
+        | Expr::SpaceAfter(inner, _)
+        | Expr::ParensAround(inner) => sub_expr_sites(inner, sites),
+        Expr::UnaryOp(inner, _) => expr_sites(inner, sites),
+        Expr::List(items) => {
+            for item in items.iter() {
+                expr_sites(item, sites);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields.iter() {
+                field_sites(&field.value, sites);
+            }
+        }
+        Expr::RecordUpdate { update, fields } => {
+            expr_sites(update, sites);
+
+            for field in fields.iter() {
+                field_sites(&field.value, sites);
+            }
+        }
+        Expr::Closure(_, body) => expr_sites(body, sites),
+        Expr::Defs(defs, final_expr) => {
+            defs_sites(defs, false, sites);
+            expr_sites(final_expr, sites);
+        }
+        Expr::Backpassing(_, first, second) => {
+            expr_sites(first, sites);
+            expr_sites(second, sites);
+        }
+        Expr::Expect(condition, continuation) => {
+            expr_sites(condition, sites);
+            expr_sites(continuation, sites);
+        }
+        Expr::Apply(function, args, _) => {
+            expr_sites(function, sites);
+
+            for arg in args.iter() {
+                expr_sites(arg, sites);
+            }
+        }
+        Expr::BinOps(pairs, last) => {
+            for (operand, _) in pairs.iter() {
+                expr_sites(operand, sites);
+            }
+
+            expr_sites(last, sites);
+        }
+        Expr::If(branches, final_else) => {
+            for (condition, then) in branches.iter() {
+                expr_sites(condition, sites);
+                expr_sites(then, sites);
+            }
+
+            expr_sites(final_else, sites);
+        }
+        Expr::When(condition, branches) => {
+            expr_sites(condition, sites);
+
+            for branch in branches.iter() {
+                if let Some(guard) = &branch.guard {
+                    expr_sites(guard, sites);
+                }
+
+                expr_sites(&branch.value, sites);
+            }
+        }
+        Expr::PrecedenceConflict(conflict) => sub_expr_sites(&conflict.expr.value, sites),
+        Expr::Float(_)
+        | Expr::Num(_)
+        | Expr::NonBase10Int { .. }
+        | Expr::Str(_)
+        | Expr::AccessorFunction(_)
+        | Expr::SingleQuote(_)
+        | Expr::Var { .. }
+        | Expr::Underscore(_)
+        | Expr::Tag(_)
+        | Expr::OpaqueRef(_)
+        | Expr::MalformedIdent(_, _)
+        | Expr::MalformedClosure
+        | Expr::Malformed(_, _) => {}
+    }
+}
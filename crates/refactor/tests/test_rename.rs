@@ -0,0 +1,434 @@
+#[macro_use]
+extern crate pretty_assertions;
+#[macro_use]
+extern crate indoc;
+
+#[cfg(test)]
+mod test_rename {
+    use bumpalo::Bump;
+    use roc_load::Threading;
+    use roc_refactor::{rename_in_package, ChangedModule, RenameError};
+    use roc_reporting::report::RenderTarget;
+    use std::path::PathBuf;
+
+    /// Write the modules to a fresh directory, and rename in the package of the first one.
+    fn rename_in_modules(
+        dir_name: &str,
+        modules: &[(&str, &str)],
+        target: &str,
+        new_name: &str,
+    ) -> Result<Vec<(String, String)>, Vec<String>> {
+        let dir = std::env::temp_dir().join(dir_name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (name, src) in modules {
+            std::fs::write(dir.join(format!("{}.roc", name)), src).unwrap();
+        }
+
+        let arena = Bump::new();
+        let root: PathBuf = dir.join(format!("{}.roc", modules[0].0));
+
+        match rename_in_package(
+            &arena,
+            root,
+            target,
+            new_name,
+            RenderTarget::Generic,
+            Threading::Single,
+        ) {
+            Ok(changed) => Ok(changed
+                .into_iter()
+                .map(|ChangedModule { path, source, .. }| {
+                    let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+
+                    (name, source)
+                })
+                .collect()),
+            Err(RenameError::Shadowing(reports)) => Err(reports),
+            Err(other) => panic!("renaming failed: {:?}", other),
+        }
+    }
+
+    fn changed(modules: &[(&str, &str)]) -> Result<Vec<(String, String)>, Vec<String>> {
+        Ok(modules
+            .iter()
+            .map(|(name, src)| (name.to_string(), src.to_string()))
+            .collect())
+    }
+
+    #[test]
+    fn value_across_modules() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports [Dep.{ double }, Other]
+
+            main =
+                # both are the same function
+                double 1 + Dep.double 2 + Other.double 3
+            "#
+        );
+        let dep = indoc!(
+            r#"
+            interface Dep
+                exposes [double, quadruple]
+                imports []
+
+            ## Twice the number
+            double : I64 -> I64
+            double = \n -> n * 2
+
+            quadruple = \n -> double (double n)
+            "#
+        );
+        let other = indoc!(
+            r#"
+            interface Other
+                exposes [double]
+                imports []
+
+            double = \n -> n + n
+            "#
+        );
+
+        assert_eq!(
+            rename_in_modules(
+                "roc-refactor-value",
+                &[("Main", main), ("Dep", dep), ("Other", other)],
+                "Dep.double",
+                "twice",
+            ),
+            changed(&[
+                (
+                    "Dep",
+                    indoc!(
+                        r#"
+                        interface Dep
+                            exposes [twice, quadruple]
+                            imports []
+
+                        ## Twice the number
+                        twice : I64 -> I64
+                        twice = \n -> n * 2
+
+                        quadruple = \n -> twice (twice n)
+                        "#
+                    )
+                ),
+                (
+                    "Main",
+                    indoc!(
+                        r#"
+                        interface Main
+                            exposes [main]
+                            imports [Dep.{ twice }, Other]
+
+                        main =
+                            # both are the same function
+                            twice 1 + Dep.twice 2 + Other.double 3
+                        "#
+                    )
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn opaque_type() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main, birthday]
+                imports [Age.{ Age }]
+
+            birthday : Age -> Age.Age
+            birthday = \age -> Age.add age 1
+
+            main = birthday (Age.fromYears 41)
+            "#
+        );
+        let age = indoc!(
+            r#"
+            interface Age
+                exposes [Age, add, fromYears]
+                imports []
+
+            Age := U32
+
+            fromYears : U32 -> Age
+            fromYears = \years -> @Age years
+
+            add : Age, U32 -> Age
+            add = \@Age years, more -> @Age (years + more)
+            "#
+        );
+
+        assert_eq!(
+            rename_in_modules(
+                "roc-refactor-opaque",
+                &[("Main", main), ("Age", age)],
+                "Age.Age",
+                "Years",
+            ),
+            changed(&[
+                (
+                    "Age",
+                    indoc!(
+                        r#"
+                        interface Age
+                            exposes [Years, add, fromYears]
+                            imports []
+
+                        Years := U32
+
+                        fromYears : U32 -> Years
+                        fromYears = \years -> @Years years
+
+                        add : Years, U32 -> Years
+                        add = \@Years years, more -> @Years (years + more)
+                        "#
+                    )
+                ),
+                (
+                    "Main",
+                    indoc!(
+                        r#"
+                        interface Main
+                            exposes [main, birthday]
+                            imports [Age.{ Years }]
+
+                        birthday : Years -> Age.Years
+                        birthday = \age -> Age.add age 1
+
+                        main = birthday (Age.fromYears 41)
+                        "#
+                    )
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn ability_member_and_implementations() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports [Describe.{ Describe, describe }]
+
+            Cat := {} has [Describe { describe: describeCat }]
+
+            describeCat = \@Cat {} -> "a cat"
+
+            Dog := {} has [Describe { describe }]
+
+            describe = \@Dog {} -> "a dog"
+
+            main = Describe.describe (@Cat {})
+            "#
+        );
+        let describe = indoc!(
+            r#"
+            interface Describe
+                exposes [Describe, describe, twice]
+                imports []
+
+            Describe has
+                describe : a -> Str | a has Describe
+
+            twice = \x -> Str.concat (describe x) (describe x)
+            "#
+        );
+
+        assert_eq!(
+            rename_in_modules(
+                "roc-refactor-ability",
+                &[("Main", main), ("Describe", describe)],
+                "Describe.describe",
+                "show",
+            ),
+            changed(&[
+                (
+                    "Describe",
+                    indoc!(
+                        r#"
+                        interface Describe
+                            exposes [Describe, show, twice]
+                            imports []
+
+                        Describe has
+                            show : a -> Str | a has Describe
+
+                        twice = \x -> Str.concat (show x) (show x)
+                        "#
+                    )
+                ),
+                (
+                    "Main",
+                    indoc!(
+                        r#"
+                        interface Main
+                            exposes [main]
+                            imports [Describe.{ Describe, show }]
+
+                        Cat := {} has [Describe { show: describeCat }]
+
+                        describeCat = \@Cat {} -> "a cat"
+
+                        Dog := {} has [Describe { show: describe }]
+
+                        describe = \@Dog {} -> "a dog"
+
+                        main = Describe.show (@Cat {})
+                        "#
+                    )
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn implementation_of_a_member() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports []
+
+            Describe has
+                describe : a -> Str | a has Describe
+
+            Cat := {} has [Describe { describe: describeCat }]
+
+            describeCat = \@Cat {} -> "a cat"
+
+            main = describe (@Cat {})
+            "#
+        );
+
+        assert_eq!(
+            rename_in_modules(
+                "roc-refactor-impl",
+                &[("Main", main)],
+                "describeCat",
+                "meow",
+            ),
+            changed(&[(
+                "Main",
+                indoc!(
+                    r#"
+                    interface Main
+                        exposes [main]
+                        imports []
+
+                    Describe has
+                        describe : a -> Str | a has Describe
+
+                    Cat := {} has [Describe { describe: meow }]
+
+                    meow = \@Cat {} -> "a cat"
+
+                    main = describe (@Cat {})
+                    "#
+                )
+            )])
+        );
+    }
+
+    #[test]
+    fn shadowing_is_refused() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports [Dep.{ double }]
+
+            main =
+                twice = 2
+
+                double twice
+            "#
+        );
+        let dep = indoc!(
+            r#"
+            interface Dep
+                exposes [double]
+                imports []
+
+            double = \n -> n * 2
+            "#
+        );
+
+        let reports = rename_in_modules(
+            "roc-refactor-shadowing",
+            &[("Main", main), ("Dep", dep)],
+            "Dep.double",
+            "twice",
+        )
+        .unwrap_err();
+
+        assert_eq!(reports.len(), 1);
+
+        // the header holds the (temporary) path of the module
+        let (header, body) = reports[0].split_once('\n').unwrap();
+
+        assert!(header.starts_with("── DUPLICATE NAME ─"), "{}", header);
+        assert!(header.ends_with("Main.roc ─"), "{}", header);
+        assert_eq!(
+            body,
+            indoc!(
+                r#"
+
+                The `twice` name is first defined here:
+
+                6│      twice = 2
+                        ^^^^^
+
+                But then it's defined a second time here:
+
+                3│      imports [Dep.{ double }]
+                                       ^^^^^^
+
+                Since these variables have the same name, it's easy to use the wrong
+                one on accident. Give one of them a new name."#
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_names_are_refused() {
+        let main = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports []
+
+            main = 1
+            "#
+        );
+
+        for new_name in ["Main", "if", "snake_case", "Str"] {
+            let dir = std::env::temp_dir().join("roc-refactor-invalid");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("Main.roc"), main).unwrap();
+
+            let arena = Bump::new();
+            let result = rename_in_package(
+                &arena,
+                dir.join("Main.roc"),
+                "main",
+                new_name,
+                RenderTarget::Generic,
+                Threading::Single,
+            );
+
+            assert!(
+                matches!(result, Err(RenameError::InvalidName(_))),
+                "{} was not refused",
+                new_name
+            );
+        }
+    }
+}
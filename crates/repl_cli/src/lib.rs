@@ -15,11 +15,12 @@ use roc_build::link::llvm_module_to_dylib;
 use roc_collections::all::MutSet;
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_gen_llvm::{run_jit_function, run_jit_function_dynamic_type};
+use roc_interpret::Interpreter;
 use roc_load::{EntryPoint, MonomorphizedModule};
 use roc_mono::ir::OptLevel;
 use roc_parse::ast::Expr;
 use roc_parse::parser::{EExpr, ELambda, SyntaxError};
-use roc_repl_eval::eval::jit_to_ast;
+use roc_repl_eval::eval::{jit_to_ast, ToAstProblem};
 use roc_repl_eval::gen::{compile_to_mono, format_answer, ReplOutput};
 use roc_repl_eval::{ReplApp, ReplAppMemory};
 use roc_reporting::report::DEFAULT_PALETTE;
@@ -46,6 +47,15 @@ pub const INSTRUCTIONS: &str = "Enter an expression, or :help, or :exit/:q.\n";
 pub const PROMPT: &str = concatcp!("\n", BLUE, "»", END_COL, " ");
 pub const CONT_PROMPT: &str = concatcp!(BLUE, "…", END_COL, " ");
 
+/// How the REPL runs the expressions it evaluates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplBackend {
+    /// Compile every expression to a dylib with LLVM, and load it
+    Llvm,
+    /// Interpret the monomorphized IR, which needs neither LLVM nor a linker
+    Interpreter,
+}
+
 #[derive(Completer, Helper, Hinter)]
 struct ReplHelper {
     validator: InputValidator,
@@ -201,6 +211,38 @@ impl ReplAppMemory for CliMemory {
     }
 }
 
+/// The result of an interpreted expression, which lives in the memory of the interpreter
+struct InterpretedApp {
+    result: *const u8,
+}
+
+impl<'a> ReplApp<'a> for InterpretedApp {
+    type Memory = CliMemory;
+
+    fn call_function<Return, F>(&mut self, _main_fn_name: &str, transform: F) -> Expr<'a>
+    where
+        F: Fn(&'a Self::Memory, Return) -> Expr<'a>,
+        Self::Memory: 'a,
+    {
+        let value = unsafe { std::ptr::read_unaligned(self.result as *const Return) };
+
+        transform(&CliMemory, value)
+    }
+
+    fn call_function_dynamic_size<T, F>(
+        &mut self,
+        _main_fn_name: &str,
+        _ret_bytes: usize,
+        transform: F,
+    ) -> T
+    where
+        F: Fn(&'a Self::Memory, usize) -> T,
+        Self::Memory: 'a,
+    {
+        transform(&CliMemory, self.result as usize)
+    }
+}
+
 pub fn mono_module_to_dylib<'a>(
     arena: &'a Bump,
     target: Triple,
@@ -242,6 +284,9 @@ pub fn mono_module_to_dylib<'a>(
         mode: LlvmBackendMode::GenTest, // so roc_panic is generated
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: None,
+        debug_refcounts: false,
+        profile_allocations: false,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -356,7 +401,82 @@ fn gen_and_eval_llvm<'a>(
     Ok(formatted)
 }
 
-fn eval_and_format<'a>(src: &str) -> Result<String, SyntaxError<'a>> {
+fn gen_and_eval_interpreted<'a>(src: &str) -> Result<ReplOutput, SyntaxError<'a>> {
+    let arena = Bump::new();
+    let target_info = TargetInfo::default_x86_64();
+
+    let mut loaded = match compile_to_mono(&arena, src, target_info, DEFAULT_PALETTE) {
+        Ok(x) => x,
+        Err(prob_strings) => {
+            return Ok(ReplOutput::Problems(prob_strings));
+        }
+    };
+
+    debug_assert_eq!(loaded.exposed_to_host.values.len(), 1);
+    let (main_fn_symbol, main_fn_var) = loaded.exposed_to_host.values.iter().next().unwrap();
+    let main_fn_symbol = *main_fn_symbol;
+    let main_fn_var = *main_fn_var;
+
+    // pretty-print the expr type string for later.
+    let expr_type_str = name_and_print_var(
+        main_fn_var,
+        &mut loaded.subs,
+        loaded.module_id,
+        &loaded.interns,
+        DebugPrint::NOTHING,
+    );
+    let content = *loaded.subs.get_content_without_compacting(main_fn_var);
+
+    let (_, main_fn_layout) = match loaded.procedures.keys().find(|(s, _)| *s == main_fn_symbol) {
+        Some(layout) => *layout,
+        None => {
+            return Ok(ReplOutput::NoProblems {
+                expr: "<function>".to_string(),
+                expr_type: expr_type_str,
+            });
+        }
+    };
+
+    if !main_fn_layout.arguments.is_empty() {
+        // `jit_to_ast` does not call functions, it prints them as `<function>`
+        return Ok(format_answer(
+            &arena,
+            Err(ToAstProblem::FunctionLayout),
+            expr_type_str,
+        ));
+    }
+
+    let procedures = std::mem::take(&mut loaded.procedures);
+    let mut interpreter = Interpreter::new(&arena, procedures);
+
+    let result = match interpreter.run_thunk(main_fn_symbol, main_fn_layout) {
+        Ok(result) => result,
+        Err(error) => {
+            return Ok(ReplOutput::Problems(vec![format!(
+                "The expression crashed: {}",
+                error
+            )]));
+        }
+    };
+
+    let mut app = InterpretedApp { result };
+
+    let res_answer = jit_to_ast(
+        &arena,
+        &mut app,
+        "main",
+        main_fn_layout,
+        &content,
+        &loaded.subs,
+        &loaded.interns,
+        target_info,
+    );
+
+    let formatted = format_answer(&arena, res_answer, expr_type_str);
+    Ok(formatted)
+}
+
+fn eval_and_format<'a>(src: &str, backend: ReplBackend) -> Result<String, SyntaxError<'a>> {
     let format_output = |output| match output {
         ReplOutput::NoProblems { expr, expr_type } => {
             format!("\n{} {}:{} {}", expr, PINK, END_COL, expr_type)
@@ -364,14 +484,18 @@ fn eval_and_format<'a>(src: &str) -> Result<String, SyntaxError<'a>> {
         ReplOutput::Problems(lines) => format!("\n{}\n", lines.join("\n\n")),
     };
 
-    gen_and_eval_llvm(src, Triple::host(), OptLevel::Normal).map(format_output)
+    match backend {
+        ReplBackend::Llvm => gen_and_eval_llvm(src, Triple::host(), OptLevel::Normal),
+        ReplBackend::Interpreter => gen_and_eval_interpreted(src),
+    }
+    .map(format_output)
 }
 
 fn report_parse_error(fail: SyntaxError) {
     println!("TODO Gracefully report parse error in repl: {:?}", fail);
 }
 
-pub fn main() -> io::Result<()> {
+pub fn main(backend: ReplBackend) -> io::Result<()> {
     use rustyline::error::ReadlineError;
     use rustyline::Editor;
 
@@ -405,7 +529,7 @@ pub fn main() -> io::Result<()> {
                         } else if prev_line_blank {
                             // After two blank lines in a row, give up and try parsing it
                             // even though it's going to fail. This way you don't get stuck.
-                            match eval_and_format(pending_src.as_str()) {
+                            match eval_and_format(pending_src.as_str(), backend) {
                                 Ok(output) => {
                                     println!("{}", output);
                                 }
@@ -433,12 +557,12 @@ pub fn main() -> io::Result<()> {
                     }
                     _ => {
                         let result = if pending_src.is_empty() {
-                            eval_and_format(trim_line)
+                            eval_and_format(trim_line, backend)
                         } else {
                             pending_src.push('\n');
                             pending_src.push_str(trim_line);
 
-                            eval_and_format(pending_src.as_str())
+                            eval_and_format(pending_src.as_str(), backend)
                         };
 
                         match result {
@@ -65,6 +65,9 @@ pub fn compile_to_mono<'a>(
             render: roc_reporting::report::RenderTarget::ColorTerminal,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Executable,
+            inline_and_fold: false,
+            record_call_regions: false,
+            collect_package_modules: false,
         },
     );
 
@@ -84,11 +84,38 @@ mod test {
     use roc_reporting::report::RenderTarget;
     use target_lexicon::Triple;
 
-    use crate::run::expect_mono_module_to_dylib;
+    use crate::run::{expect_mono_module_to_dev_dylib, expect_mono_module_to_dylib, ExpectBackend};
+    use bumpalo::Bump;
+    use roc_mono::ir::{ModifyRc, Stmt};
 
     use super::*;
 
     fn run_expect_test(source: &str, expected: &str) {
+        run_expect_test_with_backend(source, expected, ExpectBackend::Llvm)
+    }
+
+    fn run_dev_expect_test(source: &str, expected: &str) {
+        run_expect_test_with_backend(source, expected, ExpectBackend::Dev)
+    }
+
+    fn run_expect_test_with_backend(source: &str, expected: &str, backend: ExpectBackend) {
+        let actual = run_expects_in(source, backend, false, unchanged);
+
+        if actual != expected {
+            println!("{}", actual);
+        }
+
+        assert_eq!(actual, expected);
+    }
+
+    /// Compile and run the expects in `source`, after applying `tamper` to the body of every
+    /// procedure in the app module, and return what they report.
+    fn run_expects_in(
+        source: &str,
+        backend: ExpectBackend,
+        debug_refcounts: bool,
+        tamper: for<'a> fn(&'a Bump, &Stmt<'a>) -> Stmt<'a>,
+    ) -> String {
         let arena = bumpalo::Bump::new();
         let arena = &arena;
 
@@ -109,6 +136,9 @@ mod test {
             render: RenderTarget::ColorTerminal,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Test,
+            inline_and_fold: false,
+            record_call_regions: false,
+            collect_package_modules: false,
         };
         let loaded = roc_load::load_and_monomorphize_from_str(
             arena,
@@ -122,17 +152,29 @@ mod test {
 
         let mut loaded = loaded;
         let mut expectations = std::mem::take(&mut loaded.expectations);
+
+        let home = loaded.module_id;
+        for ((symbol, _), proc) in loaded.procedures.iter_mut() {
+            if symbol.module_id() == home {
+                proc.body = tamper(arena, &proc.body);
+            }
+        }
+
         let loaded = loaded;
 
         let interns = loaded.interns.clone();
 
-        let (lib, expects) = expect_mono_module_to_dylib(
-            arena,
-            target.clone(),
-            loaded,
-            opt_level,
-            LlvmBackendMode::CliTest,
-        )
+        let (lib, expects) = match backend {
+            ExpectBackend::Llvm => expect_mono_module_to_dylib(
+                arena,
+                target.clone(),
+                loaded,
+                opt_level,
+                LlvmBackendMode::CliTest,
+                debug_refcounts,
+            ),
+            ExpectBackend::Dev => expect_mono_module_to_dev_dylib(arena, target.clone(), loaded),
+        }
         .unwrap();
 
         let arena = &bumpalo::Bump::new();
@@ -154,6 +196,7 @@ mod test {
             arena,
             interns,
             &lib,
+            backend,
             &mut expectations,
             shared_buffer.as_mut_ptr(),
             expects,
@@ -172,18 +215,90 @@ mod test {
             // changes between test runs
             let p = actual.bytes().position(|c| c == b'\n').unwrap();
             let (_, x) = actual.split_at(p);
-            let x = x.trim_start();
 
-            if x != expected {
-                println!("{}", x);
-            }
-
-            assert_eq!(x, expected);
+            x.trim_start().to_string()
         } else {
-            assert_eq!(actual, expected);
+            actual
+        }
+    }
+
+    fn unchanged<'a>(_arena: &'a Bump, stmt: &Stmt<'a>) -> Stmt<'a> {
+        stmt.clone()
+    }
+
+    /// Rebuild the statement, replacing every refcount decrement using `f`
+    fn map_decrements<'a>(
+        arena: &'a Bump,
+        stmt: &Stmt<'a>,
+        f: fn(&'a Bump, ModifyRc, Stmt<'a>) -> Stmt<'a>,
+    ) -> Stmt<'a> {
+        use Stmt::*;
+
+        let go = |stmt: &Stmt<'a>| &*arena.alloc(map_decrements(arena, stmt, f));
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => Let(*symbol, expr.clone(), *layout, go(cont)),
+            Refcounting(modify @ ModifyRc::Dec(_), cont) => {
+                f(arena, *modify, map_decrements(arena, cont, f))
+            }
+            Refcounting(modify, cont) => Refcounting(*modify, go(cont)),
+            Expect {
+                condition,
+                region,
+                lookups,
+                layouts,
+                remainder,
+            } => Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                layouts: *layouts,
+                remainder: go(remainder),
+            },
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => Switch {
+                cond_symbol: *cond_symbol,
+                cond_layout: *cond_layout,
+                branches: arena.alloc_slice_fill_iter(branches.iter().map(|(id, info, branch)| {
+                    (*id, info.clone(), map_decrements(arena, branch, f))
+                })),
+                default_branch: (default_branch.0.clone(), go(default_branch.1)),
+                ret_layout: *ret_layout,
+            },
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => Join {
+                id: *id,
+                parameters: *parameters,
+                body: go(body),
+                remainder: go(remainder),
+            },
+            Ret(_) | Jump(..) | RuntimeError(_) => stmt.clone(),
         }
     }
 
+    /// Forget to free values
+    fn drop_decrements<'a>(arena: &'a Bump, stmt: &Stmt<'a>) -> Stmt<'a> {
+        map_decrements(arena, stmt, |_, _, cont| cont)
+    }
+
+    /// Free values twice
+    fn double_decrements<'a>(arena: &'a Bump, stmt: &Stmt<'a>) -> Stmt<'a> {
+        map_decrements(arena, stmt, |arena, modify, cont| {
+            let second = Stmt::Refcounting(modify, arena.alloc(cont));
+
+            Stmt::Refcounting(modify, arena.alloc(second))
+        })
+    }
+
     #[test]
     fn equals_pass() {
         run_expect_test(
@@ -259,6 +374,111 @@ mod test {
         );
     }
 
+    #[test]
+    fn dev_equals_fail() {
+        run_dev_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect 1 == 2
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│  expect 1 == 2
+                    ^^^^^^^^^^^^^
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn dev_lookup_integer() {
+        run_dev_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    a = 1
+                    b = 2
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      a = 1
+                7│>      b = 2
+                8│>
+                9│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : Num a
+                a = 1
+
+                b : Num a
+                b = 2
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn dev_lookup_string_and_list() {
+        run_dev_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    str : Str
+                    str = "short"
+
+                    list : List U64
+                    list = [1, 2, 3]
+
+                    Str.countUtf8Bytes str == List.len list
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                 5│>  expect
+                 6│>      str : Str
+                 7│>      str = "short"
+                 8│>
+                 9│>      list : List U64
+                10│>      list = [1, 2, 3]
+                11│>
+                12│>      Str.countUtf8Bytes str == List.len list
+
+                When it failed, these variables had these values:
+
+                str : Str
+                str = "short"
+
+                list : List U64
+                list = [1, 2, 3]
+                "#
+            ),
+        );
+    }
+
     #[test]
     fn lookup_list_of_strings() {
         run_expect_test(
@@ -834,4 +1054,74 @@ mod test {
             ),
         );
     }
+
+    const ALLOCATING_EXPECT: &str = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main = 0
+
+        expect
+            list = List.append [1, 2] 3
+
+            List.len list == 3
+        "#
+    );
+
+    #[test]
+    fn debug_refcounts_balanced() {
+        let actual = run_expects_in(ALLOCATING_EXPECT, ExpectBackend::Llvm, true, unchanged);
+
+        assert_eq!(actual, "");
+    }
+
+    #[test]
+    fn debug_refcounts_leak() {
+        let actual = run_expects_in(
+            ALLOCATING_EXPECT,
+            ExpectBackend::Llvm,
+            true,
+            drop_decrements,
+        );
+
+        assert!(
+            actual.starts_with(
+                "This expectation passed, but it did not free everything it allocated:"
+            ),
+            "{}",
+            actual
+        );
+        assert!(actual.contains("5│>  expect"), "{}", actual);
+        assert!(
+            actual.contains("These values were never freed:"),
+            "{}",
+            actual
+        );
+        assert!(actual.contains("    1 value allocated in "), "{}", actual);
+    }
+
+    #[test]
+    fn debug_refcounts_double_decrement() {
+        let actual = run_expects_in(
+            ALLOCATING_EXPECT,
+            ExpectBackend::Llvm,
+            true,
+            double_decrements,
+        );
+
+        assert!(
+            actual.starts_with("This expectation crashed while running:"),
+            "{}",
+            actual
+        );
+        assert!(actual.contains("5│>  expect"), "{}", actual);
+        assert!(
+            actual.contains(
+                "Refcount error: the refcount of a value was decremented after it was freed"
+            ),
+            "{}",
+            actual
+        );
+        assert!(actual.contains("It was allocated in "), "{}", actual);
+    }
 }
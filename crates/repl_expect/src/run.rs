@@ -1,17 +1,34 @@
 use bumpalo::collections::Vec as BumpVec;
 use bumpalo::Bump;
 use inkwell::context::Context;
-use roc_build::link::llvm_module_to_dylib;
+use roc_build::link::{link, llvm_module_to_dylib, LinkType};
+use roc_builtins::bitcode;
 use roc_collections::{MutSet, VecMap};
-use roc_gen_llvm::llvm::{build::LlvmBackendMode, externs::add_default_roc_externs};
+use roc_gen_dev::AssemblyBackendMode;
+use roc_gen_llvm::llvm::{
+    build::LlvmBackendMode, debug_info::DebugSources, externs::add_default_roc_externs,
+};
+use roc_interpret::Interpreter;
 use roc_load::{EntryPoint, Expectations, MonomorphizedModule};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
-use roc_mono::ir::OptLevel;
+use roc_mono::ir::{OptLevel, ProcLayout};
+use roc_mono::layout::{CapturesNiche, Layout, LayoutIds};
 use roc_region::all::Region;
 use roc_reporting::{error::expect::Renderer, report::RenderTarget};
 use roc_target::TargetInfo;
+use std::ffi::CStr;
+use std::os::raw::c_char;
 use target_lexicon::Triple;
 
+/// The backend that compiled the expects, which determines how they are called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectBackend {
+    /// Expects are wrapped to catch panics, see `LlvmBackendMode::CliTest`.
+    Llvm,
+    /// Expects are exposed directly as C functions by the dev backend.
+    Dev,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn run_expects<W: std::io::Write>(
     writer: &mut W,
@@ -19,6 +36,7 @@ pub fn run_expects<W: std::io::Write>(
     arena: &Bump,
     interns: &Interns,
     lib: &libloading::Library,
+    backend: ExpectBackend,
     expectations: &mut VecMap<ModuleId, Expectations>,
     shared_ptr: *mut u8,
     expects: bumpalo::collections::Vec<'_, ToplevelExpect<'_>>,
@@ -33,6 +51,7 @@ pub fn run_expects<W: std::io::Write>(
             arena,
             interns,
             lib,
+            backend,
             expectations,
             shared_ptr,
             expect,
@@ -54,6 +73,7 @@ fn run_expect<W: std::io::Write>(
     arena: &Bump,
     interns: &Interns,
     lib: &libloading::Library,
+    backend: ExpectBackend,
     expectations: &mut VecMap<ModuleId, Expectations>,
     shared_ptr: *mut u8,
     expect: ToplevelExpect<'_>,
@@ -62,11 +82,218 @@ fn run_expect<W: std::io::Write>(
 
     let sequence = ExpectSequence::new(shared_ptr.cast());
 
-    let result: Result<(), String> = try_run_jit_function!(lib, expect.name, (), |v: ()| v);
+    let result: Result<(), String> = match backend {
+        ExpectBackend::Llvm => try_run_jit_function!(lib, expect.name, (), |v: ()| v),
+        ExpectBackend::Dev => unsafe {
+            // the dev backend does not catch panics; `roc_panic` only reports them
+            let expect_fn: libloading::Symbol<unsafe extern "C" fn()> = lib
+                .get(expect.name.as_bytes())
+                .unwrap_or_else(|e| panic!("Unable to find {:?} in the dylib: {}", expect.name, e));
+
+            expect_fn();
+
+            Ok(())
+        },
+    };
+
+    let passed = render_expect_result(
+        writer,
+        render_target,
+        arena,
+        interns,
+        expectations,
+        expect,
+        result,
+        shared_ptr.cast(),
+        sequence.count_failures(),
+    )?;
+
+    // with `--debug-refcounts`, an expect that returns must have freed everything it allocated.
+    // A crash skips the cleanup, so we only check expects that passed.
+    let leaks = match backend {
+        ExpectBackend::Llvm => take_refcount_leaks(lib),
+        ExpectBackend::Dev => Vec::new(),
+    };
+
+    if passed && !leaks.is_empty() {
+        render_leaks(
+            writer,
+            render_target,
+            arena,
+            interns,
+            expectations,
+            expect,
+            &leaks,
+        )?;
+
+        return Ok(false);
+    }
+
+    Ok(passed)
+}
+
+/// A value that was never freed, see `debug_refcounts.zig`
+#[repr(C)]
+struct RefcountLeak {
+    /// only there to match the layout of the zig struct
+    _address: usize,
+    allocated_in: *const c_char,
+}
+
+/// The procedures that allocated values which are still live, and how many values each of them
+/// allocated. Also forgets these values, so the next expect starts with a clean slate.
+///
+/// Refcounts are only tracked in `--debug-refcounts` builds; otherwise there are never any leaks.
+fn take_refcount_leaks(lib: &libloading::Library) -> Vec<(String, usize)> {
+    type LeaksFn = unsafe extern "C" fn(*mut RefcountLeak, usize) -> usize;
+    type ResetFn = unsafe extern "C" fn();
+
+    let (leaks_fn, reset_fn) = unsafe {
+        match (
+            lib.get::<LeaksFn>(bitcode::UTILS_REFCOUNT_LEAKS.as_bytes()),
+            lib.get::<ResetFn>(bitcode::UTILS_REFCOUNT_RESET.as_bytes()),
+        ) {
+            (Ok(leaks_fn), Ok(reset_fn)) => (leaks_fn, reset_fn),
+            _ => return Vec::new(),
+        }
+    };
+
+    let mut leaks: Vec<RefcountLeak> = Vec::new();
+
+    unsafe {
+        let count = leaks_fn(std::ptr::NonNull::dangling().as_ptr(), 0);
+
+        leaks.reserve_exact(count);
+        let written = leaks_fn(leaks.as_mut_ptr(), count);
+        leaks.set_len(written.min(count));
+    }
+
+    let mut origins: Vec<(String, usize)> = Vec::new();
+
+    for leak in leaks {
+        let origin = if leak.allocated_in.is_null() {
+            "an unknown procedure".to_string()
+        } else {
+            unsafe { CStr::from_ptr(leak.allocated_in) }
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        match origins.iter_mut().find(|(known, _)| *known == origin) {
+            Some((_, count)) => *count += 1,
+            None => origins.push((origin, 1)),
+        }
+    }
+
+    // the strings live in the library, so they must be copied before this
+    unsafe { reset_fn() };
+
+    origins.sort();
+
+    origins
+}
+
+fn render_leaks<W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &Bump,
+    interns: &Interns,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expect: ToplevelExpect<'_>,
+    leaks: &[(String, usize)],
+) -> std::io::Result<()> {
+    let module_id = expect.symbol.module_id();
+    let data = expectations.get_mut(&module_id).unwrap();
+
+    let filename = data.path.to_owned();
+    let source = std::fs::read_to_string(&data.path).unwrap();
+
+    let renderer = Renderer::new(arena, interns, render_target, module_id, filename, &source);
+    renderer.render_leaks(writer, leaks, expect.region)?;
+
+    writeln!(writer)
+}
+
+/// Run the expects with `roc_interpret`, which needs neither LLVM nor a linker.
+///
+/// The interpreter recurses on the native stack, so call this through
+/// `roc_interpret::with_native_stack`.
+pub fn run_expects_interpreted<'a, W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    loaded: MonomorphizedModule<'a>,
+) -> std::io::Result<(usize, usize)> {
+    let MonomorphizedModule {
+        toplevel_expects,
+        procedures,
+        interns,
+        ..
+    } = loaded;
 
-    let shared_memory_ptr: *const u8 = shared_ptr.cast();
+    let interns: &'a Interns = arena.alloc(interns);
 
-    if result.is_err() || sequence.count_failures() > 0 {
+    let top_level = ProcLayout {
+        arguments: &[],
+        result: Layout::UNIT,
+        captures_niche: CapturesNiche::no_niche(),
+    };
+
+    let mut interpreter = Interpreter::new(arena, procedures);
+
+    let mut failed = 0;
+    let mut passed = 0;
+
+    for (symbol, region) in toplevel_expects.into_iter() {
+        let expect = ToplevelExpect {
+            name: symbol.as_str(interns),
+            symbol,
+            region,
+        };
+
+        interpreter.clear_expect_failures();
+
+        let result = interpreter
+            .run_thunk(symbol, top_level)
+            .map(|_| ())
+            .map_err(|error| error.to_string());
+
+        let result = render_expect_result(
+            writer,
+            render_target,
+            arena,
+            interns,
+            expectations,
+            expect,
+            result,
+            interpreter.expect_failures().as_ptr(),
+            interpreter.expect_failure_count(),
+        )?;
+
+        match result {
+            true => passed += 1,
+            false => failed += 1,
+        }
+    }
+
+    Ok((failed, passed))
+}
+
+/// Report a crashed or failed expect. Returns whether the expect passed.
+#[allow(clippy::too_many_arguments)]
+fn render_expect_result<W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &Bump,
+    interns: &Interns,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expect: ToplevelExpect<'_>,
+    result: Result<(), String>,
+    failures: *const u8,
+    failure_count: usize,
+) -> std::io::Result<bool> {
+    if result.is_err() || failure_count > 0 {
         let module_id = expect.symbol.module_id();
         let data = expectations.get_mut(&module_id).unwrap();
 
@@ -81,7 +308,7 @@ fn run_expect<W: std::io::Write>(
         } else {
             let mut offset = ExpectSequence::START_OFFSET;
 
-            for _ in 0..sequence.count_failures() {
+            for _ in 0..failure_count {
                 offset += render_expect_failure(
                     writer,
                     &renderer,
@@ -89,7 +316,7 @@ fn run_expect<W: std::io::Write>(
                     Some(expect),
                     expectations,
                     interns,
-                    shared_memory_ptr,
+                    failures,
                     offset,
                 )?;
             }
@@ -257,6 +484,7 @@ pub fn expect_mono_module_to_dylib<'a>(
     loaded: MonomorphizedModule<'a>,
     opt_level: OptLevel,
     mode: LlvmBackendMode,
+    debug_refcounts: bool,
 ) -> Result<(libloading::Library, BumpVec<'a, ToplevelExpect<'a>>), libloading::Error> {
     let target_info = TargetInfo::from(&target);
 
@@ -265,6 +493,9 @@ pub fn expect_mono_module_to_dylib<'a>(
         procedures,
         entry_point,
         interns,
+        sources,
+        def_regions,
+        call_regions,
         ..
     } = loaded;
 
@@ -280,6 +511,18 @@ pub fn expect_mono_module_to_dylib<'a>(
 
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
 
+    // leaks and double frees are reported with the file and line of the procedure
+    let debug_sources = if debug_refcounts {
+        Some(DebugSources::new(
+            &dibuilder,
+            &sources,
+            def_regions,
+            call_regions,
+        ))
+    } else {
+        None
+    };
+
     // Compile and add all the Procs before adding main
     let env = roc_gen_llvm::llvm::build::Env {
         arena,
@@ -293,6 +536,10 @@ pub fn expect_mono_module_to_dylib<'a>(
         mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: debug_sources.as_ref(),
+        debug_refcounts,
+        // expects are not profiled
+        profile_allocations: false,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -351,3 +598,86 @@ pub fn expect_mono_module_to_dylib<'a>(
 
     llvm_module_to_dylib(env.module, &target, opt_level).map(|lib| (lib, expects))
 }
+
+pub fn expect_mono_module_to_dev_dylib<'a>(
+    arena: &'a Bump,
+    target: Triple,
+    loaded: MonomorphizedModule<'a>,
+) -> std::io::Result<(libloading::Library, BumpVec<'a, ToplevelExpect<'a>>)> {
+    let MonomorphizedModule {
+        module_id,
+        toplevel_expects,
+        procedures,
+        interns,
+        ..
+    } = loaded;
+
+    let top_level = ProcLayout {
+        arguments: &[],
+        result: Layout::UNIT,
+        captures_niche: CapturesNiche::no_niche(),
+    };
+
+    // the dev backend exposes every expect as a C function under its exposed symbol name
+    let mut layout_ids = LayoutIds::default();
+    let expects = bumpalo::collections::Vec::from_iter_in(
+        toplevel_expects
+            .into_iter()
+            .map(|(symbol, region)| ToplevelExpect {
+                symbol,
+                region,
+                name: arena.alloc_str(
+                    &layout_ids
+                        .get_toplevel(symbol, &top_level)
+                        .to_exposed_symbol_string(symbol, &interns),
+                ),
+            }),
+        arena,
+    );
+
+    let env = arena.alloc(roc_gen_dev::Env {
+        arena,
+        module_id,
+        exposed_to_host: expects.iter().map(|expect| expect.symbol).collect(),
+        lazy_literals: true,
+        // there is no platform to provide these
+        generate_allocators: true,
+        mode: AssemblyBackendMode::Test,
+    });
+
+    let interns = arena.alloc(interns);
+    let module_object = roc_gen_dev::build_module(env, interns, &target, procedures);
+    let module_out = module_object
+        .write()
+        .expect("failed to build output object");
+
+    let dir = tempfile::tempdir()?;
+    let app_o_file = dir.path().join("app.o");
+    std::fs::write(&app_o_file, module_out)?;
+
+    let (mut child, dylib_path) = link(
+        &target,
+        app_o_file.clone(),
+        &[
+            app_o_file.to_str().unwrap(),
+            &bitcode::get_builtins_host_obj_path(),
+        ],
+        LinkType::Dylib,
+    )?;
+
+    let exit_status = child.wait()?;
+    if !exit_status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "failed to link the expects into a dylib: the linker exited with {}",
+                exit_status
+            ),
+        ));
+    }
+
+    let lib = unsafe { libloading::Library::new(dylib_path) }
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+    Ok((lib, expects))
+}
@@ -44,6 +44,10 @@ fn repl_eval(input: &str) -> Out {
 
     cmd.arg("repl");
 
+    if cfg!(feature = "interpret") {
+        cmd.arg("--interpret");
+    }
+
     let mut child = cmd
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
@@ -18,6 +18,9 @@ const NAMING_PROBLEM: &str = "NAMING PROBLEM";
 const UNRECOGNIZED_NAME: &str = "UNRECOGNIZED NAME";
 const UNUSED_DEF: &str = "UNUSED DEFINITION";
 const UNUSED_IMPORT: &str = "UNUSED IMPORT";
+const UNUSED_EXPOSED: &str = "UNUSED EXPOSED DEFINITION";
+const UNREACHABLE_DEF: &str = "UNREACHABLE DEFINITION";
+const UNUSED_IMPLEMENTATION: &str = "UNUSED IMPLEMENTATION";
 const UNUSED_ALIAS_PARAM: &str = "UNUSED TYPE ALIAS PARAMETER";
 const UNBOUND_TYPE_VARIABLE: &str = "UNBOUND TYPE VARIABLE";
 const UNUSED_ARG: &str = "UNUSED ARGUMENT";
@@ -105,6 +108,64 @@ pub fn can_problem<'b>(
             title = UNUSED_IMPORT.to_string();
             severity = Severity::Warning;
         }
+        Problem::UnusedExposed(symbol, region) => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(" is exposed, but nothing that the program uses needs it."),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("If no other module will need "),
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(", you can remove it from "),
+                    alloc.keyword("exposes"),
+                    alloc.reflow(", or remove it altogether."),
+                ]),
+            ]);
+
+            title = UNUSED_EXPOSED.to_string();
+            severity = Severity::Warning;
+        }
+        Problem::UnreachableDef(symbol, region) => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.symbol_unqualified(symbol),
+                    alloc.reflow(" is only used by code that the program never uses:"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.reflow("Once that code is gone, this definition can be removed too."),
+            ]);
+
+            title = UNREACHABLE_DEF.to_string();
+            severity = Severity::Warning;
+        }
+        Problem::UnusedImplementation {
+            opaque,
+            ability_member,
+            region,
+        } => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.reflow("This implementation of "),
+                    alloc.symbol_unqualified(ability_member),
+                    alloc.reflow(" for "),
+                    alloc.symbol_unqualified(opaque),
+                    alloc.reflow(" is never used:"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("Nothing the program uses needs "),
+                    alloc.symbol_unqualified(opaque),
+                    alloc.reflow(" to have this ability, so the ability can be removed from its "),
+                    alloc.keyword("has"),
+                    alloc.reflow(" clause."),
+                ]),
+            ]);
+
+            title = UNUSED_IMPLEMENTATION.to_string();
+            severity = Severity::Warning;
+        }
         Problem::ExposedButNotDefined(symbol) => {
             doc = alloc.stack([
                 alloc.symbol_unqualified(symbol).append(
@@ -1534,6 +1595,15 @@ fn pretty_runtime_error<'b>(
         RuntimeError::MalformedClosure(_) => {
             todo!("");
         }
+        RuntimeError::MalformedSyntax(region) => {
+            doc = alloc.stack([
+                alloc.reflow("I could not parse this code:"),
+                alloc.region(lines.convert_region(region)),
+                alloc.reflow("It will crash if it runs."),
+            ]);
+
+            title = SYNTAX_PROBLEM;
+        }
         RuntimeError::InvalidFloat(sign @ FloatErrorKind::PositiveInfinity, region, _raw_str)
         | RuntimeError::InvalidFloat(sign @ FloatErrorKind::NegativeInfinity, region, _raw_str) => {
             let tip = alloc
@@ -201,4 +201,56 @@ impl<'a> Renderer<'a> {
 
         write!(writer, "{}", buf)
     }
+
+    /// Report values that an expect allocated but never freed, see `--debug-refcounts`.
+    /// Each origin is the procedure that allocated a value, and the number of such values.
+    pub fn render_leaks<W>(
+        &self,
+        writer: &mut W,
+        origins: &[(String, usize)],
+        expect_region: Region,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use crate::report::Report;
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+
+        let leaks = origins.iter().map(|(origin, count)| {
+            let values = if *count == 1 { "value" } else { "values" };
+
+            self.alloc
+                .text(format!("{} {} allocated in ", count, values))
+                .append(self.alloc.text(origin.as_str()))
+                .indent(4)
+        });
+
+        let doc = self.alloc.stack([
+            self.alloc
+                .text("This expectation passed, but it did not free everything it allocated:"),
+            self.alloc.region(line_col_region),
+            self.alloc.text("These values were never freed:"),
+            self.alloc.vcat(leaks),
+        ]);
+
+        let report = Report {
+            title: "EXPECT LEAKED".into(),
+            doc,
+            filename: self.filename.clone(),
+            severity: crate::report::Severity::RuntimeError,
+        };
+
+        let mut buf = String::new();
+
+        report.render(
+            self.render_target,
+            &mut buf,
+            &self.alloc,
+            &crate::report::DEFAULT_PALETTE,
+        );
+
+        write!(writer, "{}", buf)
+    }
 }
@@ -3,4 +3,5 @@
 #![allow(clippy::large_enum_variant)]
 
 pub mod error;
+pub mod lint;
 pub mod report;
@@ -0,0 +1,323 @@
+//! Configuring how severe each kind of problem is.
+//!
+//! Every report has a default [`Severity`]. The problems that fall in one of the [`Lint`]
+//! categories can be allowed (not reported at all), reported as warnings, or denied (reported
+//! as errors) instead. This is configured for the whole program on the command line, and
+//! per module with a comment anywhere in the module:
+//!
+//! ```roc
+//! # roc: allow(unused-import, shadowing)
+//! # roc: deny(unused-def)
+//! ```
+//!
+//! A module's comments take precedence over the command line.
+
+use roc_collections::all::MutMap;
+use roc_problem::can::{Problem, RuntimeError};
+use roc_region::all::{LineInfo, Loc, Position, Region};
+use roc_solve_problem::TypeError;
+use std::path::PathBuf;
+
+use crate::report::{Report, RocDocAllocator, Severity};
+use ven_pretty::DocAllocator;
+
+const UNKNOWN_WARNING_LEVEL: &str = "UNKNOWN WARNING LEVEL";
+const UNKNOWN_WARNING_NAME: &str = "UNKNOWN WARNING NAME";
+
+/// The start of a comment that configures the levels of a module
+const COMMENT_PREFIX: &str = "roc:";
+
+/// A category of problems whose severity can be configured
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedDef,
+    UnusedImport,
+    UnusedArgument,
+    /// Exposed definitions, definitions and ability implementations that the program never
+    /// uses. These are only found by `roc check --unused`.
+    UnusedExposed,
+    DuplicateField,
+    Shadowing,
+    RedundantPattern,
+    /// A pattern that does not cover every possible value. The program crashes when it gets
+    /// a value that the pattern does not cover, so this is an error by default.
+    NonExhaustive,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 8] = [
+        Lint::UnusedDef,
+        Lint::UnusedImport,
+        Lint::UnusedArgument,
+        Lint::UnusedExposed,
+        Lint::DuplicateField,
+        Lint::Shadowing,
+        Lint::RedundantPattern,
+        Lint::NonExhaustive,
+    ];
+
+    /// The names of all lints, in the same order as [`Lint::ALL`]
+    pub const NAMES: [&'static str; 8] = [
+        "unused-def",
+        "unused-import",
+        "unused-argument",
+        "unused-exposed",
+        "duplicate-field",
+        "shadowing",
+        "redundant-pattern",
+        "non-exhaustive",
+    ];
+
+    pub fn name(self) -> &'static str {
+        let index = Self::ALL.iter().position(|lint| *lint == self).unwrap();
+
+        Self::NAMES[index]
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        let index = Self::NAMES.iter().position(|n| *n == name)?;
+
+        Some(Self::ALL[index])
+    }
+
+    pub fn of_can_problem(problem: &Problem) -> Option<Lint> {
+        match problem {
+            Problem::UnusedDef(..) => Some(Lint::UnusedDef),
+            Problem::UnusedImport(..) => Some(Lint::UnusedImport),
+            Problem::UnusedArgument(..) => Some(Lint::UnusedArgument),
+            Problem::UnusedExposed(..)
+            | Problem::UnreachableDef(..)
+            | Problem::UnusedImplementation { .. } => Some(Lint::UnusedExposed),
+            Problem::DuplicateRecordFieldValue { .. }
+            | Problem::DuplicateRecordFieldType { .. } => Some(Lint::DuplicateField),
+            Problem::Shadowing { .. } | Problem::RuntimeError(RuntimeError::Shadowing { .. }) => {
+                Some(Lint::Shadowing)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn of_type_problem(problem: &TypeError) -> Option<Lint> {
+        use roc_exhaustive::Error;
+
+        match problem {
+            TypeError::Exhaustive(Error::Redundant { .. }) => Some(Lint::RedundantPattern),
+            TypeError::Exhaustive(Error::Incomplete(..)) => Some(Lint::NonExhaustive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// Don't report the problem
+    Allow,
+    /// Report the problem as a warning
+    Warn,
+    /// Report the problem as an error
+    Deny,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// The levels that the command line configures for the whole program
+#[derive(Clone, Debug, Default)]
+pub struct WarningLevels {
+    /// Report every warning that isn't allowed as an error
+    pub deny_warnings: bool,
+    levels: MutMap<Lint, Level>,
+}
+
+impl WarningLevels {
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+
+    /// The severity to report a problem in a module with, or `None` if it is allowed.
+    pub fn severity(
+        &self,
+        module: &ModuleLevels,
+        lint: Option<Lint>,
+        default: Severity,
+    ) -> Option<Severity> {
+        let configured = lint.and_then(|lint| {
+            module
+                .levels
+                .get(&lint)
+                .or_else(|| self.levels.get(&lint))
+                .copied()
+        });
+
+        let level = configured.unwrap_or(match default {
+            Severity::Warning => Level::Warn,
+            Severity::RuntimeError => Level::Deny,
+        });
+
+        match level {
+            Level::Allow => None,
+            Level::Warn if !self.deny_warnings => Some(Severity::Warning),
+            Level::Warn | Level::Deny => Some(Severity::RuntimeError),
+        }
+    }
+}
+
+/// The levels that the `# roc: ...` comments of a module configure
+#[derive(Clone, Debug, Default)]
+pub struct ModuleLevels {
+    levels: MutMap<Lint, Level>,
+    /// The levels and lints in the comments that don't exist
+    pub problems: Vec<LevelProblem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LevelProblem {
+    UnknownLevel(Loc<String>),
+    UnknownLint(Loc<String>),
+}
+
+impl ModuleLevels {
+    pub fn from_source(src: &str) -> Self {
+        let mut module_levels = ModuleLevels::default();
+        let mut offset = 0;
+
+        for line in src.split('\n') {
+            let line_offset = offset;
+            offset += line.len() + 1;
+
+            let comment = match line.trim_start().strip_prefix('#') {
+                // doc comments are never configuration
+                Some(comment) if !comment.starts_with('#') => comment.trim_start(),
+                _ => continue,
+            };
+
+            let directive = match comment.strip_prefix(COMMENT_PREFIX) {
+                Some(directive) => directive.trim(),
+                None => continue,
+            };
+
+            let region_of = |part: &str| {
+                let start = line_offset + (part.as_ptr() as usize - line.as_ptr() as usize);
+
+                Region::new(
+                    Position::new(start as u32),
+                    Position::new((start + part.len()) as u32),
+                )
+            };
+
+            let parsed = directive
+                .strip_suffix(')')
+                .and_then(|directive| directive.split_once('('))
+                .and_then(|(level_name, lint_names)| {
+                    let level = Level::from_name(level_name.trim())?;
+
+                    Some((level, lint_names))
+                });
+
+            match parsed {
+                Some((level, lint_names)) => {
+                    // allow a trailing comma, and an empty list
+                    for lint_name in lint_names.split(',').map(str::trim) {
+                        if lint_name.is_empty() {
+                            continue;
+                        }
+
+                        match Lint::from_name(lint_name) {
+                            Some(lint) => {
+                                module_levels.levels.insert(lint, level);
+                            }
+                            None => {
+                                module_levels
+                                    .problems
+                                    .push(LevelProblem::UnknownLint(Loc::at(
+                                        region_of(lint_name),
+                                        lint_name.to_string(),
+                                    )));
+                            }
+                        }
+                    }
+                }
+                None => {
+                    module_levels
+                        .problems
+                        .push(LevelProblem::UnknownLevel(Loc::at(
+                            region_of(directive),
+                            directive.to_string(),
+                        )));
+                }
+            }
+        }
+
+        module_levels
+    }
+
+    pub fn get(&self, lint: Lint) -> Option<Level> {
+        self.levels.get(&lint).copied()
+    }
+}
+
+pub fn level_problem<'b>(
+    alloc: &'b RocDocAllocator<'b>,
+    lines: &LineInfo,
+    filename: PathBuf,
+    problem: LevelProblem,
+) -> Report<'b> {
+    let doc;
+    let title;
+
+    match problem {
+        LevelProblem::UnknownLevel(loc_directive) => {
+            doc = alloc.stack([
+                alloc.reflow("I don't understand this warning configuration:"),
+                alloc.region(lines.convert_region(loc_directive.region)),
+                alloc.concat([
+                    alloc.reflow("It should look like "),
+                    alloc.parser_suggestion("# roc: allow(unused-import)"),
+                    alloc.reflow(", with one of "),
+                    alloc.keyword("allow"),
+                    alloc.reflow(", "),
+                    alloc.keyword("warn"),
+                    alloc.reflow(" or "),
+                    alloc.keyword("deny"),
+                    alloc.reflow(" and a comma-separated list of warning names."),
+                ]),
+            ]);
+
+            title = UNKNOWN_WARNING_LEVEL.to_string();
+        }
+        LevelProblem::UnknownLint(loc_name) => {
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.reflow("I don't know a warning named "),
+                    alloc.string(loc_name.value),
+                    alloc.reflow(":"),
+                ]),
+                alloc.region(lines.convert_region(loc_name.region)),
+                alloc.reflow("These are the warnings that can be configured:"),
+                alloc
+                    .intersperse(
+                        Lint::NAMES.iter().map(|name| alloc.parser_suggestion(name)),
+                        alloc.reflow(", "),
+                    )
+                    .indent(4),
+            ]);
+
+            title = UNKNOWN_WARNING_NAME.to_string();
+        }
+    }
+
+    Report {
+        title,
+        filename,
+        doc,
+        severity: Severity::Warning,
+    }
+}
@@ -0,0 +1,330 @@
+#[macro_use]
+extern crate pretty_assertions;
+#[macro_use]
+extern crate indoc;
+
+#[cfg(test)]
+mod test_lint {
+    use roc_module::ident::Ident;
+    use roc_module::symbol::{Interns, ModuleId, Symbol};
+    use roc_problem::can::{Problem, ShadowKind};
+    use roc_region::all::{LineInfo, Loc, Position, Region};
+    use roc_reporting::lint::{
+        level_problem, Level, LevelProblem, Lint, ModuleLevels, WarningLevels,
+    };
+    use roc_reporting::report::{RocDocAllocator, Severity};
+    use std::path::PathBuf;
+
+    fn loc(start: u32, value: &str) -> Loc<String> {
+        Loc::at(
+            Region::new(
+                Position::new(start),
+                Position::new(start + value.len() as u32),
+            ),
+            value.to_string(),
+        )
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for lint in Lint::ALL {
+            assert_eq!(Lint::from_name(lint.name()), Some(lint));
+        }
+
+        assert_eq!(Lint::from_name("unused"), None);
+    }
+
+    #[test]
+    fn module_comments() {
+        let src = indoc!(
+            r#"
+            interface Main
+                exposes [main]
+                imports [Dep]
+
+            # roc: allow(unused-import, shadowing)
+            #roc:deny(unused-def)
+            ## roc: allow(unused-argument)
+
+            main = 1 # roc: warn(non-exhaustive)
+            "#
+        );
+
+        let module = ModuleLevels::from_source(src);
+
+        assert_eq!(module.get(Lint::UnusedImport), Some(Level::Allow));
+        assert_eq!(module.get(Lint::Shadowing), Some(Level::Allow));
+        assert_eq!(module.get(Lint::UnusedDef), Some(Level::Deny));
+        // doc comments and comments after code don't configure anything
+        assert_eq!(module.get(Lint::UnusedArgument), None);
+        assert_eq!(module.get(Lint::NonExhaustive), None);
+        assert_eq!(module.problems, Vec::new());
+    }
+
+    #[test]
+    fn unknown_levels_and_lints() {
+        let src = indoc!(
+            r#"
+            # roc: allow(unused-import, unused-variable)
+            # roc: forbid(unused-def)
+            "#
+        );
+
+        let module = ModuleLevels::from_source(src);
+
+        assert_eq!(module.get(Lint::UnusedImport), Some(Level::Allow));
+        assert_eq!(module.get(Lint::UnusedDef), None);
+        assert_eq!(
+            module.problems,
+            vec![
+                LevelProblem::UnknownLint(loc(28, "unused-variable")),
+                LevelProblem::UnknownLevel(loc(52, "forbid(unused-def)")),
+            ]
+        );
+    }
+
+    #[test]
+    fn severities() {
+        let module = ModuleLevels::from_source("# roc: warn(unused-import)\n");
+        let mut levels = WarningLevels::default();
+
+        // without configuration, every report keeps its own severity
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedDef), Severity::Warning),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            levels.severity(&module, Some(Lint::NonExhaustive), Severity::RuntimeError),
+            Some(Severity::RuntimeError)
+        );
+
+        levels.set(Lint::UnusedDef, Level::Allow);
+        levels.set(Lint::UnusedImport, Level::Deny);
+        levels.set(Lint::NonExhaustive, Level::Warn);
+
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedDef), Severity::Warning),
+            None
+        );
+        // the module's comment takes precedence over the command line
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedImport), Severity::Warning),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            levels.severity(&module, Some(Lint::NonExhaustive), Severity::RuntimeError),
+            Some(Severity::Warning)
+        );
+
+        levels.deny_warnings = true;
+
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedDef), Severity::Warning),
+            None
+        );
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedImport), Severity::Warning),
+            Some(Severity::RuntimeError)
+        );
+        // warnings that aren't in a category are denied too
+        assert_eq!(
+            levels.severity(&module, None, Severity::Warning),
+            Some(Severity::RuntimeError)
+        );
+    }
+
+    #[test]
+    fn level_names() {
+        assert_eq!(Level::from_name("allow"), Some(Level::Allow));
+        assert_eq!(Level::from_name("warn"), Some(Level::Warn));
+        assert_eq!(Level::from_name("deny"), Some(Level::Deny));
+        assert_eq!(Level::from_name("Deny"), None);
+        assert_eq!(Level::from_name("forbid"), None);
+    }
+
+    #[test]
+    fn lints_of_can_problems() {
+        let region = Region::zero();
+
+        assert_eq!(
+            Lint::of_can_problem(&Problem::UnusedDef(Symbol::LIST_MAP, region)),
+            Some(Lint::UnusedDef)
+        );
+        assert_eq!(
+            Lint::of_can_problem(&Problem::UnusedImport(ModuleId::LIST, region)),
+            Some(Lint::UnusedImport)
+        );
+        assert_eq!(
+            Lint::of_can_problem(&Problem::UnusedArgument(
+                Symbol::LIST_MAP,
+                false,
+                Symbol::LIST_LEN,
+                region
+            )),
+            Some(Lint::UnusedArgument)
+        );
+        // everything that `roc check --unused` finds is in the same category
+        assert_eq!(
+            Lint::of_can_problem(&Problem::UnusedExposed(Symbol::LIST_MAP, region)),
+            Some(Lint::UnusedExposed)
+        );
+        assert_eq!(
+            Lint::of_can_problem(&Problem::UnreachableDef(Symbol::LIST_MAP, region)),
+            Some(Lint::UnusedExposed)
+        );
+        assert_eq!(
+            Lint::of_can_problem(&Problem::Shadowing {
+                original_region: region,
+                shadow: Loc::at(region, Ident::from("x")),
+                kind: ShadowKind::Variable,
+            }),
+            Some(Lint::Shadowing)
+        );
+        // problems that are always errors can't be configured
+        assert_eq!(
+            Lint::of_can_problem(&Problem::ExposedButNotDefined(Symbol::LIST_MAP)),
+            None
+        );
+    }
+
+    #[test]
+    fn later_comments_take_precedence() {
+        let src = indoc!(
+            r#"
+            # roc: deny(unused-def, shadowing)
+            #   roc:   allow( unused-def )
+            "#
+        );
+
+        let module = ModuleLevels::from_source(src);
+
+        assert_eq!(module.get(Lint::UnusedDef), Some(Level::Allow));
+        assert_eq!(module.get(Lint::Shadowing), Some(Level::Deny));
+        assert_eq!(module.problems, Vec::new());
+    }
+
+    #[test]
+    fn malformed_comments() {
+        let src = indoc!(
+            r#"
+            # roc: allow
+            # roc: allow(unused-def
+            # roc allow(unused-def)
+            # roc: allow()
+            # roc: allow(unused-def, )
+            "#
+        );
+
+        let module = ModuleLevels::from_source(src);
+
+        // a comment without the `roc:` prefix is just a comment, and empty names are ignored
+        assert_eq!(module.get(Lint::UnusedDef), Some(Level::Allow));
+        assert_eq!(
+            module.problems,
+            vec![
+                LevelProblem::UnknownLevel(loc(7, "allow")),
+                LevelProblem::UnknownLevel(loc(20, "allow(unused-def")),
+            ]
+        );
+    }
+
+    #[test]
+    fn deny_warnings_keeps_allowed_and_errors() {
+        let module = ModuleLevels::from_source("# roc: allow(shadowing)\n");
+        let mut levels = WarningLevels::default();
+        levels.deny_warnings = true;
+        levels.set(Lint::UnusedImport, Level::Allow);
+
+        // allowed on the command line, or in the module
+        assert_eq!(
+            levels.severity(&module, Some(Lint::UnusedImport), Severity::Warning),
+            None
+        );
+        assert_eq!(
+            levels.severity(&module, Some(Lint::Shadowing), Severity::RuntimeError),
+            None
+        );
+        assert_eq!(
+            levels.severity(&module, None, Severity::RuntimeError),
+            Some(Severity::RuntimeError)
+        );
+    }
+
+    #[test]
+    fn module_levels_only_apply_to_their_module() {
+        let allowing = ModuleLevels::from_source("# roc: allow(unused-def)\n");
+        let other = ModuleLevels::default();
+        let mut levels = WarningLevels::default();
+        levels.set(Lint::UnusedDef, Level::Deny);
+
+        assert_eq!(
+            levels.severity(&allowing, Some(Lint::UnusedDef), Severity::Warning),
+            None
+        );
+        assert_eq!(
+            levels.severity(&other, Some(Lint::UnusedDef), Severity::Warning),
+            Some(Severity::RuntimeError)
+        );
+    }
+
+    fn render_level_problem(src: &str) -> String {
+        let module = ModuleLevels::from_source(src);
+        let problem = module.problems.into_iter().next().unwrap();
+
+        let src_lines: Vec<&str> = src.split('\n').collect();
+        let interns = Interns::default();
+        let alloc = RocDocAllocator::new(&src_lines, ModuleId::ATTR, &interns);
+        let lines = LineInfo::new(src);
+
+        let report = level_problem(&alloc, &lines, PathBuf::from("Main.roc"), problem);
+        assert_eq!(report.severity, Severity::Warning);
+
+        let mut buf = String::new();
+        report.render_ci(&mut buf, &alloc);
+        buf
+    }
+
+    #[test]
+    fn report_unknown_level() {
+        assert_eq!(
+            render_level_problem("# roc: forbid(unused-def)\n"),
+            indoc!(
+                r#"
+                ── UNKNOWN WARNING LEVEL ──────────────────────────────────────────── Main.roc ─
+
+                I don't understand this warning configuration:
+
+                1│  # roc: forbid(unused-def)
+                           ^^^^^^^^^^^^^^^^^^
+
+                It should look like # roc: allow(unused-import), with one of `allow`,
+                `warn` or `deny` and a comma-separated list of warning names.
+                "#
+            )
+            .trim_end()
+        );
+    }
+
+    #[test]
+    fn report_unknown_lint() {
+        assert_eq!(
+            render_level_problem("# roc: allow(unused)\n"),
+            indoc!(
+                r#"
+                ── UNKNOWN WARNING NAME ───────────────────────────────────────────── Main.roc ─
+
+                I don't know a warning named unused:
+
+                1│  # roc: allow(unused)
+                                 ^^^^^^
+
+                These are the warnings that can be configured:
+
+                    unused-def, unused-import, unused-argument, unused-exposed,
+                    duplicate-field, shadowing, redundant-pattern, non-exhaustive
+                "#
+            )
+            .trim_end()
+        );
+    }
+}
@@ -88,6 +88,9 @@ mod test_reporting {
                 render: RenderTarget::Generic,
                 threading: Threading::Single,
                 exec_mode: ExecutionMode::Check,
+                inline_and_fold: false,
+                record_call_regions: false,
+                collect_package_modules: false,
             };
             let result =
                 roc_load::load_and_typecheck(arena, full_file_path, exposed_types, load_config);
@@ -0,0 +1,42 @@
+app "cons-list"
+    packages { pf: "platform/main.roc" }
+    imports [pf.Task]
+    provides [main] to pf
+
+ConsList a : [Nil, Cons a (ConsList a)]
+
+range : I64, ConsList I64 -> ConsList I64
+range = \n, acc ->
+    if n == 0 then
+        acc
+    else
+        range (n - 1) (Cons n acc)
+
+# Every cell that is dropped here is unique, so it is freed without
+# incrementing and then decrementing the rest of the list.
+drop : ConsList a, I64 -> ConsList a
+drop = \list, n ->
+    if n == 0 then
+        list
+    else
+        when list is
+            Cons _ rest -> drop rest (n - 1)
+            Nil -> Nil
+
+sum : ConsList I64, I64 -> I64
+sum = \list, acc ->
+    when list is
+        Cons x rest -> sum rest (acc + x)
+        Nil -> acc
+
+main : Task.Task {} []
+main =
+    Task.after
+        Task.getInt
+        \n ->
+            list = range n Nil
+            rest = drop list (n // 2)
+
+            sum rest 0
+            |> Num.toStr
+            |> Task.putLine
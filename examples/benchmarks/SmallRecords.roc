@@ -0,0 +1,41 @@
+app "small-records"
+    packages { pf: "platform/main.roc" }
+    imports [pf.Task]
+    provides [main] to pf
+
+# Passes small records and a single-field opaque type to and from functions in a hot loop,
+# to measure how they are passed across function boundaries.
+Vec2 : { x : I64, y : I64 }
+
+Meters := I64
+
+step : Vec2, Vec2, Vec2, Vec2 -> Vec2
+step = \pos, vel, acc, wind ->
+    { x: pos.x + vel.x + acc.x - wind.x, y: pos.y + vel.y * acc.y - wind.y }
+
+travelled : Meters, Vec2 -> Meters
+travelled = \@Meters m, pos -> @Meters (m + Num.abs pos.x + Num.abs pos.y)
+
+loop : I64, I64, Vec2, Meters -> { pos : Vec2, dist : Meters }
+loop = \i, n, pos, dist ->
+    if i > n then
+        { pos, dist }
+    else
+        next = step pos { x: i, y: 1 } { x: 1, y: 1 } { x: 1, y: 0 }
+
+        loop (i + 1) n next (travelled dist next)
+
+main : Task.Task {} []
+main =
+    Task.after
+        Task.getInt
+        \n ->
+            result = loop 1 n { x: 0, y: 0 } (@Meters 0)
+            (@Meters dist) = result.dist
+
+            Num.toStr result.pos.x
+            |> Str.concat " & "
+            |> Str.concat (Num.toStr result.pos.y)
+            |> Str.concat " & "
+            |> Str.concat (Num.toStr dist)
+            |> Task.putLine
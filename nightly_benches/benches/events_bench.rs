@@ -1,6 +1,7 @@
 // Keep this benchmark. It's commented because it requires nightly rust.
 use cli_utils::bench_utils::{
-    bench_cfold, bench_deriv, bench_nqueens, bench_quicksort, bench_rbtree_ck, bench_rbtree_delete,
+    bench_cfold, bench_cons_list, bench_deriv, bench_nqueens, bench_quicksort, bench_rbtree_ck,
+    bench_rbtree_delete, bench_small_records,
 };
 use criterion_perf_events::Perf;
 use perfcnt::linux::HardwareEventType as Hardware;
@@ -20,6 +21,8 @@ fn bench_group(c: &mut Criterion<Perf>, hw_event_str: &str) {
         bench_rbtree_ck,
         // bench_rbtree_delete,
         bench_quicksort,
+        bench_cons_list,
+        bench_small_records,
     ];
 
     for bench_func in bench_funcs.iter() {